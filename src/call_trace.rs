@@ -0,0 +1,287 @@
+//! Tracing for validation runs. With the validator's `--trace-calls` option every call made through
+//! [`clap_call!()`][crate::util::clap_call] is logged at the trace level together with the thread
+//! it was made from and how long it took. With the `--trace-file` option the tests, the calls to
+//! the plugin's functions, and the host callbacks made by the plugin are written to a file in the
+//! Chrome trace event format. That file can be opened in `chrome://tracing` or Perfetto to inspect
+//! performance problems in plugins and in the validator itself, or to get a timeline of everything
+//! the validator did leading up to a failure.
+//!
+//! Only the validator process writes to the trace file. Processes running out-of-process tests
+//! send their events to the validator over the [`ipc`] connection. Every event is written or sent
+//! as soon as it's recorded, so the events leading up to a crash are not lost. If the validator
+//! itself crashes then the JSON array is never closed, which the trace viewers accept. Events use
+//! the wall clock time, so events from different processes line up.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::ipc;
+
+/// Whether calls to the plugin's functions should be logged. Set through [`set_log_calls()`].
+static LOG_CALLS: AtomicBool = AtomicBool::new(false);
+/// Whether trace events should be recorded. This is set when [`TRACE_OUTPUT`] is set.
+static RECORD_EVENTS: AtomicBool = AtomicBool::new(false);
+/// Where recorded trace events go. Set through [`record_to_file()`] or [`record_to_validator()`].
+static TRACE_OUTPUT: Mutex<Option<TraceOutput>> = Mutex::new(None);
+/// Used to assign every thread a numeric ID for the trace file, since Rust's thread IDs cannot be
+/// converted to integers on stable Rust.
+static NEXT_THREAD_IDX: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// This thread's ID in the trace file. See [`NEXT_THREAD_IDX`]. The thread's name is recorded
+    /// the first time this is accessed.
+    static THREAD_IDX: u64 = {
+        let thread_idx = NEXT_THREAD_IDX.fetch_add(1, Ordering::Relaxed);
+        record(TraceEvent::metadata("thread_name", thread_idx, thread_name()));
+
+        thread_idx
+    };
+}
+
+/// An event in the Chrome trace event format. Only complete events and metadata events are used.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TraceEvent {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cat: String,
+    /// The event's type. `X` for complete events and `M` for metadata events.
+    pub ph: char,
+    /// The event's start time in microseconds since the Unix epoch.
+    #[serde(default)]
+    pub ts: u64,
+    /// The event's duration in microseconds. Only used for complete events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dur: Option<f64>,
+    pub pid: u32,
+    pub tid: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, String>,
+}
+
+/// Where recorded trace events go.
+enum TraceOutput {
+    /// Events are written to the trace file as a JSON array. Used by the validator.
+    File { file: File, num_events: usize },
+    /// Events are sent to the validator. Used by out-of-process tests.
+    Validator,
+}
+
+/// A span of time that's recorded as a complete event when it's dropped. Calls to the plugin's
+/// functions may also be logged. See [`span()`], [`trace_plugin_call()`], and
+/// [`trace_host_callback()`].
+pub struct TraceSpan {
+    category: &'static str,
+    name: String,
+    /// Whether the span should be logged at the trace level when it ends.
+    log: bool,
+    /// The wall clock time in microseconds, since the trace file is shared between processes.
+    started_at_us: u64,
+    started_at: Instant,
+}
+
+/// Log every call to one of the plugin's functions at the trace level. This is a copy of
+/// [`SharedSettings::trace_calls`][crate::util::SharedSettings::trace_calls], and it's set
+/// together with the other shared settings.
+pub fn set_log_calls(enabled: bool) {
+    LOG_CALLS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether calls to the plugin's functions are logged. See [`set_log_calls()`].
+pub fn log_calls() -> bool {
+    LOG_CALLS.load(Ordering::Relaxed)
+}
+
+/// Start recording trace events to a new trace file at `path`, overwriting any existing file.
+/// [`finish()`] must be called at the end of the validation run to finish the file.
+pub fn record_to_file(path: &Path) -> Result<()> {
+    let mut file = File::create(path)
+        .with_context(|| format!("Could not create the trace file at '{}'", path.display()))?;
+    file.write_all(b"[")
+        .context("Could not write to the trace file")?;
+    set_output(TraceOutput::File {
+        file,
+        num_events: 0,
+    });
+    record(TraceEvent::metadata(
+        "process_name",
+        0,
+        String::from("clap-validator"),
+    ));
+
+    Ok(())
+}
+
+/// Start recording trace events and sending them to the validator using
+/// [`ipc::send_trace_event()`]. Used by out-of-process tests. The process is shown as
+/// `process_name` in the trace.
+pub fn record_to_validator(process_name: String) {
+    set_output(TraceOutput::Validator);
+    record(TraceEvent::metadata("process_name", 0, process_name));
+}
+
+/// Whether trace events are being recorded. See [`record_to_file()`] and
+/// [`record_to_validator()`].
+pub fn recording() -> bool {
+    RECORD_EVENTS.load(Ordering::Relaxed)
+}
+
+/// Record events sent by an out-of-process test.
+pub fn record_events(events: Vec<TraceEvent>) {
+    for event in events {
+        record(event);
+    }
+}
+
+/// Finish the trace file started with [`record_to_file()`] by closing the JSON array and flushing
+/// it to disk. Recording stops after this. Does nothing if no trace file is being written.
+pub fn finish() -> Result<()> {
+    RECORD_EVENTS.store(false, Ordering::SeqCst);
+    if let Some(TraceOutput::File { mut file, .. }) = TRACE_OUTPUT.lock().unwrap().take() {
+        file.write_all(b"\n]\n")
+            .and_then(|()| file.flush())
+            .context("Could not write to the trace file")?;
+    }
+
+    Ok(())
+}
+
+/// Record a span with the specified category until the returned guard is dropped. `name` is only
+/// called when trace events are being recorded.
+pub fn span(category: &'static str, name: impl FnOnce() -> String) -> Option<TraceSpan> {
+    if !recording() {
+        return None;
+    }
+
+    Some(TraceSpan::new(category, name(), false))
+}
+
+/// Trace a call to one of the plugin's functions until the returned guard is dropped. This is a
+/// no-op unless calls are logged or trace events are being recorded. Used in
+/// [`clap_call!()`][crate::util::clap_call]. `struct_name` is the full type name of the struct
+/// containing the function pointer.
+pub fn trace_plugin_call(
+    struct_name: &'static str,
+    function_name: &'static str,
+) -> Option<TraceSpan> {
+    let log = log_calls();
+    if !log && !recording() {
+        return None;
+    }
+
+    // The type name includes the module path, e.g. `clap_sys::plugin::clap_plugin`
+    let name = format!(
+        "{}::{}()",
+        struct_name.rsplit("::").next().unwrap(),
+        function_name
+    );
+
+    Some(TraceSpan::new("plugin-call", name, log))
+}
+
+/// Record a host callback made by the plugin until the returned guard is dropped. Used in
+/// [`catch_callback_panic()`][crate::util::catch_callback_panic].
+pub fn trace_host_callback(function_name: &str) -> Option<TraceSpan> {
+    span("host-callback", || function_name.to_owned())
+}
+
+impl TraceEvent {
+    /// A metadata event that names the process, or the thread with index `thread_idx`.
+    fn metadata(name: &str, thread_idx: u64, value: String) -> Self {
+        TraceEvent {
+            name: name.to_owned(),
+            cat: String::new(),
+            ph: 'M',
+            ts: 0,
+            dur: None,
+            pid: std::process::id(),
+            tid: thread_idx,
+            args: BTreeMap::from([(String::from("name"), value)]),
+        }
+    }
+}
+
+impl TraceSpan {
+    fn new(category: &'static str, name: String, log: bool) -> Self {
+        TraceSpan {
+            category,
+            name,
+            log,
+            started_at_us: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros() as u64,
+            started_at: Instant::now(),
+        }
+    }
+}
+
+impl Drop for TraceSpan {
+    fn drop(&mut self) {
+        let duration = self.started_at.elapsed();
+        if self.log {
+            log::trace!(
+                "'{}' on the '{}' thread took {:.3} ms",
+                self.name,
+                thread_name(),
+                duration.as_secs_f64() * 1000.0
+            );
+        }
+
+        if recording() {
+            let thread_idx = THREAD_IDX.with(|thread_idx| *thread_idx);
+            record(TraceEvent {
+                name: std::mem::take(&mut self.name),
+                cat: self.category.to_owned(),
+                ph: 'X',
+                ts: self.started_at_us,
+                dur: Some(duration.as_secs_f64() * 1_000_000.0),
+                pid: std::process::id(),
+                tid: thread_idx,
+                args: BTreeMap::new(),
+            });
+        }
+    }
+}
+
+fn set_output(output: TraceOutput) {
+    *TRACE_OUTPUT.lock().unwrap() = Some(output);
+    RECORD_EVENTS.store(true, Ordering::SeqCst);
+}
+
+/// Record an event. Every event is written to the trace file with a single write. Errors while
+/// writing to the trace file are ignored, since tracing should never cause a test to fail.
+fn record(event: TraceEvent) {
+    let send_to_validator = match TRACE_OUTPUT.lock().unwrap().as_mut() {
+        Some(TraceOutput::File { file, num_events }) => {
+            if let Ok(json) = serde_json::to_string(&event) {
+                let separator = if *num_events == 0 { "\n" } else { ",\n" };
+                let _ = file.write_all(format!("{separator}{json}").as_bytes());
+                *num_events += 1;
+            }
+
+            false
+        }
+        Some(TraceOutput::Validator) => true,
+        None => false,
+    };
+
+    // This is done after releasing the lock, since sending the event can block
+    if send_to_validator {
+        ipc::send_trace_event(event);
+    }
+}
+
+/// The name of the current thread, used in the log messages and the trace file.
+fn thread_name() -> String {
+    std::thread::current()
+        .name()
+        .unwrap_or("unnamed")
+        .to_owned()
+}
@@ -1,13 +1,28 @@
 //! All the different commands for the cli. Split up into modules and functions to make it a bit
 //! easier to navigate.
 
+use anyhow::Result;
 use std::collections::HashMap;
 
+use self::output::OutputSink;
+
+pub mod format_version;
 pub mod list;
+pub mod output;
+pub mod report;
+pub mod schema;
+pub mod stats;
+pub mod submission;
 pub mod validate;
 
-/// A helper for printing terminal wrapped and indentend strings to STDOUT.
+/// The line width used for wrapping text when the output is not written to a terminal.
+const NON_TERMINAL_WIDTH: usize = 100;
+
+/// A helper for printing terminal wrapped and indentend strings to STDOUT or another
+/// [`OutputSink`].
 pub struct TextWrapper {
+    /// Where the wrapped text is written to.
+    output: OutputSink,
     /// The basic wrapping options, minus the indent string.
     wrapping_options: textwrap::Options<'static>,
     /// Indent strings for different widths. Need to be allocated separately because textwrap
@@ -17,10 +32,7 @@ pub struct TextWrapper {
 
 impl Default for TextWrapper {
     fn default() -> Self {
-        Self {
-            wrapping_options: textwrap::Options::with_termwidth(),
-            indent_strings: HashMap::new(),
-        }
+        Self::new(OutputSink::stdout())
     }
 }
 
@@ -41,8 +53,32 @@ macro_rules! println_wrapped_no_indent {
 pub(crate) use println_wrapped_no_indent;
 
 impl TextWrapper {
-    /// Print a string to STDOUT wrapped to the terminal width using the given subsequent indent
-    /// width. The first line is not automatically indented so you can use bullets and other
+    /// Create a text wrapper that writes to an output sink. Text is only wrapped to the terminal's
+    /// width when writing to STDOUT.
+    pub fn new(output: OutputSink) -> Self {
+        Self {
+            wrapping_options: if output.is_stdout() {
+                textwrap::Options::with_termwidth()
+            } else {
+                textwrap::Options::new(NON_TERMINAL_WIDTH)
+            },
+            output,
+            indent_strings: HashMap::new(),
+        }
+    }
+
+    /// Print a string to the output sink as is, without any wrapping.
+    pub fn print_unwrapped(&mut self, text: impl AsRef<str>) {
+        self.output.write_line(text)
+    }
+
+    /// Flush the output sink, returning an error if anything could not be written.
+    pub fn finish(self) -> Result<()> {
+        self.output.finish()
+    }
+
+    /// Print a string to the output sink wrapped to the terminal width using the given subsequent
+    /// indent width. The first line is not automatically indented so you can use bullets and other
     /// formatting characters.
     pub fn print(&mut self, subsequent_indent_width: usize, text: impl AsRef<str>) {
         let indent_string = self
@@ -53,7 +89,8 @@ impl TextWrapper {
             .wrapping_options
             .clone()
             .subsequent_indent(indent_string);
-        println!("{}", textwrap::fill(text.as_ref(), wrapping_options));
+        self.output
+            .write_line(textwrap::fill(text.as_ref(), wrapping_options));
     }
 
     /// The same as [`print()`][Self::print()], but it uses a heuristic to guess the subsequent
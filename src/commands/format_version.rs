@@ -0,0 +1,107 @@
+//! Versioning for the validator's JSON output. Every JSON output contains a `format-version` field
+//! that is incremented whenever the layout changes in a way that could break existing consumers.
+//! Older layouts can be requested using the `--format-version` option. Outputs that are maps keyed
+//! by library paths are wrapped in an object containing the `format-version` and a `data` field
+//! instead.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::schema::SchemaKind;
+
+/// The version of the current JSON output layout. This is what's described by the JSON schemas.
+pub const CURRENT_FORMAT_VERSION: u32 = 2;
+/// The oldest JSON output layout that can still be requested with `--format-version`.
+pub const MIN_FORMAT_VERSION: u32 = 1;
+
+/// Format `value` as pretty printed JSON using the layout from `format_version`. `kind` describes
+/// which output `value` is. The version is validated when parsing the command line options, so
+/// `format_version` must be between [`MIN_FORMAT_VERSION`] and [`CURRENT_FORMAT_VERSION`].
+pub fn to_versioned_json<T: Serialize>(value: &T, kind: SchemaKind, format_version: u32) -> String {
+    assert!((MIN_FORMAT_VERSION..=CURRENT_FORMAT_VERSION).contains(&format_version));
+
+    let mut json = serde_json::to_value(value).expect("Could not format JSON");
+    if format_version == 1 {
+        downgrade_to_v1(&mut json, kind);
+    } else {
+        match kind {
+            // These are maps keyed by library paths, so adding another key to them would make the
+            // version look like a plugin library to anything iterating over the map
+            SchemaKind::PluginIndex | SchemaKind::PresetIndex => {
+                json = serde_json::json!({ "format-version": format_version, "data": json });
+            }
+            _ => {
+                if let Value::Object(object) = &mut json {
+                    object.insert(String::from("format-version"), Value::from(format_version));
+                }
+            }
+        }
+    }
+
+    serde_json::to_string_pretty(&json).expect("Could not format JSON")
+}
+
+/// Convert the current layout to the layout used up to and including clap-validator 0.3.2. That
+/// layout did not have a `format-version` field, and the plugin and preset indices were not wrapped
+/// in an object.
+fn downgrade_to_v1(json: &mut Value, kind: SchemaKind) {
+    match kind {
+        SchemaKind::ValidationResult => {
+            if let Value::Object(result) = json {
+                result.remove("header");
+                result.remove("plugins");
+
+                for key in ["plugin-library-tests", "plugin-tests"] {
+                    let tests = result
+                        .get_mut(key)
+                        .and_then(Value::as_object_mut)
+                        .into_iter()
+                        .flat_map(|tests| tests.values_mut())
+                        .filter_map(Value::as_array_mut)
+                        .flatten()
+                        .filter_map(Value::as_object_mut);
+                    for test in tests {
+                        test.remove("thread-safety");
+                        test.remove("plugin-log");
+                        test.remove("host-extensions");
+                        if let Some(Value::Object(status)) = test.get_mut("status") {
+                            status.remove("causes");
+                            status.remove("reason");
+                        }
+                    }
+                }
+            }
+        }
+        SchemaKind::PluginIndex => {
+            if let Value::Object(index) = json {
+                for library in index.values_mut().filter_map(Value::as_object_mut) {
+                    library.remove("sha256");
+                    library.remove("factories");
+                }
+            }
+        }
+        SchemaKind::PresetIndex => {
+            if let Value::Object(index) = json {
+                let providers = index
+                    .values_mut()
+                    .filter_map(|result| result.get_mut("success"))
+                    .filter_map(Value::as_array_mut)
+                    .flatten()
+                    .filter_map(Value::as_object_mut);
+                for provider in providers {
+                    provider.remove("statistics");
+                    provider.remove("warnings");
+                    provider.remove("notes");
+                }
+            }
+        }
+        SchemaKind::TestList => {
+            if let Value::Object(list) = json {
+                list.remove("checks");
+                list.remove("plugin-library-test-checks");
+                list.remove("plugin-test-checks");
+            }
+        }
+        SchemaKind::Stats | SchemaKind::Submission => (),
+    }
+}
@@ -5,25 +5,29 @@ use colored::Colorize;
 use std::path::Path;
 use std::process::ExitCode;
 
+use super::format_version::to_versioned_json;
+use super::schema::SchemaKind;
 use super::{println_wrapped, println_wrapped_no_indent, TextWrapper};
-use crate::index::PresetIndexResult;
-use crate::plugin::preset_discovery::PresetFile;
+use crate::index::{CrawlBudget, PresetIndexResult};
+use crate::plugin::preset_discovery::{LocationValue, PresetFile};
 
 // TODO: The indexing here always happens in the same process. We should move this over to out of
 //       process scanning at some point.
 
-/// Lists basic information about all installed CLAP plugins.
-pub fn plugins(json: bool) -> Result<ExitCode> {
+/// Lists basic information about all installed CLAP plugins. JSON output uses the layout from
+/// `format_version`.
+pub fn plugins(json: bool, format_version: u32) -> Result<ExitCode> {
     let plugin_index = crate::index::index();
 
     if json {
         println!(
             "{}",
-            serde_json::to_string_pretty(&plugin_index).expect("Could not format JSON")
+            to_versioned_json(&plugin_index, SchemaKind::PluginIndex, format_version)
         );
     } else {
         let mut wrapper = TextWrapper::default();
-        for (i, (plugin_path, metadata)) in plugin_index.0.into_iter().enumerate() {
+        for (i, (plugin_path, library)) in plugin_index.0.into_iter().enumerate() {
+            let metadata = library.metadata;
             if i > 0 {
                 println!();
             }
@@ -42,6 +46,7 @@ pub fn plugins(json: bool) -> Result<ExitCode> {
                     "plugins"
                 },
             );
+            println_wrapped!(wrapper, "factories: [{}]", metadata.factories.join(", "));
 
             for plugin in metadata.plugins {
                 println!();
@@ -78,20 +83,25 @@ pub fn plugins(json: bool) -> Result<ExitCode> {
     Ok(ExitCode::SUCCESS)
 }
 
-/// Lists presets for one, more, or all plugins.
-pub fn presets<P>(json: bool, plugin_paths: Option<&[P]>) -> Result<ExitCode>
+/// Lists presets for one, more, or all plugins. JSON output uses the layout from `format_version`.
+pub fn presets<P>(
+    json: bool,
+    format_version: u32,
+    plugin_paths: Option<&[P]>,
+    budget: CrawlBudget,
+) -> Result<ExitCode>
 where
     P: AsRef<Path>,
 {
     let preset_index = match plugin_paths {
-        Some(plugin_paths) => crate::index::index_presets(plugin_paths, false),
+        Some(plugin_paths) => crate::index::index_presets(plugin_paths, false, budget),
         None => {
             let plugin_index = crate::index::index();
             let all_plugin_paths = plugin_index.0.keys();
 
             // This 'true' indicates that plugins that don't support the preset discovery mechanism
             // should be silently skipped
-            crate::index::index_presets(all_plugin_paths, true)
+            crate::index::index_presets(all_plugin_paths, true, budget)
         }
     }
     .context("Error while crawling presets")?;
@@ -103,7 +113,7 @@ where
     if json {
         println!(
             "{}",
-            serde_json::to_string_pretty(&preset_index).expect("Could not format JSON")
+            to_versioned_json(&preset_index, SchemaKind::PresetIndex, format_version)
         );
     } else {
         let mut wrapper = TextWrapper::default();
@@ -161,6 +171,12 @@ where
                         "presets"
                     },
                 );
+                for warning in &provider_result.warnings {
+                    println_wrapped!(wrapper, "   {}: {}", "WARNING".yellow(), warning);
+                }
+                for note in &provider_result.notes {
+                    println_wrapped!(wrapper, "   {}: {}", "NOTE".cyan(), note);
+                }
 
                 if !provider_result.soundpacks.is_empty() {
                     println!();
@@ -191,11 +207,24 @@ where
                     }
                 }
 
-                if !provider_result.presets.is_empty() {
+                // Presets stored in the plugin's library are listed separately from presets
+                // stored in files
+                let (builtin_presets, file_presets): (Vec<_>, Vec<_>) = provider_result
+                    .presets
+                    .into_iter()
+                    .partition(|(location, _)| *location == LocationValue::Internal);
+                for (title, presets) in [
+                    ("Presets", file_presets),
+                    ("Built-in presets", builtin_presets),
+                ] {
+                    if presets.is_empty() {
+                        continue;
+                    }
+
                     println!();
-                    println!("   Presets:");
+                    println!("   {title}:");
 
-                    for (preset_uri, preset_file) in provider_result.presets {
+                    for (preset_uri, preset_file) in presets {
                         println!();
                         match preset_file {
                             PresetFile::Single(preset) => {
@@ -340,14 +369,14 @@ where
     })
 }
 
-/// Lists all available test cases.
-pub fn tests(json: bool) -> Result<ExitCode> {
+/// Lists all available test cases. JSON output uses the layout from `format_version`.
+pub fn tests(json: bool, format_version: u32) -> Result<ExitCode> {
     let list = crate::tests::TestList::default();
 
     if json {
         println!(
             "{}",
-            serde_json::to_string_pretty(&list).expect("Could not format JSON")
+            to_versioned_json(&list, SchemaKind::TestList, format_version)
         );
     } else {
         let mut wrapper = TextWrapper::default();
@@ -361,6 +390,11 @@ pub fn tests(json: bool) -> Result<ExitCode> {
         for (test_name, test_description) in list.plugin_tests {
             println_wrapped!(wrapper, "- {test_name}: {test_description}");
         }
+
+        println!("\nChecks:");
+        for (check, check_description) in list.checks {
+            println_wrapped!(wrapper, "- {check}: {check_description}");
+        }
     }
 
     Ok(ExitCode::SUCCESS)
@@ -0,0 +1,149 @@
+//! Output sinks for the validator's reports. Reports are normally printed to STDOUT, but they can
+//! also be written to a file or a socket for integration with other tools.
+
+use anyhow::{Context, Result};
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Where a report should be written to. Parsed from the `--output` option's value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputTarget {
+    /// Print the report to STDOUT. This is the default, and can be selected explicitly by passing
+    /// `-` as the output.
+    Stdout,
+    /// Write the report to a file, overwriting it if it already exists.
+    File(PathBuf),
+    /// Connect to a TCP socket at the specified `host:port` address and write the report to it.
+    /// Specified as `tcp://<host>:<port>`.
+    Tcp(String),
+    /// Connect to a Unix domain socket at the specified path and write the report to it.
+    /// Specified as `unix://<path>`.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// A writer for a report produced by one of the validator's commands. Write errors are stored and
+/// returned from [`finish()`][Self::finish()] so reports can be written without having to handle
+/// an error for every single line.
+pub struct OutputSink {
+    writer: Box<dyn Write>,
+    /// Whether this sink writes to STDOUT. Colors and terminal width wrapping are only used in that
+    /// case.
+    is_stdout: bool,
+    /// The first error that occurred while writing to `writer`, if any. Subsequent writes are
+    /// ignored after this point.
+    error: Option<io::Error>,
+}
+
+impl FromStr for OutputTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            Ok(OutputTarget::Stdout)
+        } else if let Some(address) = s.strip_prefix("tcp://") {
+            if address.is_empty() {
+                Err(String::from(
+                    "TCP outputs need to be specified as tcp://<host>:<port>",
+                ))
+            } else {
+                Ok(OutputTarget::Tcp(address.to_owned()))
+            }
+        } else if let Some(path) = s.strip_prefix("unix://") {
+            #[cfg(unix)]
+            {
+                Ok(OutputTarget::Unix(PathBuf::from(path)))
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = path;
+                Err(String::from(
+                    "Unix domain sockets are not supported on this platform",
+                ))
+            }
+        } else if s.is_empty() {
+            Err(String::from("The output path cannot be empty"))
+        } else {
+            Ok(OutputTarget::File(PathBuf::from(s)))
+        }
+    }
+}
+
+impl Display for OutputTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputTarget::Stdout => write!(f, "STDOUT"),
+            OutputTarget::File(path) => write!(f, "'{}'", path.display()),
+            OutputTarget::Tcp(address) => write!(f, "tcp://{address}"),
+            #[cfg(unix)]
+            OutputTarget::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+impl OutputTarget {
+    /// Open a sink for this target. For sockets this connects to the socket.
+    pub fn open(&self) -> Result<OutputSink> {
+        let writer: Box<dyn Write> = match self {
+            OutputTarget::Stdout => return Ok(OutputSink::stdout()),
+            OutputTarget::File(path) => Box::new(BufWriter::new(
+                fs::File::create(path)
+                    .with_context(|| format!("Could not create the output file {self}"))?,
+            )),
+            OutputTarget::Tcp(address) => Box::new(BufWriter::new(
+                TcpStream::connect(address.as_str())
+                    .with_context(|| format!("Could not connect to {self}"))?,
+            )),
+            #[cfg(unix)]
+            OutputTarget::Unix(path) => Box::new(BufWriter::new(
+                std::os::unix::net::UnixStream::connect(path)
+                    .with_context(|| format!("Could not connect to {self}"))?,
+            )),
+        };
+
+        Ok(OutputSink {
+            writer,
+            is_stdout: false,
+            error: None,
+        })
+    }
+}
+
+impl OutputSink {
+    /// A sink that prints to STDOUT.
+    pub fn stdout() -> Self {
+        OutputSink {
+            writer: Box::new(io::stdout()),
+            is_stdout: true,
+            error: None,
+        }
+    }
+
+    /// Whether this sink prints to STDOUT.
+    pub fn is_stdout(&self) -> bool {
+        self.is_stdout
+    }
+
+    /// Write a line of text to the sink. Errors are stored and returned from
+    /// [`finish()`][Self::finish()].
+    pub fn write_line(&mut self, line: impl AsRef<str>) {
+        if self.error.is_none() {
+            if let Err(err) = writeln!(self.writer, "{}", line.as_ref()) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    /// Flush the sink, returning the first error that occurred while writing to it.
+    pub fn finish(mut self) -> Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err).context("Could not write the output");
+        }
+
+        self.writer.flush().context("Could not write the output")
+    }
+}
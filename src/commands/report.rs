@@ -0,0 +1,306 @@
+//! The human readable report printed by `clap-validator validate`.
+
+use colored::{ColoredString, Colorize};
+use std::str::FromStr;
+
+use super::{println_wrapped, TextWrapper};
+use crate::plugin::host::HostExtensionUsage;
+use crate::tests::{PluginLibraryTestCase, PluginTestCase, TestCase, TestResult, TestStatus};
+use crate::validator::{ValidationResult, ValidationTally};
+
+/// The category used for tests that are not known to this version of the validator.
+const UNKNOWN_CATEGORY: &str = "Other";
+
+/// Options for the human readable report.
+pub struct ReportOptions {
+    /// Only show failed tests and tests that resulted in a warning.
+    pub only_failed: bool,
+    /// When `only_failed` is set, also show this many tests before and after every failed test.
+    pub context: usize,
+}
+
+/// Print a human readable report for a validation run. Tests are grouped by plugin library or
+/// plugin and by test category. This is followed by a verdict for every plugin library and plugin,
+/// and the total number of passed and failed tests. The verdicts for plugins that implement
+/// extensions the validator does not test are followed by a list of those extensions, and the
+/// verdicts for plugins are followed by the host extensions they queried and used.
+pub fn print_report(wrapper: &mut TextWrapper, result: &ValidationResult, options: &ReportOptions) {
+    print_section(
+        wrapper,
+        "Plugin library tests",
+        result
+            .plugin_library_tests
+            .iter()
+            .map(|(library_path, tests)| (library_path.display().to_string(), tests.as_slice())),
+        |test_name| {
+            PluginLibraryTestCase::from_str(test_name)
+                .map(|test| test.category())
+                .unwrap_or(UNKNOWN_CATEGORY)
+        },
+        options,
+    );
+    print_section(
+        wrapper,
+        "Plugin tests",
+        result
+            .plugin_tests
+            .iter()
+            .map(|(plugin_id, tests)| (plugin_id.clone(), tests.as_slice())),
+        |test_name| {
+            PluginTestCase::from_str(test_name)
+                .map(|test| test.category())
+                .unwrap_or(UNKNOWN_CATEGORY)
+        },
+        options,
+    );
+
+    // The verdicts are always computed from all tests, even when only the failed tests are shown
+    if !result.plugin_library_tests.is_empty() || !result.plugin_tests.is_empty() {
+        wrapper.print_unwrapped("Verdict:");
+        wrapper.print_unwrapped("");
+        let verdicts = result
+            .plugin_library_tests
+            .iter()
+            .map(|(library_path, tests)| (library_path.display().to_string(), tests))
+            .chain(
+                result
+                    .plugin_tests
+                    .iter()
+                    .map(|(plugin_id, tests)| (plugin_id.clone(), tests)),
+            );
+        for (name, tests) in verdicts {
+            let tally = ValidationTally::count(tests);
+            println_wrapped!(
+                wrapper,
+                " - {name}: {} ({})",
+                verdict_label(&tally),
+                tally_summary(&tally)
+            );
+
+            // A passing verdict should not be mistaken for full coverage
+            let extension_coverage_test_name = PluginTestCase::ExtensionCoverage.to_string();
+            let unvalidated_extensions = tests
+                .iter()
+                .find(|test| test.name == extension_coverage_test_name)
+                .filter(|test| matches!(test.status, TestStatus::Success { .. }))
+                .and_then(|test| test.status.details());
+            if let Some(unvalidated_extensions) = unvalidated_extensions {
+                println_wrapped!(wrapper, "   {}", unvalidated_extensions.yellow());
+            }
+
+            if let Some(plugin) = result.plugins.get(&name) {
+                if let Some(summary) = host_requirements_summary(&plugin.host_requirements) {
+                    println_wrapped!(wrapper, "   {summary}");
+                }
+            }
+        }
+        wrapper.print_unwrapped("");
+    }
+
+    let tally = result.tally();
+    let num_tests = tally.total();
+    println_wrapped!(
+        wrapper,
+        "{} {} run, {}",
+        num_tests,
+        if num_tests == 1 { "test" } else { "tests" },
+        tally_summary(&tally)
+    );
+
+    // Most tests use pseudo-random data, so reproducing a failure requires the same seed
+    if tally.num_failed > 0 {
+        if let Some(header) = &result.header {
+            println_wrapped!(
+                wrapper,
+                "The tests used the pseudo-random seed {}. Pass '--seed {}' to reproduce these \
+                 results.",
+                header.prng_seed,
+                header.prng_seed
+            );
+        }
+    }
+}
+
+/// Summarize the host extensions a plugin queried and used during validation. Extensions the plugin
+/// queried but never called any functions on are listed separately. Returns `None` if the plugin
+/// did not query any host extensions.
+fn host_requirements_summary(host_requirements: &HostExtensionUsage) -> Option<String> {
+    if host_requirements.is_empty() {
+        return None;
+    }
+
+    let format_ids = |ids: Vec<&String>| {
+        ids.into_iter()
+            .map(|id| format!("'{id}'"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let used: Vec<&String> = host_requirements.used.iter().collect();
+    let queried_only: Vec<&String> = host_requirements
+        .queried
+        .difference(&host_requirements.used)
+        .collect();
+
+    let mut summary = if used.is_empty() {
+        String::from("Host extensions used: none.")
+    } else {
+        format!("Host extensions used: {}.", format_ids(used))
+    };
+    if !queried_only.is_empty() {
+        summary.push_str(&format!(
+            " Queried but not used: {}.",
+            format_ids(queried_only)
+        ));
+    }
+
+    Some(summary)
+}
+
+/// Print the results for either the plugin library tests or the plugin tests. `entries` contains
+/// the library path or plugin ID and the test results for each library or plugin, and `category`
+/// returns a test's category based on its name. Nothing is printed if there are no tests to show.
+fn print_section<'a>(
+    wrapper: &mut TextWrapper,
+    title: &str,
+    entries: impl IntoIterator<Item = (String, &'a [TestResult])>,
+    category: fn(&str) -> &'static str,
+    options: &ReportOptions,
+) {
+    let mut printed_title = false;
+    for (name, tests) in entries {
+        let visible_tests = visible_tests(tests, options);
+        if visible_tests.is_empty() {
+            continue;
+        }
+
+        if !printed_title {
+            wrapper.print_unwrapped(format!("{title}:"));
+            printed_title = true;
+        }
+
+        wrapper.print_unwrapped("");
+        println_wrapped!(wrapper, " - {name}");
+
+        // Tests are grouped by category, with categories appearing in the order of their first test
+        let mut categories: Vec<(&str, Vec<&TestResult>)> = Vec::new();
+        for test in visible_tests {
+            let test_category = category(&test.name);
+            match categories
+                .iter_mut()
+                .find(|(existing_category, _)| *existing_category == test_category)
+            {
+                Some((_, category_tests)) => category_tests.push(test),
+                None => categories.push((test_category, vec![test])),
+            }
+        }
+
+        for (category, tests) in categories {
+            wrapper.print_unwrapped("");
+            println_wrapped!(wrapper, "   {category}:");
+
+            for test in tests {
+                wrapper.print_unwrapped("");
+                print_test(wrapper, test);
+            }
+        }
+    }
+
+    if printed_title {
+        wrapper.print_unwrapped("");
+    }
+}
+
+/// Print a single test's name, description, status, thread safety issues, and the messages the
+/// plugin logged during the test.
+fn print_test(wrapper: &mut TextWrapper, test: &TestResult) {
+    println_wrapped!(wrapper, "   - {}: {}", test.name, test.description);
+
+    let status_text = status_label(&test.status);
+    match &test.status {
+        // Errors with context are printed as a hierarchy of causes instead of a single line
+        TestStatus::Failed { causes, .. } if causes.len() > 1 => {
+            println_wrapped!(wrapper, "     {status_text}: {}", causes[0]);
+            for (depth, cause) in causes[1..].iter().enumerate() {
+                println_wrapped!(wrapper, "     {}Caused by: {cause}", "  ".repeat(depth + 1));
+            }
+        }
+        _ => {
+            let test_result = match test.status.details() {
+                Some(reason) => format!("     {status_text}: {reason}"),
+                None => format!("     {status_text}"),
+            };
+            wrapper.print_auto(test_result);
+        }
+    }
+
+    if !test.thread_safety.is_empty() {
+        println_wrapped!(wrapper, "     Thread safety issues:");
+        for issue in &test.thread_safety {
+            println_wrapped!(wrapper, "     - {issue}");
+        }
+    }
+
+    if !test.plugin_log.is_empty() {
+        println_wrapped!(wrapper, "     Plugin log:");
+        for message in &test.plugin_log {
+            println_wrapped!(wrapper, "     - {message}");
+        }
+    }
+}
+
+/// Get the tests that should be shown in the report. If `options.only_failed` is set, then this
+/// only includes failed tests, tests resulting in a warning, and `options.context` tests around
+/// them.
+fn visible_tests<'a>(tests: &'a [TestResult], options: &ReportOptions) -> Vec<&'a TestResult> {
+    if !options.only_failed {
+        return tests.iter().collect();
+    }
+
+    let failed_indices: Vec<usize> = tests
+        .iter()
+        .enumerate()
+        .filter(|(_, test)| test.status.failed_or_warning())
+        .map(|(idx, _)| idx)
+        .collect();
+
+    tests
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| {
+            failed_indices
+                .iter()
+                .any(|failed_idx| idx.abs_diff(*failed_idx) <= options.context)
+        })
+        .map(|(_, test)| test)
+        .collect()
+}
+
+/// The colored label for a test's status.
+fn status_label(status: &TestStatus) -> ColoredString {
+    match status {
+        TestStatus::Success { .. } => "PASSED".green(),
+        TestStatus::Crashed { .. } => "CRASHED".red().bold(),
+        TestStatus::Failed { .. } => "FAILED".red(),
+        TestStatus::Skipped { .. } => "SKIPPED".yellow(),
+        TestStatus::Warning { .. } => "WARNING".yellow(),
+    }
+}
+
+/// The colored verdict for a plugin library or plugin based on its test results.
+fn verdict_label(tally: &ValidationTally) -> ColoredString {
+    if tally.num_failed > 0 {
+        "FAILED".red().bold()
+    } else if tally.num_warnings > 0 {
+        "PASSED WITH WARNINGS".yellow().bold()
+    } else {
+        "PASSED".green().bold()
+    }
+}
+
+/// Format the number of passed, failed, skipped tests, and the number of warnings.
+fn tally_summary(tally: &ValidationTally) -> String {
+    format!(
+        "{} passed, {} failed, {} skipped, {} warnings",
+        tally.num_passed, tally.num_failed, tally.num_skipped, tally.num_warnings
+    )
+}
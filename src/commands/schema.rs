@@ -0,0 +1,425 @@
+//! Commands for printing the JSON schemas describing the validator's machine readable output.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::process::ExitCode;
+
+/// The JSON outputs that have a schema. Each schema is stored in the `schemas` directory at the
+/// root of the repository and embedded in the binary.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SchemaKind {
+    /// The output of `clap-validator validate --json`.
+    ValidationResult,
+    /// The output of `clap-validator list plugins --json`.
+    PluginIndex,
+    /// The output of `clap-validator list presets --json`.
+    PresetIndex,
+    /// The output of `clap-validator list tests --json`.
+    TestList,
+    /// The output of `clap-validator stats --json`.
+    Stats,
+    /// The report `clap-validator validate --submit-results` sends to the endpoint.
+    Submission,
+}
+
+impl SchemaKind {
+    /// The JSON schema document for this kind of output. This describes the layout for
+    /// [`CURRENT_FORMAT_VERSION`][super::format_version::CURRENT_FORMAT_VERSION].
+    pub fn schema(&self) -> &'static str {
+        match self {
+            SchemaKind::ValidationResult => {
+                include_str!("../../schemas/validation-result.schema.json")
+            }
+            SchemaKind::PluginIndex => include_str!("../../schemas/plugin-index.schema.json"),
+            SchemaKind::PresetIndex => include_str!("../../schemas/preset-index.schema.json"),
+            SchemaKind::TestList => include_str!("../../schemas/test-list.schema.json"),
+            SchemaKind::Stats => include_str!("../../schemas/stats.schema.json"),
+            SchemaKind::Submission => include_str!("../../schemas/submission.schema.json"),
+        }
+    }
+}
+
+/// Prints the JSON schema for one of the validator's JSON outputs.
+pub fn schema(kind: SchemaKind) -> Result<ExitCode> {
+    print!("{}", kind.schema());
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use clap::Parser;
+    use regex::Regex;
+    use serde_json::Value;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use super::SchemaKind;
+    use crate::commands::format_version::{to_versioned_json, CURRENT_FORMAT_VERSION};
+    use crate::index::{Index, IndexedLibrary, PresetIndex, PresetIndexResult, ProviderPresets};
+    use crate::plugin::host::{HostExtensionUsage, LogMessage, LogSeverity};
+    use crate::plugin::library::{PluginLibraryMetadata, PluginMetadata};
+    use crate::tests::{SkipReason, TestList, TestResult, TestStatus};
+    use crate::validator::{ReportHeader, ValidatedPlugin, ValidationResult, ValidatorSettings};
+
+    /// Used to parse [`ValidatorSettings`] the same way `clap-validator validate` would.
+    #[derive(Parser)]
+    struct SettingsParser {
+        #[command(flatten)]
+        settings: ValidatorSettings,
+    }
+
+    /// Check whether `value` conforms to `schema`. `root` is the schema document used for resolving
+    /// references. This only implements the parts of JSON Schema used by the validator's schemas,
+    /// and it panics when it encounters something it doesn't understand. Returns a list of every
+    /// violation.
+    fn conformance_errors(root: &Value, schema: &Value, value: &Value, path: &str) -> Vec<String> {
+        let schema = match schema {
+            Value::Bool(true) => return Vec::new(),
+            Value::Bool(false) => return vec![format!("{path}: no value is allowed here")],
+            Value::Object(schema) => schema,
+            _ => panic!("Invalid schema for '{path}': {schema}"),
+        };
+
+        let mut errors = Vec::new();
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix("#/")
+                .unwrap_or_else(|| panic!("Unsupported reference '{reference}'"))
+                .split('/')
+                .fold(root, |schema, key| &schema[key]);
+            assert!(!target.is_null(), "Unresolved reference '{reference}'");
+            errors.extend(conformance_errors(root, target, value, path));
+        }
+
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::String(ty) => vec![ty.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => panic!("Invalid type for '{path}': {types}"),
+            };
+            if !types.iter().any(|ty| has_type(value, ty)) {
+                errors.push(format!("{path}: expected {types:?}, found {value}"));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if value != expected {
+                errors.push(format!("{path}: expected {expected}, found {value}"));
+            }
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                errors.push(format!("{path}: {value} is not one of {options:?}"));
+            }
+        }
+        if let (Some(minimum), Some(number)) = (
+            schema.get("minimum").and_then(Value::as_f64),
+            value.as_f64(),
+        ) {
+            if number < minimum {
+                errors.push(format!("{path}: {number} is smaller than {minimum}"));
+            }
+        }
+        if let (Some(maximum), Some(number)) = (
+            schema.get("maximum").and_then(Value::as_f64),
+            value.as_f64(),
+        ) {
+            if number > maximum {
+                errors.push(format!("{path}: {number} is larger than {maximum}"));
+            }
+        }
+        if let (Some(pattern), Value::String(string)) =
+            (schema.get("pattern").and_then(Value::as_str), value)
+        {
+            if !Regex::new(pattern).unwrap().is_match(string) {
+                errors.push(format!("{path}: '{string}' does not match '{pattern}'"));
+            }
+        }
+
+        if let Value::Object(object) = value {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(key) {
+                        errors.push(format!("{path}: missing required property '{key}'"));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, property) in object {
+                let property_path = format!("{path}/{key}");
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property_schema) => errors.extend(conformance_errors(
+                        root,
+                        property_schema,
+                        property,
+                        &property_path,
+                    )),
+                    None => {
+                        if let Some(additional_properties) = schema.get("additionalProperties") {
+                            errors.extend(conformance_errors(
+                                root,
+                                additional_properties,
+                                property,
+                                &property_path,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Value::Array(array) = value {
+            let prefix_items = schema
+                .get("prefixItems")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for (idx, item) in array.iter().enumerate() {
+                let item_path = format!("{path}/{idx}");
+                if let Some(item_schema) = prefix_items.get(idx).or_else(|| schema.get("items")) {
+                    errors.extend(conformance_errors(root, item_schema, item, &item_path));
+                }
+            }
+
+            if let Some(min_items) = schema.get("minItems").and_then(Value::as_u64) {
+                if (array.len() as u64) < min_items {
+                    errors.push(format!("{path}: expected at least {min_items} items"));
+                }
+            }
+            if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+                if (array.len() as u64) > max_items {
+                    errors.push(format!("{path}: expected at most {max_items} items"));
+                }
+            }
+            if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+                for (idx, item) in array.iter().enumerate() {
+                    if array[..idx].contains(item) {
+                        errors.push(format!("{path}: {item} occurs more than once"));
+                    }
+                }
+            }
+        }
+
+        for subschema in schema
+            .get("allOf")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            errors.extend(conformance_errors(root, subschema, value, path));
+        }
+        if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+            let num_matches = options
+                .iter()
+                .filter(|option| conformance_errors(root, option, value, path).is_empty())
+                .count();
+            if num_matches != 1 {
+                errors.push(format!(
+                    "{path}: expected exactly one 'oneOf' option to match, but {num_matches} did"
+                ));
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            if conformance_errors(root, condition, value, path).is_empty() {
+                if let Some(then) = schema.get("then") {
+                    errors.extend(conformance_errors(root, then, value, path));
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn has_type(value: &Value, ty: &str) -> bool {
+        match ty {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => panic!("Unknown type '{ty}'"),
+        }
+    }
+
+    /// Serialize `output` the same way the validator's commands would and assert that the result
+    /// conforms to the schema for `kind`.
+    fn assert_conforms(output: &impl serde::Serialize, kind: SchemaKind) {
+        let schema: Value = serde_json::from_str(kind.schema()).unwrap();
+        let json: Value =
+            serde_json::from_str(&to_versioned_json(output, kind, CURRENT_FORMAT_VERSION)).unwrap();
+
+        let errors = conformance_errors(&schema, &schema, &json, "");
+        assert!(
+            errors.is_empty(),
+            "The {kind:?} output does not conform to its schema:\n{}",
+            errors.join("\n")
+        );
+    }
+
+    fn plugin_metadata() -> PluginMetadata {
+        PluginMetadata {
+            id: String::from("com.example.plugin"),
+            name: String::from("Example Plugin"),
+            version: Some(String::from("1.0.0")),
+            vendor: None,
+            description: None,
+            manual_url: None,
+            support_url: Some(String::from("https://example.com")),
+            features: vec![String::from("audio-effect"), String::from("stereo")],
+        }
+    }
+
+    fn host_extension_usage() -> HostExtensionUsage {
+        let mut usage = HostExtensionUsage::default();
+        usage.queried.insert(String::from("clap.log"));
+        usage.queried.insert(String::from("clap.gui"));
+        usage.used.insert(String::from("clap.log"));
+
+        usage
+    }
+
+    /// A test result for every possible test status, including all optional fields.
+    fn test_results() -> Vec<TestResult> {
+        let statuses = [
+            TestStatus::Success { details: None },
+            TestStatus::Crashed {
+                details: String::from("signal: 11 (SIGSEGV)"),
+            },
+            TestStatus::from_error(
+                &anyhow::anyhow!("Root cause").context("Could not do the thing"),
+            ),
+            TestStatus::Failed {
+                details: None,
+                causes: Vec::new(),
+            },
+            TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(String::from("The plugin does not implement the extension.")),
+            },
+            TestStatus::Warning {
+                details: Some(String::from("The plugin was slow.")),
+            },
+        ];
+
+        statuses
+            .into_iter()
+            .enumerate()
+            .map(|(idx, status)| TestResult {
+                name: format!("test-{idx}"),
+                description: String::from("A test."),
+                status,
+                thread_safety: vec![String::from(
+                    "'clap_host::request_restart()' may only be called from the main thread.",
+                )],
+                plugin_log: vec![LogMessage {
+                    severity: LogSeverity::PluginMisbehaving,
+                    message: String::from("Oops"),
+                }],
+                host_extensions: host_extension_usage(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn schemas_are_valid() {
+        for kind in <SchemaKind as clap::ValueEnum>::value_variants() {
+            let schema: Value = serde_json::from_str(kind.schema())
+                .unwrap_or_else(|err| panic!("The {kind:?} schema is not valid JSON: {err}"));
+            assert_eq!(
+                schema["$schema"], "https://json-schema.org/draft/2020-12/schema",
+                "The {kind:?} schema does not declare its dialect"
+            );
+        }
+    }
+
+    #[test]
+    fn validation_result_conforms() {
+        let settings =
+            SettingsParser::parse_from(["clap-validator", "/plugins/example.clap"]).settings;
+        let result = ValidationResult {
+            header: Some(ReportHeader {
+                clap_validator_version: env!("CARGO_PKG_VERSION"),
+                clap_version: String::from("1.1.8"),
+                os: std::env::consts::OS,
+                arch: std::env::consts::ARCH,
+                settings,
+                prng_seed: 0,
+                started_at: Utc::now(),
+                finished_at: Utc::now(),
+            }),
+            plugins: BTreeMap::from([(
+                String::from("com.example.plugin"),
+                ValidatedPlugin {
+                    library_path: PathBuf::from("/plugins/example.clap"),
+                    library_sha256: "0".repeat(64),
+                    library_clap_version: (1, 1, 8),
+                    metadata: plugin_metadata(),
+                    host_requirements: host_extension_usage(),
+                },
+            )]),
+            plugin_library_tests: BTreeMap::from([(
+                PathBuf::from("/plugins/example.clap"),
+                test_results(),
+            )]),
+            plugin_tests: BTreeMap::from([(String::from("com.example.plugin"), test_results())]),
+        };
+
+        assert_conforms(&result, SchemaKind::ValidationResult);
+    }
+
+    #[test]
+    fn test_results_round_trip() {
+        // Test results are sent from out-of-process tests to the validator as JSON, and the
+        // `stats` command reads them back from result files
+        for result in test_results() {
+            let json = serde_json::to_string(&result).unwrap();
+            let round_tripped: TestResult = serde_json::from_str(&json).unwrap();
+            assert_eq!(serde_json::to_string(&round_tripped).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn plugin_index_conforms() {
+        let index = Index(BTreeMap::from([(
+            PathBuf::from("/plugins/example.clap"),
+            IndexedLibrary {
+                sha256: "0".repeat(64),
+                metadata: PluginLibraryMetadata {
+                    version: (1, 1, 8),
+                    factories: vec![String::from("clap.plugin-factory")],
+                    plugins: vec![plugin_metadata()],
+                },
+            },
+        )]));
+
+        assert_conforms(&index, SchemaKind::PluginIndex);
+    }
+
+    #[test]
+    fn preset_index_conforms() {
+        let index = PresetIndex(BTreeMap::from([
+            (
+                PathBuf::from("/plugins/example.clap"),
+                PresetIndexResult::Success(vec![ProviderPresets {
+                    provider_name: String::from("Example Presets"),
+                    ..ProviderPresets::default()
+                }]),
+            ),
+            (
+                PathBuf::from("/plugins/broken.clap"),
+                PresetIndexResult::Error(String::from("Could not load the plugin library")),
+            ),
+        ]));
+
+        assert_conforms(&index, SchemaKind::PresetIndex);
+    }
+
+    #[test]
+    fn test_list_conforms() {
+        assert_conforms(&TestList::default(), SchemaKind::TestList);
+    }
+}
@@ -0,0 +1,328 @@
+//! Commands for aggregating the results of one or more validator runs into summary statistics.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use super::format_version::to_versioned_json;
+use super::schema::SchemaKind;
+use crate::tests::{SkipReason, TestResult, TestStatus};
+
+/// The number of tests listed in the most common failures section of the Markdown output.
+const NUM_MOST_FAILED_TESTS: usize = 10;
+/// The number of root causes listed in the most common failure causes section of the Markdown
+/// output.
+const NUM_MOST_COMMON_FAILURE_CAUSES: usize = 10;
+
+/// The parts of a `clap-validator validate --json` result file needed to compute the statistics.
+/// Only the test results are read, so result files in any format version can be used.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ResultFile {
+    #[serde(default)]
+    plugin_library_tests: BTreeMap<PathBuf, Vec<TestResult>>,
+    #[serde(default)]
+    plugin_tests: BTreeMap<String, Vec<TestResult>>,
+}
+
+/// Summary statistics for the results of one or more validator runs.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Statistics {
+    /// The number of result files the statistics were computed from.
+    pub num_result_files: usize,
+    /// The number of unique plugin IDs in the result files.
+    pub num_plugins: usize,
+    /// Statistics for every test, indexed by the test's name.
+    pub tests: BTreeMap<String, TestStatistics>,
+    /// How often every skip reason occurred across all tests.
+    pub skip_reasons: BTreeMap<SkipReason, usize>,
+    /// How often every root cause occurred across all failed tests. The root cause is the last
+    /// entry in a failure's context chain. Failures without a context chain are grouped by their
+    /// details instead.
+    pub failure_causes: BTreeMap<String, usize>,
+    /// How many plugins query and use every host extension, indexed by the extension's ID. Only
+    /// extensions that were queried by at least one plugin are included.
+    pub extension_adoption: BTreeMap<String, ExtensionAdoption>,
+}
+
+/// The outcomes of a single test across all result files.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestStatistics {
+    pub num_runs: usize,
+    pub num_passed: usize,
+    pub num_warnings: usize,
+    pub num_failed: usize,
+    pub num_crashed: usize,
+    pub num_skipped: usize,
+    /// The fraction of the runs that were not skipped where the test passed, with or without
+    /// warnings. `None` if the test was skipped in every run.
+    pub pass_rate: Option<f64>,
+}
+
+/// How many of the plugins in the result files query and use a host extension.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExtensionAdoption {
+    pub num_plugins: usize,
+    /// The number of plugins that queried the extension through `clap_host::get_extension()`.
+    pub num_querying: usize,
+    /// The number of plugins that called at least one of the extension's functions.
+    pub num_using: usize,
+    /// The fraction of the plugins that used the extension.
+    pub adoption_rate: f64,
+}
+
+/// Aggregate one or more result files produced by `clap-validator validate --json` into summary
+/// statistics. These are printed as Markdown, or as JSON using the layout from `format_version`.
+pub fn stats(result_paths: &[PathBuf], json: bool, format_version: u32) -> Result<ExitCode> {
+    let result_files = result_paths
+        .iter()
+        .map(|path| read_result_file(path))
+        .collect::<Result<Vec<_>>>()?;
+    let statistics = Statistics::compute(&result_files);
+
+    if json {
+        println!(
+            "{}",
+            to_versioned_json(&statistics, SchemaKind::Stats, format_version)
+        );
+    } else {
+        print!("{}", statistics.to_markdown());
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Read and parse a result file written by `clap-validator validate --json`.
+fn read_result_file(path: &Path) -> Result<ResultFile> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read '{}'", path.display()))?;
+
+    serde_json::from_str(&contents).with_context(|| {
+        format!(
+            "'{}' is not a valid clap-validator result file",
+            path.display()
+        )
+    })
+}
+
+impl Statistics {
+    /// Compute the statistics for one or more parsed result files.
+    fn compute(result_files: &[ResultFile]) -> Self {
+        let mut statistics = Statistics {
+            num_result_files: result_files.len(),
+            ..Default::default()
+        };
+        let mut plugin_ids: BTreeSet<&str> = BTreeSet::new();
+        // The plugins that queried a host extension, and the plugins that used it, indexed by the
+        // extension's ID
+        let mut extension_usage: BTreeMap<&str, (BTreeSet<&str>, BTreeSet<&str>)> = BTreeMap::new();
+        for result_file in result_files {
+            plugin_ids.extend(result_file.plugin_tests.keys().map(String::as_str));
+
+            let library_tests = result_file
+                .plugin_library_tests
+                .values()
+                .flatten()
+                .map(|test| (None, test));
+            let plugin_tests = result_file
+                .plugin_tests
+                .iter()
+                .flat_map(|(plugin_id, tests)| {
+                    tests
+                        .iter()
+                        .map(move |test| (Some(plugin_id.as_str()), test))
+                });
+            for (plugin_id, test) in library_tests.chain(plugin_tests) {
+                statistics
+                    .tests
+                    .entry(test.name.clone())
+                    .or_default()
+                    .count(&test.status);
+
+                match &test.status {
+                    TestStatus::Skipped { reason, .. } => {
+                        *statistics.skip_reasons.entry(*reason).or_default() += 1;
+                    }
+                    TestStatus::Failed { details, causes } => {
+                        let root_cause = causes.last().or(details.as_ref());
+                        if let Some(root_cause) = root_cause {
+                            *statistics
+                                .failure_causes
+                                .entry(root_cause.clone())
+                                .or_default() += 1;
+                        }
+                    }
+                    _ => (),
+                }
+
+                if let Some(plugin_id) = plugin_id {
+                    for extension_id in &test.host_extensions.queried {
+                        let (querying, _) = extension_usage.entry(extension_id).or_default();
+                        querying.insert(plugin_id);
+                    }
+                    for extension_id in &test.host_extensions.used {
+                        let (_, using) = extension_usage.entry(extension_id).or_default();
+                        using.insert(plugin_id);
+                    }
+                }
+            }
+        }
+
+        statistics.num_plugins = plugin_ids.len();
+        // A plugin may have been validated more than once. If it queried or used an extension in
+        // any of those runs, then it's counted once.
+        statistics.extension_adoption = extension_usage
+            .into_iter()
+            .map(|(extension_id, (querying, using))| {
+                (
+                    extension_id.to_owned(),
+                    ExtensionAdoption {
+                        num_plugins: statistics.num_plugins,
+                        num_querying: querying.len(),
+                        num_using: using.len(),
+                        adoption_rate: using.len() as f64 / statistics.num_plugins as f64,
+                    },
+                )
+            })
+            .collect();
+        for test in statistics.tests.values_mut() {
+            let num_not_skipped = test.num_runs - test.num_skipped;
+            if num_not_skipped > 0 {
+                test.pass_rate =
+                    Some((test.num_passed + test.num_warnings) as f64 / num_not_skipped as f64);
+            }
+        }
+
+        statistics
+    }
+
+    /// Format the statistics as a Markdown document.
+    fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# clap-validator statistics\n\n");
+        markdown.push_str(&format!(
+            "Aggregated from {} result {} containing {} {}.\n",
+            self.num_result_files,
+            if self.num_result_files == 1 {
+                "file"
+            } else {
+                "files"
+            },
+            self.num_plugins,
+            if self.num_plugins == 1 {
+                "plugin"
+            } else {
+                "plugins"
+            },
+        ));
+
+        markdown.push_str("\n## Tests\n\n");
+        markdown.push_str(
+            "| Test | Runs | Passed | Warnings | Failed | Crashed | Skipped | Pass rate |\n\
+             | --- | --: | --: | --: | --: | --: | --: | --: |\n",
+        );
+        for (test_name, test) in &self.tests {
+            markdown.push_str(&format!(
+                "| `{test_name}` | {} | {} | {} | {} | {} | {} | {} |\n",
+                test.num_runs,
+                test.num_passed,
+                test.num_warnings,
+                test.num_failed,
+                test.num_crashed,
+                test.num_skipped,
+                test.pass_rate
+                    .map(format_percentage)
+                    .unwrap_or_else(|| String::from("-")),
+            ));
+        }
+
+        let mut most_failed_tests: Vec<_> = self
+            .tests
+            .iter()
+            .filter(|(_, test)| test.num_failed + test.num_crashed > 0)
+            .collect();
+        most_failed_tests
+            .sort_by_key(|(_, test)| std::cmp::Reverse(test.num_failed + test.num_crashed));
+        if !most_failed_tests.is_empty() {
+            markdown.push_str("\n## Most common failures\n\n");
+            for (test_name, test) in most_failed_tests.into_iter().take(NUM_MOST_FAILED_TESTS) {
+                markdown.push_str(&format!(
+                    "- `{test_name}`: {} failed, {} crashed\n",
+                    test.num_failed, test.num_crashed
+                ));
+            }
+        }
+
+        let mut failure_causes: Vec<_> = self.failure_causes.iter().collect();
+        failure_causes.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+        if !failure_causes.is_empty() {
+            markdown.push_str(
+                "\n## Most common failure causes\n\n| Root cause | Count |\n| --- | --: |\n",
+            );
+            for (cause, count) in failure_causes
+                .into_iter()
+                .take(NUM_MOST_COMMON_FAILURE_CAUSES)
+            {
+                markdown.push_str(&format!("| {} | {count} |\n", escape_table_cell(cause)));
+            }
+        }
+
+        let mut skip_reasons: Vec<_> = self.skip_reasons.iter().collect();
+        skip_reasons.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+        if !skip_reasons.is_empty() {
+            markdown.push_str("\n## Skip reasons\n\n| Reason | Count |\n| --- | --: |\n");
+            for (reason, count) in skip_reasons {
+                markdown.push_str(&format!("| `{reason}` | {count} |\n"));
+            }
+        }
+
+        if !self.extension_adoption.is_empty() {
+            markdown.push_str(
+                "\n## Host extension adoption\n\n\
+                 | Extension | Querying plugins | Using plugins | Adoption |\n\
+                 | --- | --: | --: | --: |\n",
+            );
+            for (extension_id, adoption) in &self.extension_adoption {
+                markdown.push_str(&format!(
+                    "| `{extension_id}` | {} of {} | {} of {} | {} |\n",
+                    adoption.num_querying,
+                    adoption.num_plugins,
+                    adoption.num_using,
+                    adoption.num_plugins,
+                    format_percentage(adoption.adoption_rate),
+                ));
+            }
+        }
+
+        markdown
+    }
+}
+
+impl TestStatistics {
+    /// Count a single outcome of the test.
+    fn count(&mut self, status: &TestStatus) {
+        self.num_runs += 1;
+        match status {
+            TestStatus::Success { .. } => self.num_passed += 1,
+            TestStatus::Warning { .. } => self.num_warnings += 1,
+            TestStatus::Failed { .. } => self.num_failed += 1,
+            TestStatus::Crashed { .. } => self.num_crashed += 1,
+            TestStatus::Skipped { .. } => self.num_skipped += 1,
+        }
+    }
+}
+
+/// Make a message safe to use in a Markdown table cell. Pipes would otherwise end the cell, and
+/// line breaks would end the table.
+fn escape_table_cell(message: &str) -> String {
+    message.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Format a fraction between zero and one as a percentage.
+fn format_percentage(fraction: f64) -> String {
+    format!("{:.1}%", fraction * 100.0)
+}
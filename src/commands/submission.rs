@@ -0,0 +1,168 @@
+//! Opt-in submission of validation results to a user-specified HTTP endpoint. This allows plugin
+//! vendors and the CLAP project to collect conformance data from many CI runs without any custom
+//! glue code.
+//!
+//! The submitted report is a reduced version of the `validate --json` output. Plugins are only
+//! identified by their ID, their version, and the SHA-256 hash of their library's binary. Test
+//! results only contain the test's name and outcome. Their human readable details may contain file
+//! paths or the plugin's log output, so those are never included, and neither are library paths or
+//! the validator's settings. The layout is described by the `submission` JSON schema.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::format_version::{to_versioned_json, CURRENT_FORMAT_VERSION};
+use super::schema::SchemaKind;
+use crate::plugin::host::HostExtensionUsage;
+use crate::tests::{SkipReason, TestResult, TestStatus};
+use crate::validator::ValidationResult;
+
+/// The report that is POSTed to the endpoint passed to `--submit-results`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Submission<'a> {
+    /// The clap-validator version that produced the results.
+    pub clap_validator_version: &'static str,
+    /// The CLAP version the validator was built against, formatted as `major.minor.revision`.
+    pub clap_version: &'a str,
+    /// The operating system the validator was run on.
+    pub os: &'static str,
+    /// The CPU architecture the validator was run on.
+    pub arch: &'static str,
+    /// The seed used for the pseudo-random number generators in the tests.
+    pub prng_seed: u64,
+    /// When the validator started running the tests.
+    pub started_at: DateTime<Utc>,
+    /// When the validator finished running the tests.
+    pub finished_at: DateTime<Utc>,
+    /// The validated plugins, indexed by plugin ID.
+    pub plugins: BTreeMap<&'a str, SubmittedPlugin<'a>>,
+    /// Test results for the plugin library tests, indexed by the SHA-256 hash of the library's
+    /// binary. Libraries that could not be hashed, for instance because they could not be loaded,
+    /// are omitted.
+    pub plugin_library_tests: BTreeMap<&'a str, Vec<SubmittedTestResult<'a>>>,
+    /// Test results for the plugin tests, indexed by plugin ID.
+    pub plugin_tests: BTreeMap<&'a str, Vec<SubmittedTestResult<'a>>>,
+}
+
+/// The information that identifies a plugin in a [`Submission`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SubmittedPlugin<'a> {
+    /// The plugin's version, if it has one.
+    pub version: Option<&'a str>,
+    /// The SHA-256 hash of the plugin library's binary, formatted as a hexadecimal string.
+    pub library_sha256: &'a str,
+}
+
+/// A [`TestResult`] without any of its free-form text, in a [`Submission`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SubmittedTestResult<'a> {
+    /// The name of the test.
+    pub name: &'a str,
+    /// The outcome of the test, using the same codes as the `validate --json` output.
+    pub code: &'static str,
+    /// Why the test was skipped. Only present for skipped tests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<SkipReason>,
+    /// The host extensions the plugin queried and used while running the test.
+    #[serde(skip_serializing_if = "HostExtensionUsage::is_empty")]
+    pub host_extensions: &'a HostExtensionUsage,
+}
+
+impl<'a> Submission<'a> {
+    /// Create a submission from the results of a validator run. The results need to contain a
+    /// header, which is always the case for the results returned by `validator::validate()`.
+    pub fn new(result: &'a ValidationResult) -> Self {
+        let header = result
+            .header
+            .as_ref()
+            .expect("Tried to submit results without a header, this is a clap-validator bug");
+
+        Submission {
+            clap_validator_version: header.clap_validator_version,
+            clap_version: &header.clap_version,
+            os: header.os,
+            arch: header.arch,
+            prng_seed: header.prng_seed,
+            started_at: header.started_at,
+            finished_at: header.finished_at,
+            plugins: result
+                .plugins
+                .iter()
+                .map(|(plugin_id, plugin)| {
+                    (
+                        plugin_id.as_str(),
+                        SubmittedPlugin {
+                            version: plugin.metadata.version.as_deref(),
+                            library_sha256: &plugin.library_sha256,
+                        },
+                    )
+                })
+                .collect(),
+            plugin_library_tests: result
+                .plugin_library_tests
+                .iter()
+                .filter_map(|(library_path, tests)| {
+                    let library_sha256 = result
+                        .plugins
+                        .values()
+                        .find(|plugin| &plugin.library_path == library_path)?
+                        .library_sha256
+                        .as_str();
+
+                    Some((library_sha256, submitted_test_results(tests)))
+                })
+                .collect(),
+            plugin_tests: result
+                .plugin_tests
+                .iter()
+                .map(|(plugin_id, tests)| (plugin_id.as_str(), submitted_test_results(tests)))
+                .collect(),
+        }
+    }
+}
+
+/// Strip the details, the plugin's log output, and the thread safety issues from test results.
+/// These are free-form text that may contain file paths.
+fn submitted_test_results(tests: &[TestResult]) -> Vec<SubmittedTestResult<'_>> {
+    tests
+        .iter()
+        .map(|test| {
+            let (code, reason) = match &test.status {
+                TestStatus::Success { .. } => ("success", None),
+                TestStatus::Crashed { .. } => ("crashed", None),
+                TestStatus::Failed { .. } => ("failed", None),
+                TestStatus::Skipped { reason, .. } => ("skipped", Some(*reason)),
+                TestStatus::Warning { .. } => ("warning", None),
+            };
+
+            SubmittedTestResult {
+                name: &test.name,
+                code,
+                reason,
+                host_extensions: &test.host_extensions,
+            }
+        })
+        .collect()
+}
+
+/// POST the results of a validator run to `url` as JSON. The body always uses the current format
+/// version, regardless of the `--format-version` option.
+pub fn submit(result: &ValidationResult, url: &str) -> Result<()> {
+    let body = to_versioned_json(
+        &Submission::new(result),
+        SchemaKind::Submission,
+        CURRENT_FORMAT_VERSION,
+    );
+
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .with_context(|| format!("Could not submit the results to '{url}'"))?;
+
+    Ok(())
+}
@@ -3,141 +3,155 @@
 use std::process::ExitCode;
 
 use anyhow::{Context, Result};
-use colored::Colorize;
 
-use super::{println_wrapped, TextWrapper};
-use crate::tests::TestStatus;
+use super::format_version;
+use super::output::OutputSink;
+use super::report::{self, ReportOptions};
+use super::schema::SchemaKind;
+use super::{submission, TextWrapper};
+use crate::call_trace;
+use crate::ipc::ChildConnection;
 use crate::validator::{self, SingleTestSettings, ValidatorSettings};
 use crate::Verbosity;
 
-/// The main validator command. This will validate one or more plugins and print the results.
-pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<ExitCode> {
-    let mut result =
-        validator::validate(verbosity, settings).context("Could not run the validator")?;
+/// The exit code used when none of the tests failed, but every test was skipped. Nothing was
+/// actually validated in that case, which should not look like a successful run in CI. Exit code 1
+/// is used when a test failed or when an error occurred, and `clap` uses exit code 2 for invalid
+/// command line arguments.
+const ALL_SKIPPED_EXIT_CODE: u8 = 3;
+
+/// The main validator command. This will validate one or more plugins and print the results. JSON
+/// output uses the layout from `format_version`.
+pub fn validate(
+    verbosity: Verbosity,
+    format_version: u32,
+    settings: &ValidatorSettings,
+) -> Result<ExitCode> {
+    // The output is opened before running the validator so an invalid path or an unreachable
+    // socket is reported immediately instead of after all plugins have been validated
+    let output = match &settings.output {
+        Some(target) => target
+            .open()
+            .with_context(|| format!("Could not open {target} for writing the results"))?,
+        None => OutputSink::stdout(),
+    };
+
+    // The trace file is also finished when the validator fails, since that's when it's most useful
+    let result = validator::validate(verbosity, settings).context("Could not run the validator");
+    call_trace::finish().context("Could not finish writing the trace file")?;
+    let mut result = result?;
     let tally = result.tally();
 
-    // Filtering out tests should be done after we did the tally for consistency's sake
-    if settings.only_failed {
-        // The `.drain_filter()` methods have not been stabilized yet, so to make things
-        // easy for us we'll just inefficiently rebuild the data structures
-        result.plugin_library_tests = result
-            .plugin_library_tests
-            .into_iter()
-            .filter_map(|(library_path, tests)| {
-                let tests: Vec<_> = tests
-                    .into_iter()
-                    .filter(|test| test.status.failed_or_warning())
-                    .collect();
-                if tests.is_empty() {
-                    None
-                } else {
-                    Some((library_path, tests))
-                }
-            })
-            .collect();
+    // This is done before the results are filtered so the submission is always complete
+    if let Some(url) = &settings.submit_results {
+        match submission::submit(&result, url) {
+            Ok(()) => log::info!("Submitted the results to '{url}'"),
+            Err(err) => log::error!("{err:#}"),
+        }
+    }
 
-        result.plugin_tests = result
-            .plugin_tests
-            .into_iter()
-            .filter_map(|(plugin_id, tests)| {
-                let tests: Vec<_> = tests
-                    .into_iter()
-                    .filter(|test| test.status.failed_or_warning())
-                    .collect();
-                if tests.is_empty() {
-                    None
-                } else {
-                    Some((plugin_id, tests))
-                }
-            })
-            .collect();
+    // Escape codes for colors shouldn't end up in files or sockets
+    if !output.is_stdout() {
+        colored::control::set_override(false);
     }
 
+    let mut wrapper = TextWrapper::new(output);
     if settings.json {
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&result).expect("Could not format JSON")
-        );
-    } else {
-        let mut wrapper = TextWrapper::default();
-        // This doesn't need to be a macro but the alternatives are to either wrap `wrapper` in a
-        // refcell or to inline this, so this is probably still better
-        macro_rules! print_test {
-            ($test:expr) => {
-                println_wrapped!(wrapper, "   - {}: {}", $test.name, $test.description);
-
-                let status_text = match $test.status {
-                    TestStatus::Success { .. } => "PASSED".green(),
-                    TestStatus::Crashed { .. } => "CRASHED".red().bold(),
-                    TestStatus::Failed { .. } => "FAILED".red(),
-                    TestStatus::Skipped { .. } => "SKIPPED".yellow(),
-                    TestStatus::Warning { .. } => "WARNING".yellow(),
-                };
-                let test_result = match $test.status.details() {
-                    Some(reason) => format!("     {status_text}: {reason}"),
-                    None => format!("     {status_text}"),
-                };
-                wrapper.print_auto(test_result);
-            };
-        }
-
-        if !result.plugin_library_tests.is_empty() {
-            println!("Plugin library tests:");
-            for (library_path, tests) in result.plugin_library_tests {
-                println!();
-                println_wrapped!(wrapper, " - {}", library_path.display());
-
-                for test in tests {
-                    println!();
-                    print_test!(test);
-                }
-            }
-
-            println!();
-        }
-
-        if !result.plugin_tests.is_empty() {
-            println!("Plugin tests:");
-            for (plugin_id, tests) in result.plugin_tests {
-                println!();
-                println_wrapped!(wrapper, " - {plugin_id}");
-
-                for test in tests {
-                    println!();
-                    print_test!(test);
-                }
-            }
-
-            println!();
+        // Filtering out tests should be done after we did the tally for consistency's sake. The
+        // human readable report does its own filtering so it can show tests around failed tests.
+        if settings.only_failed {
+            // The `.drain_filter()` methods have not been stabilized yet, so to make things
+            // easy for us we'll just inefficiently rebuild the data structures
+            result.plugin_library_tests = result
+                .plugin_library_tests
+                .into_iter()
+                .filter_map(|(library_path, tests)| {
+                    let tests: Vec<_> = tests
+                        .into_iter()
+                        .filter(|test| test.status.failed_or_warning())
+                        .collect();
+                    if tests.is_empty() {
+                        None
+                    } else {
+                        Some((library_path, tests))
+                    }
+                })
+                .collect();
+
+            result.plugin_tests = result
+                .plugin_tests
+                .into_iter()
+                .filter_map(|(plugin_id, tests)| {
+                    let tests: Vec<_> = tests
+                        .into_iter()
+                        .filter(|test| test.status.failed_or_warning())
+                        .collect();
+                    if tests.is_empty() {
+                        None
+                    } else {
+                        Some((plugin_id, tests))
+                    }
+                })
+                .collect();
         }
 
-        let num_tests = tally.total();
-        println_wrapped!(
-            wrapper,
-            "{} {} run, {} passed, {} failed, {} skipped, {} warnings",
-            num_tests,
-            if num_tests == 1 { "test" } else { "tests" },
-            tally.num_passed,
-            tally.num_failed,
-            tally.num_skipped,
-            tally.num_warnings
+        wrapper.print_unwrapped(format_version::to_versioned_json(
+            &result,
+            SchemaKind::ValidationResult,
+            format_version,
+        ));
+    } else {
+        report::print_report(
+            &mut wrapper,
+            &result,
+            &ReportOptions {
+                only_failed: settings.only_failed,
+                context: settings.context,
+            },
         );
     }
+    wrapper.finish()?;
 
-    // If any of the tests failed, this process should exit with a failure code
-    if tally.num_failed == 0 {
-        Ok(ExitCode::SUCCESS)
-    } else {
+    // If any of the tests failed, this process should exit with a failure code. Runs where every
+    // test was skipped, for instance because of an overly narrow test filter, get their own code.
+    if tally.num_failed > 0 {
         Ok(ExitCode::FAILURE)
+    } else if tally.num_passed == 0 && tally.num_warnings == 0 {
+        if tally.num_skipped == 0 {
+            log::warn!(
+                "No tests were run, so nothing was validated. Exiting with code \
+                 {ALL_SKIPPED_EXIT_CODE}."
+            );
+        } else {
+            log::warn!(
+                "All {} selected tests were skipped, so nothing was validated. Exiting with code \
+                 {ALL_SKIPPED_EXIT_CODE}.",
+                tally.num_skipped
+            );
+        }
+
+        Ok(ExitCode::from(ALL_SKIPPED_EXIT_CODE))
+    } else {
+        Ok(ExitCode::SUCCESS)
     }
 }
 
-/// Run a single test and write the output to a file. This command is a hidden implementation detail
-/// used by the validator to run tests in a different process.
-pub fn run_single(settings: &SingleTestSettings) -> Result<ExitCode> {
-    // The result will be serialized as JSON and written to a file so the main validator process can
-    // read it
-    validator::run_single_test(settings)
+/// Run a single test and send the result to the validator process that spawned this process. This
+/// command is a hidden implementation detail used by the validator to run tests in a different
+/// process.
+pub fn run_single(verbosity: Verbosity, settings: &SingleTestSettings) -> Result<ExitCode> {
+    // Log messages and the test's result are sent to the main validator process over a socket. If
+    // the connection can't be established, then errors are printed to the terminal instead.
+    let connection = match ChildConnection::connect(settings.ipc_address, &settings.ipc_token) {
+        Ok(connection) => connection,
+        Err(err) => {
+            crate::init_terminal_logger(verbosity);
+            return Err(err);
+        }
+    };
+    connection.install_logger(verbosity.level_filter())?;
+
+    validator::run_single_test(&connection, settings)
         .map(|()| ExitCode::SUCCESS)
         .context("Could not run test the case")
 }
@@ -0,0 +1,258 @@
+//! Crash reports for out-of-process tests. When a test crashes the child process, the validator
+//! normally only knows the process' exit status. On Windows the single-test runner installs an
+//! unhandled exception filter that writes a minidump and a small report containing the exception
+//! code and the faulting module and offset. That report is then included in the test's result so
+//! the crash can be resolved against the plugin's debug symbols without reproducing it locally.
+
+#[cfg(windows)]
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Information about a crash in the single-test runner, written by the child process and read by
+/// the validator. Only produced on Windows.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CrashReport {
+    /// The exception code, e.g. `0xC0000005` for an access violation.
+    pub exception_code: u32,
+    /// The path to the module the exception occurred in, if it could be determined.
+    pub module: Option<PathBuf>,
+    /// The offset of the faulting instruction relative to the module's base address. This is what
+    /// needs to be resolved against the module's PDB file.
+    pub module_offset: Option<usize>,
+    /// The path to the minidump written for the crash, if writing it succeeded.
+    pub minidump_path: Option<PathBuf>,
+}
+
+impl CrashReport {
+    /// Read and remove the crash report the single-test runner wrote to `path`, if there is one.
+    /// Returns `None` if the child process did not write a report, which is always the case on
+    /// platforms other than Windows.
+    pub fn take(path: &Path) -> Option<Self> {
+        let report = fs::read_to_string(path).ok()?;
+        let _ = fs::remove_file(path);
+
+        match serde_json::from_str(&report) {
+            Ok(report) => Some(report),
+            Err(err) => {
+                log::warn!(
+                    "Could not parse the crash report at '{}': {err}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// A human readable description of the crash, appended to the test's failure details.
+    pub fn details(&self) -> String {
+        let mut details = format!("Unhandled exception 0x{:08X}", self.exception_code);
+        match (&self.module, self.module_offset) {
+            (Some(module), Some(offset)) => {
+                details.push_str(&format!(" at '{}'+0x{offset:x}", module.display()))
+            }
+            _ => details.push_str(" in an unknown module"),
+        }
+        details.push('.');
+
+        match &self.minidump_path {
+            Some(minidump_path) => details.push_str(&format!(
+                " A minidump was written to '{}'.",
+                minidump_path.display()
+            )),
+            None => details.push_str(" No minidump could be written."),
+        }
+
+        details
+    }
+}
+
+/// Install an unhandled exception filter that writes a minidump to `minidump_path` and a
+/// [`CrashReport`] to `report_path` when the process crashes. The exception is passed on
+/// afterwards, so the process still terminates with the exception code as its exit status.
+#[cfg(windows)]
+pub fn install_handler(report_path: PathBuf, minidump_path: PathBuf) -> Result<()> {
+    fs::create_dir_all(minidump_path.parent().unwrap())
+        .context("Could not create the directory for the minidump")?;
+
+    *windows::CRASH_REPORT_PATHS.lock().unwrap() = Some(windows::CrashReportPaths {
+        report_path,
+        minidump_path,
+    });
+    unsafe { windows::SetUnhandledExceptionFilter(Some(windows::unhandled_exception_filter)) };
+
+    Ok(())
+}
+
+/// The Win32 bindings for the exception filter. These are declared here instead of pulling in a
+/// dependency for a handful of functions.
+#[cfg(windows)]
+#[allow(non_snake_case, dead_code, clippy::upper_case_acronyms)]
+mod windows {
+    use std::ffi::{c_void, OsString};
+    use std::fs;
+    use std::os::windows::ffi::OsStringExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use super::CrashReport;
+
+    type BOOL = i32;
+    type HANDLE = *mut c_void;
+    type HMODULE = *mut c_void;
+
+    const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+    const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+    const MINIDUMP_NORMAL: u32 = 0x0;
+    const MINIDUMP_WITH_THREAD_INFO: u32 = 0x1000;
+    const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+    /// The maximum length of an extended-length path, in UTF-16 code units.
+    const MODULE_PATH_LEN: usize = 32768;
+
+    /// Where the exception filter should write its output. Set in [`super::install_handler()`].
+    pub static CRASH_REPORT_PATHS: Mutex<Option<CrashReportPaths>> = Mutex::new(None);
+
+    pub struct CrashReportPaths {
+        pub report_path: PathBuf,
+        pub minidump_path: PathBuf,
+    }
+
+    #[repr(C)]
+    struct EXCEPTION_RECORD {
+        ExceptionCode: u32,
+        ExceptionFlags: u32,
+        ExceptionRecord: *mut EXCEPTION_RECORD,
+        ExceptionAddress: *mut c_void,
+        NumberParameters: u32,
+        ExceptionInformation: [usize; 15],
+    }
+
+    #[repr(C)]
+    pub struct EXCEPTION_POINTERS {
+        ExceptionRecord: *mut EXCEPTION_RECORD,
+        ContextRecord: *mut c_void,
+    }
+
+    /// This struct is declared with 4-byte packing in `minidumpapiset.h`.
+    #[repr(C, packed(4))]
+    struct MINIDUMP_EXCEPTION_INFORMATION {
+        ThreadId: u32,
+        ExceptionPointers: *const EXCEPTION_POINTERS,
+        ClientPointers: BOOL,
+    }
+
+    type TopLevelExceptionFilter = unsafe extern "system" fn(*const EXCEPTION_POINTERS) -> i32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetUnhandledExceptionFilter(
+            filter: Option<TopLevelExceptionFilter>,
+        ) -> Option<TopLevelExceptionFilter>;
+        fn GetModuleHandleExW(flags: u32, module_name: *const u16, module: *mut HMODULE) -> BOOL;
+        fn GetModuleFileNameW(module: HMODULE, file_name: *mut u16, size: u32) -> u32;
+        fn GetCurrentProcess() -> HANDLE;
+        fn GetCurrentProcessId() -> u32;
+        fn GetCurrentThreadId() -> u32;
+    }
+
+    #[link(name = "dbghelp")]
+    extern "system" {
+        fn MiniDumpWriteDump(
+            process: HANDLE,
+            process_id: u32,
+            file: HANDLE,
+            dump_type: u32,
+            exception_param: *const MINIDUMP_EXCEPTION_INFORMATION,
+            user_stream_param: *const c_void,
+            callback_param: *const c_void,
+        ) -> BOOL;
+    }
+
+    /// Writes the minidump and the crash report, and then lets the exception continue to the
+    /// default handler. This runs in a crashed process, so everything here is best-effort.
+    pub unsafe extern "system" fn unhandled_exception_filter(
+        exception_pointers: *const EXCEPTION_POINTERS,
+    ) -> i32 {
+        // If the lock is somehow held while the process crashed, then there's nothing we can do
+        let paths = match CRASH_REPORT_PATHS.try_lock() {
+            Ok(paths) => paths,
+            Err(_) => return EXCEPTION_CONTINUE_SEARCH,
+        };
+        let paths = match &*paths {
+            Some(paths) => paths,
+            None => return EXCEPTION_CONTINUE_SEARCH,
+        };
+        if exception_pointers.is_null() || (*exception_pointers).ExceptionRecord.is_null() {
+            return EXCEPTION_CONTINUE_SEARCH;
+        }
+
+        let exception_record = &*(*exception_pointers).ExceptionRecord;
+        let exception_address = exception_record.ExceptionAddress;
+
+        let mut module: HMODULE = std::ptr::null_mut();
+        let (module_path, module_offset) = if GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            exception_address as *const u16,
+            &mut module,
+        ) != 0
+        {
+            let mut module_path = vec![0u16; MODULE_PATH_LEN];
+            let module_path_len =
+                GetModuleFileNameW(module, module_path.as_mut_ptr(), MODULE_PATH_LEN as u32);
+            let module_path = if module_path_len > 0 {
+                Some(PathBuf::from(OsString::from_wide(
+                    &module_path[..module_path_len as usize],
+                )))
+            } else {
+                None
+            };
+
+            (
+                module_path,
+                Some(exception_address as usize - module as usize),
+            )
+        } else {
+            (None, None)
+        };
+
+        let minidump_written = match fs::File::create(&paths.minidump_path) {
+            Ok(file) => {
+                let exception_information = MINIDUMP_EXCEPTION_INFORMATION {
+                    ThreadId: GetCurrentThreadId(),
+                    ExceptionPointers: exception_pointers,
+                    ClientPointers: 0,
+                };
+
+                MiniDumpWriteDump(
+                    GetCurrentProcess(),
+                    GetCurrentProcessId(),
+                    file.as_raw_handle() as HANDLE,
+                    MINIDUMP_NORMAL | MINIDUMP_WITH_THREAD_INFO,
+                    &exception_information,
+                    std::ptr::null(),
+                    std::ptr::null(),
+                ) != 0
+            }
+            Err(_) => false,
+        };
+
+        let report = CrashReport {
+            exception_code: exception_record.ExceptionCode,
+            module: module_path,
+            module_offset,
+            minidump_path: if minidump_written {
+                Some(paths.minidump_path.clone())
+            } else {
+                None
+            },
+        };
+        if let Ok(report) = serde_json::to_string(&report) {
+            let _ = fs::write(&paths.report_path, report);
+        }
+
+        EXCEPTION_CONTINUE_SEARCH
+    }
+}
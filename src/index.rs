@@ -2,12 +2,14 @@
 
 use anyhow::{Context, Result};
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 use walkdir::{DirEntry, WalkDir};
 
 use crate::plugin::library::{PluginLibrary, PluginLibraryMetadata};
-use crate::plugin::preset_discovery::{LocationValue, PresetFile, Soundpack};
+use crate::plugin::preset_discovery::{
+    CrawlStatistics, LocationValue, PluginAbi, PresetFile, Soundpack,
+};
 
 /// The separator for path environment variables.
 #[cfg(unix)]
@@ -21,7 +23,16 @@ const PATH_SEPARATOR: char = ';';
 ///
 /// Uses a `BTreeMap` purely so the order is stable.
 #[derive(Debug, Serialize)]
-pub struct Index(pub BTreeMap<PathBuf, PluginLibraryMetadata>);
+pub struct Index(pub BTreeMap<PathBuf, IndexedLibrary>);
+
+/// A plugin library found during [`index()`].
+#[derive(Debug, Serialize)]
+pub struct IndexedLibrary {
+    /// The SHA-256 hash of the plugin library's binary, formatted as a hexadecimal string.
+    pub sha256: String,
+    #[serde(flatten)]
+    pub metadata: PluginLibraryMetadata,
+}
 
 /// Build an index of all CLAP plugins on this system. This finds all `.clap` files as specified in
 /// [entry.h](https://github.com/free-audio/clap/blob/main/include/clap/entry.h), and lists all
@@ -39,20 +50,23 @@ pub fn index() -> Index {
 
     for directory in directories {
         for clap_plugin_path in walk_clap_plugins(&directory) {
-            let metadata = PluginLibrary::load(clap_plugin_path.path())
+            let library = PluginLibrary::load(clap_plugin_path.path())
                 .with_context(|| format!("Could not load '{}'", clap_plugin_path.path().display()))
                 .and_then(|plugin| {
-                    plugin.metadata().with_context(|| {
-                        format!(
-                            "Could not fetch plugin metadata for '{}'",
-                            clap_plugin_path.path().display()
-                        )
+                    Ok(IndexedLibrary {
+                        sha256: plugin.binary_sha256()?,
+                        metadata: plugin.metadata().with_context(|| {
+                            format!(
+                                "Could not fetch plugin metadata for '{}'",
+                                clap_plugin_path.path().display()
+                            )
+                        })?,
                     })
                 });
 
-            match metadata {
-                Ok(metadata) => {
-                    index.0.insert(clap_plugin_path.into_path(), metadata);
+            match library {
+                Ok(library) => {
+                    index.0.insert(clap_plugin_path.into_path(), library);
                 }
                 Err(err) => log::error!("{err:#}"),
             }
@@ -94,11 +108,35 @@ pub struct ProviderPresets {
     // kind+value that's not longer the case.
     #[serde(with = "serde_with::rust::btreemap_as_tuple_list")]
     pub presets: BTreeMap<LocationValue, PresetFile>,
+    /// Performance statistics for crawling the provider's locations.
+    pub statistics: CrawlStatistics,
+    /// Warnings emitted when the provider exceeded the [`CrawlBudget`].
+    pub warnings: Vec<String>,
+    /// Informational notes about the declared presets, like presets referring to plugins that are
+    /// not part of the crawled plugin library.
+    pub notes: Vec<String>,
+}
+
+/// Time budgets for crawling a provider's presets. Providers that exceed these budgets result in a
+/// warning, but crawling is never aborted.
+#[derive(Debug, Clone, Copy)]
+pub struct CrawlBudget {
+    /// The maximum amount of time a single `clap_preset_discovery_provider::get_metadata()` call
+    /// should take, in milliseconds.
+    pub max_file_time_ms: f64,
+    /// The maximum amount of time crawling all of a provider's locations should take, in
+    /// milliseconds.
+    pub max_total_time_ms: f64,
 }
 
 /// Index the presets for one or more plugins. [`index()`] can be used to build a list of all
-/// installed CLAP plugins. Plugins that
-pub fn index_presets<I, P>(plugin_paths: I, skip_unsupported: bool) -> Result<PresetIndex>
+/// installed CLAP plugins. Plugins that don't support preset discovery result in an error unless
+/// `skip_unsupported` is set. A warning is emitted for providers that exceed the crawl `budget`.
+pub fn index_presets<I, P>(
+    plugin_paths: I,
+    skip_unsupported: bool,
+    budget: CrawlBudget,
+) -> Result<PresetIndex>
 where
     I: IntoIterator<Item = P>,
     P: AsRef<Path>,
@@ -121,6 +159,9 @@ where
         }
 
         let result = preset_discovery_factory.and_then(|factory| {
+            let library_metadata = library
+                .metadata()
+                .context("Could not fetch the plugin library's metadata")?;
             let metadata = factory
                 .metadata()
                 .context("Could not get the preset discovery's provider descriptors")?;
@@ -138,17 +179,70 @@ where
 
                 let declared_data = provider.declared_data();
                 let mut presets = BTreeMap::new();
+                let mut statistics = CrawlStatistics::default();
                 for location in &declared_data.locations {
-                    presets.extend(provider.crawl_location(location).with_context(|| {
-                        format!(
-                            "Error occurred while crawling presets for the location '{}' with {} \
-                             using provider '{}' with ID '{}'",
-                            location.name,
-                            location.value,
-                            provider_metadata.name,
-                            provider_metadata.id,
-                        )
-                    })?);
+                    presets.extend(
+                        provider
+                            .crawl_location_with_statistics(location, &mut statistics)
+                            .with_context(|| {
+                                format!(
+                                    "Error occurred while crawling presets for the location '{}' \
+                                     with {} using provider '{}' with ID '{}'",
+                                    location.name,
+                                    location.value,
+                                    provider_metadata.name,
+                                    provider_metadata.id,
+                                )
+                            })?,
+                    );
+                }
+
+                let mut warnings = Vec::new();
+                if statistics.slowest_location_time_ms > budget.max_file_time_ms {
+                    warnings.push(format!(
+                        "Crawling {} took {:.1} milliseconds, exceeding the budget of {:.1} \
+                         milliseconds per file.",
+                        statistics
+                            .slowest_location
+                            .as_deref()
+                            .unwrap_or("(unknown)"),
+                        statistics.slowest_location_time_ms,
+                        budget.max_file_time_ms
+                    ));
+                }
+                if statistics.total_time_ms > budget.max_total_time_ms {
+                    warnings.push(format!(
+                        "Crawling all locations took {:.1} milliseconds, exceeding the total \
+                         budget of {:.1} milliseconds.",
+                        statistics.total_time_ms, budget.max_total_time_ms
+                    ));
+                }
+                for warning in &warnings {
+                    log::warn!(
+                        "Preset provider '{}' with ID '{}': {warning}",
+                        provider_metadata.name,
+                        provider_metadata.id
+                    );
+                }
+
+                let notes: Vec<String> =
+                    external_clap_plugin_ids(presets.values(), &library_metadata)
+                        .into_iter()
+                        .map(|plugin_id| {
+                            format!(
+                                "Presets were declared for the CLAP plugin ID '{plugin_id}', \
+                                 which does not belong to any plugin in this library. This is \
+                                 fine if the provider indexes presets for other plugins, but it \
+                                 may also be a copy-paste error."
+                            )
+                        })
+                        .collect();
+                for note in &notes {
+                    log::info!(
+                        "Preset provider '{}' with ID '{}': {note}",
+                        provider_metadata.name,
+                        provider_metadata.id
+                    );
                 }
 
                 provider_results.push(ProviderPresets {
@@ -156,6 +250,9 @@ where
                     provider_vendor: provider_metadata.vendor,
                     soundpacks: declared_data.soundpacks.clone(),
                     presets,
+                    statistics,
+                    warnings,
+                    notes,
                 });
             }
 
@@ -181,6 +278,32 @@ where
     Ok(index)
 }
 
+/// Find all CLAP plugin IDs referenced by `preset_files` that don't belong to any of the plugins
+/// in the library described by `library_metadata`. Plugin IDs for other plugin ABIs are ignored.
+pub fn external_clap_plugin_ids<'a, I>(
+    preset_files: I,
+    library_metadata: &PluginLibraryMetadata,
+) -> BTreeSet<String>
+where
+    I: IntoIterator<Item = &'a PresetFile>,
+{
+    let library_plugin_ids: HashSet<&str> = library_metadata
+        .plugins
+        .iter()
+        .map(|plugin| plugin.id.as_str())
+        .collect();
+
+    preset_files
+        .into_iter()
+        .flat_map(|preset_file| preset_file.presets())
+        .flat_map(|preset| &preset.plugin_ids)
+        .filter(|plugin_id| {
+            plugin_id.abi == PluginAbi::Clap && !library_plugin_ids.contains(plugin_id.id.as_str())
+        })
+        .map(|plugin_id| plugin_id.id.clone())
+        .collect()
+}
+
 /// Get the platform-specific CLAP directories. This takes `$CLAP_PATH` into account. Returns an
 /// error if the paths could not be parsed correctly.
 ///
@@ -0,0 +1,656 @@
+//! The protocol used to communicate between the validator and the child processes used for
+//! out-of-process tests. The validator listens on a loopback TCP socket, and the child process
+//! connects to it as soon as it starts. The child then streams its log messages, progress,
+//! heartbeats, and finally the test's result over that connection as newline-delimited JSON
+//! messages. This is not done through STDIO because the hosted plugin may also write things there.
+//!
+//! The heartbeats let the validator detect child processes that have stopped responding
+//! altogether, and the message marking the start of the test lets it enforce a time limit on the
+//! test itself. Every heartbeat also lists the plugin functions that are currently being called,
+//! which lets the validator tell a slow test that's still making progress apart from a plugin
+//! function that never returns. Heartbeats are sent from a background thread, so they keep coming
+//! when the test itself deadlocks. To detect that, the heartbeats also count the plugin calls made
+//! so far, and tests report the steps they're taking through [`progress()`]. A test that neither
+//! calls the plugin nor reports any progress for too long is considered stalled. In all of these
+//! cases the child process is killed and the test is marked as failed, instead of the validator
+//! hanging indefinitely.
+//!
+//! Tests that deliberately do something risky can describe what they're doing using
+//! [`crash_context()`]. If the child process crashes while that description is set, then it's
+//! included in the test's result.
+
+use anyhow::{Context, Result};
+use crossbeam::atomic::AtomicCell;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, ExitStatus};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use crate::call_trace::{self, TraceEvent};
+use crate::tests::TestResult;
+
+/// How often the child process sends a heartbeat to the validator.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// If the validator hasn't received any messages from the child process for this long, then the
+/// child process is considered to be unresponsive and it will be killed. This also applies to
+/// connecting to the validator after the child process has been spawned.
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the validator checks whether the child process has exited or has become unresponsive
+/// while waiting for messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether calls to the plugin's functions should be tracked. This is only enabled in the child
+/// process, since the tracked calls are only used for the heartbeats.
+static TRACK_PLUGIN_CALLS: AtomicBool = AtomicBool::new(false);
+/// The plugin calls in progress on every thread that has called one of the plugin's functions.
+/// Threads register themselves here the first time they call a plugin function, after which
+/// tracking calls on that thread doesn't require any locking.
+static TRACKING_THREADS: Mutex<Vec<Weak<ThreadPluginCalls>>> = Mutex::new(Vec::new());
+/// The total number of plugin function calls made so far. Sent along with the heartbeats.
+static NUM_PLUGIN_CALLS: AtomicU64 = AtomicU64::new(0);
+/// The maximum number of nested plugin calls tracked per thread. Deeper calls are still counted,
+/// but they're not included in the heartbeats.
+const MAX_TRACKED_CALL_DEPTH: usize = 8;
+
+thread_local! {
+    /// The plugin calls in progress on this thread. See [`track_plugin_call()`].
+    static THREAD_PLUGIN_CALLS: Arc<ThreadPluginCalls> = ThreadPluginCalls::register();
+}
+/// The child process' connection to the validator, used to send the [`crash_context()`]. This is
+/// only set in the child process.
+static CONNECTION: Mutex<Option<Arc<ChildConnection>>> = Mutex::new(None);
+
+/// A message sent from the child process to the validator. Every message is serialized as a single
+/// line of JSON.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ChildMessage {
+    /// The first message sent after connecting. Contains the token the validator passed to the
+    /// child process, so connections from other processes can be rejected.
+    Hello { token: String },
+    /// Sent periodically from a background thread to indicate the child process is still alive.
+    /// Contains the plugin functions that were being called when the heartbeat was sent, and the
+    /// total number of plugin function calls made so far. The validator uses the latter to check
+    /// whether the test is still making progress.
+    Heartbeat {
+        pending_plugin_calls: Vec<PendingPluginCall>,
+        num_plugin_calls: u64,
+    },
+    /// Describes what the test is currently doing, so a crash can be explained. Cleared by sending
+    /// `None`. See [`crash_context()`].
+    CrashContext { context: Option<String> },
+    /// The child process has finished its setup and is about to run the test. The test's time limit
+    /// starts counting from this point, so waiting for a debugger to be attached doesn't count
+    /// towards it.
+    Started,
+    /// Describes the step the test has just started on. Unlike the heartbeats, these are sent from
+    /// the test's own thread. See [`progress()`].
+    Progress { step: String },
+    /// A message logged by the validator code running in the child process.
+    Log { level: String, message: String },
+    /// Trace events recorded by the child process, when the validator's `--trace-file` option is
+    /// used. Events are sent as soon as they're recorded. See [`send_trace_event()`].
+    TraceEvents { events: Vec<TraceEvent> },
+    /// The test's result. This is the last message sent by the child process.
+    Result { result: Box<TestResult> },
+}
+
+/// The outcome of running a test in a child process, as observed by the validator.
+#[derive(Debug)]
+pub enum ChildOutcome {
+    /// The child process exited on its own. `result` contains the test result it sent, if it sent
+    /// one before exiting. `crash_context` contains the last [`ChildMessage::CrashContext`], if it
+    /// had not been cleared when the process exited.
+    Exited {
+        status: ExitStatus,
+        result: Option<TestResult>,
+        crash_context: Option<String>,
+    },
+    /// The child process did not send any messages for [`HEARTBEAT_TIMEOUT`] and it was killed.
+    Unresponsive,
+    /// The child process was still sending heartbeats, but the test neither called any of the
+    /// plugin's functions nor reported any progress for [`HEARTBEAT_TIMEOUT`], and it was killed.
+    /// Contains the last step reported through [`progress()`], if any.
+    Stalled { last_step: Option<String> },
+    /// The test did not finish within the time limit and the child process was killed. Contains
+    /// the plugin functions that were being called according to the last heartbeat, and the last
+    /// step reported through [`progress()`].
+    TimedOut {
+        timeout: Duration,
+        pending_plugin_calls: Vec<PendingPluginCall>,
+        last_step: Option<String>,
+    },
+    /// A call to one of the plugin's functions did not return within the time limit and the child
+    /// process was killed.
+    StuckPluginCall(PendingPluginCall),
+}
+
+/// A call to one of the plugin's functions that had not yet returned when a heartbeat was sent.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PendingPluginCall {
+    /// The function's name, including the struct it's part of. For example,
+    /// `clap_plugin::process`.
+    pub function: String,
+    /// The name of the thread the function was called from.
+    pub thread: String,
+    /// How long the call had been running for when the heartbeat was sent.
+    pub duration: Duration,
+}
+
+/// A plugin function call that's currently in progress. See [`track_plugin_call()`].
+#[derive(Clone, Copy)]
+struct TrackedPluginCall {
+    struct_name: &'static str,
+    function_name: &'static str,
+    started_at: Instant,
+}
+
+/// The plugin calls in progress on a single thread. Only the owning thread modifies this, so
+/// tracking a call doesn't need a mutex, and it's safe to do on the audio thread. The heartbeat
+/// thread reads it.
+struct ThreadPluginCalls {
+    thread_name: String,
+    /// The number of nested calls in progress on this thread. Only the first
+    /// [`MAX_TRACKED_CALL_DEPTH`] calls are stored in `calls`.
+    depth: AtomicUsize,
+    calls: [AtomicCell<Option<TrackedPluginCall>>; MAX_TRACKED_CALL_DEPTH],
+}
+
+/// Removes a call from the tracked plugin calls when it's dropped. See [`track_plugin_call()`].
+/// This needs to be dropped on the thread it was created on.
+pub struct PluginCallGuard {
+    _private: (),
+}
+
+/// Clears the crash context when it's dropped. See [`crash_context()`].
+pub struct CrashContextGuard {
+    _private: (),
+}
+
+/// The validator's side of the connection. Created before spawning the child process so the
+/// listener's address can be passed to it.
+pub struct ParentListener {
+    listener: TcpListener,
+    token: String,
+}
+
+/// Options for the validator's watchdog that kills child processes that hang.
+#[derive(Debug, Clone, Copy)]
+pub struct Watchdog {
+    /// Whether the watchdog is enabled at all. This is disabled while waiting for a debugger, since
+    /// a process that's paused in a debugger also stops sending heartbeats.
+    pub enabled: bool,
+    /// The maximum time the test may take, starting from the [`ChildMessage::Started`] message.
+    pub test_timeout: Option<Duration>,
+    /// The maximum time a single call to one of the plugin's functions may take.
+    pub plugin_call_timeout: Option<Duration>,
+}
+
+/// The child process' side of the connection. Messages can be sent from any thread.
+pub struct ChildConnection {
+    stream: Mutex<TcpStream>,
+}
+
+/// A logger for the child process that forwards log messages to the validator.
+struct ChildLogger {
+    connection: Arc<ChildConnection>,
+    level: log::LevelFilter,
+}
+
+impl ParentListener {
+    /// Start listening on a random port on the loopback interface.
+    pub fn bind() -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .context("Could not create a socket for communicating with the child process")?;
+        listener
+            .set_nonblocking(true)
+            .context("Could not configure the socket for the child process")?;
+
+        Ok(Self {
+            listener,
+            token: format!("{:032x}", rand::random::<u128>()),
+        })
+    }
+
+    /// The address the child process should connect to.
+    pub fn address(&self) -> Result<SocketAddr> {
+        self.listener
+            .local_addr()
+            .context("Could not get the address of the child process' socket")
+    }
+
+    /// The token the child process should send when connecting.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Wait for `child` to connect, and then handle its messages until it exits or until the
+    /// watchdog kills it. Log messages sent by the child process are logged with the test's name
+    /// prepended to them if `forward_logs` is set. The child process is killed if an error occurs
+    /// while communicating with it.
+    pub fn run(
+        self,
+        child: &mut Child,
+        test_name: &str,
+        forward_logs: bool,
+        watchdog: Watchdog,
+    ) -> Result<ChildOutcome> {
+        let outcome = self.run_inner(child, test_name, forward_logs, watchdog);
+        if !matches!(outcome, Ok(ChildOutcome::Exited { .. })) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        outcome
+    }
+
+    fn run_inner(
+        self,
+        child: &mut Child,
+        test_name: &str,
+        forward_logs: bool,
+        watchdog: Watchdog,
+    ) -> Result<ChildOutcome> {
+        let spawned_at = Instant::now();
+        let stream = loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                Err(err) => {
+                    return Err(err).context("Could not accept the child process' connection")
+                }
+            }
+
+            // If the child process exits or crashes before it could connect, then there won't be
+            // any result
+            if let Some(status) = child
+                .try_wait()
+                .context("Error while waiting on the child process")?
+            {
+                return Ok(ChildOutcome::Exited {
+                    status,
+                    result: None,
+                    crash_context: None,
+                });
+            }
+            if watchdog.enabled && spawned_at.elapsed() > HEARTBEAT_TIMEOUT {
+                return Ok(ChildOutcome::Unresponsive);
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        // Accepted sockets may inherit the listener's non-blocking mode on some platforms
+        stream
+            .set_nonblocking(false)
+            .and_then(|()| stream.set_read_timeout(Some(POLL_INTERVAL)))
+            .context("Could not configure the child process' connection")?;
+
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        let mut authenticated = false;
+        let mut last_message_at = Instant::now();
+        let mut last_progress_at = Instant::now();
+        let mut started_at = None;
+        let mut pending_plugin_calls = Vec::new();
+        let mut num_plugin_calls = 0;
+        let mut last_step = None;
+        let mut crash_context = None;
+        let mut test_result = None;
+        loop {
+            match reader.read_until(b'\n', &mut line) {
+                // Either the connection was closed, or the last message was cut off because the
+                // child process exited while sending it
+                Ok(_) if !line.ends_with(b"\n") => break,
+                Ok(_) => {
+                    last_message_at = Instant::now();
+
+                    let message: ChildMessage = serde_json::from_slice(&line)
+                        .context("Could not parse a message from the child process")?;
+                    line.clear();
+
+                    match message {
+                        ChildMessage::Hello { token } if token == self.token => {
+                            authenticated = true
+                        }
+                        ChildMessage::Hello { .. } => {
+                            anyhow::bail!("The child process sent an incorrect token")
+                        }
+                        _ if !authenticated => anyhow::bail!(
+                            "The child process sent a message before identifying itself"
+                        ),
+                        ChildMessage::Heartbeat {
+                            pending_plugin_calls: calls,
+                            num_plugin_calls: num_calls,
+                        } => {
+                            // A plugin call that takes a long time is covered by the plugin call
+                            // timeout instead
+                            if num_calls != num_plugin_calls || !calls.is_empty() {
+                                last_progress_at = Instant::now();
+                            }
+                            pending_plugin_calls = calls;
+                            num_plugin_calls = num_calls;
+                        }
+                        ChildMessage::CrashContext { context } => {
+                            last_progress_at = Instant::now();
+                            crash_context = context;
+                        }
+                        ChildMessage::Started => {
+                            last_progress_at = Instant::now();
+                            started_at = Some(Instant::now());
+                        }
+                        ChildMessage::Progress { step } => {
+                            last_progress_at = Instant::now();
+                            last_step = Some(step);
+                        }
+                        ChildMessage::Log { level, message } => {
+                            last_progress_at = Instant::now();
+                            if forward_logs {
+                                let level =
+                                    log::Level::from_str(&level).unwrap_or(log::Level::Info);
+                                log::log!(level, "[{test_name}] {message}");
+                            }
+                        }
+                        ChildMessage::TraceEvents { events } => call_trace::record_events(events),
+                        ChildMessage::Result { result } => test_result = Some(*result),
+                    }
+                }
+                // Incomplete lines are kept in the buffer until the rest of the line arrives
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                    ) => {}
+                Err(err) => {
+                    return Err(err).context("Could not read a message from the child process")
+                }
+            }
+
+            if watchdog.enabled {
+                if last_message_at.elapsed() > HEARTBEAT_TIMEOUT {
+                    return Ok(ChildOutcome::Unresponsive);
+                }
+                if last_progress_at.elapsed() > HEARTBEAT_TIMEOUT {
+                    return Ok(ChildOutcome::Stalled { last_step });
+                }
+                match (watchdog.test_timeout, started_at) {
+                    (Some(test_timeout), Some(started_at))
+                        if started_at.elapsed() > test_timeout =>
+                    {
+                        return Ok(ChildOutcome::TimedOut {
+                            timeout: test_timeout,
+                            pending_plugin_calls,
+                            last_step,
+                        });
+                    }
+                    _ => (),
+                }
+                if let Some(plugin_call_timeout) = watchdog.plugin_call_timeout {
+                    if let Some(stuck_call) = pending_plugin_calls
+                        .iter()
+                        .filter(|call| call.duration > plugin_call_timeout)
+                        .max_by_key(|call| call.duration)
+                    {
+                        return Ok(ChildOutcome::StuckPluginCall(stuck_call.clone()));
+                    }
+                }
+            }
+        }
+
+        let status = child
+            .wait()
+            .context("Error while waiting on the child process to exit")?;
+
+        Ok(ChildOutcome::Exited {
+            status,
+            result: test_result,
+            crash_context,
+        })
+    }
+}
+
+impl ChildConnection {
+    /// Connect to the validator and identify the process using `token`. This also starts a
+    /// background thread that sends heartbeats for as long as the connection is open, and it
+    /// enables tracking calls to the plugin's functions for those heartbeats.
+    pub fn connect(address: SocketAddr, token: &str) -> Result<Arc<Self>> {
+        let stream = TcpStream::connect(address)
+            .with_context(|| format!("Could not connect to the validator at '{address}'"))?;
+        let connection = Arc::new(Self {
+            stream: Mutex::new(stream),
+        });
+        connection.send(&ChildMessage::Hello {
+            token: token.to_owned(),
+        })?;
+
+        let heartbeat_connection = connection.clone();
+        std::thread::Builder::new()
+            .name(String::from("ipc-heartbeat"))
+            .spawn(move || loop {
+                std::thread::sleep(HEARTBEAT_INTERVAL);
+                let heartbeat = ChildMessage::Heartbeat {
+                    pending_plugin_calls: pending_plugin_calls(),
+                    num_plugin_calls: NUM_PLUGIN_CALLS.load(Ordering::Relaxed),
+                };
+                if heartbeat_connection.send(&heartbeat).is_err() {
+                    break;
+                }
+            })
+            .context("Could not start the heartbeat thread")?;
+        TRACK_PLUGIN_CALLS.store(true, Ordering::SeqCst);
+        *CONNECTION.lock().unwrap() = Some(connection.clone());
+
+        Ok(connection)
+    }
+
+    /// Install a logger that forwards all log messages up to `level` to the validator.
+    pub fn install_logger(self: &Arc<Self>, level: log::LevelFilter) -> Result<()> {
+        log::set_boxed_logger(Box::new(ChildLogger {
+            connection: self.clone(),
+            level,
+        }))
+        .context("Could not install the logger")?;
+        log::set_max_level(level);
+
+        Ok(())
+    }
+
+    /// Send a message to the validator.
+    pub fn send(&self, message: &ChildMessage) -> Result<()> {
+        let mut line =
+            serde_json::to_vec(message).context("Could not format the message as JSON")?;
+        line.push(b'\n');
+
+        // The entire line is written while holding the lock so messages sent from different
+        // threads don't get interleaved
+        let mut stream = self.stream.lock().unwrap();
+        stream
+            .write_all(&line)
+            .and_then(|()| stream.flush())
+            .context("Could not send a message to the validator")
+    }
+}
+
+impl Drop for CrashContextGuard {
+    fn drop(&mut self) {
+        send_crash_context(None);
+    }
+}
+
+impl Display for PendingPluginCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}()' on the '{}' thread, which had been running for {:.1} seconds",
+            self.function,
+            self.thread,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+impl Drop for PluginCallGuard {
+    fn drop(&mut self) {
+        THREAD_PLUGIN_CALLS.with(|thread_calls| thread_calls.pop());
+    }
+}
+
+impl ThreadPluginCalls {
+    /// Create the call tracking state for the current thread, and register it so the heartbeat
+    /// thread can find it.
+    fn register() -> Arc<Self> {
+        let thread_calls = Arc::new(Self {
+            thread_name: std::thread::current()
+                .name()
+                .unwrap_or("unnamed")
+                .to_owned(),
+            depth: AtomicUsize::new(0),
+            calls: std::array::from_fn(|_| AtomicCell::new(None)),
+        });
+        TRACKING_THREADS
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&thread_calls));
+
+        thread_calls
+    }
+
+    /// Start tracking a call on this thread.
+    fn push(&self, call: TrackedPluginCall) {
+        let depth = self.depth.load(Ordering::Relaxed);
+        if let Some(slot) = self.calls.get(depth) {
+            slot.store(Some(call));
+        }
+        self.depth.store(depth + 1, Ordering::Release);
+    }
+
+    /// Stop tracking the innermost call on this thread.
+    fn pop(&self) {
+        let depth = self.depth.load(Ordering::Relaxed) - 1;
+        self.depth.store(depth, Ordering::Release);
+        if let Some(slot) = self.calls.get(depth) {
+            slot.store(None);
+        }
+    }
+}
+
+/// Track a call to one of the plugin's functions until the returned guard is dropped. This is a
+/// no-op outside of the child process. Used in [`clap_call!()`][crate::util::clap_call].
+/// `struct_name` is the full type name of the struct containing the function pointer.
+pub fn track_plugin_call(
+    struct_name: &'static str,
+    function_name: &'static str,
+) -> Option<PluginCallGuard> {
+    if !TRACK_PLUGIN_CALLS.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    NUM_PLUGIN_CALLS.fetch_add(1, Ordering::Relaxed);
+    THREAD_PLUGIN_CALLS.with(|thread_calls| {
+        thread_calls.push(TrackedPluginCall {
+            struct_name,
+            function_name,
+            started_at: Instant::now(),
+        })
+    });
+
+    Some(PluginCallGuard { _private: () })
+}
+
+/// Describe what the test is doing until the returned guard is dropped. If the child process
+/// crashes in the meantime, then the validator includes `context` in the test's result. `context`
+/// should complete the sentence "The plugin crashed while ...". This is a no-op outside of the
+/// child process.
+pub fn crash_context(context: impl Into<String>) -> CrashContextGuard {
+    send_crash_context(Some(context.into()));
+
+    CrashContextGuard { _private: () }
+}
+
+/// Report that the test has started on a new step. `step` should complete the sentence "The test
+/// was ...", and it's included in the test's result if the test stalls or times out. Tests that
+/// can spend a long time without calling any of the plugin's functions need to call this
+/// regularly, or the validator considers them to be stalled. This is a no-op outside of the child
+/// process.
+pub fn progress(step: impl Into<String>) {
+    if let Some(connection) = CONNECTION.lock().unwrap().as_ref() {
+        // If the connection is gone, then the progress can't be reported anyways
+        let _ = connection.send(&ChildMessage::Progress { step: step.into() });
+    }
+}
+
+/// Send a trace event recorded in the child process to the validator. This is done for every event
+/// as soon as it's recorded, so the events leading up to a crash are not lost. This is a no-op
+/// outside of the child process. See the [`call_trace`] module.
+pub fn send_trace_event(event: TraceEvent) {
+    let connection = CONNECTION.lock().unwrap().clone();
+    if let Some(connection) = connection {
+        // If the connection is gone, then the event can't be recorded anyways
+        let _ = connection.send(&ChildMessage::TraceEvents {
+            events: vec![event],
+        });
+    }
+}
+
+/// Send the crash context to the validator, if this is the child process. The message is flushed
+/// before this returns, so it's received even if the process crashes right after.
+fn send_crash_context(context: Option<String>) {
+    if let Some(connection) = CONNECTION.lock().unwrap().as_ref() {
+        // If the connection is gone, then the context can't be reported anyways
+        let _ = connection.send(&ChildMessage::CrashContext { context });
+    }
+}
+
+/// The plugin function calls that are currently in progress.
+fn pending_plugin_calls() -> Vec<PendingPluginCall> {
+    let mut tracking_threads = TRACKING_THREADS.lock().unwrap();
+    tracking_threads.retain(|thread_calls| thread_calls.strong_count() > 0);
+
+    let mut pending_calls = Vec::new();
+    for thread_calls in tracking_threads.iter().filter_map(Weak::upgrade) {
+        let depth = thread_calls.depth.load(Ordering::Acquire);
+        for call in thread_calls
+            .calls
+            .iter()
+            .take(depth)
+            .filter_map(AtomicCell::load)
+        {
+            pending_calls.push(PendingPluginCall {
+                // The type name includes the module path, e.g. `clap_sys::plugin::clap_plugin`
+                function: format!(
+                    "{}::{}",
+                    call.struct_name.rsplit("::").next().unwrap(),
+                    call.function_name
+                ),
+                thread: thread_calls.thread_name.clone(),
+                duration: call.started_at.elapsed(),
+            });
+        }
+    }
+
+    pending_calls
+}
+
+impl log::Log for ChildLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            // If the connection is gone, then there's nowhere else to send the message to
+            let _ = self.connection.send(&ChildMessage::Log {
+                level: record.level().to_string(),
+                message: record.args().to_string(),
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
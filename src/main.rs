@@ -1,10 +1,14 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use commands::format_version::{CURRENT_FORMAT_VERSION, MIN_FORMAT_VERSION};
 use std::path::PathBuf;
 use std::process::ExitCode;
 use validator::{SingleTestSettings, ValidatorSettings};
 
+mod call_trace;
 mod commands;
+mod crash_report;
 mod index;
+mod ipc;
 mod plugin;
 mod tests;
 mod util;
@@ -18,6 +22,18 @@ struct Cli {
     /// This can be used to silence all non-essential output, or to enable more in depth tracing.
     #[arg(short, long, default_value = "debug")]
     verbosity: Verbosity,
+    /// The layout version used for JSON output.
+    ///
+    /// Scripts consuming the JSON output can set this to keep receiving the same layout across
+    /// validator releases. Version 1 is the layout used up to and including clap-validator 0.3.2.
+    #[arg(
+        long,
+        global = true,
+        default_value_t = CURRENT_FORMAT_VERSION,
+        value_parser = clap::value_parser!(u32)
+            .range(MIN_FORMAT_VERSION as i64..=CURRENT_FORMAT_VERSION as i64)
+    )]
+    format_version: u32,
 
     #[command(subcommand)]
     command: Command,
@@ -36,6 +52,20 @@ pub enum Verbosity {
     Trace,
 }
 
+impl Verbosity {
+    /// The log level filter corresponding to this verbosity level.
+    pub fn level_filter(self) -> simplelog::LevelFilter {
+        match self {
+            Verbosity::Quiet => simplelog::LevelFilter::Off,
+            Verbosity::Error => simplelog::LevelFilter::Error,
+            Verbosity::Warn => simplelog::LevelFilter::Warn,
+            Verbosity::Info => simplelog::LevelFilter::Info,
+            Verbosity::Debug => simplelog::LevelFilter::Debug,
+            Verbosity::Trace => simplelog::LevelFilter::Trace,
+        }
+    }
+}
+
 /// The validator's subcommands.
 #[derive(Subcommand)]
 enum Command {
@@ -50,6 +80,27 @@ enum Command {
 
     #[command(subcommand)]
     List(ListCommand),
+    /// Aggregate the results of one or more validator runs into summary statistics.
+    ///
+    /// This reads result files written by `clap-validator validate --json` and computes pass rates
+    /// for every test, the most common failures and skip reasons, and the adoption of extensions
+    /// among the validated plugins. The statistics are printed as Markdown by default.
+    Stats {
+        /// Paths to one or more JSON result files.
+        #[arg(required = true)]
+        results: Vec<PathBuf>,
+        /// Print JSON instead of Markdown.
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Print the JSON schema for one of the validator's JSON outputs.
+    ///
+    /// These schemas describe the output of the commands' --json options, and can be used to
+    /// validate or generate bindings for the output.
+    Schema {
+        /// The output to print the schema for.
+        kind: commands::schema::SchemaKind,
+    },
 }
 
 /// Commands for listing tests and data realted to the installed plugins.
@@ -70,6 +121,12 @@ pub enum ListCommand {
         ///
         /// All installed plugins are crawled if this value is missing.
         paths: Option<Vec<PathBuf>>,
+        /// Warn when crawling a single preset file takes longer than this many milliseconds.
+        #[arg(long, default_value = "100")]
+        max_file_crawl_time: f64,
+        /// Warn when crawling all of a provider's presets takes longer than this many milliseconds.
+        #[arg(long, default_value = "5000")]
+        max_total_crawl_time: f64,
     },
     /// Lists all available test cases.
     Tests {
@@ -79,20 +136,11 @@ pub enum ListCommand {
     },
 }
 
-fn main() -> ExitCode {
-    let cli = Cli::parse();
-
-    // For now logging everything to the terminal is fine. In the future it may be useful to have
-    // CLI options for things like the verbosity level.
+/// Log everything to the terminal. In the future it may be useful to have CLI options for things
+/// other than the verbosity level.
+fn init_terminal_logger(verbosity: Verbosity) {
     simplelog::TermLogger::init(
-        match cli.verbosity {
-            Verbosity::Quiet => simplelog::LevelFilter::Off,
-            Verbosity::Error => simplelog::LevelFilter::Error,
-            Verbosity::Warn => simplelog::LevelFilter::Warn,
-            Verbosity::Info => simplelog::LevelFilter::Info,
-            Verbosity::Debug => simplelog::LevelFilter::Debug,
-            Verbosity::Trace => simplelog::LevelFilter::Trace,
-        },
+        verbosity.level_filter(),
         simplelog::ConfigBuilder::new()
             .set_thread_mode(simplelog::ThreadLogMode::Both)
             .set_location_level(simplelog::LevelFilter::Debug)
@@ -101,16 +149,49 @@ fn main() -> ExitCode {
         simplelog::ColorChoice::Auto,
     )
     .expect("Could not initialize logger");
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // The single-test runner forwards its log messages to the validator process that spawned it
+    // instead, so it installs its own logger
+    if !matches!(cli.command, Command::RunSingleTest(_)) {
+        init_terminal_logger(cli.verbosity);
+    }
     log_panics::init();
 
     let result = match cli.command {
-        Command::Validate(settings) => commands::validate::validate(cli.verbosity, &settings),
-        Command::RunSingleTest(settings) => commands::validate::run_single(&settings),
-        Command::List(ListCommand::Plugins { json }) => commands::list::plugins(json),
-        Command::List(ListCommand::Presets { json, paths }) => {
-            commands::list::presets(json, paths.as_deref())
+        Command::Validate(settings) => {
+            commands::validate::validate(cli.verbosity, cli.format_version, &settings)
+        }
+        Command::RunSingleTest(settings) => {
+            commands::validate::run_single(cli.verbosity, &settings)
+        }
+        Command::List(ListCommand::Plugins { json }) => {
+            commands::list::plugins(json, cli.format_version)
+        }
+        Command::List(ListCommand::Presets {
+            json,
+            paths,
+            max_file_crawl_time,
+            max_total_crawl_time,
+        }) => commands::list::presets(
+            json,
+            cli.format_version,
+            paths.as_deref(),
+            index::CrawlBudget {
+                max_file_time_ms: max_file_crawl_time,
+                max_total_time_ms: max_total_crawl_time,
+            },
+        ),
+        Command::List(ListCommand::Tests { json }) => {
+            commands::list::tests(json, cli.format_version)
+        }
+        Command::Stats { results, json } => {
+            commands::stats::stats(&results, json, cli.format_version)
         }
-        Command::List(ListCommand::Tests { json }) => commands::list::tests(json),
+        Command::Schema { kind } => commands::schema::schema(kind),
     };
 
     match result {
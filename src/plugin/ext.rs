@@ -2,14 +2,94 @@
 //! `FooAudioThread` struct. The former contains functions that can be called from the main thread,
 //! while the latter contains functions that can be called from the audio thread.
 
+use clap_sys::ext::audio_ports::CLAP_EXT_AUDIO_PORTS;
+use clap_sys::ext::audio_ports_config::CLAP_EXT_AUDIO_PORTS_CONFIG;
+use clap_sys::ext::draft::ambisonic::CLAP_EXT_AMBISONIC;
+use clap_sys::ext::draft::audio_ports_activation::CLAP_EXT_AUDIO_PORTS_ACTIVATION;
+use clap_sys::ext::draft::check_for_update::CLAP_EXT_CHECK_FOR_UPDATE;
+use clap_sys::ext::draft::cv::CLAP_EXT_CV;
+use clap_sys::ext::draft::midi_mappings::CLAP_EXT_MIDI_MAPPINGS;
+use clap_sys::ext::draft::param_indication::CLAP_EXT_PARAM_INDICATION;
+use clap_sys::ext::draft::preset_load::CLAP_EXT_PRESET_LOAD;
+use clap_sys::ext::draft::remote_controls::CLAP_EXT_REMOTE_CONTROLS;
+use clap_sys::ext::draft::resource_directory::CLAP_EXT_RESOURCE_DIRECTORY;
+use clap_sys::ext::draft::state_context::CLAP_EXT_STATE_CONTEXT;
+use clap_sys::ext::draft::surround::CLAP_EXT_SURROUND;
+use clap_sys::ext::draft::track_info::CLAP_EXT_TRACK_INFO;
+use clap_sys::ext::draft::transport_control::CLAP_EXT_TRANSPORT_CONTROL;
+use clap_sys::ext::draft::triggers::CLAP_EXT_TRIGGERS;
+use clap_sys::ext::draft::tuning::CLAP_EXT_TUNING;
+use clap_sys::ext::gui::CLAP_EXT_GUI;
+use clap_sys::ext::latency::CLAP_EXT_LATENCY;
+use clap_sys::ext::note_name::CLAP_EXT_NOTE_NAME;
+use clap_sys::ext::note_ports::CLAP_EXT_NOTE_PORTS;
+use clap_sys::ext::params::CLAP_EXT_PARAMS;
+use clap_sys::ext::posix_fd_support::CLAP_EXT_POSIX_FD_SUPPORT;
+use clap_sys::ext::render::CLAP_EXT_RENDER;
+use clap_sys::ext::state::CLAP_EXT_STATE;
+use clap_sys::ext::tail::CLAP_EXT_TAIL;
+use clap_sys::ext::thread_pool::CLAP_EXT_THREAD_POOL;
+use clap_sys::ext::timer_support::CLAP_EXT_TIMER_SUPPORT;
+use clap_sys::ext::voice_info::CLAP_EXT_VOICE_INFO;
 use std::ffi::CStr;
 use std::ptr::NonNull;
 
+use context_menu::ffi::CLAP_EXT_CONTEXT_MENU;
+
 pub mod audio_ports;
+pub mod audio_ports_config;
+pub mod context_menu;
 pub mod note_ports;
 pub mod params;
 pub mod preset_load;
+pub mod remote_controls;
+pub mod render;
 pub mod state;
+pub mod tail;
+pub mod thread_pool;
+pub mod timer_support;
+pub mod track_info;
+
+/// The IDs of the plugin extensions clap-validator has tests for. This needs to be updated when
+/// adding tests for another extension.
+pub const VALIDATED_EXTENSIONS: &[&CStr] = &[
+    CLAP_EXT_AUDIO_PORTS,
+    CLAP_EXT_AUDIO_PORTS_CONFIG,
+    CLAP_EXT_CONTEXT_MENU,
+    CLAP_EXT_NOTE_PORTS,
+    CLAP_EXT_PARAMS,
+    CLAP_EXT_PRESET_LOAD,
+    CLAP_EXT_REMOTE_CONTROLS,
+    CLAP_EXT_RENDER,
+    CLAP_EXT_STATE,
+    CLAP_EXT_TAIL,
+    CLAP_EXT_THREAD_POOL,
+    CLAP_EXT_TIMER_SUPPORT,
+    CLAP_EXT_TRACK_INFO,
+];
+
+/// The IDs of the plugin extensions defined by CLAP, including the draft extensions, that
+/// clap-validator does not have any tests for. The `extension-coverage` test reports which of
+/// these a plugin implements, since a passing validator run says nothing about them.
+pub const UNVALIDATED_EXTENSIONS: &[&CStr] = &[
+    CLAP_EXT_GUI,
+    CLAP_EXT_LATENCY,
+    CLAP_EXT_NOTE_NAME,
+    CLAP_EXT_POSIX_FD_SUPPORT,
+    CLAP_EXT_VOICE_INFO,
+    CLAP_EXT_AMBISONIC,
+    CLAP_EXT_AUDIO_PORTS_ACTIVATION,
+    CLAP_EXT_CHECK_FOR_UPDATE,
+    CLAP_EXT_CV,
+    CLAP_EXT_MIDI_MAPPINGS,
+    CLAP_EXT_PARAM_INDICATION,
+    CLAP_EXT_RESOURCE_DIRECTORY,
+    CLAP_EXT_STATE_CONTEXT,
+    CLAP_EXT_SURROUND,
+    CLAP_EXT_TRANSPORT_CONTROL,
+    CLAP_EXT_TRIGGERS,
+    CLAP_EXT_TUNING,
+];
 
 /// An abstraction for a CLAP plugin extension. `P` here is the plugin type. In practice, this is
 /// either `Plugin` or `PluginAudioThread`. Abstractions for main thread functions will implement
@@ -2,8 +2,9 @@
 
 use anyhow::{Context, Result};
 use clap_sys::ext::audio_ports::{
-    clap_audio_port_info, clap_plugin_audio_ports, CLAP_EXT_AUDIO_PORTS, CLAP_PORT_MONO,
-    CLAP_PORT_STEREO,
+    clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN,
+    CLAP_AUDIO_PORT_PREFERS_64BITS, CLAP_AUDIO_PORT_SUPPORTS_64BITS, CLAP_EXT_AUDIO_PORTS,
+    CLAP_PORT_MONO, CLAP_PORT_STEREO,
 };
 use clap_sys::ext::draft::ambisonic::CLAP_PORT_AMBISONIC;
 use clap_sys::ext::draft::cv::CLAP_PORT_CV;
@@ -26,7 +27,7 @@ pub struct AudioPorts<'a> {
 }
 
 /// The audio port configuration for a plugin.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct AudioPortConfig {
     /// Configuration for the plugin's input audio ports.
     pub inputs: Vec<AudioPort>,
@@ -35,15 +36,43 @@ pub struct AudioPortConfig {
 }
 
 /// The configuration for a single audio port.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct AudioPort {
+    /// The port's stable ID.
+    pub stable_id: u32,
     /// The number of channels for an audio port.
     pub num_channels: u32,
+    /// The port's type, if the plugin specified one.
+    pub port_type: Option<AudioPortType>,
+    /// Whether the port has the `CLAP_AUDIO_PORT_IS_MAIN` flag.
+    pub is_main: bool,
+    /// Whether the port has the `CLAP_AUDIO_PORT_SUPPORTS_64BITS` flag.
+    pub supports_64bit: bool,
+    /// Whether the port has the `CLAP_AUDIO_PORT_PREFERS_64BITS` flag. This implies
+    /// `supports_64bit`.
+    pub prefers_64bit: bool,
     /// The index if the output/input port this input/output port should be connected to. This is
     /// the index in the other **port list**, not a stable ID (which have already been translated).
     pub in_place_pair_idx: Option<usize>,
 }
 
+/// The type of an audio port, parsed from `clap_audio_port_info::port_type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioPortType {
+    /// `CLAP_PORT_MONO`.
+    Mono,
+    /// `CLAP_PORT_STEREO`.
+    Stereo,
+    /// `CLAP_PORT_SURROUND`.
+    Surround,
+    /// `CLAP_PORT_AMBISONIC`.
+    Ambisonic,
+    /// `CLAP_PORT_CV`. These ports carry control voltage signals instead of audio.
+    Cv,
+    /// Any other port type. These are allowed, but the host won't know what to do with them.
+    Custom(String),
+}
+
 impl<'a> Extension<&'a Plugin<'a>> for AudioPorts<'a> {
     const EXTENSION_ID: &'static CStr = CLAP_EXT_AUDIO_PORTS;
 
@@ -88,12 +117,14 @@ impl AudioPorts<'_> {
                 );
             }
 
-            is_audio_port_type_consistent(&info).with_context(|| {
+            let port_type = parse_audio_port_type(&info).with_context(|| {
                 format!(
-                    "Inconsistent channel count for output port {i} ({num_outputs} total output \
-                     ports)"
+                    "Inconsistent port type for input port {i} ({num_inputs} total input ports)"
                 )
             })?;
+            are_audio_port_flags_consistent(&info).with_context(|| {
+                format!("Inconsistent flags for input port {i} ({num_inputs} total input ports)")
+            })?;
 
             // We'll convert these stable IDs to vector indices later
             if input_stable_index_pairs.contains_key(&info.id) {
@@ -105,7 +136,12 @@ impl AudioPorts<'_> {
             input_stable_index_pairs.insert(info.id, (i as usize, info.in_place_pair));
 
             config.inputs.push(AudioPort {
+                stable_id: info.id,
                 num_channels: info.channel_count,
+                port_type,
+                is_main: (info.flags & CLAP_AUDIO_PORT_IS_MAIN) != 0,
+                supports_64bit: (info.flags & CLAP_AUDIO_PORT_SUPPORTS_64BITS) != 0,
+                prefers_64bit: (info.flags & CLAP_AUDIO_PORT_PREFERS_64BITS) != 0,
                 // These are reconstructed from `input_stable_index_pairs` and
                 // `output_stable_index_pairs` later
                 in_place_pair_idx: None,
@@ -122,12 +158,15 @@ impl AudioPorts<'_> {
                 );
             }
 
-            is_audio_port_type_consistent(&info).with_context(|| {
+            let port_type = parse_audio_port_type(&info).with_context(|| {
                 format!(
-                    "Inconsistent channel count for output port {i} ({num_outputs} total output \
+                    "Inconsistent port type for output port {i} ({num_outputs} total output \
                      ports)"
                 )
             })?;
+            are_audio_port_flags_consistent(&info).with_context(|| {
+                format!("Inconsistent flags for output port {i} ({num_outputs} total output ports)")
+            })?;
 
             if output_stable_index_pairs.contains_key(&info.id) {
                 anyhow::bail!(
@@ -138,11 +177,44 @@ impl AudioPorts<'_> {
             output_stable_index_pairs.insert(info.id, (i as usize, info.in_place_pair));
 
             config.outputs.push(AudioPort {
+                stable_id: info.id,
                 num_channels: info.channel_count,
+                port_type,
+                is_main: (info.flags & CLAP_AUDIO_PORT_IS_MAIN) != 0,
+                supports_64bit: (info.flags & CLAP_AUDIO_PORT_SUPPORTS_64BITS) != 0,
+                prefers_64bit: (info.flags & CLAP_AUDIO_PORT_PREFERS_64BITS) != 0,
                 in_place_pair_idx: None,
             });
         }
 
+        // There can be at most one main input and one main output port, and those need to be the
+        // first port in the list
+        for (direction, ports) in [("input", &config.inputs), ("output", &config.outputs)] {
+            let main_port_indices: Vec<usize> = ports
+                .iter()
+                .enumerate()
+                .filter(|(_, port)| port.is_main)
+                .map(|(port_idx, _)| port_idx)
+                .collect();
+            match main_port_indices.as_slice() {
+                [] | [0] => (),
+                [port_idx] => anyhow::bail!(
+                    "The main {direction} port is {direction} port {port_idx}, but the main port \
+                     must be the first port."
+                ),
+                port_indices => anyhow::bail!(
+                    "{} {direction} ports have the 'CLAP_AUDIO_PORT_IS_MAIN' flag set ({}), but \
+                     there can only be a single main {direction} port.",
+                    port_indices.len(),
+                    port_indices
+                        .iter()
+                        .map(|port_idx| port_idx.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+
         // Now we need to convert the stable in-place pair indices to vector indices
         for (input_stable_id, (input_port_idx, pair_stable_id)) in input_stable_index_pairs
             .iter()
@@ -221,48 +293,99 @@ impl AudioPorts<'_> {
     }
 }
 
-/// Check whether the number of channels matches an audio port's type string, if that is set.
-/// Returns an error if the port type is not consistent
-fn is_audio_port_type_consistent(info: &clap_audio_port_info) -> Result<()> {
+/// Parse an audio port's type string, if that is set, and check whether the port's number of
+/// channels matches that type. Returns an error if the port type is not consistent.
+fn parse_audio_port_type(info: &clap_audio_port_info) -> Result<Option<AudioPortType>> {
     if info.port_type.is_null() {
-        return Ok(());
+        return Ok(None);
     }
 
     let port_type = unsafe { CStr::from_ptr(info.port_type) };
-    if port_type == CLAP_PORT_MONO {
-        if info.channel_count == 1 {
-            Ok(())
-        } else {
-            anyhow::bail!(
-                "Expected 1 channel, but the audio port has {} channels.",
-                info.channel_count
-            );
-        }
+    let port_type = if port_type.to_bytes().is_empty() {
+        return Ok(None);
+    } else if port_type == CLAP_PORT_MONO {
+        AudioPortType::Mono
     } else if port_type == CLAP_PORT_STEREO {
-        if info.channel_count == 2 {
-            Ok(())
-        } else {
+        AudioPortType::Stereo
+    } else if port_type == CLAP_PORT_SURROUND {
+        AudioPortType::Surround
+    } else if port_type == CLAP_PORT_AMBISONIC {
+        AudioPortType::Ambisonic
+    } else if port_type == CLAP_PORT_CV {
+        AudioPortType::Cv
+    } else {
+        let port_type = port_type
+            .to_str()
+            .context("The port type contains invalid UTF-8")?;
+        log::debug!("Unknown audio port type '{port_type}'");
+
+        AudioPortType::Custom(port_type.to_owned())
+    };
+
+    // An ambisonic port of order N has (N + 1)^2 channels
+    let num_channels = info.channel_count;
+    let ambisonic_order_plus_one = (num_channels as f64).sqrt().round() as u32;
+    match port_type {
+        AudioPortType::Mono if num_channels != 1 => anyhow::bail!(
+            "Expected 1 channel for a mono port, but the audio port has {num_channels} channels."
+        ),
+        AudioPortType::Stereo if num_channels != 2 => anyhow::bail!(
+            "Expected 2 channels for a stereo port, but the audio port has {num_channels} \
+             channel(s)."
+        ),
+        // TODO: Test the surround and ambisonic channel layouts by querying those extensions
+        AudioPortType::Surround | AudioPortType::Cv if num_channels == 0 => anyhow::bail!(
+            "The audio port has type '{}', but it does not have any channels.",
+            port_type.as_str()
+        ),
+        AudioPortType::Ambisonic
+            if num_channels == 0
+                || ambisonic_order_plus_one * ambisonic_order_plus_one != num_channels =>
+        {
             anyhow::bail!(
-                "Expected 2 channels, but the audio port has {} channel(s).",
-                info.channel_count
-            );
+                "Expected a square number of channels for an ambisonic port, but the audio port \
+                 has {num_channels} channel(s)."
+            )
+        }
+        _ => Ok(Some(port_type)),
+    }
+}
+
+impl AudioPortType {
+    /// Get the port type string for this type, as used in `clap_audio_port_info::port_type`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            AudioPortType::Mono => CLAP_PORT_MONO.to_str().unwrap(),
+            AudioPortType::Stereo => CLAP_PORT_STEREO.to_str().unwrap(),
+            AudioPortType::Surround => CLAP_PORT_SURROUND.to_str().unwrap(),
+            AudioPortType::Ambisonic => CLAP_PORT_AMBISONIC.to_str().unwrap(),
+            AudioPortType::Cv => CLAP_PORT_CV.to_str().unwrap(),
+            AudioPortType::Custom(port_type) => port_type,
         }
-    } else if port_type == CLAP_PORT_SURROUND
-        || port_type == CLAP_PORT_CV
-        || port_type == CLAP_PORT_AMBISONIC
+    }
+}
+
+/// Check whether the flags set on an audio port are consistent. Returns an error if a flag is set
+/// that requires another flag that is not set.
+fn are_audio_port_flags_consistent(info: &clap_audio_port_info) -> Result<()> {
+    if (info.flags & CLAP_AUDIO_PORT_PREFERS_64BITS) != 0
+        && (info.flags & CLAP_AUDIO_PORT_SUPPORTS_64BITS) == 0
     {
-        // TODO: Test the channel counts by querying those extensions
-        Ok(())
-    } else {
-        log::debug!("TODO: Unknown audio port type '{port_type:?}'");
-        Ok(())
+        anyhow::bail!(
+            "The port has the 'CLAP_AUDIO_PORT_PREFERS_64BITS' flag set without also setting \
+             'CLAP_AUDIO_PORT_SUPPORTS_64BITS'."
+        );
     }
+
+    Ok(())
 }
 
 impl AudioPortConfig {
     /// Create a pair of zero initialized `(input_buffers, output_buffers)` for this audio port
     /// configuration. These can be bassed with
     /// [`ProcessData`][super::audio_thread::process::ProcessData] to create a process data struct.
+    /// CV ports get buffers like any other port. Use [`cv_inputs()`][Self::cv_inputs()] to find out
+    /// which of the input buffers carry CV signals.
     #[allow(clippy::type_complexity)]
     pub fn create_buffers(&self, buffer_size: usize) -> (Vec<Vec<Vec<f32>>>, Vec<Vec<Vec<f32>>>) {
         let input_buffers: Vec<Vec<Vec<f32>>> = self
@@ -278,4 +401,13 @@ impl AudioPortConfig {
 
         (input_buffers, output_buffers)
     }
+
+    /// Returns for every input port whether it's a CV port. These should receive control signals
+    /// instead of audio.
+    pub fn cv_inputs(&self) -> Vec<bool> {
+        self.inputs
+            .iter()
+            .map(|port| port.port_type == Some(AudioPortType::Cv))
+            .collect()
+    }
 }
@@ -0,0 +1,76 @@
+//! Abstractions for interacting with the `audio-ports-config` extension.
+
+use anyhow::Result;
+use clap_sys::ext::audio_ports_config::{
+    clap_audio_ports_config, clap_plugin_audio_ports_config, CLAP_EXT_AUDIO_PORTS_CONFIG,
+};
+use clap_sys::id::clap_id;
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use crate::plugin::assert_plugin_state_eq;
+use crate::plugin::instance::{Plugin, PluginStatus};
+use crate::util::unsafe_clap_call;
+
+use super::Extension;
+
+/// Abstraction for the `audio-ports-config` extension covering the main thread functionality.
+#[derive(Debug)]
+pub struct AudioPortsConfig<'a> {
+    plugin: &'a Plugin<'a>,
+    audio_ports_config: NonNull<clap_plugin_audio_ports_config>,
+}
+
+impl<'a> Extension<&'a Plugin<'a>> for AudioPortsConfig<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_AUDIO_PORTS_CONFIG;
+
+    type Struct = clap_plugin_audio_ports_config;
+
+    fn new(plugin: &'a Plugin<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            audio_ports_config: extension_struct,
+        }
+    }
+}
+
+impl AudioPortsConfig<'_> {
+    /// Used by the status assertion macros.
+    fn status(&self) -> PluginStatus {
+        self.plugin.status()
+    }
+
+    /// Get the IDs of the plugin's audio port configurations, in the order the plugin lists them.
+    pub fn config_ids(&self) -> Result<Vec<clap_id>> {
+        let audio_ports_config = self.audio_ports_config.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        let num_configs = unsafe_clap_call! { audio_ports_config=>count(plugin) };
+
+        let mut config_ids = Vec::with_capacity(num_configs as usize);
+        for i in 0..num_configs {
+            let mut config: clap_audio_ports_config = unsafe { std::mem::zeroed() };
+            let success = unsafe_clap_call! { audio_ports_config=>get(plugin, i, &mut config) };
+            if !success {
+                anyhow::bail!(
+                    "Plugin returned false when querying audio port configuration {i} \
+                     ({num_configs} total configurations)."
+                );
+            }
+
+            config_ids.push(config.id);
+        }
+
+        Ok(config_ids)
+    }
+
+    /// Call `clap_plugin_audio_ports_config::select()` while the plugin is activated. Port
+    /// configurations may only be selected while the plugin is deactivated, so this is only used by
+    /// the negative tests. Returns the plugin's return value.
+    pub fn select_while_activated(&self, config_id: clap_id) -> bool {
+        assert_plugin_state_eq!(self, PluginStatus::Activated);
+
+        let audio_ports_config = self.audio_ports_config.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { audio_ports_config=>select(plugin, config_id) }
+    }
+}
@@ -0,0 +1,387 @@
+//! Abstractions for interacting with the `context-menu` draft extension.
+//!
+//! The version of `clap-sys` used by the validator does not yet contain bindings for this
+//! extension, so the [`ffi`] module contains bindings for CLAP 1.1.8's `context-menu.h` draft
+//! header.
+
+use anyhow::{Context, Result};
+use clap_sys::id::clap_id;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::ptr::NonNull;
+
+use super::Extension;
+use crate::plugin::instance::Plugin;
+use crate::util::{self, unsafe_clap_call};
+use ffi::*;
+
+/// Bindings for the `context-menu` draft extension.
+#[allow(non_camel_case_types)]
+pub mod ffi {
+    use clap_sys::host::clap_host;
+    use clap_sys::id::clap_id;
+    use clap_sys::plugin::clap_plugin;
+    use std::ffi::{c_void, CStr};
+    use std::os::raw::c_char;
+
+    pub const CLAP_EXT_CONTEXT_MENU: &CStr =
+        unsafe { CStr::from_bytes_with_nul_unchecked(b"clap.context-menu.draft/0\0") };
+
+    pub const CLAP_CONTEXT_MENU_TARGET_KIND_GLOBAL: u32 = 0;
+    pub const CLAP_CONTEXT_MENU_TARGET_KIND_PARAM: u32 = 1;
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_context_menu_target {
+        pub kind: u32,
+        pub id: clap_id,
+    }
+
+    pub type clap_context_menu_item_kind = u32;
+
+    pub const CLAP_CONTEXT_MENU_ITEM_ENTRY: clap_context_menu_item_kind = 0;
+    pub const CLAP_CONTEXT_MENU_ITEM_CHECK_ENTRY: clap_context_menu_item_kind = 1;
+    pub const CLAP_CONTEXT_MENU_ITEM_SEPARATOR: clap_context_menu_item_kind = 2;
+    pub const CLAP_CONTEXT_MENU_ITEM_BEGIN_SUBMENU: clap_context_menu_item_kind = 3;
+    pub const CLAP_CONTEXT_MENU_ITEM_END_SUBMENU: clap_context_menu_item_kind = 4;
+    pub const CLAP_CONTEXT_MENU_ITEM_TITLE: clap_context_menu_item_kind = 5;
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_context_menu_entry {
+        pub label: *const c_char,
+        pub is_enabled: bool,
+        pub action_id: clap_id,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_context_menu_check_entry {
+        pub label: *const c_char,
+        pub is_enabled: bool,
+        pub is_checked: bool,
+        pub action_id: clap_id,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_context_menu_item_title {
+        pub title: *const c_char,
+        pub is_enabled: bool,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_context_menu_submenu {
+        pub label: *const c_char,
+        pub is_enabled: bool,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_context_menu_builder {
+        pub ctx: *mut c_void,
+        pub add_item: Option<
+            unsafe extern "C" fn(
+                builder: *const clap_context_menu_builder,
+                item_kind: clap_context_menu_item_kind,
+                item_data: *const c_void,
+            ) -> bool,
+        >,
+        pub supports: Option<
+            unsafe extern "C" fn(
+                builder: *const clap_context_menu_builder,
+                item_kind: clap_context_menu_item_kind,
+            ) -> bool,
+        >,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_plugin_context_menu {
+        pub populate: Option<
+            unsafe extern "C" fn(
+                plugin: *const clap_plugin,
+                target: *const clap_context_menu_target,
+                builder: *const clap_context_menu_builder,
+            ) -> bool,
+        >,
+        pub perform: Option<
+            unsafe extern "C" fn(
+                plugin: *const clap_plugin,
+                target: *const clap_context_menu_target,
+                action_id: clap_id,
+            ) -> bool,
+        >,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct clap_host_context_menu {
+        pub populate: Option<
+            unsafe extern "C" fn(
+                host: *const clap_host,
+                target: *const clap_context_menu_target,
+                builder: *const clap_context_menu_builder,
+            ) -> bool,
+        >,
+        pub perform: Option<
+            unsafe extern "C" fn(
+                host: *const clap_host,
+                target: *const clap_context_menu_target,
+                action_id: clap_id,
+            ) -> bool,
+        >,
+        pub can_popup: Option<unsafe extern "C" fn(host: *const clap_host) -> bool>,
+        pub popup: Option<
+            unsafe extern "C" fn(
+                host: *const clap_host,
+                target: *const clap_context_menu_target,
+                screen_index: i32,
+                x: i32,
+                y: i32,
+            ) -> bool,
+        >,
+    }
+}
+
+/// Abstraction for the `context-menu` extension covering the main thread functionality.
+#[derive(Debug)]
+pub struct ContextMenu<'a> {
+    plugin: &'a Plugin<'a>,
+    context_menu: NonNull<clap_plugin_context_menu>,
+}
+
+/// The object a context menu is requested for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextMenuTarget {
+    /// The plugin as a whole.
+    Global,
+    /// The parameter with this stable ID.
+    Param(clap_id),
+}
+
+/// A context menu item added by the plugin. These are parsed from the item kinds and item data
+/// passed to `clap_context_menu_builder::add_item()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextMenuItem {
+    Entry {
+        label: String,
+        is_enabled: bool,
+        action_id: clap_id,
+    },
+    CheckEntry {
+        label: String,
+        is_enabled: bool,
+        is_checked: bool,
+        action_id: clap_id,
+    },
+    Separator,
+    BeginSubmenu {
+        label: String,
+        is_enabled: bool,
+    },
+    EndSubmenu,
+    Title {
+        title: String,
+        is_enabled: bool,
+    },
+}
+
+/// The `ctx` for the `clap_context_menu_builder` passed to the plugin. The builder's callbacks
+/// store the parsed items here, along with any errors encountered while parsing them.
+#[derive(Debug, Default)]
+struct ContextMenuBuilder {
+    items: RefCell<Vec<ContextMenuItem>>,
+    errors: RefCell<Vec<String>>,
+}
+
+impl<'a> Extension<&'a Plugin<'a>> for ContextMenu<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_CONTEXT_MENU;
+
+    type Struct = clap_plugin_context_menu;
+
+    fn new(plugin: &'a Plugin<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            context_menu: extension_struct,
+        }
+    }
+}
+
+impl ContextMenuTarget {
+    /// Get the `clap_context_menu_target` for this target.
+    pub fn to_clap(self) -> clap_context_menu_target {
+        match self {
+            ContextMenuTarget::Global => clap_context_menu_target {
+                kind: CLAP_CONTEXT_MENU_TARGET_KIND_GLOBAL,
+                id: 0,
+            },
+            ContextMenuTarget::Param(param_id) => clap_context_menu_target {
+                kind: CLAP_CONTEXT_MENU_TARGET_KIND_PARAM,
+                id: param_id,
+            },
+        }
+    }
+}
+
+impl ContextMenu<'_> {
+    /// Have the plugin populate a context menu for `target`. Returns `None` if the plugin returned
+    /// `false`. Returns an error if the plugin added items with unknown item kinds or null labels,
+    /// if the menu's submenus are not balanced, or if multiple entries share the same action ID.
+    pub fn populate(&self, target: ContextMenuTarget) -> Result<Option<Vec<ContextMenuItem>>> {
+        let builder = ContextMenuBuilder::default();
+        let clap_builder = clap_context_menu_builder {
+            ctx: &builder as *const ContextMenuBuilder as *mut c_void,
+            add_item: Some(ContextMenuBuilder::add_item),
+            supports: Some(ContextMenuBuilder::supports),
+        };
+
+        let context_menu = self.context_menu.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        let clap_target = target.to_clap();
+        let success = unsafe_clap_call! {
+            context_menu=>populate(plugin, &clap_target, &clap_builder)
+        };
+
+        let errors = builder.errors.into_inner();
+        if !errors.is_empty() {
+            anyhow::bail!(
+                "The plugin added invalid items to the context menu: {}",
+                errors.join(" ")
+            );
+        }
+        if !success {
+            return Ok(None);
+        }
+
+        let items = builder.items.into_inner();
+        let mut submenu_depth = 0usize;
+        let mut action_ids = HashSet::new();
+        for (item_idx, item) in items.iter().enumerate() {
+            match item {
+                ContextMenuItem::Entry { action_id, .. }
+                | ContextMenuItem::CheckEntry { action_id, .. } => {
+                    if !action_ids.insert(*action_id) {
+                        anyhow::bail!(
+                            "Context menu item {item_idx} uses action ID {action_id}, which is \
+                             already used by another item in the same menu."
+                        );
+                    }
+                }
+                ContextMenuItem::BeginSubmenu { .. } => submenu_depth += 1,
+                ContextMenuItem::EndSubmenu => {
+                    submenu_depth = submenu_depth.checked_sub(1).with_context(|| {
+                        format!(
+                            "Context menu item {item_idx} ends a submenu, but there is no open \
+                             submenu at that point."
+                        )
+                    })?;
+                }
+                ContextMenuItem::Separator | ContextMenuItem::Title { .. } => (),
+            }
+        }
+        if submenu_depth > 0 {
+            anyhow::bail!(
+                "The context menu contains {submenu_depth} submenu(s) that were never ended."
+            );
+        }
+
+        Ok(Some(items))
+    }
+}
+
+impl ContextMenuBuilder {
+    unsafe extern "C" fn add_item(
+        builder: *const clap_context_menu_builder,
+        item_kind: clap_context_menu_item_kind,
+        item_data: *const c_void,
+    ) -> bool {
+        if builder.is_null() || (*builder).ctx.is_null() {
+            log::error!("'clap_context_menu_builder::add_item()' was called with a null pointer");
+            return false;
+        }
+        let this = &*((*builder).ctx as *const ContextMenuBuilder);
+
+        match Self::parse_item(item_kind, item_data) {
+            Ok(item) => {
+                this.items.borrow_mut().push(item);
+                true
+            }
+            Err(err) => {
+                let item_idx = this.items.borrow().len();
+                this.errors
+                    .borrow_mut()
+                    .push(format!("Item {item_idx} is invalid: {err:#}."));
+                false
+            }
+        }
+    }
+
+    unsafe extern "C" fn supports(
+        _builder: *const clap_context_menu_builder,
+        item_kind: clap_context_menu_item_kind,
+    ) -> bool {
+        item_kind <= CLAP_CONTEXT_MENU_ITEM_TITLE
+    }
+
+    /// Parse an item passed to `clap_context_menu_builder::add_item()`.
+    unsafe fn parse_item(
+        item_kind: clap_context_menu_item_kind,
+        item_data: *const c_void,
+    ) -> Result<ContextMenuItem> {
+        let parse_label = |label: *const c_char| -> Result<String> {
+            util::cstr_ptr_to_string(label)?.context("The label is a null pointer")
+        };
+        let require_data = || {
+            if item_data.is_null() {
+                anyhow::bail!("The item kind {item_kind} requires item data, but it was null")
+            }
+
+            Ok(())
+        };
+
+        Ok(match item_kind {
+            CLAP_CONTEXT_MENU_ITEM_ENTRY => {
+                require_data()?;
+                let entry = &*(item_data as *const clap_context_menu_entry);
+                ContextMenuItem::Entry {
+                    label: parse_label(entry.label)?,
+                    is_enabled: entry.is_enabled,
+                    action_id: entry.action_id,
+                }
+            }
+            CLAP_CONTEXT_MENU_ITEM_CHECK_ENTRY => {
+                require_data()?;
+                let entry = &*(item_data as *const clap_context_menu_check_entry);
+                ContextMenuItem::CheckEntry {
+                    label: parse_label(entry.label)?,
+                    is_enabled: entry.is_enabled,
+                    is_checked: entry.is_checked,
+                    action_id: entry.action_id,
+                }
+            }
+            CLAP_CONTEXT_MENU_ITEM_SEPARATOR => ContextMenuItem::Separator,
+            CLAP_CONTEXT_MENU_ITEM_BEGIN_SUBMENU => {
+                require_data()?;
+                let submenu = &*(item_data as *const clap_context_menu_submenu);
+                ContextMenuItem::BeginSubmenu {
+                    label: parse_label(submenu.label)?,
+                    is_enabled: submenu.is_enabled,
+                }
+            }
+            CLAP_CONTEXT_MENU_ITEM_END_SUBMENU => ContextMenuItem::EndSubmenu,
+            CLAP_CONTEXT_MENU_ITEM_TITLE => {
+                require_data()?;
+                let title = &*(item_data as *const clap_context_menu_item_title);
+                ContextMenuItem::Title {
+                    title: parse_label(title.title)?,
+                    is_enabled: title.is_enabled,
+                }
+            }
+            n => anyhow::bail!("Unknown item kind {n}"),
+        })
+    }
+}
@@ -22,7 +22,7 @@ pub struct NotePorts<'a> {
 }
 
 /// The note port configuration for a plugin.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct NotePortConfig {
     /// Configuration for the plugin's input note ports.
     pub inputs: Vec<NotePort>,
@@ -31,7 +31,7 @@ pub struct NotePortConfig {
 }
 
 /// The configuration for a single note port.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NotePort {
     /// The preferred dialect for this note port. This should only ever contain a single value.
     pub prefered_dialect: clap_note_dialect,
@@ -40,6 +40,14 @@ pub struct NotePort {
     pub supported_dialects: Vec<clap_note_dialect>,
 }
 
+impl NotePort {
+    /// Whether this port supports a note dialect. `dialect` should be a single
+    /// `CLAP_NOTE_DIALECT_*` value.
+    pub fn supports_dialect(&self, dialect: clap_note_dialect) -> bool {
+        self.supported_dialects.contains(&dialect)
+    }
+}
+
 impl<'a> Extension<&'a Plugin<'a>> for NotePorts<'a> {
     const EXTENSION_ID: &'static CStr = CLAP_EXT_NOTE_PORTS;
 
@@ -112,7 +120,7 @@ impl NotePorts<'_> {
 
         for i in 0..num_outputs {
             let mut info: clap_note_port_info = unsafe { std::mem::zeroed() };
-            let success = unsafe_clap_call! { note_ports=>get(plugin, i, true, &mut info) };
+            let success = unsafe_clap_call! { note_ports=>get(plugin, i, false, &mut info) };
             if !success {
                 anyhow::bail!(
                     "Plugin returned an error when querying output note port {i} ({num_outputs} \
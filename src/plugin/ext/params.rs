@@ -21,6 +21,8 @@ use std::ptr::NonNull;
 
 use super::Extension;
 use crate::plugin::assert_plugin_state_lt;
+use crate::plugin::host::KnownParam;
+use crate::plugin::instance::audio_thread::PluginAudioThread;
 use crate::plugin::instance::process::EventQueue;
 use crate::plugin::instance::{Plugin, PluginStatus};
 use crate::util::{self, c_char_slice_to_string, unsafe_clap_call};
@@ -47,10 +49,35 @@ impl<'a> Extension<&'a Plugin<'a>> for Params<'a> {
     }
 }
 
+/// Abstraction for the `params` extension covering the audio thread. The `*_from_audio_thread()`
+/// functions call main thread functions from the audio thread, which violates CLAP's threading
+/// rules. These are only used by the negative tests.
+#[derive(Debug)]
+pub struct ParamsAudioThread<'a> {
+    plugin: &'a PluginAudioThread<'a>,
+    params: NonNull<clap_plugin_params>,
+}
+
+impl<'a> Extension<&'a PluginAudioThread<'a>> for ParamsAudioThread<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_PARAMS;
+
+    type Struct = clap_plugin_params;
+
+    fn new(plugin: &'a PluginAudioThread<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            params: extension_struct,
+        }
+    }
+}
+
 /// Information about a parameter.
 #[derive(Debug, Clone)]
 pub struct Param {
     pub name: String,
+    /// The parameter's module path, e.g. `Oscillators/Oscillator 1`. Empty if the parameter is not
+    /// part of a module.
+    pub module: String,
     /// This should be provided to the plugin when sending automation or modulation events for this
     /// parameter.
     pub cookie: *mut c_void,
@@ -62,6 +89,33 @@ pub struct Param {
     pub flags: clap_param_info_flags,
 }
 
+// SAFETY: The cookie is an opaque value that's only ever passed back to the plugin. The host never
+//         dereferences it, so the parameter information can be shared with the audio thread.
+unsafe impl Send for Param {}
+unsafe impl Sync for Param {}
+
+impl ParamsAudioThread<'_> {
+    /// Call `clap_plugin_params::count()`, which is a main thread function, from the audio thread.
+    pub fn count_from_audio_thread(&self) -> u32 {
+        let params = self.params.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { params=>count(plugin) }
+    }
+
+    /// Call `clap_plugin_params::get_info()`, which is a main thread function, from the audio
+    /// thread. Returns the parameter's stable ID, or `None` if the plugin returned `false`.
+    pub fn get_info_from_audio_thread(&self, param_index: u32) -> Option<clap_id> {
+        let params = self.params.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        let mut info: clap_param_info = unsafe { std::mem::zeroed() };
+        if unsafe_clap_call! { params=>get_info(plugin, param_index, &mut info) } {
+            Some(info.id)
+        } else {
+            None
+        }
+    }
+}
+
 impl Params<'_> {
     /// Used by the status assertion macros.
     fn status(&self) -> PluginStatus {
@@ -102,8 +156,9 @@ impl Params<'_> {
                 .map(Some)
                 .with_context(|| {
                     format!(
-                        "Could not convert the string representation of {value} for parameter \
-                         {param_id} to a UTF-8 string"
+                        "Could not convert the string representation of {} for parameter \
+                         {param_id} to a UTF-8 string",
+                        util::format_f64(value)
                     )
                 })
         } else {
@@ -163,10 +218,9 @@ impl Params<'_> {
                 )
             })?;
 
-            // We don't use the module string, but we'll still check it for consistency. Basically
-            // anything goes here as long as there are no trailing, leading, or multiple subsequent
-            // slashes.
-            let module = util::c_char_slice_to_string(&info.name).with_context(|| {
+            // Basically anything goes for the module string as long as there are no trailing,
+            // leading, or multiple subsequent slashes.
+            let module = util::c_char_slice_to_string(&info.module).with_context(|| {
                 format!(
                     "Could not read the module name for parameter '{}' (stable ID {})",
                     &name, info.id
@@ -199,43 +253,55 @@ impl Params<'_> {
             }
 
             let range = info.min_value..=info.max_value;
+            if info.min_value.is_nan() || info.max_value.is_nan() || info.default_value.is_nan() {
+                anyhow::bail!(
+                    "Parameter '{}' (stable ID {}) has a NaN minimum, maximum, or default value \
+                     ({}..={}, default {}).",
+                    &name,
+                    info.id,
+                    util::format_f64(info.min_value),
+                    util::format_f64(info.max_value),
+                    util::format_f64(info.default_value)
+                )
+            }
             if info.min_value > info.max_value {
                 anyhow::bail!(
-                    "Parameter '{}' (stable ID {}) has a minimum value ({:?}) that's higher than \
-                     it's maximum value ({:?}).",
+                    "Parameter '{}' (stable ID {}) has a minimum value ({}) that's higher than \
+                     it's maximum value ({}).",
                     &name,
                     info.id,
-                    info.min_value,
-                    info.max_value
+                    util::format_f64(info.min_value),
+                    util::format_f64(info.max_value)
                 )
             }
             if !range.contains(&info.default_value) {
                 anyhow::bail!(
-                    "Parameter '{}' (stable ID {}) has a default value ({:?}) that falls outside \
-                     of its value range ({:?}).",
+                    "Parameter '{}' (stable ID {}) has a default value ({}) that falls outside \
+                     of its value range ({}..={}).",
                     &name,
                     info.id,
-                    info.default_value,
-                    &range
+                    util::format_f64(info.default_value),
+                    util::format_f64(info.min_value),
+                    util::format_f64(info.max_value)
                 )
             }
             if (info.flags & CLAP_PARAM_IS_STEPPED) != 0 {
                 if info.min_value != info.min_value.trunc() {
                     anyhow::bail!(
                         "Parameter '{}' (stable ID {}) is a stepped parameter, but its minimum \
-                         value ({:?}) is not an integer.",
+                         value ({}) is not an integer.",
                         &name,
                         info.id,
-                        info.min_value,
+                        util::format_f64(info.min_value),
                     )
                 }
                 if info.max_value != info.max_value.trunc() {
                     anyhow::bail!(
                         "Parameter '{}' (stable ID {}) is a stepped parameter, but its maximum \
-                         value ({:?}) is not an integer.",
+                         value ({}) is not an integer.",
                         &name,
                         info.id,
-                        info.max_value,
+                        util::format_f64(info.max_value),
                     )
                 }
             }
@@ -306,6 +372,7 @@ impl Params<'_> {
 
             let processed_info = Param {
                 name,
+                module,
                 cookie: info.cookie,
                 range,
                 default: info.default_value,
@@ -319,6 +386,14 @@ impl Params<'_> {
             }
         }
 
+        // Later calls to `clap_host_params::rescan()` are checked against this
+        *self.plugin.state.known_params.lock() = Some(
+            result
+                .iter()
+                .map(|(param_id, param)| (*param_id, param.into()))
+                .collect(),
+        );
+
         Ok(result)
     }
 
@@ -348,6 +423,18 @@ impl Params<'_> {
     }
 }
 
+impl From<&Param> for KnownParam {
+    fn from(param: &Param) -> Self {
+        KnownParam {
+            name: param.name.clone(),
+            module: param.module.clone(),
+            range: param.range.clone(),
+            cookie: param.cookie as usize,
+            flags: param.flags,
+        }
+    }
+}
+
 impl Param {
     /// Whether the parameter is hidden and should be ignored.
     pub fn hidden(&self) -> bool {
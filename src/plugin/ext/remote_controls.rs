@@ -0,0 +1,96 @@
+//! Abstractions for interacting with the `remote-controls` draft extension.
+
+use anyhow::{Context, Result};
+use clap_sys::ext::draft::remote_controls::{
+    clap_plugin_remote_controls, clap_remote_controls_page, CLAP_EXT_REMOTE_CONTROLS,
+    CLAP_REMOTE_CONTROLS_COUNT,
+};
+use clap_sys::id::{clap_id, CLAP_INVALID_ID};
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use super::Extension;
+use crate::plugin::instance::Plugin;
+use crate::util::{self, unsafe_clap_call};
+
+/// Abstraction for the `remote-controls` extension covering the main thread functionality.
+#[derive(Debug)]
+pub struct RemoteControls<'a> {
+    plugin: &'a Plugin<'a>,
+    remote_controls: NonNull<clap_plugin_remote_controls>,
+}
+
+/// A page of remote controls, parsed from a `clap_remote_controls_page`.
+#[derive(Debug, Clone)]
+pub struct RemoteControlsPage {
+    pub section_name: String,
+    pub page_id: clap_id,
+    pub page_name: String,
+    /// The parameters mapped to the page's controls. Unmapped controls are `None`.
+    pub param_ids: [Option<clap_id>; CLAP_REMOTE_CONTROLS_COUNT],
+    /// Whether the page is specific to the currently loaded preset.
+    pub is_for_preset: bool,
+}
+
+impl<'a> Extension<&'a Plugin<'a>> for RemoteControls<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_REMOTE_CONTROLS;
+
+    type Struct = clap_plugin_remote_controls;
+
+    fn new(plugin: &'a Plugin<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            remote_controls: extension_struct,
+        }
+    }
+}
+
+impl RemoteControls<'_> {
+    /// Get all of the plugin's remote controls pages. Returns an error if the plugin fails to
+    /// return a page, if a page's section or page name is not a valid null-terminated UTF-8
+    /// string, or if multiple pages have the same page ID.
+    pub fn pages(&self) -> Result<Vec<RemoteControlsPage>> {
+        let remote_controls = self.remote_controls.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        let num_pages = unsafe_clap_call! { remote_controls=>count(plugin) };
+
+        let mut pages = Vec::with_capacity(num_pages as usize);
+        let mut page_ids = HashSet::new();
+        for i in 0..num_pages {
+            let mut page: clap_remote_controls_page = unsafe { std::mem::zeroed() };
+            let success = unsafe_clap_call! { remote_controls=>get(plugin, i, &mut page) };
+            if !success {
+                anyhow::bail!(
+                    "Plugin returned an error when querying remote controls page {i} ({num_pages} \
+                     total pages)."
+                );
+            }
+
+            let section_name =
+                util::c_char_slice_to_string(&page.section_name).with_context(|| {
+                    format!("Could not read the section name for remote controls page {i}")
+                })?;
+            let page_name = util::c_char_slice_to_string(&page.page_name)
+                .with_context(|| format!("Could not read the name for remote controls page {i}"))?;
+            if !page_ids.insert(page.page_id) {
+                anyhow::bail!(
+                    "The plugin contains multiple remote controls pages with page ID {}.",
+                    page.page_id
+                );
+            }
+
+            pages.push(RemoteControlsPage {
+                section_name,
+                page_id: page.page_id,
+                page_name,
+                param_ids: page
+                    .param_ids
+                    .map(|param_id| (param_id != CLAP_INVALID_ID).then_some(param_id)),
+                is_for_preset: page.is_for_preset,
+            });
+        }
+
+        Ok(pages)
+    }
+}
@@ -0,0 +1,47 @@
+//! Abstractions for interacting with the `render` extension.
+
+use clap_sys::ext::render::{clap_plugin_render, clap_plugin_render_mode, CLAP_EXT_RENDER};
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use super::Extension;
+use crate::plugin::instance::Plugin;
+use crate::util::unsafe_clap_call;
+
+/// Abstraction for the `render` extension covering the main thread functionality.
+#[derive(Debug)]
+pub struct Render<'a> {
+    plugin: &'a Plugin<'a>,
+    render: NonNull<clap_plugin_render>,
+}
+
+impl<'a> Extension<&'a Plugin<'a>> for Render<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_RENDER;
+
+    type Struct = clap_plugin_render;
+
+    fn new(plugin: &'a Plugin<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            render: extension_struct,
+        }
+    }
+}
+
+impl Render<'_> {
+    /// Returns whether the plugin has a hard requirement to process in realtime, for instance
+    /// because it acts as a proxy for a hardware device.
+    pub fn has_hard_realtime_requirement(&self) -> bool {
+        let render = self.render.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { render=>has_hard_realtime_requirement(plugin) }
+    }
+
+    /// Set the plugin's rendering mode. This should only be called while the plugin is
+    /// deactivated. Returns whether the plugin accepted the rendering mode.
+    pub fn set(&self, mode: clap_plugin_render_mode) -> bool {
+        let render = self.render.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { render=>set(plugin, mode) }
+    }
+}
@@ -0,0 +1,74 @@
+//! Abstractions for interacting with the `tail` extension.
+
+use clap_sys::ext::tail::{clap_plugin_tail, CLAP_EXT_TAIL};
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use crate::plugin::instance::audio_thread::PluginAudioThread;
+use crate::plugin::instance::Plugin;
+use crate::util::unsafe_clap_call;
+
+use super::Extension;
+
+/// Tail lengths greater than or equal to this value mean that the plugin has an infinite tail.
+pub const INFINITE_TAIL: u32 = i32::MAX as u32;
+
+/// Abstraction for the `tail` extension covering the main thread functionality.
+#[derive(Debug)]
+pub struct Tail<'a> {
+    plugin: &'a Plugin<'a>,
+    tail: NonNull<clap_plugin_tail>,
+}
+
+/// Abstraction for the `tail` extension covering the audio thread functionality.
+#[derive(Debug)]
+pub struct TailAudioThread<'a> {
+    plugin: &'a PluginAudioThread<'a>,
+    tail: NonNull<clap_plugin_tail>,
+}
+
+impl<'a> Extension<&'a Plugin<'a>> for Tail<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_TAIL;
+
+    type Struct = clap_plugin_tail;
+
+    fn new(plugin: &'a Plugin<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            tail: extension_struct,
+        }
+    }
+}
+
+impl<'a> Extension<&'a PluginAudioThread<'a>> for TailAudioThread<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_TAIL;
+
+    type Struct = clap_plugin_tail;
+
+    fn new(plugin: &'a PluginAudioThread<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            tail: extension_struct,
+        }
+    }
+}
+
+impl Tail<'_> {
+    /// Get the plugin's tail length in samples. Values greater than or equal to [`INFINITE_TAIL`]
+    /// mean that the tail is infinite.
+    pub fn get(&self) -> u32 {
+        let tail = self.tail.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { tail=>get(plugin) }
+    }
+}
+
+impl TailAudioThread<'_> {
+    /// Get the plugin's tail length in samples. Values greater than or equal to [`INFINITE_TAIL`]
+    /// mean that the tail is infinite.
+    pub fn get(&self) -> u32 {
+        let tail = self.tail.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { tail=>get(plugin) }
+    }
+}
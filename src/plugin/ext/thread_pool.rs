@@ -0,0 +1,25 @@
+//! Abstractions for interacting with the `thread-pool` extension.
+
+use clap_sys::ext::thread_pool::{clap_plugin_thread_pool, CLAP_EXT_THREAD_POOL};
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use super::Extension;
+use crate::plugin::instance::Plugin;
+
+/// Abstraction for the `thread-pool` extension. The extension's only function is called from the
+/// host's thread pool while the plugin's audio thread is blocked in a
+/// `clap_host_thread_pool::request_exec()` call, so this is only used to check whether the plugin
+/// supports the extension. See [`Host`][crate::plugin::host::Host] for the host's side.
+#[derive(Debug)]
+pub struct ThreadPool;
+
+impl<'a> Extension<&'a Plugin<'a>> for ThreadPool {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_THREAD_POOL;
+
+    type Struct = clap_plugin_thread_pool;
+
+    fn new(_plugin: &'a Plugin<'a>, _extension_struct: NonNull<Self::Struct>) -> Self {
+        Self
+    }
+}
@@ -0,0 +1,26 @@
+//! Abstractions for interacting with the `timer-support` extension.
+
+use clap_sys::ext::timer_support::{clap_plugin_timer_support, CLAP_EXT_TIMER_SUPPORT};
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use super::Extension;
+use crate::plugin::instance::Plugin;
+
+/// Abstraction for the `timer-support` extension. The extension's only function is called by the
+/// host's timer dispatcher in [`Host::handle_callbacks_blocking()`], so this is only used to check
+/// whether the plugin supports the extension.
+///
+/// [`Host::handle_callbacks_blocking()`]: crate::plugin::host::Host::handle_callbacks_blocking()
+#[derive(Debug)]
+pub struct TimerSupport;
+
+impl<'a> Extension<&'a Plugin<'a>> for TimerSupport {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_TIMER_SUPPORT;
+
+    type Struct = clap_plugin_timer_support;
+
+    fn new(_plugin: &'a Plugin<'a>, _extension_struct: NonNull<Self::Struct>) -> Self {
+        Self
+    }
+}
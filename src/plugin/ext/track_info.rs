@@ -0,0 +1,39 @@
+//! Abstractions for interacting with the `track-info` draft extension.
+
+use clap_sys::ext::draft::track_info::{clap_plugin_track_info, CLAP_EXT_TRACK_INFO};
+use std::ffi::CStr;
+use std::ptr::NonNull;
+
+use super::Extension;
+use crate::plugin::instance::Plugin;
+use crate::util::unsafe_clap_call;
+
+/// Abstraction for the `track-info` extension covering the main thread functionality.
+#[derive(Debug)]
+pub struct TrackInfo<'a> {
+    plugin: &'a Plugin<'a>,
+    track_info: NonNull<clap_plugin_track_info>,
+}
+
+impl<'a> Extension<&'a Plugin<'a>> for TrackInfo<'a> {
+    const EXTENSION_ID: &'static CStr = CLAP_EXT_TRACK_INFO;
+
+    type Struct = clap_plugin_track_info;
+
+    fn new(plugin: &'a Plugin<'a>, extension_struct: NonNull<Self::Struct>) -> Self {
+        Self {
+            plugin,
+            track_info: extension_struct,
+        }
+    }
+}
+
+impl TrackInfo<'_> {
+    /// Notify the plugin that the information returned by `clap_host_track_info::get()` has
+    /// changed. The plugin is expected to query the new information from the host.
+    pub fn changed(&self) {
+        let track_info = self.track_info.as_ptr();
+        let plugin = self.plugin.as_ptr();
+        unsafe_clap_call! { track_info=>changed(plugin) }
+    }
+}
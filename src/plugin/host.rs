@@ -1,49 +1,141 @@
 //! Data structures and utilities for hosting plugins.
 
 use anyhow::{Context, Result};
-use clap_sys::ext::audio_ports::{clap_host_audio_ports, CLAP_EXT_AUDIO_PORTS};
+use clap_sys::color::clap_color;
+use clap_sys::ext::audio_ports::{
+    clap_host_audio_ports, CLAP_AUDIO_PORTS_RESCAN_CHANNEL_COUNT, CLAP_AUDIO_PORTS_RESCAN_FLAGS,
+    CLAP_AUDIO_PORTS_RESCAN_IN_PLACE_PAIR, CLAP_AUDIO_PORTS_RESCAN_LIST,
+    CLAP_AUDIO_PORTS_RESCAN_PORT_TYPE, CLAP_EXT_AUDIO_PORTS, CLAP_PORT_STEREO,
+};
 use clap_sys::ext::draft::preset_load::{clap_host_preset_load, CLAP_EXT_PRESET_LOAD};
+use clap_sys::ext::draft::track_info::{
+    clap_host_track_info, clap_track_info, CLAP_EXT_TRACK_INFO, CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL,
+    CLAP_TRACK_INFO_HAS_TRACK_COLOR, CLAP_TRACK_INFO_HAS_TRACK_NAME,
+};
+use clap_sys::ext::event_registry::{clap_host_event_registry, CLAP_EXT_EVENT_REGISTRY};
+use clap_sys::ext::log::{
+    clap_host_log, clap_log_severity, CLAP_EXT_LOG, CLAP_LOG_DEBUG, CLAP_LOG_ERROR, CLAP_LOG_FATAL,
+    CLAP_LOG_HOST_MISBEHAVING, CLAP_LOG_INFO, CLAP_LOG_PLUGIN_MISBEHAVING, CLAP_LOG_WARNING,
+};
 use clap_sys::ext::note_ports::{
     clap_host_note_ports, clap_note_dialect, CLAP_EXT_NOTE_PORTS, CLAP_NOTE_DIALECT_CLAP,
-    CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI_MPE,
+    CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI2, CLAP_NOTE_DIALECT_MIDI_MPE,
+    CLAP_NOTE_PORTS_RESCAN_ALL,
 };
 use clap_sys::ext::params::{
-    clap_host_params, clap_param_clear_flags, clap_param_rescan_flags, CLAP_EXT_PARAMS,
+    clap_host_params, clap_param_clear_flags, clap_param_info, clap_param_info_flags,
+    clap_param_rescan_flags, clap_plugin_params, CLAP_EXT_PARAMS, CLAP_PARAM_IS_BYPASS,
+    CLAP_PARAM_IS_HIDDEN, CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_MODULATABLE_PER_CHANNEL,
+    CLAP_PARAM_IS_MODULATABLE_PER_KEY, CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID,
+    CLAP_PARAM_IS_MODULATABLE_PER_PORT, CLAP_PARAM_IS_PERIODIC, CLAP_PARAM_IS_READONLY,
+    CLAP_PARAM_IS_STEPPED, CLAP_PARAM_RESCAN_ALL, CLAP_PARAM_RESCAN_INFO,
+};
+use clap_sys::ext::posix_fd_support::{
+    clap_host_posix_fd_support, clap_posix_fd_flags, CLAP_EXT_POSIX_FD_SUPPORT,
+    CLAP_POSIX_FD_ERROR, CLAP_POSIX_FD_READ, CLAP_POSIX_FD_WRITE,
 };
 use clap_sys::ext::state::{clap_host_state, CLAP_EXT_STATE};
 use clap_sys::ext::thread_check::{clap_host_thread_check, CLAP_EXT_THREAD_CHECK};
+use clap_sys::ext::thread_pool::{
+    clap_host_thread_pool, clap_plugin_thread_pool, CLAP_EXT_THREAD_POOL,
+};
+use clap_sys::ext::timer_support::{
+    clap_host_timer_support, clap_plugin_timer_support, CLAP_EXT_TIMER_SUPPORT,
+};
 use clap_sys::factory::draft::preset_discovery::clap_preset_discovery_location_kind;
 use clap_sys::host::clap_host;
 use clap_sys::id::clap_id;
 use clap_sys::plugin::clap_plugin;
+use clap_sys::string_sizes::CLAP_NAME_SIZE;
 use clap_sys::version::CLAP_VERSION;
 use crossbeam::atomic::AtomicCell;
 use crossbeam::channel;
 use parking_lot::Mutex;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ffi::{c_void, CStr, CString};
+use std::fmt::Display;
+use std::ops::RangeInclusive;
 use std::os::raw::c_char;
 use std::pin::Pin;
 use std::rc::Rc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
+use crate::plugin::ext::context_menu::ffi::{
+    clap_context_menu_builder, clap_context_menu_entry, clap_context_menu_target,
+    clap_host_context_menu, CLAP_CONTEXT_MENU_ITEM_ENTRY, CLAP_EXT_CONTEXT_MENU,
+};
 use crate::plugin::instance::{PluginHandle, PluginStatus};
 use crate::plugin::preset_discovery::LocationValue;
-use crate::util::{self, check_null_ptr, unsafe_clap_call};
+use crate::util::{self, unsafe_clap_call};
+
+/// The boilerplate every `clap_host` callback is wrapped in. This asserts that the `clap_host`
+/// pointer, its `host_data` field, and the pointer arguments in the optional list are non-null,
+/// recovers the [`InstanceState`] and the [`Host`] from the pointer, and checks that the plugin
+/// instance has been created. The third argument selects the thread the function may be called
+/// from: `main_thread`, `audio_thread`, `not_audio_thread`, or `any_thread`. The body is then run
+/// with the instance and the host bound to the closure-like argument's patterns. Panics, including
+/// those from the null pointer checks, are caught using [`util::catch_callback_panic()`] and
+/// recorded as callback errors so they don't unwind into the plugin.
+///
+/// ```ignore
+/// host_callback!(host, "clap_host_params::rescan()", main_thread, |instance, this| {
+///     // ...
+/// })
+/// ```
+macro_rules! host_callback {
+    (
+        $host:ident,
+        $function_name:literal,
+        $thread:ident,
+        $([$($ptrs:expr),*],)?
+        |$instance:pat_param, $this:pat_param| $body:block $(,)?
+    ) => {
+        $crate::util::catch_callback_panic(
+            $function_name,
+            || {
+                $crate::util::check_null_ptr!($host, (*$host).host_data $($(, $ptrs)*)?);
+                let (instance, this) = InstanceState::from_clap_host_ptr($host);
+
+                this.assert_instance_created(instance, $function_name);
+                this.record_host_extension_call($function_name);
+                host_callback!(@thread this, $function_name, $thread);
+
+                let ($instance, $this) = (instance, this);
+                $body
+            },
+            |message| Host::record_callback_panic($host, message),
+        )
+    };
+    (@thread $this:ident, $function_name:literal, main_thread) => {
+        $this.assert_main_thread($function_name)
+    };
+    (@thread $this:ident, $function_name:literal, audio_thread) => {
+        $this.assert_audio_thread($function_name)
+    };
+    (@thread $this:ident, $function_name:literal, not_audio_thread) => {
+        $this.assert_not_audio_thread($function_name)
+    };
+    (@thread $this:ident, $function_name:literal, any_thread) => {};
+}
 
 /// An abstraction for a CLAP plugin host.
 ///
 /// - It handles callback requests made by the plugin, and it checks whether the calling thread
-///   matches up when any of its functions are called by the plugin. A `Result` indicating the first
-///   failure, of any, can be retrieved by calling the
+///   matches up when any of its functions are called by the plugin. A `Result` containing all
+///   distinct failures, if any, can be retrieved by calling the
 ///   [`callback_error_check()`][Self::callback_error_check()] method.
 /// - In order for those calblacks to be handled correctly every CLAP function call where the plugin
 ///   potentially requests a main thread callback [`Host::handle_callbacks_once()`] needs to be
 ///   called. Alternatively [`Host::handle_callbacks_blocking()`] can be called on the main thread
-///   while other audio threads are doing their thing.
+///   while other audio threads are doing their thing, and
+///   [`Host::handle_callbacks_until_idle()`] can be used after main thread function calls to also
+///   handle callbacks the plugin requests from its own background threads.
 /// - Multiple plugins can share this host instance. Because of that, we can't just cast the `*const
 ///   clap_host` directly to a `*const Host`, as that would make it impossible to figure out which
 ///   `*const clap_host` belongs to which plugin instance. Instead, every registered plugin instance
@@ -53,15 +145,46 @@ use crate::util::{self, check_null_ptr, unsafe_clap_call};
 pub struct Host {
     /// The ID of the main thread.
     main_thread_id: ThreadId,
-    /// A description of the first error encountered during a callback by this `Host`, if any. This
-    /// is primarily used to check that the plugin called all host callbacks from the correct thread
-    /// after the rest of the test has succeeded.
-    callback_error: RefCell<Option<String>>,
+    /// Determines how the host's extensions respond to the plugins hosted by this host.
+    config: HostConfig,
+    /// The distinct errors encountered during callbacks by this `Host`, if any. This is primarily
+    /// used to check that the plugin called all host callbacks from the correct thread after the
+    /// rest of the test has succeeded. This stores at most [`MAX_CALLBACK_ERRORS`] errors. This
+    /// needs to be a mutex since the thread pool's worker threads may record errors at the same
+    /// time.
+    callback_errors: Mutex<Vec<CallbackError>>,
+    /// The keys of the distinct errors that did not fit in `callback_errors`. Only the number of
+    /// these errors is reported.
+    omitted_callback_error_keys: Mutex<HashSet<String>>,
+    /// Every distinct thread safety issue encountered while hosting plugins, regardless of whether
+    /// the test checks for callback errors. These are moved to [`THREAD_SAFETY_AUDIT`] when the
+    /// host is dropped so they can be added to the test's result. Issues that have already been
+    /// reported through [`callback_error_check()`][Self::callback_error_check()] are removed from
+    /// this list so they're not reported twice. These use the same keys as `callback_errors`.
+    thread_safety_audit: Mutex<Vec<CallbackError>>,
+    /// The messages the plugin logged using `clap_host_log::log()`. These are moved to
+    /// [`PLUGIN_LOG`] when the host is dropped so they can be added to the test's result. This
+    /// stores at most [`MAX_PLUGIN_LOG_MESSAGES`] messages.
+    plugin_log: Mutex<Vec<LogMessage>>,
+    /// The number of messages that did not fit in `plugin_log`.
+    num_omitted_log_messages: AtomicUsize,
+    /// The host extensions the plugins queried and used. This is moved to
+    /// [`HOST_EXTENSION_USAGE`] when the host is dropped so it can be added to the test's result.
+    host_extension_usage: Mutex<HostExtensionUsage>,
 
     /// These are the plugin instances taht were registered on this host. They're added here when
     /// the `Plugin` object is created, and they're removed when the object is dropped. This is used
     /// to keep track of audio threads and pending callbacks.
     instances: RefCell<HashMap<PluginHandle, Pin<Arc<InstanceState>>>>,
+    /// The worker pool used to run the tasks requested through
+    /// `clap_host_thread_pool::request_exec()`. This is created on the main thread when a plugin
+    /// implementing the thread pool extension gets activated, since spawning the worker threads
+    /// from the audio thread would not be realtime-safe.
+    thread_pool: Mutex<Option<Arc<rayon::ThreadPool>>>,
+    /// The IDs of the thread pool's worker threads. These count as audio threads for the thread
+    /// safety checks, since the plugin's `clap_plugin_thread_pool::exec()` function is called from
+    /// them while the audio thread waits for the tasks to finish.
+    thread_pool_workers: Arc<Mutex<Vec<ThreadId>>>,
 
     /// Allows waking up the main thread for callbacks while running
     /// [`handle_callbacks_blocking()`][Self::handle_callbacks_blocking()]. Other threads can also
@@ -73,11 +196,266 @@ pub struct Host {
 
     // These are the vtables for the extensions supported by the host
     clap_host_audio_ports: clap_host_audio_ports,
+    clap_host_context_menu: clap_host_context_menu,
+    clap_host_event_registry: clap_host_event_registry,
+    clap_host_log: clap_host_log,
     clap_host_note_ports: clap_host_note_ports,
     clap_host_params: clap_host_params,
+    clap_host_posix_fd_support: clap_host_posix_fd_support,
     clap_host_preset_load: clap_host_preset_load,
     clap_host_state: clap_host_state,
     clap_host_thread_check: clap_host_thread_check,
+    clap_host_thread_pool: clap_host_thread_pool,
+    clap_host_timer_support: clap_host_timer_support,
+    clap_host_track_info: clap_host_track_info,
+}
+
+/// Configures the behavior of the host's extensions. This allows tests to check how a plugin
+/// adapts to hosts with differing capabilities. [`HostConfig::default()`] describes a host that
+/// supports everything.
+#[derive(Debug, Clone)]
+pub struct HostConfig {
+    /// The note dialects returned from `clap_host_note_ports::supported_dialects()`.
+    pub supported_note_dialects: clap_note_dialect,
+    /// The `CLAP_AUDIO_PORTS_RESCAN_*` flags `clap_host_audio_ports::is_rescan_flag_supported()`
+    /// returns `true` for.
+    pub supported_audio_ports_rescan_flags: u32,
+    /// Whether `clap_host_event_registry::query()` registers the event spaces the plugin asks for.
+    /// If this is `false`, then the host declines every event space.
+    pub register_event_spaces: bool,
+    /// The name, vendor, and version strings reported through the `clap_host` struct.
+    pub host_info: HostInfo,
+    /// If this is set, then the host extensions' notification and logging functions are null
+    /// pointers. The extension structs are still returned from `clap_host::get_extension()`. The
+    /// affected functions are listed in [`Host::new()`]. Plugins should check these pointers
+    /// before calling them.
+    pub null_optional_functions: bool,
+}
+
+/// The strings the host identifies itself with in the `clap_host` struct. Some plugins enable
+/// workarounds for specific hosts based on these. [`HostInfo::default()`] uses clap-validator's own
+/// name, vendor, and version unless they were overridden with the `--host-name`, `--host-vendor`,
+/// or `--host-version` options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostInfo {
+    pub name: String,
+    pub vendor: String,
+    pub version: String,
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        Self {
+            supported_note_dialects: CLAP_NOTE_DIALECT_CLAP
+                | CLAP_NOTE_DIALECT_MIDI
+                | CLAP_NOTE_DIALECT_MIDI_MPE
+                | CLAP_NOTE_DIALECT_MIDI2,
+            supported_audio_ports_rescan_flags: u32::MAX,
+            register_event_spaces: true,
+            host_info: HostInfo::default(),
+            null_optional_functions: false,
+        }
+    }
+}
+
+impl Default for HostInfo {
+    fn default() -> Self {
+        let overrides = util::host_info_overrides();
+
+        Self {
+            name: overrides
+                .name
+                .unwrap_or_else(|| String::from("clap-validator")),
+            vendor: overrides
+                .vendor
+                .unwrap_or_else(|| String::from("Robbert van der Helm")),
+            version: overrides
+                .version
+                .unwrap_or_else(|| String::from(env!("CARGO_PKG_VERSION"))),
+        }
+    }
+}
+
+/// The maximum number of distinct callback errors stored on a [`Host`]. Plugins often have several
+/// independent threading bugs, so these are all reported at once, but a misbehaving plugin
+/// shouldn't be able to flood the test's output.
+const MAX_CALLBACK_ERRORS: usize = 16;
+
+/// The `CLAP_AUDIO_PORTS_RESCAN_*` flags that may only be passed to
+/// `clap_host_audio_ports::rescan()` while the plugin is deactivated. Only port name changes may be
+/// communicated while the plugin is activated.
+const AUDIO_PORTS_RESCAN_DEACTIVATED_FLAGS: u32 = CLAP_AUDIO_PORTS_RESCAN_FLAGS
+    | CLAP_AUDIO_PORTS_RESCAN_CHANNEL_COUNT
+    | CLAP_AUDIO_PORTS_RESCAN_PORT_TYPE
+    | CLAP_AUDIO_PORTS_RESCAN_IN_PLACE_PAIR
+    | CLAP_AUDIO_PORTS_RESCAN_LIST;
+
+/// The ID handed out for the first event space registered through
+/// `clap_host_event_registry::query()`. Subsequent spaces get consecutive IDs. This is far away
+/// from `CLAP_CORE_EVENT_SPACE_ID` so plugins that assume a specific ID are caught.
+const FIRST_EVENT_SPACE_ID: u16 = 0x4000;
+
+/// The maximum number of log messages stored on a [`Host`]. Plugins may log from the audio thread
+/// during every processing cycle, and those messages shouldn't flood the test's output.
+const MAX_PLUGIN_LOG_MESSAGES: usize = 64;
+
+/// The shortest period in milliseconds the host will use for timers registered through
+/// `clap_host_timer_support::register_timer()`. Hosts are allowed to adjust the period, and this
+/// prevents a period of zero from turning the timer dispatcher into a busy loop.
+const MIN_TIMER_PERIOD_MS: u32 = 10;
+
+/// The maximum amount of time [`Host::handle_callbacks_until_idle()`] spends handling callbacks.
+/// If the plugin still has a pending callback request after this, then it's treated as an error.
+const CALLBACK_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+/// The name of `clap_plugin::on_main_thread()` as stored in
+/// [`InstanceState::active_plugin_callback`].
+const ON_MAIN_THREAD_CALLBACK: &str = "clap_plugin::on_main_thread()";
+/// The name of `clap_plugin_timer_support::on_timer()` as stored in
+/// [`InstanceState::active_plugin_callback`].
+const ON_TIMER_CALLBACK: &str = "clap_plugin_timer_support::on_timer()";
+
+/// The track name returned from `clap_host_track_info::get()`.
+const TRACK_INFO_NAME: &str = "clap-validator track";
+/// The track color returned from `clap_host_track_info::get()`.
+const TRACK_INFO_COLOR: clap_color = clap_color {
+    alpha: 0xff,
+    red: 0x3d,
+    green: 0x8e,
+    blue: 0xc9,
+};
+/// The label of the entry the host adds to the plugin's context menus in
+/// `clap_host_context_menu::populate()`.
+const HOST_CONTEXT_MENU_LABEL: &CStr =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"clap-validator\0") };
+/// The action ID of the entry the host adds to the plugin's context menus in
+/// `clap_host_context_menu::populate()`.
+const HOST_CONTEXT_MENU_ACTION_ID: clap_id = 0;
+/// The host extensions exposed through `clap_host::get_extension()`, along with the names of their
+/// vtable structs and functions that return the host's vtable for that extension. Host callbacks
+/// are named after these structs, which is used to figure out which extension a callback belongs
+/// to.
+const HOST_EXTENSIONS: [(&CStr, &str, fn(&Host) -> *const c_void); 13] = [
+    (CLAP_EXT_AUDIO_PORTS, "clap_host_audio_ports", |host| {
+        vtable_ptr(&host.clap_host_audio_ports)
+    }),
+    (CLAP_EXT_CONTEXT_MENU, "clap_host_context_menu", |host| {
+        vtable_ptr(&host.clap_host_context_menu)
+    }),
+    (
+        CLAP_EXT_EVENT_REGISTRY,
+        "clap_host_event_registry",
+        |host| vtable_ptr(&host.clap_host_event_registry),
+    ),
+    (CLAP_EXT_LOG, "clap_host_log", |host| {
+        vtable_ptr(&host.clap_host_log)
+    }),
+    (CLAP_EXT_NOTE_PORTS, "clap_host_note_ports", |host| {
+        vtable_ptr(&host.clap_host_note_ports)
+    }),
+    (CLAP_EXT_PRESET_LOAD, "clap_host_preset_load", |host| {
+        vtable_ptr(&host.clap_host_preset_load)
+    }),
+    (CLAP_EXT_PARAMS, "clap_host_params", |host| {
+        vtable_ptr(&host.clap_host_params)
+    }),
+    (
+        CLAP_EXT_POSIX_FD_SUPPORT,
+        "clap_host_posix_fd_support",
+        |host| vtable_ptr(&host.clap_host_posix_fd_support),
+    ),
+    (CLAP_EXT_STATE, "clap_host_state", |host| {
+        vtable_ptr(&host.clap_host_state)
+    }),
+    (CLAP_EXT_THREAD_CHECK, "clap_host_thread_check", |host| {
+        vtable_ptr(&host.clap_host_thread_check)
+    }),
+    (CLAP_EXT_THREAD_POOL, "clap_host_thread_pool", |host| {
+        vtable_ptr(&host.clap_host_thread_pool)
+    }),
+    (CLAP_EXT_TIMER_SUPPORT, "clap_host_timer_support", |host| {
+        vtable_ptr(&host.clap_host_timer_support)
+    }),
+    (CLAP_EXT_TRACK_INFO, "clap_host_track_info", |host| {
+        vtable_ptr(&host.clap_host_track_info)
+    }),
+];
+
+/// The host extensions a plugin queried and used while running one or more tests. This is recorded
+/// for every test so the validation report can summarize which host extensions a plugin needs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HostExtensionUsage {
+    /// The IDs of the extensions the plugin queried through `clap_host::get_extension()`. This
+    /// includes extensions the host does not implement.
+    pub queried: BTreeSet<String>,
+    /// The IDs of the extensions whose functions the plugin actually called.
+    pub used: BTreeSet<String>,
+}
+
+impl HostExtensionUsage {
+    /// Returns `true` if the plugin did not query or use any host extensions.
+    pub fn is_empty(&self) -> bool {
+        self.queried.is_empty() && self.used.is_empty()
+    }
+
+    /// Add the extensions queried and used in `other` to this object.
+    pub fn extend(&mut self, other: &HostExtensionUsage) {
+        self.queried.extend(other.queried.iter().cloned());
+        self.used.extend(other.used.iter().cloned());
+    }
+}
+
+/// A message the plugin logged through `clap_host_log::log()`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LogMessage {
+    pub severity: LogSeverity,
+    pub message: String,
+}
+
+/// The severity of a [`LogMessage`]. These correspond to the `CLAP_LOG_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, strum_macros::Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum LogSeverity {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+    HostMisbehaving,
+    PluginMisbehaving,
+}
+
+impl LogSeverity {
+    /// Convert a `clap_log_severity` to a `LogSeverity`. Returns `None` if the value is not one of
+    /// the severities defined by the CLAP specification.
+    pub fn from_clap(severity: clap_log_severity) -> Option<Self> {
+        match severity {
+            CLAP_LOG_DEBUG => Some(LogSeverity::Debug),
+            CLAP_LOG_INFO => Some(LogSeverity::Info),
+            CLAP_LOG_WARNING => Some(LogSeverity::Warning),
+            CLAP_LOG_ERROR => Some(LogSeverity::Error),
+            CLAP_LOG_FATAL => Some(LogSeverity::Fatal),
+            CLAP_LOG_HOST_MISBEHAVING => Some(LogSeverity::HostMisbehaving),
+            CLAP_LOG_PLUGIN_MISBEHAVING => Some(LogSeverity::PluginMisbehaving),
+            _ => None,
+        }
+    }
+}
+
+impl Display for LogMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.severity, self.message)
+    }
+}
+
+/// An error encountered during a host callback.
+#[derive(Debug)]
+struct CallbackError {
+    /// Errors with the same key are only recorded once. For thread safety errors this is the name
+    /// of the offending function so the same threading bug isn't reported once for every call, and
+    /// for other errors this is the error message itself.
+    key: String,
+    message: String,
 }
 
 /// Runtime information about a plugin instance. This keeps track of pending callbacks and things
@@ -96,9 +474,11 @@ pub struct InstanceState {
     /// instance from a `*const clap_host`, which we can cast to this struct to access the pointer.
     host: Rc<Host>,
 
-    /// The `clap-validator` version. Read at compile time, but it has to be stored here since it
-    /// needs to be null terminated.
-    _clap_validator_version: CString,
+    /// The host's name, vendor, and version from [`HostConfig::host_info`]. These need to be
+    /// stored here since the `clap_host` struct points to them.
+    _host_name: CString,
+    _host_vendor: CString,
+    _host_version: CString,
     /// The vtable that's passed to the plugin. The `host_data` field is populated with a pointer to
     /// this object.
     clap_host: Mutex<clap_host>,
@@ -108,9 +488,21 @@ pub struct InstanceState {
 
     /// The plugin instance's audio thread, if it has one. Used for the audio thread checks.
     pub audio_thread: AtomicCell<Option<ThreadId>>,
+    /// Whether the most recently spawned audio thread managed to enable realtime scheduling. This
+    /// is only attempted when the validator's `--realtime-audio-thread` option is enabled.
+    pub realtime_audio_thread: AtomicBool,
     /// Whether the plugin has called `clap_host::request_callback()` and expects
     /// `clap_plugin::on_main_thread()` to be called on the main thread.
     pub requested_callback: AtomicBool,
+    /// Whether the plugin's most recent call to `clap_host::request_callback()` was made from
+    /// within `clap_plugin::on_main_thread()`. This is how [`Host::handle_callbacks_once()`]
+    /// detects plugins that keep requesting callbacks recursively.
+    pub requested_callback_recursively: AtomicBool,
+    /// The plugin callback the host is currently running on the main thread in response to a
+    /// callback request or a timer, if any. Used to detect callback requests made from within
+    /// `clap_plugin::on_main_thread()`, and to report host functions the plugin calls from within
+    /// these callbacks.
+    pub active_plugin_callback: AtomicCell<Option<&'static str>>,
     /// Whether the plugin has called `clap_host::request_restart()` and expects the plugin to be
     /// deactivated and subsequently reactivated.
     ///
@@ -119,6 +511,306 @@ pub struct InstanceState {
     /// [`ProcessingTest::run`][crate::testa::plugin::processing::ProcessingTest::run] function to
     /// deactivate and reactivate.
     pub requested_restart: AtomicBool,
+    /// The number of times the plugin has called `clap_host_thread_pool::request_exec()`.
+    pub num_thread_pool_requests: AtomicUsize,
+    /// The total number of tasks the plugin has requested through
+    /// `clap_host_thread_pool::request_exec()`.
+    pub num_thread_pool_tasks: AtomicUsize,
+    /// The names of the event spaces registered for this instance through
+    /// `clap_host_event_registry::query()`. A space's ID is [`FIRST_EVENT_SPACE_ID`] plus its index
+    /// in this list.
+    pub event_spaces: Mutex<Vec<String>>,
+    /// The number of times the plugin has called `clap_host_event_registry::query()`, including
+    /// the queries the host declined.
+    pub num_event_space_queries: AtomicUsize,
+    /// The file descriptors the plugin has registered through
+    /// `clap_host_posix_fd_support::register_fd()`, along with their flags. The host doesn't poll
+    /// these, this is only used to check that the plugin uses the extension correctly.
+    pub posix_fds: Mutex<HashMap<i32, clap_posix_fd_flags>>,
+    /// The timers the plugin has registered through `clap_host_timer_support::register_timer()`,
+    /// indexed by their timer IDs. These are only dispatched while the host is handling callbacks
+    /// using [`Host::handle_callbacks_blocking()`].
+    pub timers: Mutex<HashMap<clap_id, Timer>>,
+    /// The ID the next timer registered by this instance will get. This doubles as the number of
+    /// timers the plugin has registered.
+    pub next_timer_id: AtomicU32,
+    /// The number of times the host has called `clap_plugin_timer_support::on_timer()` for this
+    /// instance.
+    pub num_timer_ticks: AtomicUsize,
+    /// The locations and load keys of the presets the plugin has reported as loaded through
+    /// `clap_host_preset_load::loaded()`.
+    pub loaded_presets: Mutex<Vec<(LocationValue, Option<String>)>>,
+    /// The number of times the plugin has called `clap_host_track_info::get()`.
+    pub num_track_info_queries: AtomicUsize,
+    /// The plugin's parameters as the host last saw them, indexed by their stable IDs. This is set
+    /// by [`Params::info()`][crate::plugin::ext::params::Params::info()] and by
+    /// `clap_host_params::rescan()`, and it is used to check whether the changes reported through a
+    /// rescan are allowed by the rescan's flags.
+    pub known_params: Mutex<Option<BTreeMap<clap_id, KnownParam>>>,
+    /// Every call to `clap_host_params::rescan()`, in order.
+    pub param_rescans: Mutex<Vec<ParamRescan>>,
+    /// The number of times the plugin has called `clap_host_audio_ports::rescan()`.
+    pub num_audio_ports_rescans: AtomicUsize,
+}
+
+/// A call to `clap_host_params::rescan()`, along with the changes to the plugin's parameters the
+/// host found by querying them again after the call.
+#[derive(Debug, Clone)]
+pub struct ParamRescan {
+    pub flags: clap_param_rescan_flags,
+    /// The changes to the plugin's parameters. This is empty if the host did not know about the
+    /// plugin's parameters before the call or if it could not query them after the call.
+    pub changes: Vec<ParamChange>,
+}
+
+/// A difference between the parameters the host knew about before a call to
+/// `clap_host_params::rescan()` and the parameters after that call. See [`diff_known_params()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamChange {
+    /// The `CLAP_PARAM_RESCAN_*` flag the plugin needs to pass to `clap_host_params::rescan()` for
+    /// this change to be allowed.
+    pub required_flag: clap_param_rescan_flags,
+    /// A human-readable description of the change.
+    pub description: String,
+}
+
+/// The parts of a parameter's `clap_param_info` the host keeps track of to validate calls to
+/// `clap_host_params::rescan()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownParam {
+    pub name: String,
+    pub module: String,
+    pub range: RangeInclusive<f64>,
+    /// The address of the parameter's cookie pointer. This is stored as an integer so the struct
+    /// can be shared between threads.
+    pub cookie: usize,
+    pub flags: clap_param_info_flags,
+}
+
+/// A timer registered by the plugin through `clap_host_timer_support::register_timer()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Timer {
+    /// The interval at which `clap_plugin_timer_support::on_timer()` is called for this timer.
+    /// This is the period requested by the plugin, clamped to [`MIN_TIMER_PERIOD_MS`].
+    pub period: Duration,
+    /// The point in time at which the timer should fire next.
+    next_tick: Instant,
+}
+
+/// The arguments for a `clap_host_thread_pool::request_exec()` call that's being executed on the
+/// host's thread pool.
+struct ThreadPoolTask {
+    plugin: *const clap_plugin,
+    thread_pool: *const clap_plugin_thread_pool,
+}
+
+// The plugin's `clap_plugin_thread_pool::exec()` function is thread-safe, and both pointers remain
+// valid until `request_exec()` returns
+unsafe impl Send for ThreadPoolTask {}
+unsafe impl Sync for ThreadPoolTask {}
+
+impl ThreadPoolTask {
+    /// Run a single task by calling the plugin's `clap_plugin_thread_pool::exec()` function.
+    fn exec(&self, task_index: u32) {
+        let (plugin, thread_pool) = (self.plugin, self.thread_pool);
+        unsafe_clap_call! { thread_pool=>exec(plugin, task_index) };
+    }
+}
+
+thread_local! {
+    /// Thread safety issues recorded by [`Host`] instances that were dropped on this thread. Test
+    /// cases drain this using [`take_thread_safety_audit()`] when creating their results, which
+    /// means that these issues are reported for every test and not just for the tests that
+    /// explicitly check for callback errors.
+    static THREAD_SAFETY_AUDIT: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Take all thread safety issues recorded by hosts that were dropped on the current thread since
+/// the last time this function was called.
+pub fn take_thread_safety_audit() -> Vec<String> {
+    THREAD_SAFETY_AUDIT.with(|audit| std::mem::take(&mut *audit.borrow_mut()))
+}
+
+thread_local! {
+    /// Messages logged by plugins through [`Host`] instances that were dropped on this thread. Test
+    /// cases drain this using [`take_plugin_log()`] when creating their results.
+    static PLUGIN_LOG: RefCell<Vec<LogMessage>> = RefCell::new(Vec::new());
+}
+
+/// Take all messages logged by plugins through hosts that were dropped on the current thread since
+/// the last time this function was called.
+pub fn take_plugin_log() -> Vec<LogMessage> {
+    PLUGIN_LOG.with(|log| std::mem::take(&mut *log.borrow_mut()))
+}
+
+thread_local! {
+    /// The host extensions queried and used by plugins through [`Host`] instances that were dropped
+    /// on this thread. Test cases drain this using [`take_host_extension_usage()`] when creating
+    /// their results.
+    static HOST_EXTENSION_USAGE: RefCell<HostExtensionUsage> =
+        RefCell::new(HostExtensionUsage::default());
+}
+
+/// Take the host extensions queried and used by plugins through hosts that were dropped on the
+/// current thread since the last time this function was called.
+pub fn take_host_extension_usage() -> HostExtensionUsage {
+    HOST_EXTENSION_USAGE.with(|usage| std::mem::take(&mut *usage.borrow_mut()))
+}
+
+/// Get a pointer to one of the host's extension vtables, for use in [`HOST_EXTENSIONS`].
+fn vtable_ptr<T>(vtable: &T) -> *const c_void {
+    vtable as *const T as *const c_void
+}
+
+/// Compute the differences between the parameters the host knew about before a call to
+/// `clap_host_params::rescan()` and the parameters after that call. Following the CLAP
+/// specification, adding or removing parameters and changing a parameter's range, cookie,
+/// modulation, stepped, read-only, or bypass flags requires `CLAP_PARAM_RESCAN_ALL`. Changing a
+/// parameter's name, module, or its hidden and periodic flags requires `CLAP_PARAM_RESCAN_INFO`.
+/// Other changes, like changes to the default value or the automation flags, are not listed.
+pub fn diff_known_params(
+    old_params: &BTreeMap<clap_id, KnownParam>,
+    new_params: &BTreeMap<clap_id, KnownParam>,
+) -> Vec<ParamChange> {
+    const INFO_FLAGS: clap_param_info_flags = CLAP_PARAM_IS_HIDDEN | CLAP_PARAM_IS_PERIODIC;
+    const ALL_FLAGS: clap_param_info_flags = CLAP_PARAM_IS_STEPPED
+        | CLAP_PARAM_IS_READONLY
+        | CLAP_PARAM_IS_BYPASS
+        | CLAP_PARAM_IS_MODULATABLE
+        | CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID
+        | CLAP_PARAM_IS_MODULATABLE_PER_KEY
+        | CLAP_PARAM_IS_MODULATABLE_PER_CHANNEL
+        | CLAP_PARAM_IS_MODULATABLE_PER_PORT;
+
+    let mut changes = Vec::new();
+    let mut change = |required_flag, description| {
+        changes.push(ParamChange {
+            required_flag,
+            description,
+        })
+    };
+    for (param_id, old_param) in old_params {
+        let name = &old_param.name;
+        let new_param = match new_params.get(param_id) {
+            Some(new_param) => new_param,
+            None => {
+                change(
+                    CLAP_PARAM_RESCAN_ALL,
+                    format!("parameter '{name}' (stable ID {param_id}) was removed"),
+                );
+                continue;
+            }
+        };
+
+        if new_param.name != old_param.name {
+            change(
+                CLAP_PARAM_RESCAN_INFO,
+                format!(
+                    "the name of parameter '{name}' (stable ID {param_id}) changed to '{}'",
+                    new_param.name
+                ),
+            );
+        }
+        if new_param.module != old_param.module {
+            change(
+                CLAP_PARAM_RESCAN_INFO,
+                format!(
+                    "the module of parameter '{name}' (stable ID {param_id}) changed from '{}' to \
+                     '{}'",
+                    old_param.module, new_param.module
+                ),
+            );
+        }
+        if (new_param.flags & INFO_FLAGS) != (old_param.flags & INFO_FLAGS) {
+            change(
+                CLAP_PARAM_RESCAN_INFO,
+                format!(
+                    "the hidden or periodic flags of parameter '{name}' (stable ID {param_id}) \
+                     changed from {:#b} to {:#b}",
+                    old_param.flags & INFO_FLAGS,
+                    new_param.flags & INFO_FLAGS
+                ),
+            );
+        }
+        if new_param.range != old_param.range {
+            change(
+                CLAP_PARAM_RESCAN_ALL,
+                format!(
+                    "the range of parameter '{name}' (stable ID {param_id}) changed from {:?} to \
+                     {:?}",
+                    old_param.range, new_param.range
+                ),
+            );
+        }
+        if new_param.cookie != old_param.cookie {
+            change(
+                CLAP_PARAM_RESCAN_ALL,
+                format!(
+                    "the cookie of parameter '{name}' (stable ID {param_id}) changed from {:#x} \
+                     to {:#x}",
+                    old_param.cookie, new_param.cookie
+                ),
+            );
+        }
+        if (new_param.flags & ALL_FLAGS) != (old_param.flags & ALL_FLAGS) {
+            change(
+                CLAP_PARAM_RESCAN_ALL,
+                format!(
+                    "the modulation, stepped, read-only, or bypass flags of parameter '{name}' \
+                     (stable ID {param_id}) changed from {:#b} to {:#b}",
+                    old_param.flags & ALL_FLAGS,
+                    new_param.flags & ALL_FLAGS
+                ),
+            );
+        }
+    }
+    for (param_id, new_param) in new_params {
+        if !old_params.contains_key(param_id) {
+            change(
+                CLAP_PARAM_RESCAN_ALL,
+                format!(
+                    "parameter '{}' (stable ID {param_id}) was added",
+                    new_param.name
+                ),
+            );
+        }
+    }
+
+    changes
+}
+
+/// Check whether the differences between the parameters the host knew about before a call to
+/// `clap_host_params::rescan()` and the parameters after that call are covered by the rescan's
+/// flags. See [`diff_known_params()`] for the flags each change requires. Returns the changes if
+/// they are allowed.
+pub fn check_param_rescan(
+    old_params: &BTreeMap<clap_id, KnownParam>,
+    new_params: &BTreeMap<clap_id, KnownParam>,
+    flags: clap_param_rescan_flags,
+) -> Result<Vec<ParamChange>> {
+    let changes = diff_known_params(old_params, new_params);
+    let disallowed_changes: Vec<String> = changes
+        .iter()
+        .filter(|change| flags & (change.required_flag | CLAP_PARAM_RESCAN_ALL) == 0)
+        .map(|change| {
+            let required_flag = if change.required_flag == CLAP_PARAM_RESCAN_INFO {
+                "CLAP_PARAM_RESCAN_INFO"
+            } else {
+                "CLAP_PARAM_RESCAN_ALL"
+            };
+
+            format!("{} (requires '{required_flag}')", change.description)
+        })
+        .collect();
+    if !disallowed_changes.is_empty() {
+        anyhow::bail!(
+            "The plugin called 'clap_host_params::rescan()' with flags {flags:#b}, but those \
+             flags don't cover all changes to its parameters: {}.",
+            disallowed_changes.join("; ")
+        );
+    }
+
+    Ok(changes)
 }
 
 /// When the host is handling callbacks in a blocking fashion, other threads can send tasks over the
@@ -143,8 +835,13 @@ impl InstanceState {
     /// audio thread and pending callbacks. The `Pin` is necessary to prevent moving the object out
     /// of the `Arc`, since that would break pointers to the `InstanceState`.
     pub fn new(host: Rc<Host>) -> Pin<Arc<Self>> {
-        let clap_validator_version =
-            CString::new(env!("CARGO_PKG_VERSION")).expect("Invalid bytes in crate version");
+        // Command line arguments cannot contain null bytes, so these conversions can't fail
+        let host_info = &host.config.host_info;
+        let host_name = CString::new(host_info.name.as_str()).expect("Null bytes in host name");
+        let host_vendor =
+            CString::new(host_info.vendor.as_str()).expect("Null bytes in host vendor");
+        let host_version =
+            CString::new(host_info.version.as_str()).expect("Null bytes in host version");
         let instance = Arc::pin(Self {
             plugin: AtomicCell::new(None),
             host,
@@ -153,22 +850,40 @@ impl InstanceState {
                 clap_version: CLAP_VERSION,
                 // This is populated with a pointer to the `Arc<Self>`'s data after creating the Arc
                 host_data: std::ptr::null_mut(),
-                name: b"clap-validator\0".as_ptr() as *const c_char,
-                vendor: b"Robbert van der Helm\0".as_ptr() as *const c_char,
+                name: host_name.as_ptr(),
+                vendor: host_vendor.as_ptr(),
                 url: b"https://github.com/free-audio/clap-validator\0".as_ptr() as *const c_char,
-                version: clap_validator_version.as_ptr(),
+                version: host_version.as_ptr(),
                 get_extension: Some(Host::get_extension),
                 request_restart: Some(Host::request_restart),
                 request_process: Some(Host::request_process),
                 request_callback: Some(Host::request_callback),
             }),
-            _clap_validator_version: clap_validator_version,
+            _host_name: host_name,
+            _host_vendor: host_vendor,
+            _host_version: host_version,
 
             status: AtomicCell::new(PluginStatus::default()),
 
             audio_thread: AtomicCell::new(None),
+            realtime_audio_thread: AtomicBool::new(false),
             requested_callback: AtomicBool::new(false),
+            requested_callback_recursively: AtomicBool::new(false),
+            active_plugin_callback: AtomicCell::new(None),
             requested_restart: AtomicBool::new(false),
+            num_thread_pool_requests: AtomicUsize::new(0),
+            num_thread_pool_tasks: AtomicUsize::new(0),
+            event_spaces: Mutex::new(Vec::new()),
+            num_event_space_queries: AtomicUsize::new(0),
+            posix_fds: Mutex::new(HashMap::new()),
+            timers: Mutex::new(HashMap::new()),
+            next_timer_id: AtomicU32::new(0),
+            num_timer_ticks: AtomicUsize::new(0),
+            loaded_presets: Mutex::new(Vec::new()),
+            num_track_info_queries: AtomicUsize::new(0),
+            known_params: Mutex::new(None),
+            param_rescans: Mutex::new(Vec::new()),
+            num_audio_ports_rescans: AtomicUsize::new(0),
         });
 
         // We need to get the pointer to the pinned `InstanceState` into the `clap_host::host_data`
@@ -205,6 +920,14 @@ impl InstanceState {
         self.clap_host.data_ptr()
     }
 
+    /// Returns whether `space_id` was handed out to this instance by
+    /// `clap_host_event_registry::query()`.
+    pub fn is_registered_event_space(&self, space_id: u16) -> bool {
+        space_id
+            .checked_sub(FIRST_EVENT_SPACE_ID)
+            .map_or(false, |idx| (idx as usize) < self.event_spaces.lock().len())
+    }
+
     /// Get a pointer to the `clap_plugin` struct for this instance.
     ///
     /// # Panics
@@ -221,11 +944,37 @@ impl InstanceState {
 
 impl Drop for Host {
     fn drop(&mut self) {
-        if let Some(error) = self.callback_error.borrow_mut().take() {
+        let thread_safety_audit = std::mem::take(&mut *self.thread_safety_audit.lock());
+        THREAD_SAFETY_AUDIT.with(|audit| {
+            let mut audit = audit.borrow_mut();
+            for issue in thread_safety_audit {
+                if !audit.contains(&issue.message) {
+                    audit.push(issue.message);
+                }
+            }
+        });
+
+        let mut plugin_log = std::mem::take(&mut *self.plugin_log.lock());
+        let num_omitted_log_messages = self.num_omitted_log_messages.load(Ordering::SeqCst);
+        if num_omitted_log_messages > 0 {
+            plugin_log.push(LogMessage {
+                severity: LogSeverity::Info,
+                message: format!(
+                    "...and {num_omitted_log_messages} more messages that were not recorded."
+                ),
+            });
+        }
+        PLUGIN_LOG.with(|log| log.borrow_mut().extend(plugin_log));
+
+        let host_extension_usage = std::mem::take(&mut *self.host_extension_usage.lock());
+        HOST_EXTENSION_USAGE.with(|usage| usage.borrow_mut().extend(&host_extension_usage));
+
+        for error in self.callback_errors.lock().drain(..) {
             log::error!(
                 "The validator's host has detected a callback error but this error has not been \
                  used as part of the test result. This is a clap-validator bug. The error message \
-                 is: {error}"
+                 is: {}",
+                error.message
             )
         }
     }
@@ -233,21 +982,37 @@ impl Drop for Host {
 
 impl Host {
     /// Initialize a CLAP host. The thread this object is created on will be designated as the main
-    /// thread for the purposes of the thread safety checks.
-    pub fn new() -> Rc<Host> {
+    /// thread for the purposes of the thread safety checks. The config determines how the host's
+    /// extensions behave. Use [`HostConfig::default()`] for a host that supports everything.
+    ///
+    /// With [`HostConfig::null_optional_functions`] the functions the plugin doesn't need to
+    /// function are set to null pointers. These are `clap_host_log::log()`, the `rescan()`,
+    /// `clear()`, and `request_flush()` functions from the audio ports, note ports, and parameter
+    /// extensions, `clap_host_state::mark_dirty()`, the preset load extension's callbacks,
+    /// `clap_host_track_info::get()`, and all context menu functions. Thread checks, thread pools,
+    /// timers, file descriptors, and functions that query the host's capabilities are kept intact.
+    pub fn new(config: HostConfig) -> Rc<Host> {
         // Normally you'd of course use bounded channel to avoid unnecessary allocations, but since
         // we're a validator it's probably better to not have to deal with the possibility that a
         // queue is full. These are used for handling callbacks on the main thread while the audio
         // thread is active.
         let (callback_task_sender, callback_task_receiver) = channel::unbounded();
 
-        Rc::new(Host {
+        let mut host = Host {
             main_thread_id: std::thread::current().id(),
-            // If the plugin never makes callbacks from the wrong thread, then this will remain an
-            // None`. Otherwise this will be replaced by the first error.
-            callback_error: RefCell::new(None),
+            config,
+            // If the plugin never makes callbacks from the wrong thread, then this will remain
+            // empty
+            callback_errors: Mutex::new(Vec::new()),
+            omitted_callback_error_keys: Mutex::new(HashSet::new()),
+            thread_safety_audit: Mutex::new(Vec::new()),
+            plugin_log: Mutex::new(Vec::new()),
+            num_omitted_log_messages: AtomicUsize::new(0),
+            host_extension_usage: Mutex::new(HostExtensionUsage::default()),
 
             instances: RefCell::new(HashMap::new()),
+            thread_pool: Mutex::new(None),
+            thread_pool_workers: Arc::new(Mutex::new(Vec::new())),
             callback_task_sender,
             callback_task_receiver,
 
@@ -255,6 +1020,18 @@ impl Host {
                 is_rescan_flag_supported: Some(Self::ext_audio_ports_is_rescan_flag_supported),
                 rescan: Some(Self::ext_audio_ports_rescan),
             },
+            clap_host_context_menu: clap_host_context_menu {
+                populate: Some(Self::ext_context_menu_populate),
+                perform: Some(Self::ext_context_menu_perform),
+                can_popup: Some(Self::ext_context_menu_can_popup),
+                popup: Some(Self::ext_context_menu_popup),
+            },
+            clap_host_event_registry: clap_host_event_registry {
+                query: Some(Self::ext_event_registry_query),
+            },
+            clap_host_log: clap_host_log {
+                log: Some(Self::ext_log_log),
+            },
             clap_host_note_ports: clap_host_note_ports {
                 supported_dialects: Some(Self::ext_note_ports_supported_dialects),
                 rescan: Some(Self::ext_note_ports_rescan),
@@ -268,6 +1045,11 @@ impl Host {
                 clear: Some(Self::ext_params_clear),
                 request_flush: Some(Self::ext_params_request_flush),
             },
+            clap_host_posix_fd_support: clap_host_posix_fd_support {
+                register_fd: Some(Self::ext_posix_fd_support_register_fd),
+                modify_fd: Some(Self::ext_posix_fd_support_modify_fd),
+                unregister_fd: Some(Self::ext_posix_fd_support_unregister_fd),
+            },
             clap_host_state: clap_host_state {
                 mark_dirty: Some(Self::ext_state_mark_dirty),
             },
@@ -275,7 +1057,42 @@ impl Host {
                 is_main_thread: Some(Self::ext_thread_check_is_main_thread),
                 is_audio_thread: Some(Self::ext_thread_check_is_audio_thread),
             },
-        })
+            clap_host_thread_pool: clap_host_thread_pool {
+                request_exec: Some(Self::ext_thread_pool_request_exec),
+            },
+            clap_host_timer_support: clap_host_timer_support {
+                register_timer: Some(Self::ext_timer_support_register_timer),
+                unregister_timer: Some(Self::ext_timer_support_unregister_timer),
+            },
+            clap_host_track_info: clap_host_track_info {
+                get: Some(Self::ext_track_info_get),
+            },
+        };
+
+        if host.config.null_optional_functions {
+            host.clap_host_audio_ports.rescan = None;
+            host.clap_host_context_menu = clap_host_context_menu {
+                populate: None,
+                perform: None,
+                can_popup: None,
+                popup: None,
+            };
+            host.clap_host_log.log = None;
+            host.clap_host_note_ports.rescan = None;
+            host.clap_host_preset_load = clap_host_preset_load {
+                on_error: None,
+                loaded: None,
+            };
+            host.clap_host_params = clap_host_params {
+                rescan: None,
+                clear: None,
+                request_flush: None,
+            };
+            host.clap_host_state.mark_dirty = None;
+            host.clap_host_track_info.get = None;
+        }
+
+        Rc::new(host)
     }
 
     /// Register a plugin instance with the host. This is used to keep track of things like audio
@@ -324,7 +1141,8 @@ impl Host {
     }
 
     /// Handle main thread callbacks until [`CallbackTask::Stop`] is send to
-    /// [`Host::callback_task_sender`] from another thread.
+    /// [`Host::callback_task_sender`] from another thread. This also dispatches the timers
+    /// registered through `clap_host_timer_support::register_timer()`.
     pub fn handle_callbacks_blocking(&self) {
         let mut should_stop = false;
         loop {
@@ -332,8 +1150,13 @@ impl Host {
                 break;
             }
 
-            let task = self.callback_task_receiver.recv().unwrap();
-            if matches!(task, CallbackTask::Stop) {
+            // If the plugin has registered any timers, then we'll need to wake up in time for the
+            // next one to fire
+            let task = match self.next_timer_deadline() {
+                Some(deadline) => self.callback_task_receiver.recv_deadline(deadline).ok(),
+                None => Some(self.callback_task_receiver.recv().unwrap()),
+            };
+            if matches!(task, Some(CallbackTask::Stop)) {
                 should_stop = true;
             }
 
@@ -347,17 +1170,182 @@ impl Host {
                 }
             }
 
-            // This function will handle up to ten recursive callback requests. We'll do this even
-            // if the handler should be stopped to make sure we did not miss any outstanding events.
+            self.handle_timers();
+
+            // This function will handle a limited number of recursive callback requests. We'll do
+            // this even if the handler should be stopped to make sure we did not miss any
+            // outstanding events.
             self.handle_callbacks_once();
         }
     }
 
+    /// Handle main thread callbacks, including callbacks requested from other threads and the
+    /// timers registered through `clap_host_timer_support::register_timer()`, until none of the
+    /// plugin instances has a pending `clap_host::request_callback()` request. This returns
+    /// immediately if nothing is pending, so it's cheap to call after main thread functions like
+    /// `clap_plugin_state::load()`. If the plugin still has a pending request after
+    /// [`CALLBACK_IDLE_TIMEOUT`], then an error indicating this can be retrieved using
+    /// [`callback_error_check()`][Self::callback_error_check()].
+    pub fn handle_callbacks_until_idle(&self) {
+        // Any poll messages that are still in the queue have already been handled by an earlier
+        // call to `handle_callbacks_once()`
+        while self.callback_task_receiver.try_recv().is_ok() {}
+
+        let start = Instant::now();
+        loop {
+            self.handle_timers();
+            self.handle_callbacks_once();
+            if !self.has_pending_callbacks() {
+                return;
+            }
+
+            if start.elapsed() >= CALLBACK_IDLE_TIMEOUT {
+                break;
+            }
+        }
+
+        self.set_callback_error(format!(
+            "The plugin still had a pending 'clap_host::request_callback()' request after the host \
+             spent {} ms handling callbacks. The plugin keeps requesting new callbacks from within \
+             'clap_plugin::on_main_thread()'.",
+            start.elapsed().as_millis()
+        ));
+    }
+
+    /// Returns whether any of the plugin instances has called `clap_host::request_callback()`
+    /// without `clap_plugin::on_main_thread()` having been called since.
+    fn has_pending_callbacks(&self) -> bool {
+        self.instances
+            .borrow()
+            .values()
+            .any(|instance| instance.requested_callback.load(Ordering::SeqCst))
+    }
+
+    /// Call `clap_plugin_timer_support::on_timer()` for every registered timer that is due. Timers
+    /// that were unregistered by an earlier `on_timer()` call in the same batch are skipped.
+    fn handle_timers(&self) {
+        let now = Instant::now();
+        let instances = self.instances.borrow();
+        for instance in instances.values() {
+            let due_timer_ids: Vec<clap_id> = instance
+                .timers
+                .lock()
+                .iter_mut()
+                .filter(|(_, timer)| timer.next_tick <= now)
+                .map(|(timer_id, timer)| {
+                    timer.next_tick = now + timer.period;
+                    *timer_id
+                })
+                .collect();
+            if due_timer_ids.is_empty() {
+                continue;
+            }
+
+            let plugin_ptr = instance.plugin_ptr();
+            let timer_support = unsafe_clap_call! {
+                plugin_ptr=>get_extension(plugin_ptr, CLAP_EXT_TIMER_SUPPORT.as_ptr())
+            } as *const clap_plugin_timer_support;
+            if timer_support.is_null() {
+                self.set_callback_error(
+                    "The plugin registered a timer using \
+                     'clap_host_timer_support::register_timer()', but it does not implement the \
+                     'clap.timer-support' extension.",
+                );
+                continue;
+            }
+
+            for timer_id in due_timer_ids {
+                let is_registered = instance.timers.lock().contains_key(&timer_id);
+                if !is_registered {
+                    continue;
+                }
+
+                log::trace!("Calling 'clap_plugin_timer_support::on_timer({timer_id})'");
+                instance.num_timer_ticks.fetch_add(1, Ordering::SeqCst);
+                instance
+                    .active_plugin_callback
+                    .store(Some(ON_TIMER_CALLBACK));
+                unsafe_clap_call! { timer_support=>on_timer(plugin_ptr, timer_id) };
+                instance.active_plugin_callback.store(None);
+            }
+        }
+    }
+
+    /// Query the parameters of the plugin instance belonging to `instance` directly through its
+    /// `clap_plugin_params` vtable. Used to handle `clap_host_params::rescan()`.
+    fn query_known_params(instance: &InstanceState) -> Result<BTreeMap<clap_id, KnownParam>> {
+        let plugin_ptr = instance.plugin_ptr();
+        let params = unsafe_clap_call! {
+            plugin_ptr=>get_extension(plugin_ptr, CLAP_EXT_PARAMS.as_ptr())
+        } as *const clap_plugin_params;
+        if params.is_null() {
+            anyhow::bail!("The plugin does not implement the 'clap.params' extension.");
+        }
+
+        let mut result = BTreeMap::new();
+        let num_params = unsafe_clap_call! { params=>count(plugin_ptr) };
+        for i in 0..num_params {
+            let mut info: clap_param_info = unsafe { std::mem::zeroed() };
+            if !unsafe_clap_call! { params=>get_info(plugin_ptr, i, &mut info) } {
+                anyhow::bail!(
+                    "Plugin returned an error when querying parameter {i} ({num_params} total \
+                     parameters)."
+                );
+            }
+
+            let name = util::c_char_slice_to_string(&info.name).with_context(|| {
+                format!(
+                    "Could not read the name for parameter with stable ID {}",
+                    info.id
+                )
+            })?;
+            let module = util::c_char_slice_to_string(&info.module).with_context(|| {
+                format!(
+                    "Could not read the module name for parameter with stable ID {}",
+                    info.id
+                )
+            })?;
+            result.insert(
+                info.id,
+                KnownParam {
+                    name,
+                    module,
+                    range: info.min_value..=info.max_value,
+                    cookie: info.cookie as usize,
+                    flags: info.flags,
+                },
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// Get the point in time at which the next registered timer should fire, if any of the
+    /// registered plugin instances has registered a timer.
+    fn next_timer_deadline(&self) -> Option<Instant> {
+        self.instances
+            .borrow()
+            .values()
+            .filter_map(|instance| {
+                instance
+                    .timers
+                    .lock()
+                    .values()
+                    .map(|timer| timer.next_tick)
+                    .min()
+            })
+            .min()
+    }
+
     /// Handle pending main thread callbacks. If a callback results in another callback, this is
-    /// allowed to loop up to ten times.
+    /// allowed to loop as many times as the validator's `--callback-recursion-budget` option
+    /// allows. If the plugin still requests another callback from within
+    /// `clap_plugin::on_main_thread()` after that, then this is noted in the thread safety audit
+    /// and the remaining request is handled the next time this function is called.
     pub fn handle_callbacks_once(&self) {
+        let recursion_budget = util::callback_recursion_budget();
         let instances = self.instances.borrow();
-        for i in 0..10 {
+        for i in 0..=recursion_budget {
             let mut handled_callback = false;
             for instance in instances.values() {
                 let plugin_ptr = instance.plugin_ptr();
@@ -368,9 +1356,16 @@ impl Host {
                 {
                     log::trace!(
                         "Calling 'clap_plugin::on_main_thread()' in response to a call to \
-                         'clap_host::request_restart()'",
+                         'clap_host::request_callback()'",
                     );
+                    instance
+                        .requested_callback_recursively
+                        .store(false, Ordering::SeqCst);
+                    instance
+                        .active_plugin_callback
+                        .store(Some(ON_MAIN_THREAD_CALLBACK));
                     unsafe_clap_call! { plugin_ptr=>on_main_thread(plugin_ptr) };
+                    instance.active_plugin_callback.store(None);
                     handled_callback = true;
                 }
             }
@@ -387,183 +1382,676 @@ impl Host {
             }
         }
 
-        log::warn!(
-            "The plugin recursively called 'clap_host::on_main_thread()'. Aborted after ten \
-             iterations."
-        )
+        // Requests made from other threads in the meantime are not interesting here. Requesting
+        // another callback from `on_main_thread()` is allowed, for instance when the plugin
+        // processes work in chunks, so this is only noted in the test's thread safety audit
+        // instead of being treated as a callback error.
+        let requested_recursively = instances.values().any(|instance| {
+            instance.requested_callback.load(Ordering::SeqCst)
+                && instance
+                    .requested_callback_recursively
+                    .load(Ordering::SeqCst)
+        });
+        if requested_recursively {
+            self.record_thread_safety_issue(
+                "clap_host::request_callback()",
+                format!(
+                    "'clap_host::request_callback()' was called from within \
+                     'clap_plugin::on_main_thread()' more than {recursion_budget} times in a row. \
+                     The host stopped calling 'clap_plugin::on_main_thread()' after \
+                     {recursion_budget} iterations to avoid blocking the main thread."
+                ),
+            );
+        }
     }
 
-    /// Check if any of the host's callbacks were called from the wrong thread. Returns the first
-    /// error if this happened. If there were errors and this function is not called before the
-    /// object is destroyed, an error will be logged.
+    /// Check if any of the host's callbacks were called from the wrong thread. Returns an error
+    /// listing all distinct errors if this happened. If there were errors and this function is not
+    /// called before the object is destroyed, an error will be logged.
     pub fn callback_error_check(&self) -> Result<()> {
-        match self.callback_error.borrow_mut().take() {
-            Some(err) => anyhow::bail!(err),
-            None => Ok(()),
+        let errors = std::mem::take(&mut *self.callback_errors.lock());
+        let num_omitted_errors =
+            std::mem::take(&mut *self.omitted_callback_error_keys.lock()).len();
+        self.thread_safety_audit
+            .lock()
+            .retain(|issue| !errors.iter().any(|error| error.key == issue.key));
+        match errors.as_slice() {
+            [] => Ok(()),
+            [error] if num_omitted_errors == 0 => anyhow::bail!(error.message.clone()),
+            errors => {
+                let mut message = format!(
+                    "{} errors occurred during host callbacks:",
+                    errors.len() + num_omitted_errors
+                );
+                for error in errors {
+                    message.push_str("\n- ");
+                    message.push_str(&error.message);
+                }
+                if num_omitted_errors > 0 {
+                    message.push_str(&format!(
+                        "\n- ...and {num_omitted_errors} more distinct errors."
+                    ));
+                }
+
+                anyhow::bail!(message)
+            }
         }
     }
 
-    /// Set the callback error field if it does not already contain a value. Earlier errors are not
-    /// overwritten.
+    /// Add an error to the list of callback errors. Identical errors are only recorded once.
     fn set_callback_error(&self, error: impl Into<String>) {
-        let mut callback_error = self.callback_error.borrow_mut();
-        if callback_error.is_none() {
-            *callback_error = Some(error.into());
+        let error = error.into();
+        self.add_callback_error(error.clone(), error);
+    }
+
+    /// Add a thread safety error for `function_name` to the list of callback errors. Only the first
+    /// error for each function is recorded.
+    fn set_thread_safety_error(&self, function_name: &str, error: impl Into<String>) {
+        self.add_callback_error(format!("thread-safety:{function_name}"), error.into());
+    }
+
+    /// Store a callback error unless an error with the same key has already been stored. If the
+    /// error list is full, then the error is only counted, again only once per key.
+    fn add_callback_error(&self, key: String, message: String) {
+        let mut callback_errors = self.callback_errors.lock();
+        if callback_errors.iter().any(|error| error.key == key) {
+            return;
+        }
+
+        if callback_errors.len() < MAX_CALLBACK_ERRORS {
+            callback_errors.push(CallbackError { key, message });
+        } else {
+            self.omitted_callback_error_keys.lock().insert(key);
+        }
+    }
+
+    /// Record a panic caught in one of the host's callbacks as a callback error. If the panic was
+    /// caused by the `clap_host` pointer or its `host_data` field being null, then there is no host
+    /// to record the error on, so it's logged instead.
+    unsafe fn record_callback_panic(host: *const clap_host, message: String) {
+        if host.is_null() || (*host).host_data.is_null() {
+            log::error!("{message}");
+        } else {
+            let (_, this) = InstanceState::from_clap_host_ptr(host);
+            this.set_callback_error(message);
         }
     }
 
     /// Checks whether this is the main thread. If it is not, then an error indicating this can be
     /// retrieved using [`callback_error_check()`][Self::callback_error_check()]. Subsequent thread
-    /// safety errors will not overwrite earlier ones.
+    /// safety errors for the same function are not recorded again.
     fn assert_main_thread(&self, function_name: &str) {
         let current_thread_id = std::thread::current().id();
         if current_thread_id != self.main_thread_id {
+            self.record_thread_safety_issue(
+                function_name,
+                format!(
+                    "'{function_name}' may only be called from the main thread, but it was \
+                     called from {}.",
+                    self.describe_thread(current_thread_id)
+                ),
+            );
+            self.set_thread_safety_error(
+                function_name,
+                format!(
+                    "'{}' may only be called from the main thread (thread {:?}), but it was \
+                     called from thread {:?}.",
+                    function_name, self.main_thread_id, current_thread_id
+                ),
+            );
+        }
+    }
+
+    /// Checks whether the plugin instance has finished being created. The plugin is not allowed to
+    /// call any host functions from within `clap_plugin_factory::create_plugin()`, and
+    /// `clap_host::get_extension()` may only be called from `clap_plugin::init()` onwards. Since
+    /// [`InstanceState::plugin`] is only set after `create_plugin()` returns, it being `None` means
+    /// the plugin is calling back into the host too early. If this happens, then an error
+    /// indicating this can be retrieved using
+    /// [`callback_error_check()`][Self::callback_error_check()].
+    fn assert_instance_created(&self, instance: &InstanceState, function_name: &str) {
+        if instance.plugin.load().is_none() {
+            self.set_callback_error(format!(
+                "'{function_name}' was called by the plugin during \
+                 'clap_plugin_factory::create_plugin()'. Host functions may not be called before \
+                 'clap_plugin::init()'."
+            ));
+        }
+    }
+
+    /// Checks whether `flags` is a valid set of `CLAP_POSIX_FD_*` flags for a file descriptor
+    /// passed to `function_name`. At least one flag needs to be set, and no undefined bits may be
+    /// set. If this is not the case, then an error indicating this can be retrieved using
+    /// [`callback_error_check()`][Self::callback_error_check()] and `false` is returned.
+    fn check_posix_fd_flags(
+        &self,
+        function_name: &str,
+        fd: i32,
+        flags: clap_posix_fd_flags,
+    ) -> bool {
+        let known_flags = CLAP_POSIX_FD_READ | CLAP_POSIX_FD_WRITE | CLAP_POSIX_FD_ERROR;
+        if flags == 0 || (flags & !known_flags) != 0 {
+            self.set_callback_error(format!(
+                "'{function_name}' was called for file descriptor {fd} with invalid flags \
+                 {flags:#b}. At least one of 'CLAP_POSIX_FD_READ', 'CLAP_POSIX_FD_WRITE', and \
+                 'CLAP_POSIX_FD_ERROR' must be set, and no other bits may be set."
+            ));
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Checks whether the plugin instance is deactivated when it calls a host function with flags
+    /// that may only be used while the plugin is deactivated. `forbidden_flags` contains those
+    /// flags. If the plugin is activated and any of those flags are set, then an error indicating
+    /// this can be retrieved using [`callback_error_check()`][Self::callback_error_check()].
+    fn assert_deactivated_for_flags(
+        &self,
+        instance: &InstanceState,
+        function_name: &str,
+        flags: u32,
+        forbidden_flags: u32,
+    ) {
+        if (flags & forbidden_flags) != 0 && instance.status.load() >= PluginStatus::Activated {
             self.set_callback_error(format!(
-                "'{}' may only be called from the main thread (thread {:?}), but it was called \
-                 from thread {:?}.",
-                function_name, self.main_thread_id, current_thread_id
+                "'{function_name}' was called with flags {flags:#b} while the plugin was \
+                 activated. The flags {:#b} may only be used while the plugin is deactivated.",
+                flags & forbidden_flags
             ));
         }
     }
 
     /// Checks whether this is the audio thread. If it is not, then an error indicating this can be
     /// retrieved using [`callback_error_check()`][Self::callback_error_check()]. Subsequent thread
-    /// safety errors will not overwrite earlier ones.
-    #[allow(unused)]
+    /// safety errors for the same function are not recorded again.
     fn assert_audio_thread(&self, function_name: &str) {
         let current_thread_id = std::thread::current().id();
         if !self.is_audio_thread(current_thread_id) {
-            if current_thread_id == self.main_thread_id {
-                self.set_callback_error(format!(
+            self.record_thread_safety_issue(
+                function_name,
+                format!(
                     "'{function_name}' may only be called from an audio thread, but it was called \
-                     from the main thread."
-                ));
+                     from {}.",
+                    self.describe_thread(current_thread_id)
+                ),
+            );
+            if current_thread_id == self.main_thread_id {
+                self.set_thread_safety_error(
+                    function_name,
+                    format!(
+                        "'{function_name}' may only be called from an audio thread, but it was \
+                         called from the main thread."
+                    ),
+                );
             } else {
-                self.set_callback_error(format!(
-                    "'{function_name}' may only be called from an audio thread, but it was called \
-                     from an unknown thread."
-                ));
+                self.set_thread_safety_error(
+                    function_name,
+                    format!(
+                        "'{function_name}' may only be called from an audio thread, but it was \
+                         called from an unknown thread."
+                    ),
+                );
             }
         }
     }
 
     /// Checks whether this is **not** the audio thread. If it is, then an error indicating this can
-    /// be retrieved using [`callback_error_check()`][Self::callback_error_check()]. Subsequent thread
-    /// safety errors will not overwrite earlier ones.
+    /// be retrieved using [`callback_error_check()`][Self::callback_error_check()]. Subsequent
+    /// thread safety errors for the same function are not recorded again.
     fn assert_not_audio_thread(&self, function_name: &str) {
         let current_thread_id = std::thread::current().id();
         if self.is_audio_thread(current_thread_id) {
-            self.set_callback_error(format!(
-                "'{function_name}' was called from an audio thread, this is not allowed.",
-            ));
+            self.record_thread_safety_issue(
+                function_name,
+                format!(
+                    "'{function_name}' may not be called from an audio thread, but it was called \
+                     from {}.",
+                    self.describe_thread(current_thread_id)
+                ),
+            );
+            self.set_thread_safety_error(
+                function_name,
+                format!("'{function_name}' was called from an audio thread, this is not allowed."),
+            );
+        }
+    }
+
+    /// Record a thread safety issue for the thread safety audit. Identical issues are only recorded
+    /// once.
+    fn record_thread_safety_issue(&self, function_name: &str, issue: String) {
+        let mut thread_safety_audit = self.thread_safety_audit.lock();
+        if !thread_safety_audit
+            .iter()
+            .any(|audit| audit.message == issue)
+        {
+            thread_safety_audit.push(CallbackError {
+                key: format!("thread-safety:{function_name}"),
+                message: issue,
+            });
+        }
+    }
+
+    /// Record that the plugin called a host callback. If the callback belongs to one of the host's
+    /// extensions, then that extension is marked as used. Functions from the `clap_host` struct
+    /// itself are not part of any extension.
+    fn record_host_extension_call(&self, function_name: &str) {
+        let interface_name = function_name.split("::").next().unwrap_or_default();
+        if let Some((extension_id, _, _)) = HOST_EXTENSIONS
+            .iter()
+            .find(|(_, name, _)| *name == interface_name)
+        {
+            self.host_extension_usage
+                .lock()
+                .used
+                .insert(extension_id.to_string_lossy().into_owned());
         }
     }
 
-    /// Returns whether the thread ID is one of the registered audio threads.
+    /// Describe a thread for use in the thread safety audit. Thread IDs are not included since new
+    /// audio threads are spawned for every processing run.
+    fn describe_thread(&self, thread_id: ThreadId) -> &'static str {
+        if thread_id == self.main_thread_id {
+            "the main thread"
+        } else if self.is_audio_thread(thread_id) {
+            "an audio thread"
+        } else {
+            "an unknown thread"
+        }
+    }
+
+    /// Returns whether the thread ID is one of the registered audio threads, or one of the thread
+    /// pool's worker threads.
     fn is_audio_thread(&self, thread_id: ThreadId) -> bool {
-        self.instances
-            .borrow()
-            .values()
-            .any(|instance| instance.audio_thread.load() == Some(thread_id))
+        self.is_thread_pool_worker(thread_id)
+            || self
+                .instances
+                .borrow()
+                .values()
+                .any(|instance| instance.audio_thread.load() == Some(thread_id))
+    }
+
+    /// Returns whether the thread ID is one of the thread pool's worker threads.
+    fn is_thread_pool_worker(&self, thread_id: ThreadId) -> bool {
+        self.thread_pool_workers.lock().contains(&thread_id)
+    }
+
+    /// Create the worker pool used for `clap_host_thread_pool::request_exec()` if it does not yet
+    /// exist. This must be called from the main thread before the plugin starts processing audio.
+    pub fn create_thread_pool(&self) {
+        let mut thread_pool = self.thread_pool.lock();
+        if thread_pool.is_none() {
+            let thread_pool_workers = self.thread_pool_workers.clone();
+            let new_thread_pool = rayon::ThreadPoolBuilder::new()
+                .thread_name(|idx| format!("clap-validator-thread-pool-{idx}"))
+                .start_handler(move |_| {
+                    thread_pool_workers.lock().push(std::thread::current().id())
+                })
+                .build()
+                .expect("Could not create the host's thread pool");
+
+            *thread_pool = Some(Arc::new(new_thread_pool));
+        }
     }
 
     unsafe extern "C" fn get_extension(
         host: *const clap_host,
         extension_id: *const c_char,
     ) -> *const c_void {
-        check_null_ptr!(host, (*host).host_data, extension_id);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
-
-        // Right now there's no way to have the host only expose certain extensions. We can always
-        // add that when test cases need it.
-        let extension_id_cstr = CStr::from_ptr(extension_id);
-        if extension_id_cstr == CLAP_EXT_AUDIO_PORTS {
-            &this.clap_host_audio_ports as *const _ as *const c_void
-        } else if extension_id_cstr == CLAP_EXT_NOTE_PORTS {
-            &this.clap_host_note_ports as *const _ as *const c_void
-        } else if extension_id_cstr == CLAP_EXT_PRESET_LOAD {
-            &this.clap_host_preset_load as *const _ as *const c_void
-        } else if extension_id_cstr == CLAP_EXT_PARAMS {
-            &this.clap_host_params as *const _ as *const c_void
-        } else if extension_id_cstr == CLAP_EXT_STATE {
-            &this.clap_host_state as *const _ as *const c_void
-        } else if extension_id_cstr == CLAP_EXT_THREAD_CHECK {
-            &this.clap_host_thread_check as *const _ as *const c_void
-        } else {
-            std::ptr::null()
-        }
+        host_callback!(
+            host,
+            "clap_host::get_extension()",
+            any_thread,
+            [extension_id],
+            |instance, this| {
+                // This function is thread-safe, but we'll still note it in the thread safety audit
+                // if the plugin queries extensions from anywhere but the main thread, or from
+                // within one of the plugin's own callbacks. Extensions should be queried once
+                // during initialization.
+                let current_thread_id = std::thread::current().id();
+                if current_thread_id != this.main_thread_id {
+                    this.record_thread_safety_issue(
+                        "clap_host::get_extension()",
+                        format!(
+                            "'clap_host::get_extension()' was called from {}. This is allowed, \
+                             but host extensions should ideally be queried on the main thread \
+                             during 'clap_plugin::init()'.",
+                            this.describe_thread(current_thread_id)
+                        ),
+                    );
+                } else if let Some(plugin_callback) = instance.active_plugin_callback.load() {
+                    this.record_thread_safety_issue(
+                        "clap_host::get_extension()",
+                        format!(
+                            "'clap_host::get_extension()' was called from within \
+                             '{plugin_callback}'. This is allowed, but host extensions should \
+                             ideally be queried once during 'clap_plugin::init()'."
+                        ),
+                    );
+                }
+
+                // Right now there's no way to have the host only expose certain extensions. We can
+                // always add that when test cases need it.
+                let extension_id_cstr = CStr::from_ptr(extension_id);
+                this.host_extension_usage
+                    .lock()
+                    .queried
+                    .insert(extension_id_cstr.to_string_lossy().into_owned());
+                match HOST_EXTENSIONS
+                    .iter()
+                    .find(|(id, _, _)| *id == extension_id_cstr)
+                {
+                    Some((_, _, vtable)) => vtable(this),
+                    None => std::ptr::null(),
+                }
+            }
+        )
     }
 
     unsafe extern "C" fn request_restart(host: *const clap_host) {
-        check_null_ptr!(host, (*host).host_data);
-        let (instance, _) = InstanceState::from_clap_host_ptr(host);
-
-        // This flag will be reset at the start of one of the `ProcessingTest::run*` functions, and
-        // in the multi-iteration run function it will trigger a deactivate->reactivate cycle
-        log::trace!("'clap_host::request_restart()' was called by the plugin, setting the flag");
-        instance.requested_restart.store(true, Ordering::SeqCst);
+        host_callback!(
+            host,
+            "clap_host::request_restart()",
+            any_thread,
+            |instance, _| {
+                // This flag will be reset at the start of one of the `ProcessingTest::run*`
+                // functions, and in the multi-iteration run function it will trigger a
+                // deactivate->reactivate cycle
+                log::trace!(
+                    "'clap_host::request_restart()' was called by the plugin, setting the flag"
+                );
+                instance.requested_restart.store(true, Ordering::SeqCst);
+            }
+        )
     }
 
     unsafe extern "C" fn request_process(host: *const clap_host) {
-        check_null_ptr!(host, (*host).host_data);
-
-        // Handling this within the context of the validator would be a bit messy. Do plugins use
-        // this?
-        log::debug!("TODO: Handle 'clap_host::request_process()'");
+        host_callback!(host, "clap_host::request_process()", any_thread, |_, _| {
+            // Handling this within the context of the validator would be a bit messy. Do plugins
+            // use this?
+            log::debug!("TODO: Handle 'clap_host::request_process()'");
+        })
     }
 
     unsafe extern "C" fn request_callback(host: *const clap_host) {
-        check_null_ptr!(host, (*host).host_data);
-        let (instance, this) = InstanceState::from_clap_host_ptr(host);
-
-        // This this is either handled by `handle_callbacks_blocking()` while the audio thread is
-        // active, or by an explicit call to `handle_callbacks_once()`. We print a warning if the
-        // callback is not handled before the plugin is destroyed.
-        log::trace!("'clap_host::request_callback()' was called by the plugin, setting the flag");
-        instance.requested_callback.store(true, Ordering::SeqCst);
-        this.callback_task_sender.send(CallbackTask::Poll).unwrap();
+        host_callback!(
+            host,
+            "clap_host::request_callback()",
+            any_thread,
+            |instance, this| {
+                // This this is either handled by `handle_callbacks_blocking()` while the audio
+                // thread is active, or by an explicit call to `handle_callbacks_once()` or
+                // `handle_callbacks_until_idle()`. We print a warning if the callback is not
+                // handled before the plugin is destroyed.
+                log::trace!(
+                    "'clap_host::request_callback()' was called by the plugin, setting the flag"
+                );
+                let is_recursive = std::thread::current().id() == this.main_thread_id
+                    && instance.active_plugin_callback.load() == Some(ON_MAIN_THREAD_CALLBACK);
+                instance
+                    .requested_callback_recursively
+                    .store(is_recursive, Ordering::SeqCst);
+                instance.requested_callback.store(true, Ordering::SeqCst);
+                this.callback_task_sender.send(CallbackTask::Poll).unwrap();
+            }
+        )
     }
 
     unsafe extern "C" fn ext_audio_ports_is_rescan_flag_supported(
         host: *const clap_host,
-        _flag: u32,
+        flag: u32,
     ) -> bool {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+        host_callback!(
+            host,
+            "clap_host_audio_ports::is_rescan_flag_supported()",
+            main_thread,
+            |_, this| { (this.config.supported_audio_ports_rescan_flags & flag) == flag }
+        )
+    }
 
-        this.assert_main_thread("clap_host_audio_ports::is_rescan_flag_supported()");
-        log::debug!("TODO: Handle 'clap_host_audio_ports::is_rescan_flag_supported()'");
+    unsafe extern "C" fn ext_audio_ports_rescan(host: *const clap_host, flags: u32) {
+        host_callback!(
+            host,
+            "clap_host_audio_ports::rescan()",
+            main_thread,
+            |instance, this| {
+                instance
+                    .num_audio_ports_rescans
+                    .fetch_add(1, Ordering::SeqCst);
+
+                this.assert_deactivated_for_flags(
+                    instance,
+                    "clap_host_audio_ports::rescan()",
+                    flags,
+                    AUDIO_PORTS_RESCAN_DEACTIVATED_FLAGS,
+                );
+                log::debug!("TODO: Handle 'clap_host_audio_ports::rescan()'");
+            }
+        )
+    }
 
-        true
+    unsafe extern "C" fn ext_context_menu_populate(
+        host: *const clap_host,
+        target: *const clap_context_menu_target,
+        builder: *const clap_context_menu_builder,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_context_menu::populate()",
+            main_thread,
+            [target, builder],
+            |_, this| {
+                // The host adds a single entry of its own to the plugin's menu
+                let entry = clap_context_menu_entry {
+                    label: HOST_CONTEXT_MENU_LABEL.as_ptr(),
+                    is_enabled: true,
+                    action_id: HOST_CONTEXT_MENU_ACTION_ID,
+                };
+                let supports_entries = match (*builder).supports {
+                    Some(supports) => supports(builder, CLAP_CONTEXT_MENU_ITEM_ENTRY),
+                    None => {
+                        this.set_callback_error(
+                            "'clap_host_context_menu::populate()' was called with a builder \
+                             whose 'supports' function is a null pointer.",
+                        );
+                        return false;
+                    }
+                };
+                if !supports_entries {
+                    return true;
+                }
+
+                match (*builder).add_item {
+                    Some(add_item) => add_item(
+                        builder,
+                        CLAP_CONTEXT_MENU_ITEM_ENTRY,
+                        &entry as *const clap_context_menu_entry as *const c_void,
+                    ),
+                    None => {
+                        this.set_callback_error(
+                            "'clap_host_context_menu::populate()' was called with a builder \
+                             whose 'add_item' function is a null pointer.",
+                        );
+                        false
+                    }
+                }
+            }
+        )
     }
 
-    unsafe extern "C" fn ext_audio_ports_rescan(host: *const clap_host, _flags: u32) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+    unsafe extern "C" fn ext_context_menu_perform(
+        host: *const clap_host,
+        target: *const clap_context_menu_target,
+        action_id: clap_id,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_context_menu::perform()",
+            main_thread,
+            [target],
+            |_, this| {
+                if action_id != HOST_CONTEXT_MENU_ACTION_ID {
+                    this.set_callback_error(format!(
+                        "'clap_host_context_menu::perform()' was called with action ID \
+                         {action_id}, but the host only added an entry with action ID \
+                         {HOST_CONTEXT_MENU_ACTION_ID} to the plugin's context menus."
+                    ));
+                    return false;
+                }
 
-        // TODO: A couple of these flags are only allowed when the plugin is not activated, make
-        //       sure to check for this when implementing this functionality
-        this.assert_main_thread("clap_host_audio_ports::rescan()");
-        log::debug!("TODO: Handle 'clap_host_audio_ports::rescan()'");
+                true
+            }
+        )
     }
 
-    unsafe extern "C" fn ext_note_ports_supported_dialects(
+    unsafe extern "C" fn ext_context_menu_can_popup(host: *const clap_host) -> bool {
+        host_callback!(
+            host,
+            "clap_host_context_menu::can_popup()",
+            main_thread,
+            |_, _| {
+                // The validator doesn't have a GUI to show context menus in
+                false
+            }
+        )
+    }
+
+    unsafe extern "C" fn ext_context_menu_popup(
         host: *const clap_host,
-    ) -> clap_note_dialect {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+        target: *const clap_context_menu_target,
+        _screen_index: i32,
+        _x: i32,
+        _y: i32,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_context_menu::popup()",
+            main_thread,
+            [target],
+            |_, this| {
+                this.set_callback_error(
+                    "'clap_host_context_menu::popup()' was called even though \
+                     'clap_host_context_menu::can_popup()' returns false.",
+                );
+
+                false
+            }
+        )
+    }
+
+    unsafe extern "C" fn ext_event_registry_query(
+        host: *const clap_host,
+        space_name: *const c_char,
+        space_id: *mut u16,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_event_registry::query()",
+            main_thread,
+            [space_name, space_id],
+            |instance, this| {
+                instance
+                    .num_event_space_queries
+                    .fetch_add(1, Ordering::SeqCst);
+
+                let space_name = match unsafe { util::cstr_ptr_to_mandatory_string(space_name) } {
+                    Ok(space_name) => space_name,
+                    Err(err) => {
+                        this.set_callback_error(format!(
+                            "'clap_host_event_registry::query()' called with an invalid \
+                             space_name parameter: {err:#}"
+                        ));
+                        unsafe { *space_id = u16::MAX };
+                        return false;
+                    }
+                };
+                if !this.config.register_event_spaces {
+                    unsafe { *space_id = u16::MAX };
+                    return false;
+                }
+
+                let mut event_spaces = instance.event_spaces.lock();
+                let idx = match event_spaces.iter().position(|name| *name == space_name) {
+                    Some(idx) => idx,
+                    None => {
+                        event_spaces.push(space_name);
+                        event_spaces.len() - 1
+                    }
+                };
+                unsafe { *space_id = FIRST_EVENT_SPACE_ID + idx as u16 };
+
+                true
+            }
+        )
+    }
 
-        this.assert_main_thread("clap_host_note_ports::supported_dialects()");
+    unsafe extern "C" fn ext_log_log(
+        host: *const clap_host,
+        severity: clap_log_severity,
+        msg: *const c_char,
+    ) {
+        host_callback!(
+            host,
+            "clap_host_log::log()",
+            any_thread,
+            [msg],
+            |_, this| {
+                let message = unsafe { CStr::from_ptr(msg) }
+                    .to_string_lossy()
+                    .into_owned();
+                let severity = match LogSeverity::from_clap(severity) {
+                    Some(severity) => severity,
+                    None => {
+                        this.set_callback_error(format!(
+                            "'clap_host_log::log()' was called with an unknown severity \
+                             {severity}."
+                        ));
+                        return;
+                    }
+                };
+                if severity == LogSeverity::HostMisbehaving {
+                    this.set_callback_error(format!(
+                        "The plugin logged a message with the 'CLAP_LOG_HOST_MISBEHAVING' \
+                         severity: {message}"
+                    ));
+                }
 
-        CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI | CLAP_NOTE_DIALECT_MIDI_MPE
+                let mut plugin_log = this.plugin_log.lock();
+                if plugin_log.len() < MAX_PLUGIN_LOG_MESSAGES {
+                    plugin_log.push(LogMessage { severity, message });
+                } else {
+                    this.num_omitted_log_messages.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        )
     }
 
-    unsafe extern "C" fn ext_note_ports_rescan(host: *const clap_host, _flags: u32) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+    unsafe extern "C" fn ext_note_ports_supported_dialects(
+        host: *const clap_host,
+    ) -> clap_note_dialect {
+        host_callback!(
+            host,
+            "clap_host_note_ports::supported_dialects()",
+            main_thread,
+            |_, this| { this.config.supported_note_dialects }
+        )
+    }
 
-        this.assert_main_thread("clap_host_note_ports::rescan()");
-        log::debug!("TODO: Handle 'clap_host_note_ports::rescan()'");
+    unsafe extern "C" fn ext_note_ports_rescan(host: *const clap_host, flags: u32) {
+        host_callback!(
+            host,
+            "clap_host_note_ports::rescan()",
+            main_thread,
+            |instance, this| {
+                this.assert_deactivated_for_flags(
+                    instance,
+                    "clap_host_note_ports::rescan()",
+                    flags,
+                    CLAP_NOTE_PORTS_RESCAN_ALL,
+                );
+                log::debug!("TODO: Handle 'clap_host_note_ports::rescan()'");
+            }
+        )
     }
 
     unsafe extern "C" fn ext_preset_load_on_error(
@@ -574,35 +2062,41 @@ impl Host {
         os_error: i32,
         msg: *const c_char,
     ) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
-
-        this.assert_main_thread("clap_host_preset_load::on_error()");
-
-        let location = LocationValue::new(location_kind, location)
-            .context("'clap_host_preset_load::on_error()' called with invalid location parameters");
-        let load_key = unsafe { util::cstr_ptr_to_optional_string(load_key) }.context(
-            "'clap_host_preset_load::on_error()' called with an invalid load_key parameter",
-        );
-        let msg = unsafe { util::cstr_ptr_to_mandatory_string(msg) }
-            .context("'clap_host_preset_load::on_error()' called with an invalid msg parameter");
-        match (location, load_key, msg) {
-            (Ok(location), Ok(Some(load_key)), Ok(msg)) => {
-                this.set_callback_error(format!(
-                    "'clap_host_preset_load::on_error()' called for {location} with load key \
-                     {load_key}, OS error code {os_error}, and the following error message: {msg}"
-                ));
-            }
-            (Ok(location), Ok(None), Ok(msg)) => {
-                this.set_callback_error(format!(
-                    "'clap_host_preset_load::on_error()' called for {location} with no load key, \
-                     OS error code {os_error}, and the following error message: {msg}"
-                ));
-            }
-            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
-                this.set_callback_error(format!("{err:#}"));
+        host_callback!(
+            host,
+            "clap_host_preset_load::on_error()",
+            main_thread,
+            |_, this| {
+                let location = LocationValue::new(location_kind, location).context(
+                    "'clap_host_preset_load::on_error()' called with invalid location parameters",
+                );
+                let load_key = unsafe { util::cstr_ptr_to_optional_string(load_key) }.context(
+                    "'clap_host_preset_load::on_error()' called with an invalid load_key parameter",
+                );
+                let msg = unsafe { util::cstr_ptr_to_mandatory_string(msg) }.context(
+                    "'clap_host_preset_load::on_error()' called with an invalid msg parameter",
+                );
+                match (location, load_key, msg) {
+                    (Ok(location), Ok(Some(load_key)), Ok(msg)) => {
+                        this.set_callback_error(format!(
+                            "'clap_host_preset_load::on_error()' called for {location} with load \
+                             key {load_key}, OS error code {os_error}, and the following error \
+                             message: {msg}"
+                        ));
+                    }
+                    (Ok(location), Ok(None), Ok(msg)) => {
+                        this.set_callback_error(format!(
+                            "'clap_host_preset_load::on_error()' called for {location} with no \
+                             load key, OS error code {os_error}, and the following error message: \
+                             {msg}"
+                        ));
+                    }
+                    (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                        this.set_callback_error(format!("{err:#}"));
+                    }
+                }
             }
-        }
+        )
     }
 
     unsafe extern "C" fn ext_preset_load_loaded(
@@ -611,34 +2105,76 @@ impl Host {
         location: *const c_char,
         load_key: *const c_char,
     ) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
-
-        this.assert_main_thread("clap_host_preset_load::loaded()");
-
-        let location = LocationValue::new(location_kind, location)
-            .context("'clap_host_preset_load::loaded()' called with invalid location parameters");
-        let load_key = unsafe { util::cstr_ptr_to_optional_string(load_key) }
-            .context("'clap_host_preset_load::loaded()' called with an invalid load_key parameter");
-        match (location, load_key) {
-            (Ok(_location), Ok(_load_key)) => {
-                log::debug!("TODO: Handle 'clap_host_preset_load::loaded()'");
-            }
-            (Err(err), _) | (_, Err(err)) => {
-                this.set_callback_error(format!("{err:#}"));
+        host_callback!(
+            host,
+            "clap_host_preset_load::loaded()",
+            main_thread,
+            |instance, this| {
+                let location = LocationValue::new(location_kind, location).context(
+                    "'clap_host_preset_load::loaded()' called with invalid location parameters",
+                );
+                let load_key = unsafe { util::cstr_ptr_to_optional_string(load_key) }.context(
+                    "'clap_host_preset_load::loaded()' called with an invalid load_key parameter",
+                );
+                match (location, load_key) {
+                    (Ok(location), Ok(load_key)) => {
+                        instance.loaded_presets.lock().push((location, load_key));
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        this.set_callback_error(format!("{err:#}"));
+                    }
+                }
             }
-        }
+        )
     }
 
-    unsafe extern "C" fn ext_params_rescan(
-        host: *const clap_host,
-        _flags: clap_param_rescan_flags,
-    ) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
-
-        this.assert_main_thread("clap_host_params::rescan()");
-        log::debug!("TODO: Handle 'clap_host_params::rescan()'");
+    unsafe extern "C" fn ext_params_rescan(host: *const clap_host, flags: clap_param_rescan_flags) {
+        host_callback!(
+            host,
+            "clap_host_params::rescan()",
+            main_thread,
+            |instance, this| {
+                this.assert_deactivated_for_flags(
+                    instance,
+                    "clap_host_params::rescan()",
+                    flags,
+                    CLAP_PARAM_RESCAN_ALL,
+                );
+
+                // Like a real host, we'll query the parameters again right away and compare them
+                // to what we knew about them before
+                let new_params = match Self::query_known_params(instance) {
+                    Ok(new_params) => new_params,
+                    Err(err) => {
+                        instance.param_rescans.lock().push(ParamRescan {
+                            flags,
+                            changes: Vec::new(),
+                        });
+                        this.set_callback_error(format!(
+                            "Could not query the plugin's parameters after a call to \
+                             'clap_host_params::rescan()': {err:#}"
+                        ));
+                        return;
+                    }
+                };
+
+                let old_params = instance.known_params.lock().replace(new_params.clone());
+                let changes = match old_params {
+                    Some(old_params) => match check_param_rescan(&old_params, &new_params, flags) {
+                        Ok(changes) => changes,
+                        Err(err) => {
+                            this.set_callback_error(format!("{err:#}"));
+                            diff_known_params(&old_params, &new_params)
+                        }
+                    },
+                    None => Vec::new(),
+                };
+                instance
+                    .param_rescans
+                    .lock()
+                    .push(ParamRescan { flags, changes });
+            }
+        )
     }
 
     unsafe extern "C" fn ext_params_clear(
@@ -646,40 +2182,326 @@ impl Host {
         _param_id: clap_id,
         _flags: clap_param_clear_flags,
     ) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
-
-        this.assert_main_thread("clap_host_params::clear()");
-        log::debug!("TODO: Handle 'clap_host_params::clear()'");
+        host_callback!(host, "clap_host_params::clear()", main_thread, |_, _| {
+            log::debug!("TODO: Handle 'clap_host_params::clear()'");
+        })
     }
 
     unsafe extern "C" fn ext_params_request_flush(host: *const clap_host) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+        host_callback!(
+            host,
+            "clap_host_params::request_flush()",
+            not_audio_thread,
+            |_, _| {
+                log::debug!("TODO: Handle 'clap_host_params::request_flush()'");
+            }
+        )
+    }
+
+    unsafe extern "C" fn ext_posix_fd_support_register_fd(
+        host: *const clap_host,
+        fd: i32,
+        flags: clap_posix_fd_flags,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_posix_fd_support::register_fd()",
+            main_thread,
+            |instance, this| {
+                if !this.check_posix_fd_flags(
+                    "clap_host_posix_fd_support::register_fd()",
+                    fd,
+                    flags,
+                ) {
+                    return false;
+                }
+
+                let mut posix_fds = instance.posix_fds.lock();
+                if posix_fds.contains_key(&fd) {
+                    this.set_callback_error(format!(
+                        "'clap_host_posix_fd_support::register_fd()' was called for file \
+                         descriptor {fd}, but that file descriptor has already been registered. \
+                         'clap_host_posix_fd_support::modify_fd()' should be used to change its \
+                         flags."
+                    ));
+                    return false;
+                }
+                posix_fds.insert(fd, flags);
 
-        this.assert_not_audio_thread("clap_host_params::request_flush()");
-        log::debug!("TODO: Handle 'clap_host_params::request_flush()'");
+                true
+            }
+        )
     }
 
-    unsafe extern "C" fn ext_state_mark_dirty(host: *const clap_host) {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+    unsafe extern "C" fn ext_posix_fd_support_modify_fd(
+        host: *const clap_host,
+        fd: i32,
+        flags: clap_posix_fd_flags,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_posix_fd_support::modify_fd()",
+            main_thread,
+            |instance, this| {
+                if !this.check_posix_fd_flags("clap_host_posix_fd_support::modify_fd()", fd, flags)
+                {
+                    return false;
+                }
+
+                match instance.posix_fds.lock().get_mut(&fd) {
+                    Some(registered_flags) => {
+                        *registered_flags = flags;
+                        true
+                    }
+                    None => {
+                        this.set_callback_error(format!(
+                            "'clap_host_posix_fd_support::modify_fd()' was called for file \
+                             descriptor {fd}, but that file descriptor has not been registered."
+                        ));
+                        false
+                    }
+                }
+            }
+        )
+    }
 
-        this.assert_main_thread("clap_host_state::mark_dirty()");
-        log::debug!("TODO: Handle 'clap_host_state::mark_dirty()'");
+    unsafe extern "C" fn ext_posix_fd_support_unregister_fd(
+        host: *const clap_host,
+        fd: i32,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_posix_fd_support::unregister_fd()",
+            main_thread,
+            |instance, this| {
+                if instance.posix_fds.lock().remove(&fd).is_none() {
+                    this.set_callback_error(format!(
+                        "'clap_host_posix_fd_support::unregister_fd()' was called for file \
+                         descriptor {fd}, but that file descriptor has not been registered."
+                    ));
+                    return false;
+                }
+
+                true
+            }
+        )
     }
 
-    unsafe extern "C" fn ext_thread_check_is_main_thread(host: *const clap_host) -> bool {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+    unsafe extern "C" fn ext_state_mark_dirty(host: *const clap_host) {
+        host_callback!(
+            host,
+            "clap_host_state::mark_dirty()",
+            main_thread,
+            |_, _| {
+                log::debug!("TODO: Handle 'clap_host_state::mark_dirty()'");
+            }
+        )
+    }
 
-        std::thread::current().id() == this.main_thread_id
+    unsafe extern "C" fn ext_thread_check_is_main_thread(host: *const clap_host) -> bool {
+        host_callback!(
+            host,
+            "clap_host_thread_check::is_main_thread()",
+            any_thread,
+            |_, this| { std::thread::current().id() == this.main_thread_id }
+        )
     }
 
     unsafe extern "C" fn ext_thread_check_is_audio_thread(host: *const clap_host) -> bool {
-        check_null_ptr!(host, (*host).host_data);
-        let (_, this) = InstanceState::from_clap_host_ptr(host);
+        host_callback!(
+            host,
+            "clap_host_thread_check::is_audio_thread()",
+            any_thread,
+            |_, this| { this.is_audio_thread(std::thread::current().id()) }
+        )
+    }
+
+    unsafe extern "C" fn ext_thread_pool_request_exec(
+        host: *const clap_host,
+        num_tasks: u32,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_thread_pool::request_exec()",
+            audio_thread,
+            |instance, this| {
+                // The thread pool's workers also count as audio threads, but the plugin is not
+                // allowed to request more tasks from within `clap_plugin_thread_pool::exec()`
+                if this.is_thread_pool_worker(std::thread::current().id()) {
+                    this.set_callback_error(
+                        "'clap_host_thread_pool::request_exec()' was called from within \
+                         'clap_plugin_thread_pool::exec()'. It may only be called from the audio \
+                         thread.",
+                    );
+                    return false;
+                }
+
+                let plugin = instance.plugin_ptr();
+                let thread_pool = unsafe_clap_call! {
+                    plugin=>get_extension(plugin, CLAP_EXT_THREAD_POOL.as_ptr())
+                } as *const clap_plugin_thread_pool;
+                if thread_pool.is_null() {
+                    this.set_callback_error(
+                        "'clap_host_thread_pool::request_exec()' was called by a plugin that does \
+                         not implement the 'clap.thread-pool' extension.",
+                    );
+                    return false;
+                }
+
+                instance
+                    .num_thread_pool_requests
+                    .fetch_add(1, Ordering::SeqCst);
+                instance
+                    .num_thread_pool_tasks
+                    .fetch_add(num_tasks as usize, Ordering::SeqCst);
+
+                // This blocks until all tasks have been executed, so the pointers remain valid
+                let task = ThreadPoolTask {
+                    plugin,
+                    thread_pool,
+                };
+                // The thread pool is created when the plugin gets activated if it implemented the
+                // thread pool extension at that point
+                let thread_pool = match this.thread_pool.lock().clone() {
+                    Some(thread_pool) => thread_pool,
+                    None => {
+                        this.set_callback_error(
+                            "'clap_host_thread_pool::request_exec()' was called, but the plugin \
+                             did not implement the 'clap.thread-pool' extension when it was \
+                             activated.",
+                        );
+                        return false;
+                    }
+                };
+                thread_pool.install(|| {
+                    (0..num_tasks)
+                        .into_par_iter()
+                        .for_each(|task_index| task.exec(task_index))
+                });
+
+                true
+            }
+        )
+    }
+
+    unsafe extern "C" fn ext_timer_support_register_timer(
+        host: *const clap_host,
+        period_ms: u32,
+        timer_id: *mut clap_id,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_timer_support::register_timer()",
+            main_thread,
+            [timer_id],
+            |instance, _| {
+                let period = Duration::from_millis(period_ms.max(MIN_TIMER_PERIOD_MS) as u64);
+                let new_timer_id = instance.next_timer_id.fetch_add(1, Ordering::SeqCst);
+                instance.timers.lock().insert(
+                    new_timer_id,
+                    Timer {
+                        period,
+                        next_tick: Instant::now() + period,
+                    },
+                );
+                *timer_id = new_timer_id;
+
+                true
+            }
+        )
+    }
+
+    unsafe extern "C" fn ext_timer_support_unregister_timer(
+        host: *const clap_host,
+        timer_id: clap_id,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_timer_support::unregister_timer()",
+            main_thread,
+            |instance, this| {
+                if instance.timers.lock().remove(&timer_id).is_none() {
+                    this.set_callback_error(format!(
+                        "'clap_host_timer_support::unregister_timer()' was called for timer ID \
+                         {timer_id}, but no timer with that ID is registered."
+                    ));
+                    return false;
+                }
+
+                true
+            }
+        )
+    }
+
+    unsafe extern "C" fn ext_track_info_get(
+        host: *const clap_host,
+        info: *mut clap_track_info,
+    ) -> bool {
+        host_callback!(
+            host,
+            "clap_host_track_info::get()",
+            main_thread,
+            [info],
+            |instance, _| {
+                instance
+                    .num_track_info_queries
+                    .fetch_add(1, Ordering::SeqCst);
+
+                // This describes a plausible stereo track in a DAW
+                let info = &mut *info;
+                info.flags = CLAP_TRACK_INFO_HAS_TRACK_NAME
+                    | CLAP_TRACK_INFO_HAS_TRACK_COLOR
+                    | CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL;
+                info.name = [0; CLAP_NAME_SIZE];
+                for (dest, byte) in info.name.iter_mut().zip(TRACK_INFO_NAME.bytes()) {
+                    *dest = byte as c_char;
+                }
+                info.color = TRACK_INFO_COLOR;
+                info.audio_channel_count = 2;
+                info.audio_port_type = CLAP_PORT_STEREO.as_ptr();
 
-        this.is_audio_thread(std::thread::current().id())
+                true
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::library::reference::ReferencePlugin;
+    use crate::plugin::library::PluginLibrary;
+
+    #[test]
+    fn handle_callbacks_until_idle_returns_when_idle() {
+        let library = PluginLibrary::reference().unwrap();
+        let host = Host::new(HostConfig::default());
+        let plugin = library
+            .create_plugin(ReferencePlugin::NoParams.id(), host.clone())
+            .unwrap();
+        plugin.init().unwrap();
+
+        let start = Instant::now();
+        host.handle_callbacks_until_idle();
+        assert!(start.elapsed() < CALLBACK_IDLE_TIMEOUT);
+        host.callback_error_check().unwrap();
+    }
+
+    #[test]
+    fn handle_callbacks_until_idle_fails_on_pending_callback() {
+        let library = PluginLibrary::reference().unwrap();
+        let host = Host::new(HostConfig::default());
+        let plugin = library
+            .create_plugin(ReferencePlugin::CallbackLoop.id(), host.clone())
+            .unwrap();
+        plugin.init().unwrap();
+
+        host.handle_callbacks_until_idle();
+        let err = host.callback_error_check().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("still had a pending 'clap_host::request_callback()' request"),
+            "Unexpected error: {err:#}"
+        );
     }
 }
@@ -1,6 +1,7 @@
 //! Abstractions for single CLAP plugin instances for main thread interactions.
 
 use anyhow::Result;
+use clap_sys::ext::thread_pool::CLAP_EXT_THREAD_POOL;
 use clap_sys::factory::plugin_factory::clap_plugin_factory;
 use clap_sys::plugin::clap_plugin;
 use std::ffi::CStr;
@@ -9,17 +10,28 @@ use std::ops::Deref;
 use std::pin::Pin;
 use std::ptr::NonNull;
 use std::rc::Rc;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use super::ext::Extension;
 use super::library::{PluginLibrary, PluginMetadata};
 use super::{assert_plugin_state_eq, assert_plugin_state_initialized};
 use crate::plugin::host::{CallbackTask, Host, InstanceState};
-use crate::util::unsafe_clap_call;
+use crate::util::{self, unsafe_clap_call};
 use audio_thread::PluginAudioThread;
 
 pub mod audio_thread;
 pub mod process;
+pub mod realtime;
+
+/// The sample rates the plugin is activated with to characterize an activation failure. See
+/// [`Plugin::activate()`].
+const ACTIVATION_DIAGNOSTIC_SAMPLE_RATES: [f64; 5] =
+    [22_050.0, 44_100.0, 48_000.0, 96_000.0, 192_000.0];
+/// The minimum and maximum buffer sizes the plugin is activated with for every sample rate in
+/// [`ACTIVATION_DIAGNOSTIC_SAMPLE_RATES`] to characterize an activation failure.
+const ACTIVATION_DIAGNOSTIC_BUFFER_SIZES: [(usize, usize); 5] =
+    [(1, 512), (512, 512), (1, 64), (1, 4096), (4096, 4096)];
 
 /// A `Send+Sync` wrapper around `*const clap_plugin`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -191,16 +203,52 @@ impl<'lib> Plugin<'lib> {
         }
     }
 
+    /// Returns whether the plugin returns a non-null pointer from `clap_plugin::get_extension()`
+    /// for `extension_id`. This can be used to check for extensions the validator doesn't have an
+    /// abstraction for. The plugin needs to be initialized using [`init()`][Self::init()] before
+    /// this may be called.
+    pub fn supports_extension(&self, extension_id: &CStr) -> bool {
+        assert_plugin_state_initialized!(self);
+
+        let plugin = self.as_ptr();
+        let extension_ptr = unsafe_clap_call! {
+            plugin=>get_extension(plugin, extension_id.as_ptr())
+        };
+
+        !extension_ptr.is_null()
+    }
+
     /// Execute some code for this plugin from an audio thread context. The closure receives a
     /// [`PluginAudioThread`], which disallows calling main thread functions, and permits calling
     /// audio thread functions.
     ///
     /// If whatever happens on the audio thread caused main-thread callback requests to be emited,
-    /// then those will be handled concurrently.
+    /// then those will be handled concurrently. When the validator's `--realtime-audio-thread`
+    /// option is enabled, the audio thread tries to enable realtime scheduling before calling the
+    /// closure.
     pub fn on_audio_thread<'a, T: Send, F: FnOnce(PluginAudioThread<'a>) -> T + Send>(
         &'a self,
         f: F,
     ) -> T {
+        self.on_audio_thread_with_main_thread_task(f, || ()).0
+    }
+
+    /// The same as [`on_audio_thread()`][Self::on_audio_thread()], but `main_thread_task` is run
+    /// on the main thread right after the audio thread has been spawned. This can be used to call
+    /// main thread functions while the audio thread is processing audio. Any synchronization
+    /// between the two closures needs to be done by the caller. Main-thread callback requests are
+    /// handled after `main_thread_task` returns.
+    pub fn on_audio_thread_with_main_thread_task<
+        'a,
+        T: Send,
+        F: FnOnce(PluginAudioThread<'a>) -> T + Send,
+        R,
+        M: FnOnce() -> R,
+    >(
+        &'a self,
+        f: F,
+        main_thread_task: M,
+    ) -> (T, R) {
         assert_plugin_state_eq!(self, PluginStatus::Activated);
 
         crossbeam::scope(|s| {
@@ -221,6 +269,20 @@ impl<'lib> Plugin<'lib> {
                     this.state
                         .audio_thread
                         .store(Some(std::thread::current().id()));
+
+                    // Not every OS or user is allowed to do this, so failing to enable realtime
+                    // scheduling is not an error
+                    let is_realtime = util::realtime_audio_thread()
+                        && match realtime::promote_current_thread() {
+                            Ok(()) => true,
+                            Err(err) => {
+                                log::debug!("Could not promote the audio thread: {err:#}");
+                                false
+                            }
+                        };
+                    this.state
+                        .realtime_audio_thread
+                        .store(is_realtime, Ordering::SeqCst);
                     let result = f(PluginAudioThread::new(this));
                     this.state.audio_thread.store(None);
 
@@ -231,10 +293,15 @@ impl<'lib> Plugin<'lib> {
                 })
                 .expect("Unable to spawn an audio thread");
 
+            let main_thread_result = main_thread_task();
+
             // Handle callbacks requests on the main thread whle the aduio thread is running
             self.host().handle_callbacks_blocking();
 
-            audio_thread.join().expect("Audio thread panicked")
+            (
+                audio_thread.join().expect("Audio thread panicked"),
+                main_thread_result,
+            )
         })
         .expect("Audio thread panicked")
     }
@@ -252,7 +319,9 @@ impl<'lib> Plugin<'lib> {
         }
     }
 
-    /// Activate the plugin. Returns an error if the plugin returned `false`. See
+    /// Activate the plugin. Returns an error if the plugin returned `false`. In that case the
+    /// plugin is activated and immediately deactivated again with a couple of other sample rates
+    /// and buffer sizes, and the error lists which of those configurations the plugin accepted. See
     /// [plugin.h](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h) for the
     /// preconditions.
     pub fn activate(
@@ -267,16 +336,75 @@ impl<'lib> Plugin<'lib> {
         assert!(min_buffer_size >= 1);
 
         let plugin = self.as_ptr();
+
+        // Plugins may request thread pool tasks from the audio thread. Spawning the worker threads
+        // at that point would not be realtime-safe, so the host's thread pool is created here.
+        let thread_pool = unsafe_clap_call! {
+            plugin=>get_extension(plugin, CLAP_EXT_THREAD_POOL.as_ptr())
+        };
+        if !thread_pool.is_null() {
+            self.host().create_thread_pool();
+        }
+
         if unsafe_clap_call! {
             plugin=>activate(plugin, sample_rate, min_buffer_size as u32, max_buffer_size as u32)
         } {
             self.state.status.store(PluginStatus::Activated);
             Ok(())
         } else {
-            anyhow::bail!("'clap_plugin::activate()' returned false.")
+            anyhow::bail!(
+                "'clap_plugin::activate()' returned false for a sample rate of {sample_rate} Hz \
+                 and buffer sizes between {min_buffer_size} and {max_buffer_size} samples. \
+                 Activating the plugin with other configurations resulted in the following: {}",
+                self.activation_diagnostics()
+            )
         }
     }
 
+    /// Try activating the plugin with every combination of [`ACTIVATION_DIAGNOSTIC_SAMPLE_RATES`]
+    /// and [`ACTIVATION_DIAGNOSTIC_BUFFER_SIZES`], deactivating it again whenever it accepts a
+    /// configuration. Returns a description of which configurations were accepted for every
+    /// sample rate. This is used to help characterize why activating the plugin failed.
+    fn activation_diagnostics(&self) -> String {
+        let plugin = self.as_ptr();
+        let sample_rate_results: Vec<String> = ACTIVATION_DIAGNOSTIC_SAMPLE_RATES
+            .into_iter()
+            .map(|sample_rate| {
+                let mut accepted = Vec::new();
+                let mut rejected = Vec::new();
+                for (min_buffer_size, max_buffer_size) in ACTIVATION_DIAGNOSTIC_BUFFER_SIZES {
+                    let buffer_sizes = format!("{min_buffer_size}..={max_buffer_size}");
+                    if unsafe_clap_call! {
+                        plugin=>activate(
+                            plugin,
+                            sample_rate,
+                            min_buffer_size as u32,
+                            max_buffer_size as u32
+                        )
+                    } {
+                        unsafe_clap_call! { plugin=>deactivate(plugin) };
+                        accepted.push(buffer_sizes);
+                    } else {
+                        rejected.push(buffer_sizes);
+                    }
+                }
+
+                match (accepted.is_empty(), rejected.is_empty()) {
+                    (true, _) => format!("{sample_rate} Hz was rejected for all buffer sizes"),
+                    (false, true) => format!("{sample_rate} Hz was accepted for all buffer sizes"),
+                    (false, false) => format!(
+                        "{sample_rate} Hz was accepted for buffer sizes {} and rejected for \
+                         buffer sizes {}",
+                        accepted.join(", "),
+                        rejected.join(", ")
+                    ),
+                }
+            })
+            .collect();
+
+        format!("{}.", sample_rate_results.join("; "))
+    }
+
     /// Deactivate the plugin. See
     /// [plugin.h](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h) for the
     /// preconditions.
@@ -288,4 +416,14 @@ impl<'lib> Plugin<'lib> {
 
         self.state.status.store(PluginStatus::Deactivated);
     }
+
+    /// Call `clap_plugin::start_processing()` from the main thread while the plugin is still
+    /// deactivated. This violates CLAP's lifecycle and threading rules, and it's only used by the
+    /// negative tests. Returns the plugin's return value. The plugin's status is not changed.
+    pub fn start_processing_while_deactivated(&self) -> bool {
+        assert_plugin_state_eq!(self, PluginStatus::Deactivated);
+
+        let plugin = self.as_ptr();
+        unsafe_clap_call! { plugin=>start_processing(plugin) }
+    }
 }
@@ -85,9 +85,6 @@ impl<'a> PluginAudioThread<'a> {
     /// Get the _audio thread_ extension abstraction for the extension `T`, if the plugin supports
     /// this extension. Returns `None` if it does not. The plugin needs to be initialized using
     /// [`init()`][Self::init()] before this may be called.
-    //
-    // TODO: Remove this unused attribute once we implement audio thread extensions
-    #[allow(unused)]
     pub fn get_extension<T: Extension<&'a Self>>(&'a self) -> Option<T> {
         assert_plugin_state_initialized!(self);
 
@@ -147,6 +144,16 @@ impl<'a> PluginAudioThread<'a> {
         }
     }
 
+    /// Reset the plugin's internal state, for instance to stop all of its voices and to clear its
+    /// buffers. See [plugin.h](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h)
+    /// for the preconditions.
+    pub fn reset(&self) {
+        assert_plugin_state_eq!(self, PluginStatus::Processing);
+
+        let plugin = self.as_ptr();
+        unsafe_clap_call! { plugin=>reset(plugin) };
+    }
+
     /// Stop processing audio. See
     /// [plugin.h](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h) for the
     /// preconditions.
@@ -3,13 +3,16 @@
 use anyhow::Result;
 use clap_sys::audio_buffer::clap_audio_buffer;
 use clap_sys::events::{
-    clap_event_header, clap_event_midi, clap_event_note, clap_event_note_expression,
-    clap_event_param_mod, clap_event_param_value, clap_event_transport, clap_input_events,
-    clap_output_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_NOTE_CHOKE,
-    CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON,
+    clap_event_header, clap_event_midi, clap_event_midi2, clap_event_midi_sysex, clap_event_note,
+    clap_event_note_expression, clap_event_param_gesture, clap_event_param_mod,
+    clap_event_param_value, clap_event_transport, clap_input_events, clap_output_events,
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI2, CLAP_EVENT_MIDI_SYSEX,
+    CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF,
+    CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END,
     CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT,
     CLAP_TRANSPORT_HAS_BEATS_TIMELINE, CLAP_TRANSPORT_HAS_SECONDS_TIMELINE,
-    CLAP_TRANSPORT_HAS_TEMPO, CLAP_TRANSPORT_HAS_TIME_SIGNATURE, CLAP_TRANSPORT_IS_PLAYING,
+    CLAP_TRANSPORT_HAS_TEMPO, CLAP_TRANSPORT_HAS_TIME_SIGNATURE, CLAP_TRANSPORT_IS_LOOP_ACTIVE,
+    CLAP_TRANSPORT_IS_PLAYING,
 };
 use clap_sys::fixedpoint::{CLAP_BEATTIME_FACTOR, CLAP_SECTIME_FACTOR};
 use clap_sys::process::clap_process;
@@ -18,6 +21,7 @@ use rand::Rng;
 use rand_pcg::Pcg32;
 use std::ffi::c_void;
 use std::pin::Pin;
+use std::sync::Arc;
 
 use crate::util::check_null_ptr;
 
@@ -33,10 +37,15 @@ pub struct ProcessData<'a> {
     config: ProcessConfig,
     /// The current transport information. This is populated when constructing this object, and the
     /// transport can be advanced `N` samples using the
-    /// [`advance_transport()`][Self::advance_transport()] method.
+    /// [`advance_transport()`][Self::advance_transport()] method. Tests can change the transport
+    /// using [`transport_info_mut()`][Self::transport_info_mut()].
     transport_info: clap_event_transport,
-    /// The current sample position. This is used to recompute values in `transport_info`.
+    /// The current sample position. This is used for the process call's `steady_time`, and it
+    /// keeps increasing even when the transport is stopped.
     sample_pos: u32,
+    /// The number of samples processed in each process call. This is the length of the audio
+    /// buffers unless it has been changed using [`reconfigure()`][Self::reconfigure()].
+    block_size: usize,
     // TODO: Maybe do something with `steady_time`
 }
 
@@ -82,6 +91,11 @@ pub struct OutOfPlaceAudioBuffers<'a> {
     clap_inputs: Vec<clap_audio_buffer>,
     clap_outputs: Vec<clap_audio_buffer>,
 
+    /// Whether the input port at the same index is a CV port. These ports are filled with control
+    /// signals instead of white noise when randomizing the buffers. Missing values are treated as
+    /// `false`.
+    cv_inputs: Vec<bool>,
+
     /// The number of samples for this buffer. This is consistent across all inner vectors.
     num_samples: usize,
 }
@@ -118,15 +132,47 @@ pub enum Event {
     NoteExpression(clap_event_note_expression),
     /// `CLAP_EVENT_MIDI`.
     Midi(clap_event_midi),
+    /// `CLAP_EVENT_MIDI2`. Contains a MIDI 2.0 Universal MIDI Packet.
+    Midi2(clap_event_midi2),
+    /// `CLAP_EVENT_MIDI_SYSEX`, along with the sysex data the event points to.
+    MidiSysex(MidiSysexEvent),
     /// `CLAP_EVENT_PARAM_VALUE`.
     ParamValue(clap_event_param_value),
     /// `CLAP_EVENT_PARAM_MOD`.
     ParamMod(clap_event_param_mod),
+    /// `CLAP_EVENT_PARAM_GESTURE_BEGIN` or `CLAP_EVENT_PARAM_GESTURE_END`.
+    ParamGesture(clap_event_param_gesture),
+    /// `CLAP_EVENT_TRANSPORT`. Some hosts send transport updates through the input event queue in
+    /// addition to the `clap_process::transport` field.
+    Transport(clap_event_transport),
+    /// An event with a `header.size` that is larger than the size of the event's struct, simulating
+    /// an extended version of the event from a future CLAP version. The data is stored as `u64`s
+    /// so it is correctly aligned. The bytes following the known struct contain garbage. See
+    /// [`Event::oversized()`]. If the event was a sysex event, then this also keeps its data alive.
+    Oversized(Vec<u64>, Option<Arc<[u8]>>),
     /// An unhandled event type. This is only used when the plugin outputs an event we don't handle
     /// or recognize.
     Unknown(clap_event_header),
 }
 
+/// The largest sysex message the plugin is expected to output. The data of larger messages is not
+/// copied, and these events are rejected by the output event checks.
+pub const MAX_SYSEX_SIZE: usize = 1 << 20;
+
+/// A `CLAP_EVENT_MIDI_SYSEX` event along with the data `event.buffer` points to. The data is shared
+/// between copies of the event, so the pointer remains valid for as long as any copy exists.
+#[derive(Debug, Clone)]
+pub struct MidiSysexEvent {
+    pub event: clap_event_midi_sysex,
+    /// The sysex data. This is empty if the event's buffer was a null pointer or if its size
+    /// exceeded [`MAX_SYSEX_SIZE`]. In those cases `event.buffer` is left as is.
+    pub data: Arc<[u8]>,
+}
+
+// The buffer pointer either points to the immutable `data`, or it is never dereferenced
+unsafe impl Send for MidiSysexEvent {}
+unsafe impl Sync for MidiSysexEvent {}
+
 impl Default for ProcessConfig {
     fn default() -> Self {
         Self {
@@ -142,9 +188,9 @@ impl<'a> ProcessData<'a> {
     /// Initialize the process data using the given audio buffers. The transport information will be
     /// initialized at the start of the project, and it can be moved using the
     /// [`advance_transport()`][Self::advance_transport()] method.
-    //
-    // TODO: More transport info options. Missing fields, loop regions, flags, etc.
     pub fn new(buffers: &'a mut AudioBuffers<'a>, config: ProcessConfig) -> Self {
+        let block_size = buffers.len();
+
         ProcessData {
             buffers,
             input_events: EventQueue::new_input(),
@@ -168,7 +214,7 @@ impl<'a> ProcessData<'a> {
                 song_pos_seconds: 0,
                 tempo: config.tempo,
                 tempo_inc: 0.0,
-                // These four currently aren't used
+                // These are only used when a test enables `CLAP_TRANSPORT_IS_LOOP_ACTIVE`
                 loop_start_beats: 0,
                 loop_end_beats: 0,
                 loop_start_seconds: 0,
@@ -179,6 +225,7 @@ impl<'a> ProcessData<'a> {
                 tsig_denom: config.time_sig_denominator,
             },
             sample_pos: 0,
+            block_size,
         }
     }
 
@@ -186,12 +233,11 @@ impl<'a> ProcessData<'a> {
     /// contains raw pointers to this struct's data, so the closure is there to prevent dangling
     /// pointers.
     pub fn with_clap_process_data<T, F: FnOnce(clap_process) -> T>(&mut self, f: F) -> T {
-        let num_samples = self.buffers.len();
         let (inputs, outputs) = self.buffers.io_buffers();
 
         let process_data = clap_process {
             steady_time: self.sample_pos as i64,
-            frames_count: num_samples as u32,
+            frames_count: self.block_size as u32,
             transport: &self.transport_info,
             audio_inputs: if inputs.is_empty() {
                 std::ptr::null()
@@ -212,24 +258,73 @@ impl<'a> ProcessData<'a> {
         f(process_data)
     }
 
+    /// The number of samples processed in each process call. This is never larger than the audio
+    /// buffers' length.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Change the sample rate and the number of samples processed in each process call. This is
+    /// used when the host reactivates the plugin with a different configuration. The transport's
+    /// position in seconds is preserved. A block size of zero results in process calls with a
+    /// `frames_count` of zero, which some hosts use to send events without processing any audio.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is larger than the audio buffers.
+    pub fn reconfigure(&mut self, sample_rate: f64, block_size: usize) {
+        assert!(block_size <= self.buffers.len());
+
+        self.sample_pos =
+            (self.sample_pos as f64 * sample_rate / self.config.sample_rate).round() as u32;
+        self.config.sample_rate = sample_rate;
+        self.block_size = block_size;
+    }
+
     /// Get current the transport information.
     #[allow(unused)]
     pub fn transport_info(&self) -> clap_event_transport {
         self.transport_info
     }
 
+    /// Get mutable access to the transport information sent with the next process call. This can
+    /// be used to change the tempo, to start or stop playback, to set up loop regions, or to
+    /// remove timelines. [`advance_transport()`][Self::advance_transport()] respects these
+    /// changes.
+    pub fn transport_info_mut(&mut self) -> &mut clap_event_transport {
+        &mut self.transport_info
+    }
+
+    /// Create a `CLAP_EVENT_TRANSPORT` event for sample `time` in the current block. This contains
+    /// the same information as [`transport_info()`][Self::transport_info()], but with the song
+    /// positions advanced to that sample. This can be added to the input event queue to mimic hosts
+    /// that send transport updates in the middle of a block.
+    pub fn transport_event(&self, time: u32) -> Event {
+        let (song_pos_beats, song_pos_seconds) = self.song_positions_after(time);
+
+        Event::Transport(clap_event_transport {
+            header: clap_event_header {
+                time,
+                ..self.transport_info.header
+            },
+            song_pos_beats,
+            song_pos_seconds,
+            ..self.transport_info
+        })
+    }
+
     /// Advance the transport by a certain number of samples. Make sure to also call
-    /// [`clear_events()`][Self::clear_events()].
+    /// [`clear_events()`][Self::clear_events()]. The song positions only move while the transport
+    /// is playing, they wrap around at the end of an active loop region, and the tempo changes
+    /// according to the transport's `tempo_inc` field.
     pub fn advance_transport(&mut self, samples: u32) {
+        let (song_pos_beats, song_pos_seconds) = self.song_positions_after(samples);
         self.sample_pos += samples;
-
-        self.transport_info.song_pos_beats =
-            ((self.sample_pos as f64 / self.config.sample_rate / 60.0 * self.transport_info.tempo)
-                * CLAP_BEATTIME_FACTOR as f64)
-                .round() as i64;
-        self.transport_info.song_pos_seconds = ((self.sample_pos as f64 / self.config.sample_rate)
-            * CLAP_SECTIME_FACTOR as f64)
-            .round() as i64;
+        self.transport_info.song_pos_beats = song_pos_beats;
+        self.transport_info.song_pos_seconds = song_pos_seconds;
+        if (self.transport_info.flags & CLAP_TRANSPORT_HAS_TEMPO) != 0 {
+            self.transport_info.tempo += self.transport_info.tempo_inc * samples as f64;
+        }
     }
 
     /// Clear the event queues. Make sure to also call
@@ -238,6 +333,47 @@ impl<'a> ProcessData<'a> {
         self.input_events.events.lock().clear();
         self.output_events.events.lock().clear();
     }
+
+    /// Compute the transport's song position in beats and in seconds `samples` samples after the
+    /// current position, as fixed point values. The position doesn't change if the transport is
+    /// not playing, and it wraps around to the start of the loop region if the transport is
+    /// looping.
+    fn song_positions_after(&self, samples: u32) -> (i64, i64) {
+        let transport = &self.transport_info;
+        if (transport.flags & CLAP_TRANSPORT_IS_PLAYING) == 0 {
+            return (transport.song_pos_beats, transport.song_pos_seconds);
+        }
+
+        let seconds = samples as f64 / self.config.sample_rate;
+        let mut song_pos_beats = transport.song_pos_beats
+            + (seconds / 60.0 * transport.tempo * CLAP_BEATTIME_FACTOR as f64).round() as i64;
+        let mut song_pos_seconds =
+            transport.song_pos_seconds + (seconds * CLAP_SECTIME_FACTOR as f64).round() as i64;
+        if (transport.flags & CLAP_TRANSPORT_IS_LOOP_ACTIVE) != 0 {
+            song_pos_beats = wrap_loop_position(
+                song_pos_beats,
+                transport.loop_start_beats,
+                transport.loop_end_beats,
+            );
+            song_pos_seconds = wrap_loop_position(
+                song_pos_seconds,
+                transport.loop_start_seconds,
+                transport.loop_end_seconds,
+            );
+        }
+
+        (song_pos_beats, song_pos_seconds)
+    }
+}
+
+/// Wrap a fixed point song position that has moved past the end of a loop region back into that
+/// region. Positions before the end of the loop region are left unchanged.
+fn wrap_loop_position(position: i64, loop_start: i64, loop_end: i64) -> i64 {
+    if loop_end > loop_start && position >= loop_end {
+        loop_start + (position - loop_start).rem_euclid(loop_end - loop_start)
+    } else {
+        position
+    }
 }
 
 impl AudioBuffers<'_> {
@@ -277,6 +413,14 @@ impl AudioBuffers<'_> {
             AudioBuffers::OutOfPlace(buffers) => buffers.randomize(prng),
         }
     }
+
+    /// Fill the input buffers with silence. If `impulse` is set, then the first sample of every
+    /// input channel is set to 1.0 instead.
+    pub fn fill_inputs_silence(&mut self, impulse: bool) {
+        match self {
+            AudioBuffers::OutOfPlace(buffers) => buffers.fill_inputs_silence(impulse),
+        }
+    }
 }
 
 impl<'a> OutOfPlaceAudioBuffers<'a> {
@@ -352,6 +496,8 @@ impl<'a> OutOfPlaceAudioBuffers<'a> {
             clap_inputs,
             clap_outputs,
 
+            cv_inputs: Vec::new(),
+
             // This cannot default to 0, because 0 isn't a valid buffer size in CLAP
             num_samples: num_samples.unwrap_or(512),
         })
@@ -368,12 +514,39 @@ impl<'a> OutOfPlaceAudioBuffers<'a> {
         (&self.clap_inputs, &mut self.clap_outputs)
     }
 
+    /// Mark the input ports that are CV ports. The vector should contain a value for every input
+    /// port, like the one returned by `AudioPortConfig::cv_inputs()`.
+    pub fn with_cv_inputs(mut self, cv_inputs: Vec<bool>) -> Self {
+        self.cv_inputs = cv_inputs;
+        self
+    }
+
     /// Fill the input and output buffers with white noise. The values are distributed between `[-1,
-    /// 1]`, and denormals are snapped to zero.
+    /// 1]`, and denormals are snapped to zero. CV input ports are instead filled with a random
+    /// value that's held for the entire buffer.
     pub fn randomize(&mut self, prng: &mut Pcg32) {
-        randomize_audio_buffers(prng, self.inputs);
+        for (port_idx, channel_slices) in self.inputs.iter_mut().enumerate() {
+            if self.cv_inputs.get(port_idx).copied().unwrap_or(false) {
+                randomize_cv_buffers(prng, channel_slices);
+            } else {
+                randomize_audio_buffers(prng, std::slice::from_mut(channel_slices));
+            }
+        }
         randomize_audio_buffers(prng, self.outputs);
     }
+
+    /// Fill the input buffers with silence. If `impulse` is set, then the first sample of every
+    /// input channel is set to 1.0 instead.
+    pub fn fill_inputs_silence(&mut self, impulse: bool) {
+        for channel_slice in self.inputs.iter_mut().flatten() {
+            channel_slice.fill(0.0);
+            if impulse {
+                if let Some(first_sample) = channel_slice.first_mut() {
+                    *first_sample = 1.0;
+                }
+            }
+        }
+    }
 }
 
 impl EventQueue<clap_input_events> {
@@ -490,13 +663,73 @@ impl Event {
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_MOD) => {
                 Ok(Event::ParamMod(*(ptr as *const clap_event_param_mod)))
             }
+            (
+                CLAP_CORE_EVENT_SPACE_ID,
+                CLAP_EVENT_PARAM_GESTURE_BEGIN | CLAP_EVENT_PARAM_GESTURE_END,
+            ) => Ok(Event::ParamGesture(
+                *(ptr as *const clap_event_param_gesture),
+            )),
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI) => {
                 Ok(Event::Midi(*(ptr as *const clap_event_midi)))
             }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI2) => {
+                Ok(Event::Midi2(*(ptr as *const clap_event_midi2)))
+            }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI_SYSEX) => {
+                // The buffer is only guaranteed to be valid during the `try_push()` call, so the
+                // data needs to be copied
+                let event = *(ptr as *const clap_event_midi_sysex);
+                if event.buffer.is_null() || event.size as usize > MAX_SYSEX_SIZE {
+                    Ok(Event::MidiSysex(MidiSysexEvent {
+                        event,
+                        data: Arc::from([]),
+                    }))
+                } else {
+                    let mut sysex_event = MidiSysexEvent::new(
+                        event.header,
+                        event.port_index,
+                        std::slice::from_raw_parts(event.buffer, event.size as usize).to_vec(),
+                    );
+                    // The plugin's size field is checked as part of the output consistency checks
+                    sysex_event.event.header.size = event.header.size;
+
+                    Ok(Event::MidiSysex(sysex_event))
+                }
+            }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_TRANSPORT) => {
+                Ok(Event::Transport(*(ptr as *const clap_event_transport)))
+            }
             (_, _) => Ok(Event::Unknown(*ptr)),
         }
     }
 
+    /// Create an [`Event::Oversized`] copy of this event with `extra_bytes` garbage bytes appended
+    /// to the event's struct. `header.size` is increased accordingly. The plugin should only read
+    /// the part of the event it knows about.
+    pub fn oversized(&self, extra_bytes: usize) -> Event {
+        let header = self.header();
+        let event_size = header.size as usize;
+        let oversized_size = event_size + extra_bytes;
+
+        // The garbage is a recognizable bit pattern, which makes it easier to spot in a debugger
+        let mut data = vec![0xDEAD_BEEF_DEAD_BEEFu64; (oversized_size + 7) / 8];
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                header as *const clap_event_header as *const u8,
+                data.as_mut_ptr() as *mut u8,
+                event_size,
+            );
+            (*(data.as_mut_ptr() as *mut clap_event_header)).size = oversized_size as u32;
+        }
+
+        let sysex_data = match self {
+            Event::MidiSysex(event) => Some(event.data.clone()),
+            _ => None,
+        };
+
+        Event::Oversized(data, sysex_data)
+    }
+
     /// Get a a reference to the event's header.
     pub fn header(&self) -> &clap_event_header {
         match self {
@@ -504,12 +737,52 @@ impl Event {
             Event::NoteExpression(event) => &event.header,
             Event::ParamValue(event) => &event.header,
             Event::ParamMod(event) => &event.header,
+            Event::ParamGesture(event) => &event.header,
             Event::Midi(event) => &event.header,
+            Event::Midi2(event) => &event.header,
+            Event::MidiSysex(event) => &event.event.header,
+            Event::Transport(event) => &event.header,
+            // SAFETY: This always starts with a copy of another event, see `Event::oversized()`
+            Event::Oversized(data, _) => unsafe { &*(data.as_ptr() as *const clap_event_header) },
             Event::Unknown(header) => header,
         }
     }
 }
 
+impl MidiSysexEvent {
+    /// Create a sysex event that points to `data`. `header.size` is set to the size of the event's
+    /// struct.
+    pub fn new(header: clap_event_header, port_index: u16, data: Vec<u8>) -> Self {
+        let data: Arc<[u8]> = Arc::from(data);
+
+        MidiSysexEvent {
+            event: clap_event_midi_sysex {
+                header: clap_event_header {
+                    size: std::mem::size_of::<clap_event_midi_sysex>() as u32,
+                    ..header
+                },
+                port_index,
+                buffer: data.as_ptr(),
+                size: data.len() as u32,
+            },
+            data,
+        }
+    }
+}
+
+/// Fill a CV port's channels with a random value between `[-1, 1]` for each channel. This value is
+/// held for the entire buffer, like a control signal would be.
+fn randomize_cv_buffers(prng: &mut Pcg32, channel_slices: &mut [Vec<f32>]) {
+    for channel_slice in channel_slices {
+        let mut value = prng.gen_range(-1.0..=1.0);
+        if f32::is_subnormal(value) {
+            value = 0.0;
+        }
+
+        channel_slice.fill(value);
+    }
+}
+
 /// Set each sample in the buffers to a random value in `[-1, 1]`. Denormals are snapped to zero.
 fn randomize_audio_buffers(prng: &mut Pcg32, buffers: &mut [Vec<Vec<f32>>]) {
     for channel_slices in buffers {
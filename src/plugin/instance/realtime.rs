@@ -0,0 +1,77 @@
+//! Utilities for running the audio thread with realtime scheduling, like a real host would. This
+//! is only done when the validator's `--realtime-audio-thread` option is enabled.
+
+use anyhow::Result;
+
+/// The `SCHED_FIFO` priority requested for the audio thread on Unix-like platforms. This is
+/// clamped to the range supported by the OS. Like most audio software, this stays below the
+/// maximum priority so the audio thread doesn't starve the kernel's own realtime threads.
+#[cfg(unix)]
+const AUDIO_THREAD_PRIORITY: i32 = 80;
+
+/// Try to give the calling thread realtime scheduling or the highest priority the OS permits.
+/// Returns an error describing why this was not possible otherwise. This commonly fails for
+/// unprivileged users, in which case the thread keeps running with its normal priority.
+#[cfg(unix)]
+pub fn promote_current_thread() -> Result<()> {
+    let min_priority = unsafe { libc::sched_get_priority_min(libc::SCHED_FIFO) };
+    let max_priority = unsafe { libc::sched_get_priority_max(libc::SCHED_FIFO) };
+    if min_priority < 0 || max_priority < 0 {
+        anyhow::bail!("Could not query the priority range for the 'SCHED_FIFO' scheduling policy.");
+    }
+
+    // `sched_param` contains additional reserved fields on some platforms
+    let mut param: libc::sched_param = unsafe { std::mem::zeroed() };
+    param.sched_priority = AUDIO_THREAD_PRIORITY.clamp(min_priority, max_priority);
+    let result =
+        unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+    if result != 0 {
+        anyhow::bail!(
+            "'pthread_setschedparam()' could not enable the 'SCHED_FIFO' scheduling policy: {}",
+            std::io::Error::from_raw_os_error(result)
+        );
+    }
+
+    Ok(())
+}
+
+/// Try to give the calling thread realtime scheduling or the highest priority the OS permits.
+/// Returns an error describing why this was not possible otherwise.
+#[cfg(windows)]
+pub fn promote_current_thread() -> Result<()> {
+    let success =
+        unsafe { windows::SetThreadPriority(windows::GetCurrentThread(), windows::PRIORITY) };
+    if success == 0 {
+        anyhow::bail!(
+            "'SetThreadPriority()' could not set the 'THREAD_PRIORITY_TIME_CRITICAL' priority: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+
+    Ok(())
+}
+
+/// Try to give the calling thread realtime scheduling or the highest priority the OS permits.
+/// Returns an error describing why this was not possible otherwise.
+#[cfg(not(any(unix, windows)))]
+pub fn promote_current_thread() -> Result<()> {
+    anyhow::bail!("Realtime audio threads are not supported on this platform.")
+}
+
+#[cfg(windows)]
+#[allow(non_snake_case, clippy::upper_case_acronyms)]
+mod windows {
+    use std::ffi::c_void;
+
+    type BOOL = i32;
+    type HANDLE = *mut c_void;
+
+    /// `THREAD_PRIORITY_TIME_CRITICAL`.
+    pub const PRIORITY: i32 = 15;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetCurrentThread() -> HANDLE;
+        pub fn SetThreadPriority(thread: HANDLE, priority: i32) -> BOOL;
+    }
+}
@@ -9,8 +9,11 @@ use clap_sys::factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY
 use clap_sys::plugin::clap_plugin_descriptor;
 use clap_sys::version::clap_version;
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
-use std::ffi::CString;
+use std::ffi::{c_void, CString};
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
 use std::rc::Rc;
@@ -20,6 +23,20 @@ use super::preset_discovery::PresetDiscoveryFactory;
 use crate::plugin::host::Host;
 use crate::util::{self, unsafe_clap_call};
 
+mod binary;
+#[cfg(test)]
+pub mod reference;
+
+/// The ID of the draft plugin invalidation factory from `factory/draft/plugin-invalidation.h`.
+pub const PLUGIN_INVALIDATION_FACTORY_ID: &str = "clap.plugin-invalidation-factory/1";
+/// The ID of the draft plugin state converter factory from
+/// `factory/draft/plugin-state-converter.h`.
+pub const PLUGIN_STATE_CONVERTER_FACTORY_ID: &str = "clap.plugin-state-converter-factory/1";
+/// The ID of the ARA factory from the ARA SDK's CLAP integration.
+pub const ARA_FACTORY_ID: &str = "org.ara-audio.ara.factory/2";
+/// The ID of the factory clap-wrapper uses to provide additional information for VST3 wrappers.
+pub const PLUGIN_FACTORY_INFO_VST3_ID: &str = "clap.plugin-factory-info-as-vst3/0";
+
 /// A CLAP plugin library built from a CLAP plugin's entry point. This can be used to iterate over
 /// all plugins exposed by the library and to initialize plugins.
 #[derive(Debug)]
@@ -27,22 +44,38 @@ pub struct PluginLibrary {
     /// The path to this plugin. On macOS, this points to the bundle's root instead of the library
     /// contained within the bundle.
     plugin_path: PathBuf,
-    /// The plugin's library. Its entry point has already been initialized, and it will
-    /// autoamtically be deinitialized when this object gets dropped.
-    library: libloading::Library,
+    /// The path to the plugin's actual binary. On macOS this is the library contained within the
+    /// bundle. Otherwise this is the same as `plugin_path`.
+    binary_path: PathBuf,
+    /// The plugin's entry point. It has already been initialized, and it will automatically be
+    /// deinitialized when this object gets dropped.
+    entry_point: EntryPoint,
+}
+
+/// Where a [`PluginLibrary`]'s entry point comes from.
+#[derive(Debug)]
+enum EntryPoint {
+    /// The `clap_entry` symbol exported by a dynamically loaded plugin library.
+    Library(libloading::Library),
+    /// An entry point that's compiled into the validator itself, used to run the validator's own
+    /// tests against the [reference plugin][reference].
+    #[cfg(test)]
+    Static(&'static clap_plugin_entry),
 }
 
 /// Metadata for a CLAP plugin library, which may contain multiple plugins.
 #[derive(Debug, Serialize)]
 pub struct PluginLibraryMetadata {
     pub version: (u32, u32, u32),
+    /// The IDs of the [known factories][known_factory_ids()] the plugin library exposes.
+    pub factories: Vec<String>,
     pub plugins: Vec<PluginMetadata>,
 }
 
 /// Metadata for a single plugin within a CLAP plugin library. See
 /// [plugin.h](https://github.com/free-audio/clap/blob/main/include/clap/plugin.h) for a description
 /// of the fields.
-#[derive(Debug, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 pub struct PluginMetadata {
     pub id: String,
     pub name: String,
@@ -82,8 +115,7 @@ impl Drop for PluginLibrary {
     fn drop(&mut self) {
         // The `Plugin` only exists if `init()` returned true, so we ned to deinitialize the
         // plugin here
-        let entry_point = get_clap_entry_point(&self.library)
-            .expect("A Plugin was constructed for a plugin with no entry point");
+        let entry_point = self.entry_point();
         unsafe_clap_call! { entry_point=>deinit() };
     }
 }
@@ -122,9 +154,12 @@ impl PluginLibrary {
 
         // NOTE: Apple says you can dlopen() bundles. This is a lie.
         #[cfg(not(target_os = "macos"))]
-        let library = load(&path)?;
+        let (library, binary_path) = (
+            load(&path).map_err(|err| explain_load_error(err, &path))?,
+            path.clone(),
+        );
         #[cfg(target_os = "macos")]
-        let library = {
+        let (library, binary_path) = {
             use core_foundation::bundle::CFBundle;
             use core_foundation::url::CFURL;
 
@@ -139,31 +174,83 @@ impl PluginLibrary {
                 .to_path()
                 .context("Could not convert bundle executable path")?;
 
-            load(&library_path)?
+            (
+                load(&library_path).map_err(|err| explain_load_error(err, &library_path))?,
+                library_path,
+            )
         };
 
         // The entry point needs to be initialized before it can be used. It will be deinitialized
         // when the `Plugin` object is dropped.
-        let entry_point = get_clap_entry_point(&library)?;
+        let entry_point = get_clap_entry_point(&library).with_context(|| {
+            format!(
+                "'{}' is a shared library, but it is not a CLAP plugin",
+                binary_path.display()
+            )
+        })?;
         if !unsafe_clap_call! { entry_point=>init(path_cstring.as_ptr()) } {
             anyhow::bail!("'clap_plugin_entry::init({path_cstring:?})' returned false.");
         }
 
         Ok(PluginLibrary {
             plugin_path: path,
-            library,
+            binary_path,
+            entry_point: EntryPoint::Library(library),
         })
     }
 
+    /// Get the library's entry point. This has already been initialized.
+    fn entry_point(&self) -> &clap_plugin_entry {
+        match &self.entry_point {
+            EntryPoint::Library(library) => get_clap_entry_point(library)
+                .expect("A Plugin was constructed for a plugin with no entry point"),
+            #[cfg(test)]
+            EntryPoint::Static(entry_point) => entry_point,
+        }
+    }
+
     pub fn plugin_path(&self) -> &Path {
         &self.plugin_path
     }
 
+    /// Compute the SHA-256 hash of the plugin's binary, formatted as a lowercase hexadecimal
+    /// string. On macOS this hashes the library contained within the bundle. This can be used to
+    /// verify that the validated plugin is byte-identical to a released version.
+    pub fn binary_sha256(&self) -> Result<String> {
+        let mut file = fs::File::open(&self.binary_path).with_context(|| {
+            format!(
+                "Could not open '{}' for hashing",
+                self.binary_path.display()
+            )
+        })?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)
+            .with_context(|| format!("Could not hash '{}'", self.binary_path.display()))?;
+
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect())
+    }
+
     /// Get the metadata for all plugins stored in this plugin library. Most plugin libraries
     /// contain a single plugin, but this may return metadata for zero or more plugins.
     pub fn metadata(&self) -> Result<PluginLibraryMetadata> {
-        let entry_point = get_clap_entry_point(&self.library)
-            .expect("A Plugin was constructed for a plugin with no entry point");
+        let (metadata, broken_descriptors) = self.metadata_lenient()?;
+        match broken_descriptors.into_iter().next() {
+            Some((_, err)) => Err(err),
+            None => Ok(metadata),
+        }
+    }
+
+    /// The same as [`metadata()`][Self::metadata()], but a single plugin with a broken descriptor
+    /// doesn't cause the entire query to fail. The descriptors that could not be read are returned
+    /// separately along with their index in the plugin factory, so the library's other plugins can
+    /// still be validated. This still returns an error if the plugin factory itself is missing or
+    /// if it contains duplicate plugin IDs.
+    pub fn metadata_lenient(&self) -> Result<(PluginLibraryMetadata, Vec<(u32, anyhow::Error)>)> {
+        let entry_point = self.entry_point();
         let plugin_factory = unsafe_clap_call! { entry_point=>get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()) }
             as *const clap_plugin_factory;
         // TODO: Should we log anything here? In theory not supporting the plugin factory is
@@ -181,50 +268,75 @@ impl PluginLibrary {
                 entry_point.clap_version.minor,
                 entry_point.clap_version.revision,
             ),
+            factories: known_factory_ids()
+                .into_iter()
+                .filter(|factory_id| self.factory_exists(factory_id))
+                .map(String::from)
+                .collect(),
             plugins: Vec::new(),
         };
+        let mut broken_descriptors = Vec::new();
         let num_plugins = unsafe_clap_call! { plugin_factory=>get_plugin_count(plugin_factory) };
         for i in 0..num_plugins {
             let descriptor =
                 unsafe_clap_call! { plugin_factory=>get_plugin_descriptor(plugin_factory, i) };
             if descriptor.is_null() {
-                anyhow::bail!(
-                    "The plugin returned a null plugin descriptor for plugin index {i} (expected \
-                     {num_plugins} total plugins)."
-                );
+                broken_descriptors.push((
+                    i,
+                    anyhow::anyhow!(
+                        "The plugin returned a null plugin descriptor for plugin index {i} \
+                         (expected {num_plugins} total plugins)."
+                    ),
+                ));
+                continue;
             }
 
-            metadata
-                .plugins
-                .push(PluginMetadata::from_descriptor(unsafe { &*descriptor })?)
+            match PluginMetadata::from_descriptor(unsafe { &*descriptor }) {
+                Ok(plugin_metadata) => metadata.plugins.push(plugin_metadata),
+                Err(err) => broken_descriptors.push((i, err)),
+            }
         }
 
         // As a sanity check we'll make sure there are no duplicate plugin IDs here
-        let unique_plugin_ids: HashSet<&str> = metadata
-            .plugins
-            .iter()
-            .map(|plugin_metadata| plugin_metadata.id.as_str())
-            .collect();
-        if unique_plugin_ids.len() != metadata.plugins.len() {
-            anyhow::bail!("The plugin's factory contains multiple entries for the same plugin ID.");
+        let mut unique_plugin_ids: HashSet<&str> = HashSet::new();
+        let mut duplicate_plugin_ids: Vec<&str> = Vec::new();
+        for plugin_metadata in &metadata.plugins {
+            if !unique_plugin_ids.insert(plugin_metadata.id.as_str())
+                && !duplicate_plugin_ids.contains(&plugin_metadata.id.as_str())
+            {
+                duplicate_plugin_ids.push(plugin_metadata.id.as_str());
+            }
+        }
+        if !duplicate_plugin_ids.is_empty() {
+            anyhow::bail!(
+                "The plugin's factory contains multiple entries for the same plugin ID: {}.",
+                duplicate_plugin_ids
+                    .iter()
+                    .map(|id| format!("'{id}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
 
-        Ok(metadata)
+        Ok((metadata, broken_descriptors))
     }
 
     /// Returns whether or not a factory with the specified ID exists. This is used in a test to
     /// assert that querying a factory with a non-existent ID returns a null pointer instead of
     /// always returning the plugin factory.
     pub fn factory_exists(&self, factory_id: &str) -> bool {
+        !self.factory_pointer(factory_id).is_null()
+    }
+
+    /// Returns the raw pointer returned by `clap_entry::get_factory()` for the specified factory
+    /// ID. This is only used in tests to check whether the plugin returns the same pointer every
+    /// time a factory is queried.
+    pub fn factory_pointer(&self, factory_id: &str) -> *const c_void {
         let factory_id_cstring =
             CString::new(factory_id).expect("The factory ID contained internal null bytes");
 
-        let entry_point = get_clap_entry_point(&self.library)
-            .expect("A Plugin was constructed for a plugin with no entry point");
-        let factory_pointer =
-            unsafe_clap_call! { entry_point=>get_factory(factory_id_cstring.as_ptr()) };
-
-        !factory_pointer.is_null()
+        let entry_point = self.entry_point();
+        unsafe_clap_call! { entry_point=>get_factory(factory_id_cstring.as_ptr()) }
     }
 
     /// Try to create the plugin with the given ID, and using the provided host instance. The plugin
@@ -232,8 +344,7 @@ impl PluginLibrary {
     /// [`metadata()`][Self::metadata()]. The returned plugin has not yet been initialized, and
     /// `destroy()` will be called automatically when the object is dropped.
     pub fn create_plugin(&self, id: &str, host: Rc<Host>) -> Result<Plugin> {
-        let entry_point = get_clap_entry_point(&self.library)
-            .expect("A Plugin was constructed for a plugin with no entry point");
+        let entry_point = self.entry_point();
         let plugin_factory = unsafe_clap_call! { entry_point=>get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()) }
             as *const clap_plugin_factory;
         if plugin_factory.is_null() {
@@ -249,8 +360,7 @@ impl PluginLibrary {
 
     /// Returns the plugin's preset discovery factory, if it has one.
     pub fn preset_discovery_factory(&self) -> Result<PresetDiscoveryFactory> {
-        let entry_point = get_clap_entry_point(&self.library)
-            .expect("A Plugin was constructed for a plugin with no entry point");
+        let entry_point = self.entry_point();
         let preset_discovery_factory = unsafe_clap_call! {
             entry_point=>get_factory(CLAP_PRESET_DISCOVERY_FACTORY_ID.as_ptr())
         } as *mut clap_preset_discovery_factory;
@@ -280,6 +390,29 @@ impl PluginLibraryMetadata {
     }
 }
 
+/// The IDs of all factories whose presence is reported in a plugin library's metadata. Besides the
+/// factories the validator interacts with, this includes draft and third party factories so their
+/// support can be read from the plugin index.
+pub fn known_factory_ids() -> [&'static str; 6] {
+    [
+        CLAP_PLUGIN_FACTORY_ID.to_str().unwrap(),
+        CLAP_PRESET_DISCOVERY_FACTORY_ID.to_str().unwrap(),
+        PLUGIN_INVALIDATION_FACTORY_ID,
+        PLUGIN_STATE_CONVERTER_FACTORY_ID,
+        ARA_FACTORY_ID,
+        PLUGIN_FACTORY_INFO_VST3_ID,
+    ]
+}
+
+/// Add a more specific explanation to an error from loading a plugin's binary if the binary turns
+/// out not to be a shared library for this platform and architecture.
+fn explain_load_error(err: anyhow::Error, binary_path: &Path) -> anyhow::Error {
+    match binary::diagnose_load_error(binary_path) {
+        Some(explanation) => err.context(explanation),
+        None => err,
+    }
+}
+
 /// Get a plugin's entry point.
 fn get_clap_entry_point(library: &libloading::Library) -> Result<&clap_plugin_entry> {
     let entry_point: libloading::Symbol<*const clap_plugin_entry> =
@@ -291,3 +424,60 @@ fn get_clap_entry_point(library: &libloading::Library) -> Result<&clap_plugin_en
 
     Ok(unsafe { &**entry_point })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Compile a shared library that exports a single function, but no `clap_entry` symbol, into
+    /// `dir`. This is a shared library, but it is certainly not a CLAP plugin.
+    fn compile_fixture_library(dir: &tempfile::TempDir) -> PathBuf {
+        let source_path = dir.path().join("fixture.rs");
+        fs::write(
+            &source_path,
+            "#[no_mangle]\npub extern \"C\" fn not_a_clap_plugin() {}\n",
+        )
+        .expect("Could not write the fixture library's source code");
+
+        let library_path = dir
+            .path()
+            .join(format!("fixture.{}", std::env::consts::DLL_EXTENSION));
+        let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let status = Command::new(rustc)
+            .args(["--crate-type", "cdylib", "--crate-name", "fixture", "-o"])
+            .arg(&library_path)
+            .arg(&source_path)
+            .status()
+            .expect("Could not run rustc to compile the fixture library");
+        assert!(status.success(), "Could not compile the fixture library");
+
+        library_path
+    }
+
+    // On macOS plugins are loaded from bundles, and the fixture library is not a bundle
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn shared_library_without_entry_point() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = compile_fixture_library(&dir);
+
+        let err = match PluginLibrary::load(&path) {
+            Ok(_) => panic!("'{}' was loaded as a CLAP plugin", path.display()),
+            Err(err) => err,
+        };
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "'{}' is a shared library, but it is not a CLAP plugin",
+                path.display()
+            )
+        );
+        assert!(
+            err.chain()
+                .any(|cause| cause.to_string()
+                    == "The library does not expose a 'clap_entry' symbol"),
+            "Unexpected error: {err:#}"
+        );
+    }
+}
@@ -0,0 +1,651 @@
+//! Inspection of binaries that could not be loaded as a plugin library. The dynamic loader's errors
+//! are often not very descriptive, so this tries to figure out whether the file is a shared library
+//! for this platform and architecture in the first place.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// The number of bytes read from the start of the binary. This covers the ELF and Mach-O headers,
+/// and the PE header in all but the most unusual DLLs.
+const HEADER_SIZE: u64 = 4096;
+
+/// `e_type` for ELF relocatable object files.
+const ELF_TYPE_RELOCATABLE: u16 = 1;
+/// `e_type` for ELF executables.
+const ELF_TYPE_EXECUTABLE: u16 = 2;
+/// `filetype` for Mach-O object files.
+const MACH_O_TYPE_OBJECT: u32 = 1;
+/// `filetype` for Mach-O executables.
+const MACH_O_TYPE_EXECUTE: u32 = 2;
+/// The `IMAGE_FILE_DLL` flag in a PE file's characteristics.
+const PE_CHARACTERISTICS_DLL: u16 = 0x2000;
+
+/// The binary formats the validator can recognize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display)]
+enum BinaryFormat {
+    #[strum(serialize = "ELF")]
+    Elf,
+    #[strum(serialize = "Mach-O")]
+    MachO,
+    /// A universal binary containing Mach-O binaries for one or more architectures.
+    #[strum(serialize = "universal Mach-O")]
+    MachOUniversal,
+    #[strum(serialize = "PE")]
+    Pe,
+}
+
+/// The format shared libraries use on this platform.
+#[cfg(all(unix, not(target_os = "macos")))]
+const NATIVE_FORMAT: BinaryFormat = BinaryFormat::Elf;
+/// The format shared libraries use on this platform.
+#[cfg(target_os = "macos")]
+const NATIVE_FORMAT: BinaryFormat = BinaryFormat::MachO;
+/// The format shared libraries use on this platform.
+#[cfg(windows)]
+const NATIVE_FORMAT: BinaryFormat = BinaryFormat::Pe;
+
+/// Try to explain why the binary at `path` could not be loaded. Returns `None` if the file looks
+/// like a shared library for this platform and architecture, or if it could not be read. The
+/// dynamic loader's error is the most specific explanation available in that case.
+pub fn diagnose_load_error(path: &Path) -> Option<String> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Some(format!("'{}' does not exist.", path.display()))
+        }
+        Err(_) => return None,
+    };
+    if metadata.is_dir() {
+        return Some(format!(
+            "'{}' is a directory, not a shared library.",
+            path.display()
+        ));
+    }
+
+    let mut header = Vec::new();
+    fs::File::open(path)
+        .and_then(|file| file.take(HEADER_SIZE).read_to_end(&mut header))
+        .ok()?;
+
+    let format = match binary_format(&header) {
+        Some(format) => format,
+        None => {
+            return Some(format!(
+                "'{}' is not a shared library. CLAP plugins on {} need to be {NATIVE_FORMAT} \
+                 shared libraries.",
+                path.display(),
+                std::env::consts::OS
+            ))
+        }
+    };
+    let is_native_format = format == NATIVE_FORMAT
+        || (format == BinaryFormat::MachOUniversal && NATIVE_FORMAT == BinaryFormat::MachO);
+    if !is_native_format {
+        return Some(format!(
+            "'{}' is a {format} binary, but CLAP plugins on {} need to be {NATIVE_FORMAT} shared \
+             libraries.",
+            path.display(),
+            std::env::consts::OS
+        ));
+    }
+
+    let (is_shared_library, arch) = match format {
+        BinaryFormat::Elf => elf_info(&header)?,
+        BinaryFormat::MachO => mach_o_info(&header)?,
+        BinaryFormat::Pe => pe_info(&header)?,
+        // The architectures in a universal binary are listed in the file's header, but the loader's
+        // error already explains when none of them match
+        BinaryFormat::MachOUniversal => return None,
+    };
+    if !is_shared_library {
+        return Some(format!(
+            "'{}' is a {format} binary, but it is not a shared library.",
+            path.display()
+        ));
+    }
+    if arch != std::env::consts::ARCH {
+        return Some(format!(
+            "'{}' was built for the {arch} architecture, but the validator is running on {}. The \
+             plugin and the validator need to use the same architecture.",
+            path.display(),
+            std::env::consts::ARCH
+        ));
+    }
+
+    None
+}
+
+/// Determine a binary's format from its magic number.
+fn binary_format(header: &[u8]) -> Option<BinaryFormat> {
+    match header.get(..4)? {
+        [0x7f, b'E', b'L', b'F'] => Some(BinaryFormat::Elf),
+        [0xfe, 0xed, 0xfa, 0xce | 0xcf] | [0xce | 0xcf, 0xfa, 0xed, 0xfe] => {
+            Some(BinaryFormat::MachO)
+        }
+        [0xca, 0xfe, 0xba, 0xbe] => Some(BinaryFormat::MachOUniversal),
+        [b'M', b'Z', _, _] => Some(BinaryFormat::Pe),
+        _ => None,
+    }
+}
+
+/// Returns whether an ELF binary is a shared library, and the architecture it was built for.
+/// Position independent executables also use the shared object type, so those are not caught here.
+fn elf_info(header: &[u8]) -> Option<(bool, String)> {
+    let is_64bit = *header.get(4)? == 2;
+    let little_endian = *header.get(5)? == 1;
+    let file_type = read_u16(header, 16, little_endian)?;
+    let machine = read_u16(header, 18, little_endian)?;
+
+    let arch = match (machine, is_64bit) {
+        (3, _) => Some("x86"),
+        (62, _) => Some("x86_64"),
+        (40, _) => Some("arm"),
+        (183, _) => Some("aarch64"),
+        (243, true) => Some("riscv64"),
+        (243, false) => Some("riscv32"),
+        (8, _) => Some("mips"),
+        (20, _) => Some("powerpc"),
+        (21, _) => Some("powerpc64"),
+        (22, _) => Some("s390x"),
+        (258, _) => Some("loongarch64"),
+        _ => None,
+    };
+    let is_shared_library = !matches!(file_type, ELF_TYPE_RELOCATABLE | ELF_TYPE_EXECUTABLE);
+
+    Some((
+        is_shared_library,
+        arch.map(String::from)
+            .unwrap_or_else(|| format!("unknown (ELF machine {machine})")),
+    ))
+}
+
+/// Returns whether a Mach-O binary is a shared library or a bundle, and the architecture it was
+/// built for.
+fn mach_o_info(header: &[u8]) -> Option<(bool, String)> {
+    let little_endian = *header.first()? != 0xfe;
+    let cpu_type = read_u32(header, 4, little_endian)?;
+    let file_type = read_u32(header, 12, little_endian)?;
+
+    let arch = match cpu_type {
+        7 => Some("x86"),
+        0x0100_0007 => Some("x86_64"),
+        12 => Some("arm"),
+        0x0100_000c => Some("aarch64"),
+        18 => Some("powerpc"),
+        0x0100_0012 => Some("powerpc64"),
+        _ => None,
+    };
+    let is_shared_library = !matches!(file_type, MACH_O_TYPE_OBJECT | MACH_O_TYPE_EXECUTE);
+
+    Some((
+        is_shared_library,
+        arch.map(String::from)
+            .unwrap_or_else(|| format!("unknown (Mach-O CPU type {cpu_type:#x})")),
+    ))
+}
+
+/// Returns whether a PE binary is a DLL, and the architecture it was built for.
+fn pe_info(header: &[u8]) -> Option<(bool, String)> {
+    let pe_header_offset = read_u32(header, 0x3c, true)? as usize;
+    if pe_header_offset >= header.len()
+        || header.get(pe_header_offset..pe_header_offset + 4)? != b"PE\0\0"
+    {
+        return None;
+    }
+    let machine = read_u16(header, pe_header_offset + 4, true)?;
+    let characteristics = read_u16(header, pe_header_offset + 22, true)?;
+
+    let arch = match machine {
+        0x014c => Some("x86"),
+        0x8664 => Some("x86_64"),
+        0x01c0 | 0x01c4 => Some("arm"),
+        0xaa64 => Some("aarch64"),
+        _ => None,
+    };
+    let is_shared_library = characteristics & PE_CHARACTERISTICS_DLL != 0;
+
+    Some((
+        is_shared_library,
+        arch.map(String::from)
+            .unwrap_or_else(|| format!("unknown (PE machine {machine:#x})")),
+    ))
+}
+
+fn read_u16(header: &[u8], offset: usize, little_endian: bool) -> Option<u16> {
+    let bytes: [u8; 2] = header.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if little_endian {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    })
+}
+
+fn read_u32(header: &[u8], offset: usize, little_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = header.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if little_endian {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// `e_type` for ELF shared objects.
+    const ELF_TYPE_SHARED_OBJECT: u16 = 3;
+    /// `filetype` for Mach-O dynamic libraries.
+    const MACH_O_TYPE_DYLIB: u32 = 6;
+    /// `filetype` for Mach-O bundles, which is what CLAP plugins on macOS usually are.
+    const MACH_O_TYPE_BUNDLE: u32 = 8;
+    /// The offset of the PE header in [`pe_header()`].
+    const PE_HEADER_OFFSET: usize = 0x80;
+
+    /// Build the start of an ELF file with the given class, byte order, file type, and machine.
+    fn elf_header(is_64bit: bool, little_endian: bool, file_type: u16, machine: u16) -> Vec<u8> {
+        let mut header = vec![0; 64];
+        header[..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+        header[4] = if is_64bit { 2 } else { 1 };
+        header[5] = if little_endian { 1 } else { 2 };
+        header[6] = 1;
+        let (file_type, machine) = if little_endian {
+            (file_type.to_le_bytes(), machine.to_le_bytes())
+        } else {
+            (file_type.to_be_bytes(), machine.to_be_bytes())
+        };
+        header[16..18].copy_from_slice(&file_type);
+        header[18..20].copy_from_slice(&machine);
+
+        header
+    }
+
+    /// Build the start of a Mach-O file. The magic number determines the byte order.
+    fn mach_o_header(magic: [u8; 4], cpu_type: u32, file_type: u32) -> Vec<u8> {
+        let little_endian = magic[0] != 0xfe;
+        let mut header = vec![0; 32];
+        header[..4].copy_from_slice(&magic);
+        let (cpu_type, file_type) = if little_endian {
+            (cpu_type.to_le_bytes(), file_type.to_le_bytes())
+        } else {
+            (cpu_type.to_be_bytes(), file_type.to_be_bytes())
+        };
+        header[4..8].copy_from_slice(&cpu_type);
+        header[12..16].copy_from_slice(&file_type);
+
+        header
+    }
+
+    /// Build the start of a PE file with a DOS stub pointing to a PE header at
+    /// [`PE_HEADER_OFFSET`].
+    fn pe_header(machine: u16, characteristics: u16) -> Vec<u8> {
+        let mut header = vec![0; 512];
+        header[..2].copy_from_slice(b"MZ");
+        header[0x3c..0x40].copy_from_slice(&(PE_HEADER_OFFSET as u32).to_le_bytes());
+        header[PE_HEADER_OFFSET..PE_HEADER_OFFSET + 4].copy_from_slice(b"PE\0\0");
+        header[PE_HEADER_OFFSET + 4..PE_HEADER_OFFSET + 6].copy_from_slice(&machine.to_le_bytes());
+        header[PE_HEADER_OFFSET + 22..PE_HEADER_OFFSET + 24]
+            .copy_from_slice(&characteristics.to_le_bytes());
+
+        header
+    }
+
+    /// Build the start of a shared library or executable in this platform's native format for
+    /// either x86_64 or AArch64.
+    fn native_header(is_shared_library: bool, aarch64: bool) -> Vec<u8> {
+        match NATIVE_FORMAT {
+            BinaryFormat::Elf => elf_header(
+                true,
+                true,
+                if is_shared_library {
+                    ELF_TYPE_SHARED_OBJECT
+                } else {
+                    ELF_TYPE_EXECUTABLE
+                },
+                if aarch64 { 183 } else { 62 },
+            ),
+            BinaryFormat::MachO | BinaryFormat::MachOUniversal => mach_o_header(
+                [0xcf, 0xfa, 0xed, 0xfe],
+                if aarch64 { 0x0100_000c } else { 0x0100_0007 },
+                if is_shared_library {
+                    MACH_O_TYPE_BUNDLE
+                } else {
+                    MACH_O_TYPE_EXECUTE
+                },
+            ),
+            BinaryFormat::Pe => pe_header(
+                if aarch64 { 0xaa64 } else { 0x8664 },
+                if is_shared_library {
+                    PE_CHARACTERISTICS_DLL
+                } else {
+                    0
+                },
+            ),
+        }
+    }
+
+    /// Write a fixture file with the given contents to `dir`, and return its path.
+    fn write_fixture(dir: &tempfile::TempDir, name: &str, contents: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).expect("Could not write the fixture file");
+
+        path
+    }
+
+    #[test]
+    fn diagnose_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.clap");
+        assert_eq!(
+            diagnose_load_error(&path),
+            Some(format!("'{}' does not exist.", path.display()))
+        );
+    }
+
+    #[test]
+    fn diagnose_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            diagnose_load_error(dir.path()),
+            Some(format!(
+                "'{}' is a directory, not a shared library.",
+                dir.path().display()
+            ))
+        );
+    }
+
+    #[test]
+    fn diagnose_text_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in [
+            ("plugin.clap", &b"This is not a plugin.\n"[..]),
+            ("script.clap", b"#!/bin/sh\necho 'not a plugin'\n"),
+            ("empty.clap", b""),
+        ] {
+            let path = write_fixture(&dir, name, contents);
+            assert_eq!(
+                diagnose_load_error(&path),
+                Some(format!(
+                    "'{}' is not a shared library. CLAP plugins on {} need to be {NATIVE_FORMAT} \
+                     shared libraries.",
+                    path.display(),
+                    std::env::consts::OS
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn diagnose_foreign_format() {
+        let (foreign_format, header) = if NATIVE_FORMAT == BinaryFormat::Pe {
+            (
+                BinaryFormat::Elf,
+                elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 62),
+            )
+        } else {
+            (BinaryFormat::Pe, pe_header(0x8664, PE_CHARACTERISTICS_DLL))
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(&dir, "foreign.clap", &header);
+        assert_eq!(
+            diagnose_load_error(&path),
+            Some(format!(
+                "'{}' is a {foreign_format} binary, but CLAP plugins on {} need to be \
+                 {NATIVE_FORMAT} shared libraries.",
+                path.display(),
+                std::env::consts::OS
+            ))
+        );
+    }
+
+    #[test]
+    fn diagnose_non_shared_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(&dir, "executable.clap", &native_header(false, false));
+        assert_eq!(
+            diagnose_load_error(&path),
+            Some(format!(
+                "'{}' is a {NATIVE_FORMAT} binary, but it is not a shared library.",
+                path.display()
+            ))
+        );
+    }
+
+    #[test]
+    fn diagnose_wrong_architecture() {
+        // This needs to be an architecture the validator is not running on
+        let (foreign_arch, is_aarch64) = if std::env::consts::ARCH == "aarch64" {
+            ("x86_64", false)
+        } else {
+            ("aarch64", true)
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(&dir, "foreign-arch.clap", &native_header(true, is_aarch64));
+        assert_eq!(
+            diagnose_load_error(&path),
+            Some(format!(
+                "'{}' was built for the {foreign_arch} architecture, but the validator is running \
+                 on {}. The plugin and the validator need to use the same architecture.",
+                path.display(),
+                std::env::consts::ARCH
+            ))
+        );
+    }
+
+    #[test]
+    fn diagnose_native_shared_library() {
+        // The loader's own error is the best explanation for shared libraries that look fine
+        let is_aarch64 = match std::env::consts::ARCH {
+            "x86_64" => false,
+            "aarch64" => true,
+            _ => return,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(&dir, "native.clap", &native_header(true, is_aarch64));
+        assert_eq!(diagnose_load_error(&path), None);
+    }
+
+    #[test]
+    fn detects_binary_formats() {
+        assert_eq!(
+            binary_format(&elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 62)),
+            Some(BinaryFormat::Elf)
+        );
+        for magic in [
+            [0xfe, 0xed, 0xfa, 0xce],
+            [0xfe, 0xed, 0xfa, 0xcf],
+            [0xce, 0xfa, 0xed, 0xfe],
+            [0xcf, 0xfa, 0xed, 0xfe],
+        ] {
+            assert_eq!(
+                binary_format(&mach_o_header(magic, 0x0100_000c, MACH_O_TYPE_BUNDLE)),
+                Some(BinaryFormat::MachO)
+            );
+        }
+        assert_eq!(
+            binary_format(&[0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 2]),
+            Some(BinaryFormat::MachOUniversal)
+        );
+        assert_eq!(
+            binary_format(&pe_header(0x8664, PE_CHARACTERISTICS_DLL)),
+            Some(BinaryFormat::Pe)
+        );
+    }
+
+    #[test]
+    fn rejects_junk() {
+        assert_eq!(binary_format(b""), None);
+        assert_eq!(binary_format(b"\x7fEL"), None);
+        assert_eq!(binary_format(b"#!/bin/sh\necho 'not a plugin'\n"), None);
+        assert_eq!(binary_format(&[0; 64]), None);
+
+        // Files with a valid magic number can still be truncated or malformed
+        assert_eq!(
+            elf_info(&elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 62)[..18]),
+            None
+        );
+        assert_eq!(mach_o_info(b""), None);
+        assert_eq!(mach_o_info(&[0xcf, 0xfa, 0xed, 0xfe, 0x0c, 0, 0, 1]), None);
+        assert_eq!(pe_info(b"MZ"), None);
+
+        let mut invalid_signature = pe_header(0x8664, PE_CHARACTERISTICS_DLL);
+        invalid_signature[PE_HEADER_OFFSET..PE_HEADER_OFFSET + 4].copy_from_slice(b"NE\0\0");
+        assert_eq!(pe_info(&invalid_signature), None);
+
+        let mut out_of_bounds_offset = pe_header(0x8664, PE_CHARACTERISTICS_DLL);
+        out_of_bounds_offset[0x3c..0x40].copy_from_slice(&0xffff_fff0u32.to_le_bytes());
+        assert_eq!(pe_info(&out_of_bounds_offset), None);
+    }
+
+    #[test]
+    fn elf_shared_libraries() {
+        assert_eq!(
+            elf_info(&elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 62)),
+            Some((true, String::from("x86_64")))
+        );
+        assert_eq!(
+            elf_info(&elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 183)),
+            Some((true, String::from("aarch64")))
+        );
+        assert_eq!(
+            elf_info(&elf_header(false, true, ELF_TYPE_SHARED_OBJECT, 3)),
+            Some((true, String::from("x86")))
+        );
+        assert_eq!(
+            elf_info(&elf_header(true, false, ELF_TYPE_SHARED_OBJECT, 21)),
+            Some((true, String::from("powerpc64")))
+        );
+    }
+
+    #[test]
+    fn elf_architectures() {
+        // RISC-V uses the same machine for both widths, so the class decides the architecture
+        assert_eq!(
+            elf_info(&elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 243)),
+            Some((true, String::from("riscv64")))
+        );
+        assert_eq!(
+            elf_info(&elf_header(false, true, ELF_TYPE_SHARED_OBJECT, 243)),
+            Some((true, String::from("riscv32")))
+        );
+        assert_eq!(
+            elf_info(&elf_header(true, true, ELF_TYPE_SHARED_OBJECT, 0x1234)),
+            Some((true, String::from("unknown (ELF machine 4660)")))
+        );
+    }
+
+    #[test]
+    fn elf_non_shared_libraries() {
+        assert_eq!(
+            elf_info(&elf_header(true, true, ELF_TYPE_EXECUTABLE, 62)),
+            Some((false, String::from("x86_64")))
+        );
+        assert_eq!(
+            elf_info(&elf_header(false, false, ELF_TYPE_RELOCATABLE, 8)),
+            Some((false, String::from("mips")))
+        );
+    }
+
+    #[test]
+    fn mach_o_shared_libraries() {
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xcf, 0xfa, 0xed, 0xfe],
+                0x0100_000c,
+                MACH_O_TYPE_BUNDLE
+            )),
+            Some((true, String::from("aarch64")))
+        );
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xcf, 0xfa, 0xed, 0xfe],
+                0x0100_0007,
+                MACH_O_TYPE_DYLIB
+            )),
+            Some((true, String::from("x86_64")))
+        );
+        // Big-endian headers were used on PowerPC Macs
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xfe, 0xed, 0xfa, 0xce],
+                18,
+                MACH_O_TYPE_BUNDLE
+            )),
+            Some((true, String::from("powerpc")))
+        );
+    }
+
+    #[test]
+    fn mach_o_architectures() {
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xce, 0xfa, 0xed, 0xfe],
+                7,
+                MACH_O_TYPE_DYLIB
+            )),
+            Some((true, String::from("x86")))
+        );
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xcf, 0xfa, 0xed, 0xfe],
+                0x0100_0099,
+                MACH_O_TYPE_DYLIB
+            )),
+            Some((true, String::from("unknown (Mach-O CPU type 0x1000099)")))
+        );
+    }
+
+    #[test]
+    fn mach_o_non_shared_libraries() {
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xcf, 0xfa, 0xed, 0xfe],
+                0x0100_000c,
+                MACH_O_TYPE_EXECUTE
+            )),
+            Some((false, String::from("aarch64")))
+        );
+        assert_eq!(
+            mach_o_info(&mach_o_header(
+                [0xcf, 0xfa, 0xed, 0xfe],
+                0x0100_0007,
+                MACH_O_TYPE_OBJECT
+            )),
+            Some((false, String::from("x86_64")))
+        );
+    }
+
+    #[test]
+    fn pe_shared_libraries() {
+        assert_eq!(
+            pe_info(&pe_header(0x8664, PE_CHARACTERISTICS_DLL | 0x0022)),
+            Some((true, String::from("x86_64")))
+        );
+        assert_eq!(
+            pe_info(&pe_header(0xaa64, PE_CHARACTERISTICS_DLL)),
+            Some((true, String::from("aarch64")))
+        );
+        assert_eq!(
+            pe_info(&pe_header(0x014c, PE_CHARACTERISTICS_DLL)),
+            Some((true, String::from("x86")))
+        );
+        assert_eq!(
+            pe_info(&pe_header(0x0200, PE_CHARACTERISTICS_DLL)),
+            Some((true, String::from("unknown (PE machine 0x200)")))
+        );
+    }
+
+    #[test]
+    fn pe_non_shared_libraries() {
+        assert_eq!(
+            pe_info(&pe_header(0x8664, 0x0022)),
+            Some((false, String::from("x86_64")))
+        );
+    }
+}
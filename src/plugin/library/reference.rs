@@ -0,0 +1,387 @@
+//! A minimal plugin library that's compiled into the validator's unit tests. This makes it possible
+//! to run the validator's tests against plugins with specific behaviors without having to build and
+//! load a separate plugin library. The library contains one plugin for every [`ReferencePlugin`].
+
+use anyhow::{Context, Result};
+use clap_sys::entry::clap_plugin_entry;
+use clap_sys::events::{clap_input_events, clap_output_events};
+use clap_sys::ext::note_ports::{
+    clap_note_port_info, clap_plugin_note_ports, CLAP_EXT_NOTE_PORTS, CLAP_NOTE_DIALECT_CLAP,
+};
+use clap_sys::ext::params::{clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS};
+use clap_sys::ext::state::{clap_plugin_state, CLAP_EXT_STATE};
+use clap_sys::factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID};
+use clap_sys::host::clap_host;
+use clap_sys::id::clap_id;
+use clap_sys::plugin::{clap_plugin, clap_plugin_descriptor};
+use clap_sys::process::{clap_process, clap_process_status, CLAP_PROCESS_CONTINUE};
+use clap_sys::stream::{clap_istream, clap_ostream};
+use clap_sys::version::CLAP_VERSION;
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use super::{EntryPoint, PluginLibrary};
+use crate::util::unsafe_clap_call;
+
+/// The plugins contained in the reference plugin library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferencePlugin {
+    /// A plugin that implements the params extension, but that doesn't have any parameters. It also
+    /// implements the state extension, and it has a single input note port that supports CLAP note
+    /// events.
+    NoParams,
+    /// A plugin that requests a main thread callback when it's initialized, and that requests
+    /// another one every time `clap_plugin::on_main_thread()` is called.
+    CallbackLoop,
+}
+
+/// All plugins in the reference plugin library, in the order they're listed in the factory.
+const REFERENCE_PLUGINS: [ReferencePlugin; 2] =
+    [ReferencePlugin::NoParams, ReferencePlugin::CallbackLoop];
+
+impl ReferencePlugin {
+    /// The plugin's ID in the reference plugin library.
+    pub fn id(self) -> &'static str {
+        unsafe { CStr::from_ptr(self.descriptor().id) }
+            .to_str()
+            .unwrap()
+    }
+
+    fn descriptor(self) -> &'static clap_plugin_descriptor {
+        match self {
+            ReferencePlugin::NoParams => &NO_PARAMS_DESCRIPTOR,
+            ReferencePlugin::CallbackLoop => &CALLBACK_LOOP_DESCRIPTOR,
+        }
+    }
+}
+
+impl PluginLibrary {
+    /// Load the reference plugin library. The entry point is initialized in the same way as a
+    /// regular plugin library's entry point, and it's deinitialized when the object is dropped.
+    pub fn reference() -> Result<PluginLibrary> {
+        // The plugins are part of the test binary
+        let path = std::env::current_exe().context("Could not find the test binary")?;
+        let path_cstring = CString::new(path.to_str().context("Path contains invalid UTF-8")?)
+            .context("Path contains null bytes")?;
+
+        let entry_point = &ENTRY_POINT;
+        if !unsafe_clap_call! { entry_point=>init(path_cstring.as_ptr()) } {
+            anyhow::bail!("'clap_plugin_entry::init({path_cstring:?})' returned false.");
+        }
+
+        Ok(PluginLibrary {
+            plugin_path: path.clone(),
+            binary_path: path,
+            entry_point: EntryPoint::Static(entry_point),
+        })
+    }
+}
+
+/// A null terminated array of feature strings for a plugin descriptor. Raw pointers are not `Sync`,
+/// so they can't be stored in a static without this wrapper.
+struct Features([*const c_char; 2]);
+
+unsafe impl Sync for Features {}
+
+static FEATURES: Features = Features([b"instrument\0".as_ptr() as *const c_char, ptr::null()]);
+
+static NO_PARAMS_DESCRIPTOR: clap_plugin_descriptor = descriptor(
+    b"org.free-audio.clap-validator.reference.no-params\0",
+    b"Reference Plugin (no parameters)\0",
+    FEATURES.0.as_ptr(),
+);
+
+static CALLBACK_LOOP_DESCRIPTOR: clap_plugin_descriptor = descriptor(
+    b"org.free-audio.clap-validator.reference.callback-loop\0",
+    b"Reference Plugin (callback loop)\0",
+    FEATURES.0.as_ptr(),
+);
+
+/// Create a descriptor for one of the reference plugins. `id` and `name` need to be null
+/// terminated.
+const fn descriptor(
+    id: &'static [u8],
+    name: &'static [u8],
+    features: *const *const c_char,
+) -> clap_plugin_descriptor {
+    clap_plugin_descriptor {
+        clap_version: CLAP_VERSION,
+        id: id.as_ptr() as *const c_char,
+        name: name.as_ptr() as *const c_char,
+        vendor: b"clap-validator\0".as_ptr() as *const c_char,
+        url: b"\0".as_ptr() as *const c_char,
+        manual_url: b"\0".as_ptr() as *const c_char,
+        support_url: b"\0".as_ptr() as *const c_char,
+        version: b"0.0.0\0".as_ptr() as *const c_char,
+        description: b"A plugin used to test clap-validator itself.\0".as_ptr() as *const c_char,
+        features,
+    }
+}
+
+static ENTRY_POINT: clap_plugin_entry = clap_plugin_entry {
+    clap_version: CLAP_VERSION,
+    init: Some(entry_init),
+    deinit: Some(entry_deinit),
+    get_factory: Some(entry_get_factory),
+};
+
+static FACTORY: clap_plugin_factory = clap_plugin_factory {
+    get_plugin_count: Some(factory_get_plugin_count),
+    get_plugin_descriptor: Some(factory_get_plugin_descriptor),
+    create_plugin: Some(factory_create_plugin),
+};
+
+static PARAMS: clap_plugin_params = clap_plugin_params {
+    count: Some(params_count),
+    get_info: Some(params_get_info),
+    get_value: Some(params_get_value),
+    value_to_text: Some(params_value_to_text),
+    text_to_value: Some(params_text_to_value),
+    flush: Some(params_flush),
+};
+
+static STATE: clap_plugin_state = clap_plugin_state {
+    save: Some(state_save),
+    load: Some(state_load),
+};
+
+static NOTE_PORTS: clap_plugin_note_ports = clap_plugin_note_ports {
+    count: Some(note_ports_count),
+    get: Some(note_ports_get),
+};
+
+/// The data for a single instance of one of the reference plugins. `clap_plugin::plugin_data`
+/// points to this object.
+struct Instance {
+    /// The `clap_plugin` struct passed to the host.
+    clap_plugin: clap_plugin,
+    host: *const clap_host,
+    plugin: ReferencePlugin,
+}
+
+impl Instance {
+    /// Get the instance belonging to a `clap_plugin` pointer created by
+    /// [`factory_create_plugin()`].
+    unsafe fn from_clap_plugin_ptr<'a>(plugin: *const clap_plugin) -> &'a Instance {
+        &*((*plugin).plugin_data as *const Instance)
+    }
+
+    fn request_callback(&self) {
+        unsafe {
+            let host = &*self.host;
+            (host.request_callback.unwrap())(host);
+        }
+    }
+}
+
+unsafe extern "C" fn entry_init(_plugin_path: *const c_char) -> bool {
+    true
+}
+
+unsafe extern "C" fn entry_deinit() {}
+
+unsafe extern "C" fn entry_get_factory(factory_id: *const c_char) -> *const c_void {
+    if CStr::from_ptr(factory_id) == CLAP_PLUGIN_FACTORY_ID {
+        &FACTORY as *const clap_plugin_factory as *const c_void
+    } else {
+        ptr::null()
+    }
+}
+
+unsafe extern "C" fn factory_get_plugin_count(_factory: *const clap_plugin_factory) -> u32 {
+    REFERENCE_PLUGINS.len() as u32
+}
+
+unsafe extern "C" fn factory_get_plugin_descriptor(
+    _factory: *const clap_plugin_factory,
+    index: u32,
+) -> *const clap_plugin_descriptor {
+    match REFERENCE_PLUGINS.get(index as usize) {
+        Some(plugin) => plugin.descriptor(),
+        None => ptr::null(),
+    }
+}
+
+unsafe extern "C" fn factory_create_plugin(
+    _factory: *const clap_plugin_factory,
+    host: *const clap_host,
+    plugin_id: *const c_char,
+) -> *const clap_plugin {
+    let plugin_id = CStr::from_ptr(plugin_id).to_str().unwrap_or_default();
+    let plugin = match REFERENCE_PLUGINS
+        .into_iter()
+        .find(|plugin| plugin.id() == plugin_id)
+    {
+        Some(plugin) => plugin,
+        None => return ptr::null(),
+    };
+
+    let instance = Box::into_raw(Box::new(Instance {
+        clap_plugin: clap_plugin {
+            desc: plugin.descriptor(),
+            plugin_data: ptr::null_mut(),
+            init: Some(plugin_init),
+            destroy: Some(plugin_destroy),
+            activate: Some(plugin_activate),
+            deactivate: Some(plugin_deactivate),
+            start_processing: Some(plugin_start_processing),
+            stop_processing: Some(plugin_stop_processing),
+            reset: Some(plugin_reset),
+            process: Some(plugin_process),
+            get_extension: Some(plugin_get_extension),
+            on_main_thread: Some(plugin_on_main_thread),
+        },
+        host,
+        plugin,
+    }));
+    (*instance).clap_plugin.plugin_data = instance as *mut c_void;
+
+    &(*instance).clap_plugin
+}
+
+unsafe extern "C" fn plugin_init(plugin: *const clap_plugin) -> bool {
+    let instance = Instance::from_clap_plugin_ptr(plugin);
+    if instance.plugin == ReferencePlugin::CallbackLoop {
+        instance.request_callback();
+    }
+
+    true
+}
+
+unsafe extern "C" fn plugin_destroy(plugin: *const clap_plugin) {
+    drop(Box::from_raw((*plugin).plugin_data as *mut Instance));
+}
+
+unsafe extern "C" fn plugin_activate(
+    _plugin: *const clap_plugin,
+    _sample_rate: f64,
+    _min_frames_count: u32,
+    _max_frames_count: u32,
+) -> bool {
+    true
+}
+
+unsafe extern "C" fn plugin_deactivate(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn plugin_start_processing(_plugin: *const clap_plugin) -> bool {
+    true
+}
+
+unsafe extern "C" fn plugin_stop_processing(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn plugin_reset(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn plugin_process(
+    _plugin: *const clap_plugin,
+    _process: *const clap_process,
+) -> clap_process_status {
+    CLAP_PROCESS_CONTINUE
+}
+
+unsafe extern "C" fn plugin_get_extension(
+    _plugin: *const clap_plugin,
+    id: *const c_char,
+) -> *const c_void {
+    let id = CStr::from_ptr(id);
+    if id == CLAP_EXT_PARAMS {
+        &PARAMS as *const clap_plugin_params as *const c_void
+    } else if id == CLAP_EXT_STATE {
+        &STATE as *const clap_plugin_state as *const c_void
+    } else if id == CLAP_EXT_NOTE_PORTS {
+        &NOTE_PORTS as *const clap_plugin_note_ports as *const c_void
+    } else {
+        ptr::null()
+    }
+}
+
+unsafe extern "C" fn plugin_on_main_thread(plugin: *const clap_plugin) {
+    let instance = Instance::from_clap_plugin_ptr(plugin);
+    if instance.plugin == ReferencePlugin::CallbackLoop {
+        instance.request_callback();
+    }
+}
+
+unsafe extern "C" fn params_count(_plugin: *const clap_plugin) -> u32 {
+    0
+}
+
+unsafe extern "C" fn params_get_info(
+    _plugin: *const clap_plugin,
+    _param_index: u32,
+    _param_info: *mut clap_param_info,
+) -> bool {
+    false
+}
+
+unsafe extern "C" fn params_get_value(
+    _plugin: *const clap_plugin,
+    _param_id: clap_id,
+    _out_value: *mut f64,
+) -> bool {
+    false
+}
+
+unsafe extern "C" fn params_value_to_text(
+    _plugin: *const clap_plugin,
+    _param_id: clap_id,
+    _value: f64,
+    _out_buffer: *mut c_char,
+    _out_buffer_capacity: u32,
+) -> bool {
+    false
+}
+
+unsafe extern "C" fn params_text_to_value(
+    _plugin: *const clap_plugin,
+    _param_id: clap_id,
+    _param_value_text: *const c_char,
+    _out_value: *mut f64,
+) -> bool {
+    false
+}
+
+unsafe extern "C" fn params_flush(
+    _plugin: *const clap_plugin,
+    _in: *const clap_input_events,
+    _out: *const clap_output_events,
+) {
+}
+
+// None of the reference plugins have any state, so nothing is written or read
+unsafe extern "C" fn state_save(_plugin: *const clap_plugin, _stream: *const clap_ostream) -> bool {
+    true
+}
+
+unsafe extern "C" fn state_load(_plugin: *const clap_plugin, _stream: *const clap_istream) -> bool {
+    true
+}
+
+unsafe extern "C" fn note_ports_count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
+    if is_input {
+        1
+    } else {
+        0
+    }
+}
+
+unsafe extern "C" fn note_ports_get(
+    _plugin: *const clap_plugin,
+    index: u32,
+    is_input: bool,
+    info: *mut clap_note_port_info,
+) -> bool {
+    if !is_input || index != 0 {
+        return false;
+    }
+
+    let info = &mut *info;
+    info.id = 0;
+    info.supported_dialects = CLAP_NOTE_DIALECT_CLAP;
+    info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
+    for (dest, src) in info.name.iter_mut().zip(b"Note Input\0") {
+        *dest = *src as c_char;
+    }
+
+    true
+}
@@ -17,7 +17,7 @@ mod provider;
 
 pub use self::indexer::{FileType, Flags, IndexerResults, Location, LocationValue, Soundpack};
 pub use self::metadata_receiver::{PluginAbi, Preset, PresetFile, PresetFlags};
-pub use self::provider::Provider;
+pub use self::provider::{CrawlStatistics, Provider};
 
 /// A `Send+Sync` wrapper around `*const clap_preset_discovery_factory`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -23,6 +23,34 @@ use parking_lot::Mutex;
 
 use crate::util::{self, check_null_ptr};
 
+/// The boilerplate every `clap_preset_discovery_indexer` callback is wrapped in. This asserts that
+/// the indexer pointer, its `indexer_data` field, and the pointer arguments in the optional list
+/// are non-null, recovers the [`Indexer`] from the pointer, and checks that the callback was made
+/// from the indexer's thread. The body is then run with the `&Indexer` bound to the closure-like
+/// argument's pattern. Panics are caught and recorded as callback errors.
+macro_rules! indexer_callback {
+    (
+        $indexer:ident,
+        $function_name:literal,
+        $([$($ptrs:expr),*],)?
+        |$this:pat_param| $body:block $(,)?
+    ) => {
+        util::catch_callback_panic(
+            $function_name,
+            || {
+                check_null_ptr!($indexer, (*$indexer).indexer_data $($(, $ptrs)*)?);
+                let this = &*((*$indexer).indexer_data as *const Self);
+
+                this.assert_same_thread($function_name);
+
+                let $this = this;
+                $body
+            },
+            |message| Self::record_callback_panic($indexer, message),
+        )
+    };
+}
+
 #[derive(Debug)]
 pub struct Indexer {
     /// The thread ID for the thread this object was created on. This object is not thread-safe, so
@@ -450,85 +478,118 @@ impl Indexer {
         }
     }
 
+    /// Record a panic caught in one of the indexer's callbacks as a callback error. If the panic
+    /// was caused by the indexer pointer or its `indexer_data` field being null, then the error is
+    /// logged instead.
+    unsafe fn record_callback_panic(
+        indexer: *const clap_preset_discovery_indexer,
+        message: String,
+    ) {
+        if indexer.is_null() || (*indexer).indexer_data.is_null() {
+            log::error!("{message}");
+        } else {
+            let this = &*((*indexer).indexer_data as *const Self);
+            this.set_callback_error(message);
+        }
+    }
+
     unsafe extern "C" fn declare_filetype(
         indexer: *const clap_preset_discovery_indexer,
         filetype: *const clap_preset_discovery_filetype,
     ) -> bool {
-        check_null_ptr!(indexer, (*indexer).indexer_data, filetype);
-        let this = &*((*indexer).indexer_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_indexer::declare_filetype()");
-        match FileType::from_descriptor(&*filetype) {
-            Ok(file_type) => {
-                this.results.borrow_mut().file_types.push(file_type);
-
-                true
-            }
-            Err(err) => {
-                this.set_callback_error(format!(
-                    "Error in 'clap_preset_discovery_indexer::declare_filetype()' call: {err:#}"
-                ));
-
-                false
+        indexer_callback!(
+            indexer,
+            "clap_preset_discovery_indexer::declare_filetype()",
+            [filetype],
+            |this| {
+                match FileType::from_descriptor(&*filetype) {
+                    Ok(file_type) => {
+                        this.results.borrow_mut().file_types.push(file_type);
+
+                        true
+                    }
+                    Err(err) => {
+                        this.set_callback_error(format!(
+                            "Error in 'clap_preset_discovery_indexer::declare_filetype()' call: \
+                             {err:#}"
+                        ));
+
+                        false
+                    }
+                }
             }
-        }
+        )
     }
 
     unsafe extern "C" fn declare_location(
         indexer: *const clap_preset_discovery_indexer,
         location: *const clap_preset_discovery_location,
     ) -> bool {
-        check_null_ptr!(indexer, (*indexer).indexer_data, location);
-        let this = &*((*indexer).indexer_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_indexer::declare_location()");
-        match Location::from_descriptor(&*location) {
-            Ok(location) => {
-                this.results.borrow_mut().locations.push(location);
-
-                true
-            }
-            Err(err) => {
-                this.set_callback_error(format!(
-                    "Error in 'clap_preset_discovery_indexer::declare_location()' call: {err:#}"
-                ));
-
-                false
+        indexer_callback!(
+            indexer,
+            "clap_preset_discovery_indexer::declare_location()",
+            [location],
+            |this| {
+                match Location::from_descriptor(&*location) {
+                    Ok(location) => {
+                        this.results.borrow_mut().locations.push(location);
+
+                        true
+                    }
+                    Err(err) => {
+                        this.set_callback_error(format!(
+                            "Error in 'clap_preset_discovery_indexer::declare_location()' call: \
+                             {err:#}"
+                        ));
+
+                        false
+                    }
+                }
             }
-        }
+        )
     }
 
     unsafe extern "C" fn declare_soundpack(
         indexer: *const clap_preset_discovery_indexer,
         soundpack: *const clap_preset_discovery_soundpack,
     ) -> bool {
-        check_null_ptr!(indexer, (*indexer).indexer_data, soundpack);
-        let this = &*((*indexer).indexer_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_indexer::declare_soundpack()");
-        match Soundpack::from_descriptor(&*soundpack) {
-            Ok(soundpack) => {
-                this.results.borrow_mut().soundpacks.push(soundpack);
-
-                true
-            }
-            Err(err) => {
-                this.set_callback_error(format!(
-                    "Error in 'clap_preset_discovery_indexer::declare_soundpack()' call: {err:#}"
-                ));
-
-                false
+        indexer_callback!(
+            indexer,
+            "clap_preset_discovery_indexer::declare_soundpack()",
+            [soundpack],
+            |this| {
+                match Soundpack::from_descriptor(&*soundpack) {
+                    Ok(soundpack) => {
+                        this.results.borrow_mut().soundpacks.push(soundpack);
+
+                        true
+                    }
+                    Err(err) => {
+                        this.set_callback_error(format!(
+                            "Error in 'clap_preset_discovery_indexer::declare_soundpack()' call: \
+                             {err:#}"
+                        ));
+
+                        false
+                    }
+                }
             }
-        }
+        )
     }
 
     unsafe extern "C" fn get_extension(
         indexer: *const clap_preset_discovery_indexer,
         extension_id: *const c_char,
     ) -> *const c_void {
-        check_null_ptr!(indexer, (*indexer).indexer_data, extension_id);
+        util::catch_callback_panic(
+            "clap_preset_discovery_indexer::get_extension()",
+            || {
+                check_null_ptr!(indexer, (*indexer).indexer_data, extension_id);
 
-        // There are currently no extensions for the preset discovery factory
-        std::ptr::null()
+                // There are currently no extensions for the preset discovery factory
+                std::ptr::null()
+            },
+            |message| Self::record_callback_panic(indexer, message),
+        )
     }
 }
@@ -11,7 +11,7 @@ use clap_sys::factory::draft::preset_discovery::{
 };
 use parking_lot::Mutex;
 use serde::Serialize;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::BTreeMap;
 use std::ffi::{c_char, c_void};
 use std::fmt::Display;
@@ -21,6 +21,36 @@ use std::thread::ThreadId;
 use super::{Flags, LocationValue};
 use crate::util::{self, check_null_ptr};
 
+/// The boilerplate every `clap_preset_discovery_metadata_receiver` callback is wrapped in. This
+/// asserts that the receiver pointer, its `receiver_data` field, and the pointer arguments in the
+/// optional list are non-null, recovers the [`MetadataReceiver`] from the pointer, counts the call,
+/// and checks that the callback was made from the receiver's thread. The body is then run with the
+/// `&MetadataReceiver` bound to the closure-like argument's pattern. Panics are caught and recorded
+/// as callback errors.
+macro_rules! receiver_callback {
+    (
+        $receiver:ident,
+        $function_name:literal,
+        $([$($ptrs:expr),*],)?
+        |$this:pat_param| $body:block $(,)?
+    ) => {
+        util::catch_callback_panic(
+            $function_name,
+            || {
+                check_null_ptr!($receiver, (*$receiver).receiver_data $($(, $ptrs)*)?);
+                let this = &*((*$receiver).receiver_data as *const Self);
+
+                this.num_calls.set(this.num_calls.get() + 1);
+                this.assert_same_thread($function_name);
+
+                let $this = this;
+                $body
+            },
+            |message| Self::record_callback_panic($receiver, message),
+        )
+    };
+}
+
 /// An implementation of the preset discovery's metadata receiver. This borrows a
 /// `Result<PresetFile>` because the important work is done when this object is dropped. When this
 /// object is dropped, that result will contain either an error, a single preset, or a container of
@@ -68,6 +98,9 @@ pub struct MetadataReceiver<'a> {
     /// on the presence of `load_key`. If this is not set, then subsequent `begin_preset()` calls
     /// are treated as errors. Used in `maybe_write_preset()`.
     next_load_key: RefCell<Option<String>>,
+    /// The number of times the plugin called any of the metadata receiver's functions. Used for the
+    /// preset crawling statistics.
+    num_calls: Cell<usize>,
 
     /// The vtable that's passed to the provider. The `receiver_data` field is populated with a
     /// pointer to this object.
@@ -248,6 +281,17 @@ impl Preset {
     }
 }
 
+impl PresetFile {
+    /// All presets contained within this preset file. This contains exactly one preset for
+    /// [`PresetFile::Single`].
+    pub fn presets(&self) -> Vec<&Preset> {
+        match self {
+            PresetFile::Single(preset) => vec![preset],
+            PresetFile::Container(presets) => presets.values().collect(),
+        }
+    }
+}
+
 impl Drop for MetadataReceiver<'_> {
     fn drop(&mut self) {
         // If the plugin declared a(nother) preset file, then this will be added to `self.result`
@@ -282,6 +326,7 @@ impl<'a> MetadataReceiver<'a> {
             result: RefCell::new(result),
             next_preset_data: RefCell::new(None),
             next_load_key: RefCell::new(None),
+            num_calls: Cell::new(0),
 
             clap_preset_discovery_metadata_receiver: Mutex::new(
                 clap_preset_discovery_metadata_receiver {
@@ -317,6 +362,11 @@ impl<'a> MetadataReceiver<'a> {
         self.clap_preset_discovery_metadata_receiver.data_ptr()
     }
 
+    /// The number of times the plugin called any of the metadata receiver's functions so far.
+    pub fn num_calls(&self) -> usize {
+        self.num_calls.get()
+    }
+
     /// Checks that this function is called from the same thread the indexer was created on. If it
     /// is not, then an error indicating this can be retrieved using
     /// [`callback_error_check()`][Self::callback_error_check()]. Subsequent thread safety errors
@@ -383,29 +433,46 @@ impl<'a> MetadataReceiver<'a> {
         }
     }
 
+    /// Record a panic caught in one of the receiver's callbacks as a callback error. If the panic
+    /// was caused by the receiver pointer or its `receiver_data` field being null, then the error
+    /// is logged instead.
+    unsafe fn record_callback_panic(
+        receiver: *const clap_preset_discovery_metadata_receiver,
+        message: String,
+    ) {
+        if receiver.is_null() || (*receiver).receiver_data.is_null() {
+            log::error!("{message}");
+        } else {
+            let this = &*((*receiver).receiver_data as *const Self);
+            this.set_callback_error(message);
+        }
+    }
+
     unsafe extern "C" fn on_error(
         receiver: *const clap_preset_discovery_metadata_receiver,
         os_error: i32,
         error_message: *const c_char,
     ) {
         // We'll have a dedicated error message for a missing `error_message`
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::on_error()");
-
-        let error_message = unsafe { util::cstr_ptr_to_mandatory_string(error_message) }.context(
-            "'clap_preset_discovery_metadata_receiver::on_error()' called with an invalid error \
-             message",
-        );
-        match error_message {
-            Ok(error_message) => this.set_callback_error(format!(
-                "'clap_preset_discovery_metadata_receiver::on_error()' called for OS error code \
-                 {os_error} with the following error message: {error_message}"
-            )),
-            // This would be quite ironic
-            Err(err) => this.set_callback_error(format!("{err:#}")),
-        }
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::on_error()",
+            |this| {
+                let error_message = unsafe { util::cstr_ptr_to_mandatory_string(error_message) }
+                    .context(
+                        "'clap_preset_discovery_metadata_receiver::on_error()' called with an \
+                         invalid error message",
+                    );
+                match error_message {
+                    Ok(error_message) => this.set_callback_error(format!(
+                        "'clap_preset_discovery_metadata_receiver::on_error()' called for OS error \
+                         code {os_error} with the following error message: {error_message}"
+                    )),
+                    // This would be quite ironic
+                    Err(err) => this.set_callback_error(format!("{err:#}")),
+                }
+            }
+        )
     }
 
     unsafe extern "C" fn begin_preset(
@@ -413,278 +480,297 @@ impl<'a> MetadataReceiver<'a> {
         name: *const c_char,
         load_key: *const c_char,
     ) -> bool {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::begin_preset()");
-
-        let name = unsafe { util::cstr_ptr_to_optional_string(name) }.context(
-            "'clap_preset_discovery_metadata_receiver::begin_preset()' called with an invalid \
-             name parameter",
-        );
-        let load_key = unsafe { util::cstr_ptr_to_optional_string(load_key) }.context(
-            "'clap_preset_discovery_metadata_receiver::begin_preset()' called with an invalid \
-             load_key parameter",
-        );
-        match (name, load_key) {
-            (Ok(name), Ok(load_key)) => {
-                // We'll check for some errorous situations first. The `result` borrow needs to be
-                // dropped before calling `maybe_write_preset()` as it will try to borrow it mutably
-                {
-                    let result = this.result.borrow();
-                    let error_message = match (&*result, &load_key) {
-                        // If there was an error then just immediately exit since nothing will change that
-                        (Some(Err(_)), _) => return false,
-                        (Some(Ok(PresetFile::Single(_))), None) => Some(
-                            "calling 'begin_preset()' a second time for a non-container preset \
-                             file with no load key is not allowed.",
-                        ),
-                        (Some(Ok(PresetFile::Single(_))), Some(_)) => Some(
-                            "'begin_preset()' was called without a load key for the first time, \
-                             and with a load key the second time. This is invalid behavior.",
-                        ),
-                        (Some(Ok(PresetFile::Container(_))), None) => Some(
-                            "'begin_preset()' was called with a load key for the first time, and \
-                             without a load key the second time. This is invalid behavior.",
-                        ),
-                        // If this is the first call and there are no errors then everything's fine
-                        (None, _) | (Some(Ok(PresetFile::Container(_))), Some(_)) => None,
-                    };
-
-                    if let Some(error_message) = error_message {
-                        this.set_callback_error(format!(
-                            "Error in 'clap_preset_discovery_metadata_receiver::begin_preset()' \
-                             call: {error_message}"
-                        ));
-                        return false;
-                    }
-                }
-
-                // Container presets have a load key, single-preset files don't have a load key. The
-                // name field is mandatory for container presets, and optional for non-container
-                // presets. If it's not specified we'll use the file name instead.
-                let preset_name = match (name, &load_key) {
-                    (None, None) => PresetName::Filename(match this.location.file_name() {
-                        Ok(file_name) => file_name,
-                        Err(err) => {
-                            this.set_callback_error(format!(
-                                "Could not derive a file name from {}: {:#}",
-                                this.location, err
-                            ));
-                            return false;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::begin_preset()",
+            |this| {
+                let name = unsafe { util::cstr_ptr_to_optional_string(name) }.context(
+                    "'clap_preset_discovery_metadata_receiver::begin_preset()' called with an \
+                     invalid name parameter",
+                );
+                let load_key = unsafe { util::cstr_ptr_to_optional_string(load_key) }.context(
+                    "'clap_preset_discovery_metadata_receiver::begin_preset()' called with an \
+                     invalid load_key parameter",
+                );
+                match (name, load_key) {
+                    (Ok(name), Ok(load_key)) => {
+                        // We'll check for some errorous situations first. The `result` borrow needs
+                        // to be dropped before calling `maybe_write_preset()` as it will try to
+                        // borrow it mutably
+                        {
+                            let result = this.result.borrow();
+                            let error_message = match (&*result, &load_key) {
+                                // If there was an error then just immediately exit since nothing
+                                // will change that
+                                (Some(Err(_)), _) => return false,
+                                (Some(Ok(PresetFile::Single(_))), None) => Some(
+                                    "calling 'begin_preset()' a second time for a non-container \
+                                     preset file with no load key is not allowed.",
+                                ),
+                                (Some(Ok(PresetFile::Single(_))), Some(_)) => Some(
+                                    "'begin_preset()' was called without a load key for the first \
+                                     time, and with a load key the second time. This is invalid \
+                                     behavior.",
+                                ),
+                                (Some(Ok(PresetFile::Container(_))), None) => Some(
+                                    "'begin_preset()' was called with a load key for the first \
+                                     time, and without a load key the second time. This is invalid \
+                                     behavior.",
+                                ),
+                                // If this is the first call and there are no errors then
+                                // everything's fine
+                                (None, _) | (Some(Ok(PresetFile::Container(_))), Some(_)) => None,
+                            };
+
+                            if let Some(error_message) = error_message {
+                                this.set_callback_error(format!(
+                                    "Error in \
+                                     'clap_preset_discovery_metadata_receiver::begin_preset()' \
+                                     call: {error_message}"
+                                ));
+                                return false;
+                            }
                         }
-                    }),
-                    (Some(name), _) => PresetName::Explicit(name),
-                    (None, Some(_)) => {
-                        this.set_callback_error(
-                            "Container presets must specify a preset name.".to_string(),
-                        );
-                        return false;
-                    }
-                };
 
-                // If this is a subsequent `begin_preset()` call for a container preset, then the
-                // old preset is written to `self.result` before starting a new one.
-                if load_key.is_some() {
-                    this.maybe_write_preset();
-                }
+                        // Container presets have a load key, single-preset files don't have a load
+                        // key. The name field is mandatory for container presets, and optional for
+                        // non-container presets. If it's not specified we'll use the file name
+                        // instead.
+                        let preset_name = match (name, &load_key) {
+                            (None, None) => PresetName::Filename(match this.location.file_name() {
+                                Ok(file_name) => file_name,
+                                Err(err) => {
+                                    this.set_callback_error(format!(
+                                        "Could not derive a file name from {}: {:#}",
+                                        this.location, err
+                                    ));
+                                    return false;
+                                }
+                            }),
+                            (Some(name), _) => PresetName::Explicit(name),
+                            (None, Some(_)) => {
+                                this.set_callback_error(
+                                    "Container presets must specify a preset name.".to_string(),
+                                );
+                                return false;
+                            }
+                        };
+
+                        // If this is a subsequent `begin_preset()` call for a container preset,
+                        // then the old preset is written to `self.result` before starting a new
+                        // one.
+                        if load_key.is_some() {
+                            this.maybe_write_preset();
+                        }
 
-                // This starts the declaration of a new preset. The methods below this write to this
-                // data structure, and it is finally added to `self.result` in a
-                // `maybe_write_preset()` call either from here or from the drop handler.
-                *this.next_load_key.borrow_mut() = load_key;
-                *this.next_preset_data.borrow_mut() = Some(PartialPreset::new(preset_name));
+                        // This starts the declaration of a new preset. The methods below this write
+                        // to this data structure, and it is finally added to `self.result` in a
+                        // `maybe_write_preset()` call either from here or from the drop handler.
+                        *this.next_load_key.borrow_mut() = load_key;
+                        *this.next_preset_data.borrow_mut() = Some(PartialPreset::new(preset_name));
 
-                true
-            }
-            (Err(err), _) | (_, Err(err)) => {
-                this.set_callback_error(format!("{err:#}"));
+                        true
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        this.set_callback_error(format!("{err:#}"));
 
-                false
+                        false
+                    }
+                }
             }
-        }
+        )
     }
 
     unsafe extern "C" fn add_plugin_id(
         receiver: *const clap_preset_discovery_metadata_receiver,
         plugin_id: *const clap_plugin_id,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data, plugin_id);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::add_plugin_id()");
-
-        let abi = unsafe { util::cstr_ptr_to_mandatory_string((*plugin_id).abi) }.context(
-            "'clap_preset_discovery_metadata_receiver::add_plugin_id()' called with an invalid \
-             abi field",
-        );
-        let id = unsafe { util::cstr_ptr_to_mandatory_string((*plugin_id).id) }.context(
-            "'clap_preset_discovery_metadata_receiver::add_plugin_id()' called with an invalid id \
-             field",
-        );
-        match (abi, id) {
-            (Ok(abi), Ok(id)) => {
-                let mut next_preset_data = this.next_preset_data.borrow_mut();
-                let next_preset_data = match &mut *next_preset_data {
-                    Some(next_preset_data) => next_preset_data,
-                    None => {
-                        this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::add_plugin_id()' with no \
-                             preceding 'begin_preset()' call. This is not valid.",
-                        );
-                        return;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::add_plugin_id()",
+            [plugin_id],
+            |this| {
+                let abi = unsafe { util::cstr_ptr_to_mandatory_string((*plugin_id).abi) }.context(
+                    "'clap_preset_discovery_metadata_receiver::add_plugin_id()' called with an \
+                     invalid abi field",
+                );
+                let id = unsafe { util::cstr_ptr_to_mandatory_string((*plugin_id).id) }.context(
+                    "'clap_preset_discovery_metadata_receiver::add_plugin_id()' called with an \
+                     invalid id field",
+                );
+                match (abi, id) {
+                    (Ok(abi), Ok(id)) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::add_plugin_id()' \
+                                     with no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        if abi == "clap" {
+                            next_preset_data.plugin_ids.push(PluginId {
+                                abi: PluginAbi::Clap,
+                                id,
+                            });
+                        } else if abi.trim().eq_ignore_ascii_case("clap") {
+                            // Let's just assume noone comes up with a painfully sarcastic 'ClAp'
+                            // standard
+                            this.set_callback_error(format!(
+                                "'{abi}' was provided as an ABI argument to \
+                                 'clap_preset_discovery_metadata_receiver::add_plugin_id()'. This \
+                                 is probably a typo. The expected value is 'clap' in all \
+                                 lowercase."
+                            ));
+                        } else {
+                            next_preset_data.plugin_ids.push(PluginId {
+                                abi: PluginAbi::Other(abi),
+                                id,
+                            });
+                        }
                     }
-                };
-
-                if abi == "clap" {
-                    next_preset_data.plugin_ids.push(PluginId {
-                        abi: PluginAbi::Clap,
-                        id,
-                    });
-                } else if abi.trim().eq_ignore_ascii_case("clap") {
-                    // Let's just assume noone comes up with a painfully sarcastic 'ClAp' standard
-                    this.set_callback_error(format!(
-                        "'{abi}' was provided as an ABI argument to \
-                         'clap_preset_discovery_metadata_receiver::add_plugin_id()'. This is \
-                         probably a typo. The expected value is 'clap' in all lowercase."
-                    ));
-                } else {
-                    next_preset_data.plugin_ids.push(PluginId {
-                        abi: PluginAbi::Other(abi),
-                        id,
-                    });
+                    (Err(err), _) | (_, Err(err)) => this.set_callback_error(format!("{err:#}")),
                 }
             }
-            (Err(err), _) | (_, Err(err)) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
     }
 
     unsafe extern "C" fn set_soundpack_id(
         receiver: *const clap_preset_discovery_metadata_receiver,
         soundpack_id: *const c_char,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::set_soundpack_id()");
-
-        let soundpack_id = unsafe { util::cstr_ptr_to_mandatory_string(soundpack_id) }.context(
-            "'clap_preset_discovery_metadata_receiver::set_soundpack_id()' called with an invalid \
-             parameter",
-        );
-        match soundpack_id {
-            Ok(soundpack_id) => {
-                let mut next_preset_data = this.next_preset_data.borrow_mut();
-                let next_preset_data = match &mut *next_preset_data {
-                    Some(next_preset_data) => next_preset_data,
-                    None => {
-                        this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::set_soundpack_id()' with \
-                             no preceding 'begin_preset()' call. This is not valid.",
-                        );
-                        return;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::set_soundpack_id()",
+            |this| {
+                let soundpack_id = unsafe { util::cstr_ptr_to_mandatory_string(soundpack_id) }
+                    .context(
+                        "'clap_preset_discovery_metadata_receiver::set_soundpack_id()' called \
+                         with an invalid parameter",
+                    );
+                match soundpack_id {
+                    Ok(soundpack_id) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::set_soundpack_id()' \
+                                     with no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        next_preset_data.soundpack_id = Some(soundpack_id);
                     }
-                };
-
-                next_preset_data.soundpack_id = Some(soundpack_id);
+                    Err(err) => this.set_callback_error(format!("{err:#}")),
+                }
             }
-            Err(err) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
     }
 
     unsafe extern "C" fn set_flags(
         receiver: *const clap_preset_discovery_metadata_receiver,
         flags: u32,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::set_flags()");
-
-        let mut next_preset_data = this.next_preset_data.borrow_mut();
-        let next_preset_data = match &mut *next_preset_data {
-            Some(next_preset_data) => next_preset_data,
-            None => {
-                this.set_callback_error(
-                    "'clap_preset_discovery_metadata_receiver::set_flags()' with no preceding \
-                     'begin_preset()' call. This is not valid.",
-                );
-                return;
-            }
-        };
-
-        next_preset_data.flags = Some(Flags {
-            is_factory_content: (flags & CLAP_PRESET_DISCOVERY_IS_FACTORY_CONTENT) != 0,
-            is_user_content: (flags & CLAP_PRESET_DISCOVERY_IS_USER_CONTENT) != 0,
-            is_demo_content: (flags & CLAP_PRESET_DISCOVERY_IS_DEMO_CONTENT) != 0,
-            is_favorite: (flags & CLAP_PRESET_DISCOVERY_IS_FAVORITE) != 0,
-        });
-    }
-
-    unsafe extern "C" fn add_creator(
-        receiver: *const clap_preset_discovery_metadata_receiver,
-        creator: *const c_char,
-    ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::set_creator()");
-
-        let creator = unsafe { util::cstr_ptr_to_mandatory_string(creator) }.context(
-            "'clap_preset_discovery_metadata_receiver::set_creator()' called with an invalid \
-             parameter",
-        );
-        match creator {
-            Ok(creator) => {
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::set_flags()",
+            |this| {
                 let mut next_preset_data = this.next_preset_data.borrow_mut();
                 let next_preset_data = match &mut *next_preset_data {
                     Some(next_preset_data) => next_preset_data,
                     None => {
                         this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::set_creator()' with no \
+                            "'clap_preset_discovery_metadata_receiver::set_flags()' with no \
                              preceding 'begin_preset()' call. This is not valid.",
                         );
                         return;
                     }
                 };
 
-                next_preset_data.creators.push(creator);
+                next_preset_data.flags = Some(Flags {
+                    is_factory_content: (flags & CLAP_PRESET_DISCOVERY_IS_FACTORY_CONTENT) != 0,
+                    is_user_content: (flags & CLAP_PRESET_DISCOVERY_IS_USER_CONTENT) != 0,
+                    is_demo_content: (flags & CLAP_PRESET_DISCOVERY_IS_DEMO_CONTENT) != 0,
+                    is_favorite: (flags & CLAP_PRESET_DISCOVERY_IS_FAVORITE) != 0,
+                });
             }
-            Err(err) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
+    }
+
+    unsafe extern "C" fn add_creator(
+        receiver: *const clap_preset_discovery_metadata_receiver,
+        creator: *const c_char,
+    ) {
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::set_creator()",
+            |this| {
+                let creator = unsafe { util::cstr_ptr_to_mandatory_string(creator) }.context(
+                    "'clap_preset_discovery_metadata_receiver::set_creator()' called with an \
+                     invalid parameter",
+                );
+                match creator {
+                    Ok(creator) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::set_creator()' with \
+                                     no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        next_preset_data.creators.push(creator);
+                    }
+                    Err(err) => this.set_callback_error(format!("{err:#}")),
+                }
+            }
+        )
     }
 
     unsafe extern "C" fn set_description(
         receiver: *const clap_preset_discovery_metadata_receiver,
         description: *const c_char,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::set_description()");
-
-        let description = unsafe { util::cstr_ptr_to_mandatory_string(description) }.context(
-            "'clap_preset_discovery_metadata_receiver::set_description()' called with an invalid \
-             parameter",
-        );
-        match description {
-            Ok(description) => {
-                let mut next_preset_data = this.next_preset_data.borrow_mut();
-                let next_preset_data = match &mut *next_preset_data {
-                    Some(next_preset_data) => next_preset_data,
-                    None => {
-                        this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::set_description()' with no \
-                             preceding 'begin_preset()' call. This is not valid.",
-                        );
-                        return;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::set_description()",
+            |this| {
+                let description = unsafe { util::cstr_ptr_to_mandatory_string(description) }
+                    .context(
+                        "'clap_preset_discovery_metadata_receiver::set_description()' called with \
+                         an invalid parameter",
+                    );
+                match description {
+                    Ok(description) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::set_description()' \
+                                     with no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        next_preset_data.description = Some(description);
                     }
-                };
-
-                next_preset_data.description = Some(description);
+                    Err(err) => this.set_callback_error(format!("{err:#}")),
+                }
             }
-            Err(err) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
     }
 
     unsafe extern "C" fn set_timestamps(
@@ -692,77 +778,80 @@ impl<'a> MetadataReceiver<'a> {
         creation_time: clap_timestamp,
         modification_time: clap_timestamp,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::set_timestamps()");
-
-        // These are parsed to `None` values if the timestamp is 0/CLAP_TIMESTAMP_UNKNOWN
-        let creation_time = util::parse_timestamp(creation_time).context(
-            "'clap_preset_discovery_metadata_receiver::set_timestamps()' called with an invalid \
-             creation_time parameter",
-        );
-        let modification_time = util::parse_timestamp(modification_time).context(
-            "'clap_preset_discovery_metadata_receiver::set_timestamps()' called with an invalid \
-             modification_time parameter",
-        );
-        match (creation_time, modification_time) {
-            // Calling the function like htis doesn't make any sense, so we'll point that out
-            (Ok(None), Ok(None)) => this.set_callback_error(
-                "'clap_preset_discovery_metadata_receiver::set_timestamps()' called with both \
-                 arguments set to 'CLAP_TIMESTAMP_UNKNOWN'.",
-            ),
-            (Ok(creation_time), Ok(modification_time)) => {
-                let mut next_preset_data = this.next_preset_data.borrow_mut();
-                let next_preset_data = match &mut *next_preset_data {
-                    Some(next_preset_data) => next_preset_data,
-                    None => {
-                        this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::set_timestamps()' with no \
-                             preceding 'begin_preset()' call. This is not valid.",
-                        );
-                        return;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::set_timestamps()",
+            |this| {
+                // These are parsed to `None` values if the timestamp is 0/CLAP_TIMESTAMP_UNKNOWN
+                let creation_time = util::parse_timestamp(creation_time).context(
+                    "'clap_preset_discovery_metadata_receiver::set_timestamps()' called with an \
+                     invalid creation_time parameter",
+                );
+                let modification_time = util::parse_timestamp(modification_time).context(
+                    "'clap_preset_discovery_metadata_receiver::set_timestamps()' called with an \
+                     invalid modification_time parameter",
+                );
+                match (creation_time, modification_time) {
+                    // Calling the function like htis doesn't make any sense, so we'll point that
+                    // out
+                    (Ok(None), Ok(None)) => this.set_callback_error(
+                        "'clap_preset_discovery_metadata_receiver::set_timestamps()' called with \
+                         both arguments set to 'CLAP_TIMESTAMP_UNKNOWN'.",
+                    ),
+                    (Ok(creation_time), Ok(modification_time)) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::set_timestamps()' \
+                                     with no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        next_preset_data.creation_time = creation_time;
+                        next_preset_data.modification_time = modification_time;
                     }
-                };
-
-                next_preset_data.creation_time = creation_time;
-                next_preset_data.modification_time = modification_time;
+                    (Err(err), _) | (_, Err(err)) => this.set_callback_error(format!("{err:#}")),
+                }
             }
-            (Err(err), _) | (_, Err(err)) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
     }
 
     unsafe extern "C" fn add_feature(
         receiver: *const clap_preset_discovery_metadata_receiver,
         feature: *const c_char,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::add_feature()");
-
-        let feature = unsafe { util::cstr_ptr_to_mandatory_string(feature) }.context(
-            "'clap_preset_discovery_metadata_receiver::add_feature()' called with an invalid \
-             parameter",
-        );
-        match feature {
-            Ok(feature) => {
-                let mut next_preset_data = this.next_preset_data.borrow_mut();
-                let next_preset_data = match &mut *next_preset_data {
-                    Some(next_preset_data) => next_preset_data,
-                    None => {
-                        this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::add_plugin_id()' with no \
-                             preceding 'begin_preset()' call. This is not valid.",
-                        );
-                        return;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::add_feature()",
+            |this| {
+                let feature = unsafe { util::cstr_ptr_to_mandatory_string(feature) }.context(
+                    "'clap_preset_discovery_metadata_receiver::add_feature()' called with an \
+                     invalid parameter",
+                );
+                match feature {
+                    Ok(feature) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::add_plugin_id()' \
+                                     with no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        next_preset_data.features.push(feature);
                     }
-                };
-
-                next_preset_data.features.push(feature);
+                    Err(err) => this.set_callback_error(format!("{err:#}")),
+                }
             }
-            Err(err) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
     }
 
     unsafe extern "C" fn add_extra_info(
@@ -770,36 +859,37 @@ impl<'a> MetadataReceiver<'a> {
         key: *const c_char,
         value: *const c_char,
     ) {
-        check_null_ptr!(receiver, (*receiver).receiver_data);
-        let this = &*((*receiver).receiver_data as *const Self);
-
-        this.assert_same_thread("clap_preset_discovery_metadata_receiver::add_extra_info()");
-
-        let key = unsafe { util::cstr_ptr_to_mandatory_string(key) }.context(
-            "'clap_preset_discovery_metadata_receiver::add_extra_info()' called with an invalid \
-             key parameter",
-        );
-        let value = unsafe { util::cstr_ptr_to_mandatory_string(value) }.context(
-            "'clap_preset_discovery_metadata_receiver::add_extra_info()' called with an invalid \
-             value parameter",
-        );
-        match (key, value) {
-            (Ok(key), Ok(value)) => {
-                let mut next_preset_data = this.next_preset_data.borrow_mut();
-                let next_preset_data = match &mut *next_preset_data {
-                    Some(next_preset_data) => next_preset_data,
-                    None => {
-                        this.set_callback_error(
-                            "'clap_preset_discovery_metadata_receiver::add_extra_info()' with no \
-                             preceding 'begin_preset()' call. This is not valid.",
-                        );
-                        return;
+        receiver_callback!(
+            receiver,
+            "clap_preset_discovery_metadata_receiver::add_extra_info()",
+            |this| {
+                let key = unsafe { util::cstr_ptr_to_mandatory_string(key) }.context(
+                    "'clap_preset_discovery_metadata_receiver::add_extra_info()' called with an \
+                     invalid key parameter",
+                );
+                let value = unsafe { util::cstr_ptr_to_mandatory_string(value) }.context(
+                    "'clap_preset_discovery_metadata_receiver::add_extra_info()' called with an \
+                     invalid value parameter",
+                );
+                match (key, value) {
+                    (Ok(key), Ok(value)) => {
+                        let mut next_preset_data = this.next_preset_data.borrow_mut();
+                        let next_preset_data = match &mut *next_preset_data {
+                            Some(next_preset_data) => next_preset_data,
+                            None => {
+                                this.set_callback_error(
+                                    "'clap_preset_discovery_metadata_receiver::add_extra_info()' \
+                                     with no preceding 'begin_preset()' call. This is not valid.",
+                                );
+                                return;
+                            }
+                        };
+
+                        next_preset_data.extra_info.insert(key, value);
                     }
-                };
-
-                next_preset_data.extra_info.insert(key, value);
+                    (Err(err), _) | (_, Err(err)) => this.set_callback_error(format!("{err:#}")),
+                }
             }
-            (Err(err), _) | (_, Err(err)) => this.set_callback_error(format!("{err:#}")),
-        }
+        )
     }
 }
@@ -1,11 +1,13 @@
 //! A wrapper around `clap_preset_discovery_provider`.
 
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::collections::{BTreeMap, HashSet};
 use std::ffi::CString;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::ptr::NonNull;
+use std::time::Instant;
 use walkdir::WalkDir;
 
 use clap_sys::factory::draft::preset_discovery::clap_preset_discovery_provider;
@@ -15,6 +17,11 @@ use super::metadata_receiver::{MetadataReceiver, PresetFile};
 use super::{Location, LocationValue, PresetDiscoveryFactory, ProviderMetadata};
 use crate::util::unsafe_clap_call;
 
+/// The maximum number of files crawled within a single directory location. This prevents the
+/// crawler from running for hours when a plugin declares something like the user's home directory
+/// as a preset location.
+const MAX_CRAWLED_FILES: usize = 100_000;
+
 /// A preset discovery provider created from a preset discovery factory. The provider is initialized
 /// and the declared contents are read when the object is created, and the provider is destroyed
 /// when this object is dropped.
@@ -41,6 +48,24 @@ pub struct Provider<'a> {
     _send_sync_marker: PhantomData<*const ()>,
 }
 
+/// Performance statistics gathered while crawling one or more locations using
+/// [`Provider::crawl_location_with_statistics()`].
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CrawlStatistics {
+    /// The total time spent in `clap_preset_discovery_provider::get_metadata()` calls, in
+    /// milliseconds.
+    pub total_time_ms: f64,
+    /// The number of files and other locations `get_metadata()` was called for.
+    pub num_files_visited: usize,
+    /// The total number of calls the provider made to the metadata receivers' functions.
+    pub num_metadata_receiver_calls: usize,
+    /// The location that took the longest to crawl, if any locations were crawled.
+    pub slowest_location: Option<String>,
+    /// The time spent crawling `slowest_location`, in milliseconds.
+    pub slowest_location_time_ms: f64,
+}
+
 impl<'a> Provider<'a> {
     /// Create a wrapper around a preset discovery factory instance returned from a CLAP plugin's
     /// entry point.
@@ -130,6 +155,16 @@ impl<'a> Provider<'a> {
     pub fn crawl_location(
         &self,
         location: &Location,
+    ) -> Result<BTreeMap<LocationValue, PresetFile>> {
+        self.crawl_location_with_statistics(location, &mut CrawlStatistics::default())
+    }
+
+    /// The same as [`crawl_location()`][Self::crawl_location()], but this also adds timing
+    /// information and the number of visited files and metadata receiver calls to `statistics`.
+    pub fn crawl_location_with_statistics(
+        &self,
+        location: &Location,
+        statistics: &mut CrawlStatistics,
     ) -> Result<BTreeMap<LocationValue, PresetFile>> {
         let mut results = BTreeMap::new();
 
@@ -147,6 +182,7 @@ impl<'a> Provider<'a> {
                     MetadataReceiver::new(&mut result, &location, location_flags);
 
                 let provider = self.as_ptr();
+                let crawl_start = Instant::now();
                 let success = unsafe_clap_call! {
                     provider=>get_metadata(
                         provider,
@@ -155,6 +191,18 @@ impl<'a> Provider<'a> {
                         metadata_receiver.clap_preset_discovery_metadata_receiver_ptr()
                     )
                 };
+                let crawl_time_ms = crawl_start.elapsed().as_secs_f64() * 1000.0;
+
+                statistics.total_time_ms += crawl_time_ms;
+                statistics.num_files_visited += 1;
+                statistics.num_metadata_receiver_calls += metadata_receiver.num_calls();
+                if statistics.slowest_location.is_none()
+                    || crawl_time_ms > statistics.slowest_location_time_ms
+                {
+                    statistics.slowest_location = Some(location.to_string());
+                    statistics.slowest_location_time_ms = crawl_time_ms;
+                }
+
                 if !success {
                     // TODO: Is the plugin allowed to return false here? If it doesn't have any
                     //       presets it should just not declare any, right?
@@ -183,7 +231,10 @@ impl<'a> Provider<'a> {
                     .to_str()
                     .context("Invalid UTF-8 in location path")?;
                 let metadata = std::fs::metadata(file_path_str).with_context(|| {
-                    "Could not query metadata for the declared file location '{file_path_str}'"
+                    format!(
+                        "Could not query metadata for the declared file location \
+                         '{file_path_str}'"
+                    )
                 })?;
                 if metadata.is_dir() {
                     // If the plugin declared valid file extensions, then we'll filter by those file
@@ -195,22 +246,54 @@ impl<'a> Provider<'a> {
                         .map(|file_type| file_type.extension.as_str())
                         .collect();
 
+                    // Symlinks are followed like a host's preset browser would. With
+                    // `follow_links()` enabled walkdir detects symlink loops and yields an error
+                    // for them instead of recursing forever.
                     let walker = WalkDir::new(file_path_str)
                         .min_depth(1)
                         .follow_links(true)
                         .same_file_system(false)
                         .into_iter()
-                        .filter_map(|entry| entry.ok())
+                        .filter_map(|entry| match entry {
+                            Ok(entry) => Some(entry),
+                            Err(err) => {
+                                match err.loop_ancestor() {
+                                    Some(ancestor) => log::warn!(
+                                        "Skipping a symlink loop to '{}' while crawling '{}'",
+                                        ancestor.display(),
+                                        file_path_str
+                                    ),
+                                    None => log::debug!(
+                                        "Error while crawling '{file_path_str}', skipping: {err}"
+                                    ),
+                                }
+
+                                None
+                            }
+                        })
                         .filter(|entry| entry.file_type().is_file())
                         .filter(|entry| {
                             allowed_extensions.is_empty()
-                                || matches!(entry.path().extension(), Some(extension)
-                                               if allowed_extensions.contains(extension.to_str().unwrap()))
+                                || entry
+                                    .path()
+                                    .extension()
+                                    .and_then(|extension| extension.to_str())
+                                    .map_or(false, |extension| {
+                                        allowed_extensions.contains(extension)
+                                    })
                         });
 
-                    for candidate in walker {
+                    for (num_crawled_files, candidate) in walker.enumerate() {
                         assert!(candidate.path().is_absolute());
 
+                        if num_crawled_files >= MAX_CRAWLED_FILES {
+                            log::warn!(
+                                "Stopped crawling '{file_path_str}' after {MAX_CRAWLED_FILES} \
+                                 files. Any remaining files in this location are ignored."
+                            );
+                            break;
+                        }
+
                         // TODO: Not quite sure what should be done with errors when crawling
                         //       directories. If the plugin doesn't return an error but also doesn't
                         //       declare any presets then that gets handled gracefully
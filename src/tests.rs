@@ -13,7 +13,6 @@ use anyhow::{Context, Result};
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::ffi::OsStr;
 use std::fmt::Display;
 use std::fs;
 use std::path::PathBuf;
@@ -21,12 +20,18 @@ use std::process::{Command, Stdio};
 use std::str::FromStr;
 use strum::IntoEnumIterator;
 
+use crate::call_trace;
+use crate::crash_report::CrashReport;
+use crate::ipc::{self, ChildOutcome, ParentListener, Watchdog};
+use crate::plugin::host::{self, HostExtensionUsage, LogMessage};
 use crate::{util, Verbosity};
 
+mod check;
 mod plugin;
 mod plugin_library;
 pub mod rng;
 
+pub use check::Check;
 pub use plugin::PluginTestCase;
 pub use plugin_library::PluginLibraryTestCase;
 
@@ -40,6 +45,21 @@ pub struct TestResult {
     pub description: String,
     /// The outcome of the test.
     pub status: TestStatus,
+    /// Host functions the plugin called from the wrong thread or from within one of its own
+    /// callbacks while running this test. These are collected separately from the test's status so
+    /// they are reported even if the test itself did not check for them, for instance because it
+    /// was skipped or because it failed earlier.
+    #[serde(rename = "thread-safety")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thread_safety: Vec<String>,
+    /// Messages the plugin logged through the host's `log` extension while running this test.
+    #[serde(rename = "plugin-log")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugin_log: Vec<LogMessage>,
+    /// The host extensions the plugin queried and used while running this test.
+    #[serde(rename = "host-extensions")]
+    #[serde(default, skip_serializing_if = "HostExtensionUsage::is_empty")]
+    pub host_extensions: HostExtensionUsage,
 }
 
 /// The result of running a test. Skipped and failed test may optionally include an explanation for
@@ -54,15 +74,74 @@ pub enum TestStatus {
     /// caught for out-of-process validation, for obvious reasons.
     Crashed { details: String },
     /// The test failed.
-    Failed { details: Option<String> },
-    /// Preconditions for running the test were not met, so the test has been skipped.
-    Skipped { details: Option<String> },
+    Failed {
+        details: Option<String>,
+        /// If the failure was caused by an error, then this contains the error's context chain
+        /// from the outermost context to the root cause. `details` contains the same messages
+        /// joined into a single line.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        causes: Vec<String>,
+    },
+    /// Preconditions for running the test were not met, so the test has been skipped. The reason
+    /// is serialized alongside the human readable details so skipped tests can be grouped.
+    Skipped {
+        /// Results written by older versions of the validator don't contain a reason.
+        #[serde(default)]
+        reason: SkipReason,
+        details: Option<String>,
+    },
     /// The test did not succeed, but this should not be treated as a hard failure. This is reserved
     /// for tests involving runtime performance that might otherwise yield different results
     /// depending on the target system.
     Warning { details: Option<String> },
 }
 
+/// Why a test was skipped. Every skipped test has exactly one of these reasons, which makes it
+/// possible to gather statistics on why tests are being skipped without parsing the details.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    Serialize,
+    strum_macros::Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum SkipReason {
+    /// The plugin does not implement an extension or factory the test needs.
+    MissingExtension,
+    /// The plugin implements the note ports extension, but it doesn't have the note ports the test
+    /// needs.
+    NoNotePorts,
+    /// The plugin doesn't have the audio ports the test needs.
+    NoAudioPorts,
+    /// The plugin implements the params extension, but it doesn't have any parameters the test can
+    /// change.
+    NoParams,
+    /// The plugin library does not expose any plugins.
+    NoPlugins,
+    /// The plugin doesn't support optional functionality the test relies on, like converting
+    /// parameter values to and from text.
+    UnsupportedFeature,
+    /// The plugin library uses a CLAP version the validator does not support.
+    UnsupportedClapVersion,
+    /// The test is not relevant on the platform the validator is running on.
+    UnsupportedPlatform,
+    /// The test needs to open the plugin's GUI.
+    RequiresGui,
+    /// The test only runs when a specific option is passed to the validator.
+    RequiresFlag,
+    /// Any other reason. The test's details contain an explanation.
+    #[default]
+    Other,
+}
+
 /// Stores all of the available tests and their descriptions. Used solely for pretty printing
 /// purposes in `clap-validator list tests`.
 #[derive(Debug, Serialize)]
@@ -70,19 +149,43 @@ pub enum TestStatus {
 pub struct TestList {
     pub plugin_library_tests: BTreeMap<String, String>,
     pub plugin_tests: BTreeMap<String, String>,
+    /// All check IDs, mapped to a description of what the check asserts.
+    pub checks: BTreeMap<Check, &'static str>,
+    /// The checks performed by each plugin library test.
+    pub plugin_library_test_checks: BTreeMap<String, Vec<Check>>,
+    /// The checks performed by each plugin test.
+    pub plugin_test_checks: BTreeMap<String, Vec<Check>>,
 }
 
 /// An abstraction for a test case. This mostly exists because we need two separate kinds of tests
 /// (per library and per plugin), and it's good to keep the interface uniform.
-pub trait TestCase<'a>: Display + FromStr + IntoEnumIterator + Sized + 'static {
+pub trait TestCase<'a>: Display + FromStr + Sized + 'static {
     /// The type of the arguments the test cases are parameterized over. This can be an instance of
     /// the plugin library and a plugin ID, or just the file path to the plugin library.
     type TestArgs;
 
+    /// All test cases of this type in the order they should be run in. Parameterized test cases are
+    /// expanded to one test case per variant.
+    fn all() -> Vec<Self>;
+
     /// Get the textual description for a test case. This description won't contain any line breaks,
     /// but it may consist of multiple sentences.
     fn description(&self) -> String;
 
+    /// The category this test case belongs to. Used to group related tests together in the human
+    /// readable output.
+    fn category(&self) -> &'static str;
+
+    /// The individual checks this test case performs. These are stable IDs that can be used to map
+    /// the validator's coverage to external requirements.
+    fn checks(&self) -> Vec<Check>;
+
+    /// Whether this test case needs to open the plugin's GUI. These tests are skipped on headless
+    /// systems unless the validator's `--force-gui-tests` option is used.
+    fn requires_gui(&self) -> bool {
+        false
+    }
+
     /// Set the arguments for `clap-validator run-single-test` to run this test with the specified
     /// arguments. This way the [`run_out_of_process()`][Self::run_out_of_process()] method can be
     /// defined in a way that works for all `TestCase`s.
@@ -99,7 +202,12 @@ pub trait TestCase<'a>: Display + FromStr + IntoEnumIterator + Sized + 'static {
 
     /// Run a test case for a plugin in another process, returning the result. If the test cuases the
     /// plugin to segfault, then the result will have a status of `TestStatus::Crashed`. If
-    /// `hide_output` is set, then the tested plugin's output will not be printed to STDIO.
+    /// `hide_output` is set, then the tested plugin's output will not be printed to STDIO. If
+    /// `wait_for_debugger` is set, then the child process will print its process ID and wait for
+    /// the user to press Enter before running the test so a debugger can be attached to it. The
+    /// child process is killed and the test is marked as failed if it stops responding, if the test
+    /// takes longer than the watchdog's test timeout, or if a single call to one of the plugin's
+    /// functions takes longer than the watchdog's plugin call timeout.
     ///
     /// The verbosity option is threaded through here so out of process tests use the same logger
     /// verbosity as in-process tests.
@@ -113,20 +221,23 @@ pub trait TestCase<'a>: Display + FromStr + IntoEnumIterator + Sized + 'static {
         args: Self::TestArgs,
         verbosity: Verbosity,
         hide_output: bool,
+        wait_for_debugger: bool,
+        watchdog: Watchdog,
     ) -> Result<TestResult> {
         // The idea here is that we'll invoke the same clap-validator binary with a special hidden command
         // that runs a single test. This is the reason why test cases must be convertible to and
-        // from strings. If everything goes correctly, then the child process will write the results
-        // as JSON to the specified file path. This is intentionaly not done through STDIO since the
-        // hosted plugin may also write things there, and doing STDIO redirection within the child
-        // process is more complicated than just writing the result to a temporary file.
-
-        // This temporary file will automatically be removed when this function exits
-        let output_file_path = tempfile::Builder::new()
-            .suffix(".json")
-            .tempfile()
-            .context("Could not create a temporary file path")?
-            .into_temp_path();
+        // from strings. The child process connects to a socket owned by this process, and it sends
+        // its log messages, heartbeats, and eventually the test's result there. See the `ipc`
+        // module for more information.
+        let listener = ParentListener::bind()?;
+
+        // This temporary directory will automatically be removed when this function exits. The
+        // child process only writes a crash report here when it crashes on Windows.
+        let crash_report_dir = tempfile::Builder::new()
+            .prefix("clap-validator-")
+            .tempdir()
+            .context("Could not create a temporary directory for crash reports")?;
+        let crash_report_path = crash_report_dir.path().join("crash.json");
         let clap_validator_binary =
             std::env::current_exe().context("Could not find the path to the current executable")?;
         let mut command = Command::new(clap_validator_binary);
@@ -135,42 +246,119 @@ pub trait TestCase<'a>: Display + FromStr + IntoEnumIterator + Sized + 'static {
             .arg("--verbosity")
             .arg(verbosity.to_possible_value().unwrap().get_name())
             .arg("run-single-test")
-            .args([OsStr::new("--output-file"), output_file_path.as_os_str()]);
+            .arg("--ipc-address")
+            .arg(listener.address()?.to_string())
+            .args(["--ipc-token", listener.token()])
+            .arg("--crash-report-file")
+            .arg(&crash_report_path)
+            .arg("--shared-settings")
+            .arg(
+                serde_json::to_string(&util::shared_settings())
+                    .expect("Could not serialize the validator's settings"),
+            );
+        if wait_for_debugger {
+            command.arg("--wait-for-debugger");
+        }
+        if call_trace::recording() {
+            command.arg("--record-trace");
+        }
         self.set_out_of_process_args(&mut command, args);
         if hide_output {
             command.stdout(Stdio::null());
             command.stderr(Stdio::null());
         }
 
-        let exit_status = command
+        let mut child = command
             .spawn()
-            .context("Could not call clap-validator for out-of-process validation")?
-            // The docs make it seem like this can only fail if the process isn't running, but if
-            // spawn succeeds then this can never fail:
-            .wait()
-            .context("Error while waiting on clap-validator to finish running the test")?;
-        if !exit_status.success() {
-            return Ok(TestResult {
-                name: self.to_string(),
-                description: self.description(),
-                status: TestStatus::Crashed {
-                    details: exit_status.to_string(),
-                },
-            });
-        }
+            .context("Could not call clap-validator for out-of-process validation")?;
+        let outcome = listener
+            .run(&mut child, &self.to_string(), !hide_output, watchdog)
+            .context("Error while communicating with the child process")?;
+
+        let status = match outcome {
+            ChildOutcome::Exited {
+                status,
+                crash_context,
+                ..
+            } if !status.success() => {
+                // On Windows the child process writes a crash report with the faulting module and
+                // a minidump when it crashes
+                let mut details = match CrashReport::take(&crash_report_path) {
+                    Some(crash_report) => format!("{status}. {}", crash_report.details()),
+                    None => status.to_string(),
+                };
+                if let Some(crash_context) = crash_context {
+                    if !details.ends_with('.') {
+                        details.push('.');
+                    }
+                    details.push_str(&format!(" The plugin crashed while {crash_context}."));
+                }
 
-        // At this point, the child process _should_ have written its output to `output_file_path`,
-        // and we can just parse it from there
-        let result =
-            serde_json::from_str(&fs::read_to_string(&output_file_path).with_context(|| {
-                format!(
-                    "Could not read the child process output from '{}'",
-                    output_file_path.display()
-                )
-            })?)
-            .context("Could not parse the child process output to JSON")?;
-
-        Ok(result)
+                TestStatus::Crashed { details }
+            }
+            ChildOutcome::Exited {
+                result: Some(result),
+                ..
+            } => return Ok(result),
+            ChildOutcome::Exited { result: None, .. } => {
+                anyhow::bail!("The child process exited without sending the test's result")
+            }
+            ChildOutcome::Unresponsive => TestStatus::from_error(&anyhow::anyhow!(
+                "The test's process stopped responding and it was killed. No messages were \
+                 received from it for {} seconds.",
+                ipc::HEARTBEAT_TIMEOUT.as_secs()
+            )),
+            ChildOutcome::Stalled { last_step } => {
+                let mut message = format!(
+                    "The test stopped making progress and its process was killed. It did not call \
+                     any of the plugin's functions for {} seconds.",
+                    ipc::HEARTBEAT_TIMEOUT.as_secs()
+                );
+                if let Some(last_step) = last_step {
+                    message.push_str(&format!(" The test was last {last_step}."));
+                }
+
+                TestStatus::from_error(&anyhow::anyhow!(message))
+            }
+            ChildOutcome::TimedOut {
+                timeout,
+                pending_plugin_calls,
+                last_step,
+            } => {
+                let mut message = format!(
+                    "The test did not finish within {} seconds and its process was killed.",
+                    timeout.as_secs()
+                );
+                if !pending_plugin_calls.is_empty() {
+                    message.push_str(" The plugin was still handling these calls: ");
+                    message.push_str(
+                        &pending_plugin_calls
+                            .iter()
+                            .map(|call| call.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    );
+                    message.push('.');
+                }
+                if let Some(last_step) = last_step {
+                    message.push_str(&format!(" The test was last {last_step}."));
+                }
+
+                TestStatus::from_error(&anyhow::anyhow!(message))
+            }
+            ChildOutcome::StuckPluginCall(call) => TestStatus::from_error(&anyhow::anyhow!(
+                "A call to {call} did not return, and the test's process was killed."
+            )),
+        };
+
+        Ok(TestResult {
+            name: self.to_string(),
+            description: self.description(),
+            status,
+            thread_safety: Vec::new(),
+            plugin_log: Vec::new(),
+            host_extensions: HostExtensionUsage::default(),
+        })
     }
 
     /// Get a writable temporary file handle for this test case. The file will be located at
@@ -200,18 +388,31 @@ pub trait TestCase<'a>: Display + FromStr + IntoEnumIterator + Sized + 'static {
     /// Create a [`TestResult`] for this test case. The test status is wrapped in an anyhow
     /// [`Result`] to make writing test cases more ergonomic using the question mark operator. `Err`
     /// values are converted to [`TestStatus::Failed`] statuses containing the full error backtrace.
+    /// Any thread safety issues recorded by the hosts used in the test, any messages the plugin
+    /// logged through those hosts, and the host extensions the plugin queried and used are added to
+    /// the result.
     fn create_result(&self, status: Result<TestStatus>) -> TestResult {
         TestResult {
             name: self.to_string(),
             description: self.description(),
-            status: status.unwrap_or_else(|err| TestStatus::Failed {
-                details: Some(format!("{err:#}")),
-            }),
+            status: status.unwrap_or_else(|err| TestStatus::from_error(&err)),
+            thread_safety: host::take_thread_safety_audit(),
+            plugin_log: host::take_plugin_log(),
+            host_extensions: host::take_host_extension_usage(),
         }
     }
 }
 
 impl TestStatus {
+    /// Create a [`TestStatus::Failed`] status for an error. The error's entire context chain is
+    /// stored in the status, both as a single line and as separate causes.
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        TestStatus::Failed {
+            details: Some(format!("{err:#}")),
+            causes: err.chain().map(|cause| cause.to_string()).collect(),
+        }
+    }
+
     /// Returns `true` if tests with this status should be shown when running the validator with the
     /// `--only-failed` option.
     pub fn failed_or_warning(&self) -> bool {
@@ -227,8 +428,8 @@ impl TestStatus {
     pub fn details(&self) -> Option<&str> {
         match self {
             TestStatus::Success { details }
-            | TestStatus::Failed { details }
-            | TestStatus::Skipped { details }
+            | TestStatus::Failed { details, .. }
+            | TestStatus::Skipped { details, .. }
             | TestStatus::Warning { details } => details.as_deref(),
             TestStatus::Crashed { details } => Some(details),
         }
@@ -238,12 +439,23 @@ impl TestStatus {
 impl Default for TestList {
     fn default() -> Self {
         Self {
-            plugin_library_tests: PluginLibraryTestCase::iter()
+            plugin_library_tests: PluginLibraryTestCase::all()
+                .into_iter()
                 .map(|c| (c.to_string(), c.description()))
                 .collect(),
-            plugin_tests: PluginTestCase::iter()
+            plugin_tests: PluginTestCase::all()
+                .into_iter()
                 .map(|c| (c.to_string(), c.description()))
                 .collect(),
+            checks: Check::iter().map(|c| (c, c.description())).collect(),
+            plugin_library_test_checks: PluginLibraryTestCase::all()
+                .into_iter()
+                .map(|c| (c.to_string(), c.checks()))
+                .collect(),
+            plugin_test_checks: PluginTestCase::all()
+                .into_iter()
+                .map(|c| (c.to_string(), c.checks()))
+                .collect(),
         }
     }
 }
@@ -0,0 +1,120 @@
+//! The individual checks performed by the validator's tests. Every test case lists the checks it
+//! performs, which makes it possible to map the validator's coverage to external requirements
+//! without having to parse the tests' descriptions. The check IDs are stable, so checks may be
+//! added, but existing IDs should never be renamed or removed.
+
+use serde::Serialize;
+
+/// Defines the [`Check`] enum. Every check is listed together with its description, which is used
+/// both as the variant's doc comment and as the check's [`Check::description()`].
+macro_rules! checks {
+    ($($variant:ident => $description:literal,)*) => {
+        /// A single assertion made by one or more tests. Serialized as a kebab-case check ID, in
+        /// the same way as [`SkipReason`][super::SkipReason].
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Serialize,
+            strum_macros::Display,
+            strum_macros::EnumIter,
+        )]
+        #[serde(rename_all = "kebab-case")]
+        #[strum(serialize_all = "kebab-case")]
+        pub enum Check {
+            $(
+                #[doc = $description]
+                $variant,
+            )*
+        }
+
+        impl Check {
+            /// A short description of what this check asserts.
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(Check::$variant => $description,)*
+                }
+            }
+        }
+    };
+}
+
+checks! {
+    HostCallbacks =>
+        "Host callbacks are made from the correct threads, with valid arguments, and at the right \
+         points in the plugin's lifecycle.",
+    LibraryLoading => "The plugin library loads, and the plugin's entry point can be initialized.",
+    ScanTime => "The plugin library can be scanned within a time limit.",
+    PluginDescriptors => "The plugin factory's descriptors have unique IDs and non-empty names.",
+    FactoryBehavior =>
+        "The plugin factory rejects unknown factory IDs and plugin IDs, and it can be used from \
+         multiple threads at the same time.",
+    PresetDiscovery =>
+        "Preset discovery providers can be created, and their locations can be crawled.",
+    PresetLoading => "Presets found through preset discovery can be loaded by the plugin.",
+    DescriptorValidity => "The plugin's descriptor contains valid and consistent information.",
+    Features => "The plugin's features are well-formed and contain a main category.",
+    InstanceTiming => "Plugin instances can be created and destroyed within a time limit.",
+    ExtensionQueries =>
+        "The plugin's extensions can be queried, and their vtables don't contain null pointers.",
+    HostIndependence =>
+        "The plugin behaves the same regardless of the host's name, optional host functions, and \
+         the working directory.",
+    AudioPorts => "The plugin's audio port configuration is valid.",
+    NotePorts => "The plugin's note port configuration is valid.",
+    Processing => "The plugin can be activated, and audio processing succeeds.",
+    OutputAudio =>
+        "The plugin only outputs finite audio samples, and it does not modify its input buffers.",
+    OutputEvents =>
+        "The plugin's output events are in order, fall within the block, and have valid types and \
+         sizes.",
+    BlockSizes =>
+        "The plugin handles every block size within its activation range, including very small \
+         blocks and blocks smaller than the maximum block size.",
+    NoteEvents => "The plugin handles note events, including events for notes that do not exist.",
+    Transport => "The plugin handles transport information and transport events.",
+    Restarts =>
+        "The plugin handles the host restarting it, both on its own request and when the host's \
+         audio settings change.",
+    ThreadPool => "The plugin uses the host's thread pool correctly.",
+    EventSpaces => "The plugin handles note dialects and event spaces correctly.",
+    ParamInfo => "The plugin's parameter information is valid and consistent.",
+    ParamSanity => "The plugin's parameters are not degenerate.",
+    ParamConversions =>
+        "Parameter values roundtrip through the plugin's value to text and text to value \
+         conversions.",
+    ParamAutomation =>
+        "Parameter value and modulation events sent to the plugin are applied, including events \
+         at block boundaries and per-note events.",
+    ParamEventSpaces => "The plugin ignores events from unknown event spaces.",
+    ParamEcho =>
+        "The plugin does not output parameter values that contradict the host's automation.",
+    ParamRescan =>
+        "Parameter changes reported through 'clap_host_params::rescan()' match the rescan's \
+         flags.",
+    ParamFlush => "Parameters can be set through 'clap_plugin_params::flush()'.",
+    ParamOutputEvents =>
+        "The parameter events output by the plugin refer to existing parameters, contain values \
+         within the parameters' ranges, and have balanced gestures.",
+    StateReproducibility =>
+        "Saving and reloading the plugin's state restores its parameters, and saving it again \
+         results in the same state.",
+    StateInvalid => "The plugin rejects invalid states.",
+    StateStreams => "The plugin can read and write states in small chunks.",
+    TailLength => "The plugin's tail length is consistent with its output.",
+    RenderModes => "The plugin's render modes can be set and are reported correctly.",
+    Timers => "The plugin uses the host's timer support correctly.",
+    RemoteControls => "The plugin's remote control pages are valid.",
+    TrackInfo => "The plugin handles track information changes.",
+    ContextMenu => "The plugin's context menus can be populated and performed.",
+    WrongThreadCalls =>
+        "The plugin does not crash when the host calls its main thread functions from the audio \
+         thread. Only checked by the negative tests.",
+    WrongStateCalls =>
+        "The plugin rejects calls made in the wrong lifecycle state, like starting audio \
+         processing before the plugin has been activated. Only checked by the negative tests.",
+}
@@ -1,50 +1,338 @@
 //! Tests for individual plugin instances.
 
 use clap::ValueEnum;
+use std::fmt::Display;
 use std::process::Command;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
 
-use super::{TestCase, TestResult};
+use super::{Check, TestCase, TestResult};
 use crate::plugin::library::PluginLibrary;
 
+mod audio_ports;
+mod context_menu;
 mod descriptor;
+mod instance;
+mod note_effect;
 mod params;
 mod processing;
+mod remote_controls;
+mod render;
 mod state;
+mod tail;
+mod timer_support;
+mod track_info;
+mod working_directory;
 
 pub use processing::ProcessingTest;
 
 /// The tests for individual CLAP plugins. See the module's heading for more information, and the
 /// `description` function below for a description of each test case.
-#[derive(strum_macros::Display, strum_macros::EnumString, strum_macros::EnumIter)]
+///
+/// Some tests are parameterized over a variant enum, like [`ProcessingVariant`]. Every variant of
+/// those enums results in a separate test case, and the test's name is the name of the test family
+/// followed by the variant's name. Adding a variant to one of those enums thus automatically adds
+/// a test case for every test family using it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PluginTestCase {
-    #[strum(serialize = "descriptor-consistency")]
     DescriptorConsistency,
-    #[strum(serialize = "features-categories")]
     FeaturesCategories,
-    #[strum(serialize = "features-duplicates")]
     FeaturesDuplicates,
-    #[strum(serialize = "process-audio-out-of-place-basic")]
-    ProcessAudioOutOfPlaceBasic,
-    #[strum(serialize = "process-note-out-of-place-basic")]
-    ProcessNoteOutOfPlaceBasic,
-    #[strum(serialize = "process-note-inconsistent")]
+    InstanceCreationTime,
+    InstanceDestroyTime,
+    ExtensionCoverage,
+    HostNameIndependence,
+    HostMissingFunctions,
+    WorkingDirectoryIndependence,
+    AudioPortFlags,
+    AudioPortsRescanActive,
+    AudioPortsConfigSelectActive,
+    ProcessAudio(ProcessingVariant),
+    ProcessNote(ProcessingVariant),
     ProcessNoteInconsistent,
-    #[strum(serialize = "param-conversions")]
+    ProcessNoteOversizedEvents,
+    ProcessNoteSysex,
+    ProcessNoteEnd,
+    ProcessHostRestarts,
+    ProcessBufferSizeRange,
+    ProcessConfigSweep,
+    ProcessTinyBlocks,
+    ProcessTransportEvents,
+    ProcessTransportVariations,
+    ProcessThreadPool,
+    ProcessStartBeforeActivate,
+    NoteDialect(NoteDialectVariant),
+    ProcessNoteDialectPreference(NoteDialectPreferenceVariant),
+    ProcessNoteOutputOnly,
+    ProcessNoteEffectChain,
+    ProcessNotePanic(NotePanicVariant),
+    EventRegistry(EventRegistryVariant),
+    ParamSanity,
     ParamConversions,
-    #[strum(serialize = "param-fuzz-basic")]
     ParamFuzzBasic,
-    #[strum(serialize = "param-set-wrong-namespace")]
     ParamSetWrongNamespace,
-    #[strum(serialize = "state-invalid")]
+    ParamEchoConsistency,
+    ParamAutomationBlockEdges,
+    ParamPolyphonicAutomation,
+    ParamRescanStateLoad,
+    ParamFlushDeactivated,
+    ParamOutputEvents,
+    ParamInfoAudioThread,
     StateInvalid,
-    #[strum(serialize = "state-reproducibility-basic")]
-    StateReproducibilityBasic,
-    #[strum(serialize = "state-reproducibility-null-cookies")]
-    StateReproducibilityNullCookies,
-    #[strum(serialize = "state-reproducibility-flush")]
-    StateReproducibilityFlush,
-    #[strum(serialize = "state-buffered-streams")]
+    StateReproducibility(StateReproducibilityVariant),
     StateBufferedStreams,
+    TailLength,
+    RenderModes,
+    TimerSupport,
+    RemoteControlsPages,
+    TrackInfoChanged,
+    ContextMenuPopulate,
+}
+
+/// The variants for the `process-audio-*` and `process-note-*` tests. These determine how audio is
+/// passed to the plugin.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+pub enum ProcessingVariant {
+    /// Out-of-place processing with the plugin's default parameter values.
+    #[strum(serialize = "out-of-place-basic")]
+    OutOfPlaceBasic,
+    /// The same as `OutOfPlaceBasic`, but the plugin is activated with the same minimum and maximum
+    /// block size.
+    #[strum(serialize = "out-of-place-fixed-block-size")]
+    OutOfPlaceFixedBlockSize,
+    /// The same as `OutOfPlaceBasic`, but every process call uses a random block size.
+    #[strum(serialize = "out-of-place-variable-block-size")]
+    OutOfPlaceVariableBlockSize,
+}
+
+/// The variants for the `note-dialect-*` tests. These determine which note dialect the host claims
+/// to support.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+pub enum NoteDialectVariant {
+    /// The host only supports `CLAP_NOTE_DIALECT_MIDI`.
+    #[strum(serialize = "midi-only")]
+    MidiOnly,
+    /// The host only supports `CLAP_NOTE_DIALECT_CLAP`.
+    #[strum(serialize = "clap-only")]
+    ClapOnly,
+}
+
+/// The variants for the `process-note-*-dialect` tests. These determine which of a note port's
+/// supported note dialects the host sends events in.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+pub enum NoteDialectPreferenceVariant {
+    /// Events are only sent in each port's preferred note dialect.
+    #[strum(serialize = "preferred")]
+    Preferred,
+    /// Events are only sent in supported note dialects other than each port's preferred dialect.
+    #[strum(serialize = "non-preferred")]
+    NonPreferred,
+}
+
+/// The variants for the `process-note-panic-*` tests. These determine how the host tries to stop
+/// all of the plugin's voices.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+pub enum NotePanicVariant {
+    /// The host sends MIDI 'All Notes Off' messages on every channel.
+    #[strum(serialize = "all-notes-off")]
+    AllNotesOff,
+    /// The host calls `clap_plugin::reset()`.
+    #[strum(serialize = "reset")]
+    Reset,
+}
+
+/// The variants for the `event-registry-*` tests.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+pub enum EventRegistryVariant {
+    /// The host registers every event space the plugin queries.
+    #[strum(serialize = "accepted")]
+    Accepted,
+    /// The host declines to register any event spaces.
+    #[strum(serialize = "declined")]
+    Declined,
+}
+
+/// The variants for the `state-reproducibility-*` tests.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+pub enum StateReproducibilityVariant {
+    /// Parameter values are set using the process function.
+    #[strum(serialize = "basic")]
+    Basic,
+    /// The same as `Basic`, but with all cookies in the parameter events set to null pointers.
+    #[strum(serialize = "null-cookies")]
+    NullCookies,
+    /// Parameter values for the second state are set using the flush function.
+    #[strum(serialize = "flush")]
+    Flush,
+    /// The same as `Basic`, but the second instance's parameters are changed before the state is
+    /// loaded.
+    #[strum(serialize = "overwrite")]
+    Overwrite,
+    /// The state is loaded on the main thread while the audio thread is processing audio.
+    #[strum(serialize = "while-processing")]
+    WhileProcessing,
+}
+
+impl ProcessingVariant {
+    /// A sentence describing this variant, appended to the processing tests' descriptions.
+    fn description(&self) -> &'static str {
+        match self {
+            ProcessingVariant::OutOfPlaceBasic => "Uses out-of-place audio processing.",
+            ProcessingVariant::OutOfPlaceFixedBlockSize => {
+                "Uses out-of-place audio processing with a fixed block size, with the plugin \
+                 activated using the same minimum and maximum frame counts."
+            }
+            ProcessingVariant::OutOfPlaceVariableBlockSize => {
+                "Uses out-of-place audio processing with a random block size for every process \
+                 call. Fails if the plugin only works with fixed block sizes, since CLAP plugins \
+                 cannot declare such a requirement."
+            }
+        }
+    }
+}
+
+impl Display for PluginTestCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginTestCase::DescriptorConsistency => write!(f, "descriptor-consistency"),
+            PluginTestCase::FeaturesCategories => write!(f, "features-categories"),
+            PluginTestCase::FeaturesDuplicates => write!(f, "features-duplicates"),
+            PluginTestCase::InstanceCreationTime => write!(f, "instance-creation-time"),
+            PluginTestCase::InstanceDestroyTime => write!(f, "instance-destroy-time"),
+            PluginTestCase::ExtensionCoverage => write!(f, "extension-coverage"),
+            PluginTestCase::HostNameIndependence => write!(f, "host-name-independence"),
+            PluginTestCase::HostMissingFunctions => write!(f, "host-missing-functions"),
+            PluginTestCase::WorkingDirectoryIndependence => {
+                write!(f, "working-directory-independence")
+            }
+            PluginTestCase::AudioPortFlags => write!(f, "audio-port-flags"),
+            PluginTestCase::AudioPortsRescanActive => write!(f, "audio-ports-rescan-active"),
+            PluginTestCase::AudioPortsConfigSelectActive => {
+                write!(f, "audio-ports-config-select-active")
+            }
+            PluginTestCase::ProcessAudio(variant) => write!(f, "process-audio-{variant}"),
+            PluginTestCase::ProcessNote(variant) => write!(f, "process-note-{variant}"),
+            PluginTestCase::ProcessNoteInconsistent => write!(f, "process-note-inconsistent"),
+            PluginTestCase::ProcessNoteOversizedEvents => {
+                write!(f, "process-note-oversized-events")
+            }
+            PluginTestCase::ProcessNoteSysex => write!(f, "process-note-sysex"),
+            PluginTestCase::ProcessNoteEnd => write!(f, "process-note-end"),
+            PluginTestCase::ProcessHostRestarts => write!(f, "process-host-restarts"),
+            PluginTestCase::ProcessBufferSizeRange => write!(f, "process-buffer-size-range"),
+            PluginTestCase::ProcessConfigSweep => write!(f, "process-config-sweep"),
+            PluginTestCase::ProcessTinyBlocks => write!(f, "process-tiny-blocks"),
+            PluginTestCase::ProcessTransportEvents => write!(f, "process-transport-events"),
+            PluginTestCase::ProcessTransportVariations => {
+                write!(f, "process-transport-variations")
+            }
+            PluginTestCase::ProcessThreadPool => write!(f, "process-thread-pool"),
+            PluginTestCase::ProcessStartBeforeActivate => {
+                write!(f, "process-start-before-activate")
+            }
+            PluginTestCase::NoteDialect(variant) => write!(f, "note-dialect-{variant}"),
+            PluginTestCase::ProcessNoteDialectPreference(variant) => {
+                write!(f, "process-note-{variant}-dialect")
+            }
+            PluginTestCase::ProcessNoteOutputOnly => write!(f, "process-note-output-only"),
+            PluginTestCase::ProcessNoteEffectChain => write!(f, "process-note-effect-chain"),
+            PluginTestCase::ProcessNotePanic(variant) => write!(f, "process-note-panic-{variant}"),
+            PluginTestCase::EventRegistry(variant) => write!(f, "event-registry-{variant}"),
+            PluginTestCase::ParamSanity => write!(f, "param-sanity"),
+            PluginTestCase::ParamConversions => write!(f, "param-conversions"),
+            PluginTestCase::ParamFuzzBasic => write!(f, "param-fuzz-basic"),
+            PluginTestCase::ParamSetWrongNamespace => write!(f, "param-set-wrong-namespace"),
+            PluginTestCase::ParamEchoConsistency => write!(f, "param-echo-consistency"),
+            PluginTestCase::ParamAutomationBlockEdges => {
+                write!(f, "param-automation-block-edges")
+            }
+            PluginTestCase::ParamPolyphonicAutomation => {
+                write!(f, "param-polyphonic-automation")
+            }
+            PluginTestCase::ParamRescanStateLoad => write!(f, "param-rescan-state-load"),
+            PluginTestCase::ParamFlushDeactivated => write!(f, "param-flush-deactivated"),
+            PluginTestCase::ParamOutputEvents => write!(f, "param-output-events"),
+            PluginTestCase::ParamInfoAudioThread => write!(f, "param-info-audio-thread"),
+            PluginTestCase::StateInvalid => write!(f, "state-invalid"),
+            PluginTestCase::StateReproducibility(variant) => {
+                write!(f, "state-reproducibility-{variant}")
+            }
+            PluginTestCase::StateBufferedStreams => write!(f, "state-buffered-streams"),
+            PluginTestCase::TailLength => write!(f, "tail-length"),
+            PluginTestCase::RenderModes => write!(f, "render-modes"),
+            PluginTestCase::TimerSupport => write!(f, "timer-support"),
+            PluginTestCase::RemoteControlsPages => write!(f, "remote-controls-pages"),
+            PluginTestCase::TrackInfoChanged => write!(f, "track-info-changed"),
+            PluginTestCase::ContextMenuPopulate => write!(f, "context-menu-populate"),
+        }
+    }
+}
+
+impl FromStr for PluginTestCase {
+    type Err = strum::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The parameterized test names can't be parsed unambiguously on their own, so this simply
+        // compares the name against all test cases
+        Self::all()
+            .into_iter()
+            .find(|test| test.to_string() == s)
+            .ok_or(strum::ParseError::VariantNotFound)
+    }
 }
 
 impl<'a> TestCase<'a> for PluginTestCase {
@@ -52,6 +340,75 @@ impl<'a> TestCase<'a> for PluginTestCase {
     /// should be tested.
     type TestArgs = (&'a PluginLibrary, &'a str);
 
+    fn all() -> Vec<Self> {
+        let mut tests = vec![
+            PluginTestCase::DescriptorConsistency,
+            PluginTestCase::FeaturesCategories,
+            PluginTestCase::FeaturesDuplicates,
+            PluginTestCase::InstanceCreationTime,
+            PluginTestCase::InstanceDestroyTime,
+            PluginTestCase::ExtensionCoverage,
+            PluginTestCase::HostNameIndependence,
+            PluginTestCase::HostMissingFunctions,
+            PluginTestCase::WorkingDirectoryIndependence,
+            PluginTestCase::AudioPortFlags,
+            PluginTestCase::AudioPortsRescanActive,
+            PluginTestCase::AudioPortsConfigSelectActive,
+        ];
+        tests.extend(ProcessingVariant::iter().map(PluginTestCase::ProcessAudio));
+        tests.extend(ProcessingVariant::iter().map(PluginTestCase::ProcessNote));
+        tests.extend([
+            PluginTestCase::ProcessNoteInconsistent,
+            PluginTestCase::ProcessNoteOversizedEvents,
+            PluginTestCase::ProcessNoteSysex,
+            PluginTestCase::ProcessNoteEnd,
+            PluginTestCase::ProcessHostRestarts,
+            PluginTestCase::ProcessBufferSizeRange,
+            PluginTestCase::ProcessConfigSweep,
+            PluginTestCase::ProcessTinyBlocks,
+            PluginTestCase::ProcessTransportEvents,
+            PluginTestCase::ProcessTransportVariations,
+            PluginTestCase::ProcessThreadPool,
+            PluginTestCase::ProcessStartBeforeActivate,
+        ]);
+        tests.extend(NoteDialectVariant::iter().map(PluginTestCase::NoteDialect));
+        tests.extend(
+            NoteDialectPreferenceVariant::iter().map(PluginTestCase::ProcessNoteDialectPreference),
+        );
+        tests.extend([
+            PluginTestCase::ProcessNoteOutputOnly,
+            PluginTestCase::ProcessNoteEffectChain,
+        ]);
+        tests.extend(NotePanicVariant::iter().map(PluginTestCase::ProcessNotePanic));
+        tests.extend(EventRegistryVariant::iter().map(PluginTestCase::EventRegistry));
+        tests.extend([
+            PluginTestCase::ParamSanity,
+            PluginTestCase::ParamConversions,
+            PluginTestCase::ParamFuzzBasic,
+            PluginTestCase::ParamSetWrongNamespace,
+            PluginTestCase::ParamEchoConsistency,
+            PluginTestCase::ParamAutomationBlockEdges,
+            PluginTestCase::ParamPolyphonicAutomation,
+            PluginTestCase::ParamRescanStateLoad,
+            PluginTestCase::ParamFlushDeactivated,
+            PluginTestCase::ParamOutputEvents,
+            PluginTestCase::ParamInfoAudioThread,
+            PluginTestCase::StateInvalid,
+        ]);
+        tests.extend(StateReproducibilityVariant::iter().map(PluginTestCase::StateReproducibility));
+        tests.extend([
+            PluginTestCase::StateBufferedStreams,
+            PluginTestCase::TailLength,
+            PluginTestCase::RenderModes,
+            PluginTestCase::TimerSupport,
+            PluginTestCase::RemoteControlsPages,
+            PluginTestCase::TrackInfoChanged,
+            PluginTestCase::ContextMenuPopulate,
+        ]);
+
+        tests
+    }
+
     fn description(&self) -> String {
         match self {
             PluginTestCase::DescriptorConsistency => String::from(
@@ -59,26 +416,226 @@ impl<'a> TestCase<'a> for PluginTestCase {
                  stored on the 'clap_plugin object should be equivalent.",
             ),
             PluginTestCase::FeaturesCategories => String::from(
-                "The plugin needs to have at least one of the main CLAP category features.",
+                "The plugin needs to have at least one of the main CLAP category features. \
+                 Features that are not defined by CLAP and that are not namespaced (e.g. \
+                 'vendor:feature') result in a warning.",
             ),
             PluginTestCase::FeaturesDuplicates => {
                 String::from("The plugin's features array should not contain any duplicates.")
             }
-            PluginTestCase::ProcessAudioOutOfPlaceBasic => String::from(
+            PluginTestCase::InstanceCreationTime => String::from(
+                "Measures how long it takes to create and initialize an instance of the plugin. \
+                 Results in a warning if this exceeds the threshold set with the \
+                 '--creation-time-threshold' option, as slow instantiation slows down loading \
+                 projects in a host.",
+            ),
+            PluginTestCase::InstanceDestroyTime => String::from(
+                "Activates and deactivates the plugin once, and then measures how long it takes \
+                 to destroy the plugin instance. Results in a warning if this exceeds the \
+                 threshold set with the '--destroy-time-threshold' option, as slow teardown \
+                 stalls the host when closing a project.",
+            ),
+            PluginTestCase::ExtensionCoverage => String::from(
+                "Lists the extensions the plugin implements that clap-validator does not have any \
+                 tests for, including draft extensions. These extensions are not validated. This \
+                 test never fails.",
+            ),
+            PluginTestCase::HostNameIndependence => String::from(
+                "Creates instances of the plugin while the host reports itself as several \
+                 well-known hosts, and compares the plugin's extensions, audio and note ports, and \
+                 parameters to those of an instance created with the regular host name. \
+                 Differences result in a warning, since they mean that the other tests' results \
+                 may not apply to those hosts.",
+            ),
+            PluginTestCase::HostMissingFunctions => String::from(
+                "A negative test that only runs with the '--negative-tests' option. Provides the \
+                 host's extensions with null pointers for their logging and notification \
+                 functions, like 'clap_host_log::log()', 'clap_host_state::mark_dirty()', and \
+                 'clap_host_params::rescan()'. The plugin is initialized, processes audio, and \
+                 has its state saved and reloaded. It should check these function pointers \
+                 before calling them.",
+            ),
+            PluginTestCase::WorkingDirectoryIndependence => String::from(
+                "Changes the working directory to an empty read-only directory before creating \
+                 the plugin, and then initializes the plugin, processes audio, and saves and \
+                 reloads its state. The plugin should not rely on the working directory to \
+                 locate its resources, and it should not create any files there.",
+            ),
+            PluginTestCase::AudioPortFlags => String::from(
+                "Checks whether the plugin's audio ports have consistent flags. There can be at \
+                 most one main input and one main output port, which must be the first port, and \
+                 ports that prefer 64-bit audio must also support it. The stable IDs and flags \
+                 should be the same for every instance of the plugin.",
+            ),
+            PluginTestCase::AudioPortsRescanActive => format!(
+                "Processes {} blocks of audio and reloads the plugin's state while it is \
+                 activated. The plugin may not call 'clap_host_audio_ports::rescan()' with flags \
+                 other than 'CLAP_AUDIO_PORTS_RESCAN_NAMES' while it is activated or processing.",
+                audio_ports::RESCAN_ACTIVE_NUM_BLOCKS
+            ),
+            PluginTestCase::AudioPortsConfigSelectActive => String::from(
+                "A negative test that only runs with the '--negative-tests' option. Calls \
+                 'clap_plugin_audio_ports_config::select()' while the plugin is activated. Audio \
+                 port configurations may only be selected while the plugin is deactivated, so the \
+                 plugin should reject this call without crashing, and its audio ports should not \
+                 change. Accepting the call results in a warning.",
+            ),
+            PluginTestCase::ProcessAudio(variant) => format!(
                 "Processes random audio through the plugin with its default parameter values and \
-                 tests whether the output does not contain any non-finite or subnormal values. \
-                 Uses out-of-place audio processing.",
+                 tests whether the output does not contain any non-finite or subnormal values. {}",
+                variant.description()
             ),
-            PluginTestCase::ProcessNoteOutOfPlaceBasic => String::from(
+            PluginTestCase::ProcessNote(variant) => format!(
                 "Sends audio and random note and MIDI events to the plugin with its default \
-                 parameter values and tests the output for consistency. Uses out-of-place audio \
-                 processing.",
+                 parameter values and tests the output for consistency. {}",
+                variant.description()
             ),
             PluginTestCase::ProcessNoteInconsistent => String::from(
                 "Sends intentionally inconsistent and mismatching note and MIDI events to the \
                  plugin with its default parameter values and tests the output for consistency. \
                  Uses out-of-place audio processing.",
             ),
+            PluginTestCase::ProcessNoteOversizedEvents => String::from(
+                "Sends audio and random note and MIDI events to the plugin where every event's \
+                 'header.size' field is larger than the event's struct, simulating extended \
+                 events from a future CLAP version. The plugin should only read the part of the \
+                 event it knows about. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessNoteSysex => format!(
+                "Sends only MIDI system exclusive messages to every input note port that \
+                 supports MIDI, ranging from messages without any data bytes to a message with \
+                 {} data bytes. The plugin should not crash, and any sysex events it outputs \
+                 must be valid. Uses out-of-place audio processing.",
+                processing::SYSEX_LONG_MESSAGE_DATA_BYTES
+            ),
+            PluginTestCase::ProcessNoteEnd => String::from(
+                "Plays and releases notes using CLAP note events while keeping track of which \
+                 notes are playing. Every 'CLAP_EVENT_NOTE_END' event and passed through note-off \
+                 event the plugin outputs must refer to a playing note using the same port, \
+                 channel, key, and note ID. If the plugin outputs note end events, then it \
+                 should do so for every note. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessHostRestarts => String::from(
+                "Processes random audio through the plugin while the host periodically \
+                 deactivates and reactivates the plugin with a different sample rate and maximum \
+                 buffer size, and tests whether the output does not contain any non-finite or \
+                 subnormal values. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessBufferSizeRange => String::from(
+                "Activates the plugin with several combinations of minimum and maximum block \
+                 sizes, ranging from 32 to 16384 samples, and processes a single block at the \
+                 maximum size after every activation. The plugin may not write past the end of \
+                 the block, or output events for samples outside of it.",
+            ),
+            PluginTestCase::ProcessConfigSweep => String::from(
+                "Processes random audio through the plugin at sample rates from 22050 Hz to \
+                 192000 Hz, combined with maximum buffer sizes from 1 to 4096 samples. Every \
+                 other block is smaller than the maximum buffer size the plugin was activated \
+                 with. The output is tested for consistency. Uses out-of-place audio \
+                 processing.",
+            ),
+            PluginTestCase::ProcessTinyBlocks => String::from(
+                "Processes random audio and notes through the plugin in blocks of only a couple \
+                 samples, and then in a sequence of blocks that includes blocks with zero \
+                 samples. The plugin may not output events past the end of a block. Problems \
+                 with zero-sample blocks result in a warning, since those are outside of the \
+                 range the plugin was activated with. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessTransportEvents => String::from(
+                "Processes random audio through the plugin while also sending transport events \
+                 through the input event queue in the middle of each block, like some hosts do, \
+                 and tests the output for consistency. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessTransportVariations => format!(
+                "Processes random audio through the plugin for {} blocks while changing the \
+                 transport before every block. This includes tempo changes, loop regions, \
+                 stopping and starting playback, missing beats and seconds timelines, and bar \
+                 offsets. The output is tested for consistency. Uses out-of-place audio \
+                 processing.",
+                processing::TRANSPORT_VARIATION_NUM_BLOCKS
+            ),
+            PluginTestCase::ProcessThreadPool => String::from(
+                "Processes random audio through a plugin that implements the 'thread-pool' \
+                 extension while running the tasks it requests on the host's thread pool. \
+                 'clap_host_thread_pool::request_exec()' may only be called from the audio \
+                 thread, and not from within 'clap_plugin_thread_pool::exec()'.",
+            ),
+            PluginTestCase::ProcessStartBeforeActivate => String::from(
+                "A negative test that only runs with the '--negative-tests' option. Calls \
+                 'clap_plugin::start_processing()' before the plugin has been activated. The \
+                 plugin should reject this call without crashing, and it should still process \
+                 audio normally afterwards. Accepting the call results in a warning.",
+            ),
+            PluginTestCase::NoteDialect(NoteDialectVariant::MidiOnly) => String::from(
+                "Pretends to be a host that only supports MIDI note events, processes audio and \
+                 random MIDI events with the plugin, and asserts that the plugin does not output \
+                 any CLAP note events.",
+            ),
+            PluginTestCase::NoteDialect(NoteDialectVariant::ClapOnly) => String::from(
+                "Pretends to be a host that only supports CLAP note events, processes audio and \
+                 random CLAP note events with the plugin, and asserts that the plugin does not \
+                 output any MIDI events.",
+            ),
+            PluginTestCase::ProcessNoteDialectPreference(
+                NoteDialectPreferenceVariant::Preferred,
+            ) => String::from(
+                "Sends audio and random note and MIDI events to the plugin, only using each \
+                 input note port's preferred note dialect. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessNoteDialectPreference(
+                NoteDialectPreferenceVariant::NonPreferred,
+            ) => String::from(
+                "Sends audio and random note and MIDI events to the plugin, only using the note \
+                 dialects the input note ports support in addition to their preferred dialect. \
+                 This verifies that the plugin handles events in all of the dialects it claims to \
+                 support. Uses out-of-place audio processing.",
+            ),
+            PluginTestCase::ProcessNoteOutputOnly => format!(
+                "Processes {} blocks of random audio with a plugin that has output note ports but \
+                 no input note ports, like an arpeggiator or a MIDI generator, and checks that \
+                 the events it outputs are valid and target one of its output note ports.",
+                processing::NOTE_OUTPUT_ONLY_NUM_BLOCKS
+            ),
+            PluginTestCase::ProcessNoteEffectChain => format!(
+                "Only runs for plugins with the 'note-effect' feature. Sends a scripted MIDI \
+                 performance of chords and a melody to the plugin, followed by 'All Notes Off' \
+                 messages on every channel, and processes a total of {} blocks. None of the notes \
+                 the plugin outputs may still be playing at the end, and the plugin's first \
+                 output note should not be delayed by more than a tenth of a second.",
+                note_effect::CHAIN_NUM_BLOCKS
+            ),
+            PluginTestCase::ProcessNotePanic(NotePanicVariant::AllNotesOff) => format!(
+                "Starts {} notes without releasing them, and then sends MIDI 'All Notes Off' \
+                 messages on every channel. All of the plugin's voices should terminate \
+                 afterwards. Notes the plugin outputs that are still playing result in a \
+                 failure. Missing note end events and audio output that does not decay to \
+                 silence result in a warning.",
+                processing::NOTE_PANIC_NUM_NOTES
+            ),
+            PluginTestCase::ProcessNotePanic(NotePanicVariant::Reset) => format!(
+                "Starts {} notes without releasing them, and then calls 'clap_plugin::reset()'. \
+                 All of the plugin's voices should terminate afterwards. Notes the plugin \
+                 outputs that are still playing result in a failure. Missing note end events and \
+                 audio output that does not decay to silence result in a warning.",
+                processing::NOTE_PANIC_NUM_NOTES
+            ),
+            PluginTestCase::EventRegistry(EventRegistryVariant::Accepted) => String::from(
+                "Processes audio with a host that registers every event space the plugin queries \
+                 through the 'event-registry' extension, and asserts that the plugin only outputs \
+                 events in the core event space and in the spaces it has registered.",
+            ),
+            PluginTestCase::EventRegistry(EventRegistryVariant::Declined) => String::from(
+                "Processes audio with a host that declines to register any event spaces through \
+                 the 'event-registry' extension, and asserts that the plugin then only outputs \
+                 events in the core event space.",
+            ),
+            PluginTestCase::ParamSanity => String::from(
+                "Checks the plugin's parameters for degenerate definitions: parameters with the \
+                 same minimum and maximum value, stepped parameters with a single step, infinite \
+                 ranges or default values, duplicate names within the same module, and stable \
+                 IDs that refer to different parameters after the plugin reloads its own state. \
+                 These result in warnings.",
+            ),
             PluginTestCase::ParamConversions => String::from(
                 "Asserts that value to string and string to value conversions are supported for \
                  ether all or none of the plugin's parameters, and that conversions between \
@@ -96,38 +653,314 @@ impl<'a> TestCase<'a> for PluginTestCase {
                  a mismatching namespace ID. Asserts that the plugin's parameter values don't \
                  change.",
             ),
+            PluginTestCase::ParamEchoConsistency => format!(
+                "Sets random parameter values through the process function for {} processing \
+                 cycles. Asserts that the plugin does not output 'CLAP_EVENT_PARAM_VALUE' events \
+                 with different values for those parameters during the same cycle, as that would \
+                 cause automation feedback loops in hosts.",
+                params::ECHO_NUM_CYCLES
+            ),
+            PluginTestCase::ParamAutomationBlockEdges => format!(
+                "Ramps all of the plugin's parameters over {} blocks of {} samples, with \
+                 parameter changes on the first and last samples of those blocks. Asserts that \
+                 the plugin's parameter values match the last parameter change after the final \
+                 block, once with that change on the final block's last sample and once with it \
+                 on its first sample.",
+                params::BLOCK_EDGES_NUM_BLOCKS,
+                params::BLOCK_EDGES_BLOCK_SIZE
+            ),
+            PluginTestCase::ParamPolyphonicAutomation => format!(
+                "Plays a chord and sends per-note parameter value and modulation events for all \
+                 parameters that support polyphonic automation or modulation over {} blocks. \
+                 Asserts that the plugin produces finite output, and that it ends every note \
+                 after the notes are released if it outputs 'CLAP_EVENT_NOTE_END' events.",
+                params::POLYPHONIC_NOTE_OFF_BLOCK
+            ),
+            PluginTestCase::ParamRescanStateLoad => format!(
+                "Alternates between loading the plugin's default state and a state with random \
+                 parameter values {} times, with every other load happening while the plugin is \
+                 activated. Asserts that all parameter changes the plugin reports through \
+                 'clap_host_params::rescan()' are covered by the rescan's flags, that \
+                 'CLAP_PARAM_RESCAN_ALL' is only used while the plugin is deactivated, and that \
+                 the parameters' stable IDs don't change without a full rescan. The changes found \
+                 after every rescan are listed in the test's details.",
+                params::RESCAN_NUM_STATE_LOADS
+            ),
+            PluginTestCase::ParamFlushDeactivated => format!(
+                "Sets random parameter values by calling 'clap_plugin_params::flush()' on the \
+                 main thread while the plugin is deactivated, and asserts that the parameter \
+                 values change. The plugin is then activated and processes {} blocks without \
+                 parameter events, after which the parameter values should not have changed.",
+                params::FLUSH_DEACTIVATED_NUM_BLOCKS
+            ),
+            PluginTestCase::ParamOutputEvents => format!(
+                "Sends random parameter values to the plugin through \
+                 'clap_plugin_params::flush()' on the main thread, and during {} blocks of audio \
+                 processing. The parameter events the plugin outputs must refer to existing \
+                 parameters, parameter values must be within the parameter's range, and every \
+                 gesture the plugin begins must also be ended.",
+                params::OUTPUT_EVENTS_NUM_BLOCKS
+            ),
+            PluginTestCase::ParamInfoAudioThread => String::from(
+                "A negative test that only runs with the '--negative-tests' option. Calls the main \
+                 thread functions 'clap_plugin_params::count()' and \
+                 'clap_plugin_params::get_info()' from the audio thread while the plugin is \
+                 activated. This violates CLAP's threading rules, so the plugin may reject these \
+                 calls, but it should not crash.",
+            ),
             PluginTestCase::StateInvalid => String::from(
                 "The plugin should return false when 'clap_plugin_state::load()' is called with \
                  an empty state.",
             ),
-            PluginTestCase::StateReproducibilityBasic => String::from(
-                "Randomizes a plugin's parameters, saves its state, recreates the plugin \
-                 instance, reloads the state, and then checks whether the parameter values are \
-                 the same and whether saving the state once more results in the same state file \
-                 as before. The parameter values are updated using the process function.",
-            ),
-            PluginTestCase::StateReproducibilityNullCookies => format!(
-                "The exact same test as {}, but with all cookies in the parameter events set to \
-                 null pointers. The plugin should handle this in the same way as the other test \
-                 case.",
-                PluginTestCase::StateReproducibilityBasic
-            ),
-            PluginTestCase::StateReproducibilityFlush => String::from(
-                "Randomizes a plugin's parameters, saves its state, recreates the plugin \
-                 instance, sets the same parameters as before, saves the state again, and then \
-                 asserts that the two states are identical. The parameter values are set updated \
-                 using the process function to create the first state, and using the flush \
-                 function to create the second state.",
-            ),
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Basic) => {
+                String::from(
+                    "Randomizes a plugin's parameters, saves its state, recreates the plugin \
+                     instance, reloads the state, and then checks whether the parameter values \
+                     are the same and whether saving the state once more results in the same \
+                     state file as before. The parameter values are updated using the process \
+                     function.",
+                )
+            }
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::NullCookies) => {
+                format!(
+                    "The exact same test as {}, but with all cookies in the parameter events set \
+                     to null pointers. The plugin should handle this in the same way as the other \
+                     test case.",
+                    PluginTestCase::StateReproducibility(StateReproducibilityVariant::Basic)
+                )
+            }
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Overwrite) => {
+                format!(
+                    "The same test as {}, but the recreated plugin instance's parameters are set \
+                     to different random values before the state is loaded. Loading a state \
+                     should fully replace the plugin's current state, rather than merging the \
+                     two.",
+                    PluginTestCase::StateReproducibility(StateReproducibilityVariant::Basic)
+                )
+            }
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::WhileProcessing) => {
+                format!(
+                    "Randomizes a plugin's parameters, saves its state, and randomizes the \
+                     parameters again. The saved state is then loaded on the main thread after \
+                     the audio thread has processed {} blocks, and the audio thread processes {} \
+                     more blocks after that. Asserts that the parameter values and a newly saved \
+                     state match the loaded state.",
+                    state::WHILE_PROCESSING_BLOCKS_BEFORE_LOAD,
+                    state::WHILE_PROCESSING_BLOCKS_AFTER_LOAD
+                )
+            }
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Flush) => {
+                String::from(
+                    "Randomizes a plugin's parameters, saves its state, recreates the plugin \
+                     instance, sets the same parameters as before, saves the state again, and \
+                     then asserts that the two states are identical. The parameter values are set \
+                     updated using the process function to create the first state, and using the \
+                     flush function to create the second state.",
+                )
+            }
             PluginTestCase::StateBufferedStreams => format!(
                 "Performs the same state and parameter reproducibility check as in '{}', but this \
                  time the plugin is only allowed to read a small prime number of bytes at a time \
                  when reloading and resaving the state.",
-                PluginTestCase::StateReproducibilityBasic
+                PluginTestCase::StateReproducibility(StateReproducibilityVariant::Basic)
+            ),
+            PluginTestCase::TailLength => String::from(
+                "Sends an impulse followed by silence to the plugin, and queries the plugin's tail \
+                 length on the audio thread after every block. The tail length should not change \
+                 after every block, and it should only be infinite if the plugin's output doesn't \
+                 decay to silence.",
+            ),
+            PluginTestCase::RenderModes => String::from(
+                "Switches the deactivated plugin to the realtime and offline rendering modes, and \
+                 processes random audio in every mode the plugin accepts. The plugin may only \
+                 reject the offline mode if it reports a hard realtime requirement.",
+            ),
+            PluginTestCase::TimerSupport => String::from(
+                "Calls 'clap_plugin_timer_support::on_timer()' on the main thread for the timers \
+                 the plugin registered during initialization. The plugin needs to implement the \
+                 timer support extension if it registers timers, and it needs to unregister all \
+                 of its timers before it is destroyed.",
+            ),
+            PluginTestCase::RemoteControlsPages => String::from(
+                "Queries the plugin's remote controls pages. Every page needs to have a unique \
+                 page ID and valid UTF-8 section and page names, and every mapped control needs \
+                 to refer to an existing parameter. Pages without any mapped controls or without \
+                 a section name, sections whose pages are not listed consecutively, and sections \
+                 containing both preset specific and regular pages result in a warning.",
+            ),
+            PluginTestCase::TrackInfoChanged => String::from(
+                "Calls 'clap_plugin_track_info::changed()' on the main thread. The host provides \
+                 information for a stereo track, and the plugin may only query it from the main \
+                 thread.",
             ),
+            PluginTestCase::ContextMenuPopulate => String::from(
+                "Has the plugin populate its global context menu and the context menus for all of \
+                 its parameters. Menu items may not have null labels, submenus need to be \
+                 balanced, and action IDs need to be unique within a menu.",
+            ),
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            PluginTestCase::DescriptorConsistency
+            | PluginTestCase::FeaturesCategories
+            | PluginTestCase::FeaturesDuplicates => "Descriptor",
+            PluginTestCase::InstanceCreationTime
+            | PluginTestCase::InstanceDestroyTime
+            | PluginTestCase::ExtensionCoverage
+            | PluginTestCase::HostNameIndependence
+            | PluginTestCase::HostMissingFunctions
+            | PluginTestCase::WorkingDirectoryIndependence => "Instance",
+            PluginTestCase::AudioPortFlags
+            | PluginTestCase::AudioPortsRescanActive
+            | PluginTestCase::AudioPortsConfigSelectActive => "Audio ports",
+            PluginTestCase::ProcessAudio(_)
+            | PluginTestCase::ProcessNote(_)
+            | PluginTestCase::ProcessNoteInconsistent
+            | PluginTestCase::ProcessNoteOversizedEvents
+            | PluginTestCase::ProcessNoteSysex
+            | PluginTestCase::ProcessNoteEnd
+            | PluginTestCase::ProcessHostRestarts
+            | PluginTestCase::ProcessBufferSizeRange
+            | PluginTestCase::ProcessConfigSweep
+            | PluginTestCase::ProcessTinyBlocks
+            | PluginTestCase::ProcessTransportEvents
+            | PluginTestCase::ProcessTransportVariations
+            | PluginTestCase::ProcessThreadPool
+            | PluginTestCase::ProcessStartBeforeActivate
+            | PluginTestCase::NoteDialect(_)
+            | PluginTestCase::ProcessNoteDialectPreference(_)
+            | PluginTestCase::ProcessNoteOutputOnly
+            | PluginTestCase::ProcessNoteEffectChain
+            | PluginTestCase::ProcessNotePanic(_)
+            | PluginTestCase::EventRegistry(_) => "Processing",
+            PluginTestCase::ParamSanity
+            | PluginTestCase::ParamConversions
+            | PluginTestCase::ParamFuzzBasic
+            | PluginTestCase::ParamSetWrongNamespace
+            | PluginTestCase::ParamEchoConsistency
+            | PluginTestCase::ParamAutomationBlockEdges
+            | PluginTestCase::ParamPolyphonicAutomation
+            | PluginTestCase::ParamRescanStateLoad
+            | PluginTestCase::ParamFlushDeactivated
+            | PluginTestCase::ParamOutputEvents
+            | PluginTestCase::ParamInfoAudioThread => "Parameters",
+            PluginTestCase::StateInvalid
+            | PluginTestCase::StateReproducibility(_)
+            | PluginTestCase::StateBufferedStreams => "State",
+            PluginTestCase::TailLength => "Tail",
+            PluginTestCase::RenderModes => "Render",
+            PluginTestCase::TimerSupport => "Timers",
+            PluginTestCase::RemoteControlsPages => "Remote controls",
+            PluginTestCase::TrackInfoChanged => "Track info",
+            PluginTestCase::ContextMenuPopulate => "Context menu",
         }
     }
 
+    fn checks(&self) -> Vec<Check> {
+        let mut checks = match self {
+            PluginTestCase::DescriptorConsistency => vec![Check::DescriptorValidity],
+            PluginTestCase::FeaturesCategories | PluginTestCase::FeaturesDuplicates => {
+                vec![Check::Features]
+            }
+            PluginTestCase::InstanceCreationTime | PluginTestCase::InstanceDestroyTime => {
+                vec![Check::InstanceTiming]
+            }
+            PluginTestCase::ExtensionCoverage => vec![Check::ExtensionQueries],
+            PluginTestCase::HostNameIndependence
+            | PluginTestCase::HostMissingFunctions
+            | PluginTestCase::WorkingDirectoryIndependence => {
+                vec![Check::HostIndependence, Check::Processing]
+            }
+            PluginTestCase::AudioPortFlags => vec![Check::AudioPorts],
+            PluginTestCase::AudioPortsRescanActive => {
+                vec![Check::AudioPorts, Check::HostCallbacks]
+            }
+            PluginTestCase::AudioPortsConfigSelectActive => {
+                vec![Check::AudioPorts, Check::WrongStateCalls]
+            }
+            PluginTestCase::ProcessAudio(_) => vec![Check::AudioPorts, Check::Processing],
+            PluginTestCase::ProcessNote(_)
+            | PluginTestCase::ProcessNoteInconsistent
+            | PluginTestCase::ProcessNoteOversizedEvents
+            | PluginTestCase::ProcessNoteSysex
+            | PluginTestCase::ProcessNoteEnd
+            | PluginTestCase::ProcessNoteDialectPreference(_)
+            | PluginTestCase::ProcessNoteOutputOnly
+            | PluginTestCase::ProcessNoteEffectChain
+            | PluginTestCase::ProcessNotePanic(_) => {
+                vec![Check::NotePorts, Check::Processing, Check::NoteEvents]
+            }
+            PluginTestCase::ProcessHostRestarts => vec![Check::Processing, Check::Restarts],
+            PluginTestCase::ProcessBufferSizeRange
+            | PluginTestCase::ProcessConfigSweep
+            | PluginTestCase::ProcessTinyBlocks => vec![Check::Processing, Check::BlockSizes],
+            PluginTestCase::ProcessTransportEvents | PluginTestCase::ProcessTransportVariations => {
+                vec![Check::Processing, Check::Transport]
+            }
+            PluginTestCase::ProcessThreadPool => vec![Check::Processing, Check::ThreadPool],
+            PluginTestCase::ProcessStartBeforeActivate => {
+                vec![Check::Processing, Check::WrongStateCalls]
+            }
+            PluginTestCase::NoteDialect(_) | PluginTestCase::EventRegistry(_) => {
+                vec![Check::Processing, Check::EventSpaces]
+            }
+            PluginTestCase::ParamSanity => vec![Check::ParamInfo, Check::ParamSanity],
+            PluginTestCase::ParamConversions => vec![Check::ParamInfo, Check::ParamConversions],
+            PluginTestCase::ParamFuzzBasic => vec![Check::ParamInfo, Check::Processing],
+            PluginTestCase::ParamSetWrongNamespace => {
+                vec![Check::ParamInfo, Check::Processing, Check::ParamEventSpaces]
+            }
+            PluginTestCase::ParamEchoConsistency => {
+                vec![Check::ParamInfo, Check::Processing, Check::ParamEcho]
+            }
+            PluginTestCase::ParamAutomationBlockEdges
+            | PluginTestCase::ParamPolyphonicAutomation => {
+                vec![Check::ParamInfo, Check::Processing, Check::ParamAutomation]
+            }
+            PluginTestCase::ParamRescanStateLoad => {
+                vec![
+                    Check::ParamInfo,
+                    Check::ParamRescan,
+                    Check::StateReproducibility,
+                ]
+            }
+            PluginTestCase::ParamFlushDeactivated => vec![Check::ParamInfo, Check::ParamFlush],
+            PluginTestCase::ParamOutputEvents => {
+                vec![Check::ParamInfo, Check::ParamFlush, Check::Processing]
+            }
+            PluginTestCase::ParamInfoAudioThread => vec![Check::ParamInfo, Check::WrongThreadCalls],
+            PluginTestCase::StateInvalid => vec![Check::StateInvalid],
+            PluginTestCase::StateReproducibility(_) => {
+                vec![Check::ParamInfo, Check::StateReproducibility]
+            }
+            PluginTestCase::StateBufferedStreams => {
+                vec![Check::StateReproducibility, Check::StateStreams]
+            }
+            PluginTestCase::TailLength => vec![Check::Processing, Check::TailLength],
+            PluginTestCase::RenderModes => vec![Check::RenderModes],
+            PluginTestCase::TimerSupport => vec![Check::Timers],
+            PluginTestCase::RemoteControlsPages => vec![Check::RemoteControls],
+            PluginTestCase::TrackInfoChanged => vec![Check::TrackInfo],
+            PluginTestCase::ContextMenuPopulate => vec![Check::ContextMenu],
+        };
+
+        // Every plugin test checks the plugin's host callbacks, and every test that processes audio
+        // checks the plugin's output
+        if checks.contains(&Check::Processing) {
+            checks.extend([
+                Check::OutputAudio,
+                Check::OutputEvents,
+                Check::ParamOutputEvents,
+            ]);
+        }
+        checks.push(Check::HostCallbacks);
+        checks.sort();
+
+        checks
+    }
+
     fn set_out_of_process_args(&self, command: &mut Command, (library, plugin_id): Self::TestArgs) {
         let test_name = self.to_string();
 
@@ -154,35 +987,186 @@ impl<'a> TestCase<'a> for PluginTestCase {
             PluginTestCase::FeaturesDuplicates => {
                 descriptor::test_features_duplicates(library, plugin_id)
             }
-            PluginTestCase::ProcessAudioOutOfPlaceBasic => {
-                processing::test_process_audio_out_of_place_basic(library, plugin_id)
+            PluginTestCase::InstanceCreationTime => {
+                instance::test_instance_creation_time(library, plugin_id)
+            }
+            PluginTestCase::InstanceDestroyTime => {
+                instance::test_instance_destroy_time(library, plugin_id)
             }
-            PluginTestCase::ProcessNoteOutOfPlaceBasic => {
-                processing::test_process_note_out_of_place_basic(library, plugin_id)
+            PluginTestCase::ExtensionCoverage => {
+                instance::test_extension_coverage(library, plugin_id)
+            }
+            PluginTestCase::HostNameIndependence => {
+                instance::test_host_name_independence(library, plugin_id)
+            }
+            PluginTestCase::HostMissingFunctions => {
+                instance::test_host_missing_functions(library, plugin_id)
+            }
+            PluginTestCase::WorkingDirectoryIndependence => {
+                working_directory::test_working_directory_independence(library, plugin_id)
+            }
+            PluginTestCase::AudioPortFlags => {
+                audio_ports::test_audio_port_flags(library, plugin_id)
+            }
+            PluginTestCase::AudioPortsRescanActive => {
+                audio_ports::test_audio_ports_rescan_active(library, plugin_id)
+            }
+            PluginTestCase::AudioPortsConfigSelectActive => {
+                audio_ports::test_audio_ports_config_select_active(library, plugin_id)
+            }
+            PluginTestCase::ProcessAudio(variant) => {
+                processing::test_process_audio(library, plugin_id, *variant)
+            }
+            PluginTestCase::ProcessNote(variant) => {
+                processing::test_process_note(library, plugin_id, *variant)
             }
             PluginTestCase::ProcessNoteInconsistent => {
                 processing::test_process_note_inconsistent(library, plugin_id)
             }
+            PluginTestCase::ProcessNoteOversizedEvents => {
+                processing::test_process_note_oversized_events(library, plugin_id)
+            }
+            PluginTestCase::ProcessNoteSysex => {
+                processing::test_process_note_sysex(library, plugin_id)
+            }
+            PluginTestCase::ProcessNoteEnd => processing::test_process_note_end(library, plugin_id),
+            PluginTestCase::ProcessHostRestarts => {
+                processing::test_process_host_restarts(library, plugin_id)
+            }
+            PluginTestCase::ProcessBufferSizeRange => {
+                processing::test_process_buffer_size_range(library, plugin_id)
+            }
+            PluginTestCase::ProcessConfigSweep => {
+                processing::test_process_config_sweep(library, plugin_id)
+            }
+            PluginTestCase::ProcessTinyBlocks => {
+                processing::test_process_tiny_blocks(library, plugin_id)
+            }
+            PluginTestCase::ProcessTransportEvents => {
+                processing::test_process_transport_events(library, plugin_id)
+            }
+            PluginTestCase::ProcessTransportVariations => {
+                processing::test_process_transport_variations(library, plugin_id)
+            }
+            PluginTestCase::ProcessThreadPool => {
+                processing::test_process_thread_pool(library, plugin_id)
+            }
+            PluginTestCase::ProcessStartBeforeActivate => {
+                processing::test_process_start_before_activate(library, plugin_id)
+            }
+            PluginTestCase::NoteDialect(variant) => {
+                processing::test_note_dialect(library, plugin_id, *variant)
+            }
+            PluginTestCase::ProcessNoteDialectPreference(variant) => {
+                processing::test_process_note_dialect_preference(library, plugin_id, *variant)
+            }
+            PluginTestCase::ProcessNoteOutputOnly => {
+                processing::test_process_note_output_only(library, plugin_id)
+            }
+            PluginTestCase::ProcessNoteEffectChain => {
+                note_effect::test_process_note_effect_chain(library, plugin_id)
+            }
+            PluginTestCase::ProcessNotePanic(variant) => {
+                processing::test_process_note_panic(library, plugin_id, *variant)
+            }
+            PluginTestCase::EventRegistry(variant) => {
+                processing::test_event_registry(library, plugin_id, *variant)
+            }
+            PluginTestCase::ParamSanity => params::test_param_sanity(library, plugin_id),
             PluginTestCase::ParamConversions => params::test_param_conversions(library, plugin_id),
             PluginTestCase::ParamFuzzBasic => params::test_param_fuzz_basic(library, plugin_id),
             PluginTestCase::ParamSetWrongNamespace => {
                 params::test_param_set_wrong_namespace(library, plugin_id)
             }
-            PluginTestCase::StateInvalid => state::test_state_invalid(library, plugin_id),
-            PluginTestCase::StateReproducibilityBasic => {
-                state::test_state_reproducibility_null_cookies(library, plugin_id, false)
+            PluginTestCase::ParamEchoConsistency => {
+                params::test_param_echo_consistency(library, plugin_id)
+            }
+            PluginTestCase::ParamAutomationBlockEdges => {
+                params::test_param_automation_block_edges(library, plugin_id)
             }
-            PluginTestCase::StateReproducibilityNullCookies => {
-                state::test_state_reproducibility_null_cookies(library, plugin_id, true)
+            PluginTestCase::ParamPolyphonicAutomation => {
+                params::test_param_polyphonic_automation(library, plugin_id)
             }
-            PluginTestCase::StateReproducibilityFlush => {
+            PluginTestCase::ParamRescanStateLoad => {
+                params::test_param_rescan_state_load(library, plugin_id)
+            }
+            PluginTestCase::ParamFlushDeactivated => {
+                params::test_param_flush_deactivated(library, plugin_id)
+            }
+            PluginTestCase::ParamOutputEvents => {
+                params::test_param_output_events(library, plugin_id)
+            }
+            PluginTestCase::ParamInfoAudioThread => {
+                params::test_param_info_audio_thread(library, plugin_id)
+            }
+            PluginTestCase::StateInvalid => state::test_state_invalid(library, plugin_id),
+            PluginTestCase::StateReproducibility(
+                variant @ (StateReproducibilityVariant::Basic
+                | StateReproducibilityVariant::NullCookies
+                | StateReproducibilityVariant::Overwrite),
+            ) => state::test_state_reproducibility(library, plugin_id, *variant),
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Flush) => {
                 state::test_state_reproducibility_flush(library, plugin_id)
             }
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::WhileProcessing) => {
+                state::test_state_reproducibility_while_processing(library, plugin_id)
+            }
             PluginTestCase::StateBufferedStreams => {
                 state::test_state_buffered_streams(library, plugin_id)
             }
+            PluginTestCase::TailLength => tail::test_tail_length(library, plugin_id),
+            PluginTestCase::RenderModes => render::test_render_modes(library, plugin_id),
+            PluginTestCase::TimerSupport => timer_support::test_timer_support(library, plugin_id),
+            PluginTestCase::RemoteControlsPages => {
+                remote_controls::test_remote_controls_pages(library, plugin_id)
+            }
+            PluginTestCase::TrackInfoChanged => {
+                track_info::test_track_info_changed(library, plugin_id)
+            }
+            PluginTestCase::ContextMenuPopulate => {
+                context_menu::test_context_menu_populate(library, plugin_id)
+            }
         };
 
         self.create_result(status)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::library::reference::ReferencePlugin;
+    use crate::tests::{SkipReason, TestStatus};
+
+    #[test]
+    fn param_tests_skip_without_params() {
+        let library = PluginLibrary::reference().unwrap();
+        let plugin_id = ReferencePlugin::NoParams.id();
+
+        for test in [
+            PluginTestCase::ParamConversions,
+            PluginTestCase::ParamFuzzBasic,
+            PluginTestCase::ParamSetWrongNamespace,
+            PluginTestCase::ParamEchoConsistency,
+            PluginTestCase::ParamAutomationBlockEdges,
+            PluginTestCase::ParamPolyphonicAutomation,
+            PluginTestCase::ParamFlushDeactivated,
+            PluginTestCase::ParamOutputEvents,
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Overwrite),
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Flush),
+        ] {
+            let result = test.run_in_process((&library, plugin_id));
+            assert!(
+                matches!(
+                    result.status,
+                    TestStatus::Skipped {
+                        reason: SkipReason::NoParams,
+                        ..
+                    }
+                ),
+                "'{test}' resulted in {:?}",
+                result.status
+            );
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! Tests surrounding the plugin's audio port configuration.
+
+use anyhow::{Context, Result};
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
+
+use super::processing::ProcessingTest;
+use crate::ipc;
+use crate::plugin::ext::audio_ports::{AudioPort, AudioPortConfig, AudioPorts};
+use crate::plugin::ext::audio_ports_config::AudioPortsConfig;
+use crate::plugin::ext::state::State;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::instance::process::ProcessConfig;
+use crate::plugin::library::PluginLibrary;
+use crate::tests::rng::new_prng;
+use crate::tests::{SkipReason, TestStatus};
+use crate::util;
+
+/// The number of processing cycles in the audio ports rescan test.
+pub const RESCAN_ACTIVE_NUM_BLOCKS: usize = 10;
+
+/// The test for `PluginTestCase::AudioPortFlags`. Querying the audio port configuration already
+/// checks the port flags for consistency. This additionally checks whether a second instance of the
+/// plugin reports the same stable IDs and flags.
+pub fn test_audio_port_flags(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let config = match query_audio_port_config(library, plugin_id, &host)? {
+        Some(config) => config,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let second_config = query_audio_port_config(library, plugin_id, &host)?.context(
+        "A second instance of the plugin does not implement the 'audio-ports' extension",
+    )?;
+
+    check_ports_stable("input", &config.inputs, &second_config.inputs)?;
+    check_ports_stable("output", &config.outputs, &second_config.outputs)?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(TestStatus::Success { details: None })
+}
+
+/// The test for `PluginTestCase::AudioPortsRescanActive`. The host records every call to
+/// `clap_host_audio_ports::rescan()` along with the plugin's status at that time, and it already
+/// flags rescans that require the plugin to be deactivated as callback errors. This test gives the
+/// plugin some opportunities to make those calls while it is activated or processing.
+pub fn test_audio_ports_rescan_active(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?.run(
+        RESCAN_ACTIVE_NUM_BLOCKS,
+        ProcessConfig::default(),
+        |process_data| {
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        },
+    )?;
+
+    // Plugins that derive their port layout from their state may try to change it when a state is
+    // loaded, which they need to defer until the plugin is deactivated
+    if let Some(state) = plugin.get_extension::<State>() {
+        let state_bytes = state
+            .save()
+            .context("Error while saving the plugin's state")?;
+
+        let process_config = ProcessConfig::default();
+        plugin.activate(process_config.sample_rate, 1, 512)?;
+        let load_result = state
+            .load(&state_bytes)
+            .context("Error while reloading the plugin's state while the plugin is activated");
+        host.handle_callbacks_once();
+        plugin.deactivate();
+        load_result?;
+    }
+    host.handle_callbacks_once();
+
+    // Rescans with flags that require the plugin to be deactivated are reported as callback errors
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let num_rescans = plugin.state.num_audio_ports_rescans.load(Ordering::SeqCst);
+    Ok(TestStatus::Success {
+        details: match num_rescans {
+            0 => None,
+            num_rescans => Some(format!(
+                "The plugin called 'clap_host_audio_ports::rescan()' {num_rescans} {}, all with \
+                 flags that were allowed at the time.",
+                if num_rescans == 1 { "time" } else { "times" }
+            )),
+        },
+    })
+}
+
+/// The test for `PluginTestCase::AudioPortsConfigSelectActive`. This is a negative test that calls
+/// `clap_plugin_audio_ports_config::select()` while the plugin is activated, even though audio
+/// port configurations may only be selected while the plugin is deactivated. The plugin should
+/// reject this call without crashing, and its audio ports should stay the same. Accepting the call
+/// results in a warning.
+pub fn test_audio_ports_config_select_active(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    if !util::negative_tests() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::RequiresFlag,
+            details: Some(String::from(
+                "This is a negative test, which only runs when the '--negative-tests' option is \
+                 passed to the validator.",
+            )),
+        });
+    }
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPortsConfig>() {
+        Some(audio_ports_config) => audio_ports_config,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    AudioPortsConfig::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let config_ids = audio_ports_config
+        .config_ids()
+        .context("Error while querying the plugin's audio port configurations")?;
+    // The last configuration is the one that's least likely to already be selected
+    let config_id = match config_ids.last() {
+        Some(config_id) => *config_id,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::Other,
+                details: Some(String::from(
+                    "The plugin does not have any audio port configurations.",
+                )),
+            })
+        }
+    };
+    let audio_ports = plugin.get_extension::<AudioPorts>();
+    let ports_before = match &audio_ports {
+        Some(audio_ports) => Some(
+            audio_ports
+                .config()
+                .context("Error while querying 'audio-ports' IO configuration")?,
+        ),
+        None => None,
+    };
+    host.handle_callbacks_once();
+
+    plugin.activate(ProcessConfig::default().sample_rate, 1, 512)?;
+    let accepted = {
+        let _crash_context = ipc::crash_context(format!(
+            "the validator was calling 'clap_plugin_audio_ports_config::select({config_id})' \
+             while the plugin was activated"
+        ));
+
+        audio_ports_config.select_while_activated(config_id)
+    };
+    // Querying the audio ports is allowed while the plugin is activated
+    let ports_after = match &audio_ports {
+        Some(audio_ports) => Some(
+            audio_ports
+                .config()
+                .context("Error while querying 'audio-ports' IO configuration")?,
+        ),
+        None => None,
+    };
+    host.handle_callbacks_once();
+    plugin.deactivate();
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    if accepted {
+        Ok(TestStatus::Warning {
+            details: Some(format!(
+                "'clap_plugin_audio_ports_config::select({config_id})' returned true while the \
+                 plugin was activated. Audio port configurations may only be selected while the \
+                 plugin is deactivated, so the plugin should reject this call."
+            )),
+        })
+    } else if ports_before != ports_after {
+        Ok(TestStatus::Failed {
+            details: Some(format!(
+                "'clap_plugin_audio_ports_config::select({config_id})' returned false while the \
+                 plugin was activated, but the plugin's audio ports changed anyway."
+            )),
+            causes: Vec::new(),
+        })
+    } else {
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin rejected the call to \
+                 'clap_plugin_audio_ports_config::select({config_id})' while it was activated."
+            )),
+        })
+    }
+}
+
+/// Create and initialize a new instance of the plugin and query its audio port configuration.
+/// Returns `None` if the plugin does not implement the `audio-ports` extension.
+fn query_audio_port_config(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    host: &Rc<Host>,
+) -> Result<Option<AudioPortConfig>> {
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => Some(
+            audio_ports
+                .config()
+                .context("Error while querying 'audio-ports' IO configuration")?,
+        ),
+        None => None,
+    };
+    host.handle_callbacks_once();
+
+    Ok(config)
+}
+
+/// Check whether two instances of the same plugin report the same stable IDs and flags for their
+/// input or output ports.
+fn check_ports_stable(
+    direction: &str,
+    ports: &[AudioPort],
+    other_ports: &[AudioPort],
+) -> Result<()> {
+    if ports.len() != other_ports.len() {
+        anyhow::bail!(
+            "The plugin has {} {direction} ports, but a second instance of the plugin has {} \
+             {direction} ports.",
+            ports.len(),
+            other_ports.len()
+        );
+    }
+
+    for (port_idx, (port, other_port)) in ports.iter().zip(other_ports).enumerate() {
+        if port.stable_id != other_port.stable_id {
+            anyhow::bail!(
+                "The {direction} port {port_idx} has stable ID {}, but the same port on a second \
+                 instance of the plugin has stable ID {}.",
+                port.stable_id,
+                other_port.stable_id
+            );
+        }
+
+        if (port.is_main, port.supports_64bit, port.prefers_64bit)
+            != (
+                other_port.is_main,
+                other_port.supports_64bit,
+                other_port.prefers_64bit,
+            )
+        {
+            anyhow::bail!(
+                "The flags for {direction} port {port_idx} differ between two instances of the \
+                 plugin."
+            );
+        }
+    }
+
+    Ok(())
+}
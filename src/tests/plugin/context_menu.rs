@@ -0,0 +1,78 @@
+//! Tests for the `context-menu` draft extension.
+
+use anyhow::{Context, Result};
+
+use crate::plugin::ext::context_menu::{ContextMenu, ContextMenuTarget};
+use crate::plugin::ext::params::Params;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::library::PluginLibrary;
+use crate::tests::{SkipReason, TestStatus};
+
+/// The test for `PluginTestCase::ContextMenuPopulate`. Has the plugin populate its global context
+/// menu and the context menus for all of its parameters using a validator-provided builder. The
+/// builder rejects items with null labels, and [`ContextMenu::populate()`] checks that submenus
+/// are balanced and that action IDs are unique within a menu. The menu actions are not performed,
+/// since those may have arbitrary side effects.
+pub fn test_context_menu_populate(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let context_menu = match plugin.get_extension::<ContextMenu>() {
+        Some(context_menu) => context_menu,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    ContextMenu::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let mut targets = vec![ContextMenuTarget::Global];
+    if let Some(params) = plugin.get_extension::<Params>() {
+        let param_infos = params
+            .info()
+            .context("Failure while fetching the plugin's parameters")?;
+        targets.extend(
+            param_infos
+                .keys()
+                .map(|param_id| ContextMenuTarget::Param(*param_id)),
+        );
+    }
+    host.handle_callbacks_once();
+
+    let mut num_menus = 0;
+    let mut num_items = 0;
+    for target in targets {
+        let items = context_menu
+            .populate(target)
+            .with_context(|| match target {
+                ContextMenuTarget::Global => {
+                    String::from("Error while populating the global context menu")
+                }
+                ContextMenuTarget::Param(param_id) => {
+                    format!("Error while populating the context menu for parameter {param_id}")
+                }
+            })?;
+        if let Some(items) = items {
+            num_menus += 1;
+            num_items += items.len();
+        }
+    }
+    host.handle_callbacks_once();
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "The plugin populated {num_menus} context menu(s) containing {num_items} item(s) in \
+             total."
+        )),
+    })
+}
@@ -2,15 +2,68 @@
 
 use anyhow::{Context, Result};
 use clap_sys::plugin_features::{
-    CLAP_PLUGIN_FEATURE_ANALYZER, CLAP_PLUGIN_FEATURE_AUDIO_EFFECT, CLAP_PLUGIN_FEATURE_INSTRUMENT,
-    CLAP_PLUGIN_FEATURE_NOTE_DETECTOR, CLAP_PLUGIN_FEATURE_NOTE_EFFECT,
+    CLAP_PLUGIN_FEATURE_AMBISONIC, CLAP_PLUGIN_FEATURE_ANALYZER, CLAP_PLUGIN_FEATURE_AUDIO_EFFECT,
+    CLAP_PLUGIN_FEATURE_CHORUS, CLAP_PLUGIN_FEATURE_COMPRESSOR, CLAP_PLUGIN_FEATURE_DEESSER,
+    CLAP_PLUGIN_FEATURE_DELAY, CLAP_PLUGIN_FEATURE_DISTORTION, CLAP_PLUGIN_FEATURE_DRUM,
+    CLAP_PLUGIN_FEATURE_DRUM_MACHINE, CLAP_PLUGIN_FEATURE_EQUALIZER, CLAP_PLUGIN_FEATURE_EXPANDER,
+    CLAP_PLUGIN_FEATURE_FILTER, CLAP_PLUGIN_FEATURE_FLANGER, CLAP_PLUGIN_FEATURE_FREQUENCY_SHIFTER,
+    CLAP_PLUGIN_FEATURE_GATE, CLAP_PLUGIN_FEATURE_GLITCH, CLAP_PLUGIN_FEATURE_GRANULAR,
+    CLAP_PLUGIN_FEATURE_INSTRUMENT, CLAP_PLUGIN_FEATURE_LIMITER, CLAP_PLUGIN_FEATURE_MASTERING,
+    CLAP_PLUGIN_FEATURE_MIXING, CLAP_PLUGIN_FEATURE_MONO, CLAP_PLUGIN_FEATURE_MULTI_EFFECTS,
+    CLAP_PLUGIN_FEATURE_NOTE_DETECTOR, CLAP_PLUGIN_FEATURE_NOTE_EFFECT, CLAP_PLUGIN_FEATURE_PHASER,
+    CLAP_PLUGIN_FEATURE_PHASE_VOCODER, CLAP_PLUGIN_FEATURE_PITCH_CORRECTION,
+    CLAP_PLUGIN_FEATURE_PITCH_SHIFTER, CLAP_PLUGIN_FEATURE_RESTORATION, CLAP_PLUGIN_FEATURE_REVERB,
+    CLAP_PLUGIN_FEATURE_SAMPLER, CLAP_PLUGIN_FEATURE_STEREO, CLAP_PLUGIN_FEATURE_SURROUND,
+    CLAP_PLUGIN_FEATURE_SYNTHESIZER, CLAP_PLUGIN_FEATURE_TRANSIENT_SHAPER,
+    CLAP_PLUGIN_FEATURE_TREMOLO, CLAP_PLUGIN_FEATURE_UTILITY,
 };
 use std::collections::HashSet;
+use std::ffi::CStr;
 
-use crate::plugin::host::Host;
+use crate::plugin::host::{Host, HostConfig};
 use crate::plugin::library::PluginLibrary;
 use crate::tests::TestStatus;
 
+/// The features defined in `plugin-features.h`, other than the main plugin categories. Features
+/// that are not in this list and that are not a main category should be namespaced, e.g.
+/// `vendor:feature`.
+const STANDARD_FEATURES: &[&CStr] = &[
+    CLAP_PLUGIN_FEATURE_SYNTHESIZER,
+    CLAP_PLUGIN_FEATURE_SAMPLER,
+    CLAP_PLUGIN_FEATURE_DRUM,
+    CLAP_PLUGIN_FEATURE_DRUM_MACHINE,
+    CLAP_PLUGIN_FEATURE_FILTER,
+    CLAP_PLUGIN_FEATURE_PHASER,
+    CLAP_PLUGIN_FEATURE_EQUALIZER,
+    CLAP_PLUGIN_FEATURE_DEESSER,
+    CLAP_PLUGIN_FEATURE_PHASE_VOCODER,
+    CLAP_PLUGIN_FEATURE_GRANULAR,
+    CLAP_PLUGIN_FEATURE_FREQUENCY_SHIFTER,
+    CLAP_PLUGIN_FEATURE_PITCH_SHIFTER,
+    CLAP_PLUGIN_FEATURE_DISTORTION,
+    CLAP_PLUGIN_FEATURE_TRANSIENT_SHAPER,
+    CLAP_PLUGIN_FEATURE_COMPRESSOR,
+    CLAP_PLUGIN_FEATURE_EXPANDER,
+    CLAP_PLUGIN_FEATURE_GATE,
+    CLAP_PLUGIN_FEATURE_LIMITER,
+    CLAP_PLUGIN_FEATURE_FLANGER,
+    CLAP_PLUGIN_FEATURE_CHORUS,
+    CLAP_PLUGIN_FEATURE_DELAY,
+    CLAP_PLUGIN_FEATURE_REVERB,
+    CLAP_PLUGIN_FEATURE_TREMOLO,
+    CLAP_PLUGIN_FEATURE_GLITCH,
+    CLAP_PLUGIN_FEATURE_UTILITY,
+    CLAP_PLUGIN_FEATURE_PITCH_CORRECTION,
+    CLAP_PLUGIN_FEATURE_RESTORATION,
+    CLAP_PLUGIN_FEATURE_MULTI_EFFECTS,
+    CLAP_PLUGIN_FEATURE_MIXING,
+    CLAP_PLUGIN_FEATURE_MASTERING,
+    CLAP_PLUGIN_FEATURE_MONO,
+    CLAP_PLUGIN_FEATURE_STEREO,
+    CLAP_PLUGIN_FEATURE_SURROUND,
+    CLAP_PLUGIN_FEATURE_AMBISONIC,
+];
+
 /// Verifies that the descriptor stored in the factory and the descriptor stored on the plugin
 /// object are equivalent.
 pub fn test_consistency(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
@@ -26,7 +79,7 @@ pub fn test_consistency(library: &PluginLibrary, plugin_id: &str) -> Result<Test
         .find(|plugin_meta| plugin_meta.id == plugin_id)
         .expect("Incorrect plugin ID for metadata query, this is a bug in clap-validator");
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host)
         .context("Could not create the plugin instance")?;
@@ -40,12 +93,14 @@ pub fn test_consistency(library: &PluginLibrary, plugin_id: &str) -> Result<Test
                 "The 'clap_plugin_descriptor' stored on '{plugin_id}'s 'clap_plugin' object \
                  contains different values than the one returned by the factory."
             )),
+            causes: Vec::new(),
         })
     }
 }
 
-/// Check whether the plugin's categories are consistent. Currently this just makes sure that the
-/// plugin has one of the four main plugin category features.
+/// Check whether the plugin's categories are consistent. This makes sure that the plugin has one of
+/// the main plugin category features. Features that are not defined by CLAP and that are not
+/// namespaced result in a warning.
 pub fn test_features_categories(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
     let features = plugin_features(library, plugin_id)?;
 
@@ -65,15 +120,43 @@ pub fn test_features_categories(library: &PluginLibrary, plugin_id: &str) -> Res
             || feature == analyzer_feature
     });
 
-    if has_main_category {
-        Ok(TestStatus::Success { details: None })
-    } else {
+    if !has_main_category {
         anyhow::bail!(
             "The plugin needs to have at least one of thw following plugin category features: \
              \"{instrument_feature}\", \"{audio_effect_feature}\", \"{note_effect_feature}\", or \
              \"{analyzer_feature}\"."
         )
     }
+
+    // Custom features should use a namespace so they can't clash with features CLAP adds later
+    let non_namespaced_features: Vec<&str> = features
+        .iter()
+        .map(|feature| feature.as_str())
+        .filter(|feature| {
+            !feature.contains(':')
+                && ![
+                    instrument_feature,
+                    audio_effect_feature,
+                    note_detector_feature,
+                    note_effect_feature,
+                    analyzer_feature,
+                ]
+                .contains(feature)
+                && !STANDARD_FEATURES
+                    .iter()
+                    .any(|standard_feature| standard_feature.to_str() == Ok(*feature))
+        })
+        .collect();
+    if non_namespaced_features.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(format!(
+                "The following features are not defined by CLAP and are not namespaced (e.g. \
+                 'vendor:feature'): {non_namespaced_features:?}."
+            )),
+        })
+    }
 }
 
 /// Confirm that the plugin does not have any duplicate features.
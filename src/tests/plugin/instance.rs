@@ -0,0 +1,391 @@
+//! Tests surrounding the creation and destruction of plugin instances.
+
+use anyhow::{Context, Result};
+use clap_sys::ext::params::clap_param_info_flags;
+use clap_sys::id::clap_id;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use crate::plugin::ext::audio_ports::{AudioPortConfig, AudioPorts};
+use crate::plugin::ext::note_ports::{NotePortConfig, NotePorts};
+use crate::plugin::ext::params::Params;
+use crate::plugin::ext::state::State;
+use crate::plugin::ext::{UNVALIDATED_EXTENSIONS, VALIDATED_EXTENSIONS};
+use crate::plugin::host::{Host, HostConfig, HostInfo};
+use crate::plugin::instance::process::ProcessConfig;
+use crate::plugin::library::PluginLibrary;
+use crate::tests::rng::new_prng;
+use crate::tests::{SkipReason, TestStatus};
+use crate::util;
+
+use super::processing::ProcessingTest;
+
+/// The sample rate the plugin is activated with before measuring the destroy time.
+const DESTROY_TIME_SAMPLE_RATE: f64 = 44_100.0;
+/// The maximum buffer size the plugin is activated with before measuring the destroy time.
+const DESTROY_TIME_BUFFER_SIZE: usize = 512;
+
+/// The host names the `host-name-independence` test compares the plugin's behavior for. These are
+/// hosts plugins are known to enable workarounds for. Names that match the host name set with the
+/// `--host-name` option are skipped.
+const HOST_NAME_VARIATIONS: [&str; 3] = ["Bitwig Studio", "REAPER", "FL Studio"];
+
+/// The number of processing cycles in the `host-missing-functions` test.
+const MISSING_FUNCTIONS_NUM_BLOCKS: usize = 5;
+
+/// A parameter's ID, name, range, default value, and flags. The cookie is left out since it can
+/// differ between instances.
+type ParamSummary = (
+    clap_id,
+    String,
+    RangeInclusive<f64>,
+    f64,
+    clap_param_info_flags,
+);
+
+/// The parts of a plugin instance's configuration the `host-name-independence` test compares
+/// between host names.
+#[derive(Debug)]
+struct HostNameObservation {
+    /// The IDs of all known extensions the plugin returned a non-null pointer for.
+    supported_extensions: Vec<String>,
+    audio_ports: Option<AudioPortConfig>,
+    note_ports: Option<NotePortConfig>,
+    params: Option<Vec<ParamSummary>>,
+}
+
+/// The test for `PluginTestCase::InstanceCreationTime`. Measures how long it takes to create and
+/// initialize a plugin instance. This directly affects how long it takes for a host to load a
+/// project, so exceeding the configured threshold results in a warning.
+pub fn test_instance_creation_time(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+
+    let creation_start = Instant::now();
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+    let creation_duration = creation_start.elapsed();
+
+    // Callbacks made during initialization are handled afterwards so they're not part of the
+    // measurement
+    host.handle_callbacks_once();
+    drop(plugin);
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let threshold = Duration::from_millis(util::creation_time_threshold_ms());
+    if creation_duration <= threshold {
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin was created and initialized in {}.",
+                format_duration(creation_duration)
+            )),
+        })
+    } else {
+        // Like the scanning time, this depends on the system so it's not treated as a failure
+        Ok(TestStatus::Warning {
+            details: Some(format!(
+                "Creating and initializing the plugin took {}, which exceeds the {} millisecond \
+                 threshold.",
+                format_duration(creation_duration),
+                threshold.as_millis()
+            )),
+        })
+    }
+}
+
+/// The test for `PluginTestCase::InstanceDestroyTime`. Measures how long `clap_plugin::destroy()`
+/// takes. The plugin is activated once first so any background threads it spawns will be running
+/// by the time it gets destroyed. Plugins that block on those threads for a long time stall the
+/// host when it closes a project, so exceeding the configured threshold results in a warning.
+pub fn test_instance_destroy_time(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+    plugin
+        .activate(DESTROY_TIME_SAMPLE_RATE, 1, DESTROY_TIME_BUFFER_SIZE)
+        .context("Error while activating the plugin")?;
+    host.handle_callbacks_once();
+    plugin.deactivate();
+    host.handle_callbacks_once();
+
+    // Dropping the plugin instance calls `clap_plugin::destroy()`. The plugin has already been
+    // deactivated, so that's the only plugin function called here.
+    let destroy_start = Instant::now();
+    drop(plugin);
+    let destroy_duration = destroy_start.elapsed();
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let threshold = Duration::from_millis(util::destroy_time_threshold_ms());
+    if destroy_duration <= threshold {
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin was destroyed in {}.",
+                format_duration(destroy_duration)
+            )),
+        })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(format!(
+                "Destroying the plugin took {}, which exceeds the {} millisecond threshold. The \
+                 plugin may be waiting on background threads in 'clap_plugin::destroy()'.",
+                format_duration(destroy_duration),
+                threshold.as_millis()
+            )),
+        })
+    }
+}
+
+/// The test for `PluginTestCase::ExtensionCoverage`. Queries the plugin for every extension in
+/// [`UNVALIDATED_EXTENSIONS`]. This test never fails, but if the plugin implements any of those
+/// extensions then they're listed in the test's details so it's clear that they were not validated.
+/// The report also shows these details next to the plugin's verdict.
+pub fn test_extension_coverage(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let unvalidated_extensions: Vec<String> = UNVALIDATED_EXTENSIONS
+        .iter()
+        .filter(|extension_id| plugin.supports_extension(extension_id))
+        .map(|extension_id| format!("'{}'", extension_id.to_string_lossy()))
+        .collect();
+    host.handle_callbacks_once();
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    if unvalidated_extensions.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "Not validated: the plugin implements {} that clap-validator does not have any \
+                 tests for: {}. A passing validator run says nothing about the plugin's \
+                 implementation of these extensions.",
+                if unvalidated_extensions.len() == 1 {
+                    "an extension"
+                } else {
+                    "extensions"
+                },
+                unvalidated_extensions.join(", ")
+            )),
+        })
+    }
+}
+
+/// The test for `PluginTestCase::HostNameIndependence`. Creates an instance of the plugin for the
+/// configured host name and for every name in [`HOST_NAME_VARIATIONS`], and compares the
+/// extensions, audio and note ports, and parameters of those instances. Plugins are allowed to
+/// behave differently in specific hosts, so differences result in a warning rather than a failure.
+pub fn test_host_name_independence(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host_info = HostInfo::default();
+    let reference = observe_with_host_info(library, plugin_id, host_info.clone())?;
+
+    let mut differences = Vec::new();
+    for host_name in HOST_NAME_VARIATIONS {
+        if host_name == host_info.name {
+            continue;
+        }
+
+        let observation = observe_with_host_info(
+            library,
+            plugin_id,
+            HostInfo {
+                name: String::from(host_name),
+                ..host_info.clone()
+            },
+        )?;
+
+        let mut changed = Vec::new();
+        if observation.supported_extensions != reference.supported_extensions {
+            changed.push("supported extensions");
+        }
+        if observation.audio_ports != reference.audio_ports {
+            changed.push("audio ports");
+        }
+        if observation.note_ports != reference.note_ports {
+            changed.push("note ports");
+        }
+        if observation.params != reference.params {
+            changed.push("parameters");
+        }
+        if !changed.is_empty() {
+            differences.push(format!("'{host_name}' ({})", changed.join(", ")));
+        }
+    }
+
+    if differences.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(format!(
+                "The plugin behaves differently when the host reports itself as {} instead of \
+                 '{}'. This may be an intentional workaround, but the other tests' results may not \
+                 apply to those hosts. Use the '--host-name' option to run the validator with one \
+                 of those names.",
+                differences.join(", "),
+                host_info.name
+            )),
+        })
+    }
+}
+
+/// Create and initialize an instance of the plugin for a host with the specified host info, and
+/// record the configuration compared in [`test_host_name_independence()`].
+fn observe_with_host_info(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    host_info: HostInfo,
+) -> Result<HostNameObservation> {
+    let host_name = host_info.name.clone();
+    let host = Host::new(HostConfig {
+        host_info,
+        ..HostConfig::default()
+    });
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .with_context(|| {
+            format!("Could not create the plugin instance with the host name '{host_name}'")
+        })?;
+    plugin
+        .init()
+        .with_context(|| format!("Error during initialization with the host name '{host_name}'"))?;
+
+    let supported_extensions = VALIDATED_EXTENSIONS
+        .iter()
+        .chain(UNVALIDATED_EXTENSIONS)
+        .filter(|extension_id| plugin.supports_extension(extension_id))
+        .map(|extension_id| extension_id.to_string_lossy().into_owned())
+        .collect();
+    let audio_ports = plugin
+        .get_extension::<AudioPorts>()
+        .map(|audio_ports| audio_ports.config())
+        .transpose()
+        .context("Error while querying 'audio-ports' IO configuration")?;
+    let note_ports = plugin
+        .get_extension::<NotePorts>()
+        .map(|note_ports| note_ports.config())
+        .transpose()
+        .context("Error while querying 'note-ports' IO configuration")?;
+    let params = plugin
+        .get_extension::<Params>()
+        .map(|params| params.info())
+        .transpose()
+        .context("Failure while fetching the plugin's parameters")?
+        .map(|param_infos| {
+            param_infos
+                .into_iter()
+                .map(|(param_id, param)| {
+                    (
+                        param_id,
+                        param.name,
+                        param.range,
+                        param.default,
+                        param.flags,
+                    )
+                })
+                .collect()
+        });
+    host.handle_callbacks_once();
+    drop(plugin);
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    Ok(HostNameObservation {
+        supported_extensions,
+        audio_ports,
+        note_ports,
+        params,
+    })
+}
+
+/// The test for `PluginTestCase::HostMissingFunctions`. Uses a host where the optional host
+/// extension functions are null pointers, see [`HostConfig::null_optional_functions`]. The plugin
+/// is initialized, its ports and parameters are queried, it processes a couple blocks of random
+/// audio, and its state is saved and reloaded. Any of these can cause the plugin to call one of
+/// the missing functions, which should not cause it to crash. CLAP doesn't allow hosts to leave
+/// these functions out, so this is a negative test.
+pub fn test_host_missing_functions(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    if !util::negative_tests() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::RequiresFlag,
+            details: Some(String::from(
+                "This is a negative test, which only runs when the '--negative-tests' option is \
+                 passed to the validator.",
+            )),
+        });
+    }
+
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig {
+        null_optional_functions: true,
+        ..HostConfig::default()
+    });
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    if let Some(note_ports) = plugin.get_extension::<NotePorts>() {
+        note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?;
+    }
+    if let Some(params) = plugin.get_extension::<Params>() {
+        params
+            .info()
+            .context("Failure while fetching the plugin's parameters")?;
+    }
+    host.handle_callbacks_once();
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(
+            MISSING_FUNCTIONS_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+        )?;
+
+    // Plugins commonly call `clap_host_state::mark_dirty()` or `clap_host_params::rescan()` after
+    // loading a state
+    if let Some(state) = plugin.get_extension::<State>() {
+        let state_bytes = state
+            .save()
+            .context("Error while saving the plugin's state")?;
+        state
+            .load(&state_bytes)
+            .context("Error while reloading the plugin's state")?;
+        host.handle_callbacks_once();
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// Format a duration as a number of milliseconds with sub-millisecond precision.
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} milliseconds", duration.as_secs_f64() * 1000.0)
+}
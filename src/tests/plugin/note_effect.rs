@@ -0,0 +1,428 @@
+//! Tests for note effect plugins, like arpeggiators, chorders, and MIDI delays.
+
+use anyhow::{Context, Result};
+use clap_sys::events::{
+    clap_event_header, clap_event_midi, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI,
+    CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON,
+};
+use clap_sys::ext::note_ports::CLAP_NOTE_DIALECT_MIDI;
+use clap_sys::plugin_features::CLAP_PLUGIN_FEATURE_NOTE_EFFECT;
+use midi_consts::channel_event as midi;
+use std::collections::BTreeMap;
+
+use super::processing::ProcessingTest;
+use crate::plugin::ext::audio_ports::{AudioPortConfig, AudioPorts};
+use crate::plugin::ext::note_ports::NotePorts;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::instance::audio_thread::PluginAudioThread;
+use crate::plugin::instance::process::{Event, ProcessConfig, ProcessData};
+use crate::plugin::library::PluginLibrary;
+use crate::tests::rng::{new_prng, MIDI2_NOTE_OFF, MIDI2_NOTE_ON, UMP_MIDI2_CHANNEL_VOICE};
+use crate::tests::{SkipReason, TestStatus};
+
+/// The block size used in the `process-note-effect-chain` test.
+const CHAIN_BLOCK_SIZE: usize = 512;
+/// The length of a single step in [`CHAIN_PERFORMANCE`], in samples. This is a sixteenth note at
+/// roughly 160 BPM.
+const CHAIN_STEP_SAMPLES: u32 = 4096;
+/// The number of steps in [`CHAIN_PERFORMANCE`].
+const CHAIN_NUM_STEPS: u32 = 16;
+/// The block the 'All Notes Off' messages are sent in. This is eight blocks after the block
+/// containing the performance's last note-off events.
+const CHAIN_ALL_NOTES_OFF_BLOCK: usize =
+    (CHAIN_NUM_STEPS * CHAIN_STEP_SAMPLES) as usize / CHAIN_BLOCK_SIZE + 8;
+/// The number of blocks processed in the `process-note-effect-chain` test. The blocks after the
+/// 'All Notes Off' messages give the plugin time to release any remaining notes.
+pub const CHAIN_NUM_BLOCKS: usize = 160;
+/// The maximum delay between the first note sent to the plugin and the first note it outputs
+/// before the test results in a warning, in samples. This is about a tenth of a second.
+const CHAIN_MAX_LATENCY_SAMPLES: u64 = 4410;
+
+/// The MIDI 'All Notes Off' channel mode message.
+pub const MIDI_CC_ALL_NOTES_OFF: u8 = 123;
+/// The MIDI 'All Sound Off' channel mode message. Plugins may send this instead of 'All Notes Off'.
+pub const MIDI_CC_ALL_SOUND_OFF: u8 = 120;
+
+/// The Universal MIDI Packet message type for MIDI 1.0 channel voice messages.
+const UMP_MIDI1_CHANNEL_VOICE: u32 = 0x2;
+/// The status nibble for MIDI 2.0 control change messages.
+const MIDI2_CONTROL_CHANGE: u32 = 0xb;
+
+/// The scripted performance sent to the plugin in the `process-note-effect-chain` test. These are
+/// `(channel, first_step, num_steps, key, velocity)` tuples. The chords are played on the first
+/// channel, and a melody with some repeated notes is played on top of them on the second channel.
+const CHAIN_PERFORMANCE: &[(u8, u32, u32, u8, u8)] = &[
+    // C major
+    (0, 0, 4, 60, 80),
+    (0, 0, 4, 64, 80),
+    (0, 0, 4, 67, 80),
+    // F major
+    (0, 4, 4, 65, 80),
+    (0, 4, 4, 69, 80),
+    (0, 4, 4, 72, 80),
+    // G major
+    (0, 8, 4, 67, 80),
+    (0, 8, 4, 71, 80),
+    (0, 8, 4, 74, 80),
+    // C major
+    (0, 12, 4, 60, 80),
+    (0, 12, 4, 64, 80),
+    (0, 12, 4, 67, 80),
+    // The melody
+    (1, 0, 1, 72, 100),
+    (1, 1, 1, 74, 90),
+    (1, 2, 2, 76, 110),
+    (1, 4, 2, 77, 100),
+    (1, 6, 2, 72, 90),
+    (1, 8, 1, 74, 100),
+    (1, 9, 1, 71, 80),
+    (1, 10, 2, 67, 90),
+    (1, 12, 1, 72, 110),
+    (1, 13, 1, 72, 70),
+    (1, 14, 2, 72, 100),
+];
+
+/// The test for `PluginTestCase::ProcessNoteEffectChain`. Note effect plugins are usually chained
+/// with other note effects and instruments. This sends a scripted MIDI performance to the plugin,
+/// followed by 'All Notes Off' messages on every channel, and keeps track of the notes the plugin
+/// outputs. After the plugin had some time to react to the 'All Notes Off' messages, none of its
+/// output notes should still be playing, since those would result in stuck notes on the next
+/// plugin in the chain.
+pub fn test_process_note_effect_chain(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let note_effect_feature = CLAP_PLUGIN_FEATURE_NOTE_EFFECT.to_str().unwrap();
+    if !plugin
+        .descriptor()?
+        .features
+        .iter()
+        .any(|feature| feature == note_effect_feature)
+    {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
+            details: Some(format!(
+                "The plugin does not have the '{note_effect_feature}' feature."
+            )),
+        });
+    }
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let midi_note_port_idx = note_ports_config.inputs.iter().position(|note_port| {
+        note_port
+            .supported_dialects
+            .contains(&CLAP_NOTE_DIALECT_MIDI)
+    });
+    let note_port_idx = match midi_note_port_idx {
+        Some(note_port_idx) => note_port_idx as u16,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::NoNotePorts,
+                details: Some(String::from(
+                    "The plugin does not have any input note ports that support MIDI.",
+                )),
+            })
+        }
+    };
+    if note_ports_config.outputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(String::from(
+                "The plugin does not have any output note ports.",
+            )),
+        });
+    }
+
+    let mut preprocess_block_start = 0;
+    let mut preprocess_block_idx = 0;
+    let preprocess = |process_data: &mut ProcessData| -> Result<()> {
+        let block_start = preprocess_block_start;
+        let block_end = block_start + process_data.block_size() as u32;
+
+        // Note-off events are added first so a note that ends at the same time another note with
+        // the same key starts does not cut off the new note
+        let mut events = Vec::new();
+        for &(channel, first_step, num_steps, key, _) in CHAIN_PERFORMANCE {
+            let off_time = (first_step + num_steps) * CHAIN_STEP_SAMPLES;
+            if (block_start..block_end).contains(&off_time) {
+                events.push(midi_event(
+                    off_time - block_start,
+                    note_port_idx,
+                    [midi::NOTE_OFF | channel, key, 0],
+                ));
+            }
+        }
+        for &(channel, first_step, _, key, velocity) in CHAIN_PERFORMANCE {
+            let on_time = first_step * CHAIN_STEP_SAMPLES;
+            if (block_start..block_end).contains(&on_time) {
+                events.push(midi_event(
+                    on_time - block_start,
+                    note_port_idx,
+                    [midi::NOTE_ON | channel, key, velocity],
+                ));
+            }
+        }
+        if preprocess_block_idx == CHAIN_ALL_NOTES_OFF_BLOCK {
+            events.extend((0..16).map(|channel| {
+                midi_event(
+                    0,
+                    note_port_idx,
+                    [midi::CONTROL_CHANGE | channel, MIDI_CC_ALL_NOTES_OFF, 0],
+                )
+            }));
+        }
+        events.sort_by_key(|event| event.header().time);
+
+        process_data.input_events.events.lock().extend(events);
+        process_data.buffers.randomize(&mut prng);
+        preprocess_block_start = block_end;
+        preprocess_block_idx += 1;
+
+        Ok(())
+    };
+
+    let mut output_notes = OutputNoteTracker::default();
+    let postprocess = |_: &PluginAudioThread, process_data: &ProcessData| -> Result<()> {
+        output_notes.process_block(process_data);
+
+        Ok(())
+    };
+
+    let (mut input_buffers, mut output_buffers) =
+        audio_ports_config.create_buffers(CHAIN_BLOCK_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            CHAIN_NUM_BLOCKS,
+            ProcessConfig::default(),
+            preprocess,
+            postprocess,
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    if !output_notes.is_empty() {
+        anyhow::bail!(
+            "The plugin did not release all of its output notes after receiving 'All Notes Off' \
+             messages on every channel. These notes were still playing {} blocks later: {}.",
+            CHAIN_NUM_BLOCKS - CHAIN_ALL_NOTES_OFF_BLOCK,
+            output_notes.describe_playing_notes()
+        );
+    }
+
+    // The performance's first notes are played at the very first sample
+    let mut warnings = Vec::new();
+    match output_notes.first_note_on_time() {
+        None => warnings.push(String::from(
+            "The plugin did not output any notes during the performance. A note effect would \
+             normally pass through or generate at least some notes.",
+        )),
+        Some(latency) if latency > CHAIN_MAX_LATENCY_SAMPLES => warnings.push(format!(
+            "The plugin only output its first note {latency} samples after receiving the first \
+             note. Other plugins in the chain would be noticeably delayed by this."
+        )),
+        Some(_) => (),
+    }
+    if let TestStatus::Warning {
+        details: Some(details),
+    } = stats.status()
+    {
+        warnings.push(details);
+    }
+
+    if warnings.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join(" ")),
+        })
+    }
+}
+
+/// Keeps track of the notes a plugin outputs, so tests can check whether the plugin released all of
+/// them. Notes are identified by `(port_index, channel, key)` tuples, and they can be started and
+/// ended using CLAP note events, MIDI, and MIDI 2.0. A note that was started more than once also
+/// needs as many MIDI note-off events before it's released.
+#[derive(Debug, Default)]
+pub struct OutputNoteTracker {
+    /// The notes that are currently playing, mapped to the number of note-on events for that note
+    /// without a matching note-off event.
+    notes: BTreeMap<(i16, i16, i16), usize>,
+    /// The time of the first note-on event, in samples since the start of the first block.
+    first_note_on_time: Option<u64>,
+    /// The start of the next block, in samples since the start of the first block.
+    block_start: u64,
+}
+
+impl OutputNoteTracker {
+    /// Update the playing notes using the plugin's output events. This needs to be called after
+    /// every processing cycle.
+    pub fn process_block(&mut self, process_data: &ProcessData) {
+        for event in process_data.output_events.events.lock().iter() {
+            let time = self.block_start + event.header().time as u64;
+            match event {
+                Event::Note(event) => match event.header.type_ {
+                    CLAP_EVENT_NOTE_ON => {
+                        self.note_on((event.port_index, event.channel, event.key), time)
+                    }
+                    // Wildcards may be used to end multiple notes at once
+                    CLAP_EVENT_NOTE_OFF | CLAP_EVENT_NOTE_CHOKE => {
+                        let matches = |pattern: i16, value: i16| pattern == -1 || pattern == value;
+                        self.notes.retain(|&(port_index, channel, key), _| {
+                            !(matches(event.port_index, port_index)
+                                && matches(event.channel, channel)
+                                && matches(event.key, key))
+                        })
+                    }
+                    _ => (),
+                },
+                Event::Midi(event) => self.midi_message(event.port_index as i16, event.data, time),
+                // The Universal MIDI Packet's group is not taken into account
+                Event::Midi2(event) => {
+                    let port_index = event.port_index as i16;
+                    let word = event.data[0];
+                    match word >> 28 {
+                        UMP_MIDI1_CHANNEL_VOICE => self.midi_message(
+                            port_index,
+                            [
+                                (word >> 16) as u8,
+                                (word >> 8) as u8 & 0x7f,
+                                word as u8 & 0x7f,
+                            ],
+                            time,
+                        ),
+                        UMP_MIDI2_CHANNEL_VOICE => {
+                            let channel = ((word >> 16) & 0x0f) as i16;
+                            let index = ((word >> 8) & 0x7f) as u8;
+                            match (word >> 20) & 0x0f {
+                                // Unlike in MIDI 1.0, a note-on with zero velocity is still a
+                                // note-on
+                                MIDI2_NOTE_ON => {
+                                    self.note_on((port_index, channel, index as i16), time)
+                                }
+                                MIDI2_NOTE_OFF => {
+                                    self.note_off((port_index, channel, index as i16))
+                                }
+                                MIDI2_CONTROL_CHANGE
+                                    if index == MIDI_CC_ALL_NOTES_OFF
+                                        || index == MIDI_CC_ALL_SOUND_OFF =>
+                                {
+                                    self.all_notes_off(port_index, channel)
+                                }
+                                _ => (),
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        self.block_start += process_data.block_size() as u64;
+    }
+
+    /// Whether all of the plugin's output notes have been released.
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// The time of the plugin's first note-on event, in samples since the start of the first block.
+    /// Returns `None` if the plugin has not output any notes.
+    pub fn first_note_on_time(&self) -> Option<u64> {
+        self.first_note_on_time
+    }
+
+    /// A human readable list of the notes that are still playing, for use in error messages.
+    pub fn describe_playing_notes(&self) -> String {
+        self.notes
+            .keys()
+            .map(|(port_index, channel, key)| {
+                format!("port {port_index}, channel {channel}, key {key}")
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Handle a MIDI 1.0 channel voice message.
+    fn midi_message(&mut self, port_index: i16, data: [u8; 3], time: u64) {
+        let channel = (data[0] & 0x0f) as i16;
+        let note = (port_index, channel, data[1] as i16);
+        match (data[0] & 0xf0, data[1], data[2]) {
+            (midi::NOTE_ON, _, velocity) if velocity > 0 => self.note_on(note, time),
+            (midi::NOTE_ON | midi::NOTE_OFF, _, _) => self.note_off(note),
+            (midi::CONTROL_CHANGE, MIDI_CC_ALL_NOTES_OFF | MIDI_CC_ALL_SOUND_OFF, _) => {
+                self.all_notes_off(port_index, channel)
+            }
+            _ => (),
+        }
+    }
+
+    /// Start a note, or start it again if it's already playing.
+    fn note_on(&mut self, note: (i16, i16, i16), time: u64) {
+        *self.notes.entry(note).or_default() += 1;
+        self.first_note_on_time.get_or_insert(time);
+    }
+
+    /// End one instance of a note started using a MIDI note-on event.
+    fn note_off(&mut self, note: (i16, i16, i16)) {
+        if let Some(count) = self.notes.get_mut(&note) {
+            *count -= 1;
+            if *count == 0 {
+                self.notes.remove(&note);
+            }
+        }
+    }
+
+    /// End all notes on a channel, in response to an 'All Notes Off' or 'All Sound Off' message.
+    fn all_notes_off(&mut self, port_index: i16, channel: i16) {
+        self.notes.retain(|&(note_port_index, note_channel, _), _| {
+            (note_port_index, note_channel) != (port_index, channel)
+        });
+    }
+}
+
+/// Create a MIDI event for the input event queue.
+fn midi_event(time: u32, port_index: u16, data: [u8; 3]) -> Event {
+    Event::Midi(clap_event_midi {
+        header: clap_event_header {
+            size: std::mem::size_of::<clap_event_midi>() as u32,
+            time,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_: CLAP_EVENT_MIDI,
+            flags: 0,
+        },
+        port_index,
+        data,
+    })
+}
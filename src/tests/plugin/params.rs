@@ -1,23 +1,38 @@
 //! Tests that focus on parameters.
 
 use anyhow::{Context, Result};
-use clap_sys::events::CLAP_EVENT_PARAM_VALUE;
+use clap_sys::events::{
+    clap_event_header, clap_event_note, clap_event_param_mod, clap_event_param_value,
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON,
+    CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE,
+};
+use clap_sys::ext::note_ports::CLAP_NOTE_DIALECT_CLAP;
+use clap_sys::ext::params::{
+    clap_param_info_flags, CLAP_PARAM_IS_AUTOMATABLE_PER_CHANNEL,
+    CLAP_PARAM_IS_AUTOMATABLE_PER_KEY, CLAP_PARAM_IS_AUTOMATABLE_PER_NOTE_ID,
+    CLAP_PARAM_IS_MODULATABLE_PER_CHANNEL, CLAP_PARAM_IS_MODULATABLE_PER_KEY,
+    CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID, CLAP_PARAM_RESCAN_ALL,
+};
 use clap_sys::id::clap_id;
 use rand::Rng;
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use super::processing::ProcessingTest;
+use super::processing::{check_output_param_events, check_unended_notes, ProcessingTest};
+use super::state::format_mismatching_values;
 use super::PluginTestCase;
 use crate::plugin::ext::audio_ports::{AudioPortConfig, AudioPorts};
 use crate::plugin::ext::note_ports::NotePorts;
-use crate::plugin::ext::params::Params;
+use crate::plugin::ext::params::{Param, ParamInfo, Params, ParamsAudioThread};
+use crate::plugin::ext::state::State;
 use crate::plugin::ext::Extension;
-use crate::plugin::host::Host;
-use crate::plugin::instance::process::{Event, ProcessConfig};
+use crate::plugin::host::{Host, HostConfig, KnownParam};
+use crate::plugin::instance::audio_thread::PluginAudioThread;
+use crate::plugin::instance::process::{Event, EventQueue, ProcessConfig, ProcessData};
 use crate::plugin::library::PluginLibrary;
 use crate::tests::rng::{new_prng, NoteGenerator, ParamFuzzer};
-use crate::tests::{TestCase, TestStatus};
+use crate::tests::{SkipReason, TestCase, TestStatus};
+use crate::{ipc, util};
 
 /// The fixed buffer size to use for these tests.
 const BUFFER_SIZE: usize = 512;
@@ -26,6 +41,43 @@ pub const FUZZ_NUM_PERMUTATIONS: usize = 50;
 /// How many buffers of [`BUFFER_SIZE`] samples to process at each parameter permutation. This
 /// allows the plugin's state to settle in before moving to the next set of parameter values.
 pub const FUZZ_RUNS_PER_PERMUTATION: usize = 5;
+/// The number of processing cycles in the parameter echo test. Every cycle sends new random values
+/// for all of the plugin's parameters.
+pub const ECHO_NUM_CYCLES: usize = 20;
+/// The maximum difference between a parameter value sent by the host and the value the plugin
+/// outputs for that parameter in the same cycle, relative to the parameter's range. This allows
+/// plugins to store parameter values with single precision.
+const ECHO_TOLERANCE: f64 = 1e-6;
+/// The number of blocks the parameter ramps in the block edge automation test are spread over.
+pub const BLOCK_EDGES_NUM_BLOCKS: usize = 32;
+/// The block size used in the block edge automation test. Parameter changes are sent for the first
+/// and the last sample of these blocks.
+pub const BLOCK_EDGES_BLOCK_SIZE: usize = 32;
+
+/// The keys of the notes played in the polyphonic automation test. Every note uses its index in
+/// this array as its note ID and its MIDI channel, so the notes can be targeted individually
+/// regardless of whether the plugin supports per-note ID, per-key, or per-channel automation.
+const POLYPHONIC_KEYS: [i16; 4] = [48, 60, 64, 67];
+/// The block at the start of which all notes are released in the polyphonic automation test. The
+/// blocks before this one contain per-note parameter events for every note.
+pub const POLYPHONIC_NOTE_OFF_BLOCK: usize = 8;
+/// The number of blocks processed after releasing the notes in the polyphonic automation test.
+/// This is about three seconds at 44.1 kHz, which leaves the plugin enough time to end notes with
+/// long release tails.
+const POLYPHONIC_RELEASE_BLOCKS: usize = 256;
+/// The number of blocks processed in the polyphonic automation test.
+const POLYPHONIC_NUM_BLOCKS: usize = POLYPHONIC_NOTE_OFF_BLOCK + POLYPHONIC_RELEASE_BLOCKS;
+
+/// The number of times a state is loaded in the parameter rescan test. Every other state load
+/// happens while the plugin is activated.
+pub const RESCAN_NUM_STATE_LOADS: usize = 8;
+
+/// The number of blocks processed after activating the plugin in the deactivated flush test.
+pub const FLUSH_DEACTIVATED_NUM_BLOCKS: usize = 5;
+
+/// The number of blocks processed in the output parameter events test. Random parameter values are
+/// sent at the start of every block.
+pub const OUTPUT_EVENTS_NUM_BLOCKS: usize = 32;
 
 /// The file name we'll use to dump the previous parameter values when a fuzzing test fails.
 const PREVIOUS_PARAM_VALUES_FILE_NAME: &str = "param-values-previous.json";
@@ -41,11 +93,217 @@ struct ParamValue<'a> {
     value: f64,
 }
 
+/// Returns a `Skipped` status if the plugin doesn't have any parameters at all. Tests that also
+/// work with read-only or hidden parameters use this instead of
+/// [`skip_without_settable_params()`].
+pub fn skip_without_params(param_infos: &ParamInfo) -> Option<TestStatus> {
+    if !param_infos.is_empty() {
+        return None;
+    }
+
+    Some(TestStatus::Skipped {
+        reason: SkipReason::NoParams,
+        details: Some(String::from(
+            "The plugin implements the 'clap.params' extension, but it does not have any \
+             parameters.",
+        )),
+    })
+}
+
+/// Returns a `Skipped` status if the plugin doesn't have any parameters the host can change. Tests
+/// that revolve around sending parameter changes to the plugin would otherwise succeed without
+/// testing anything.
+pub fn skip_without_settable_params(param_infos: &ParamInfo) -> Option<TestStatus> {
+    if let Some(status) = skip_without_params(param_infos) {
+        return Some(status);
+    }
+    if param_infos
+        .values()
+        .any(|param_info| !param_info.readonly() && !param_info.hidden())
+    {
+        return None;
+    }
+
+    Some(TestStatus::Skipped {
+        reason: SkipReason::NoParams,
+        details: Some(String::from(
+            "The plugin does not have any parameters that can be set by the host. All of its \
+             parameters are read-only or hidden.",
+        )),
+    })
+}
+
+/// The test for `PluginTestCase::ParamSanity`. `Params::info()` already rejects parameters that
+/// are outright invalid. This test instead looks for parameters that are technically valid but
+/// almost certainly not intended, and reports them as warnings.
+pub fn test_param_sanity(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+
+    let mut warnings = Vec::new();
+    let mut params_by_name: BTreeMap<(&str, &str), Vec<clap_id>> = BTreeMap::new();
+    for (param_id, param_info) in &param_infos {
+        let (min, max) = (*param_info.range.start(), *param_info.range.end());
+        if min == max {
+            if param_info.stepped() {
+                warnings.push(format!(
+                    "Parameter '{}' (stable ID {param_id}) is a stepped parameter with only a \
+                     single step ({}).",
+                    param_info.name,
+                    util::format_f64(min)
+                ));
+            } else {
+                warnings.push(format!(
+                    "Parameter '{}' (stable ID {param_id}) has the same minimum and maximum value \
+                     ({}).",
+                    param_info.name,
+                    util::format_f64(min)
+                ));
+            }
+        }
+        if !min.is_finite() || !max.is_finite() || !param_info.default.is_finite() {
+            warnings.push(format!(
+                "Parameter '{}' (stable ID {param_id}) has an infinite minimum, maximum, or \
+                 default value ({}..={}, default {}).",
+                param_info.name,
+                util::format_f64(min),
+                util::format_f64(max),
+                util::format_f64(param_info.default)
+            ));
+        }
+
+        // Hidden parameters are not shown to the user, so they may share names
+        if !param_info.hidden() {
+            params_by_name
+                .entry((&param_info.module, &param_info.name))
+                .or_default()
+                .push(*param_id);
+        }
+    }
+    for ((module, name), param_ids) in params_by_name {
+        if param_ids.len() > 1 {
+            warnings.push(format!(
+                "Parameters with stable IDs {} all have the name '{name}'{}.",
+                param_ids
+                    .iter()
+                    .map(|param_id| param_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                if module.is_empty() {
+                    String::new()
+                } else {
+                    format!(" in module '{module}'")
+                }
+            ));
+        }
+    }
+
+    // The stable IDs should still refer to the same parameters after the plugin reloads its own
+    // state, which is when a plugin would rebuild its parameter list and rescan its parameters.
+    // This is only possible when the plugin implements the state extension.
+    let rescanned_param_infos = match plugin.get_extension::<State>() {
+        Some(state) => {
+            let saved_state = state.save()?;
+            state.load(&saved_state)?;
+            host.handle_callbacks_until_idle();
+
+            Some(
+                params
+                    .info()
+                    .context("Failure while fetching the plugin's parameters after a state load")?,
+            )
+        }
+        None => None,
+    };
+    if let Some(rescanned_param_infos) = &rescanned_param_infos {
+        let param_ids: Vec<clap_id> = param_infos.keys().copied().collect();
+        let rescanned_param_ids: Vec<clap_id> = rescanned_param_infos.keys().copied().collect();
+        if rescanned_param_ids != param_ids {
+            warnings.push(format!(
+                "Reloading the plugin's own state changed its parameters' stable IDs from \
+                 {param_ids:?} to {rescanned_param_ids:?}."
+            ));
+        } else {
+            for (param_id, param_info) in &param_infos {
+                let rescanned_param_info = &rescanned_param_infos[param_id];
+                let mut changes = Vec::new();
+                if rescanned_param_info.name != param_info.name {
+                    changes.push(format!(
+                        "its name changed from '{}' to '{}'",
+                        param_info.name, rescanned_param_info.name
+                    ));
+                }
+                if rescanned_param_info.module != param_info.module {
+                    changes.push(format!(
+                        "its module changed from '{}' to '{}'",
+                        param_info.module, rescanned_param_info.module
+                    ));
+                }
+                if rescanned_param_info.range != param_info.range {
+                    changes.push(format!(
+                        "its range changed from {}..={} to {}..={}",
+                        util::format_f64(*param_info.range.start()),
+                        util::format_f64(*param_info.range.end()),
+                        util::format_f64(*rescanned_param_info.range.start()),
+                        util::format_f64(*rescanned_param_info.range.end())
+                    ));
+                }
+                if rescanned_param_info.flags != param_info.flags {
+                    changes.push(format!(
+                        "its flags changed from {:#x} to {:#x}",
+                        param_info.flags, rescanned_param_info.flags
+                    ));
+                }
+
+                if !changes.is_empty() {
+                    warnings.push(format!(
+                        "Parameter '{}' (stable ID {param_id}) changed after the plugin reloaded \
+                         its own state: {}.",
+                        param_info.name,
+                        changes.join(", ")
+                    ));
+                }
+            }
+        }
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    if warnings.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join("\n")),
+        })
+    }
+}
+
 /// The test for `ProcessingTest::ParamConversions`.
 pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -55,6 +313,7 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
         Some(params) => params,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
                     Params::EXTENSION_ID.to_str().unwrap(),
@@ -67,6 +326,9 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
     let param_infos = params
         .info()
         .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_params(&param_infos) {
+        return Ok(status);
+    }
 
     // We keep track of how many parameters support these conversions. A plugin
     // should support either conversion either for all of its parameters, or for
@@ -76,7 +338,8 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
 
     let mut num_supported_value_to_text = 0;
     let mut num_supported_text_to_value = 0;
-    let mut failed_value_to_text_calls: Vec<(String, f64)> = Vec::new();
+    // The values are formatted using `util::format_f64()` so they're printed exactly
+    let mut failed_value_to_text_calls: Vec<(String, String)> = Vec::new();
     let mut failed_text_to_value_calls: Vec<(String, String)> = Vec::new();
     'param_loop: for (param_id, param_info) in param_infos {
         let param_name = &param_info.name;
@@ -99,7 +362,8 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
             let starting_text = match params.value_to_text(param_id, starting_value)? {
                 Some(text) => text,
                 None => {
-                    failed_value_to_text_calls.push((param_name.to_owned(), starting_value));
+                    failed_value_to_text_calls
+                        .push((param_name.to_owned(), util::format_f64(starting_value)));
                     continue 'param_loop;
                 }
             };
@@ -128,10 +392,11 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
             // Both of these are produced by the plugin, so they should be equal
             if starting_text != reconverted_text {
                 anyhow::bail!(
-                    "Converting {starting_value:?} to a string, back to a value, and then back to \
-                     a string again for parameter {param_id} ('{param_name}') results in \
-                     '{starting_text}' -> {reconverted_value:?} -> '{reconverted_text}', which is \
-                     not consistent."
+                    "Converting {} to a string, back to a value, and then back to a string again \
+                     for parameter {param_id} ('{param_name}') results in '{starting_text}' -> {} \
+                     -> '{reconverted_text}', which is not consistent.",
+                    util::format_f64(starting_value),
+                    util::format_f64(reconverted_value),
                 );
             }
 
@@ -146,10 +411,13 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
                 })?;
             if final_value != reconverted_value {
                 anyhow::bail!(
-                    "Converting {starting_value:?} to a string, back to a value, back to a \
-                     string, and then back to a value again for parameter {param_id} \
-                     ('{param_name}') results in '{starting_text}' -> {reconverted_value:?} -> \
-                     '{reconverted_text}' -> {final_value:?}, which is not consistent."
+                    "Converting {} to a string, back to a value, back to a string, and then back \
+                     to a value again for parameter {param_id} ('{param_name}') results in \
+                     '{starting_text}' -> {} -> '{reconverted_text}' -> {}, which is not \
+                     consistent.",
+                    util::format_f64(starting_value),
+                    util::format_f64(reconverted_value),
+                    util::format_f64(final_value),
                 );
             }
         }
@@ -176,6 +444,7 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
         .context("An error occured during a host callback")?;
     if num_supported_value_to_text == 0 || num_supported_text_to_value == 0 {
         Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
             details: Some(String::from(
                 "The plugin's parameters need to support both value to text and text to value \
                  conversions for this test.",
@@ -190,7 +459,7 @@ pub fn test_param_conversions(library: &PluginLibrary, plugin_id: &str) -> Resul
 pub fn test_param_fuzz_basic(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -203,6 +472,7 @@ pub fn test_param_fuzz_basic(library: &PluginLibrary, plugin_id: &str) -> Result
         Some(params) => params,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
                     Params::EXTENSION_ID.to_str().unwrap(),
@@ -226,6 +496,9 @@ pub fn test_param_fuzz_basic(library: &PluginLibrary, plugin_id: &str) -> Result
     let param_infos = params
         .info()
         .context("Could not fetch the plugin's parameters")?;
+    if let Some(status) = skip_without_settable_params(&param_infos) {
+        return Ok(status);
+    }
 
     // For each set of runs we'll generate new parameter values, and if the plugin supports notes
     // we'll also generate note events.
@@ -335,7 +608,7 @@ pub fn test_param_set_wrong_namespace(
 ) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -351,6 +624,7 @@ pub fn test_param_set_wrong_namespace(
         Some(params) => params,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
                     Params::EXTENSION_ID.to_str().unwrap(),
@@ -363,6 +637,9 @@ pub fn test_param_set_wrong_namespace(
     let param_infos = params
         .info()
         .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_settable_params(&param_infos) {
+        return Ok(status);
+    }
     let initial_param_values: BTreeMap<clap_id, f64> = param_infos
         .keys()
         .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
@@ -411,6 +688,977 @@ pub fn test_param_set_wrong_namespace(
                  values to change. This should not happen. The plugin may not be checking the \
                  event's namespace ID."
             )),
+            causes: Vec::new(),
         })
     }
 }
+
+/// The test for `PluginTestCase::ParamEchoConsistency`.
+pub fn test_param_echo_consistency(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_settable_params(&param_infos) {
+        return Ok(status);
+    }
+
+    // Every cycle sets all parameters to new random values. Any parameter value events the plugin
+    // outputs for those parameters during the same cycle should agree with the host's values.
+    let param_fuzzer = ParamFuzzer::new(&param_infos);
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            ECHO_NUM_CYCLES,
+            ProcessConfig::default(),
+            |process_data| {
+                *process_data.input_events.events.lock() =
+                    param_fuzzer.randomize_params_at(&mut prng, 0).collect();
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+            |_, process_data| check_param_output_echo(process_data, &param_infos),
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    Ok(TestStatus::Success { details: None })
+}
+
+/// The test for `PluginTestCase::ParamPolyphonicAutomation`. Plays a chord, and sends per-note
+/// parameter value and modulation events for every parameter that supports polyphonic automation
+/// or modulation while the notes are held. The notes are targeted by note ID, by key, or by
+/// channel, depending on the parameter's flags. If the plugin outputs `CLAP_EVENT_NOTE_END` events,
+/// then it should do so for every note after the notes have been released.
+pub fn test_param_polyphonic_automation(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let clap_note_port_idx = note_ports_config.inputs.iter().position(|note_port| {
+        note_port
+            .supported_dialects
+            .contains(&CLAP_NOTE_DIALECT_CLAP)
+    });
+    let note_port_idx = match clap_note_port_idx {
+        Some(note_port_idx) => note_port_idx as i16,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::NoNotePorts,
+                details: Some(String::from(
+                    "The plugin does not have any input note ports that support CLAP note \
+                     events.",
+                )),
+            })
+        }
+    };
+
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_settable_params(&param_infos) {
+        return Ok(status);
+    }
+    let polyphonic_params: Vec<(clap_id, &Param)> = param_infos
+        .iter()
+        .filter(|(_, param_info)| {
+            !param_info.readonly()
+                && (per_note_target(param_info.flags, false, 0).is_some()
+                    || per_note_target(param_info.flags, true, 0).is_some())
+        })
+        .map(|(param_id, param_info)| (*param_id, param_info))
+        .collect();
+    if polyphonic_params.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
+            details: Some(String::from(
+                "The plugin does not have any parameters that support per-note automation or \
+                 modulation.",
+            )),
+        });
+    }
+
+    let mut block_idx = 0;
+    let preprocess = |process_data: &mut ProcessData| -> Result<()> {
+        let mut events = Vec::new();
+        if block_idx == 0 || block_idx == POLYPHONIC_NOTE_OFF_BLOCK {
+            let event_type = if block_idx == 0 {
+                CLAP_EVENT_NOTE_ON
+            } else {
+                CLAP_EVENT_NOTE_OFF
+            };
+            for (voice_idx, key) in POLYPHONIC_KEYS.iter().enumerate() {
+                events.push(Event::Note(clap_event_note {
+                    header: clap_event_header {
+                        size: std::mem::size_of::<clap_event_note>() as u32,
+                        time: 0,
+                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                        type_: event_type,
+                        flags: 0,
+                    },
+                    note_id: voice_idx as i32,
+                    port_index: note_port_idx,
+                    channel: voice_idx as i16,
+                    key: *key,
+                    velocity: 0.8,
+                }));
+            }
+        } else if block_idx < POLYPHONIC_NOTE_OFF_BLOCK {
+            for voice_idx in 0..POLYPHONIC_KEYS.len() {
+                for (param_id, param_info) in &polyphonic_params {
+                    let time = prng.gen_range(0..process_data.block_size() as u32);
+                    let header = |type_, size| clap_event_header {
+                        size: size as u32,
+                        time,
+                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                        type_,
+                        flags: 0,
+                    };
+
+                    if let Some((note_id, channel, key)) =
+                        per_note_target(param_info.flags, false, voice_idx)
+                    {
+                        events.push(Event::ParamValue(clap_event_param_value {
+                            header: header(
+                                CLAP_EVENT_PARAM_VALUE,
+                                std::mem::size_of::<clap_event_param_value>(),
+                            ),
+                            param_id: *param_id,
+                            cookie: param_info.cookie,
+                            note_id,
+                            port_index: if note_id == -1 { note_port_idx } else { -1 },
+                            channel,
+                            key,
+                            value: prng.gen_range(param_info.range.clone()),
+                        }));
+                    }
+                    if let Some((note_id, channel, key)) =
+                        per_note_target(param_info.flags, true, voice_idx)
+                    {
+                        let range_size = param_info.range.end() - param_info.range.start();
+                        events.push(Event::ParamMod(clap_event_param_mod {
+                            header: header(
+                                CLAP_EVENT_PARAM_MOD,
+                                std::mem::size_of::<clap_event_param_mod>(),
+                            ),
+                            param_id: *param_id,
+                            cookie: param_info.cookie,
+                            note_id,
+                            port_index: if note_id == -1 { note_port_idx } else { -1 },
+                            channel,
+                            key,
+                            amount: prng.gen_range(-range_size..=range_size) / 2.0,
+                        }));
+                    }
+                }
+            }
+
+            events.sort_by_key(|event| event.header().time);
+        }
+        process_data.input_events.events.lock().extend(events);
+        process_data.buffers.randomize(&mut prng);
+        block_idx += 1;
+
+        Ok(())
+    };
+
+    let mut ended_note_ids = Vec::new();
+    let postprocess = |_: &PluginAudioThread, process_data: &ProcessData| -> Result<()> {
+        for event in process_data.output_events.events.lock().iter() {
+            if let Event::Note(note_event) = event {
+                if note_event.header.type_ == CLAP_EVENT_NOTE_END {
+                    ended_note_ids.push(note_event.note_id);
+                }
+            }
+        }
+
+        Ok(())
+    };
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            POLYPHONIC_NUM_BLOCKS,
+            ProcessConfig::default(),
+            preprocess,
+            postprocess,
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let unended_note_ids: Vec<i32> = (0..POLYPHONIC_KEYS.len() as i32)
+        .filter(|note_id| !ended_note_ids.contains(note_id))
+        .collect();
+    if let Some(warning) = check_unended_notes(
+        !ended_note_ids.is_empty(),
+        &unended_note_ids,
+        POLYPHONIC_RELEASE_BLOCKS,
+        BUFFER_SIZE,
+    ) {
+        return Ok(warning);
+    }
+
+    Ok(stats.status())
+}
+
+/// Determine how a per-note parameter event for the note at `voice_idx` in [`POLYPHONIC_KEYS`]
+/// should be addressed based on a parameter's flags. Returns a `(note_id, channel, key)` tuple,
+/// with -1 for wildcards, or `None` if the parameter doesn't support per-note automation (or
+/// per-note modulation if `modulation` is set). Note IDs are preferred over keys, and keys are
+/// preferred over channels.
+fn per_note_target(
+    flags: clap_param_info_flags,
+    modulation: bool,
+    voice_idx: usize,
+) -> Option<(i32, i16, i16)> {
+    let (per_note_id, per_key, per_channel) = if modulation {
+        (
+            CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID,
+            CLAP_PARAM_IS_MODULATABLE_PER_KEY,
+            CLAP_PARAM_IS_MODULATABLE_PER_CHANNEL,
+        )
+    } else {
+        (
+            CLAP_PARAM_IS_AUTOMATABLE_PER_NOTE_ID,
+            CLAP_PARAM_IS_AUTOMATABLE_PER_KEY,
+            CLAP_PARAM_IS_AUTOMATABLE_PER_CHANNEL,
+        )
+    };
+
+    if flags & per_note_id != 0 {
+        Some((voice_idx as i32, -1, -1))
+    } else if flags & per_key != 0 {
+        Some((-1, voice_idx as i16, POLYPHONIC_KEYS[voice_idx]))
+    } else if flags & per_channel != 0 {
+        Some((-1, voice_idx as i16, -1))
+    } else {
+        None
+    }
+}
+
+/// The test for `PluginTestCase::ParamRescanStateLoad`. Saves the plugin's default state and a
+/// state with random parameter values, and then alternates between loading those two states, both
+/// while the plugin is deactivated and while it is activated. Loading a state is the most common
+/// reason for plugins to call `clap_host_params::rescan()`. The host checks whether the changes to
+/// the plugin's parameters are covered by the rescan flags, and whether `CLAP_PARAM_RESCAN_ALL` is
+/// only used while the plugin is deactivated.
+pub fn test_param_rescan_state_load(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let state = match plugin.get_extension::<State>() {
+        Some(state) => state,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    State::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let initial_param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    let default_state = state
+        .save()
+        .context("Error while saving the plugin's default state")?;
+
+    let random_param_set_events: Vec<_> = ParamFuzzer::new(&initial_param_infos)
+        .randomize_params_at(&mut prng, 0)
+        .collect();
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?.run_once(
+        ProcessConfig::default(),
+        move |process_data| {
+            *process_data.input_events.events.lock() = random_param_set_events;
+
+            Ok(())
+        },
+    )?;
+    let random_state = state
+        .save()
+        .context("Error while saving the plugin's state after randomizing its parameters")?;
+    host.handle_callbacks_until_idle();
+
+    let process_config = ProcessConfig::default();
+    for load_idx in 0..RESCAN_NUM_STATE_LOADS {
+        let activated = load_idx % 2 == 1;
+        let (state_name, state_bytes) = if load_idx % 4 < 2 {
+            ("default", &default_state)
+        } else {
+            ("randomized", &random_state)
+        };
+
+        if activated {
+            plugin.activate(process_config.sample_rate, 1, BUFFER_SIZE)?;
+        }
+        state.load(state_bytes).with_context(|| {
+            format!(
+                "Error while loading the {state_name} state {}",
+                if activated {
+                    "while the plugin was activated"
+                } else {
+                    "while the plugin was deactivated"
+                }
+            )
+        })?;
+        host.handle_callbacks_once();
+        if activated {
+            plugin.deactivate();
+            host.handle_callbacks_once();
+        }
+    }
+
+    // The host verifies every individual rescan, but the plugin may also change its parameters
+    // without telling the host about it
+    let param_rescans = plugin.state.param_rescans.lock().clone();
+    let final_param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters after loading the states")?;
+    if !final_param_infos.keys().eq(initial_param_infos.keys())
+        && !param_rescans
+            .iter()
+            .any(|rescan| rescan.flags & CLAP_PARAM_RESCAN_ALL != 0)
+    {
+        anyhow::bail!(
+            "The plugin's parameters' stable IDs changed from {:?} to {:?} after loading states, \
+             but the plugin never called 'clap_host_params::rescan()' with \
+             'CLAP_PARAM_RESCAN_ALL'.",
+            initial_param_infos.keys().collect::<Vec<_>>(),
+            final_param_infos.keys().collect::<Vec<_>>()
+        );
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    if param_rescans.is_empty() {
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin did not call 'clap_host_params::rescan()' during {} state loads.",
+                RESCAN_NUM_STATE_LOADS
+            )),
+        })
+    } else {
+        // The host has already verified that these changes are allowed by the rescans' flags
+        let changes: Vec<String> = param_rescans
+            .iter()
+            .enumerate()
+            .filter(|(_, rescan)| !rescan.changes.is_empty())
+            .map(|(rescan_idx, rescan)| {
+                format!(
+                    " Rescan {} with flags {:#b}: {}.",
+                    rescan_idx + 1,
+                    rescan.flags,
+                    rescan
+                        .changes
+                        .iter()
+                        .map(|change| change.description.as_str())
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                )
+            })
+            .collect();
+
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin called 'clap_host_params::rescan()' {} times during {} state loads.{}",
+                param_rescans.len(),
+                RESCAN_NUM_STATE_LOADS,
+                if changes.is_empty() {
+                    String::from(" None of these calls changed the parameters' information.")
+                } else {
+                    changes.concat()
+                }
+            )),
+        })
+    }
+}
+
+/// The test for `PluginTestCase::ParamFlushDeactivated`. Sets random parameter values by calling
+/// `clap_plugin_params::flush()` on the main thread while the plugin is deactivated, which the
+/// specification explicitly allows. The plugin's parameter values should change as a result, and
+/// they should remain the same after the plugin has been activated and has processed a couple of
+/// blocks without any parameter events.
+pub fn test_param_flush_deactivated(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_settable_params(&param_infos) {
+        return Ok(status);
+    }
+    let initial_param_values: BTreeMap<clap_id, f64> = param_infos
+        .keys()
+        .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
+        .collect::<Result<BTreeMap<clap_id, f64>>>()?;
+
+    let input_events = EventQueue::new_input();
+    *input_events.events.lock() = ParamFuzzer::new(&param_infos)
+        .randomize_params_at(&mut prng, 0)
+        .collect();
+    let output_events = EventQueue::new_output();
+    params.flush(&input_events, &output_events);
+    host.handle_callbacks_once();
+
+    // The plugin may round or otherwise quantize the values, so we can't compare them to the
+    // events directly
+    let flushed_param_values: BTreeMap<clap_id, f64> = param_infos
+        .keys()
+        .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
+        .collect::<Result<BTreeMap<clap_id, f64>>>()?;
+    if flushed_param_values == initial_param_values {
+        anyhow::bail!(
+            "'clap_plugin_params::flush()' has been called on the main thread with random \
+             parameter values while the plugin was deactivated, but the plugin's reported \
+             parameter values have not changed."
+        )
+    }
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(
+            FLUSH_DEACTIVATED_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+        )?;
+
+    let actual_param_values: BTreeMap<clap_id, f64> = param_infos
+        .keys()
+        .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
+        .collect::<Result<BTreeMap<clap_id, f64>>>()?;
+    if actual_param_values != flushed_param_values {
+        anyhow::bail!(
+            "The plugin's parameter values changed after activating the plugin and processing \
+             {FLUSH_DEACTIVATED_NUM_BLOCKS} blocks without any parameter events. The values had \
+             been set by calling 'clap_plugin_params::flush()' on the main thread while the \
+             plugin was deactivated. The mismatching values are {}.",
+            format_mismatching_values(actual_param_values, &flushed_param_values, &param_infos)
+        );
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ParamOutputEvents`.
+pub fn test_param_output_events(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    // Plugins with only read-only parameters, like meters, can still output parameter events, so
+    // this test doesn't require any settable parameters
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_params(&param_infos) {
+        return Ok(status);
+    }
+    let param_fuzzer = ParamFuzzer::new(&param_infos);
+
+    // A flush is a single atomic operation, so every gesture that was started during the flush also
+    // needs to be ended during it
+    let known_params: BTreeMap<clap_id, KnownParam> = param_infos
+        .iter()
+        .map(|(param_id, param)| (*param_id, param.into()))
+        .collect();
+    let input_events = EventQueue::new_input();
+    *input_events.events.lock() = param_fuzzer.randomize_params_at(&mut prng, 0).collect();
+    let output_events = EventQueue::new_output();
+    params.flush(&input_events, &output_events);
+    host.handle_callbacks_once();
+
+    let mut open_gestures = BTreeSet::new();
+    check_output_param_events(
+        &output_events.events.lock(),
+        Some(&known_params),
+        &mut open_gestures,
+    )
+    .context("Invalid output events after calling 'clap_plugin_params::flush()'")?;
+    if !open_gestures.is_empty() {
+        anyhow::bail!(
+            "The plugin began parameter gestures during a call to 'clap_plugin_params::flush()' \
+             without ending them for the parameters with IDs {open_gestures:?}."
+        );
+    }
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(
+            OUTPUT_EVENTS_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                *process_data.input_events.events.lock() =
+                    param_fuzzer.randomize_params_at(&mut prng, 0).collect();
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+        )?;
+    if !stats.unfinished_param_gestures.is_empty() {
+        anyhow::bail!(
+            "The plugin began parameter gestures during audio processing without ending them for \
+             the parameters with IDs {:?}.",
+            stats.unfinished_param_gestures
+        );
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ParamAutomationBlockEdges`.
+pub fn test_param_automation_block_edges(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    if let Some(status) = skip_without_settable_params(&param_infos) {
+        return Ok(status);
+    }
+    let ramped_params: Vec<(clap_id, &Param)> = param_infos
+        .iter()
+        .filter(|(_, param_info)| !param_info.readonly() && !param_info.hidden())
+        .map(|(param_id, param_info)| (*param_id, param_info))
+        .collect();
+
+    // Every parameter is ramped from the start to the end of its range. Every other block contains
+    // parameter changes on both its first and its last sample, and the remaining blocks only
+    // contain a parameter change on their first sample. The parameter values are checked after
+    // processing all blocks, so this is done twice: once where the final block's last change is
+    // on its last sample, and once where it's on its first sample. That way a parameter change
+    // that gets dropped at either edge of a block results in the plugin holding a different value.
+    const NUM_RAMP_POINTS: usize = BLOCK_EDGES_NUM_BLOCKS + BLOCK_EDGES_NUM_BLOCKS / 2;
+    let ramp_value = |param_info: &Param, point: usize| {
+        let t = point as f64 / (NUM_RAMP_POINTS - 1) as f64;
+        let value =
+            param_info.range.start() + (param_info.range.end() - param_info.range.start()) * t;
+        if param_info.stepped() {
+            value.round()
+        } else {
+            value
+        }
+    };
+
+    let (mut input_buffers, mut output_buffers) =
+        audio_ports_config.create_buffers(BLOCK_EDGES_BLOCK_SIZE);
+    let mut status = TestStatus::Success { details: None };
+    for final_event_time in [BLOCK_EDGES_BLOCK_SIZE as u32 - 1, 0] {
+        let last_block_has_two_events = final_event_time != 0;
+        let mut ramp_point = 0;
+        let mut expected_values = Vec::with_capacity(ramped_params.len());
+        let mut block_events: Vec<Vec<Event>> = (0..BLOCK_EDGES_NUM_BLOCKS)
+            .map(|block_idx| {
+                let is_last_block = block_idx == BLOCK_EDGES_NUM_BLOCKS - 1;
+                let has_two_events = ((BLOCK_EDGES_NUM_BLOCKS - 1 - block_idx) % 2 == 0)
+                    == last_block_has_two_events;
+                let event_times: &[u32] = if has_two_events {
+                    &[0, BLOCK_EDGES_BLOCK_SIZE as u32 - 1]
+                } else {
+                    &[0]
+                };
+
+                let mut events = Vec::with_capacity(event_times.len() * ramped_params.len());
+                for time in event_times {
+                    for (param_id, param_info) in &ramped_params {
+                        let value = ramp_value(param_info, ramp_point);
+                        events.push(Event::ParamValue(clap_event_param_value {
+                            header: clap_event_header {
+                                size: std::mem::size_of::<clap_event_param_value>() as u32,
+                                time: *time,
+                                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                                type_: CLAP_EVENT_PARAM_VALUE,
+                                flags: 0,
+                            },
+                            param_id: *param_id,
+                            cookie: param_info.cookie,
+                            note_id: -1,
+                            port_index: -1,
+                            channel: -1,
+                            key: -1,
+                            value,
+                        }));
+                        if is_last_block {
+                            expected_values.push(value);
+                        }
+                    }
+                    if is_last_block && *time != final_event_time {
+                        expected_values.clear();
+                    }
+                    ramp_point += 1;
+                }
+
+                events
+            })
+            .collect();
+        block_events.reverse();
+
+        let stats =
+            ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+                .run(
+                    BLOCK_EDGES_NUM_BLOCKS,
+                    ProcessConfig::default(),
+                    |process_data| {
+                        *process_data.input_events.events.lock() =
+                            block_events.pop().unwrap_or_default();
+
+                        Ok(())
+                    },
+                )?;
+        host.callback_error_check()
+            .context("An error occured during a host callback")?;
+
+        // The last value sent for every parameter should now be the parameter's current value
+        for ((param_id, param_info), expected_value) in ramped_params.iter().zip(&expected_values) {
+            let actual_value = params.get(*param_id)?;
+            let tolerance = (param_info.range.end() - param_info.range.start()) * ECHO_TOLERANCE;
+            if (actual_value - expected_value).abs() > tolerance {
+                return Ok(TestStatus::Failed {
+                    details: Some(format!(
+                        "After processing {BLOCK_EDGES_NUM_BLOCKS} {BLOCK_EDGES_BLOCK_SIZE}-sample \
+                         blocks, the last of which set parameter {param_id} ('{}') to {} at \
+                         sample {final_event_time}, the plugin reports a value of {} for that \
+                         parameter. The plugin may be dropping parameter changes on the {} \
+                         sample of a block.",
+                        param_info.name,
+                        util::format_f64(*expected_value),
+                        util::format_f64(actual_value),
+                        if final_event_time == 0 { "first" } else { "last" },
+                    )),
+                    causes: Vec::new(),
+                });
+            }
+        }
+
+        if let TestStatus::Warning { .. } = stats.status() {
+            status = stats.status();
+        }
+    }
+
+    Ok(status)
+}
+
+/// The test for `PluginTestCase::ParamInfoAudioThread`. This is a negative test that calls the main
+/// thread functions `clap_plugin_params::count()` and `clap_plugin_params::get_info()` from the
+/// audio thread while the plugin is activated. The plugin may answer or reject these calls, but it
+/// should not crash. If it does crash, then the test's result explains what the validator was
+/// doing at the time.
+pub fn test_param_info_audio_thread(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    if !util::negative_tests() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::RequiresFlag,
+            details: Some(String::from(
+                "This is a negative test, which only runs when the '--negative-tests' option is \
+                 passed to the validator.",
+            )),
+        });
+    }
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let num_params = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?
+        .len() as u32;
+    host.handle_callbacks_once();
+
+    plugin.activate(ProcessConfig::default().sample_rate, 1, BUFFER_SIZE)?;
+    let audio_thread_results = {
+        let _crash_context = ipc::crash_context(
+            "the validator was calling the main thread functions 'clap_plugin_params::count()' \
+             and 'clap_plugin_params::get_info()' from the audio thread",
+        );
+
+        plugin.on_audio_thread(|plugin| {
+            plugin.get_extension::<ParamsAudioThread>().map(|params| {
+                let audio_thread_num_params = params.count_from_audio_thread();
+                let num_answered = (0..num_params)
+                    .filter(|&param_index| params.get_info_from_audio_thread(param_index).is_some())
+                    .count();
+
+                (audio_thread_num_params, num_answered)
+            })
+        })
+    };
+    plugin.deactivate();
+    host.handle_callbacks_once();
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    match audio_thread_results {
+        Some((audio_thread_num_params, num_answered)) => Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin did not crash when its main thread functions were called from the \
+                 audio thread. 'clap_plugin_params::count()' returned {audio_thread_num_params} \
+                 ({num_params} on the main thread), and {num_answered} out of {num_params} calls \
+                 to 'clap_plugin_params::get_info()' succeeded."
+            )),
+        }),
+        None => Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin did not return its '{}' extension when it was queried from the audio \
+                 thread, so its main thread functions could not be called.",
+                Params::EXTENSION_ID.to_str().unwrap(),
+            )),
+        }),
+    }
+}
+
+/// Check whether the plugin output `CLAP_EVENT_PARAM_VALUE` events that contradict the parameter
+/// values the host sent during the same processing cycle. A host would record those events as
+/// automation and send them back to the plugin, creating a feedback loop. CLAP has no way to mark
+/// a value as being clamped, so the only accepted difference is the host's value clamped to the
+/// parameter's range.
+fn check_param_output_echo(process_data: &ProcessData, param_infos: &ParamInfo) -> Result<()> {
+    let host_values: BTreeMap<clap_id, f64> = process_data
+        .input_events
+        .events
+        .lock()
+        .iter()
+        .filter_map(|event| match event {
+            Event::ParamValue(event) => Some((event.param_id, event.value)),
+            _ => None,
+        })
+        .collect();
+
+    for event in process_data.output_events.events.lock().iter() {
+        let event = match event {
+            Event::ParamValue(event) if event.header.space_id == CLAP_CORE_EVENT_SPACE_ID => event,
+            _ => continue,
+        };
+        let (host_value, param_info) = match (
+            host_values.get(&event.param_id),
+            param_infos.get(&event.param_id),
+        ) {
+            (Some(host_value), Some(param_info)) => (*host_value, param_info),
+            _ => continue,
+        };
+
+        let expected_value = host_value.clamp(*param_info.range.start(), *param_info.range.end());
+        let tolerance = (param_info.range.end() - param_info.range.start()) * ECHO_TOLERANCE;
+        if (event.value - expected_value).abs() > tolerance {
+            anyhow::bail!(
+                "The host set parameter {} ('{}') to {}, but the plugin output a \
+                 'CLAP_EVENT_PARAM_VALUE' event for the same parameter with value {} during the \
+                 same processing cycle. This would cause automation feedback loops in hosts.",
+                event.param_id,
+                param_info.name,
+                util::format_f64(host_value),
+                util::format_f64(event.value),
+            );
+        }
+    }
+
+    Ok(())
+}
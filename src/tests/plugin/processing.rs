@@ -1,25 +1,282 @@
 //! Contains most of the boilerplate around testing audio processing.
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeInclusive;
 use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
+use clap_sys::events::{
+    clap_event_header, clap_event_midi, clap_event_midi2, clap_event_midi_sysex, clap_event_note,
+    clap_event_note_expression, clap_event_param_gesture, clap_event_param_mod,
+    clap_event_param_value, clap_event_transport, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_DONT_RECORD,
+    CLAP_EVENT_IS_LIVE, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI2, CLAP_EVENT_MIDI_SYSEX,
+    CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF,
+    CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END,
+    CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT,
+    CLAP_TRANSPORT_HAS_BEATS_TIMELINE, CLAP_TRANSPORT_HAS_SECONDS_TIMELINE,
+    CLAP_TRANSPORT_HAS_TEMPO, CLAP_TRANSPORT_HAS_TIME_SIGNATURE, CLAP_TRANSPORT_IS_LOOP_ACTIVE,
+    CLAP_TRANSPORT_IS_PLAYING,
+};
+use clap_sys::ext::note_ports::{
+    clap_note_dialect, CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI2,
+    CLAP_NOTE_DIALECT_MIDI_MPE,
+};
+use clap_sys::fixedpoint::{CLAP_BEATTIME_FACTOR, CLAP_SECTIME_FACTOR};
+use clap_sys::id::clap_id;
+use midi_consts::channel_event as midi;
+use rand::Rng;
+use rand_pcg::Pcg32;
 
+use crate::ipc;
 use crate::plugin::ext::audio_ports::{AudioPortConfig, AudioPorts};
-use crate::plugin::ext::note_ports::NotePorts;
+use crate::plugin::ext::note_ports::{NotePortConfig, NotePorts};
+use crate::plugin::ext::params::Params;
+use crate::plugin::ext::thread_pool::ThreadPool;
 use crate::plugin::ext::Extension;
-use crate::plugin::host::Host;
+use crate::plugin::host::{Host, HostConfig, InstanceState, KnownParam};
+use crate::plugin::instance::audio_thread::PluginAudioThread;
 use crate::plugin::instance::process::{
-    AudioBuffers, OutOfPlaceAudioBuffers, ProcessConfig, ProcessData,
+    AudioBuffers, Event, MidiSysexEvent, OutOfPlaceAudioBuffers, ProcessConfig, ProcessData,
+    MAX_SYSEX_SIZE,
 };
 use crate::plugin::instance::Plugin;
 use crate::plugin::library::PluginLibrary;
-use crate::tests::rng::{new_prng, NoteGenerator};
-use crate::tests::TestStatus;
+use crate::tests::rng::{
+    new_prng, random_sysex_message, DialectSelection, NoteGenerator, SYSEX_DATA_LEN_RANGE,
+};
+use crate::tests::{SkipReason, TestStatus};
+use crate::util;
+
+use super::note_effect::{OutputNoteTracker, MIDI_CC_ALL_NOTES_OFF};
+use super::tail::SILENCE_THRESHOLD;
+use super::{
+    EventRegistryVariant, NoteDialectPreferenceVariant, NoteDialectVariant, NotePanicVariant,
+    ProcessingVariant,
+};
 
 /// A helper to handle the boilerplate that comes with testing a plugin's audio processing behavior.
 pub struct ProcessingTest<'a> {
     plugin: &'a Plugin<'a>,
     audio_buffers: AudioBuffers<'a>,
+    /// If set, the host deactivates and reactivates the plugin every `n` processing cycles. See
+    /// [`with_host_restarts()`][Self::with_host_restarts()].
+    host_restart_interval: Option<usize>,
+    /// Determines the block sizes used for the process calls. See
+    /// [`with_block_size_mode()`][Self::with_block_size_mode()].
+    block_size_mode: BlockSizeMode,
+    /// A task that's run on the main thread while the audio thread is processing audio. See
+    /// [`with_main_thread_task()`][Self::with_main_thread_task()].
+    main_thread_task: Option<&'a mut (dyn FnMut() + 'a)>,
+}
+
+/// How a [`ProcessingTest`] chooses the number of samples processed in each process call. CLAP has
+/// no way for plugins to declare that they need fixed or power-of-two block sizes, so plugins must
+/// support every block size between the minimum and maximum frame counts passed to
+/// `clap_plugin::activate()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockSizeMode {
+    /// The plugin is activated with a minimum block size of one sample, and every process call
+    /// uses the maximum block size.
+    #[default]
+    Flexible,
+    /// The plugin is activated with the same minimum and maximum block size, and every process call
+    /// uses that block size.
+    Fixed,
+    /// The plugin is activated with a minimum block size of one sample, and every process call uses
+    /// a random block size between one sample and the maximum block size.
+    Variable,
+}
+
+/// Statistics about a [`ProcessingTest`] run. These are used to detect behavior that isn't outright
+/// wrong, but that would cause problems in real hosts.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingStats {
+    /// The number of processing cycles that were run.
+    pub num_cycles: usize,
+    /// The number of times the plugin was restarted because it called
+    /// `clap_host::request_restart()`. The validator always performs these restarts right after the
+    /// processing cycle the restart was requested in.
+    pub num_requested_restarts: usize,
+    /// The number of events output by the plugin that had flag bits set that are not defined by
+    /// the CLAP specification.
+    pub num_unknown_event_flags: usize,
+    /// All undefined flag bits set on the plugin's output events, combined.
+    pub unknown_event_flags: u32,
+    /// The number of events output by the plugin that were exact duplicates of another event
+    /// output for the same sample during the same processing cycle.
+    pub num_duplicate_output_events: usize,
+    /// The first duplicate output event, formatted using its `Debug` implementation.
+    pub first_duplicate_output_event: Option<String>,
+    /// The number of processing cycles where `clap_plugin::process()` took longer than the
+    /// duration of the processed block. This is only checked when the validator's
+    /// `--realtime-audio-thread` option is enabled.
+    pub num_deadline_misses: usize,
+    /// The deadline miss that exceeded its deadline by the largest factor, if any.
+    pub worst_deadline_miss: Option<DeadlineMiss>,
+    /// Whether the audio thread managed to enable realtime scheduling.
+    pub realtime_audio_thread: bool,
+    /// The IDs of the parameters the plugin output a `CLAP_EVENT_PARAM_GESTURE_BEGIN` event for
+    /// without a matching `CLAP_EVENT_PARAM_GESTURE_END` event before the run finished.
+    pub unfinished_param_gestures: Vec<clap_id>,
+}
+
+/// A processing cycle where the plugin took longer to process a block than a real host would
+/// allow. See [`ProcessingStats::num_deadline_misses`].
+#[derive(Debug, Clone, Copy)]
+pub struct DeadlineMiss {
+    /// How long the `clap_plugin::process()` call took.
+    pub process_duration: Duration,
+    /// The duration of the processed block, which is the deadline for the process call.
+    pub deadline: Duration,
+    /// The number of samples in the block.
+    pub block_size: usize,
+    /// The sample rate the block was processed at.
+    pub sample_rate: f64,
+}
+
+impl DeadlineMiss {
+    /// How many times longer the process call took than the deadline allowed.
+    fn overrun_ratio(&self) -> f64 {
+        self.process_duration.as_secs_f64() / self.deadline.as_secs_f64()
+    }
+}
+
+/// The event flags defined by the CLAP specification. Any other bits set in a
+/// `clap_event_header::flags` field are unknown.
+const KNOWN_EVENT_FLAGS: u32 = CLAP_EVENT_IS_LIVE | CLAP_EVENT_DONT_RECORD;
+
+/// The number of samples in the audio buffers used by most of the processing tests.
+const BUFFER_SIZE: usize = 512;
+
+/// The sample rates the plugin is reactivated with when the host restarts the plugin during a
+/// [`ProcessingTest`]. These are cycled through in order.
+const HOST_RESTART_SAMPLE_RATES: [f64; 4] = [48_000.0, 96_000.0, 22_050.0, 44_100.0];
+
+/// The number of processing cycles between host restarts in the `process-host-restarts` test.
+const HOST_RESTART_INTERVAL: usize = 4;
+
+/// The minimum and maximum block sizes the plugin is activated with in the
+/// `process-buffer-size-range` test. A single block at the maximum size is processed after every
+/// activation.
+const BUFFER_SIZE_RANGE_PROBES: [(usize, usize); 6] = [
+    (1, 32),
+    (32, 32),
+    (1, 512),
+    (512, 512),
+    (1, 16384),
+    (16384, 16384),
+];
+
+/// The number of samples past the maximum block size in the `process-buffer-size-range` test's
+/// buffers. The plugin may not write to these samples.
+const BUFFER_SIZE_RANGE_GUARD_SAMPLES: usize = 64;
+
+/// The sample rates the `process-config-sweep` test processes audio at. Every sample rate is
+/// combined with every buffer size from [`PROCESS_CONFIG_SWEEP_BUFFER_SIZES`].
+const PROCESS_CONFIG_SWEEP_SAMPLE_RATES: [f64; 4] = [22_050.0, 48_000.0, 96_000.0, 192_000.0];
+
+/// The maximum buffer sizes the plugin is activated with in the `process-config-sweep` test.
+const PROCESS_CONFIG_SWEEP_BUFFER_SIZES: [usize; 4] = [1, 17, 64, 4096];
+
+/// The number of processing cycles for every configuration in the `process-config-sweep` test.
+/// Every other cycle processes fewer samples than the maximum buffer size.
+const PROCESS_CONFIG_SWEEP_NUM_BLOCKS: usize = 4;
+
+/// The number of samples in the audio buffers used by the `process-tiny-blocks` test.
+const TINY_BLOCKS_BUFFER_SIZE: usize = 512;
+
+/// The frame counts for the process calls in the first part of the `process-tiny-blocks` test.
+/// The plugin is activated with a minimum block size of 1 and a maximum block size of
+/// [`TINY_BLOCKS_BUFFER_SIZE`].
+const TINY_BLOCK_SIZES: [usize; 10] = [1, 2, 3, TINY_BLOCKS_BUFFER_SIZE, 1, 1, 17, 1, 64, 1];
+
+/// The frame counts for the process calls in the second part of the `process-tiny-blocks` test.
+/// CLAP's activation range starts at one sample, but some hosts still call `clap_plugin::process()`
+/// with zero frames to send events without processing any audio.
+const ZERO_FRAME_BLOCK_SIZES: [usize; 8] = [0, TINY_BLOCKS_BUFFER_SIZE, 0, 0, 1, 0, 64, 0];
+
+/// The range for the number of garbage bytes appended to the events in the
+/// `process-note-oversized-events` test.
+const OVERSIZED_EVENT_EXTRA_BYTES: RangeInclusive<usize> = 1..=64;
+
+/// The number of blocks processed in the `process-note-sysex` test.
+const SYSEX_NUM_BLOCKS: usize = 8;
+/// The number of sysex messages sent to every MIDI input port per block in the `process-note-sysex`
+/// test. The first message in every block doesn't contain any data bytes.
+const SYSEX_EVENTS_PER_BLOCK: usize = 4;
+/// The number of data bytes in the long sysex message sent to every MIDI input port in the last
+/// block of the `process-note-sysex` test. Sample dumps and firmware updates can be this large.
+pub const SYSEX_LONG_MESSAGE_DATA_BYTES: usize = 4096;
+
+/// The number of notes played in the `process-note-end` test. Every note has a unique note ID and
+/// key.
+const NOTE_END_NUM_NOTES: usize = 8;
+/// The note-on and note-off events in the `process-note-end` test are spread out over this many
+/// blocks.
+const NOTE_END_SPREAD_BLOCKS: usize = 4;
+/// The notes in the `process-note-end` test are started in the first few blocks, and they are
+/// released starting from this block.
+const NOTE_END_NOTE_OFF_BLOCK: usize = 8;
+/// The number of blocks processed in the `process-note-end` test. The blocks after the last
+/// note-off event give the plugin time to finish the notes' release stages.
+const NOTE_END_NUM_BLOCKS: usize = 64;
+
+/// The number of notes started in the `process-note-panic-*` tests. None of these notes are
+/// released.
+pub const NOTE_PANIC_NUM_NOTES: usize = 32;
+/// The note-on events in the `process-note-panic-*` tests are spread out over this many blocks.
+const NOTE_PANIC_SPREAD_BLOCKS: usize = 4;
+/// The host sends the 'All Notes Off' messages in this block in the `process-note-panic-*` tests,
+/// or it resets the plugin right before processing this block.
+const NOTE_PANIC_BLOCK: usize = 8;
+/// The number of blocks processed in the `process-note-panic-*` tests.
+const NOTE_PANIC_NUM_BLOCKS: usize = 64;
+/// The plugin's output should be silent during this many blocks at the end of the
+/// `process-note-panic-*` tests.
+const NOTE_PANIC_SILENT_BLOCKS: usize = 8;
+
+/// The number of blocks processed in the `process-note-output-only` test. Plugins like arpeggiators
+/// may need a couple of blocks before they output their first notes.
+pub const NOTE_OUTPUT_ONLY_NUM_BLOCKS: usize = 20;
+
+/// The maximum number of transport events sent through the input event queue per block in the
+/// `process-transport-events` test.
+const TRANSPORT_EVENTS_PER_BLOCK: usize = 4;
+
+/// The changes to the transport the `process-transport-variations` test cycles through. One of
+/// these is applied before every processing cycle, on top of the changes made before it.
+const TRANSPORT_VARIATIONS: [TransportVariation; 7] = [
+    TransportVariation::TempoChange,
+    TransportVariation::LoopRegion,
+    TransportVariation::Stop,
+    TransportVariation::Start,
+    TransportVariation::NoBeatsTimeline,
+    TransportVariation::NoSecondsTimeline,
+    TransportVariation::BarOffsets,
+];
+/// The number of blocks processed in the `process-transport-variations` test. The test cycles
+/// through [`TRANSPORT_VARIATIONS`] four times.
+pub const TRANSPORT_VARIATION_NUM_BLOCKS: usize = TRANSPORT_VARIATIONS.len() * 4;
+
+/// A change to the transport information made in the `process-transport-variations` test.
+#[derive(Debug, Clone, Copy)]
+enum TransportVariation {
+    /// Jump to a random tempo, and have the tempo slowly drift during the block.
+    TempoChange,
+    /// Loop the bar containing the current song position.
+    LoopRegion,
+    /// Stop playback. The song position stays where it is.
+    Stop,
+    /// Start playback again and disable the loop region.
+    Start,
+    /// Remove the beats timeline, the tempo, and the time signature.
+    NoBeatsTimeline,
+    /// Restore the beats timeline, but remove the seconds timeline.
+    NoSecondsTimeline,
+    /// Restore all timelines, and move to a random bar number. This may be negative to simulate a
+    /// pre-roll.
+    BarOffsets,
 }
 
 impl<'a> ProcessingTest<'a> {
@@ -31,15 +288,77 @@ impl<'a> ProcessingTest<'a> {
         input_buffers: &'a mut [Vec<Vec<f32>>],
         output_buffers: &'a mut [Vec<Vec<f32>>],
     ) -> Result<Self> {
+        // CV input ports should receive control signals rather than white noise
+        let cv_inputs = match plugin.get_extension::<AudioPorts>() {
+            Some(audio_ports) => audio_ports
+                .config()
+                .context("Error while querying 'audio-ports' IO configuration")?
+                .cv_inputs(),
+            None => Vec::new(),
+        };
+
         Ok(Self {
             plugin,
-            audio_buffers: AudioBuffers::OutOfPlace(OutOfPlaceAudioBuffers::new(
-                input_buffers,
-                output_buffers,
-            )?),
+            audio_buffers: AudioBuffers::OutOfPlace(
+                OutOfPlaceAudioBuffers::new(input_buffers, output_buffers)?
+                    .with_cv_inputs(cv_inputs),
+            ),
+            host_restart_interval: None,
+            block_size_mode: BlockSizeMode::default(),
+            main_thread_task: None,
         })
     }
 
+    /// Construct a new processing test for one of the [`ProcessingVariant`]s used by the
+    /// parameterized processing tests.
+    pub fn new(
+        plugin: &'a Plugin<'a>,
+        variant: ProcessingVariant,
+        input_buffers: &'a mut [Vec<Vec<f32>>],
+        output_buffers: &'a mut [Vec<Vec<f32>>],
+    ) -> Result<Self> {
+        let block_size_mode = match variant {
+            ProcessingVariant::OutOfPlaceBasic => BlockSizeMode::Flexible,
+            ProcessingVariant::OutOfPlaceFixedBlockSize => BlockSizeMode::Fixed,
+            ProcessingVariant::OutOfPlaceVariableBlockSize => BlockSizeMode::Variable,
+        };
+
+        let test = Self::new_out_of_place(plugin, input_buffers, output_buffers)?;
+        Ok(test.with_block_size_mode(block_size_mode))
+    }
+
+    /// Change how the block sizes for the process calls in [`run()`][Self::run()] are chosen. See
+    /// [`BlockSizeMode`].
+    pub fn with_block_size_mode(mut self, block_size_mode: BlockSizeMode) -> Self {
+        self.block_size_mode = block_size_mode;
+        self
+    }
+
+    /// Have the host deactivate and reactivate the plugin after every `interval` processing cycles
+    /// in [`run()`][Self::run()]. Every time this happens the plugin is reactivated with a
+    /// different sample rate and maximum buffer size, and the following process calls use that
+    /// buffer size. This simulates a host changing its audio settings while the plugin is in use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_host_restarts(mut self, interval: usize) -> Self {
+        assert!(interval >= 1);
+
+        self.host_restart_interval = Some(interval);
+        self
+    }
+
+    /// Run `task` on the main thread right after the audio thread has been spawned for the first
+    /// time in [`run()`][Self::run()]. This can be used to call main thread functions while the
+    /// plugin is processing audio. Any synchronization between the task and the processing
+    /// closures needs to be done by the caller. Main-thread callbacks are handled after the task
+    /// returns. The task is only run once, even if the host restarts the plugin.
+    pub fn with_main_thread_task(mut self, task: &'a mut (dyn FnMut() + 'a)) -> Self {
+        self.main_thread_task = Some(task);
+        self
+    }
+
     /// Run the standard audio processing test for a still **deactivated** plugin. This calls the
     /// process function `num_iters` times, and checks the output for consistency each time.
     ///
@@ -47,33 +366,92 @@ impl<'a> ProcessingTest<'a> {
     /// modified for the next process cycle.
     ///
     /// Main-thread callbacks that were made to the plugin while the audio thread was active are
-    /// handled implicitly.
+    /// handled implicitly. The returned [`ProcessingStats`] can be used to check for excessive
+    /// restart requests.
     pub fn run<Preprocess>(
+        &'a mut self,
+        num_iters: usize,
+        process_config: ProcessConfig,
+        preprocess: Preprocess,
+    ) -> Result<ProcessingStats>
+    where
+        Preprocess: FnMut(&mut ProcessData) -> Result<()> + Send,
+    {
+        self.run_with_postprocess(num_iters, process_config, preprocess, |_, _| Ok(()))
+    }
+
+    /// The same as [`run()`][Self::run()], but with an additional `Postprocess` closure that is
+    /// called on the audio thread after each processing cycle. This can be used to perform
+    /// additional checks on the plugin's output before the output events are cleared, or to query
+    /// the plugin's audio thread extensions.
+    pub fn run_with_postprocess<Preprocess, Postprocess>(
         &'a mut self,
         num_iters: usize,
         process_config: ProcessConfig,
         mut preprocess: Preprocess,
-    ) -> Result<()>
+        mut postprocess: Postprocess,
+    ) -> Result<ProcessingStats>
     where
         Preprocess: FnMut(&mut ProcessData) -> Result<()> + Send,
+        Postprocess: FnMut(&PluginAudioThread, &ProcessData) -> Result<()> + Send,
     {
         self.plugin
             .state
             .requested_restart
             .store(false, Ordering::SeqCst);
 
+        // The events the plugin outputs are checked against its output note ports and its
+        // parameters. Gestures may span multiple processing cycles.
+        let note_port_config = self.note_port_config()?;
+        self.query_params();
+        let mut open_param_gestures = BTreeSet::new();
+
+        let host_restart_interval = self.host_restart_interval;
+        let block_size_mode = self.block_size_mode;
+        let mut block_size_prng: Option<Pcg32> =
+            (block_size_mode == BlockSizeMode::Variable).then(new_prng);
         let buffer_size = self.audio_buffers.len();
+        let mut main_thread_task = self.main_thread_task.take();
         let mut process_data = ProcessData::new(&mut self.audio_buffers, process_config);
 
+        // These are changed when the host restarts the plugin
+        let mut sample_rate = process_config.sample_rate;
+        let mut block_size = buffer_size;
+        let mut num_host_restarts = 0;
+        let mut num_requested_restarts = 0;
+        let mut num_unknown_event_flags = 0;
+        let mut unknown_event_flags = 0;
+        let mut num_duplicate_output_events = 0;
+        let mut first_duplicate_output_event = None;
+
+        // With the `--realtime-audio-thread` option every process call also needs to finish
+        // within the duration of the block it processes, like in a real host
+        let check_deadlines = util::realtime_audio_thread();
+        let mut num_deadline_misses = 0;
+        let mut worst_deadline_miss: Option<DeadlineMiss> = None;
+        let mut realtime_audio_thread = false;
+
         // If the plugin requests a restart in the middle of processing, then the plugin will be
         // stopped, deactivated, reactivated, and started again. Because of that, we need to keep
         // track of the number of processed iterations manually instead of using a for loop.
         let mut iters_done = 0;
         while iters_done < num_iters {
+            let min_block_size = match block_size_mode {
+                BlockSizeMode::Fixed => block_size,
+                BlockSizeMode::Flexible | BlockSizeMode::Variable => 1,
+            };
+            ipc::progress(format!(
+                "processing audio at {sample_rate} Hz with up to {block_size} samples per block, \
+                 starting at processing cycle {} out of {num_iters}",
+                iters_done + 1
+            ));
             self.plugin
-                .activate(process_config.sample_rate, 1, buffer_size)?;
+                .activate(sample_rate, min_block_size, block_size)?;
+            process_data.reconfigure(sample_rate, block_size);
 
-            self.plugin.on_audio_thread(|plugin| -> Result<()> {
+            let mut host_restart = false;
+            let process = |plugin: PluginAudioThread| -> Result<()> {
+                realtime_audio_thread = plugin.state().realtime_audio_thread.load(Ordering::SeqCst);
                 plugin.start_processing()?;
 
                 // This test can be repeated a couple of times
@@ -81,15 +459,45 @@ impl<'a> ProcessingTest<'a> {
                 'processing: while iters_done < num_iters {
                     iters_done += 1;
 
+                    if let Some(block_size_prng) = &mut block_size_prng {
+                        process_data
+                            .reconfigure(sample_rate, block_size_prng.gen_range(1..=block_size));
+                    }
                     preprocess(&mut process_data)?;
 
                     // We'll check that the plugin hasn't modified the input buffers after the
                     // test
                     let original_input_buffers = process_data.buffers.inputs_ref().to_owned();
 
-                    plugin
-                        .process(&mut process_data)
-                        .context("Error during audio processing")?;
+                    let process_start = Instant::now();
+                    let process_result = plugin.process(&mut process_data);
+                    let process_duration = process_start.elapsed();
+                    process_result.context("Error during audio processing")?;
+
+                    // Empty blocks don't have a deadline
+                    if check_deadlines && process_data.block_size() > 0 {
+                        let block_size = process_data.block_size();
+                        let deadline = Duration::from_secs_f64(block_size as f64 / sample_rate);
+                        if process_duration > deadline {
+                            let deadline_miss = DeadlineMiss {
+                                process_duration,
+                                deadline,
+                                block_size,
+                                sample_rate,
+                            };
+                            num_deadline_misses += 1;
+                            let is_worst_deadline_miss = match worst_deadline_miss {
+                                Some(worst_deadline_miss) => {
+                                    deadline_miss.overrun_ratio()
+                                        > worst_deadline_miss.overrun_ratio()
+                                }
+                                None => true,
+                            };
+                            if is_worst_deadline_miss {
+                                worst_deadline_miss = Some(deadline_miss);
+                            }
+                        }
+                    }
 
                     // When we add in-place processing this will need some slightly different checks
                     match process_data.buffers {
@@ -98,6 +506,15 @@ impl<'a> ProcessingTest<'a> {
                             &original_input_buffers,
                         ),
                     }
+                    .and_then(|()| check_output_note_events(&process_data, &note_port_config))
+                    .and_then(|()| {
+                        check_output_param_events(
+                            &process_data.output_events.events.lock(),
+                            plugin.state().known_params.lock().as_ref(),
+                            &mut open_param_gestures,
+                        )
+                    })
+                    .and_then(|()| postprocess(&plugin, &process_data))
                     .with_context(|| {
                         format!(
                             "Failed during processing cycle {} out of {}",
@@ -106,8 +523,24 @@ impl<'a> ProcessingTest<'a> {
                         )
                     })?;
 
+                    // Unknown flags aren't fatal, but they're reported as a warning afterwards
+                    for event in process_data.output_events.events.lock().iter() {
+                        let flags = event.header().flags & !KNOWN_EVENT_FLAGS;
+                        if flags != 0 {
+                            num_unknown_event_flags += 1;
+                            unknown_event_flags |= flags;
+                        }
+                    }
+
+                    // The same goes for duplicate events, which usually indicate that the plugin
+                    // processed something twice
+                    for duplicate_event in duplicate_output_events(&process_data) {
+                        num_duplicate_output_events += 1;
+                        first_duplicate_output_event.get_or_insert(duplicate_event);
+                    }
+
                     process_data.clear_events();
-                    process_data.advance_transport(buffer_size as u32);
+                    process_data.advance_transport(process_data.block_size() as u32);
 
                     // Restart processing as necesasry
                     if plugin
@@ -122,6 +555,19 @@ impl<'a> ProcessingTest<'a> {
                             iters_done + 1,
                             num_iters
                         );
+                        num_requested_restarts += 1;
+                        break 'processing;
+                    }
+
+                    if matches!(host_restart_interval, Some(interval) if iters_done % interval == 0)
+                    {
+                        log::trace!(
+                            "Restarting the plugin during processing cycle {} out of {} to change \
+                             the host's audio settings",
+                            iters_done + 1,
+                            num_iters
+                        );
+                        host_restart = true;
                         break 'processing;
                     }
                 }
@@ -129,15 +575,43 @@ impl<'a> ProcessingTest<'a> {
                 plugin.stop_processing();
 
                 Ok(())
-            })?;
+            };
+            let (process_result, ()) =
+                self.plugin
+                    .on_audio_thread_with_main_thread_task(process, || {
+                        if let Some(main_thread_task) = main_thread_task.take() {
+                            main_thread_task();
+                        }
+                    });
+            process_result?;
 
             self.plugin.deactivate();
+
+            // Every host restart uses a different sample rate, and the buffer size cycles between
+            // a half, a quarter, an eighth, and the entirety of the original buffer size
+            if host_restart {
+                sample_rate =
+                    HOST_RESTART_SAMPLE_RATES[num_host_restarts % HOST_RESTART_SAMPLE_RATES.len()];
+                num_host_restarts += 1;
+                block_size = (buffer_size >> (num_host_restarts % 4)).max(1);
+            }
         }
 
         // Handle callbacks the plugin may have made during deactivate
         self.plugin.host().handle_callbacks_once();
 
-        Ok(())
+        Ok(ProcessingStats {
+            num_cycles: iters_done,
+            num_requested_restarts,
+            num_unknown_event_flags,
+            unknown_event_flags,
+            num_duplicate_output_events,
+            first_duplicate_output_event,
+            num_deadline_misses,
+            worst_deadline_miss,
+            realtime_audio_thread,
+            unfinished_param_gestures: open_param_gestures.into_iter().collect(),
+        })
     }
 
     /// Run the standard audio processing test for a still **deactivated** plugin. This is identical
@@ -159,6 +633,12 @@ impl<'a> ProcessingTest<'a> {
             .requested_restart
             .store(false, Ordering::SeqCst);
 
+        // The events the plugin outputs are checked against its output note ports and its
+        // parameters. Gestures may span multiple processing cycles.
+        let note_port_config = self.note_port_config()?;
+        self.query_params();
+        let mut open_param_gestures = BTreeSet::new();
+
         let buffer_size = self.audio_buffers.len();
         let mut process_data = ProcessData::new(&mut self.audio_buffers, process_config);
 
@@ -184,6 +664,14 @@ impl<'a> ProcessingTest<'a> {
                     check_out_of_place_output_consistency(&process_data, &original_input_buffers)
                 }
             }
+            .and_then(|()| check_output_note_events(&process_data, &note_port_config))
+            .and_then(|()| {
+                check_output_param_events(
+                    &process_data.output_events.events.lock(),
+                    plugin.state().known_params.lock().as_ref(),
+                    &mut open_param_gestures,
+                )
+            })
             .context("Failed during processing")?;
 
             process_data.clear_events();
@@ -201,16 +689,204 @@ impl<'a> ProcessingTest<'a> {
 
         Ok(())
     }
+
+    /// Get the plugin's note port configuration. If the plugin does not support the `note-ports`
+    /// extension, then this returns an empty configuration.
+    fn note_port_config(&self) -> Result<NotePortConfig> {
+        match self.plugin.get_extension::<NotePorts>() {
+            Some(note_ports) => note_ports
+                .config()
+                .context("Error while querying 'note-ports' IO configuration"),
+            None => Ok(NotePortConfig::default()),
+        }
+    }
+
+    /// Query the plugin's parameters, if it supports the `params` extension. This stores them in
+    /// the instance's state, where they're also kept up to date when the plugin rescans its
+    /// parameters. Errors are ignored here since those are already reported by the parameter
+    /// tests, and the output parameter events are only checked if the parameters could be queried.
+    fn query_params(&self) {
+        if let Some(params) = self.plugin.get_extension::<Params>() {
+            let _ = params.info();
+        }
+    }
+}
+
+impl TransportVariation {
+    /// Apply this variation to the transport information for the next processing cycle.
+    fn apply(self, transport: &mut clap_event_transport, prng: &mut Pcg32) {
+        const ALL_TIMELINES: u32 = CLAP_TRANSPORT_HAS_TEMPO
+            | CLAP_TRANSPORT_HAS_BEATS_TIMELINE
+            | CLAP_TRANSPORT_HAS_SECONDS_TIMELINE
+            | CLAP_TRANSPORT_HAS_TIME_SIGNATURE;
+
+        // The tempo only drifts during the block right after a tempo change
+        transport.tempo_inc = 0.0;
+        match self {
+            TransportVariation::TempoChange => {
+                transport.flags |= CLAP_TRANSPORT_HAS_TEMPO;
+                transport.tempo = prng.gen_range(20.0..=300.0);
+                transport.tempo_inc = prng.gen_range(-1e-4..=1e-4);
+            }
+            TransportVariation::LoopRegion => {
+                let bar_length_beats = transport.tsig_num.max(1) as i64 * CLAP_BEATTIME_FACTOR;
+                let bar_length_seconds = (transport.tsig_num.max(1) as f64 * 60.0 / transport.tempo
+                    * CLAP_SECTIME_FACTOR as f64)
+                    .round() as i64;
+
+                transport.flags |= CLAP_TRANSPORT_IS_LOOP_ACTIVE;
+                transport.loop_start_beats = transport.song_pos_beats
+                    - transport.song_pos_beats.rem_euclid(bar_length_beats);
+                transport.loop_end_beats = transport.loop_start_beats + bar_length_beats;
+                transport.loop_start_seconds = transport.song_pos_seconds;
+                transport.loop_end_seconds = transport.loop_start_seconds + bar_length_seconds;
+            }
+            TransportVariation::Stop => transport.flags &= !CLAP_TRANSPORT_IS_PLAYING,
+            TransportVariation::Start => {
+                transport.flags |= CLAP_TRANSPORT_IS_PLAYING;
+                transport.flags &= !CLAP_TRANSPORT_IS_LOOP_ACTIVE;
+            }
+            TransportVariation::NoBeatsTimeline => {
+                transport.flags &= !(CLAP_TRANSPORT_HAS_TEMPO
+                    | CLAP_TRANSPORT_HAS_BEATS_TIMELINE
+                    | CLAP_TRANSPORT_HAS_TIME_SIGNATURE);
+            }
+            TransportVariation::NoSecondsTimeline => {
+                transport.flags |= ALL_TIMELINES;
+                transport.flags &= !CLAP_TRANSPORT_HAS_SECONDS_TIMELINE;
+            }
+            TransportVariation::BarOffsets => {
+                let bar_length_beats = transport.tsig_num.max(1) as i64 * CLAP_BEATTIME_FACTOR;
+
+                transport.flags |= ALL_TIMELINES;
+                transport.bar_number = prng.gen_range(-2..=64);
+                transport.bar_start = transport.song_pos_beats
+                    - transport.song_pos_beats.rem_euclid(bar_length_beats);
+            }
+        }
+    }
+}
+
+impl ProcessingStats {
+    /// Check whether the plugin requested restarts so often that a real host would spend most of
+    /// its time restarting the plugin, whether it output events with unknown flags, whether it
+    /// output duplicate events, and whether it missed any processing deadlines. Returns a warning
+    /// status if any of those were the case, and a success status otherwise.
+    pub fn status(&self) -> TestStatus {
+        let mut warnings = Vec::new();
+        if self.num_requested_restarts >= 2 && self.num_requested_restarts * 2 >= self.num_cycles {
+            warnings.push(format!(
+                "The plugin called 'clap_host::request_restart()' {} times during {} processing \
+                 cycles, or once every {:.1} cycles on average. Requesting a restart this often \
+                 would prevent a host from processing any audio.",
+                self.num_requested_restarts,
+                self.num_cycles,
+                self.num_cycles as f64 / self.num_requested_restarts as f64
+            ));
+        }
+        if self.num_unknown_event_flags > 0 {
+            warnings.push(format!(
+                "The plugin output {} {} with undefined flag bits set ({:#x}). Only \
+                 'CLAP_EVENT_IS_LIVE' and 'CLAP_EVENT_DONT_RECORD' are defined.",
+                self.num_unknown_event_flags,
+                if self.num_unknown_event_flags == 1 {
+                    "event"
+                } else {
+                    "events"
+                },
+                self.unknown_event_flags
+            ));
+        }
+        if let Some(first_duplicate_output_event) = &self.first_duplicate_output_event {
+            warnings.push(format!(
+                "The plugin output {} {} that exactly matched another event output for the same \
+                 sample, which may indicate that the plugin processed something twice. The first \
+                 duplicate event was {first_duplicate_output_event}.",
+                self.num_duplicate_output_events,
+                if self.num_duplicate_output_events == 1 {
+                    "event"
+                } else {
+                    "events"
+                },
+            ));
+        }
+        if let Some(worst_deadline_miss) = &self.worst_deadline_miss {
+            warnings.push(format!(
+                "The plugin took longer to process a block than the block's duration in {} out of \
+                 {} processing cycles. The slowest of these took {:.3} ms to process {} samples at \
+                 {} Hz, while a real host would only have {:.3} ms.{}",
+                self.num_deadline_misses,
+                self.num_cycles,
+                worst_deadline_miss.process_duration.as_secs_f64() * 1000.0,
+                worst_deadline_miss.block_size,
+                worst_deadline_miss.sample_rate,
+                worst_deadline_miss.deadline.as_secs_f64() * 1000.0,
+                if self.realtime_audio_thread {
+                    ""
+                } else {
+                    " The audio thread could not enable realtime scheduling, so the system's \
+                     scheduler may also have caused this."
+                }
+            ));
+        }
+
+        if !self.unfinished_param_gestures.is_empty() {
+            warnings.push(format!(
+                "The plugin began parameter gestures that were never ended for the parameters \
+                 with IDs {:?}.",
+                self.unfinished_param_gestures
+            ));
+        }
+
+        if warnings.is_empty() {
+            TestStatus::Success { details: None }
+        } else {
+            TestStatus::Warning {
+                details: Some(warnings.join(" ")),
+            }
+        }
+    }
+}
+
+/// The test for `PluginTestCase::ProcessAudio`. If the plugin fails the variable block size
+/// variant, then the test is repeated with a fixed block size. If the plugin does work correctly
+/// with fixed block sizes, then the failure is reported as the plugin silently requiring fixed
+/// block sizes.
+pub fn test_process_audio(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    variant: ProcessingVariant,
+) -> Result<TestStatus> {
+    let result = process_audio(library, plugin_id, variant);
+    match (variant, result) {
+        (ProcessingVariant::OutOfPlaceVariableBlockSize, Err(err)) => {
+            match process_audio(
+                library,
+                plugin_id,
+                ProcessingVariant::OutOfPlaceFixedBlockSize,
+            ) {
+                Ok(TestStatus::Success { .. } | TestStatus::Warning { .. }) => Err(err.context(
+                    "The plugin processes audio correctly with a fixed block size, but not with \
+                     variable block sizes. CLAP plugins cannot declare that they require fixed \
+                     block sizes, so they must support every block size between the minimum and \
+                     maximum frame counts passed to 'clap_plugin::activate()'",
+                )),
+                _ => Err(err),
+            }
+        }
+        (_, result) => result,
+    }
 }
 
-/// The test for `ProcessingTest::ProcessAudioOutOfPlaceBasic`.
-pub fn test_process_audio_out_of_place_basic(
+/// The actual processing for [`test_process_audio()`].
+fn process_audio(
     library: &PluginLibrary,
     plugin_id: &str,
+    variant: ProcessingVariant,
 ) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -222,6 +898,7 @@ pub fn test_process_audio_out_of_place_basic(
             .context("Error while querying 'audio-ports' IO configuration")?,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
                     AudioPorts::EXTENSION_ID.to_str().unwrap(),
@@ -235,104 +912,76 @@ pub fn test_process_audio_out_of_place_basic(
     host.handle_callbacks_once();
 
     let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
-    ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?.run(
-        5,
-        ProcessConfig::default(),
-        |process_data| {
+    let stats = ProcessingTest::new(&plugin, variant, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
             process_data.buffers.randomize(&mut prng);
 
             Ok(())
-        },
-    )?;
+        })?;
 
     // The `Host` contains built-in thread safety checks
     host.callback_error_check()
         .context("An error occured during a host callback")?;
-    Ok(TestStatus::Success { details: None })
+    Ok(stats.status())
 }
 
-/// The test for `ProcessingTest::ProcessNoteOutOfPlaceBasic`. This test is very similar to
-/// `ProcessAudioOutOfPlaceBasic`, but it requires the `note-ports` extension, sends notes and/or
-/// MIDI to the plugin, and doesn't require the `audio-ports` extension.
-pub fn test_process_note_out_of_place_basic(
-    library: &PluginLibrary,
-    plugin_id: &str,
-) -> Result<TestStatus> {
+/// The test for `PluginTestCase::ProcessHostRestarts`. This is the same as `ProcessAudio`, but the
+/// host periodically reactivates the plugin with a different sample rate and buffer size.
+pub fn test_process_host_restarts(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
     plugin.init().context("Error during initialization")?;
 
-    // You can have note/MIDI-only plugins, so not having any audio ports is perfectly fine here
     let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
         Some(audio_ports) => audio_ports
             .config()
             .context("Error while querying 'audio-ports' IO configuration")?,
-        None => AudioPortConfig::default(),
-    };
-    let note_ports_config = match plugin.get_extension::<NotePorts>() {
-        Some(note_ports) => note_ports
-            .config()
-            .context("Error while querying 'note-ports' IO configuration")?,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
-                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
                 )),
             })
         }
     };
-    if note_ports_config.inputs.is_empty() {
-        return Ok(TestStatus::Skipped {
-            details: Some(format!(
-                "The plugin implements the '{}' extension but it does not have any input note \
-                 ports.",
-                NotePorts::EXTENSION_ID.to_str().unwrap()
-            )),
-        });
-    }
     host.handle_callbacks_once();
 
-    // We'll fill the input event queue with (consistent) random CLAP note and/or MIDI
-    // events depending on what's supported by the plugin supports
-    let mut note_event_rng = NoteGenerator::new(note_ports_config);
-
-    const BUFFER_SIZE: usize = 512;
-    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
-    ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?.run(
-        5,
-        ProcessConfig::default(),
-        |process_data| {
-            note_event_rng.fill_event_queue(
-                &mut prng,
-                &process_data.input_events,
-                BUFFER_SIZE as u32,
-            )?;
-            process_data.buffers.randomize(&mut prng);
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .with_host_restarts(HOST_RESTART_INTERVAL)
+        .run(
+            HOST_RESTART_INTERVAL * (HOST_RESTART_SAMPLE_RATES.len() + 1),
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.randomize(&mut prng);
 
-            Ok(())
-        },
-    )?;
+                Ok(())
+            },
+        )?;
 
     host.callback_error_check()
         .context("An error occured during a host callback")?;
-    Ok(TestStatus::Success { details: None })
+    Ok(stats.status())
 }
 
-/// The test for `ProcessingTest::ProcessNoteInconsistent`. This is the same test as
-/// `ProcessAudioOutOfPlaceBasic`, but without requiring matched note on/off pairs and similar
-/// invariants
-pub fn test_process_note_inconsistent(
+/// The test for `PluginTestCase::ProcessBufferSizeRange`. Activates the plugin with the minimum
+/// and maximum block sizes from [`BUFFER_SIZE_RANGE_PROBES`], and processes a single block at the
+/// maximum size after every activation. The buffers are slightly larger than the maximum block
+/// size, and the plugin may not write past the end of the block or output events for samples
+/// outside of it.
+pub fn test_process_buffer_size_range(
     library: &PluginLibrary,
     plugin_id: &str,
 ) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -342,113 +991,2046 @@ pub fn test_process_note_inconsistent(
         Some(audio_ports) => audio_ports
             .config()
             .context("Error while querying 'audio-ports' IO configuration")?,
-        None => AudioPortConfig::default(),
-    };
-    let note_port_config = match plugin.get_extension::<NotePorts>() {
-        Some(note_ports) => note_ports
-            .config()
-            .context("Error while querying 'note-ports' IO configuration")?,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
-                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
                 )),
             })
         }
     };
-    if note_port_config.inputs.is_empty() {
-        return Ok(TestStatus::Skipped {
-            details: Some(format!(
-                "The plugin implements the '{}' extension but it does not have any input note \
-                 ports.",
-                NotePorts::EXTENSION_ID.to_str().unwrap()
-            )),
-        });
-    }
+    let note_port_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => NotePortConfig::default(),
+    };
     host.handle_callbacks_once();
 
-    // This RNG (Random Note Generator) allows generates mismatching events
-    let mut note_event_rng = NoteGenerator::new(note_port_config).with_inconsistent_events();
+    let process_config = ProcessConfig::default();
+    for (min_block_size, max_block_size) in BUFFER_SIZE_RANGE_PROBES {
+        let (mut input_buffers, mut output_buffers) =
+            audio_ports_config.create_buffers(max_block_size + BUFFER_SIZE_RANGE_GUARD_SAMPLES);
+        let mut audio_buffers = AudioBuffers::OutOfPlace(
+            OutOfPlaceAudioBuffers::new(&mut input_buffers, &mut output_buffers)?
+                .with_cv_inputs(audio_ports_config.cv_inputs()),
+        );
+        let mut process_data = ProcessData::new(&mut audio_buffers, process_config);
+        process_data.reconfigure(process_config.sample_rate, max_block_size);
 
-    // TODO: Use in-place processing for this test
-    const BUFFER_SIZE: usize = 512;
-    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
-    ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?.run(
-        5,
-        ProcessConfig::default(),
-        |process_data| {
-            note_event_rng.fill_event_queue(
-                &mut prng,
-                &process_data.input_events,
-                BUFFER_SIZE as u32,
-            )?;
-            process_data.buffers.randomize(&mut prng);
+        // The random output samples past the end of the block act as canaries
+        process_data.buffers.randomize(&mut prng);
+        let original_guard_samples =
+            output_guard_samples(process_data.buffers.outputs_ref(), max_block_size);
+
+        plugin
+            .activate(process_config.sample_rate, min_block_size, max_block_size)
+            .with_context(|| {
+                format!(
+                    "Could not activate the plugin with a minimum block size of {min_block_size} \
+                     and a maximum block size of {max_block_size}"
+                )
+            })?;
+        plugin.on_audio_thread(|plugin| -> Result<()> {
+            plugin.start_processing()?;
+            plugin.process(&mut process_data).with_context(|| {
+                format!(
+                    "Error while processing {max_block_size} samples after activating the plugin \
+                     with a minimum block size of {min_block_size} and a maximum block size of \
+                     {max_block_size}"
+                )
+            })?;
+            plugin.stop_processing();
 
             Ok(())
-        },
-    )?;
+        })?;
+        plugin.deactivate();
+        host.handle_callbacks_once();
+
+        let guard_samples =
+            output_guard_samples(process_data.buffers.outputs_ref(), max_block_size);
+        if guard_samples != original_guard_samples {
+            anyhow::bail!(
+                "The plugin was activated with a maximum block size of {max_block_size} and \
+                 processed a block of {max_block_size} samples, but it wrote to the output buffers \
+                 past the end of that block."
+            );
+        }
+        for event in process_data.output_events.events.lock().iter() {
+            let time = event.header().time;
+            if time as usize >= max_block_size {
+                anyhow::bail!(
+                    "The plugin output an event for sample {time} while processing a block of \
+                     {max_block_size} samples after being activated with a maximum block size of \
+                     {max_block_size}. Event: {event:?}"
+                );
+            }
+        }
+        check_output_note_events(&process_data, &note_port_config).with_context(|| {
+            format!(
+                "Failed while processing {max_block_size} samples after activating the plugin \
+                 with a minimum block size of {min_block_size} and a maximum block size of \
+                 {max_block_size}"
+            )
+        })?;
+    }
 
     host.callback_error_check()
         .context("An error occured during a host callback")?;
+
     Ok(TestStatus::Success { details: None })
 }
 
-/// The process for consistency. This verifies that the output buffer doesn't contain any NaN,
-/// infinite, or denormal values, that the input buffers have not been modified by the plugin, and
-/// that the output event queue is monotonically ordered.
-fn check_out_of_place_output_consistency(
-    process_data: &ProcessData,
-    original_input_buffers: &[Vec<Vec<f32>>],
-) -> Result<()> {
-    // The input buffer must not be overwritten during out of place processing, and the outputs
-    // should not contain any non-finite or denormal values
-    let num_samples = process_data.buffers.len() as u32;
-    let input_buffers = process_data.buffers.inputs_ref();
-    let output_buffers = process_data.buffers.outputs_ref();
-    if input_buffers != original_input_buffers {
-        anyhow::bail!(
-            "The plugin has overwritten the input buffers during out-of-place processing."
-        );
-    }
-    for (port_idx, channel_slices) in output_buffers.iter().enumerate() {
-        for (channel_idx, channel_slice) in channel_slices.iter().enumerate() {
-            for (sample_idx, sample) in channel_slice.iter().enumerate() {
-                if !sample.is_finite() {
-                    anyhow::bail!(
-                        "The sample written to output port {port_idx}, channel {channel_idx}, and \
-                         sample index {sample_idx} is {sample:?}."
-                    );
-                } else if sample.is_subnormal() {
-                    anyhow::bail!(
-                        "The sample written to output port {port_idx}, channel {channel_idx}, and \
-                         sample index {sample_idx} is subnormal ({sample:?})."
-                    );
-                }
-            }
-        }
-    }
+/// The test for `PluginTestCase::ProcessConfigSweep`. Processes random audio at every combination
+/// of [`PROCESS_CONFIG_SWEEP_SAMPLE_RATES`] and [`PROCESS_CONFIG_SWEEP_BUFFER_SIZES`]. The plugin
+/// is activated with the buffer size as its maximum block size, and every other block only
+/// processes half of that to make sure the plugin doesn't assume that every block is as large as
+/// the maximum block size.
+pub fn test_process_config_sweep(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
 
-    // If the plugin output any events, then they should be in a monotonically increasing order
-    let mut last_event_time = 0;
-    for event in process_data.output_events.events.lock().iter() {
-        let event_time = event.header().time;
-        if event_time < last_event_time {
-            anyhow::bail!(
-                "The plugin output an event for sample {event_time} after it had previously \
-                 output an event for sample {last_event_time}."
-            )
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    let mut warnings = Vec::new();
+    for sample_rate in PROCESS_CONFIG_SWEEP_SAMPLE_RATES {
+        for buffer_size in PROCESS_CONFIG_SWEEP_BUFFER_SIZES {
+            let process_config = ProcessConfig {
+                sample_rate,
+                ..ProcessConfig::default()
+            };
+
+            let (mut input_buffers, mut output_buffers) =
+                audio_ports_config.create_buffers(buffer_size);
+            // The plugin is always activated with `buffer_size` as its maximum block size, but
+            // every other block processes fewer samples than that
+            let mut iteration = 0;
+            let preprocess = |process_data: &mut ProcessData| -> Result<()> {
+                let block_size = if iteration % 2 == 0 {
+                    buffer_size
+                } else {
+                    (buffer_size / 2).max(1)
+                };
+                process_data.reconfigure(sample_rate, block_size);
+                process_data.buffers.randomize(&mut prng);
+                iteration += 1;
+
+                Ok(())
+            };
+
+            let stats =
+                ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+                    .run(PROCESS_CONFIG_SWEEP_NUM_BLOCKS, process_config, preprocess)
+                    .with_context(|| {
+                        format!(
+                            "Failed while processing audio at a sample rate of {sample_rate} Hz \
+                             with a maximum buffer size of {buffer_size} samples"
+                        )
+                    })?;
+
+            if let TestStatus::Warning {
+                details: Some(details),
+            } = stats.status()
+            {
+                warnings.push(format!(
+                    "At a sample rate of {sample_rate} Hz with a maximum buffer size of \
+                     {buffer_size} samples: {details}"
+                ));
+            }
+        }
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    if warnings.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join("\n")),
+        })
+    }
+}
+
+/// The test for `PluginTestCase::ProcessTinyBlocks`. Processes random audio and, if the plugin
+/// accepts note events, random notes in blocks of the sizes from [`TINY_BLOCK_SIZES`]. Errors
+/// during this part of the test are failures. Afterwards the same is done for
+/// [`ZERO_FRAME_BLOCK_SIZES`], which also contains blocks without any samples. Since those are
+/// technically outside of the range the plugin was activated with, errors during that part only
+/// result in a warning.
+pub fn test_process_tiny_blocks(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => NotePortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    // Notes are only sent to plugins that can receive them
+    let mut note_event_rng =
+        (!note_ports_config.inputs.is_empty()).then(|| NoteGenerator::new(note_ports_config));
+
+    let stats = process_block_size_sequence(
+        &plugin,
+        &audio_ports_config,
+        &mut prng,
+        note_event_rng.as_mut(),
+        &TINY_BLOCK_SIZES,
+    )
+    .context("Failed while processing blocks of only a couple samples")?;
+    let status = stats.status();
+
+    let zero_frame_result = process_block_size_sequence(
+        &plugin,
+        &audio_ports_config,
+        &mut prng,
+        note_event_rng.as_mut(),
+        &ZERO_FRAME_BLOCK_SIZES,
+    );
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    match zero_frame_result {
+        Ok(_) => Ok(status),
+        Err(err) => Ok(TestStatus::Warning {
+            details: Some(format!(
+                "The plugin handles small blocks correctly, but failed while processing a \
+                 sequence of blocks containing blocks with zero samples. CLAP's activation range \
+                 starts at one sample, but some hosts call 'clap_plugin::process()' with zero \
+                 frames to send events without processing any audio: {err:#}"
+            )),
+        }),
+    }
+}
+
+/// Process random audio and notes with a block size from `block_sizes` for every processing
+/// cycle. Used in [`test_process_tiny_blocks()`].
+fn process_block_size_sequence(
+    plugin: &Plugin,
+    audio_ports_config: &AudioPortConfig,
+    prng: &mut Pcg32,
+    mut note_event_rng: Option<&mut NoteGenerator>,
+    block_sizes: &[usize],
+) -> Result<ProcessingStats> {
+    let process_config = ProcessConfig::default();
+    let (mut input_buffers, mut output_buffers) =
+        audio_ports_config.create_buffers(TINY_BLOCKS_BUFFER_SIZE);
+
+    let mut block_sizes_iter = block_sizes.iter().copied();
+    let preprocess = |process_data: &mut ProcessData| -> Result<()> {
+        let block_size = block_sizes_iter.next().unwrap_or(TINY_BLOCKS_BUFFER_SIZE);
+        process_data.reconfigure(process_config.sample_rate, block_size);
+        // Note events can only be sent for blocks that contain at least one sample
+        if let Some(note_event_rng) = note_event_rng.as_deref_mut().filter(|_| block_size > 0) {
+            note_event_rng.fill_event_queue(prng, &process_data.input_events, block_size as u32)?;
+        }
+        process_data.buffers.randomize(prng);
+
+        Ok(())
+    };
+
+    ProcessingTest::new_out_of_place(plugin, &mut input_buffers, &mut output_buffers)?.run(
+        block_sizes.len(),
+        process_config,
+        preprocess,
+    )
+}
+
+/// The test for `PluginTestCase::ProcessTransportEvents`. Some hosts send transport updates
+/// through the input event queue in the middle of a block in addition to the
+/// `clap_process::transport` pointer. This sends a couple of those events with every block. The
+/// regular output consistency checks make sure the plugin's output events remain monotonic.
+pub fn test_process_transport_events(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    // Transport events can be sent to any plugin, even if it doesn't have any audio ports
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            let num_events = prng.gen_range(1..=TRANSPORT_EVENTS_PER_BLOCK);
+            let mut times: Vec<u32> = (0..num_events)
+                .map(|_| prng.gen_range(0..BUFFER_SIZE as u32))
+                .collect();
+            times.sort_unstable();
+
+            let transport_events: Vec<Event> = times
+                .into_iter()
+                .map(|time| process_data.transport_event(time))
+                .collect();
+            process_data
+                .input_events
+                .events
+                .lock()
+                .extend(transport_events);
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessTransportVariations`. Processes random audio while
+/// changing the transport information before every block. See [`TRANSPORT_VARIATIONS`].
+pub fn test_process_transport_variations(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    let mut variations = TRANSPORT_VARIATIONS.iter().cycle();
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(
+            TRANSPORT_VARIATION_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                let variation = variations.next().unwrap();
+                variation.apply(process_data.transport_info_mut(), &mut prng);
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessThreadPool`. This is the same as `ProcessAudio`, but it
+/// requires the plugin to implement the `thread-pool` extension. Any tasks the plugin requests
+/// through `clap_host_thread_pool::request_exec()` are run on the host's worker pool. The host
+/// checks that this function is only called from the audio thread, and never from within
+/// `clap_plugin_thread_pool::exec()`.
+pub fn test_process_thread_pool(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    if plugin.get_extension::<ThreadPool>().is_none() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::MissingExtension,
+            details: Some(format!(
+                "The plugin does not implement the '{}' extension.",
+                ThreadPool::EXTENSION_ID.to_str().unwrap(),
+            )),
+        });
+    }
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let num_requests = plugin.state.num_thread_pool_requests.load(Ordering::SeqCst);
+    let num_tasks = plugin.state.num_thread_pool_tasks.load(Ordering::SeqCst);
+    match stats.status() {
+        TestStatus::Success { .. } if num_requests == 0 => Ok(TestStatus::Success {
+            details: Some(String::from(
+                "The plugin never called 'clap_host_thread_pool::request_exec()' while \
+                 processing audio.",
+            )),
+        }),
+        TestStatus::Success { .. } => Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin called 'clap_host_thread_pool::request_exec()' {num_requests} times \
+                 for a total of {num_tasks} tasks.",
+            )),
+        }),
+        status => Ok(status),
+    }
+}
+
+/// The test for `PluginTestCase::ProcessStartBeforeActivate`. This is a negative test that calls
+/// `clap_plugin::start_processing()` before the plugin has been activated. The plugin should
+/// reject this call without crashing, and it should still be able to process audio normally
+/// afterwards. Accepting the call results in a warning.
+pub fn test_process_start_before_activate(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    if !util::negative_tests() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::RequiresFlag,
+            details: Some(String::from(
+                "This is a negative test, which only runs when the '--negative-tests' option is \
+                 passed to the validator.",
+            )),
+        });
+    }
+
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    let accepted = {
+        let _crash_context = ipc::crash_context(
+            "the validator was calling 'clap_plugin::start_processing()' before the plugin was \
+             activated",
+        );
+
+        plugin.start_processing_while_deactivated()
+    };
+    if accepted {
+        // The plugin may now be in an inconsistent state, so it isn't used any further
+        return Ok(TestStatus::Warning {
+            details: Some(String::from(
+                "'clap_plugin::start_processing()' returned true even though the plugin had not \
+                 been activated yet. The plugin should reject this call.",
+            )),
+        });
+    }
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    // After rejecting the call, the plugin should still work as usual
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    match stats.status() {
+        TestStatus::Success { .. } => Ok(TestStatus::Success {
+            details: Some(String::from(
+                "The plugin rejected the call to 'clap_plugin::start_processing()' before it was \
+                 activated, and it processed audio normally afterwards.",
+            )),
+        }),
+        status => Ok(status),
+    }
+}
+
+/// The test for `PluginTestCase::ProcessNote`. This test is very similar to `ProcessAudio`, but it
+/// requires the `note-ports` extension, sends notes and/or MIDI to the plugin, and doesn't require
+/// the `audio-ports` extension.
+pub fn test_process_note(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    variant: ProcessingVariant,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    // You can have note/MIDI-only plugins, so not having any audio ports is perfectly fine here
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if note_ports_config.inputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(format!(
+                "The plugin implements the '{}' extension but it does not have any input note \
+                 ports.",
+                NotePorts::EXTENSION_ID.to_str().unwrap()
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    // We'll fill the input event queue with (consistent) random CLAP note and/or MIDI
+    // events depending on what's supported by the plugin supports
+    let mut note_event_rng = NoteGenerator::new(note_ports_config);
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new(&plugin, variant, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            // The block size may change between process calls with some processing variants
+            note_event_rng.fill_event_queue(
+                &mut prng,
+                &process_data.input_events,
+                process_data.block_size() as u32,
+            )?;
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessNoteOversizedEvents`. This is the same test as
+/// `ProcessNote`, but every event's `header.size` field is larger than the event's struct and the
+/// extra bytes contain garbage. This simulates extended events from a future CLAP version. The
+/// plugin should use the known prefix of the event or skip it entirely.
+pub fn test_process_note_oversized_events(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if note_ports_config.inputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(format!(
+                "The plugin implements the '{}' extension but it does not have any input note \
+                 ports.",
+                NotePorts::EXTENSION_ID.to_str().unwrap()
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    let mut note_event_rng = NoteGenerator::new(note_ports_config);
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            note_event_rng.fill_event_queue(
+                &mut prng,
+                &process_data.input_events,
+                BUFFER_SIZE as u32,
+            )?;
+            for event in process_data.input_events.events.lock().iter_mut() {
+                let extra_bytes = prng.gen_range(OVERSIZED_EVENT_EXTRA_BYTES);
+                *event = event.oversized(extra_bytes);
+            }
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessNoteSysex`. Sends only MIDI system exclusive messages to
+/// every input note port that supports MIDI. These range from empty messages to a long message in
+/// the last block. The plugin doesn't need to do anything with the messages, but it should not
+/// crash, and any sysex events it outputs are validated like in the other processing tests.
+pub fn test_process_note_sysex(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let midi_note_port_indices: Vec<u16> = note_ports_config
+        .inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, note_port)| {
+            note_port
+                .supported_dialects
+                .contains(&CLAP_NOTE_DIALECT_MIDI)
+        })
+        .map(|(note_port_idx, _)| note_port_idx as u16)
+        .collect();
+    if midi_note_port_indices.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(String::from(
+                "The plugin does not have any input note ports that support MIDI.",
+            )),
+        });
+    }
+
+    let mut block_idx = 0;
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(SYSEX_NUM_BLOCKS, ProcessConfig::default(), |process_data| {
+            let mut events = Vec::new();
+            for &note_port_idx in &midi_note_port_indices {
+                for event_idx in 0..SYSEX_EVENTS_PER_BLOCK {
+                    let num_data_bytes = if event_idx == 0 {
+                        0
+                    } else if block_idx == SYSEX_NUM_BLOCKS - 1
+                        && event_idx == SYSEX_EVENTS_PER_BLOCK - 1
+                    {
+                        SYSEX_LONG_MESSAGE_DATA_BYTES
+                    } else {
+                        prng.gen_range(SYSEX_DATA_LEN_RANGE)
+                    };
+
+                    events.push(Event::MidiSysex(MidiSysexEvent::new(
+                        clap_event_header {
+                            size: 0,
+                            time: prng.gen_range(0..process_data.block_size() as u32),
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_MIDI_SYSEX,
+                            flags: 0,
+                        },
+                        note_port_idx,
+                        random_sysex_message(&mut prng, num_data_bytes),
+                    )));
+                }
+            }
+            events.sort_by_key(|event| event.header().time);
+
+            *process_data.input_events.events.lock() = events;
+            process_data.buffers.randomize(&mut prng);
+            block_idx += 1;
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// A note played in the `process-note-end` and `process-note-panic-*` tests. The note starts in
+/// block `on_block` and it is released in block `off_block`.
+#[derive(Debug, Clone, Copy)]
+struct ScheduledNote {
+    on_block: usize,
+    off_block: usize,
+    channel: i16,
+    key: i16,
+    note_id: i32,
+}
+
+/// The test for `PluginTestCase::ProcessNoteEnd`. Plays and releases a number of notes using CLAP
+/// note events, and keeps track of the notes that are still playing, keyed by their port, channel,
+/// key, and note ID. Every `CLAP_EVENT_NOTE_END` event the plugin outputs, as well as note-off
+/// events it passes through, must refer to a note that is playing. Plugins that don't track their
+/// voices don't need to output note end events at all, but if they do then every note should be
+/// ended eventually.
+pub fn test_process_note_end(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let clap_note_port_idx = note_ports_config.inputs.iter().position(|note_port| {
+        note_port
+            .supported_dialects
+            .contains(&CLAP_NOTE_DIALECT_CLAP)
+    });
+    let note_port_idx = match clap_note_port_idx {
+        Some(note_port_idx) => note_port_idx as i16,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::NoNotePorts,
+                details: Some(String::from(
+                    "The plugin does not have any input note ports that support CLAP note \
+                     events.",
+                )),
+            })
+        }
+    };
+
+    // The notes are spread out over the first couple of blocks and they all use different keys, so
+    // the only way to match up a note end event is by using the note's exact port, channel, key,
+    // and note ID
+    let scheduled_notes: Vec<ScheduledNote> = (0..NOTE_END_NUM_NOTES)
+        .map(|note_idx| ScheduledNote {
+            on_block: note_idx % NOTE_END_SPREAD_BLOCKS,
+            off_block: NOTE_END_NOTE_OFF_BLOCK + (note_idx % NOTE_END_SPREAD_BLOCKS),
+            channel: prng.gen_range(0..16),
+            key: 48 + (note_idx as i16 * 5),
+            note_id: note_idx as i32,
+        })
+        .collect();
+
+    let mut preprocess_block_idx = 0;
+    let preprocess = |process_data: &mut ProcessData| -> Result<()> {
+        let mut events = Vec::new();
+        for note in &scheduled_notes {
+            let event_type = if note.on_block == preprocess_block_idx {
+                CLAP_EVENT_NOTE_ON
+            } else if note.off_block == preprocess_block_idx {
+                CLAP_EVENT_NOTE_OFF
+            } else {
+                continue;
+            };
+
+            events.push(Event::Note(clap_event_note {
+                header: clap_event_header {
+                    size: std::mem::size_of::<clap_event_note>() as u32,
+                    time: prng.gen_range(0..process_data.block_size() as u32),
+                    space_id: CLAP_CORE_EVENT_SPACE_ID,
+                    type_: event_type,
+                    flags: 0,
+                },
+                note_id: note.note_id,
+                port_index: note_port_idx,
+                channel: note.channel,
+                key: note.key,
+                velocity: 0.8,
+            }));
+        }
+        events.sort_by_key(|event| event.header().time);
+
+        process_data.input_events.events.lock().extend(events);
+        process_data.buffers.randomize(&mut prng);
+        preprocess_block_idx += 1;
+
+        Ok(())
+    };
+
+    // These are `(port_index, channel, key, note_id)` tuples. Notes the plugin outputs itself, for
+    // instance when passing through note events, are tracked separately.
+    let mut playing_notes: BTreeSet<(i16, i16, i16, i32)> = BTreeSet::new();
+    let mut output_notes: BTreeSet<(i16, i16, i16, i32)> = BTreeSet::new();
+    let mut num_note_end_events = 0;
+    let mut postprocess_block_idx = 0;
+    let postprocess = |_: &PluginAudioThread, process_data: &ProcessData| -> Result<()> {
+        // Notes started in this block may already be ended in the same block
+        for note in &scheduled_notes {
+            if note.on_block == postprocess_block_idx {
+                playing_notes.insert((note_port_idx, note.channel, note.key, note.note_id));
+            }
+        }
+        postprocess_block_idx += 1;
+
+        for event in process_data.output_events.events.lock().iter() {
+            let note_event = match event {
+                Event::Note(note_event) => note_event,
+                _ => continue,
+            };
+            let note = (
+                note_event.port_index,
+                note_event.channel,
+                note_event.key,
+                note_event.note_id,
+            );
+            let event_name = match note_event.header.type_ {
+                CLAP_EVENT_NOTE_ON => {
+                    output_notes.insert(note);
+                    continue;
+                }
+                CLAP_EVENT_NOTE_OFF => "CLAP_EVENT_NOTE_OFF",
+                CLAP_EVENT_NOTE_END => {
+                    num_note_end_events += 1;
+                    "CLAP_EVENT_NOTE_END"
+                }
+                _ => continue,
+            };
+
+            // Passed through note-off events may end both the plugin's own output note and the
+            // note sent by the host
+            let was_output_note = output_notes.remove(&note);
+            let was_playing_note = playing_notes.remove(&note);
+            if was_output_note || was_playing_note {
+                continue;
+            }
+
+            let (port_index, channel, key, note_id) = note;
+            let same_note_id = playing_notes
+                .iter()
+                .find(|(_, _, _, playing_note_id)| *playing_note_id == note_id);
+            let same_key = playing_notes.iter().find(
+                |(playing_port_index, playing_channel, playing_key, _)| {
+                    (*playing_port_index, *playing_channel, *playing_key)
+                        == (port_index, channel, key)
+                },
+            );
+            match (same_note_id, same_key) {
+                (Some((playing_port_index, playing_channel, playing_key, _)), _) => {
+                    anyhow::bail!(
+                        "The plugin output a '{event_name}' event for the note with ID \
+                         {note_id} on port {port_index}, channel {channel}, and key {key}, but \
+                         that note was played on port {playing_port_index}, channel \
+                         {playing_channel}, and key {playing_key}."
+                    )
+                }
+                (None, Some((_, _, _, playing_note_id))) => anyhow::bail!(
+                    "The plugin output a '{event_name}' event with note ID {note_id} for the \
+                     note on port {port_index}, channel {channel}, and key {key}, but that note \
+                     was played with note ID {playing_note_id}."
+                ),
+                (None, None) => anyhow::bail!(
+                    "The plugin output a '{event_name}' event for a note that is not playing \
+                     (port {port_index}, channel {channel}, key {key}, note ID {note_id})."
+                ),
+            }
+        }
+
+        Ok(())
+    };
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            NOTE_END_NUM_BLOCKS,
+            ProcessConfig::default(),
+            preprocess,
+            postprocess,
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let unended_note_ids: Vec<i32> = playing_notes
+        .iter()
+        .map(|(_, _, _, note_id)| *note_id)
+        .collect();
+    if let Some(warning) = check_unended_notes(
+        num_note_end_events > 0,
+        &unended_note_ids,
+        NOTE_END_NUM_BLOCKS - NOTE_END_NOTE_OFF_BLOCK - NOTE_END_SPREAD_BLOCKS,
+        BUFFER_SIZE,
+    ) {
+        return Ok(warning);
+    }
+
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessNotePanic`. Starts a lot of notes without ever releasing
+/// them, and then either sends MIDI 'All Notes Off' messages on every channel or resets the plugin.
+/// Afterwards none of the plugin's voices should still be playing. If the plugin outputs note end
+/// events then every note should have been ended, any notes the plugin outputs itself should have
+/// been released, and the plugin's audio output should decay to silence. Only the output notes
+/// result in a failure, since voices with long release times may still be fading out.
+pub fn test_process_note_panic(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    variant: NotePanicVariant,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    // 'All Notes Off' is a MIDI message, so that variant needs a port that supports MIDI. Resets
+    // work with any dialect, and CLAP note events are preferred there since they have note IDs.
+    let find_note_port = |dialect: clap_note_dialect| {
+        note_ports_config
+            .inputs
+            .iter()
+            .position(|note_port| note_port.supported_dialects.contains(&dialect))
+    };
+    let (note_port_idx, use_midi) = match variant {
+        NotePanicVariant::AllNotesOff => (find_note_port(CLAP_NOTE_DIALECT_MIDI), true),
+        NotePanicVariant::Reset => match find_note_port(CLAP_NOTE_DIALECT_CLAP) {
+            Some(note_port_idx) => (Some(note_port_idx), false),
+            None => (find_note_port(CLAP_NOTE_DIALECT_MIDI), true),
+        },
+    };
+    let note_port_idx = match note_port_idx {
+        Some(note_port_idx) => note_port_idx as i16,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::NoNotePorts,
+                details: Some(format!(
+                    "The plugin does not have any input note ports that support {}.",
+                    match variant {
+                        NotePanicVariant::AllNotesOff => "MIDI",
+                        NotePanicVariant::Reset => "CLAP note events or MIDI",
+                    }
+                )),
+            })
+        }
+    };
+    let panic_description = match variant {
+        NotePanicVariant::AllNotesOff => "the host sent 'All Notes Off' messages",
+        NotePanicVariant::Reset => "the host called 'clap_plugin::reset()'",
+    };
+
+    // Every note uses a different key, so MIDI notes can also be matched up without note IDs. The
+    // notes are never released.
+    let scheduled_notes: Vec<ScheduledNote> = (0..NOTE_PANIC_NUM_NOTES)
+        .map(|note_idx| ScheduledNote {
+            on_block: note_idx % NOTE_PANIC_SPREAD_BLOCKS,
+            off_block: NOTE_PANIC_NUM_BLOCKS,
+            channel: (note_idx % 16) as i16,
+            key: 36 + note_idx as i16,
+            note_id: if use_midi { -1 } else { note_idx as i32 },
+        })
+        .collect();
+
+    let mut preprocess_block_idx = 0;
+    let preprocess = |process_data: &mut ProcessData| -> Result<()> {
+        let mut events = Vec::new();
+        for note in &scheduled_notes {
+            if note.on_block != preprocess_block_idx {
+                continue;
+            }
+
+            let time = prng.gen_range(0..process_data.block_size() as u32);
+            events.push(if use_midi {
+                Event::Midi(clap_event_midi {
+                    header: clap_event_header {
+                        size: std::mem::size_of::<clap_event_midi>() as u32,
+                        time,
+                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                        type_: CLAP_EVENT_MIDI,
+                        flags: 0,
+                    },
+                    port_index: note_port_idx as u16,
+                    data: [midi::NOTE_ON | note.channel as u8, note.key as u8, 100],
+                })
+            } else {
+                Event::Note(clap_event_note {
+                    header: clap_event_header {
+                        size: std::mem::size_of::<clap_event_note>() as u32,
+                        time,
+                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                        type_: CLAP_EVENT_NOTE_ON,
+                        flags: 0,
+                    },
+                    note_id: note.note_id,
+                    port_index: note_port_idx,
+                    channel: note.channel,
+                    key: note.key,
+                    velocity: 0.8,
+                })
+            });
+        }
+        if variant == NotePanicVariant::AllNotesOff && preprocess_block_idx == NOTE_PANIC_BLOCK {
+            events.extend((0..16).map(|channel| {
+                Event::Midi(clap_event_midi {
+                    header: clap_event_header {
+                        size: std::mem::size_of::<clap_event_midi>() as u32,
+                        time: 0,
+                        space_id: CLAP_CORE_EVENT_SPACE_ID,
+                        type_: CLAP_EVENT_MIDI,
+                        flags: 0,
+                    },
+                    port_index: note_port_idx as u16,
+                    data: [midi::CONTROL_CHANGE | channel, MIDI_CC_ALL_NOTES_OFF, 0],
+                })
+            }));
+        }
+        events.sort_by_key(|event| event.header().time);
+
+        process_data.input_events.events.lock().extend(events);
+        // With silent inputs, any sound the plugin outputs comes from its voices
+        process_data.buffers.fill_inputs_silence(false);
+        preprocess_block_idx += 1;
+
+        Ok(())
+    };
+
+    // These are `(port_index, channel, key, note_id)` tuples like in the `process-note-end` test.
+    // The notes the plugin outputs itself are tracked separately.
+    let mut playing_notes: BTreeSet<(i16, i16, i16, i32)> = BTreeSet::new();
+    let mut output_notes = OutputNoteTracker::default();
+    let mut num_note_end_events = 0;
+    let mut last_audible_block = None;
+    let mut postprocess_block_idx = 0;
+    let postprocess = |plugin: &PluginAudioThread, process_data: &ProcessData| -> Result<()> {
+        for note in &scheduled_notes {
+            if note.on_block == postprocess_block_idx {
+                playing_notes.insert((note_port_idx, note.channel, note.key, note.note_id));
+            }
+        }
+
+        output_notes.process_block(process_data);
+
+        // Note events may use -1 as a wildcard for any of these fields
+        let matches = |pattern: i32, value: i32| pattern == -1 || pattern == value;
+        for event in process_data.output_events.events.lock().iter() {
+            if let Event::Note(event) = event {
+                if event.header.type_ == CLAP_EVENT_NOTE_END {
+                    num_note_end_events += 1;
+                    playing_notes.retain(|&(port_index, channel, key, note_id)| {
+                        !(matches(event.port_index as i32, port_index as i32)
+                            && matches(event.channel as i32, channel as i32)
+                            && matches(event.key as i32, key as i32)
+                            && matches(event.note_id, note_id))
+                    })
+                }
+            }
+        }
+
+        let is_audible = process_data
+            .buffers
+            .outputs_ref()
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|sample| sample.abs() >= SILENCE_THRESHOLD);
+        if is_audible {
+            last_audible_block = Some(postprocess_block_idx);
+        }
+
+        postprocess_block_idx += 1;
+        if variant == NotePanicVariant::Reset && postprocess_block_idx == NOTE_PANIC_BLOCK {
+            plugin.reset();
+        }
+
+        Ok(())
+    };
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            NOTE_PANIC_NUM_BLOCKS,
+            ProcessConfig::default(),
+            preprocess,
+            postprocess,
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let num_blocks_after_panic = NOTE_PANIC_NUM_BLOCKS - NOTE_PANIC_BLOCK;
+    if !output_notes.is_empty() {
+        anyhow::bail!(
+            "The plugin was still outputting these notes {num_blocks_after_panic} blocks after \
+             {panic_description}: {}.",
+            output_notes.describe_playing_notes()
+        );
+    }
+
+    // Voices may take a while to release, so these only result in warnings
+    let mut warnings = Vec::new();
+    if num_note_end_events > 0 && !playing_notes.is_empty() {
+        warnings.push(format!(
+            "The plugin output 'CLAP_EVENT_NOTE_END' events, but {} out of the \
+             {NOTE_PANIC_NUM_NOTES} notes were not ended {num_blocks_after_panic} blocks of \
+             {BUFFER_SIZE} samples after {panic_description}.",
+            playing_notes.len()
+        ));
+    }
+    let silent_from_block = NOTE_PANIC_NUM_BLOCKS - NOTE_PANIC_SILENT_BLOCKS;
+    if last_audible_block.map_or(false, |block| block >= silent_from_block) {
+        warnings.push(format!(
+            "The plugin's output was still audible {num_blocks_after_panic} blocks of \
+             {BUFFER_SIZE} samples after {panic_description}. Some of its voices may be stuck."
+        ));
+    }
+    if let TestStatus::Warning {
+        details: Some(details),
+    } = stats.status()
+    {
+        warnings.push(details);
+    }
+
+    if warnings.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join(" ")),
+        })
+    }
+}
+
+/// The test for `PluginTestCase::ProcessNoteInconsistent`. This is the same test as
+/// `ProcessNote`, but without requiring matched note on/off pairs and similar
+/// invariants
+pub fn test_process_note_inconsistent(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_port_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if note_port_config.inputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(format!(
+                "The plugin implements the '{}' extension but it does not have any input note \
+                 ports.",
+                NotePorts::EXTENSION_ID.to_str().unwrap()
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    // This RNG (Random Note Generator) allows generates mismatching events
+    let mut note_event_rng = NoteGenerator::new(note_port_config).with_inconsistent_events();
+
+    // TODO: Use in-place processing for this test
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            note_event_rng.fill_event_queue(
+                &mut prng,
+                &process_data.input_events,
+                BUFFER_SIZE as u32,
+            )?;
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::NoteDialect`. The host claims to support only a single note
+/// dialect, and the plugin should then not output any note events in the other dialect.
+pub fn test_note_dialect(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    variant: NoteDialectVariant,
+) -> Result<TestStatus> {
+    let host_dialect = match variant {
+        NoteDialectVariant::MidiOnly => CLAP_NOTE_DIALECT_MIDI,
+        NoteDialectVariant::ClapOnly => CLAP_NOTE_DIALECT_CLAP,
+    };
+
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig {
+        supported_note_dialects: host_dialect,
+        ..HostConfig::default()
+    });
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let mut note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if note_ports_config.outputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(format!(
+                "The plugin implements the '{}' extension but it does not have any output note \
+                 ports.",
+                NotePorts::EXTENSION_ID.to_str().unwrap()
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    // The host only sends events in the dialect it supports. If one of the plugin's input note
+    // ports doesn't support that dialect, then the plugin will only receive audio.
+    for port in &mut note_ports_config.inputs {
+        port.supported_dialects
+            .retain(|dialect| *dialect == host_dialect);
+    }
+    let mut note_event_rng = if !note_ports_config.inputs.is_empty()
+        && note_ports_config
+            .inputs
+            .iter()
+            .all(|port| !port.supported_dialects.is_empty())
+    {
+        Some(NoteGenerator::new(note_ports_config))
+    } else {
+        None
+    };
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            5,
+            ProcessConfig::default(),
+            |process_data| {
+                if let Some(note_event_rng) = &mut note_event_rng {
+                    note_event_rng.fill_event_queue(
+                        &mut prng,
+                        &process_data.input_events,
+                        BUFFER_SIZE as u32,
+                    )?;
+                }
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+            |_, process_data| check_output_note_dialect(process_data, host_dialect),
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessNoteDialectPreference`. This is the same test as
+/// `ProcessNote`, but events are either only sent in each input note port's preferred note dialect,
+/// or only in the other note dialects the port supports.
+pub fn test_process_note_dialect_preference(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    variant: NoteDialectPreferenceVariant,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if note_ports_config.inputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(format!(
+                "The plugin implements the '{}' extension but it does not have any input note \
+                 ports.",
+                NotePorts::EXTENSION_ID.to_str().unwrap()
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    let dialect_selection = match variant {
+        NoteDialectPreferenceVariant::Preferred => DialectSelection::PreferredOnly,
+        NoteDialectPreferenceVariant::NonPreferred => DialectSelection::NonPreferredOnly,
+    };
+    let mut note_event_rng =
+        NoteGenerator::new(note_ports_config).with_dialect_selection(dialect_selection);
+    if !note_event_rng.can_generate_events() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
+            details: Some(String::from(match variant {
+                NoteDialectPreferenceVariant::Preferred => {
+                    "None of the plugin's input note ports support their own preferred note \
+                     dialect."
+                }
+                NoteDialectPreferenceVariant::NonPreferred => {
+                    "None of the plugin's input note ports support any note dialects other than \
+                     their preferred dialect."
+                }
+            })),
+        });
+    }
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(BUFFER_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(5, ProcessConfig::default(), |process_data| {
+            note_event_rng.fill_event_queue(
+                &mut prng,
+                &process_data.input_events,
+                BUFFER_SIZE as u32,
+            )?;
+            process_data.buffers.randomize(&mut prng);
+
+            Ok(())
+        })?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// The test for `PluginTestCase::ProcessNoteOutputOnly`. Plugins that generate notes without
+/// receiving any, like arpeggiators with a built-in pattern or MIDI generators, only have output
+/// note ports. The note generator based tests skip these plugins, so this test processes audio
+/// with them and checks the events they output against their output note ports.
+pub fn test_process_note_output_only(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let note_ports_config = match plugin.get_extension::<NotePorts>() {
+        Some(note_ports) => note_ports
+            .config()
+            .context("Error while querying 'note-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    NotePorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if note_ports_config.outputs.is_empty() || !note_ports_config.inputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoNotePorts,
+            details: Some(format!(
+                "This test only applies to plugins with output note ports and without input note \
+                 ports. The plugin has {} input and {} output note ports.",
+                note_ports_config.inputs.len(),
+                note_ports_config.outputs.len()
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    // The processing test already checks the output events against the plugin's output note
+    // ports. This only counts them so the result shows whether the plugin output anything.
+    let mut num_note_events = 0;
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            NOTE_OUTPUT_ONLY_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+            |_, process_data| {
+                num_note_events += process_data
+                    .output_events
+                    .events
+                    .lock()
+                    .iter()
+                    .filter(|event| {
+                        matches!(
+                            event,
+                            Event::Note(_)
+                                | Event::NoteExpression(_)
+                                | Event::Midi(_)
+                                | Event::Midi2(_)
+                                | Event::MidiSysex(_)
+                        )
+                    })
+                    .count();
+
+                Ok(())
+            },
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    match stats.status() {
+        TestStatus::Success { .. } => Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin output {num_note_events} note {} over {NOTE_OUTPUT_ONLY_NUM_BLOCKS} \
+                 processing cycles.",
+                if num_note_events == 1 {
+                    "event"
+                } else {
+                    "events"
+                }
+            )),
+        }),
+        status => Ok(status),
+    }
+}
+
+/// The test for `PluginTestCase::EventRegistry`. The host either registers or declines every event
+/// space the plugin queries through `clap_host_event_registry::query()`, and the plugin should
+/// then only output events in the core event space and in the spaces that were registered for it.
+pub fn test_event_registry(
+    library: &PluginLibrary,
+    plugin_id: &str,
+    variant: EventRegistryVariant,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig {
+        register_event_spaces: variant == EventRegistryVariant::Accepted,
+        ..HostConfig::default()
+    });
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    // Custom events can be output by any plugin, even if it doesn't have any audio ports
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            5,
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+            |plugin, process_data| check_output_event_spaces(plugin.state(), process_data),
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    // Plugins that never query an event space can't be checked for how they handle the response
+    if plugin.state.num_event_space_queries.load(Ordering::SeqCst) == 0 {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
+            details: Some(String::from(
+                "The plugin did not query any event spaces using \
+                 'clap_host_event_registry::query()'.",
+            )),
+        });
+    }
+
+    Ok(stats.status())
+}
+
+/// Check whether all of the events the plugin output during the last processing cycle are either
+/// in the core event space or in one of the event spaces registered for the plugin through
+/// `clap_host_event_registry::query()`.
+fn check_output_event_spaces(state: &InstanceState, process_data: &ProcessData) -> Result<()> {
+    for event in process_data.output_events.events.lock().iter() {
+        let space_id = event.header().space_id;
+        if space_id != CLAP_CORE_EVENT_SPACE_ID && !state.is_registered_event_space(space_id) {
+            anyhow::bail!(
+                "The plugin output an event in event space {space_id}, which was not registered \
+                 for the plugin using 'clap_host_event_registry::query()': {event:?}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the events in the plugin's output queue that are exact duplicates of an earlier event for
+/// the same sample. The duplicates are returned formatted using their `Debug` implementation, which
+/// includes all of the event's fields. This assumes the events are already ordered by time.
+fn duplicate_output_events(process_data: &ProcessData) -> Vec<String> {
+    let events = process_data.output_events.events.lock();
+
+    let mut duplicates = Vec::new();
+    let mut same_time_events: Vec<String> = Vec::new();
+    let mut current_time = None;
+    for event in events.iter() {
+        let time = event.header().time;
+        if current_time != Some(time) {
+            current_time = Some(time);
+            same_time_events.clear();
+        }
+
+        let formatted_event = format!("{event:?}");
+        if same_time_events.contains(&formatted_event) {
+            duplicates.push(formatted_event);
+        } else {
+            same_time_events.push(formatted_event);
+        }
+    }
+
+    duplicates
+}
+
+/// Copy the samples past `block_size` from every output channel. These are used as canaries to
+/// detect writes past the end of the processed block.
+fn output_guard_samples(output_buffers: &[Vec<Vec<f32>>], block_size: usize) -> Vec<Vec<f32>> {
+    output_buffers
+        .iter()
+        .flatten()
+        .map(|channel_slice| channel_slice[block_size..].to_vec())
+        .collect()
+}
+
+/// Check whether the plugin only output note events in the note dialect supported by the host.
+/// `host_dialect` should be either `CLAP_NOTE_DIALECT_CLAP` or `CLAP_NOTE_DIALECT_MIDI`.
+fn check_output_note_dialect(
+    process_data: &ProcessData,
+    host_dialect: clap_note_dialect,
+) -> Result<()> {
+    for event in process_data.output_events.events.lock().iter() {
+        let header = event.header();
+        let is_clap_note_event = matches!(event, Event::Note(_) | Event::NoteExpression(_));
+        let is_midi_event = header.space_id == CLAP_CORE_EVENT_SPACE_ID
+            && matches!(
+                header.type_,
+                CLAP_EVENT_MIDI | CLAP_EVENT_MIDI_SYSEX | CLAP_EVENT_MIDI2
+            );
+
+        if host_dialect == CLAP_NOTE_DIALECT_MIDI && is_clap_note_event {
+            anyhow::bail!(
+                "The host only supports MIDI note events, but the plugin output a CLAP note event \
+                 with type {} for sample {}.",
+                header.type_,
+                header.time
+            );
+        } else if host_dialect == CLAP_NOTE_DIALECT_CLAP && is_midi_event {
+            anyhow::bail!(
+                "The host only supports CLAP note events, but the plugin output a MIDI event with \
+                 type {} for sample {}.",
+                header.type_,
+                header.time
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether all note and MIDI events output by the plugin target one of its output note ports,
+/// and whether that port supports the event's note dialect. Events with a port index of -1 are not
+/// tied to a specific port.
+fn check_output_note_events(
+    process_data: &ProcessData,
+    note_port_config: &NotePortConfig,
+) -> Result<()> {
+    let num_output_ports = note_port_config.outputs.len();
+    for event in process_data.output_events.events.lock().iter() {
+        let (event_name, port_index, dialects): (_, i32, &[clap_note_dialect]) = match event {
+            Event::Note(event) => (
+                "CLAP note event",
+                event.port_index as i32,
+                &[CLAP_NOTE_DIALECT_CLAP],
+            ),
+            Event::NoteExpression(event) => (
+                "CLAP note expression event",
+                event.port_index as i32,
+                &[CLAP_NOTE_DIALECT_CLAP],
+            ),
+            Event::Midi(event) => (
+                "MIDI event",
+                event.port_index as i32,
+                &[CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI_MPE],
+            ),
+            Event::Midi2(event) => (
+                "MIDI 2.0 event",
+                event.port_index as i32,
+                &[CLAP_NOTE_DIALECT_MIDI2],
+            ),
+            Event::MidiSysex(MidiSysexEvent { event, .. }) => (
+                "MIDI sysex event",
+                event.port_index as i32,
+                &[CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI_MPE],
+            ),
+            _ => continue,
+        };
+
+        let header = event.header();
+        if port_index == -1 {
+            if num_output_ports == 0 {
+                anyhow::bail!(
+                    "The plugin output a {event_name} for sample {} but it does not have any \
+                     output note ports.",
+                    header.time
+                );
+            }
+
+            continue;
+        }
+
+        let port = match usize::try_from(port_index)
+            .ok()
+            .and_then(|idx| note_port_config.outputs.get(idx))
+        {
+            Some(port) => port,
+            None => anyhow::bail!(
+                "The plugin output a {event_name} for sample {} on output note port {port_index}, \
+                 but the plugin only has {num_output_ports} output note ports.",
+                header.time
+            ),
+        };
+        if !port
+            .supported_dialects
+            .iter()
+            .any(|dialect| dialects.contains(dialect))
+        {
+            anyhow::bail!(
+                "The plugin output a {event_name} for sample {} on output note port {port_index}, \
+                 but that port's supported note dialects ({:?}) don't allow this.",
+                header.time,
+                port.supported_dialects
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Plugins that don't track voices don't need to output `CLAP_EVENT_NOTE_END` events at all, but if
+/// they do then every note should be ended. Returns a warning if the plugin output note end events
+/// but the notes in `unended_note_ids` were not ended, even though they had been released for at
+/// least `release_blocks` blocks of `block_size` samples.
+pub fn check_unended_notes(
+    output_note_end_events: bool,
+    unended_note_ids: &[i32],
+    release_blocks: usize,
+    block_size: usize,
+) -> Option<TestStatus> {
+    if !output_note_end_events || unended_note_ids.is_empty() {
+        return None;
+    }
+
+    let unended_note_ids: Vec<String> = unended_note_ids
+        .iter()
+        .map(|note_id| note_id.to_string())
+        .collect();
+
+    Some(TestStatus::Warning {
+        details: Some(format!(
+            "The plugin output 'CLAP_EVENT_NOTE_END' events, but not for the notes with IDs {} \
+             after they had been released for at least {release_blocks} blocks of {block_size} \
+             samples.",
+            unended_note_ids.join(", ")
+        )),
+    })
+}
+
+/// Check the parameter events output by the plugin. Parameter value, modulation, and gesture events
+/// must refer to one of the plugin's parameters, and values must fall within the parameter's range.
+/// Gestures must not be started for parameters that are already in a gesture, and they must not be
+/// ended for parameters that aren't. `open_gestures` contains the parameters that are currently in
+/// a gesture, and it is updated according to the events. If `known_params` is `None`, then only
+/// the gestures are checked.
+pub fn check_output_param_events(
+    events: &[Event],
+    known_params: Option<&BTreeMap<clap_id, KnownParam>>,
+    open_gestures: &mut BTreeSet<clap_id>,
+) -> Result<()> {
+    for event in events {
+        let (event_name, param_id) = match event {
+            Event::ParamValue(event) => ("parameter value event", event.param_id),
+            Event::ParamMod(event) => ("parameter modulation event", event.param_id),
+            Event::ParamGesture(event) if event.header.type_ == CLAP_EVENT_PARAM_GESTURE_BEGIN => {
+                ("gesture begin event", event.param_id)
+            }
+            Event::ParamGesture(event) => ("gesture end event", event.param_id),
+            _ => continue,
+        };
+        let time = event.header().time;
+
+        if let Some(known_params) = known_params {
+            let param = match known_params.get(&param_id) {
+                Some(param) => param,
+                None => anyhow::bail!(
+                    "The plugin output a {event_name} for sample {time} for parameter ID \
+                     {param_id}, but the plugin does not have a parameter with that ID."
+                ),
+            };
+
+            if let Event::ParamValue(event) = event {
+                if !param.range.contains(&event.value) {
+                    anyhow::bail!(
+                        "The plugin output a parameter value event for sample {time} with value \
+                         {} for parameter {param_id} ('{}'), but that parameter's range is [{}, \
+                         {}].",
+                        util::format_f64(event.value),
+                        param.name,
+                        util::format_f64(*param.range.start()),
+                        util::format_f64(*param.range.end())
+                    );
+                }
+            }
         }
 
-        last_event_time = event_time;
+        if let Event::ParamGesture(event) = event {
+            if event.header.type_ == CLAP_EVENT_PARAM_GESTURE_BEGIN {
+                if !open_gestures.insert(param_id) {
+                    anyhow::bail!(
+                        "The plugin output a gesture begin event for sample {time} for parameter \
+                         {param_id}, but a gesture for that parameter had already begun."
+                    );
+                }
+            } else if !open_gestures.remove(&param_id) {
+                anyhow::bail!(
+                    "The plugin output a gesture end event for sample {time} for parameter \
+                     {param_id} without beginning a gesture for that parameter first."
+                );
+            }
+        }
     }
 
-    if last_event_time >= num_samples {
+    Ok(())
+}
+
+/// The process for consistency. This verifies that the output buffer doesn't contain any NaN,
+/// infinite, or denormal values, that the input buffers have not been modified by the plugin, and
+/// that the output event queue is monotonically ordered. Every output event also needs to fall
+/// within the block, and events in the core event space need to be of a known type with a
+/// `header.size` that matches that type's struct. Sysex events must point to a non-empty buffer
+/// that is no larger than [`MAX_SYSEX_SIZE`].
+fn check_out_of_place_output_consistency(
+    process_data: &ProcessData,
+    original_input_buffers: &[Vec<Vec<f32>>],
+) -> Result<()> {
+    // The input buffer must not be overwritten during out of place processing, and the outputs
+    // should not contain any non-finite or denormal values
+    let num_samples = process_data.block_size() as u32;
+    let input_buffers = process_data.buffers.inputs_ref();
+    let output_buffers = process_data.buffers.outputs_ref();
+    if input_buffers != original_input_buffers {
         anyhow::bail!(
-            "The plugin output an event for sample {last_event_time} but the audio buffer only \
-             contains {num_samples} samples."
-        )
+            "The plugin has overwritten the input buffers during out-of-place processing."
+        );
+    }
+    for (port_idx, channel_slices) in output_buffers.iter().enumerate() {
+        for (channel_idx, channel_slice) in channel_slices.iter().enumerate() {
+            for (sample_idx, sample) in channel_slice.iter().enumerate() {
+                if !sample.is_finite() {
+                    anyhow::bail!(
+                        "The sample written to output port {port_idx}, channel {channel_idx}, and \
+                         sample index {sample_idx} is {}.",
+                        util::format_f32(*sample)
+                    );
+                } else if sample.is_subnormal() {
+                    anyhow::bail!(
+                        "The sample written to output port {port_idx}, channel {channel_idx}, and \
+                         sample index {sample_idx} is subnormal ({}).",
+                        util::format_f32(*sample)
+                    );
+                }
+            }
+        }
+    }
+
+    // If the plugin output any events, then they should be in a monotonically increasing order
+    let mut last_event_time = None;
+    for event in process_data.output_events.events.lock().iter() {
+        let header = event.header();
+        let event_time = header.time;
+        match last_event_time {
+            Some(last_event_time) if event_time < last_event_time => anyhow::bail!(
+                "The plugin output an event for sample {event_time} after it had previously \
+                 output an event for sample {last_event_time}. Event: {event:?}"
+            ),
+            _ => (),
+        }
+
+        // Blocks without any samples can still contain events for the first sample
+        if event_time >= num_samples.max(1) {
+            anyhow::bail!(
+                "The plugin output an event for sample {event_time} but the audio buffer only \
+                 contains {num_samples} samples. Event: {event:?}"
+            );
+        }
+
+        // Future CLAP versions may extend the event structs, so only events that are too small to
+        // contain the struct are rejected
+        if header.space_id == CLAP_CORE_EVENT_SPACE_ID {
+            match core_event_size(header.type_) {
+                Some(expected_size) if (header.size as usize) < expected_size => anyhow::bail!(
+                    "The plugin output an event of type {} with a size of {} bytes, but events of \
+                     that type are at least {expected_size} bytes large. Event: {event:?}",
+                    header.type_,
+                    header.size
+                ),
+                Some(_) => (),
+                None => anyhow::bail!(
+                    "The plugin output an event with unknown type {} in the core event space. \
+                     Event: {event:?}",
+                    header.type_
+                ),
+            }
+        }
+
+        if let Event::MidiSysex(MidiSysexEvent { event, .. }) = event {
+            if event.buffer.is_null() {
+                anyhow::bail!(
+                    "The plugin output a MIDI sysex event for sample {event_time} with a null \
+                     pointer as its buffer. Event: {event:?}"
+                );
+            } else if event.size == 0 {
+                anyhow::bail!(
+                    "The plugin output a MIDI sysex event for sample {event_time} with an empty \
+                     buffer. Event: {event:?}"
+                );
+            } else if event.size as usize > MAX_SYSEX_SIZE {
+                anyhow::bail!(
+                    "The plugin output a MIDI sysex event for sample {event_time} with a {} byte \
+                     buffer, which is larger than the {MAX_SYSEX_SIZE} bytes the validator \
+                     accepts. This is likely an uninitialized size field. Event: {event:?}",
+                    event.size
+                );
+            }
+        }
+
+        last_event_time = Some(event_time);
     }
 
     Ok(())
 }
+
+/// The size of the struct for an event type from the core event space, or `None` if the event type
+/// is not defined by CLAP.
+fn core_event_size(event_type: u16) -> Option<usize> {
+    match event_type {
+        CLAP_EVENT_NOTE_ON | CLAP_EVENT_NOTE_OFF | CLAP_EVENT_NOTE_CHOKE | CLAP_EVENT_NOTE_END => {
+            Some(std::mem::size_of::<clap_event_note>())
+        }
+        CLAP_EVENT_NOTE_EXPRESSION => Some(std::mem::size_of::<clap_event_note_expression>()),
+        CLAP_EVENT_PARAM_VALUE => Some(std::mem::size_of::<clap_event_param_value>()),
+        CLAP_EVENT_PARAM_MOD => Some(std::mem::size_of::<clap_event_param_mod>()),
+        CLAP_EVENT_PARAM_GESTURE_BEGIN | CLAP_EVENT_PARAM_GESTURE_END => {
+            Some(std::mem::size_of::<clap_event_param_gesture>())
+        }
+        CLAP_EVENT_TRANSPORT => Some(std::mem::size_of::<clap_event_transport>()),
+        CLAP_EVENT_MIDI => Some(std::mem::size_of::<clap_event_midi>()),
+        CLAP_EVENT_MIDI_SYSEX => Some(std::mem::size_of::<clap_event_midi_sysex>()),
+        CLAP_EVENT_MIDI2 => Some(std::mem::size_of::<clap_event_midi2>()),
+        _ => None,
+    }
+}
@@ -0,0 +1,153 @@
+//! Tests for the `remote-controls` draft extension.
+
+use anyhow::{Context, Result};
+
+use crate::plugin::ext::params::{ParamInfo, Params};
+use crate::plugin::ext::remote_controls::RemoteControls;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::library::PluginLibrary;
+use crate::tests::{SkipReason, TestStatus};
+
+/// The test for `PluginTestCase::RemoteControlsPages`. Queries all of the plugin's remote controls
+/// pages and checks that they only reference parameters the plugin actually has. Pages without any
+/// mapped controls or without a section name, sections that are split up, and sections mixing
+/// device and preset pages result in a warning.
+pub fn test_remote_controls_pages(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let remote_controls = match plugin.get_extension::<RemoteControls>() {
+        Some(remote_controls) => remote_controls,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    RemoteControls::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    // A plugin without parameters can't have any mapped remote controls
+    let param_infos = match plugin.get_extension::<Params>() {
+        Some(params) => params
+            .info()
+            .context("Failure while fetching the plugin's parameters")?,
+        None => ParamInfo::new(),
+    };
+    host.handle_callbacks_once();
+
+    let pages = remote_controls
+        .pages()
+        .context("Failure while fetching the plugin's remote controls pages")?;
+
+    let mut empty_pages = Vec::new();
+    let mut unnamed_section_pages = Vec::new();
+    // Hosts group pages by their section, so all pages from a section should be listed together
+    let mut finished_sections: Vec<&str> = Vec::new();
+    let mut split_sections: Vec<&str> = Vec::new();
+    for (page_idx, page) in pages.iter().enumerate() {
+        for (control_idx, param_id) in page.param_ids.iter().enumerate() {
+            if let Some(param_id) = param_id {
+                if !param_infos.contains_key(param_id) {
+                    anyhow::bail!(
+                        "Control {control_idx} on remote controls page '{}' (page ID {}) is \
+                         mapped to parameter {param_id}, but the plugin does not have a \
+                         parameter with that stable ID.",
+                        page.page_name,
+                        page.page_id,
+                    );
+                }
+            }
+        }
+
+        if page.param_ids.iter().all(Option::is_none) {
+            empty_pages.push(format!("'{}' (page ID {})", page.page_name, page.page_id));
+        }
+        if page.section_name.is_empty() {
+            unnamed_section_pages.push(format!("'{}' (page ID {})", page.page_name, page.page_id));
+        }
+
+        let section_name = page.section_name.as_str();
+        if page_idx > 0 && pages[page_idx - 1].section_name != section_name {
+            finished_sections.push(pages[page_idx - 1].section_name.as_str());
+            if finished_sections.contains(&section_name) && !split_sections.contains(&section_name)
+            {
+                split_sections.push(section_name);
+            }
+        }
+    }
+
+    // The `is_for_preset` flag separates the plugin's own pages from pages that only exist for the
+    // current preset, so a single section should not contain both kinds of pages
+    let mut mixed_sections: Vec<&str> = Vec::new();
+    for page in &pages {
+        let section_name = page.section_name.as_str();
+        let is_mixed = pages.iter().any(|other_page| {
+            other_page.section_name == section_name
+                && other_page.is_for_preset != page.is_for_preset
+        });
+        if is_mixed && !mixed_sections.contains(&section_name) {
+            mixed_sections.push(section_name);
+        }
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let mut warnings = Vec::new();
+    if !empty_pages.is_empty() {
+        warnings.push(format!(
+            "The plugin has remote controls pages without any mapped controls: {}.",
+            empty_pages.join(", ")
+        ));
+    }
+    if !unnamed_section_pages.is_empty() {
+        warnings.push(format!(
+            "The plugin has remote controls pages with an empty section name: {}.",
+            unnamed_section_pages.join(", ")
+        ));
+    }
+    if !split_sections.is_empty() {
+        warnings.push(format!(
+            "The pages for the following remote controls sections are not listed consecutively: \
+             {}.",
+            quoted_list(&split_sections)
+        ));
+    }
+    if !mixed_sections.is_empty() {
+        warnings.push(format!(
+            "The following remote controls sections contain both preset specific pages and \
+             regular pages: {}.",
+            quoted_list(&mixed_sections)
+        ));
+    }
+
+    if warnings.is_empty() {
+        let num_preset_pages = pages.iter().filter(|page| page.is_for_preset).count();
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "The plugin has {} remote controls pages, {num_preset_pages} of which are \
+                 specific to the current preset.",
+                pages.len()
+            )),
+        })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join(" ")),
+        })
+    }
+}
+
+/// Format section names as a comma separated list of quoted names.
+fn quoted_list(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("'{name}'"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
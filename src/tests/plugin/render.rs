@@ -0,0 +1,138 @@
+//! Tests surrounding the plugin's rendering modes.
+
+use anyhow::{Context, Result};
+use clap_sys::ext::render::{CLAP_RENDER_OFFLINE, CLAP_RENDER_REALTIME};
+
+use super::processing::ProcessingTest;
+use crate::plugin::ext::audio_ports::AudioPorts;
+use crate::plugin::ext::render::Render;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::instance::process::ProcessConfig;
+use crate::plugin::library::PluginLibrary;
+use crate::tests::rng::new_prng;
+use crate::tests::{SkipReason, TestStatus};
+
+/// The number of processing cycles run in every rendering mode in the `render-modes` test.
+const RENDER_NUM_CYCLES: usize = 5;
+
+/// The test for `PluginTestCase::RenderModes`. Switches the deactivated plugin to the realtime and
+/// offline rendering modes and processes random audio in each mode it accepts. Plugins must accept
+/// the realtime mode, and they may only reject the offline mode if they have a hard realtime
+/// requirement.
+pub fn test_render_modes(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let render = match plugin.get_extension::<Render>() {
+        Some(render) => render,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Render::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let has_hard_realtime_requirement = render.has_hard_realtime_requirement();
+    host.handle_callbacks_once();
+
+    let mut warnings = Vec::new();
+    let mut rendered_modes = Vec::new();
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    for (mode, mode_name) in [
+        (CLAP_RENDER_REALTIME, "CLAP_RENDER_REALTIME"),
+        (CLAP_RENDER_OFFLINE, "CLAP_RENDER_OFFLINE"),
+    ] {
+        // `ProcessingTest::run()` deactivates the plugin again when it's done, so the rendering
+        // mode is always changed while the plugin is deactivated
+        if !render.set(mode) {
+            if mode == CLAP_RENDER_OFFLINE && has_hard_realtime_requirement {
+                continue;
+            }
+
+            return Ok(TestStatus::Failed {
+                details: Some(if mode == CLAP_RENDER_OFFLINE {
+                    String::from(
+                        "'clap_plugin_render::set()' returned false for 'CLAP_RENDER_OFFLINE', \
+                         but 'clap_plugin_render::has_hard_realtime_requirement()' returned \
+                         false. Plugins without a hard realtime requirement should support \
+                         offline rendering.",
+                    )
+                } else {
+                    String::from(
+                        "'clap_plugin_render::set()' returned false for 'CLAP_RENDER_REALTIME'. \
+                         Plugins should always support realtime rendering.",
+                    )
+                }),
+                causes: Vec::new(),
+            });
+        }
+        host.handle_callbacks_once();
+
+        let stats =
+            ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+                .run(
+                    RENDER_NUM_CYCLES,
+                    ProcessConfig::default(),
+                    |process_data| {
+                        process_data.buffers.randomize(&mut prng);
+
+                        Ok(())
+                    },
+                )
+                .with_context(|| format!("Error while processing audio in '{mode_name}' mode"))?;
+        if let TestStatus::Warning {
+            details: Some(details),
+        } = stats.status()
+        {
+            warnings.push(details);
+        }
+
+        rendered_modes.push(mode_name);
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    if warnings.is_empty() {
+        Ok(TestStatus::Success {
+            details: Some(if rendered_modes.len() == 1 {
+                String::from(
+                    "The plugin has a hard realtime requirement and only accepted the \
+                     'CLAP_RENDER_REALTIME' rendering mode.",
+                )
+            } else {
+                String::from(
+                    "The plugin accepted and processed audio in both the 'CLAP_RENDER_REALTIME' \
+                     and 'CLAP_RENDER_OFFLINE' rendering modes.",
+                )
+            }),
+        })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join(" ")),
+        })
+    }
+}
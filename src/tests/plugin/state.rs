@@ -4,28 +4,47 @@ use anyhow::{Context, Result};
 use clap_sys::id::clap_id;
 use std::collections::BTreeMap;
 use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use crate::plugin::ext::audio_ports::{AudioPortConfig, AudioPorts};
 use crate::plugin::ext::params::{ParamInfo, Params};
 use crate::plugin::ext::state::State;
 use crate::plugin::ext::Extension;
-use crate::plugin::host::Host;
+use crate::plugin::host::{Host, HostConfig};
 use crate::plugin::instance::process::{Event, EventQueue, ProcessConfig};
 use crate::plugin::library::PluginLibrary;
 use crate::tests::rng::{new_prng, ParamFuzzer};
-use crate::tests::{TestCase, TestStatus};
+use crate::tests::{SkipReason, TestCase, TestStatus};
+use crate::util;
 
 use super::processing::ProcessingTest;
-use super::PluginTestCase;
+use super::{params, PluginTestCase, StateReproducibilityVariant};
 
 /// The file name we'll use to dump the expected state when a test fails.
 const EXPECTED_STATE_FILE_NAME: &str = "state-expected";
 /// The file name we'll use to dump the actual state when a test fails.
 const ACTUAL_STATE_FILE_NAME: &str = "state-actual";
 
+/// The buffer size used in the state load during processing test.
+const WHILE_PROCESSING_BUFFER_SIZE: usize = 512;
+/// The number of blocks the audio thread processes before the state is loaded on the main thread in
+/// the state load during processing test.
+pub const WHILE_PROCESSING_BLOCKS_BEFORE_LOAD: usize = 8;
+/// The maximum number of blocks the audio thread processes while the state is being loaded. If
+/// loading the state takes longer than that, then the audio thread waits for it to finish.
+const WHILE_PROCESSING_MAX_BLOCKS_DURING_LOAD: usize = 64;
+/// The number of blocks the audio thread processes after the state has been loaded, before the
+/// parameter values and the state are compared to the expected values.
+pub const WHILE_PROCESSING_BLOCKS_AFTER_LOAD: usize = 8;
+/// How long the main thread waits for the audio thread to process the first
+/// [`WHILE_PROCESSING_BLOCKS_BEFORE_LOAD`] blocks. The state is loaded anyways after this time so
+/// the test cannot hang when processing fails.
+const WHILE_PROCESSING_LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// The test for `PluginTestCase::StateInvalid`.
 pub fn test_state_invalid(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -35,6 +54,7 @@ pub fn test_state_invalid(library: &PluginLibrary, plugin_id: &str) -> Result<Te
         Some(state) => state,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
                     State::EXTENSION_ID.to_str().unwrap(),
@@ -50,7 +70,7 @@ pub fn test_state_invalid(library: &PluginLibrary, plugin_id: &str) -> Result<Te
              state, this is likely a bug."
         ),
         Err(_) => {
-            host.handle_callbacks_once();
+            host.handle_callbacks_until_idle();
             host.callback_error_check()
                 .context("An error occured during a host callback")?;
 
@@ -59,21 +79,23 @@ pub fn test_state_invalid(library: &PluginLibrary, plugin_id: &str) -> Result<Te
     }
 }
 
-/// The test for `PluginTestCase::StateReproducibilityNullCookies`. See the description of this test
-/// for a detailed explanation, but we essentially check if saving a loaded state results in the
-/// same state file, and whether a plugin's parameters are the same after loading the state.
+/// The test for `PluginTestCase::StateReproducibility`, with the `Basic`, `NullCookies`, and
+/// `Overwrite` variants. See the description of this test for a detailed explanation, but we
+/// essentially check if saving a loaded state results in the same state file, and whether a
+/// plugin's parameters are the same after loading the state.
 ///
-/// The `zero_out_cookies` parameter offers an alternative on this test that sends parameter change
-/// events with all cookies set to null pointers. The plugin should behave identically when this
-/// happens.
-pub fn test_state_reproducibility_null_cookies(
+/// The `NullCookies` variant sends parameter change events with all cookies set to null pointers.
+/// The plugin should behave identically when this happens. The `Overwrite` variant sets the second
+/// plugin instance's parameters to different values before loading the state, which catches
+/// plugins that merge the loaded state with their current state instead of replacing it.
+pub fn test_state_reproducibility(
     library: &PluginLibrary,
     plugin_id: &str,
-    zero_out_cookies: bool,
+    variant: StateReproducibilityVariant,
 ) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -92,6 +114,7 @@ pub fn test_state_reproducibility_null_cookies(
             Some(params) => params,
             None => {
                 return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
                     details: Some(format!(
                         "The plugin does not implement the '{}' extension.",
                         Params::EXTENSION_ID.to_str().unwrap(),
@@ -103,6 +126,7 @@ pub fn test_state_reproducibility_null_cookies(
             Some(state) => state,
             None => {
                 return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
                     details: Some(format!(
                         "The plugin does not implement the '{}' extension.",
                         State::EXTENSION_ID.to_str().unwrap(),
@@ -115,6 +139,12 @@ pub fn test_state_reproducibility_null_cookies(
         let param_infos = params
             .info()
             .context("Failure while fetching the plugin's parameters")?;
+        // Without any parameters to change, this variant would be identical to the basic test
+        if variant == StateReproducibilityVariant::Overwrite {
+            if let Some(status) = params::skip_without_settable_params(&param_infos) {
+                return Ok(status);
+            }
+        }
 
         // We can't compare the values from these events direclty as the plugin
         // may round the values during the parameter set
@@ -124,7 +154,7 @@ pub fn test_state_reproducibility_null_cookies(
 
         // This is a variation on the test that checks whether the plugin handles null
         // pointer cookies correctly
-        if zero_out_cookies {
+        if variant == StateReproducibilityVariant::NullCookies {
             for event in &mut random_param_set_events {
                 match event {
                     Event::ParamValue(event) => {
@@ -154,7 +184,7 @@ pub fn test_state_reproducibility_null_cookies(
             .collect::<Result<BTreeMap<clap_id, f64>>>()?;
 
         let expected_state = state.save()?;
-        host.handle_callbacks_once();
+        host.handle_callbacks_until_idle();
 
         (expected_state, expected_param_values)
     };
@@ -177,6 +207,7 @@ pub fn test_state_reproducibility_null_cookies(
         None => {
             // I sure hope that no plugin will ever hit this
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' extension.",
                     Params::EXTENSION_ID.to_str().unwrap(),
@@ -188,6 +219,7 @@ pub fn test_state_reproducibility_null_cookies(
         Some(state) => state,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin's second instance does not implement the '{}' extension.",
                     State::EXTENSION_ID.to_str().unwrap()
@@ -197,8 +229,34 @@ pub fn test_state_reproducibility_null_cookies(
     };
     host.handle_callbacks_once();
 
+    // For the `Overwrite` variant the parameters are set to different values first. Loading the
+    // state should then completely override these values.
+    if variant == StateReproducibilityVariant::Overwrite {
+        let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+            Some(audio_ports) => audio_ports
+                .config()
+                .context("Error while querying 'audio-ports' IO configuration")?,
+            None => AudioPortConfig::default(),
+        };
+        let param_infos = params
+            .info()
+            .context("Failure while fetching the plugin's parameters")?;
+        let param_fuzzer = ParamFuzzer::new(&param_infos);
+        let random_param_set_events: Vec<_> =
+            param_fuzzer.randomize_params_at(&mut prng, 0).collect();
+
+        let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+        ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+            .run_once(ProcessConfig::default(), move |process_data| {
+                *process_data.input_events.events.lock() = random_param_set_events;
+
+                Ok(())
+            })?;
+        host.handle_callbacks_once();
+    }
+
     state.load(&expected_state)?;
-    host.handle_callbacks_once();
+    host.handle_callbacks_until_idle();
 
     let actual_param_values: BTreeMap<clap_id, f64> = expected_param_values
         .keys()
@@ -219,7 +277,7 @@ pub fn test_state_reproducibility_null_cookies(
 
     // Now for the monent of truth
     let actual_state = state.save()?;
-    host.handle_callbacks_once();
+    host.handle_callbacks_until_idle();
 
     host.callback_error_check()
         .context("An error occured during a host callback")?;
@@ -227,10 +285,10 @@ pub fn test_state_reproducibility_null_cookies(
         Ok(TestStatus::Success { details: None })
     } else {
         let (expected_state_file_path, mut expected_state_file) =
-            PluginTestCase::StateReproducibilityBasic
+            PluginTestCase::StateReproducibility(variant)
                 .temporary_file(plugin_id, EXPECTED_STATE_FILE_NAME)?;
         let (actual_state_file_path, mut actual_state_file) =
-            PluginTestCase::StateReproducibilityBasic
+            PluginTestCase::StateReproducibility(variant)
                 .temporary_file(plugin_id, ACTUAL_STATE_FILE_NAME)?;
 
         expected_state_file.write_all(&expected_state)?;
@@ -245,14 +303,14 @@ pub fn test_state_reproducibility_null_cookies(
     }
 }
 
-/// The test for `PluginTestCase::StateReproducibilityFlush`.
+/// The test for `PluginTestCase::StateReproducibility(StateReproducibilityVariant::Flush)`.
 pub fn test_state_reproducibility_flush(
     library: &PluginLibrary,
     plugin_id: &str,
 ) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -268,6 +326,7 @@ pub fn test_state_reproducibility_flush(
             Some(params) => params,
             None => {
                 return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
                     details: Some(format!(
                         "The plugin does not implement the '{}' extension.",
                         Params::EXTENSION_ID.to_str().unwrap(),
@@ -279,6 +338,7 @@ pub fn test_state_reproducibility_flush(
             Some(state) => state,
             None => {
                 return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
                     details: Some(format!(
                         "The plugin does not implement the '{}' extension.",
                         State::EXTENSION_ID.to_str().unwrap(),
@@ -291,6 +351,9 @@ pub fn test_state_reproducibility_flush(
         let param_infos = params
             .info()
             .context("Failure while fetching the plugin's parameters")?;
+        if let Some(status) = params::skip_without_settable_params(&param_infos) {
+            return Ok(status);
+        }
 
         // Make sure the flush does _something_. If nothing changes, then the plugin has not
         // implemented flush.
@@ -317,10 +380,9 @@ pub fn test_state_reproducibility_flush(
             .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
             .collect::<Result<BTreeMap<clap_id, f64>>>()?;
         let expected_state = state.save()?;
-        host.handle_callbacks_once();
+        host.handle_callbacks_until_idle();
 
-        // Plugins with no parameters at all should of course not trigger this error
-        if expected_param_values == initial_param_values && !param_infos.is_empty() {
+        if expected_param_values == initial_param_values {
             anyhow::bail!(
                 "'clap_plugin_params::flush()' has been called with random parameter values, but \
                  the plugin's reported parameter values have not changed."
@@ -356,6 +418,7 @@ pub fn test_state_reproducibility_flush(
         None => {
             // I sure hope that no plugin will eer hit this
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin's second instance does not implement the '{}' extension.",
                     Params::EXTENSION_ID.to_str().unwrap()
@@ -367,6 +430,7 @@ pub fn test_state_reproducibility_flush(
         Some(state) => state,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin's second instance does not implement the '{}' extension.",
                     State::EXTENSION_ID.to_str().unwrap()
@@ -429,7 +493,7 @@ pub fn test_state_reproducibility_flush(
     }
 
     let actual_state = state.save()?;
-    host.handle_callbacks_once();
+    host.handle_callbacks_until_idle();
 
     host.callback_error_check()
         .context("An error occured during a host callback")?;
@@ -437,10 +501,10 @@ pub fn test_state_reproducibility_flush(
         Ok(TestStatus::Success { details: None })
     } else {
         let (expected_state_file_path, mut expected_state_file) =
-            PluginTestCase::StateReproducibilityFlush
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Flush)
                 .temporary_file(plugin_id, EXPECTED_STATE_FILE_NAME)?;
         let (actual_state_file_path, mut actual_state_file) =
-            PluginTestCase::StateReproducibilityFlush
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::Flush)
                 .temporary_file(plugin_id, ACTUAL_STATE_FILE_NAME)?;
 
         expected_state_file.write_all(&expected_state)?;
@@ -455,11 +519,182 @@ pub fn test_state_reproducibility_flush(
     }
 }
 
+/// The test for
+/// `PluginTestCase::StateReproducibility(StateReproducibilityVariant::WhileProcessing)`.
+/// Randomizes the plugin's parameters and saves its state, randomizes the parameters again, and
+/// then loads the saved state on the main thread while the audio thread is processing audio. This
+/// is how hosts load presets during playback. Afterwards the parameter values and a newly saved
+/// state should match the saved values and state.
+pub fn test_state_reproducibility_while_processing(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    let params = match plugin.get_extension::<Params>() {
+        Some(params) => params,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Params::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let state = match plugin.get_extension::<State>() {
+        Some(state) => state,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    State::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let param_infos = params
+        .info()
+        .context("Failure while fetching the plugin's parameters")?;
+    let param_fuzzer = ParamFuzzer::new(&param_infos);
+    let (mut input_buffers, mut output_buffers) =
+        audio_ports_config.create_buffers(WHILE_PROCESSING_BUFFER_SIZE);
+
+    // The first set of random values is stored in the state we'll load later, and the second set
+    // of values should be overwritten when that state is loaded
+    let mut expected_state_and_values = None;
+    for _ in 0..2 {
+        let random_param_set_events: Vec<_> =
+            param_fuzzer.randomize_params_at(&mut prng, 0).collect();
+        ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+            .run_once(ProcessConfig::default(), move |process_data| {
+                *process_data.input_events.events.lock() = random_param_set_events;
+
+                Ok(())
+            })?;
+
+        if expected_state_and_values.is_none() {
+            let expected_param_values: BTreeMap<clap_id, f64> = param_infos
+                .keys()
+                .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
+                .collect::<Result<BTreeMap<clap_id, f64>>>()?;
+            let expected_state = state.save()?;
+            host.handle_callbacks_until_idle();
+
+            expected_state_and_values = Some((expected_state, expected_param_values));
+        }
+    }
+    let (expected_state, expected_param_values) = expected_state_and_values.unwrap();
+
+    // The main thread waits for the audio thread to process a couple of blocks before loading the
+    // state. The audio thread keeps processing while the state is being loaded, but it waits for
+    // the state to be loaded before processing the last couple of blocks.
+    let blocks_processed = AtomicUsize::new(0);
+    let state_loaded = AtomicBool::new(false);
+    let mut load_result = None;
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .with_main_thread_task(&mut || {
+            let deadline = Instant::now() + WHILE_PROCESSING_LOAD_TIMEOUT;
+            while blocks_processed.load(Ordering::SeqCst) < WHILE_PROCESSING_BLOCKS_BEFORE_LOAD
+                && Instant::now() < deadline
+            {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+
+            load_result = Some(state.load(&expected_state));
+            state_loaded.store(true, Ordering::SeqCst);
+        })
+        .run_with_postprocess(
+            WHILE_PROCESSING_BLOCKS_BEFORE_LOAD
+                + WHILE_PROCESSING_MAX_BLOCKS_DURING_LOAD
+                + WHILE_PROCESSING_BLOCKS_AFTER_LOAD,
+            ProcessConfig::default(),
+            |process_data| {
+                if blocks_processed.load(Ordering::SeqCst)
+                    >= WHILE_PROCESSING_BLOCKS_BEFORE_LOAD + WHILE_PROCESSING_MAX_BLOCKS_DURING_LOAD
+                {
+                    while !state_loaded.load(Ordering::SeqCst) {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                }
+
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+            |_, _| {
+                blocks_processed.fetch_add(1, Ordering::SeqCst);
+
+                Ok(())
+            },
+        )?;
+    host.handle_callbacks_until_idle();
+
+    load_result
+        .context("The state was not loaded during audio processing")?
+        .context("Error while loading the state during audio processing")?;
+
+    let actual_param_values: BTreeMap<clap_id, f64> = expected_param_values
+        .keys()
+        .map(|param_id| params.get(*param_id).map(|value| (*param_id, value)))
+        .collect::<Result<BTreeMap<clap_id, f64>>>()?;
+    if actual_param_values != expected_param_values {
+        anyhow::bail!(
+            "After loading the state while the plugin was processing audio, the plugin's \
+             parameter values do not match the values from when the state was saved. The \
+             mismatching values are {}.",
+            format_mismatching_values(actual_param_values, &expected_param_values, &param_infos)
+        );
+    }
+
+    let actual_state = state.save()?;
+    host.handle_callbacks_until_idle();
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    if actual_state == expected_state {
+        Ok(stats.status())
+    } else {
+        let (expected_state_file_path, mut expected_state_file) =
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::WhileProcessing)
+                .temporary_file(plugin_id, EXPECTED_STATE_FILE_NAME)?;
+        let (actual_state_file_path, mut actual_state_file) =
+            PluginTestCase::StateReproducibility(StateReproducibilityVariant::WhileProcessing)
+                .temporary_file(plugin_id, ACTUAL_STATE_FILE_NAME)?;
+
+        expected_state_file.write_all(&expected_state)?;
+        actual_state_file.write_all(&actual_state)?;
+
+        anyhow::bail!(
+            "Re-saving a state that was loaded while the plugin was processing audio resulted in a \
+             different state file. Expected: '{}'. Actual: '{}'.",
+            expected_state_file_path.display(),
+            actual_state_file_path.display(),
+        )
+    }
+}
+
 /// The test for `PluginTestCase::StateBufferedStreams`.
 pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
     let mut prng = new_prng();
 
-    let host = Host::new();
+    let host = Host::new(HostConfig::default());
     let plugin = library
         .create_plugin(plugin_id, host.clone())
         .context("Could not create the plugin instance")?;
@@ -477,6 +712,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
             Some(params) => params,
             None => {
                 return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
                     details: Some(format!(
                         "The plugin does not implement the '{}' extension.",
                         Params::EXTENSION_ID.to_str().unwrap(),
@@ -488,6 +724,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
             Some(state) => state,
             None => {
                 return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
                     details: Some(format!(
                         "The plugin does not implement the '{}' extension.",
                         State::EXTENSION_ID.to_str().unwrap(),
@@ -520,7 +757,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
         // implementsq this correctly, so we can check if it handles buffered streams correctly by
         // treating this as the ground truth.
         let expected_stae = state.save()?;
-        host.handle_callbacks_once();
+        host.handle_callbacks_until_idle();
 
         (expected_stae, expected_param_values)
     };
@@ -539,6 +776,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
         Some(params) => params,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin's second instance does not implement the '{}' extension.",
                     Params::EXTENSION_ID.to_str().unwrap()
@@ -550,6 +788,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
         Some(state) => state,
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin's second instance does not implement the '{}' extension.",
                     State::EXTENSION_ID.to_str().unwrap()
@@ -562,7 +801,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
     // This is a buffered load that only loads 17 bytes at a time. Why 17? Because.
     const BUFFERED_LOAD_MAX_BYTES: usize = 17;
     state.load_buffered(&expected_state, BUFFERED_LOAD_MAX_BYTES)?;
-    host.handle_callbacks_once();
+    host.handle_callbacks_until_idle();
 
     let actual_param_values: BTreeMap<clap_id, f64> = expected_param_values
         .keys()
@@ -587,7 +826,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
     // Because we're mean, we'll use a different prime number for the saving
     const BUFFERED_SAVE_MAX_BYTES: usize = 23;
     let actual_state = state.save_buffered(BUFFERED_SAVE_MAX_BYTES)?;
-    host.handle_callbacks_once();
+    host.handle_callbacks_until_idle();
 
     host.callback_error_check()
         .context("An error occured during a host callback")?;
@@ -621,7 +860,7 @@ pub fn test_state_buffered_streams(library: &PluginLibrary, plugin_id: &str) ->
 ///
 /// If the parameters in `actual_param_values` don't have corresponding entries in
 /// `expected_param_values` and `param_infos`.
-fn format_mismatching_values(
+pub fn format_mismatching_values(
     actual_param_values: BTreeMap<clap_id, f64>,
     expected_param_values: &BTreeMap<clap_id, f64>,
     param_infos: &ParamInfo,
@@ -635,8 +874,9 @@ fn format_mismatching_values(
             } else {
                 let param_name = &param_infos[&param_id].name;
                 Some(format!(
-                    "parameter {param_id} ('{param_name}'), expected {expected_value:?}, actual \
-                     {actual_value:?}"
+                    "parameter {param_id} ('{param_name}'), expected {}, actual {}",
+                    util::format_f64(expected_value),
+                    util::format_f64(actual_value)
                 ))
             }
         })
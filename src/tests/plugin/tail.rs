@@ -0,0 +1,178 @@
+//! Tests surrounding the plugin's tail length.
+
+use anyhow::{Context, Result};
+
+use super::processing::ProcessingTest;
+use crate::plugin::ext::audio_ports::AudioPorts;
+use crate::plugin::ext::tail::{Tail, TailAudioThread, INFINITE_TAIL};
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::instance::process::ProcessConfig;
+use crate::plugin::library::PluginLibrary;
+use crate::tests::{SkipReason, TestStatus};
+
+/// The number of blocks processed in the `tail-length` test. The first block contains an impulse,
+/// and the remaining blocks are silent.
+const TAIL_NUM_BLOCKS: usize = 64;
+/// The block size used in the `tail-length` test.
+const TAIL_BLOCK_SIZE: usize = 512;
+/// The number of blocks the plugin's output may remain audible after its reported tail has ended.
+/// The validator doesn't query the plugin's latency, so this leaves some room for plugins that
+/// delay their output.
+const TAIL_SLACK_BLOCKS: usize = 8;
+/// Output samples with an absolute value below this value are considered to be silent.
+pub const SILENCE_THRESHOLD: f32 = 1e-5;
+
+/// The test for `PluginTestCase::TailLength`. Sends an impulse followed by silence to the plugin,
+/// and queries the tail length on the main thread before processing and on the audio thread after
+/// every block. The tail length should not change constantly, and it should be roughly consistent
+/// with the plugin's output.
+pub fn test_tail_length(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let tail = match plugin.get_extension::<Tail>() {
+        Some(tail) => tail,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    Tail::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    AudioPorts::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    if audio_ports_config.inputs.is_empty() || audio_ports_config.outputs.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoAudioPorts,
+            details: Some(String::from(
+                "The plugin needs to have at least one audio input and one audio output port.",
+            )),
+        });
+    }
+    host.handle_callbacks_once();
+
+    // The tail length can be queried from both the main thread and the audio thread. The value
+    // reported on the main thread is taken into account the same way as the audio thread values.
+    let main_thread_tail_length = tail.get();
+
+    let mut block_idx = 0;
+    let mut tail_lengths: Vec<u32> = Vec::with_capacity(TAIL_NUM_BLOCKS);
+    let mut last_audible_block = None;
+    let (mut input_buffers, mut output_buffers) =
+        audio_ports_config.create_buffers(TAIL_BLOCK_SIZE);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run_with_postprocess(
+            TAIL_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.fill_inputs_silence(block_idx == 0);
+                block_idx += 1;
+
+                Ok(())
+            },
+            |plugin, process_data| {
+                let tail = plugin.get_extension::<TailAudioThread>().with_context(|| {
+                    format!(
+                        "The plugin does not implement the '{}' extension on the audio thread.",
+                        Tail::EXTENSION_ID.to_str().unwrap(),
+                    )
+                })?;
+                tail_lengths.push(tail.get());
+
+                let is_audible = process_data
+                    .buffers
+                    .outputs_ref()
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .any(|sample| sample.abs() >= SILENCE_THRESHOLD);
+                if is_audible {
+                    last_audible_block = Some(tail_lengths.len() - 1);
+                }
+
+                Ok(())
+            },
+        )?;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let mut warnings = Vec::new();
+    let num_changes = tail_lengths
+        .windows(2)
+        .filter(|lengths| lengths[0] != lengths[1])
+        .count();
+    if tail_lengths.len() > 2 && num_changes == tail_lengths.len() - 1 {
+        warnings.push(format!(
+            "The plugin's reported tail length changed after every one of the {} processing \
+             cycles.",
+            tail_lengths.len()
+        ));
+    }
+
+    let max_tail_length = tail_lengths
+        .iter()
+        .copied()
+        .chain([main_thread_tail_length])
+        .max()
+        .unwrap_or(0);
+    let num_samples = tail_lengths.len() * TAIL_BLOCK_SIZE;
+    if max_tail_length >= INFINITE_TAIL {
+        // An infinite tail should still be audible during the second half of the test
+        if !matches!(last_audible_block, Some(block) if block >= tail_lengths.len() / 2) {
+            warnings.push(format!(
+                "The plugin reports an infinite tail, but its output decayed to silence within \
+                 {num_samples} samples after a single impulse."
+            ));
+        }
+    } else if let Some(last_audible_block) = last_audible_block {
+        let last_audible_sample = (last_audible_block + 1) * TAIL_BLOCK_SIZE;
+        if last_audible_sample > max_tail_length as usize + TAIL_SLACK_BLOCKS * TAIL_BLOCK_SIZE {
+            warnings.push(format!(
+                "The plugin reports a tail length of at most {max_tail_length} samples, but its \
+                 output was still audible up until sample {last_audible_sample} after a single \
+                 impulse."
+            ));
+        }
+    }
+
+    if let TestStatus::Warning {
+        details: Some(details),
+    } = stats.status()
+    {
+        warnings.push(details);
+    }
+
+    if warnings.is_empty() {
+        Ok(TestStatus::Success {
+            details: Some(if max_tail_length >= INFINITE_TAIL {
+                String::from("The plugin reports an infinite tail.")
+            } else {
+                format!("The plugin reports a tail length of at most {max_tail_length} samples.")
+            }),
+        })
+    } else {
+        Ok(TestStatus::Warning {
+            details: Some(warnings.join(" ")),
+        })
+    }
+}
@@ -0,0 +1,95 @@
+//! Tests surrounding the plugin's use of the host's timers.
+
+use anyhow::{Context, Result};
+use clap_sys::id::clap_id;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::plugin::ext::timer_support::TimerSupport;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{CallbackTask, Host, HostConfig};
+use crate::plugin::library::PluginLibrary;
+use crate::tests::{SkipReason, TestStatus};
+
+/// How long the host dispatches the plugin's timers for in the `timer-support` test.
+const TIMER_DISPATCH_DURATION: Duration = Duration::from_millis(500);
+
+/// The test for `PluginTestCase::TimerSupport`. Dispatches the timers the plugin registered during
+/// initialization on the main thread for a while, and then checks that the plugin unregisters all
+/// of its timers before it is destroyed.
+pub fn test_timer_support(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let implements_timer_support = plugin.get_extension::<TimerSupport>().is_some();
+    host.handle_callbacks_once();
+
+    // A plugin that registers timers without implementing the extension is caught by the host's
+    // timer dispatcher
+    let num_registered_timers = plugin.state.next_timer_id.load(Ordering::SeqCst);
+    if num_registered_timers == 0 {
+        return Ok(if implements_timer_support {
+            TestStatus::Skipped {
+                reason: SkipReason::UnsupportedFeature,
+                details: Some(String::from(
+                    "The plugin did not register any timers during initialization.",
+                )),
+            }
+        } else {
+            TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    TimerSupport::EXTENSION_ID.to_str().unwrap(),
+                )),
+            }
+        });
+    }
+
+    // The timers are dispatched on this thread until the other thread tells the host to stop
+    let callback_task_sender = host.callback_task_sender.clone();
+    let stop_thread = std::thread::spawn(move || {
+        std::thread::sleep(TIMER_DISPATCH_DURATION);
+        callback_task_sender.send(CallbackTask::Stop).unwrap();
+    });
+    host.handle_callbacks_blocking();
+    stop_thread.join().unwrap();
+
+    let num_timer_ticks = plugin.state.num_timer_ticks.load(Ordering::SeqCst);
+    let instance_state = plugin.state.clone();
+    drop(plugin);
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    let mut remaining_timer_ids: Vec<clap_id> =
+        instance_state.timers.lock().keys().copied().collect();
+    remaining_timer_ids.sort_unstable();
+    if !remaining_timer_ids.is_empty() {
+        return Ok(TestStatus::Failed {
+            details: Some(format!(
+                "The plugin did not unregister the timers with IDs {} before it was destroyed. \
+                 Timers registered through 'clap_host_timer_support::register_timer()' need to \
+                 be unregistered using 'clap_host_timer_support::unregister_timer()'.",
+                remaining_timer_ids
+                    .iter()
+                    .map(|timer_id| timer_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            causes: Vec::new(),
+        });
+    }
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "The plugin registered {num_registered_timers} timer(s), and \
+             'clap_plugin_timer_support::on_timer()' was called {num_timer_ticks} times over {} \
+             ms.",
+            TIMER_DISPATCH_DURATION.as_millis()
+        )),
+    })
+}
@@ -0,0 +1,52 @@
+//! Tests for the `track-info` draft extension.
+
+use anyhow::{Context, Result};
+use std::sync::atomic::Ordering;
+
+use crate::plugin::ext::track_info::TrackInfo;
+use crate::plugin::ext::Extension;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::library::PluginLibrary;
+use crate::tests::{SkipReason, TestStatus};
+
+/// The test for `PluginTestCase::TrackInfoChanged`. Calls `clap_plugin_track_info::changed()` on
+/// the main thread and handles the callbacks the plugin makes in response. The host's
+/// `clap_host_track_info::get()` implementation checks that the plugin queries the track
+/// information from the main thread.
+pub fn test_track_info_changed(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let track_info = match plugin.get_extension::<TrackInfo>() {
+        Some(track_info) => track_info,
+        None => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' extension.",
+                    TrackInfo::EXTENSION_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+    host.handle_callbacks_once();
+
+    let num_queries_before = plugin.state.num_track_info_queries.load(Ordering::SeqCst);
+    track_info.changed();
+    host.handle_callbacks_once();
+    let num_queries =
+        plugin.state.num_track_info_queries.load(Ordering::SeqCst) - num_queries_before;
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "The plugin called 'clap_host_track_info::get()' {num_queries} time(s) after \
+             'clap_plugin_track_info::changed()' was called."
+        )),
+    })
+}
@@ -0,0 +1,182 @@
+//! Tests that check whether the plugin depends on the host's current working directory.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::plugin::ext::audio_ports::{AudioPortConfig, AudioPorts};
+use crate::plugin::ext::state::State;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::instance::process::ProcessConfig;
+use crate::plugin::library::PluginLibrary;
+use crate::tests::rng::new_prng;
+use crate::tests::TestStatus;
+use crate::util;
+
+use super::processing::ProcessingTest;
+
+/// The number of processing cycles run while the working directory is changed.
+const WORKING_DIRECTORY_NUM_BLOCKS: usize = 5;
+
+/// Changes the process' working directory to a read-only directory, and restores the old working
+/// directory and the directory's permissions when dropped. This is also restored if the test fails
+/// halfway through so the validator can continue running tests in-process.
+struct WorkingDirectoryGuard {
+    directory: PathBuf,
+    previous_directory: PathBuf,
+}
+
+impl WorkingDirectoryGuard {
+    /// Create an empty read-only directory at `directory`, and make that the current working
+    /// directory.
+    fn new(directory: PathBuf) -> Result<Self> {
+        if directory.exists() {
+            set_readonly(&directory, false)?;
+            fs::remove_dir_all(&directory)
+                .context("Could not remove the old temporary working directory")?;
+        }
+        fs::create_dir_all(&directory)
+            .context("Could not create the temporary working directory")?;
+        set_readonly(&directory, true)?;
+
+        let previous_directory =
+            std::env::current_dir().context("Could not get the current working directory")?;
+        std::env::set_current_dir(&directory).with_context(|| {
+            format!(
+                "Could not change the working directory to '{}'",
+                directory.display()
+            )
+        })?;
+
+        Ok(WorkingDirectoryGuard {
+            directory,
+            previous_directory,
+        })
+    }
+
+    /// The names of all files and directories the plugin created in the working directory.
+    fn created_entries(&self) -> Result<Vec<String>> {
+        let mut entries = fs::read_dir(&self.directory)
+            .context("Could not list the contents of the temporary working directory")?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        entries.sort();
+
+        Ok(entries)
+    }
+}
+
+impl Drop for WorkingDirectoryGuard {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.previous_directory);
+        let _ = set_readonly(&self.directory, false);
+    }
+}
+
+/// The test for `PluginTestCase::WorkingDirectoryIndependence`. Changes the working directory to
+/// an empty read-only directory, and then creates, initializes, activates, and processes audio
+/// with the plugin from that directory. If the plugin supports the `state` extension, its state
+/// is also saved and reloaded. Plugins should locate their resources relative to the path passed
+/// to `clap_entry::init()` or some other absolute location, and they should never write files to
+/// the working directory. Since file permissions are not enforced everywhere (e.g. on Windows, or
+/// when running as root), the test also fails if any files appear in the directory.
+pub fn test_working_directory_independence(
+    library: &PluginLibrary,
+    plugin_id: &str,
+) -> Result<TestStatus> {
+    let working_directory = WorkingDirectoryGuard::new(
+        util::validator_temp_dir()
+            .join(plugin_id)
+            .join("working-directory"),
+    )?;
+
+    let status = run_plugin(library, plugin_id).with_context(|| {
+        format!(
+            "The plugin failed while the working directory was set to the read-only directory \
+             '{}'. Plugins should not rely on the working directory to locate their resources.",
+            working_directory.directory.display()
+        )
+    })?;
+
+    let created_entries = working_directory.created_entries()?;
+    if !created_entries.is_empty() {
+        anyhow::bail!(
+            "The plugin created the following files in the working directory '{}': {}. Plugins \
+             should not write to the host's working directory.",
+            working_directory.directory.display(),
+            created_entries
+                .iter()
+                .map(|name| format!("'{name}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    Ok(status)
+}
+
+/// The part of [`test_working_directory_independence()`] that interacts with the plugin. The
+/// plugin instance is destroyed before this function returns.
+fn run_plugin(library: &PluginLibrary, plugin_id: &str) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let host = Host::new(HostConfig::default());
+    let plugin = library
+        .create_plugin(plugin_id, host.clone())
+        .context("Could not create the plugin instance")?;
+    plugin.init().context("Error during initialization")?;
+
+    let audio_ports_config = match plugin.get_extension::<AudioPorts>() {
+        Some(audio_ports) => audio_ports
+            .config()
+            .context("Error while querying 'audio-ports' IO configuration")?,
+        None => AudioPortConfig::default(),
+    };
+    host.handle_callbacks_once();
+
+    let (mut input_buffers, mut output_buffers) = audio_ports_config.create_buffers(512);
+    let stats = ProcessingTest::new_out_of_place(&plugin, &mut input_buffers, &mut output_buffers)?
+        .run(
+            WORKING_DIRECTORY_NUM_BLOCKS,
+            ProcessConfig::default(),
+            |process_data| {
+                process_data.buffers.randomize(&mut prng);
+
+                Ok(())
+            },
+        )?;
+
+    if let Some(state) = plugin.get_extension::<State>() {
+        let state_bytes = state
+            .save()
+            .context("Error while saving the plugin's state")?;
+        state
+            .load(&state_bytes)
+            .context("Error while reloading the plugin's state")?;
+        host.handle_callbacks_once();
+    }
+
+    drop(plugin);
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")?;
+    Ok(stats.status())
+}
+
+/// Change whether `path` is read-only.
+fn set_readonly(path: &Path, readonly: bool) -> Result<()> {
+    let mut permissions = fs::metadata(path)
+        .with_context(|| format!("Could not get the permissions of '{}'", path.display()))?
+        .permissions();
+    // `Permissions::set_readonly(false)` would make the directory world-writable on Unix-likes
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        permissions.set_mode(if readonly { 0o555 } else { 0o755 });
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(readonly);
+    fs::set_permissions(path, permissions)
+        .with_context(|| format!("Could not change the permissions of '{}'", path.display()))
+}
@@ -4,8 +4,9 @@ use clap::ValueEnum;
 use std::path::Path;
 use std::process::Command;
 use std::time::Duration;
+use strum::IntoEnumIterator;
 
-use super::{TestCase, TestResult};
+use super::{Check, TestCase, TestResult};
 
 mod factories;
 mod preset_discovery;
@@ -24,20 +25,38 @@ pub enum PluginLibraryTestCase {
     PresetDiscoveryDescriptorConsistency,
     #[strum(serialize = "preset-discovery-load")]
     PresetDiscoveryLoad,
+    #[strum(serialize = "preset-discovery-internal-locations")]
+    PresetDiscoveryInternalLocations,
+    #[strum(serialize = "preset-load")]
+    PresetLoad,
     #[strum(serialize = "scan-time")]
     ScanTime,
     #[strum(serialize = "scan-rtld-now")]
     ScanRtldNow,
+    #[strum(serialize = "scan-descriptor-ids")]
+    ScanDescriptorIds,
+    #[strum(serialize = "library-reload")]
+    LibraryReload,
     #[strum(serialize = "query-factory-nonexistent")]
     QueryNonexistentFactory,
+    #[strum(serialize = "query-factory-consistency")]
+    QueryFactoryConsistency,
+    #[strum(serialize = "query-factory-known")]
+    QueryKnownFactories,
     #[strum(serialize = "create-id-with-trailing-garbage")]
     CreateIdWithTrailingGarbage,
+    #[strum(serialize = "factory-concurrent-access")]
+    ConcurrentFactoryAccess,
 }
 
 impl<'a> TestCase<'a> for PluginLibraryTestCase {
     /// The path to a CLAP plugin library.
     type TestArgs = &'a Path;
 
+    fn all() -> Vec<Self> {
+        PluginLibraryTestCase::iter().collect()
+    }
+
     fn description(&self) -> String {
         match self {
             PluginLibraryTestCase::PresetDiscoveryCrawl => String::from(
@@ -54,6 +73,18 @@ impl<'a> TestCase<'a> for PluginLibraryTestCase {
                  presets, and the process function is called after loading each preset.",
                 PluginLibraryTestCase::PresetDiscoveryCrawl
             ),
+            PluginLibraryTestCase::PresetDiscoveryInternalLocations => String::from(
+                "Crawls all 'CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN' locations declared by the \
+                 plugin's preset providers, and checks that each of them contains at least one \
+                 preset.",
+            ),
+            PluginLibraryTestCase::PresetLoad => format!(
+                "Loads a random sample of up to {} of the presets found through the preset \
+                 discovery factory for every plugin in the library. The plugin needs to call \
+                 'clap_host_preset_load::loaded()' for every preset it loads, and loading \
+                 different presets should change the plugin's state.",
+                preset_discovery::PRESET_LOAD_SAMPLE_SIZE
+            ),
             PluginLibraryTestCase::ScanTime => format!(
                 "Checks whether the plugin can be scanned in under {} milliseconds.",
                 SCAN_TIME_LIMIT.as_millis()
@@ -62,14 +93,94 @@ impl<'a> TestCase<'a> for PluginLibraryTestCase {
                 "Checks whether the plugin loads correctly when loaded using 'dlopen(..., \
                  RTLD_LOCAL | RTLD_NOW)'. Only run on Unix-like platforms.",
             ),
+            PluginLibraryTestCase::ScanDescriptorIds => String::from(
+                "Enumerates all plugin descriptors from the plugin factory. The test fails if two \
+                 descriptors share the same ID or if a descriptor's ID or name is a null pointer \
+                 or an empty string. A warning is shown if an ID is not in reverse domain name \
+                 notation.",
+            ),
+            PluginLibraryTestCase::LibraryReload => format!(
+                "Loads and unloads the plugin library {} times in a row, and then {} more times \
+                 from as many threads that run one after the other. Every time, the plugin's \
+                 entry point is initialized and deinitialized again, and an instance of every \
+                 plugin is created and initialized. The plugin should not crash on \
+                 reinitialization, and its descriptors should not change.",
+                scanning::RELOAD_NUM_ITERATIONS,
+                scanning::RELOAD_NUM_THREADS
+            ),
             PluginLibraryTestCase::QueryNonexistentFactory => String::from(
                 "Tries to query a factory from the plugin's entry point with a non-existent ID. \
                  This should return a null pointer.",
             ),
+            PluginLibraryTestCase::QueryFactoryConsistency => String::from(
+                "Queries the plugin and preset discovery factories several times in an unusual \
+                 order, interleaved with non-existent factory IDs. The non-existent IDs should \
+                 return null pointers, and the same factory should always return the same pointer.",
+            ),
+            PluginLibraryTestCase::QueryKnownFactories => String::from(
+                "Reports which of the known factories the plugin exposes, including draft and \
+                 third party factories like the plugin invalidation, plugin state converter, and \
+                 ARA factories. This test is purely informational.",
+            ),
             PluginLibraryTestCase::CreateIdWithTrailingGarbage => String::from(
                 "Attempts to create a plugin instance using an existing plugin ID with some extra \
                  text appended to the end. This should return a null pointer.",
             ),
+            PluginLibraryTestCase::ConcurrentFactoryAccess => format!(
+                "Queries the plugin factory and creates instances of all plugins from {} threads \
+                 at the same time. The factory's functions are thread-safe, so this should not \
+                 cause any errors or crashes.",
+                factories::CONCURRENT_ACCESS_NUM_THREADS
+            ),
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            PluginLibraryTestCase::PresetDiscoveryCrawl
+            | PluginLibraryTestCase::PresetDiscoveryDescriptorConsistency
+            | PluginLibraryTestCase::PresetDiscoveryLoad
+            | PluginLibraryTestCase::PresetDiscoveryInternalLocations
+            | PluginLibraryTestCase::PresetLoad => "Preset discovery",
+            PluginLibraryTestCase::ScanTime
+            | PluginLibraryTestCase::ScanRtldNow
+            | PluginLibraryTestCase::ScanDescriptorIds
+            | PluginLibraryTestCase::LibraryReload => "Scanning",
+            PluginLibraryTestCase::QueryNonexistentFactory
+            | PluginLibraryTestCase::QueryFactoryConsistency
+            | PluginLibraryTestCase::QueryKnownFactories
+            | PluginLibraryTestCase::CreateIdWithTrailingGarbage
+            | PluginLibraryTestCase::ConcurrentFactoryAccess => "Factories",
+        }
+    }
+
+    fn checks(&self) -> Vec<Check> {
+        match self {
+            PluginLibraryTestCase::PresetDiscoveryCrawl
+            | PluginLibraryTestCase::PresetDiscoveryDescriptorConsistency
+            | PluginLibraryTestCase::PresetDiscoveryInternalLocations => {
+                vec![Check::LibraryLoading, Check::PresetDiscovery]
+            }
+            PluginLibraryTestCase::PresetDiscoveryLoad | PluginLibraryTestCase::PresetLoad => vec![
+                Check::LibraryLoading,
+                Check::PresetDiscovery,
+                Check::PresetLoading,
+                Check::HostCallbacks,
+            ],
+            PluginLibraryTestCase::ScanTime | PluginLibraryTestCase::ScanRtldNow => {
+                vec![Check::LibraryLoading, Check::ScanTime]
+            }
+            PluginLibraryTestCase::ScanDescriptorIds => {
+                vec![Check::LibraryLoading, Check::PluginDescriptors]
+            }
+            PluginLibraryTestCase::LibraryReload => vec![Check::LibraryLoading],
+            PluginLibraryTestCase::QueryNonexistentFactory
+            | PluginLibraryTestCase::QueryFactoryConsistency
+            | PluginLibraryTestCase::QueryKnownFactories
+            | PluginLibraryTestCase::CreateIdWithTrailingGarbage
+            | PluginLibraryTestCase::ConcurrentFactoryAccess => {
+                vec![Check::LibraryLoading, Check::FactoryBehavior]
+            }
         }
     }
 
@@ -102,14 +213,31 @@ impl<'a> TestCase<'a> for PluginLibraryTestCase {
             PluginLibraryTestCase::PresetDiscoveryLoad => {
                 preset_discovery::test_crawl(library_path, true)
             }
+            PluginLibraryTestCase::PresetDiscoveryInternalLocations => {
+                preset_discovery::test_internal_locations(library_path)
+            }
+            PluginLibraryTestCase::PresetLoad => preset_discovery::test_preset_load(library_path),
             PluginLibraryTestCase::ScanTime => scanning::test_scan_time(library_path),
             PluginLibraryTestCase::ScanRtldNow => scanning::test_scan_rtld_now(library_path),
+            PluginLibraryTestCase::ScanDescriptorIds => {
+                scanning::test_scan_descriptor_ids(library_path)
+            }
+            PluginLibraryTestCase::LibraryReload => scanning::test_library_reload(library_path),
             PluginLibraryTestCase::QueryNonexistentFactory => {
                 factories::test_query_nonexistent_factory(library_path)
             }
+            PluginLibraryTestCase::QueryFactoryConsistency => {
+                factories::test_query_factory_consistency(library_path)
+            }
+            PluginLibraryTestCase::QueryKnownFactories => {
+                factories::test_query_known_factories(library_path)
+            }
             PluginLibraryTestCase::CreateIdWithTrailingGarbage => {
                 factories::test_create_id_with_trailing_garbage(library_path)
             }
+            PluginLibraryTestCase::ConcurrentFactoryAccess => {
+                factories::test_concurrent_factory_access(library_path)
+            }
         };
 
         self.create_result(status)
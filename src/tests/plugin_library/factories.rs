@@ -1,12 +1,21 @@
 //! Tests interacting with the plugin's factories.
 
 use anyhow::{Context, Result};
+use clap_sys::factory::draft::preset_discovery::CLAP_PRESET_DISCOVERY_FACTORY_ID;
+use clap_sys::factory::plugin_factory::CLAP_PLUGIN_FACTORY_ID;
 use clap_sys::version::clap_version_is_compatible;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::ffi::c_void;
 use std::path::Path;
+use std::sync::Barrier;
 
-use crate::plugin::host::Host;
-use crate::plugin::library::PluginLibrary;
-use crate::tests::TestStatus;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::library::{known_factory_ids, PluginLibrary};
+use crate::tests::{SkipReason, TestStatus};
+
+/// The number of threads used in the concurrent factory access test.
+pub const CONCURRENT_ACCESS_NUM_THREADS: usize = 8;
 
 /// The test for `PluginLibraryTestCase::QueryNonexistentFactory`.
 pub fn test_query_nonexistent_factory(library_path: &Path) -> Result<TestStatus> {
@@ -30,6 +39,95 @@ pub fn test_query_nonexistent_factory(library_path: &Path) -> Result<TestStatus>
     }
 }
 
+/// The test for `PluginLibraryTestCase::QueryFactoryConsistency`. Queries the plugin and preset
+/// discovery factories in an unusual order, interleaved with factory IDs that should not exist.
+/// The non-existent IDs should always return null pointers, and every query for the same factory
+/// should return the same pointer.
+pub fn test_query_factory_consistency(library_path: &Path) -> Result<TestStatus> {
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+
+    let plugin_factory_id = CLAP_PLUGIN_FACTORY_ID.to_str().unwrap();
+    let preset_discovery_factory_id = CLAP_PRESET_DISCOVERY_FACTORY_ID.to_str().unwrap();
+    // The truncated and extended versions of the existing factory IDs catch plugins that only
+    // compare a prefix of the ID
+    let nonexistent_factory_ids = [
+        format!("foo-factory-{}", rand::random::<u64>()),
+        String::new(),
+        String::from(&plugin_factory_id[..plugin_factory_id.len() - 1]),
+        format!("{plugin_factory_id}.foo"),
+        format!("{preset_discovery_factory_id}.foo"),
+    ];
+
+    // Hosts usually query the plugin factory first, so this starts with the preset discovery
+    // factory instead and then keeps alternating the order
+    let mut factory_ids = Vec::new();
+    for (idx, nonexistent_factory_id) in nonexistent_factory_ids.iter().enumerate() {
+        if idx % 2 == 0 {
+            factory_ids.extend([preset_discovery_factory_id, plugin_factory_id]);
+        } else {
+            factory_ids.extend([plugin_factory_id, preset_discovery_factory_id]);
+        }
+        factory_ids.push(nonexistent_factory_id.as_str());
+    }
+
+    let mut factory_pointers: HashMap<&str, *const c_void> = HashMap::new();
+    for factory_id in factory_ids {
+        let factory_pointer = library.factory_pointer(factory_id);
+        if nonexistent_factory_ids.iter().any(|id| id == factory_id) {
+            if !factory_pointer.is_null() {
+                anyhow::bail!(
+                    "Querying a factory with the non-existent factory ID '{factory_id}' should \
+                     return a null pointer, but the plugin returned a non-null pointer instead."
+                );
+            }
+
+            continue;
+        }
+
+        match factory_pointers.entry(factory_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(factory_pointer);
+            }
+            Entry::Occupied(entry) if *entry.get() != factory_pointer => anyhow::bail!(
+                "Querying the '{factory_id}' factory multiple times returned different pointers \
+                 ({:p} and {factory_pointer:p}).",
+                *entry.get()
+            ),
+            Entry::Occupied(_) => (),
+        }
+    }
+
+    Ok(TestStatus::Success { details: None })
+}
+
+/// The test for `PluginLibraryTestCase::QueryKnownFactories`. This is purely informational and
+/// reports which of the known factories the plugin library exposes.
+pub fn test_query_known_factories(library_path: &Path) -> Result<TestStatus> {
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+
+    let (exposed_factory_ids, missing_factory_ids): (Vec<&str>, Vec<&str>) = known_factory_ids()
+        .into_iter()
+        .partition(|factory_id| library.factory_exists(factory_id));
+    let format_ids = |factory_ids: &[&str]| match factory_ids {
+        [] => String::from("(none)"),
+        factory_ids => factory_ids
+            .iter()
+            .map(|factory_id| format!("'{factory_id}'"))
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "Exposed factories: {}. Factories that are not exposed: {}.",
+            format_ids(&exposed_factory_ids),
+            format_ids(&missing_factory_ids)
+        )),
+    })
+}
+
 /// The test for `PluginLibraryTestCase::CreateIdWithTrailingGarbage`.
 pub fn test_create_id_with_trailing_garbage(library_path: &Path) -> Result<TestStatus> {
     let library = PluginLibrary::load(library_path)
@@ -40,6 +138,7 @@ pub fn test_create_id_with_trailing_garbage(library_path: &Path) -> Result<TestS
         .context("Could not query the plugin's metadata")?;
     if !clap_version_is_compatible(metadata.clap_version()) {
         return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedClapVersion,
             details: Some(format!(
                 "'{}' uses an unsupported CLAP version ({}.{}.{})",
                 library_path.display(),
@@ -67,6 +166,7 @@ pub fn test_create_id_with_trailing_garbage(library_path: &Path) -> Result<TestS
                 // intentionally triggering it
                 None => {
                     return Ok(TestStatus::Skipped {
+                        reason: SkipReason::Other,
                         details: Some(String::from(
                             "All of the coolest plugins already exists. In other words, could not \
                              come up a fake unused plugin ID.",
@@ -77,6 +177,7 @@ pub fn test_create_id_with_trailing_garbage(library_path: &Path) -> Result<TestS
         }
         None => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::NoPlugins,
                 details: Some(String::from(
                     "The plugin library does not expose any plugins",
                 )),
@@ -86,7 +187,10 @@ pub fn test_create_id_with_trailing_garbage(library_path: &Path) -> Result<TestS
 
     // This should return an error/null-pointer instead of actually instantiating a
     // plugin
-    if library.create_plugin(&fake_plugin_id, Host::new()).is_ok() {
+    if library
+        .create_plugin(&fake_plugin_id, Host::new(HostConfig::default()))
+        .is_ok()
+    {
         anyhow::bail!(
             "Creating a plugin instance with a non-existent plugin ID '{fake_plugin_id}' should \
              return a null pointer, but it did not."
@@ -95,3 +199,98 @@ pub fn test_create_id_with_trailing_garbage(library_path: &Path) -> Result<TestS
         Ok(TestStatus::Success { details: None })
     }
 }
+
+/// The test for `PluginLibraryTestCase::ConcurrentFactoryAccess`. The plugin factory's functions
+/// are thread-safe, so querying the factory and creating plugin instances from multiple threads at
+/// the same time should work the same as doing it from a single thread.
+pub fn test_concurrent_factory_access(library_path: &Path) -> Result<TestStatus> {
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+
+    let metadata = library
+        .metadata()
+        .context("Could not query the plugin's metadata")?;
+    if !clap_version_is_compatible(metadata.clap_version()) {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedClapVersion,
+            details: Some(format!(
+                "'{}' uses an unsupported CLAP version ({}.{}.{})",
+                library_path.display(),
+                metadata.version.0,
+                metadata.version.1,
+                metadata.version.2
+            )),
+        });
+    }
+    if metadata.plugins.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::NoPlugins,
+            details: Some(String::from(
+                "The plugin library does not expose any plugins",
+            )),
+        });
+    }
+
+    // All threads wait on this barrier so they access the factory at the same time
+    let barrier = Barrier::new(CONCURRENT_ACCESS_NUM_THREADS);
+    let results: Vec<Result<()>> = crossbeam::scope(|s| {
+        let threads: Vec<_> = (0..CONCURRENT_ACCESS_NUM_THREADS)
+            .map(|thread_idx| {
+                let (library, metadata, barrier) = (&library, &metadata, &barrier);
+                s.builder()
+                    .name(format!("factory-thread-{thread_idx}"))
+                    .spawn(move |_| -> Result<()> {
+                        barrier.wait();
+
+                        let thread_metadata = library
+                            .metadata()
+                            .context("Could not query the plugin's metadata")?;
+                        if thread_metadata.plugins != metadata.plugins {
+                            anyhow::bail!(
+                                "The plugin descriptors returned by the factory differ from the \
+                                 ones returned when querying the factory from a single thread."
+                            );
+                        }
+
+                        // Every thread gets its own host, which treats this thread as the main
+                        // thread
+                        let host = Host::new(HostConfig::default());
+                        for plugin_metadata in &metadata.plugins {
+                            let plugin = library
+                                .create_plugin(&plugin_metadata.id, host.clone())
+                                .with_context(|| {
+                                format!("Could not create an instance of '{}'", plugin_metadata.id)
+                            })?;
+                            drop(plugin);
+                        }
+
+                        host.callback_error_check()
+                            .context("An error occured during a host callback")
+                    })
+                    .expect("Unable to spawn a thread")
+            })
+            .collect();
+
+        threads
+            .into_iter()
+            .map(|thread| {
+                thread
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("The thread panicked")))
+            })
+            .collect()
+    })
+    .expect("A factory access thread panicked");
+
+    for (thread_idx, result) in results.into_iter().enumerate() {
+        result.with_context(|| {
+            format!(
+                "Error while accessing the factory from thread {} out of \
+                 {CONCURRENT_ACCESS_NUM_THREADS}",
+                thread_idx + 1
+            )
+        })?;
+    }
+
+    Ok(TestStatus::Success { details: None })
+}
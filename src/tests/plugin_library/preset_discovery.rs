@@ -2,23 +2,41 @@
 
 use anyhow::{Context, Result};
 use clap_sys::factory::draft::preset_discovery::CLAP_PRESET_DISCOVERY_FACTORY_ID;
+use rand::seq::SliceRandom;
 use std::collections::BTreeMap;
 use std::path::Path;
 
+use crate::ipc;
 use crate::plugin::ext::audio_ports::AudioPorts;
 use crate::plugin::ext::preset_load::PresetLoad;
+use crate::plugin::ext::state::State;
 use crate::plugin::ext::Extension;
-use crate::plugin::host::Host;
+use crate::plugin::host::{Host, HostConfig};
 use crate::plugin::instance::process::ProcessConfig;
 use crate::plugin::library::PluginLibrary;
-use crate::plugin::preset_discovery::{LocationValue, PluginAbi, Preset, PresetFile};
+use crate::plugin::preset_discovery::{
+    LocationValue, PluginAbi, Preset, PresetDiscoveryFactory, PresetFile,
+};
 use crate::tests::plugin::ProcessingTest;
-use crate::tests::TestStatus;
+use crate::tests::rng::new_prng;
+use crate::tests::{SkipReason, TestStatus};
 
 // TODO: Test for duplicate locations and soundpacks in declared data across all providers
 
 /// The fixed buffer size to use for these tests.
 const BUFFER_SIZE: usize = 512;
+/// The maximum number of presets loaded per plugin in the `preset-load` test.
+pub const PRESET_LOAD_SAMPLE_SIZE: usize = 8;
+
+/// A single preset that can be loaded using the preset load extension. Because container presets
+/// can contain presets for multiple different plugins storing all presets grouped by plugin ID is
+/// not possible by storing `PresetFiles`s, so this is a simple wrapper around `PresetFile`. The
+/// `Preset` is technically not needed to load the preset, but it's nice for error reporting.
+struct LoadablePreset {
+    location: LocationValue,
+    load_key: Option<String>,
+    preset: Preset,
+}
 
 /// The test for `PluginLibraryTestCase::PresetDiscoveryCrawl`. Makes sure that all of a plugin's
 /// reported preset locations can be crawled successfully. If `load_presets` is enabled, then the
@@ -30,6 +48,7 @@ pub fn test_crawl(library_path: &Path, load_presets: bool) -> Result<TestStatus>
         Ok(preset_discovery_factory) => preset_discovery_factory,
         Err(_) => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' factory.",
                     CLAP_PRESET_DISCOVERY_FACTORY_ID.to_str().unwrap(),
@@ -38,84 +57,22 @@ pub fn test_crawl(library_path: &Path, load_presets: bool) -> Result<TestStatus>
         }
     };
 
-    // All found presets, indexed by location (value)
-    let mut found_presets: BTreeMap<LocationValue, PresetFile> = BTreeMap::new();
+    let found_presets = crawl_presets(&preset_discovery_factory)?;
 
-    let metadata = preset_discovery_factory
+    // Presets for plugins outside of this library are allowed, but they can also be the result of
+    // a copy-paste error in the preset provider so they're mentioned in the test result
+    let library_metadata = library
         .metadata()
-        .context("Could not fetch the preset provider descriptors from the factory")?;
-    for provider_metadata in metadata {
-        let provider = preset_discovery_factory
-            .create_provider(&provider_metadata)
-            .with_context(|| {
-                format!(
-                    "Could not create the provider with ID '{}'",
-                    provider_metadata.id
-                )
-            })?;
-        for location in &provider.declared_data().locations {
-            let presets = provider.crawl_location(location).with_context(|| {
-                format!(
-                    "Error occurred while crawling presets for the location '{}' with {} using \
-                     provider '{}' with ID '{}'",
-                    location.name, location.value, provider_metadata.name, provider_metadata.id,
-                )
-            })?;
-            found_presets.extend(presets);
-        }
-    }
+        .context("Could not fetch the plugin library's metadata")?;
+    let external_plugin_ids =
+        crate::index::external_clap_plugin_ids(found_presets.values(), &library_metadata);
 
     // After crawling, group the presets by CLAP plugin ID and try to load them
     if load_presets {
-        // Because container presets can contain presets for multiple different plugins storing all
-        // presets grouped by plugin ID is not possible by storing `PresetFiles`s. So this is a
-        // simple wrapper around `PresetFile` to use with the preset load extension. The `Preset` is
-        // technically not needed anymore but it's nice for error reporting.
-        struct LoadablePreset {
-            location: LocationValue,
-            load_key: Option<String>,
-            preset: Preset,
-        }
-
-        // Stores `PresetFile`s with their associated locations for all CLAP plugin IDs in
-        // `found_presets`
-        let mut loadable_presets_by_plugin_id: BTreeMap<String, Vec<LoadablePreset>> =
-            BTreeMap::new();
-        let mut maybe_add_preset =
-            |location: &LocationValue, load_key: Option<String>, preset: Preset| {
-                for plugin_id in &preset.plugin_ids {
-                    if plugin_id.abi == PluginAbi::Clap {
-                        if !loadable_presets_by_plugin_id.contains_key(&plugin_id.id) {
-                            loadable_presets_by_plugin_id.insert(plugin_id.id.clone(), Vec::new());
-                        }
-
-                        loadable_presets_by_plugin_id
-                            .get_mut(&plugin_id.id)
-                            .unwrap()
-                            .push(LoadablePreset {
-                                location: location.clone(),
-                                load_key: load_key.clone(),
-                                preset: preset.clone(),
-                            })
-                    }
-                }
-            };
-
-        for (location, preset_file) in found_presets {
-            match preset_file {
-                PresetFile::Single(preset) => maybe_add_preset(&location, None, preset),
-                PresetFile::Container(presets) => {
-                    for (load_key, preset) in presets {
-                        maybe_add_preset(&location, Some(load_key), preset);
-                    }
-                }
-            }
-        }
-
         // With everything indexed, we can try loading these presets. We'll reuse one plugin
         // instance per plugin.
-        for (plugin_id, presets) in loadable_presets_by_plugin_id {
-            let host = Host::new();
+        for (plugin_id, presets) in loadable_presets_by_plugin_id(found_presets) {
+            let host = Host::new(HostConfig::default());
             let plugin = library
                 .create_plugin(&plugin_id, host.clone())
                 .with_context(|| format!("Could not create a plugin instance for '{plugin_id}'"))?;
@@ -127,6 +84,7 @@ pub fn test_crawl(library_path: &Path, load_presets: bool) -> Result<TestStatus>
                 Some(preset_load) => preset_load,
                 None => {
                     return Ok(TestStatus::Skipped {
+                        reason: SkipReason::MissingExtension,
                         details: Some(format!(
                             "'{}' does not implement the '{}' extension.",
                             plugin_id,
@@ -169,7 +127,7 @@ pub fn test_crawl(library_path: &Path, load_presets: bool) -> Result<TestStatus>
                 // In case the plugin uses `clap_host_preset_load::on_error()` to report an error,
                 // we will check that first before making sure the preset loaded correctly. This
                 // might otherwise mask the error message.
-                host.handle_callbacks_once();
+                host.handle_callbacks_until_idle();
                 host.callback_error_check().with_context(|| {
                     format!(
                         "An error occurred while loading the preset '{}' for plugin '{}'",
@@ -203,7 +161,31 @@ pub fn test_crawl(library_path: &Path, load_presets: bool) -> Result<TestStatus>
         }
     }
 
-    Ok(TestStatus::Success { details: None })
+    if external_plugin_ids.is_empty() {
+        Ok(TestStatus::Success { details: None })
+    } else {
+        Ok(TestStatus::Success {
+            details: Some(format!(
+                "Presets were declared for {} that {} not part of this library: {}. This is fine \
+                 if the plugin's preset providers index presets for other plugins.",
+                if external_plugin_ids.len() == 1 {
+                    "a CLAP plugin ID"
+                } else {
+                    "CLAP plugin IDs"
+                },
+                if external_plugin_ids.len() == 1 {
+                    "is"
+                } else {
+                    "are"
+                },
+                external_plugin_ids
+                    .iter()
+                    .map(|plugin_id| format!("'{plugin_id}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        })
+    }
 }
 
 /// The test for `PluginLibraryTestCase::PresetDiscoveryDescriptorConsistency`. Verifies that the
@@ -215,6 +197,7 @@ pub fn test_descriptor_consistency(library_path: &Path) -> Result<TestStatus> {
         Ok(preset_discovery_factory) => preset_discovery_factory,
         Err(_) => {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
                 details: Some(format!(
                     "The plugin does not implement the '{}' factory.",
                     CLAP_PRESET_DISCOVERY_FACTORY_ID.to_str().unwrap(),
@@ -255,3 +238,356 @@ pub fn test_descriptor_consistency(library_path: &Path) -> Result<TestStatus> {
 
     Ok(TestStatus::Success { details: None })
 }
+
+/// The test for `PluginLibraryTestCase::PresetDiscoveryInternalLocations`. Crawls all internal
+/// `CLAP_PRESET_DISCOVERY_LOCATION_PLUGIN` locations, and fails if any of them don't contain any
+/// presets. Declaring an internal location without any presets is almost certainly a mistake.
+pub fn test_internal_locations(library_path: &Path) -> Result<TestStatus> {
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+    let preset_discovery_factory = match library.preset_discovery_factory() {
+        Ok(preset_discovery_factory) => preset_discovery_factory,
+        Err(_) => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' factory.",
+                    CLAP_PRESET_DISCOVERY_FACTORY_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+
+    let mut num_internal_locations = 0;
+    let mut num_internal_presets = 0;
+    let metadata = preset_discovery_factory
+        .metadata()
+        .context("Could not fetch the preset provider descriptors from the factory")?;
+    for provider_metadata in metadata {
+        let provider = preset_discovery_factory
+            .create_provider(&provider_metadata)
+            .with_context(|| {
+                format!(
+                    "Could not create the provider with ID '{}'",
+                    provider_metadata.id
+                )
+            })?;
+        for location in &provider.declared_data().locations {
+            if location.value != LocationValue::Internal {
+                continue;
+            }
+
+            num_internal_locations += 1;
+            let presets = provider.crawl_location(location).with_context(|| {
+                format!(
+                    "Error occurred while crawling presets for the location '{}' with {} using \
+                     provider '{}' with ID '{}'",
+                    location.name, location.value, provider_metadata.name, provider_metadata.id,
+                )
+            })?;
+
+            let num_presets: usize = presets
+                .values()
+                .map(|preset_file| match preset_file {
+                    PresetFile::Single(_) => 1,
+                    PresetFile::Container(presets) => presets.len(),
+                })
+                .sum();
+            if num_presets == 0 {
+                anyhow::bail!(
+                    "The provider '{}' with ID '{}' declared the internal location '{}' with {}, \
+                     but it did not declare any presets for it.",
+                    provider_metadata.name,
+                    provider_metadata.id,
+                    location.name,
+                    location.value,
+                );
+            }
+
+            num_internal_presets += num_presets;
+        }
+    }
+
+    if num_internal_locations == 0 {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
+            details: Some(String::from(
+                "The plugin's preset providers don't declare any internal locations.",
+            )),
+        });
+    }
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "Found {num_internal_presets} {} in {num_internal_locations} internal {}.",
+            if num_internal_presets == 1 {
+                "preset"
+            } else {
+                "presets"
+            },
+            if num_internal_locations == 1 {
+                "location"
+            } else {
+                "locations"
+            },
+        )),
+    })
+}
+
+/// The test for `PluginLibraryTestCase::PresetLoad`. Loads a random sample of the presets found by
+/// the preset discovery factory for every plugin in the library. The plugin needs to confirm every
+/// successfully loaded preset using `clap_host_preset_load::loaded()`, and loading different
+/// presets should change the plugin's state.
+pub fn test_preset_load(library_path: &Path) -> Result<TestStatus> {
+    let mut prng = new_prng();
+
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+    let preset_discovery_factory = match library.preset_discovery_factory() {
+        Ok(preset_discovery_factory) => preset_discovery_factory,
+        Err(_) => {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::MissingExtension,
+                details: Some(format!(
+                    "The plugin does not implement the '{}' factory.",
+                    CLAP_PRESET_DISCOVERY_FACTORY_ID.to_str().unwrap(),
+                )),
+            })
+        }
+    };
+
+    // Presets for plugins from other libraries can't be loaded here
+    let found_presets = crawl_presets(&preset_discovery_factory)?;
+    let library_metadata = library
+        .metadata()
+        .context("Could not fetch the plugin library's metadata")?;
+    let external_plugin_ids =
+        crate::index::external_clap_plugin_ids(found_presets.values(), &library_metadata);
+    let loadable_presets_by_plugin_id: BTreeMap<String, Vec<LoadablePreset>> =
+        loadable_presets_by_plugin_id(found_presets)
+            .into_iter()
+            .filter(|(plugin_id, _)| !external_plugin_ids.contains(plugin_id))
+            .collect();
+    if loadable_presets_by_plugin_id.is_empty() {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedFeature,
+            details: Some(String::from(
+                "The plugin's preset providers did not declare any presets for the plugins in \
+                 this library.",
+            )),
+        });
+    }
+
+    let mut num_loaded_presets = 0;
+    let mut unchanged_state_plugin_ids = Vec::new();
+    for (plugin_id, presets) in &loadable_presets_by_plugin_id {
+        let host = Host::new(HostConfig::default());
+        let plugin = library
+            .create_plugin(plugin_id, host.clone())
+            .with_context(|| format!("Could not create a plugin instance for '{plugin_id}'"))?;
+        plugin
+            .init()
+            .with_context(|| format!("Error while initializing '{plugin_id}'"))?;
+
+        let preset_load = match plugin.get_extension::<PresetLoad>() {
+            Some(preset_load) => preset_load,
+            None => {
+                return Ok(TestStatus::Skipped {
+                    reason: SkipReason::MissingExtension,
+                    details: Some(format!(
+                        "'{}' does not implement the '{}' extension.",
+                        plugin_id,
+                        PresetLoad::EXTENSION_ID.to_str().unwrap(),
+                    )),
+                })
+            }
+        };
+        // The state extension is optional, but if the plugin supports it then loading a preset
+        // should be reflected in the plugin's state
+        let state = plugin.get_extension::<State>();
+        host.handle_callbacks_once();
+
+        let mut previous_state = state
+            .as_ref()
+            .map(|state| state.save())
+            .transpose()
+            .with_context(|| format!("Could not save the initial state for '{plugin_id}'"))?;
+        let mut num_state_changes = 0;
+        let sampled_presets: Vec<&LoadablePreset> = presets
+            .choose_multiple(&mut prng, PRESET_LOAD_SAMPLE_SIZE)
+            .collect();
+        for LoadablePreset {
+            location,
+            load_key,
+            preset,
+        } in &sampled_presets
+        {
+            plugin.state.loaded_presets.lock().clear();
+            let load_result = preset_load
+                .from_location(location, load_key.as_deref())
+                .with_context(|| {
+                    format!(
+                        "Could not load the preset '{}' for plugin '{}'",
+                        preset.name, plugin_id
+                    )
+                });
+
+            // Errors reported through `clap_host_preset_load::on_error()` are checked first since
+            // they likely explain why loading the preset failed
+            host.handle_callbacks_until_idle();
+            host.callback_error_check().with_context(|| {
+                format!(
+                    "An error occurred while loading the preset '{}' for plugin '{}'",
+                    preset.name, plugin_id
+                )
+            })?;
+            load_result?;
+
+            let loaded_presets = std::mem::take(&mut *plugin.state.loaded_presets.lock());
+            if !loaded_presets.contains(&(location.clone(), load_key.clone())) {
+                anyhow::bail!(
+                    "'clap_plugin_preset_load::from_location()' returned true for the preset '{}' \
+                     with {}{}, but '{}' did not call 'clap_host_preset_load::loaded()' for that \
+                     preset.",
+                    preset.name,
+                    location,
+                    match load_key {
+                        Some(load_key) => format!(" and load key '{load_key}'"),
+                        None => String::new(),
+                    },
+                    plugin_id
+                );
+            }
+            num_loaded_presets += 1;
+
+            if let Some(state) = &state {
+                let new_state = state.save().with_context(|| {
+                    format!(
+                        "Could not save the state for '{}' after loading the preset '{}'",
+                        plugin_id, preset.name
+                    )
+                })?;
+                if previous_state.as_ref() != Some(&new_state) {
+                    num_state_changes += 1;
+                }
+                previous_state = Some(new_state);
+            }
+        }
+
+        host.handle_callbacks_once();
+        host.callback_error_check().with_context(|| {
+            format!("An error occured during a host callback made by '{plugin_id}'")
+        })?;
+
+        // Two presets can be identical, but if none of the loaded presets changed the plugin's
+        // state then the presets were most likely not loaded at all
+        if state.is_some() && sampled_presets.len() > 1 && num_state_changes == 0 {
+            unchanged_state_plugin_ids.push(plugin_id.clone());
+        }
+    }
+
+    if !unchanged_state_plugin_ids.is_empty() {
+        return Ok(TestStatus::Warning {
+            details: Some(format!(
+                "Loading different presets did not change the state of {}. Either all of the \
+                 loaded presets are identical, or the presets were not actually loaded.",
+                unchanged_state_plugin_ids
+                    .iter()
+                    .map(|plugin_id| format!("'{plugin_id}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        });
+    }
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "Loaded {num_loaded_presets} {} for {} {}.",
+            if num_loaded_presets == 1 {
+                "preset"
+            } else {
+                "presets"
+            },
+            loadable_presets_by_plugin_id.len(),
+            if loadable_presets_by_plugin_id.len() == 1 {
+                "plugin"
+            } else {
+                "plugins"
+            },
+        )),
+    })
+}
+
+/// Crawl all locations declared by all of the factory's preset providers. The found presets are
+/// indexed by location (value).
+fn crawl_presets(
+    preset_discovery_factory: &PresetDiscoveryFactory,
+) -> Result<BTreeMap<LocationValue, PresetFile>> {
+    let mut found_presets: BTreeMap<LocationValue, PresetFile> = BTreeMap::new();
+
+    let metadata = preset_discovery_factory
+        .metadata()
+        .context("Could not fetch the preset provider descriptors from the factory")?;
+    for provider_metadata in metadata {
+        let provider = preset_discovery_factory
+            .create_provider(&provider_metadata)
+            .with_context(|| {
+                format!(
+                    "Could not create the provider with ID '{}'",
+                    provider_metadata.id
+                )
+            })?;
+        for location in &provider.declared_data().locations {
+            ipc::progress(format!(
+                "crawling the preset location '{}' with provider '{}'",
+                location.name, provider_metadata.id
+            ));
+            let presets = provider.crawl_location(location).with_context(|| {
+                format!(
+                    "Error occurred while crawling presets for the location '{}' with {} using \
+                     provider '{}' with ID '{}'",
+                    location.name, location.value, provider_metadata.name, provider_metadata.id,
+                )
+            })?;
+            found_presets.extend(presets);
+        }
+    }
+
+    Ok(found_presets)
+}
+
+/// Group the presets from [`crawl_presets()`] by CLAP plugin ID. Presets for other plugin ABIs are
+/// ignored.
+fn loadable_presets_by_plugin_id(
+    found_presets: BTreeMap<LocationValue, PresetFile>,
+) -> BTreeMap<String, Vec<LoadablePreset>> {
+    let mut loadable_presets_by_plugin_id: BTreeMap<String, Vec<LoadablePreset>> = BTreeMap::new();
+    let mut maybe_add_preset =
+        |location: &LocationValue, load_key: Option<String>, preset: Preset| {
+            for plugin_id in &preset.plugin_ids {
+                if plugin_id.abi == PluginAbi::Clap {
+                    loadable_presets_by_plugin_id
+                        .entry(plugin_id.id.clone())
+                        .or_default()
+                        .push(LoadablePreset {
+                            location: location.clone(),
+                            load_key: load_key.clone(),
+                            preset: preset.clone(),
+                        })
+                }
+            }
+        };
+
+    for (location, preset_file) in found_presets {
+        match preset_file {
+            PresetFile::Single(preset) => maybe_add_preset(&location, None, preset),
+            PresetFile::Container(presets) => {
+                for (load_key, preset) in presets {
+                    maybe_add_preset(&location, Some(load_key), preset);
+                }
+            }
+        }
+    }
+
+    loadable_presets_by_plugin_id
+}
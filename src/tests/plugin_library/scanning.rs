@@ -6,8 +6,16 @@ use std::path::Path;
 use std::time::Instant;
 
 use super::SCAN_TIME_LIMIT;
-use crate::plugin::library::PluginLibrary;
-use crate::tests::TestStatus;
+use crate::plugin::host::{Host, HostConfig};
+use crate::plugin::library::{PluginLibrary, PluginLibraryMetadata};
+use crate::tests::{SkipReason, TestStatus};
+
+/// The number of times the library is loaded and unloaded on the main thread in the
+/// `library-reload` test.
+pub const RELOAD_NUM_ITERATIONS: usize = 5;
+/// The number of threads that load and unload the library one after the other in the
+/// `library-reload` test.
+pub const RELOAD_NUM_THREADS: usize = 2;
 
 /// The test for `PluginLibraryTestCase::ScanTime`.
 pub fn test_scan_time(library_path: &Path) -> Result<TestStatus> {
@@ -28,6 +36,7 @@ pub fn test_scan_time(library_path: &Path) -> Result<TestStatus> {
         })?;
         if !clap_version_is_compatible(metadata.clap_version()) {
             return Ok(TestStatus::Skipped {
+                reason: SkipReason::UnsupportedClapVersion,
                 details: Some(format!(
                     "'{}' uses an unsupported CLAP version ({}.{}.{})",
                     library_path.display(),
@@ -66,6 +75,170 @@ pub fn test_scan_time(library_path: &Path) -> Result<TestStatus> {
     }
 }
 
+/// The test for `PluginLibraryTestCase::ScanDescriptorIds`.
+pub fn test_scan_descriptor_ids(library_path: &Path) -> Result<TestStatus> {
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+
+    // This fails if the factory contains duplicate IDs. Descriptors with null or empty IDs or names
+    // are returned separately so all of them can be listed at once.
+    let (metadata, broken_descriptors) = library
+        .metadata_lenient()
+        .context("Could not query the plugin's metadata")?;
+    if !clap_version_is_compatible(metadata.clap_version()) {
+        return Ok(TestStatus::Skipped {
+            reason: SkipReason::UnsupportedClapVersion,
+            details: Some(format!(
+                "'{}' uses an unsupported CLAP version ({}.{}.{})",
+                library_path.display(),
+                metadata.version.0,
+                metadata.version.1,
+                metadata.version.2
+            )),
+        });
+    }
+
+    if !broken_descriptors.is_empty() {
+        anyhow::bail!(
+            "The plugin factory contains invalid descriptors: {}",
+            broken_descriptors
+                .iter()
+                .map(|(index, err)| format!("descriptor {index}: {err:#}"))
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+
+    // Plugin IDs should look like `com.vendor.plugin`. This is only a convention, so it's not
+    // treated as an error.
+    let unconventional_ids: Vec<String> = metadata
+        .plugins
+        .iter()
+        .filter(|plugin| !is_reverse_domain_name(&plugin.id))
+        .map(|plugin| format!("'{}'", plugin.id))
+        .collect();
+    if !unconventional_ids.is_empty() {
+        return Ok(TestStatus::Warning {
+            details: Some(format!(
+                "The following plugin IDs are not in reverse domain name notation (e.g. \
+                 'com.vendor.plugin'): {}.",
+                unconventional_ids.join(", ")
+            )),
+        });
+    }
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "All {} plugin descriptors have unique IDs.",
+            metadata.plugins.len()
+        )),
+    })
+}
+
+/// Checks whether an ID consists of at least two non-empty dot-separated segments without any
+/// whitespace.
+fn is_reverse_domain_name(id: &str) -> bool {
+    id.contains('.')
+        && !id.contains(char::is_whitespace)
+        && id.split('.').all(|segment| !segment.is_empty())
+}
+
+/// The test for `PluginLibraryTestCase::LibraryReload`. Hosts may load and unload a plugin library
+/// many times during their lifetime, for instance when rescanning plugins. Every load
+/// reinitializes the library's entry point, so global state left behind by a previous
+/// `clap_entry::deinit()` call can cause crashes or different behavior on the next load.
+pub fn test_library_reload(library_path: &Path) -> Result<TestStatus> {
+    // The descriptors from the first load are used as a reference for every following load
+    let metadata = {
+        let library = PluginLibrary::load(library_path)
+            .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+        let metadata = library
+            .metadata()
+            .context("Could not query the plugin's metadata")?;
+        if !clap_version_is_compatible(metadata.clap_version()) {
+            return Ok(TestStatus::Skipped {
+                reason: SkipReason::UnsupportedClapVersion,
+                details: Some(format!(
+                    "'{}' uses an unsupported CLAP version ({}.{}.{})",
+                    library_path.display(),
+                    metadata.version.0,
+                    metadata.version.1,
+                    metadata.version.2
+                )),
+            });
+        }
+
+        metadata
+    };
+
+    for iteration in 1..=RELOAD_NUM_ITERATIONS {
+        reload_library(library_path, &metadata).with_context(|| {
+            format!(
+                "Error while reloading the plugin library on the main thread ({iteration} out of \
+                 {RELOAD_NUM_ITERATIONS})"
+            )
+        })?;
+    }
+
+    // The threads run one after the other, so the library is never loaded twice at the same time
+    for thread_idx in 0..RELOAD_NUM_THREADS {
+        crossbeam::scope(|s| {
+            s.builder()
+                .name(format!("reload-thread-{thread_idx}"))
+                .spawn(|_| reload_library(library_path, &metadata))
+                .expect("Unable to spawn a thread")
+                .join()
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("The thread panicked")))
+        })
+        .expect("A library reload thread panicked")
+        .with_context(|| {
+            format!(
+                "Error while reloading the plugin library from thread {} out of \
+                 {RELOAD_NUM_THREADS}",
+                thread_idx + 1
+            )
+        })?;
+    }
+
+    Ok(TestStatus::Success {
+        details: Some(format!(
+            "The plugin library was loaded and unloaded {} times.",
+            1 + RELOAD_NUM_ITERATIONS + RELOAD_NUM_THREADS
+        )),
+    })
+}
+
+/// Load the plugin library, check that its descriptors match `expected_metadata`, create and
+/// initialize an instance of every plugin, and then unload the library again. The current thread is
+/// treated as the main thread.
+fn reload_library(library_path: &Path, expected_metadata: &PluginLibraryMetadata) -> Result<()> {
+    let library = PluginLibrary::load(library_path)
+        .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+    let metadata = library
+        .metadata()
+        .context("Could not query the plugin's metadata")?;
+    if metadata.plugins != expected_metadata.plugins {
+        anyhow::bail!(
+            "The plugin descriptors differ from the ones returned when the library was first \
+             loaded."
+        );
+    }
+
+    let host = Host::new(HostConfig::default());
+    for plugin_metadata in &metadata.plugins {
+        let plugin = library
+            .create_plugin(&plugin_metadata.id, host.clone())
+            .with_context(|| format!("Could not create an instance of '{}'", plugin_metadata.id))?;
+        plugin
+            .init()
+            .with_context(|| format!("Error while initializing '{}'", plugin_metadata.id))?;
+        host.handle_callbacks_once();
+    }
+
+    host.callback_error_check()
+        .context("An error occured during a host callback")
+}
+
 /// The test for `PluginLibraryTestCase::ScanRtldNow`.
 #[cfg(unix)]
 pub fn test_scan_rtld_now(library_path: &Path) -> Result<TestStatus> {
@@ -93,6 +266,7 @@ pub fn test_scan_rtld_now(library_path: &Path) -> Result<TestStatus> {
 #[cfg(not(unix))]
 pub fn test_scan_rtld_now(library_path: &Path) -> Result<TestStatus> {
     Ok(TestStatus::Skipped {
+        reason: SkipReason::UnsupportedPlatform,
         details: Some(String::from(
             "This test is only relevant to Unix-like platforms",
         )),
@@ -1,27 +1,76 @@
 //! Utilities for generating pseudo-random data.
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap_sys::events::{
-    clap_event_header, clap_event_midi, clap_event_note, clap_event_note_expression,
-    clap_event_param_value, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_NOTE_CHOKE,
-    CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_VALUE, CLAP_NOTE_EXPRESSION_PRESSURE,
-    CLAP_NOTE_EXPRESSION_TUNING, CLAP_NOTE_EXPRESSION_VOLUME,
+    clap_event_header, clap_event_midi, clap_event_midi2, clap_event_note,
+    clap_event_note_expression, clap_event_param_value, CLAP_CORE_EVENT_SPACE_ID,
+    CLAP_EVENT_DONT_RECORD, CLAP_EVENT_IS_LIVE, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI2,
+    CLAP_EVENT_MIDI_SYSEX, CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON,
+    CLAP_EVENT_PARAM_VALUE, CLAP_NOTE_EXPRESSION_PRESSURE, CLAP_NOTE_EXPRESSION_TUNING,
+    CLAP_NOTE_EXPRESSION_VOLUME,
 };
 use clap_sys::ext::note_ports::{
-    CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI_MPE,
+    clap_note_dialect, CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI2,
+    CLAP_NOTE_DIALECT_MIDI_MPE,
 };
 use midi_consts::channel_event as midi;
 use rand::Rng;
 use rand_pcg::Pcg32;
 use std::ops::RangeInclusive;
 
-use crate::plugin::ext::note_ports::NotePortConfig;
+use crate::plugin::ext::note_ports::{NotePort, NotePortConfig};
 use crate::plugin::ext::params::ParamInfo;
-use crate::plugin::instance::process::{Event, EventQueue};
+use crate::plugin::instance::process::{Event, EventQueue, MidiSysexEvent};
+use crate::util;
+
+/// The default seed used for the pseudo-random number generators created using [`new_prng()`].
+/// This can be changed using the validator's `--seed` option.
+pub const DEFAULT_PRNG_SEED: u64 = 1337;
+/// The PCG stream used for the pseudo-random number generators created using [`new_prng()`].
+const PRNG_STREAM: u64 = 420;
+
+/// The Universal MIDI Packet message type for MIDI 2.0 channel voice messages. These messages are
+/// 64 bits long.
+pub const UMP_MIDI2_CHANNEL_VOICE: u32 = 0x4;
+/// The status nibbles for the MIDI 2.0 channel voice messages generated by [`NoteGenerator`].
+const MIDI2_REGISTERED_PER_NOTE_CONTROLLER: u32 = 0x0;
+const MIDI2_REGISTERED_CONTROLLER: u32 = 0x2;
+pub const MIDI2_NOTE_OFF: u32 = 0x8;
+pub const MIDI2_NOTE_ON: u32 = 0x9;
+
+/// The status bytes that start and end a MIDI 1.0 system exclusive message.
+const SYSEX_START: u8 = 0xf0;
+const SYSEX_END: u8 = 0xf7;
+/// The manufacturer ID reserved for non-commercial use. The generated sysex messages use this ID
+/// so they don't trigger any actual device-specific behavior.
+const SYSEX_NON_COMMERCIAL_ID: u8 = 0x7d;
+/// The range for the number of data bytes in a generated sysex message, excluding the start byte,
+/// the manufacturer ID, and the end byte.
+pub const SYSEX_DATA_LEN_RANGE: RangeInclusive<usize> = 0..=64;
+
+/// The seed used for the pseudo-random number generators. See
+/// [`SharedSettings::prng_seed`][crate::util::SharedSettings::prng_seed].
+pub fn prng_seed() -> u64 {
+    util::shared_settings().prng_seed
+}
 
-/// Create a new pseudo-random number generator with a fixed seed.
+/// Create a new pseudo-random number generator using the seed from [`prng_seed()`]. Every
+/// generator created this way produces the same sequence of values.
 pub fn new_prng() -> Pcg32 {
-    Pcg32::new(1337, 420)
+    Pcg32::new(prng_seed(), PRNG_STREAM)
+}
+
+/// Generate a MIDI 1.0 system exclusive message with `num_data_bytes` random data bytes. The
+/// message includes the start and end bytes, and it uses the manufacturer ID reserved for
+/// non-commercial use.
+pub fn random_sysex_message(prng: &mut Pcg32, num_data_bytes: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(num_data_bytes + 3);
+    data.push(SYSEX_START);
+    data.push(SYSEX_NON_COMMERCIAL_ID);
+    data.extend((0..num_data_bytes).map(|_| prng.gen_range(0..128u8)));
+    data.push(SYSEX_END);
+
+    data
 }
 
 /// A random note and MIDI event generator that generates consistent events based on the
@@ -34,6 +83,8 @@ pub struct NoteGenerator {
     /// aren't playing, double note on events, and generating note expressions for notes that aren't
     /// active.
     only_consistent_events: bool,
+    /// Which of a note port's supported note dialects events are generated for.
+    dialect_selection: DialectSelection,
 
     /// Contains the currently playing notes per-port. We'll be nice and not send overlapping notes
     /// or note-offs without a corresponding note-on.
@@ -44,6 +95,18 @@ pub struct NoteGenerator {
     next_note_id: i32,
 }
 
+/// Which of a note port's supported note dialects the [`NoteGenerator`] generates events for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialectSelection {
+    /// Generate events for all supported note dialects, with half of the events using the port's
+    /// preferred dialect. This is the default.
+    All,
+    /// Only generate events in the port's preferred note dialect.
+    PreferredOnly,
+    /// Only generate events in supported note dialects other than the port's preferred dialect.
+    NonPreferredOnly,
+}
+
 /// A helper to generate random parameter automation and modulation events in a couple different
 /// ways to stress test a plugin's parameter handling.
 pub struct ParamFuzzer<'a> {
@@ -61,7 +124,7 @@ struct Note {
 }
 
 /// The different kinds of events we can generate. The event type chosen depends on the plugin.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum NoteEventType {
     ClapNoteOn,
     ClapNoteOff,
@@ -74,6 +137,11 @@ enum NoteEventType {
     MidiPitchBend,
     MidiCc,
     MidiProgramChange,
+    MidiSysex,
+    Midi2NoteOn,
+    Midi2NoteOff,
+    Midi2PerNoteController,
+    Midi2Rpn,
 }
 
 impl NoteGenerator {
@@ -86,6 +154,7 @@ impl NoteGenerator {
         NoteGenerator {
             config,
             only_consistent_events: true,
+            dialect_selection: DialectSelection::All,
 
             active_notes: vec![Vec::new(); num_inputs],
             next_note_id: 0,
@@ -99,6 +168,23 @@ impl NoteGenerator {
         self
     }
 
+    /// Only generate events for some of the note ports' supported note dialects. Note ports that
+    /// don't support any dialects matching the selection won't receive any events.
+    pub fn with_dialect_selection(mut self, dialect_selection: DialectSelection) -> Self {
+        self.dialect_selection = dialect_selection;
+        self
+    }
+
+    /// Whether at least one of the input note ports supports a note dialect matching the
+    /// generator's [`DialectSelection`]. [`generate()`][Self::generate] returns an error if this
+    /// is not the case.
+    pub fn can_generate_events(&self) -> bool {
+        self.config
+            .inputs
+            .iter()
+            .any(|note_port| !self.selected_types(note_port).is_empty())
+    }
+
     /// Fill an event queue with random events for the next `num_samples` samples. This does not
     /// clear the event queue. If the queue was not empty, then this will do a stable sort after
     /// inserting _all_ events. If an error was returned, then the queue will not have been sorted.
@@ -135,38 +221,57 @@ impl NoteGenerator {
 
     /// Generate a random note event for one of the plugin's note ports depending on the port's
     /// capabilities. Returns an error if the plugin doesn't have any note ports or if the note
-    /// ports don't support either MIDI or CLAP note events.
+    /// ports don't support CLAP note events, MIDI, or MIDI 2.0.
     pub fn generate(&mut self, prng: &mut Pcg32, time_offset: u32) -> Result<Event> {
         if self.config.inputs.is_empty() {
             anyhow::bail!("Cannot generate note events for a plugin with no input note ports.");
         }
 
-        // We'll ignore the prefered note dialect and pick from all of the supported note dialects.
-        // The plugin may get a CLAP note on and a MIDI note off if it supports both of those things
-        let note_port_idx = prng.gen_range(0..self.config.inputs.len());
-        let supports_clap_note_events = self.config.inputs[note_port_idx]
-            .supported_dialects
-            .contains(&CLAP_NOTE_DIALECT_CLAP);
-        let supports_midi_events = self.config.inputs[note_port_idx]
-            .supported_dialects
-            .contains(&CLAP_NOTE_DIALECT_MIDI)
-            || self.config.inputs[note_port_idx]
-                .supported_dialects
-                .contains(&CLAP_NOTE_DIALECT_MIDI_MPE);
-        let possible_events =
-            NoteEventType::supported_types(supports_clap_note_events, supports_midi_events)
-                .with_context(|| {
-                    format!(
-                        "Note input port {note_port_idx} supports neither CLAP note events nor \
-                         MIDI. This is technically allowed, but few hosts will be able to \
-                         interact with the plugin."
-                    )
-                })?;
+        // When only generating events for some dialects, ports that don't support any of those
+        // dialects are skipped
+        let note_port_idx = if self.dialect_selection == DialectSelection::All {
+            prng.gen_range(0..self.config.inputs.len())
+        } else {
+            let note_port_indices: Vec<usize> = (0..self.config.inputs.len())
+                .filter(|&idx| !self.selected_types(&self.config.inputs[idx]).is_empty())
+                .collect();
+            if note_port_indices.is_empty() {
+                anyhow::bail!(
+                    "None of the plugin's input note ports support the note dialects needed to \
+                     generate events with {:?}.",
+                    self.dialect_selection
+                );
+            }
+
+            note_port_indices[prng.gen_range(0..note_port_indices.len())]
+        };
+        let note_port = &self.config.inputs[note_port_idx];
+        let selected_events = self.selected_types(note_port);
+        if selected_events.is_empty() {
+            anyhow::bail!(
+                "Note input port {note_port_idx} supports neither CLAP note events nor any MIDI \
+                 dialect. This is technically allowed, but few hosts will be able to interact \
+                 with the plugin."
+            );
+        }
+        // With the default selection half of the events use the port's preferred note dialect, and
+        // the other half are picked from all of the supported note dialects. The plugin may get a
+        // CLAP note on and a MIDI note off if it supports both of those things
+        let preferred_events = NoteEventType::preferred_types(note_port);
+        let possible_events = if self.dialect_selection == DialectSelection::All
+            && !preferred_events.is_empty()
+            && prng.gen_bool(0.5)
+        {
+            preferred_events
+        } else {
+            selected_events.as_slice()
+        };
 
         // We could do this in a smarter way to avoid generating impossible event types (like a note
         // off when there are no active notes), but this should work fine.
         for _ in 0..1024 {
             let event_type = prng.sample(rand::distributions::Slice::new(possible_events).unwrap());
+            let flags = random_event_flags(prng);
             match event_type {
                 NoteEventType::ClapNoteOn => {
                     let note = if self.only_consistent_events {
@@ -202,8 +307,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_NOTE_ON,
-                            // TODO: There's a live flag here, should we also randomize this?
-                            flags: 0,
+                            flags,
                         },
                         note_id: note.note_id,
                         port_index: note_port_idx as i16,
@@ -236,7 +340,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_NOTE_OFF,
-                            flags: 0,
+                            flags,
                         },
                         note_id: note.note_id,
                         port_index: note_port_idx as i16,
@@ -277,7 +381,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_NOTE_CHOKE,
-                            flags: 0,
+                            flags,
                         },
                         note_id: note.note_id,
                         port_index: note_port_idx as i16,
@@ -318,7 +422,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_NOTE_CHOKE,
-                            flags: 0,
+                            flags,
                         },
                         expression_id,
                         note_id: note.note_id,
@@ -362,7 +466,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [
@@ -396,7 +500,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [
@@ -415,7 +519,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [midi::CHANNEL_KEY_PRESSURE | channel, pressure, 0],
@@ -445,7 +549,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [
@@ -466,7 +570,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [midi::PITCH_BEND_CHANGE | channel, byte1, byte2],
@@ -482,7 +586,7 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [midi::CONTROL_CHANGE | channel, cc, value],
@@ -497,12 +601,147 @@ impl NoteGenerator {
                             time: time_offset,
                             space_id: CLAP_CORE_EVENT_SPACE_ID,
                             type_: CLAP_EVENT_MIDI,
-                            flags: 0,
+                            flags,
                         },
                         port_index: note_port_idx as u16,
                         data: [midi::PROGRAM_CHANGE | channel, program_number, 0],
                     }));
                 }
+                NoteEventType::MidiSysex => {
+                    let num_data_bytes = prng.gen_range(SYSEX_DATA_LEN_RANGE);
+                    let data = random_sysex_message(prng, num_data_bytes);
+
+                    // The event owns the data its buffer points to. The header's size is set by
+                    // `MidiSysexEvent::new()`.
+                    return Ok(Event::MidiSysex(MidiSysexEvent::new(
+                        clap_event_header {
+                            size: 0,
+                            time: time_offset,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_MIDI_SYSEX,
+                            flags,
+                        },
+                        note_port_idx as u16,
+                        data,
+                    )));
+                }
+                NoteEventType::Midi2NoteOn | NoteEventType::Midi2NoteOff => {
+                    let is_note_on = matches!(event_type, NoteEventType::Midi2NoteOn);
+                    let note = if self.only_consistent_events {
+                        if is_note_on {
+                            let note = Note {
+                                key: prng.gen_range(0..128),
+                                channel: prng.gen_range(0..16),
+                                note_id: self.next_note_id,
+                                choked: false,
+                            };
+                            if self.active_notes[note_port_idx].contains(&note) {
+                                continue;
+                            }
+                            self.active_notes[note_port_idx].push(note);
+                            self.next_note_id = self.next_note_id.wrapping_add(1);
+
+                            note
+                        } else {
+                            if self.active_notes[note_port_idx].is_empty() {
+                                continue;
+                            }
+
+                            let note_idx =
+                                prng.gen_range(0..self.active_notes[note_port_idx].len());
+                            self.active_notes[note_port_idx].remove(note_idx)
+                        }
+                    } else {
+                        Note {
+                            key: prng.gen_range(0..128),
+                            channel: prng.gen_range(0..16),
+                            note_id: prng.gen_range(0..100),
+                            choked: false,
+                        }
+                    };
+
+                    // MIDI 2.0 note events have a 16-bit velocity. The attribute type in the lower
+                    // byte of the index is left at zero, meaning no attribute.
+                    let status = if is_note_on {
+                        MIDI2_NOTE_ON
+                    } else {
+                        MIDI2_NOTE_OFF
+                    };
+                    let velocity: u32 = prng.gen_range(0..=u16::MAX as u32);
+                    return Ok(Event::Midi2(clap_event_midi2 {
+                        header: clap_event_header {
+                            size: std::mem::size_of::<clap_event_midi2>() as u32,
+                            time: time_offset,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_MIDI2,
+                            flags,
+                        },
+                        port_index: note_port_idx as u16,
+                        data: midi2_channel_voice_message(
+                            status,
+                            note.channel as u32,
+                            (note.key as u32) << 8,
+                            velocity << 16,
+                        ),
+                    }));
+                }
+                NoteEventType::Midi2PerNoteController => {
+                    let note = if self.only_consistent_events {
+                        if self.active_notes[note_port_idx].is_empty() {
+                            continue;
+                        }
+
+                        let note_idx = prng.gen_range(0..self.active_notes[note_port_idx].len());
+                        self.active_notes[note_port_idx][note_idx]
+                    } else {
+                        Note {
+                            key: prng.gen_range(0..128),
+                            channel: prng.gen_range(0..16),
+                            note_id: prng.gen_range(0..100),
+                            choked: false,
+                        }
+                    };
+
+                    let controller: u32 = prng.gen_range(0..256);
+                    return Ok(Event::Midi2(clap_event_midi2 {
+                        header: clap_event_header {
+                            size: std::mem::size_of::<clap_event_midi2>() as u32,
+                            time: time_offset,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_MIDI2,
+                            flags,
+                        },
+                        port_index: note_port_idx as u16,
+                        data: midi2_channel_voice_message(
+                            MIDI2_REGISTERED_PER_NOTE_CONTROLLER,
+                            note.channel as u32,
+                            ((note.key as u32) << 8) | controller,
+                            prng.gen(),
+                        ),
+                    }));
+                }
+                NoteEventType::Midi2Rpn => {
+                    // The bank and index are both 7-bit values, and the data is a full 32-bit value
+                    let channel = prng.gen_range(0..16);
+                    let bank: u32 = prng.gen_range(0..128);
+                    let index: u32 = prng.gen_range(0..128);
+                    return Ok(Event::Midi2(clap_event_midi2 {
+                        header: clap_event_header {
+                            size: std::mem::size_of::<clap_event_midi2>() as u32,
+                            time: time_offset,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_MIDI2,
+                            flags,
+                        },
+                        port_index: note_port_idx as u16,
+                        data: midi2_channel_voice_message(
+                            MIDI2_REGISTERED_CONTROLLER,
+                            channel,
+                            (bank << 8) | index,
+                            prng.gen(),
+                        ),
+                    }));
+                }
             }
         }
 
@@ -513,20 +752,53 @@ impl NoteGenerator {
     }
 }
 
+/// Generate random flags for a note event. `CLAP_EVENT_IS_LIVE` is set for roughly a quarter of
+/// the events, and `CLAP_EVENT_DONT_RECORD` for roughly an eighth of them. Plugins should handle
+/// these events the same way as any other event.
+fn random_event_flags(prng: &mut Pcg32) -> u32 {
+    let mut flags = 0;
+    if prng.gen_bool(1.0 / 4.0) {
+        flags |= CLAP_EVENT_IS_LIVE;
+    }
+    if prng.gen_bool(1.0 / 8.0) {
+        flags |= CLAP_EVENT_DONT_RECORD;
+    }
+
+    flags
+}
+
+/// Build a 64-bit MIDI 2.0 channel voice message for group 0, stored in the first two words of a
+/// `clap_event_midi2`'s data. `index` contains the 16 bits following the channel, and `data` is the
+/// message's second word.
+fn midi2_channel_voice_message(status: u32, channel: u32, index: u32, data: u32) -> [u32; 4] {
+    [
+        (UMP_MIDI2_CHANNEL_VOICE << 28) | (status << 20) | (channel << 16) | (index & 0xffff),
+        data,
+        0,
+        0,
+    ]
+}
+
+impl NoteGenerator {
+    /// Get the event types that can be generated for a note port with the generator's
+    /// [`DialectSelection`].
+    fn selected_types(&self, note_port: &NotePort) -> Vec<NoteEventType> {
+        match self.dialect_selection {
+            DialectSelection::All => NoteEventType::supported_types(note_port),
+            DialectSelection::PreferredOnly => NoteEventType::preferred_types(note_port).to_vec(),
+            DialectSelection::NonPreferredOnly => {
+                let preferred_events = NoteEventType::preferred_types(note_port);
+
+                NoteEventType::supported_types(note_port)
+                    .into_iter()
+                    .filter(|event_type| !preferred_events.contains(event_type))
+                    .collect()
+            }
+        }
+    }
+}
+
 impl NoteEventType {
-    const ALL: &'static [NoteEventType] = &[
-        NoteEventType::ClapNoteOn,
-        NoteEventType::ClapNoteOff,
-        NoteEventType::ClapNoteChoke,
-        NoteEventType::ClapNoteExpression,
-        NoteEventType::MidiNoteOn,
-        NoteEventType::MidiNoteOff,
-        NoteEventType::MidiChannelPressure,
-        NoteEventType::MidiPolyKeyPressure,
-        NoteEventType::MidiPitchBend,
-        NoteEventType::MidiCc,
-        NoteEventType::MidiProgramChange,
-    ];
     const CLAP_EVENTS: &'static [NoteEventType] = &[
         NoteEventType::ClapNoteOn,
         NoteEventType::ClapNoteOff,
@@ -541,24 +813,55 @@ impl NoteEventType {
         NoteEventType::MidiPitchBend,
         NoteEventType::MidiCc,
         NoteEventType::MidiProgramChange,
+        NoteEventType::MidiSysex,
+    ];
+    const MIDI2_EVENTS: &'static [NoteEventType] = &[
+        NoteEventType::Midi2NoteOn,
+        NoteEventType::Midi2NoteOff,
+        NoteEventType::Midi2PerNoteController,
+        NoteEventType::Midi2Rpn,
     ];
 
-    /// Get a slice containing the event types supported by a plugin. Returns None if the plugin
-    /// supports neither CLAP note events nor MIDI.
-    pub fn supported_types(
-        supports_clap_note_events: bool,
-        supports_midi_events: bool,
-    ) -> Option<&'static [NoteEventType]> {
-        if supports_clap_note_events && supports_midi_events {
-            Some(NoteEventType::ALL)
-        } else if supports_clap_note_events {
-            Some(NoteEventType::CLAP_EVENTS)
-        } else if supports_midi_events {
-            Some(NoteEventType::MIDI_EVENTS)
+    /// Get the event types for a single note dialect. Returns an empty slice for unknown dialects.
+    pub fn dialect_types(dialect: clap_note_dialect) -> &'static [NoteEventType] {
+        match dialect {
+            CLAP_NOTE_DIALECT_CLAP => NoteEventType::CLAP_EVENTS,
+            CLAP_NOTE_DIALECT_MIDI | CLAP_NOTE_DIALECT_MIDI_MPE => NoteEventType::MIDI_EVENTS,
+            CLAP_NOTE_DIALECT_MIDI2 => NoteEventType::MIDI2_EVENTS,
+            _ => &[],
+        }
+    }
+
+    /// Get the event types for a note port's preferred note dialect. Some tests remove dialects
+    /// from the port's supported dialects, and the preferred dialect may be one of them. The result
+    /// is empty in that case.
+    pub fn preferred_types(note_port: &NotePort) -> &'static [NoteEventType] {
+        if note_port.supports_dialect(note_port.prefered_dialect) {
+            NoteEventType::dialect_types(note_port.prefered_dialect)
         } else {
-            None
+            &[]
         }
     }
+
+    /// Get the event types supported by a note port. The result is empty if the port supports
+    /// neither CLAP note events, MIDI, nor MIDI 2.0.
+    pub fn supported_types(note_port: &NotePort) -> Vec<NoteEventType> {
+        let mut types = Vec::new();
+        if note_port.supports_dialect(CLAP_NOTE_DIALECT_CLAP) {
+            types.extend_from_slice(NoteEventType::CLAP_EVENTS);
+        }
+        // MIDI and MIDI MPE use the same events
+        if note_port.supports_dialect(CLAP_NOTE_DIALECT_MIDI)
+            || note_port.supports_dialect(CLAP_NOTE_DIALECT_MIDI_MPE)
+        {
+            types.extend_from_slice(NoteEventType::MIDI_EVENTS);
+        }
+        if note_port.supports_dialect(CLAP_NOTE_DIALECT_MIDI2) {
+            types.extend_from_slice(NoteEventType::MIDI2_EVENTS);
+        }
+
+        types
+    }
 }
 
 impl<'a> ParamFuzzer<'a> {
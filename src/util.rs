@@ -3,9 +3,101 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, TimeZone, Utc};
 use clap_sys::factory::draft::preset_discovery::{clap_timestamp, CLAP_TIMESTAMP_UNKNOWN};
-use std::ffi::CStr;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::Cell;
+use std::ffi::{c_void, CStr};
 use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::call_trace;
+use crate::tests::rng;
+
+/// The default value for the validator's `--creation-time-threshold` option, in milliseconds.
+pub const DEFAULT_CREATION_TIME_THRESHOLD_MS: u64 = 500;
+
+/// The default value for the validator's `--destroy-time-threshold` option, in milliseconds.
+pub const DEFAULT_DESTROY_TIME_THRESHOLD_MS: u64 = 500;
+
+/// The default value for the validator's `--callback-recursion-budget` option.
+pub const DEFAULT_CALLBACK_RECURSION_BUDGET: u32 = 10;
+
+/// The process-wide settings. Set through [`set_shared_settings()`].
+static SHARED_SETTINGS: RwLock<SharedSettings> = RwLock::new(SharedSettings::DEFAULT);
+
+/// Process-wide settings that change how the tests behave. These are set from the validator's
+/// options when it starts, and the validator passes them on to out-of-process tests as a single
+/// JSON encoded argument.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SharedSettings {
+    /// Whether [`format_f64()`] and [`format_f32()`] should also print hexadecimal float literals.
+    /// Set through the validator's `--strict` option.
+    pub strict_number_formatting: bool,
+    /// How long creating and initializing a plugin instance may take in milliseconds before the
+    /// `instance-creation-time` test results in a warning. Set through the validator's
+    /// `--creation-time-threshold` option.
+    pub creation_time_threshold_ms: u64,
+    /// How long destroying a plugin instance may take in milliseconds before the
+    /// `instance-destroy-time` test results in a warning. Set through the validator's
+    /// `--destroy-time-threshold` option.
+    pub destroy_time_threshold_ms: u64,
+    /// How many times in a row the plugin may request another main thread callback from within
+    /// `clap_plugin::on_main_thread()` before the host stops calling that function. Set through
+    /// the validator's `--callback-recursion-budget` option.
+    pub callback_recursion_budget: u32,
+    /// Whether audio threads should try to enable realtime scheduling, and whether processing
+    /// tests should check every processing cycle against a simulated deadline. Set through the
+    /// validator's `--realtime-audio-thread` option.
+    pub realtime_audio_thread: bool,
+    /// Whether tests that deliberately break CLAP's rules to see how the plugin reacts should be
+    /// run. Set through the validator's `--negative-tests` option.
+    pub negative_tests: bool,
+    /// Why the plugins' GUIs cannot be opened on this system, if that is the case. Detected at
+    /// startup using [`detect_headless()`] unless the validator's `--force-gui-tests` option is
+    /// set. Tests that require a GUI are skipped when this is set.
+    pub headless_reason: Option<String>,
+    /// The seed used for the pseudo-random number generators created using
+    /// [`new_prng()`][crate::tests::rng::new_prng()]. Set through the validator's `--seed` option.
+    pub prng_seed: u64,
+    /// Whether calls to the plugin's functions should be logged. Set through the validator's
+    /// `--trace-calls` option.
+    pub trace_calls: bool,
+    /// The name, vendor, and version strings the host reports to plugins instead of
+    /// clap-validator's own. Set through the validator's `--host-name`, `--host-vendor`, and
+    /// `--host-version` options.
+    pub host_info_overrides: HostInfoOverrides,
+}
+
+/// Overrides for the strings in the `clap_host` struct. `None` values use clap-validator's own
+/// name, vendor, and version.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HostInfoOverrides {
+    pub name: Option<String>,
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+}
+
+impl SharedSettings {
+    /// The settings used when the validator's options are left at their default values.
+    pub const DEFAULT: SharedSettings = SharedSettings {
+        strict_number_formatting: false,
+        creation_time_threshold_ms: DEFAULT_CREATION_TIME_THRESHOLD_MS,
+        destroy_time_threshold_ms: DEFAULT_DESTROY_TIME_THRESHOLD_MS,
+        callback_recursion_budget: DEFAULT_CALLBACK_RECURSION_BUDGET,
+        realtime_audio_thread: false,
+        negative_tests: false,
+        headless_reason: None,
+        prng_seed: rng::DEFAULT_PRNG_SEED,
+        trace_calls: false,
+        host_info_overrides: HostInfoOverrides {
+            name: None,
+            vendor: None,
+            version: None,
+        },
+    };
+}
 
 // TODO: Remove these attributes once we start implementing host interfaces
 
@@ -31,7 +123,17 @@ macro_rules! check_null_ptr {
 macro_rules! clap_call {
     { $obj_ptr:expr=>$function_name:ident($($args:expr),* $(, )?) } => {
         match (*$obj_ptr).$function_name {
-            Some(function_ptr) => function_ptr($($args),*),
+            Some(function_ptr) => {
+                let _call_guard = $crate::ipc::track_plugin_call(
+                    $crate::util::type_name_of_ptr($obj_ptr),
+                    stringify!($function_name),
+                );
+                let _trace_guard = $crate::call_trace::trace_plugin_call(
+                    $crate::util::type_name_of_ptr($obj_ptr),
+                    stringify!($function_name),
+                );
+                function_ptr($($args),*)
+            }
             None => panic!("'{}::{}' is a null pointer, but this is not allowed", $crate::util::type_name_of_ptr($obj_ptr), stringify!($function_name)),
         }
     }
@@ -48,6 +150,86 @@ pub(crate) use check_null_ptr;
 pub(crate) use clap_call;
 pub(crate) use unsafe_clap_call;
 
+/// The value returned from a callback called by the plugin when handling that callback panicked.
+pub trait PanicFallback {
+    const FALLBACK: Self;
+}
+
+impl PanicFallback for () {
+    const FALLBACK: Self = ();
+}
+
+impl PanicFallback for bool {
+    const FALLBACK: Self = false;
+}
+
+impl PanicFallback for u32 {
+    const FALLBACK: Self = 0;
+}
+
+impl PanicFallback for *const c_void {
+    const FALLBACK: Self = std::ptr::null();
+}
+
+thread_local! {
+    /// The number of nested [`catch_callback_panic()`] calls on this thread. Panics are caught
+    /// while this is non-zero.
+    static CALLBACK_PANIC_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+/// Run the body of an `extern "C"` callback called by the plugin. Unwinding from a panic across the
+/// C boundary back into the plugin's code is undefined behavior, and the callbacks use panics to
+/// reject null pointers. If `body` panics, then the panic is caught, `record_panic` is called with
+/// a message describing the panic so it can be recorded as a callback error, and
+/// [`PanicFallback::FALLBACK`] is returned to the plugin instead. The process is aborted if
+/// `record_panic` also panics, since at that point there's no safe way to return to the plugin.
+pub fn catch_callback_panic<T: PanicFallback>(
+    function_name: &str,
+    body: impl FnOnce() -> T,
+    record_panic: impl FnOnce(String),
+) -> T {
+    let _trace_span = crate::call_trace::trace_host_callback(function_name);
+    CALLBACK_PANIC_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = format!(
+                "The validator panicked while handling a call to '{function_name}': {}",
+                panic_message(payload.as_ref())
+            );
+            if panic::catch_unwind(AssertUnwindSafe(|| record_panic(message))).is_err() {
+                log::error!(
+                    "Panicked while recording a panic in '{function_name}', aborting the process"
+                );
+                std::process::abort();
+            }
+
+            T::FALLBACK
+        }
+    };
+    CALLBACK_PANIC_DEPTH.with(|depth| depth.set(depth.get() - 1));
+
+    result
+}
+
+/// Whether a panic on the current thread would be caught by [`catch_callback_panic()`]. Panic hooks
+/// run before the panic is caught, so they can use this to tell caught and uncaught panics apart.
+pub fn in_callback_panic_handler() -> bool {
+    CALLBACK_PANIC_DEPTH.with(|depth| depth.get() > 0)
+}
+
+/// Get the message from a panic's payload. Panics created using `panic!()` always contain either a
+/// `&str` or a `String`.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "<unknown panic payload>"
+    }
+}
+
 /// Similar to, [`std::any::type_name_of_val()`], but on stable Rust, and stripping away the pointer
 /// part.
 #[must_use]
@@ -143,6 +325,130 @@ pub fn parse_timestamp(timestamp: clap_timestamp) -> Result<Option<DateTime<Utc>
     Ok(parsed)
 }
 
+/// Set the process-wide settings. The validator sets these once at startup, and out-of-process
+/// tests set them to the values they received from the validator.
+pub fn set_shared_settings(settings: SharedSettings) {
+    // This is checked for every call to the plugin's functions, so the tracing module keeps its
+    // own copy
+    call_trace::set_log_calls(settings.trace_calls);
+    *SHARED_SETTINGS.write().unwrap() = settings;
+}
+
+/// The process-wide settings. See [`set_shared_settings()`].
+pub fn shared_settings() -> SharedSettings {
+    SHARED_SETTINGS.read().unwrap().clone()
+}
+
+/// Whether strict number formatting is enabled. See [`SharedSettings::strict_number_formatting`].
+pub fn strict_number_formatting() -> bool {
+    SHARED_SETTINGS.read().unwrap().strict_number_formatting
+}
+
+/// The instance creation time threshold in milliseconds. See
+/// [`SharedSettings::creation_time_threshold_ms`].
+pub fn creation_time_threshold_ms() -> u64 {
+    SHARED_SETTINGS.read().unwrap().creation_time_threshold_ms
+}
+
+/// The instance destroy time threshold in milliseconds. See
+/// [`SharedSettings::destroy_time_threshold_ms`].
+pub fn destroy_time_threshold_ms() -> u64 {
+    SHARED_SETTINGS.read().unwrap().destroy_time_threshold_ms
+}
+
+/// The number of recursive main thread callback requests the host allows. See
+/// [`SharedSettings::callback_recursion_budget`].
+pub fn callback_recursion_budget() -> u32 {
+    SHARED_SETTINGS.read().unwrap().callback_recursion_budget
+}
+
+/// Whether realtime audio threads and deadline checks are enabled. See
+/// [`SharedSettings::realtime_audio_thread`].
+pub fn realtime_audio_thread() -> bool {
+    SHARED_SETTINGS.read().unwrap().realtime_audio_thread
+}
+
+/// Whether the negative tests are enabled. See [`SharedSettings::negative_tests`].
+pub fn negative_tests() -> bool {
+    SHARED_SETTINGS.read().unwrap().negative_tests
+}
+
+/// Check whether the validator is running on a headless system where plugins can't open their
+/// GUIs. Returns a description of why that is the case, or `None` if a display is available. This
+/// only detects a missing X11 or Wayland display server. On Windows and macOS a display is assumed
+/// to be available.
+pub fn detect_headless() -> Option<String> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let is_set = |name: &str| std::env::var_os(name).map_or(false, |value| !value.is_empty());
+        if !is_set("DISPLAY") && !is_set("WAYLAND_DISPLAY") {
+            return Some(String::from(
+                "neither 'DISPLAY' nor 'WAYLAND_DISPLAY' is set",
+            ));
+        }
+    }
+
+    None
+}
+
+/// Why plugin GUIs can't be opened on this system, if that's the case. See
+/// [`SharedSettings::headless_reason`].
+pub fn headless_reason() -> Option<String> {
+    SHARED_SETTINGS.read().unwrap().headless_reason.clone()
+}
+
+/// The overridden host name, vendor, and version strings. See
+/// [`SharedSettings::host_info_overrides`].
+pub fn host_info_overrides() -> HostInfoOverrides {
+    SHARED_SETTINGS.read().unwrap().host_info_overrides.clone()
+}
+
+/// Format a floating point value for use in error messages. This prints the shortest decimal
+/// representation that parses back to the exact same value, regardless of the system's locale.
+/// When strict number formatting is enabled the value's exact hexadecimal float literal is
+/// appended, e.g. `0.1 (0x1.999999999999ap-4)`.
+pub fn format_f64(value: f64) -> String {
+    if strict_number_formatting() && value.is_finite() {
+        format!("{value:?} ({})", hex_float(value))
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// The same as [`format_f64()`], but for single precision floats. The decimal representation is
+/// the shortest one that parses back to the same `f32` value.
+pub fn format_f32(value: f32) -> String {
+    if strict_number_formatting() && value.is_finite() {
+        // Widening the value to an `f64` is lossless
+        format!("{value:?} ({})", hex_float(value as f64))
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Format a finite floating point value as a C99-style hexadecimal float literal, like `printf()`'s
+/// `%a` format specifier.
+fn hex_float(value: f64) -> String {
+    let bits = value.to_bits();
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    let biased_exponent = ((bits >> 52) & 0x7ff) as i64;
+    let mantissa = bits & 0x000f_ffff_ffff_ffff;
+
+    let (leading_digit, exponent) = match (biased_exponent, mantissa) {
+        (0, 0) => (0, 0),
+        // Subnormals don't have an implicit leading one
+        (0, _) => (0, -1022),
+        _ => (1, biased_exponent - 1023),
+    };
+    let fraction = format!("{mantissa:013x}");
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{sign}0x{leading_digit}p{exponent:+}")
+    } else {
+        format!("{sign}0x{leading_digit}.{fraction}p{exponent:+}")
+    }
+}
+
 /// [`std::env::temp_dir`], but taking `XDG_RUNTIME_DIR` on Linux into account.
 fn temp_dir() -> PathBuf {
     #[cfg(all(unix, not(target_os = "macos")))]
@@ -2,21 +2,35 @@
 //! way that somewhat mimics a real host.
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use clap::{Args, ValueEnum};
-use clap_sys::version::clap_version_is_compatible;
+use clap_sys::version::{clap_version_is_compatible, CLAP_VERSION};
 use rayon::prelude::*;
 use regex::{Regex, RegexBuilder};
 use serde::Serialize;
 use std::collections::BTreeMap;
-use std::fs;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use strum::IntoEnumIterator;
-
-use crate::plugin::library::{PluginLibrary, PluginMetadata};
-use crate::tests::{PluginLibraryTestCase, PluginTestCase, TestCase, TestResult, TestStatus};
-use crate::util;
+use std::time::Duration;
+
+use crate::call_trace;
+use crate::commands::output::OutputTarget;
+use crate::ipc::{ChildConnection, ChildMessage, Watchdog};
+use crate::plugin::host::HostExtensionUsage;
+use crate::plugin::library::{PluginLibrary, PluginLibraryMetadata, PluginMetadata};
+use crate::tests::rng;
+use crate::tests::{
+    PluginLibraryTestCase, PluginTestCase, SkipReason, TestCase, TestResult, TestStatus,
+};
+use crate::util::{self, HostInfoOverrides, SharedSettings};
 use crate::Verbosity;
 
+/// The default value for the validator's `--test-timeout` option, in seconds.
+pub const DEFAULT_TEST_TIMEOUT_SECS: u64 = 300;
+/// The default value for the validator's `--plugin-call-timeout` option, in seconds.
+pub const DEFAULT_PLUGIN_CALL_TIMEOUT_SECS: u64 = 60;
+
 /// The results of running the validation test suite on one or more plugins. Use the
 /// [`tally()`][Self::tally()] method to compute the number of successful and failed tests.
 ///
@@ -24,6 +38,14 @@ use crate::Verbosity;
 #[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct ValidationResult {
+    /// Information about the validator run that produced these results. This is only `None` for
+    /// the partial results that are combined while the validator is running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<ReportHeader>,
+    /// A map indexed by plugin IDs containing information about every plugin that was tested. This
+    /// makes the report meaningful on its own without having to run `clap-validator list plugins`
+    /// separately.
+    pub plugins: BTreeMap<String, ValidatedPlugin>,
     /// A map indexed by plugin library paths containing the results of running the per-plugin
     /// library tests on one or more plugin libraries. These tests mainly examine the plugin's
     /// scanning behavior.
@@ -33,6 +55,49 @@ pub struct ValidationResult {
     pub plugin_tests: BTreeMap<String, Vec<TestResult>>,
 }
 
+/// Information about the validator run that produced a [`ValidationResult`]. This makes archived
+/// reports self-describing and comparable across different environments.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ReportHeader {
+    /// The clap-validator version that produced the report.
+    pub clap_validator_version: &'static str,
+    /// The CLAP version the validator was built against, formatted as `major.minor.revision`.
+    pub clap_version: String,
+    /// The operating system the validator was run on, as reported by Rust's `std::env::consts::OS`.
+    pub os: &'static str,
+    /// The CPU architecture the validator was run on, as reported by Rust's
+    /// `std::env::consts::ARCH`.
+    pub arch: &'static str,
+    /// The options the validator was invoked with.
+    pub settings: ValidatorSettings,
+    /// The seed used for the pseudo-random number generators in the tests.
+    pub prng_seed: u64,
+    /// When the validator started running the tests.
+    pub started_at: DateTime<Utc>,
+    /// When the validator finished running the tests.
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Information about a plugin included in a [`ValidationResult`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ValidatedPlugin {
+    /// The path to the plugin library containing the plugin.
+    pub library_path: PathBuf,
+    /// The SHA-256 hash of the plugin library's binary, formatted as a hexadecimal string. This
+    /// can be used to tie the results to an exact build of the plugin.
+    pub library_sha256: String,
+    /// The CLAP version the plugin library was built against.
+    pub library_clap_version: (u32, u32, u32),
+    /// The plugin's descriptor.
+    #[serde(flatten)]
+    pub metadata: PluginMetadata,
+    /// The host extensions the plugin queried and used across all of its tests. This tells host
+    /// developers which host extensions the plugin needs to function.
+    pub host_requirements: HostExtensionUsage,
+}
+
 /// Statistics for the validator.
 pub struct ValidationTally {
     /// The number of passed test cases.
@@ -46,7 +111,8 @@ pub struct ValidationTally {
 }
 
 /// Options for the validator.
-#[derive(Debug, Args)]
+#[derive(Debug, Clone, Args, Serialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct ValidatorSettings {
     /// Paths to one or more plugins that should be validated.
     #[arg(required = true)]
@@ -61,6 +127,23 @@ pub struct ValidatorSettings {
     /// Print the test output as JSON instead of human readable text.
     #[arg(long)]
     pub json: bool,
+    /// Write the results to a file or socket instead of printing them to STDOUT.
+    ///
+    /// This can be a file path, a TCP socket specified as tcp://<host>:<port>, or a Unix domain
+    /// socket specified as unix://<path>. Colors are disabled when writing to a file or a socket.
+    #[arg(short = 'o', long)]
+    #[serde(skip)]
+    pub output: Option<OutputTarget>,
+    /// Send the results to this HTTP(S) endpoint as a JSON POST request.
+    ///
+    /// This is strictly opt-in. The submitted report only identifies plugins by their ID, version,
+    /// and the SHA-256 hash of their binary. Test results only contain the test's name and outcome
+    /// without any details, and the report never contains library paths or the validator's
+    /// settings. Run `clap-validator schema submission` for a description of the report. Failing
+    /// to submit the results does not affect the exit code.
+    #[arg(long, value_name = "URL")]
+    #[serde(skip)]
+    pub submit_results: Option<String>,
     /// Only run the tests that match this case-insensitive regular expression.
     #[arg(short = 'f', long)]
     pub test_filter: Option<String>,
@@ -77,6 +160,11 @@ pub struct ValidatorSettings {
     /// This affects both the human readable and the JSON output.
     #[arg(long)]
     pub only_failed: bool,
+    /// Show this many tests before and after every failed test when using --only-failed.
+    ///
+    /// This only affects the human readable output.
+    #[arg(short = 'C', long, default_value_t = 0, requires = "only_failed")]
+    pub context: usize,
     /// Run the tests within this process.
     ///
     /// Tests are normally run in separate processes in case the plugin crashes. Another benefit
@@ -90,6 +178,129 @@ pub struct ValidatorSettings {
     /// --in-process option is used. Can be useful for keeping plugin output in the correct order.
     #[arg(long, conflicts_with = "in_process")]
     pub no_parallel: bool,
+    /// Load at most this many plugin libraries at the same time when running tests in parallel.
+    ///
+    /// Libraries are normally all validated at once, which keeps every library loaded until its
+    /// tests have finished. When validating a directory containing hundreds of plugins, this
+    /// option bounds the validator's memory usage by validating the libraries in batches of this
+    /// size and unloading them in between. The number of child processes running at the same time
+    /// is bounded by the number of threads regardless. Libraries are always validated one at a
+    /// time with --no-parallel or --in-process.
+    #[arg(long, value_name = "N")]
+    pub max_resident_libraries: Option<NonZeroUsize>,
+    /// Print exact values in test failures.
+    ///
+    /// Floating point values in failure messages are always printed with enough precision to
+    /// reproduce them exactly. With this option their hexadecimal float literals are printed as
+    /// well, which can help with debugging rounding issues.
+    #[arg(long)]
+    pub strict: bool,
+    /// Warn when creating and initializing a plugin instance takes longer than this many
+    /// milliseconds.
+    ///
+    /// Slow instantiation directly affects how long it takes for a host to load a project. The
+    /// measured time is included in the 'instance-creation-time' test's result.
+    #[arg(long, value_name = "MS", default_value_t = util::DEFAULT_CREATION_TIME_THRESHOLD_MS)]
+    pub creation_time_threshold: u64,
+    /// Warn when destroying a plugin instance takes longer than this many milliseconds.
+    ///
+    /// Slow teardown, for instance because the plugin waits on its background threads, stalls the
+    /// host when closing a project. The measured time is included in the 'instance-destroy-time'
+    /// test's result.
+    #[arg(long, value_name = "MS", default_value_t = util::DEFAULT_DESTROY_TIME_THRESHOLD_MS)]
+    pub destroy_time_threshold: u64,
+    /// Stop calling 'clap_plugin::on_main_thread()' when the plugin requests another main thread
+    /// callback from within that function more than this many times in a row.
+    ///
+    /// The host calls 'clap_plugin::on_main_thread()' again for every callback requested from
+    /// within that function, so a plugin that always requests a new callback would otherwise keep
+    /// the host's main thread busy forever. This is noted in the test's 'thread-safety' list, and
+    /// the remaining request is handled the next time the host handles callbacks.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = util::DEFAULT_CALLBACK_RECURSION_BUDGET
+    )]
+    pub callback_recursion_budget: u32,
+    /// Fail tests that take longer than this many seconds, or 0 to disable the time limit.
+    ///
+    /// When running the validation out-of-process, the test's child process is killed when it
+    /// exceeds this time limit. Child processes that stop responding altogether are always killed.
+    /// Neither applies when running tests in-process or when waiting for a debugger.
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_TEST_TIMEOUT_SECS)]
+    pub test_timeout: u64,
+    /// Fail tests where a single call to one of the plugin's functions takes longer than this many
+    /// seconds, or 0 to disable the time limit.
+    ///
+    /// This is checked separately from --test-timeout so that slow tests that are still making
+    /// progress can be told apart from plugin functions that never return. The stuck function's
+    /// name is included in the test's result. Like --test-timeout, this only applies to tests
+    /// running out-of-process when not waiting for a debugger.
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_PLUGIN_CALL_TIMEOUT_SECS)]
+    pub plugin_call_timeout: u64,
+    /// Pause every test's child process until a debugger has been attached.
+    ///
+    /// Before running a test, the out-of-process test runner prints its process ID and waits for
+    /// Enter to be pressed, giving you time to attach lldb, gdb, or WinDbg to it. If the test
+    /// panics, then the process is also kept alive until Enter is pressed. Implies --no-parallel.
+    #[arg(long, conflicts_with_all = ["in_process", "hide_output"])]
+    pub wait_for_debugger: bool,
+    /// The seed for the pseudo-random number generators used in the tests.
+    ///
+    /// The tests use the same seed on every run by default so the results are reproducible. This
+    /// can be changed to exercise the plugin with different random audio, events, and parameter
+    /// values. The seed is printed when a test fails so the failure can be reproduced.
+    #[arg(long, default_value_t = rng::DEFAULT_PRNG_SEED)]
+    pub seed: u64,
+    /// Run the processing tests' audio threads with realtime scheduling, and report deadline
+    /// misses.
+    ///
+    /// The audio thread tries to enable realtime scheduling ('SCHED_FIFO' on Linux and macOS, and
+    /// the time critical priority on Windows), which may require additional privileges. Every
+    /// processing cycle is also checked against the deadline a real host would have for that
+    /// block, and the processing tests result in a warning when the plugin misses it.
+    #[arg(long)]
+    pub realtime_audio_thread: bool,
+    /// Run the negative tests, which deliberately break CLAP's rules to see how the plugin reacts.
+    ///
+    /// For instance, these tests call main thread functions from the audio thread. Plugins are not
+    /// required to handle this, but they should not crash either. If the plugin crashes during one
+    /// of these tests, then the test's result describes what the validator was doing at the time.
+    #[arg(long)]
+    pub negative_tests: bool,
+    /// Run tests that open the plugin's GUI, even on a system that looks headless.
+    ///
+    /// On Linux and the BSDs, tests that need to open the plugin's GUI are skipped when neither
+    /// 'DISPLAY' nor 'WAYLAND_DISPLAY' is set. This option runs those tests anyway.
+    #[arg(long)]
+    pub force_gui_tests: bool,
+    /// Log every call to one of the plugin's functions, along with its thread and duration.
+    ///
+    /// The calls are logged at the trace level, so they are only shown with '--verbosity trace'.
+    /// This gives a timeline of exactly what the validator did leading up to a failure.
+    #[arg(long)]
+    pub trace_calls: bool,
+    /// Write a timeline of the validation run to this file in the Chrome trace event format.
+    ///
+    /// The timeline contains the tests, the calls to the plugin's functions, and the host
+    /// callbacks made by the plugin, including those from tests running out-of-process. The file
+    /// can be opened in 'chrome://tracing' or in Perfetto to inspect performance problems in the
+    /// plugin and in the validator itself.
+    #[arg(long, value_name = "PATH")]
+    pub trace_file: Option<PathBuf>,
+    /// Report this name to the plugin instead of 'clap-validator'.
+    ///
+    /// Some plugins enable workarounds for specific hosts based on the host's name. This option
+    /// makes it possible to reproduce those behaviors in the validator. The
+    /// 'host-name-independence' test still compares the plugin's behavior with other host names.
+    #[arg(long, value_name = "NAME")]
+    pub host_name: Option<String>,
+    /// Report this vendor to the plugin instead of clap-validator's own.
+    #[arg(long, value_name = "VENDOR")]
+    pub host_vendor: Option<String>,
+    /// Report this version to the plugin instead of clap-validator's own.
+    #[arg(long, value_name = "VERSION")]
+    pub host_version: Option<String>,
 }
 
 /// Options for running a single test. This is used for the out-of-process testing method. This
@@ -104,10 +315,34 @@ pub struct SingleTestSettings {
     /// The name of the test to run. [`TestCase`]s can be converted to and from strings to
     /// facilitate this.
     pub name: String,
-    /// The name of the file to write the test's JSON result to. This is not done through STDIO
-    /// because the hosted plugin may also write things there.
+    /// The address of the validator's socket. The test's log messages and result are sent there.
+    /// See the [`ipc`][crate::ipc] module for more information.
     #[arg(long)]
-    pub output_file: PathBuf,
+    pub ipc_address: SocketAddr,
+    /// The token to identify this process with when connecting to the validator's socket.
+    #[arg(long)]
+    pub ipc_token: String,
+    /// The file to write a crash report to if the plugin crashes the process. Only used on
+    /// Windows.
+    #[arg(long)]
+    pub crash_report_file: PathBuf,
+    /// The validator's process-wide settings, encoded as JSON. These are derived from the
+    /// `validate` command's options.
+    #[arg(long, value_parser = parse_shared_settings)]
+    pub shared_settings: SharedSettings,
+    /// Print the process ID and wait for Enter to be pressed before running the test and when the
+    /// test panics. Passed on from the `validate` command's `--wait-for-debugger` option.
+    #[arg(long)]
+    pub wait_for_debugger: bool,
+    /// Record trace events and send them to the validator. Set when the `validate` command's
+    /// `--trace-file` option is used.
+    #[arg(long)]
+    pub record_trace: bool,
+}
+
+/// Parse the JSON encoded [`SharedSettings`] passed to out-of-process tests.
+fn parse_shared_settings(json: &str) -> Result<SharedSettings, serde_json::Error> {
+    serde_json::from_str(json)
 }
 
 /// The type of test to run when only running a single test. This is only used for out-of-process
@@ -125,6 +360,35 @@ pub enum SingleTestType {
 /// Run the validator using the specified settings. Returns an error if any of the plugin paths
 /// could not loaded, or if the plugin ID filter did not match any plugins.
 pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<ValidationResult> {
+    let started_at = Utc::now();
+    let headless_reason = if settings.force_gui_tests {
+        None
+    } else {
+        util::detect_headless()
+    };
+    if let Some(reason) = &headless_reason {
+        log::debug!("Running on a headless system ({reason}), tests that need a GUI are skipped");
+    }
+    util::set_shared_settings(SharedSettings {
+        strict_number_formatting: settings.strict,
+        creation_time_threshold_ms: settings.creation_time_threshold,
+        destroy_time_threshold_ms: settings.destroy_time_threshold,
+        callback_recursion_budget: settings.callback_recursion_budget,
+        realtime_audio_thread: settings.realtime_audio_thread,
+        negative_tests: settings.negative_tests,
+        headless_reason,
+        prng_seed: settings.seed,
+        trace_calls: settings.trace_calls,
+        host_info_overrides: HostInfoOverrides {
+            name: settings.host_name.clone(),
+            vendor: settings.host_vendor.clone(),
+            version: settings.host_version.clone(),
+        },
+    });
+    if let Some(trace_file) = &settings.trace_file {
+        call_trace::record_to_file(trace_file)?;
+    }
+
     // Before doing anything, we need to make sure any temporary artifact files from the previous
     // run are cleaned up. These are used for things like state dumps when one of the state tests
     // fail. This is allowed to fail since the directory may not exist and even if it does and we
@@ -150,7 +414,8 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
     //       parallel and scalar versions need to be duplicated here. We could also create a single
     //       threaded shim that implements Rayon's parallel iterator methods, and then branch on the
     //       places where we create parallel iterators instead.
-    let mut results = if settings.no_parallel || settings.in_process {
+    // NOTE: Waiting for a debugger requires user input, so only one test may run at a time then
+    let mut results = if settings.no_parallel || settings.in_process || settings.wait_for_debugger {
         settings
             .paths
             .iter()
@@ -166,21 +431,23 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
                 let mut plugin_library_tests: BTreeMap<PathBuf, Vec<TestResult>> = BTreeMap::new();
                 plugin_library_tests.insert(
                     library_path.clone(),
-                    PluginLibraryTestCase::iter()
+                    PluginLibraryTestCase::all()
+                        .into_iter()
                         .filter(|test| test_filter(test, settings, &test_filter_re))
                         .map(|test| run_test(&test, verbosity, settings, library_path))
-                        .collect::<Result<Vec<TestResult>>>()?,
+                        .collect(),
                 );
 
                 // And these are the per-plugin instance tests
                 let plugin_library = PluginLibrary::load(library_path)
                     .with_context(|| format!("Could not load '{}'", library_path.display()))?;
-                let plugin_metadata = plugin_library.metadata().with_context(|| {
-                    format!(
-                        "Could not fetch plugin metadata for '{}'",
-                        library_path.display()
-                    )
-                })?;
+                let (plugin_metadata, broken_descriptors) =
+                    plugin_library.metadata_lenient().with_context(|| {
+                        format!(
+                            "Could not fetch plugin metadata for '{}'",
+                            library_path.display()
+                        )
+                    })?;
                 if !clap_version_is_compatible(plugin_metadata.clap_version()) {
                     log::debug!(
                         "'{}' uses an unsupported CLAP version ({}.{}.{}), skipping...",
@@ -196,9 +463,17 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
                     return Ok(ValidationResult::default());
                 }
 
+                // Plugins with broken descriptors can't be tested, but that shouldn't prevent the
+                // library's other plugins from being validated
+                plugin_library_tests
+                    .entry(library_path.clone())
+                    .or_default()
+                    .extend(broken_descriptor_results(broken_descriptors, settings));
+
                 // We only now know how many tests will be run for this plugin library. We'll count
                 // the number of plugins that match the filters and then compare that against the
                 // number of entries in the map to make sure there are no dupli
+                let plugins = validated_plugins(&plugin_library, &plugin_metadata, settings)?;
                 let plugin_tests: BTreeMap<String, Vec<TestResult>> = plugin_metadata
                     .plugins
                     .into_iter()
@@ -206,9 +481,10 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
                     // We're building a `BTreeMap` containing the results for all plugins in the
                     // plugin's library
                     .map(|plugin_metadata| {
-                        Ok((
+                        (
                             plugin_metadata.id.clone(),
-                            PluginTestCase::iter()
+                            PluginTestCase::all()
+                                .into_iter()
                                 .filter(|test| test_filter(test, settings, &test_filter_re))
                                 .map(|test| {
                                     run_test(
@@ -218,12 +494,19 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
                                         (&plugin_library, &plugin_metadata.id),
                                     )
                                 })
-                                .collect::<Result<Vec<TestResult>>>()?,
-                        ))
+                                .collect(),
+                        )
                     })
-                    .collect::<Result<BTreeMap<_, _>>>()?;
+                    .collect();
+
+                // The library is no longer needed at this point. Unloading it before moving on to
+                // the next library keeps the validator's memory usage flat when validating many
+                // plugins in-process.
+                drop(plugin_library);
 
                 Ok(ValidationResult {
+                    header: None,
+                    plugins,
                     plugin_library_tests,
                     plugin_tests,
                 })
@@ -231,99 +514,29 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
             .reduce(|a, b| {
                 // Monads galore! The fact that we need to handle errors for plugin tests makes this
                 // a bit more complicated.
-                let (a, b) = (a?, b?);
-
-                // In the serial version this could be done when iterating over the plugins, but
-                // when using iterators you can't do that. But it's still essential to make sure we
-                // don't test two versionsq of the same plugin.
-                if a.intersects(&b) {
-                    anyhow::bail!(
-                        "Duplicate plugin ID in validation results. Maybe multiple versions of \
-                         the same plugin are being validated."
-                    );
-                }
-
-                Ok(ValidationResult::union(a, b))
+                a?.try_union(b?)
             })
             .unwrap_or_else(|| Ok(ValidationResult::default()))
     } else {
-        settings
-            .paths
-            .par_iter()
-            .map(|library_path| {
-                let mut plugin_library_tests: BTreeMap<PathBuf, Vec<TestResult>> = BTreeMap::new();
-                plugin_library_tests.insert(
-                    library_path.clone(),
-                    PluginLibraryTestCase::iter()
-                        .par_bridge()
-                        .filter(|test| test_filter(test, settings, &test_filter_re))
-                        .map(|test| run_test(&test, verbosity, settings, library_path))
-                        .collect::<Result<Vec<TestResult>>>()?,
-                );
-
-                let plugin_library = PluginLibrary::load(library_path)
-                    .with_context(|| format!("Could not load '{}'", library_path.display()))?;
-                let plugin_metadata = plugin_library.metadata().with_context(|| {
-                    format!(
-                        "Could not fetch plugin metadata for '{}'",
-                        library_path.display()
-                    )
-                })?;
-                if !clap_version_is_compatible(plugin_metadata.clap_version()) {
-                    log::debug!(
-                        "'{}' uses an unsupported CLAP version ({}.{}.{}), skipping...",
-                        library_path.display(),
-                        plugin_metadata.version.0,
-                        plugin_metadata.version.1,
-                        plugin_metadata.version.2
-                    );
-
-                    return Ok(ValidationResult::default());
-                }
-
-                let plugin_tests: BTreeMap<String, Vec<TestResult>> = plugin_metadata
-                    .plugins
-                    .into_par_iter()
-                    .filter(|plugin_metadata| plugin_filter(plugin_metadata, settings))
-                    .map(|plugin_metadata| {
-                        Ok((
-                            plugin_metadata.id.clone(),
-                            PluginTestCase::iter()
-                                .par_bridge()
-                                .filter(|test| test_filter(test, settings, &test_filter_re))
-                                .map(|test| {
-                                    run_test(
-                                        &test,
-                                        verbosity,
-                                        settings,
-                                        (&plugin_library, &plugin_metadata.id),
-                                    )
-                                })
-                                .collect::<Result<Vec<TestResult>>>()?,
-                        ))
-                    })
-                    .collect::<Result<BTreeMap<_, _>>>()?;
-
-                Ok(ValidationResult {
-                    plugin_library_tests,
-                    plugin_tests,
-                })
-            })
-            .reduce(
-                || Ok(ValidationResult::default()),
-                |a, b| {
-                    let (a, b) = (a?, b?);
-
-                    if a.intersects(&b) {
-                        anyhow::bail!(
-                            "Duplicate plugin ID in validation results. Maybe multiple versions \
-                             of the same plugin are being validated."
-                        );
-                    }
-
-                    Ok(ValidationResult::union(a, b))
-                },
-            )
+        // Only this many plugin libraries are loaded at the same time. Every chunk of libraries is
+        // validated in parallel, and the libraries are unloaded before moving on to the next chunk.
+        let chunk_size = settings
+            .max_resident_libraries
+            .map(NonZeroUsize::get)
+            .unwrap_or(settings.paths.len())
+            .max(1);
+
+        settings.paths.chunks(chunk_size).try_fold(
+            ValidationResult::default(),
+            |results, library_paths| {
+                results.try_union(validate_parallel(
+                    library_paths,
+                    verbosity,
+                    settings,
+                    &test_filter_re,
+                )?)
+            },
+        )
     }?;
 
     // The parallel iterators don't preserve order, so this needs to be sorted to make sure the test
@@ -336,18 +549,157 @@ pub fn validate(verbosity: Verbosity, settings: &ValidatorSettings) -> Result<Va
         tests.sort_by(|a, b| Ord::cmp(&a.name, &b.name));
     }
 
+    // The host extensions are recorded per test, so this can only be summarized once all of a
+    // plugin's tests have run
+    for (plugin_id, plugin) in results.plugins.iter_mut() {
+        for test in results.plugin_tests.get(plugin_id).into_iter().flatten() {
+            plugin.host_requirements.extend(&test.host_extensions);
+        }
+    }
+
     if let Some(plugin_id) = &settings.plugin_id {
         if results.plugin_tests.is_empty() {
             anyhow::bail!("No plugins matched the plugin ID '{plugin_id}'.");
         }
     }
 
+    results.header = Some(ReportHeader {
+        clap_validator_version: env!("CARGO_PKG_VERSION"),
+        clap_version: format!(
+            "{}.{}.{}",
+            CLAP_VERSION.major, CLAP_VERSION.minor, CLAP_VERSION.revision
+        ),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        settings: settings.clone(),
+        prng_seed: settings.seed,
+        started_at,
+        finished_at: Utc::now(),
+    });
+
     Ok(results)
 }
 
-/// Run a single test case, and write the result to specified the output file path. This is used for
-/// the out-of-process validation mode.
-pub fn run_single_test(settings: &SingleTestSettings) -> Result<()> {
+/// Validate a chunk of plugin libraries in parallel. The libraries are unloaded again before this
+/// function returns, so at most `library_paths.len()` libraries are loaded at the same time.
+fn validate_parallel(
+    library_paths: &[PathBuf],
+    verbosity: Verbosity,
+    settings: &ValidatorSettings,
+    test_filter_re: &Option<Regex>,
+) -> Result<ValidationResult> {
+    library_paths
+        .par_iter()
+        .map(|library_path| {
+            let mut plugin_library_tests: BTreeMap<PathBuf, Vec<TestResult>> = BTreeMap::new();
+            plugin_library_tests.insert(
+                library_path.clone(),
+                PluginLibraryTestCase::all()
+                    .into_iter()
+                    .par_bridge()
+                    .filter(|test| test_filter(test, settings, test_filter_re))
+                    .map(|test| run_test(&test, verbosity, settings, library_path))
+                    .collect(),
+            );
+
+            let plugin_library = PluginLibrary::load(library_path)
+                .with_context(|| format!("Could not load '{}'", library_path.display()))?;
+            let (plugin_metadata, broken_descriptors) =
+                plugin_library.metadata_lenient().with_context(|| {
+                    format!(
+                        "Could not fetch plugin metadata for '{}'",
+                        library_path.display()
+                    )
+                })?;
+            if !clap_version_is_compatible(plugin_metadata.clap_version()) {
+                log::debug!(
+                    "'{}' uses an unsupported CLAP version ({}.{}.{}), skipping...",
+                    library_path.display(),
+                    plugin_metadata.version.0,
+                    plugin_metadata.version.1,
+                    plugin_metadata.version.2
+                );
+
+                return Ok(ValidationResult::default());
+            }
+
+            plugin_library_tests
+                .entry(library_path.clone())
+                .or_default()
+                .extend(broken_descriptor_results(broken_descriptors, settings));
+
+            let plugins = validated_plugins(&plugin_library, &plugin_metadata, settings)?;
+            let plugin_tests: BTreeMap<String, Vec<TestResult>> = plugin_metadata
+                .plugins
+                .into_par_iter()
+                .filter(|plugin_metadata| plugin_filter(plugin_metadata, settings))
+                .map(|plugin_metadata| {
+                    (
+                        plugin_metadata.id.clone(),
+                        PluginTestCase::all()
+                            .into_iter()
+                            .par_bridge()
+                            .filter(|test| test_filter(test, settings, test_filter_re))
+                            .map(|test| {
+                                run_test(
+                                    &test,
+                                    verbosity,
+                                    settings,
+                                    (&plugin_library, &plugin_metadata.id),
+                                )
+                            })
+                            .collect(),
+                    )
+                })
+                .collect();
+
+            Ok(ValidationResult {
+                header: None,
+                plugins,
+                plugin_library_tests,
+                plugin_tests,
+            })
+        })
+        .reduce(|| Ok(ValidationResult::default()), |a, b| a?.try_union(b?))
+}
+
+/// Run a single test case, and send the result to the validator process through `connection`. This
+/// is used for the out-of-process validation mode.
+pub fn run_single_test(connection: &ChildConnection, settings: &SingleTestSettings) -> Result<()> {
+    util::set_shared_settings(settings.shared_settings.clone());
+    if settings.record_trace {
+        call_trace::record_to_validator(format!("clap-validator ({})", settings.name));
+    }
+
+    // This allows the crash to be inspected after the fact when the plugin crashes the process
+    #[cfg(windows)]
+    crate::crash_report::install_handler(
+        settings.crash_report_file.clone(),
+        util::validator_temp_dir().join("crashes").join(format!(
+            "{}-{}.dmp",
+            settings.name,
+            std::process::id()
+        )),
+    )
+    .context("Could not install the crash handler")?;
+
+    if settings.wait_for_debugger {
+        // The default panic hook logs the panic, after which the process is kept alive until the
+        // user has had the chance to inspect it. Panics in host callbacks are caught and reported
+        // as callback errors, so those don't stop the test.
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+            if !util::in_callback_panic_handler() {
+                wait_for_debugger("The test panicked");
+            }
+        }));
+
+        wait_for_debugger(&format!("Waiting to run '{}'", settings.name));
+    }
+
+    connection.send(&ChildMessage::Started)?;
+
     let result = match settings.test_type {
         SingleTestType::PluginLibrary => {
             let test_case = settings
@@ -369,18 +721,23 @@ pub fn run_single_test(settings: &SingleTestSettings) -> Result<()> {
         }
     };
 
-    fs::write(
-        &settings.output_file,
-        serde_json::to_string(&result).context("Could not format the result as JSON")?,
-    )
-    .with_context(|| {
-        format!(
-            "Could not write the result to '{}'",
-            settings.output_file.display()
-        )
+    connection.send(&ChildMessage::Result {
+        result: Box::new(result),
     })
 }
 
+/// Print this process' ID and block until the user presses Enter. Used for the single-test runner's
+/// `--wait-for-debugger` option so a debugger can be attached to the process.
+fn wait_for_debugger(reason: &str) {
+    eprintln!(
+        "{reason}. Attach a debugger to process {} and press Enter to continue...",
+        std::process::id()
+    );
+
+    // If STDIN is closed then there's no way to wait, in which case we'll simply continue
+    let _ = std::io::stdin().read_line(&mut String::new());
+}
+
 /// The filter function for determining whether or not a test should be run based on the validator's
 /// settings settings.
 fn test_filter<'a, T: TestCase<'a>>(
@@ -396,6 +753,34 @@ fn test_filter<'a, T: TestCase<'a>>(
     }
 }
 
+/// Collect the information for the plugins in a library that match the plugin filter. This is
+/// included in the validation results.
+fn validated_plugins(
+    library: &PluginLibrary,
+    library_metadata: &PluginLibraryMetadata,
+    settings: &ValidatorSettings,
+) -> Result<BTreeMap<String, ValidatedPlugin>> {
+    let library_sha256 = library.binary_sha256()?;
+
+    Ok(library_metadata
+        .plugins
+        .iter()
+        .filter(|plugin_metadata| plugin_filter(plugin_metadata, settings))
+        .map(|plugin_metadata| {
+            (
+                plugin_metadata.id.clone(),
+                ValidatedPlugin {
+                    library_path: library.plugin_path().to_owned(),
+                    library_sha256: library_sha256.clone(),
+                    library_clap_version: library_metadata.version,
+                    metadata: plugin_metadata.clone(),
+                    host_requirements: HostExtensionUsage::default(),
+                },
+            )
+        })
+        .collect())
+}
+
 /// The filter function for determining whether or not tests should be run for a particular plugin.
 fn plugin_filter(plugin_metadata: &PluginMetadata, settings: &ValidatorSettings) -> bool {
     // It's possible to filter by plugin ID in case you want to validate a single plugin
@@ -407,48 +792,89 @@ fn plugin_filter(plugin_metadata: &PluginMetadata, settings: &ValidatorSettings)
     }
 }
 
-/// The filter function for determining whether or not a test should be run based on the validator's
-/// settings settings.
+/// Run a test either in-process or out-of-process, depending on the validator's settings. If the
+/// test could not be run out-of-process, then this returns a failed result for that test so the
+/// remaining tests and plugins can still be validated.
 fn run_test<'a, T: TestCase<'a>>(
     test: &T,
     verbosity: Verbosity,
     settings: &ValidatorSettings,
     args: T::TestArgs,
-) -> Result<TestResult> {
+) -> TestResult {
+    let _trace_span = call_trace::span("test", || test.to_string());
+    if test.requires_gui() {
+        if let Some(reason) = util::headless_reason() {
+            return test.create_result(Ok(TestStatus::Skipped {
+                reason: SkipReason::RequiresGui,
+                details: Some(format!(
+                    "This test needs to open the plugin's GUI, but the system appears to be \
+                     headless because {reason}. Use '--force-gui-tests' to run it anyway."
+                )),
+            }));
+        }
+    }
+
     if settings.in_process {
-        Ok(test.run_in_process(args))
+        test.run_in_process(args)
     } else {
-        test.run_out_of_process(args, verbosity, settings.hide_output)
+        let timeout = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+        let watchdog = Watchdog {
+            enabled: !settings.wait_for_debugger,
+            test_timeout: timeout(settings.test_timeout),
+            plugin_call_timeout: timeout(settings.plugin_call_timeout),
+        };
+
+        test.run_out_of_process(
+            args,
+            verbosity,
+            settings.hide_output,
+            settings.wait_for_debugger,
+            watchdog,
+        )
+        .unwrap_or_else(|err| {
+            test.create_result(Err(
+                err.context("Could not run the test in a separate process")
+            ))
+        })
     }
 }
 
+/// Create a failed test result for every plugin in a library whose descriptor could not be read.
+/// These are added to the library's tests, since the plugin's ID may not be known. Nothing is
+/// reported when the validator is only validating a single plugin ID.
+fn broken_descriptor_results(
+    broken_descriptors: Vec<(u32, anyhow::Error)>,
+    settings: &ValidatorSettings,
+) -> Vec<TestResult> {
+    if settings.plugin_id.is_some() {
+        return Vec::new();
+    }
+
+    broken_descriptors
+        .into_iter()
+        .map(|(index, err)| TestResult {
+            name: format!("plugin-descriptor-{index}"),
+            description: format!(
+                "Reads the descriptor for the plugin at index {index} in the library's plugin \
+                 factory. The plugin cannot be validated if its descriptor is invalid."
+            ),
+            status: TestStatus::from_error(&err),
+            thread_safety: Vec::new(),
+            plugin_log: Vec::new(),
+            host_extensions: HostExtensionUsage::default(),
+        })
+        .collect()
+}
+
 impl ValidationResult {
     /// Count the number of passing, failing, and skipped tests.
     pub fn tally(&self) -> ValidationTally {
-        let mut num_passed = 0;
-        let mut num_failed = 0;
-        let mut num_skipped = 0;
-        let mut num_warnings = 0;
-        for test in self
-            .plugin_library_tests
-            .values()
-            .chain(self.plugin_tests.values())
-            .flatten()
-        {
-            match test.status {
-                TestStatus::Success { .. } => num_passed += 1,
-                TestStatus::Crashed { .. } | TestStatus::Failed { .. } => num_failed += 1,
-                TestStatus::Skipped { .. } => num_skipped += 1,
-                TestStatus::Warning { .. } => num_warnings += 1,
-            }
-        }
-
-        ValidationTally {
-            num_passed,
-            num_failed,
-            num_skipped,
-            num_warnings,
-        }
+        ValidationTally::count(
+            self.plugin_library_tests
+                .values()
+                .chain(self.plugin_tests.values())
+                .flatten(),
+        )
     }
 
     // Check whether the maps in the object intersect. Useful to ensure that a plugin ID only occurs
@@ -472,14 +898,52 @@ impl ValidationResult {
     /// Merge the results from two validation result objects. If `other` contains a key that also
     /// exists in this object, then the version from `other` is used.
     pub fn union(mut self, other: Self) -> Self {
+        self.plugins.extend(other.plugins);
         self.plugin_library_tests.extend(other.plugin_library_tests);
         self.plugin_tests.extend(other.plugin_tests);
 
         self
     }
+
+    /// Merge the results from two validation result objects like [`union()`][Self::union()], but
+    /// return an error if they contain the same plugin library or plugin ID. This makes sure we
+    /// don't test two versions of the same plugin.
+    pub fn try_union(self, other: Self) -> Result<Self> {
+        if self.intersects(&other) {
+            anyhow::bail!(
+                "Duplicate plugin ID in validation results. Maybe multiple versions of the same \
+                 plugin are being validated."
+            );
+        }
+
+        Ok(self.union(other))
+    }
 }
 
 impl ValidationTally {
+    /// Count the number of passing, failing, and skipped tests in a set of test results.
+    pub fn count<'a>(tests: impl IntoIterator<Item = &'a TestResult>) -> Self {
+        let mut num_passed = 0;
+        let mut num_failed = 0;
+        let mut num_skipped = 0;
+        let mut num_warnings = 0;
+        for test in tests {
+            match test.status {
+                TestStatus::Success { .. } => num_passed += 1,
+                TestStatus::Crashed { .. } | TestStatus::Failed { .. } => num_failed += 1,
+                TestStatus::Skipped { .. } => num_skipped += 1,
+                TestStatus::Warning { .. } => num_warnings += 1,
+            }
+        }
+
+        ValidationTally {
+            num_passed,
+            num_failed,
+            num_skipped,
+            num_warnings,
+        }
+    }
+
     /// Get the total number of tests run.
     pub fn total(&self) -> u32 {
         self.num_passed + self.num_failed + self.num_skipped